@@ -0,0 +1,173 @@
+//! Análisis de capturas de las lambdas
+//!
+//! Una lambda puede usar variables locales del scope donde se crea. Esta pasada
+//! recorre el cuerpo y devuelve los nombres libres (los que no son parámetros ni
+//! variables ligadas dentro del propio cuerpo), en orden de primera aparición.
+//! El generador se queda solo con los que son variables del frame actual: esos se
+//! copian al entorno de la closure al crearla (captura por valor).
+
+use adead_parser::{ComprehensionClause, Expr, FStringPart, Spanned};
+
+/// Nombres libres del cuerpo de una lambda con parámetros `params`
+pub(crate) fn free_names(params: &[String], body: &Expr) -> Vec<String> {
+    let mut walker = FreeNames { bound: params.to_vec(), found: Vec::new() };
+    walker.expr(body);
+    walker.found
+}
+
+struct FreeNames {
+    /// Nombres ligados en el punto actual (parámetros, lambdas y comprehensions anidadas)
+    bound: Vec<String>,
+    found: Vec<String>,
+}
+
+impl FreeNames {
+    fn name(&mut self, name: &str) {
+        if !self.bound.iter().any(|b| b == name) && !self.found.iter().any(|f| f == name) {
+            self.found.push(name.to_string());
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Spanned<Expr>]) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Number(_) | Expr::Float(_) | Expr::Bool(_) | Expr::String(_) | Expr::None => {}
+            Expr::Ident(name) => self.name(name),
+            Expr::BinaryOp { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Assign { name, value } | Expr::CompoundAssign { name, value, .. } => {
+                self.name(name);
+                self.expr(value);
+            }
+            Expr::Call { module, name, args } => {
+                // Una llamada por nombre puede ser a una closure guardada en una variable
+                if module.is_none() {
+                    self.name(name);
+                }
+                self.exprs(args);
+            }
+            Expr::SuperCall { args, .. } | Expr::EnumVariant { args, .. } => self.exprs(args),
+            Expr::Borrow { expr, .. }
+            | Expr::Deref(expr)
+            | Expr::Some(expr)
+            | Expr::Ok(expr)
+            | Expr::Err(expr)
+            | Expr::PropagateError(expr)
+            | Expr::Not(expr)
+            | Expr::FieldAccess { object: expr, .. } => self.expr(expr),
+            Expr::Lambda { params, body } => {
+                let depth = self.bound.len();
+                self.bound.extend(params.iter().cloned());
+                self.expr(body);
+                self.bound.truncate(depth);
+            }
+            Expr::Match { expr, arms } => {
+                // Las variables de los patrones quedan como nombres libres de más: el
+                // generador descarta los que no son variables del frame
+                self.expr(expr);
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&arm.body);
+                }
+            }
+            Expr::StructLiteral { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            Expr::FieldAssign { object, value, .. } => {
+                self.expr(object);
+                self.expr(value);
+            }
+            Expr::MethodCall { object, args, .. } => {
+                self.expr(object);
+                self.exprs(args);
+            }
+            Expr::ArrayLiteral(items) | Expr::TupleLiteral(items) | Expr::SetLiteral(items) => self.exprs(items),
+            Expr::Index { array, index } => {
+                self.expr(array);
+                self.expr(index);
+            }
+            Expr::Slice { object, start, end, step } => {
+                self.expr(object);
+                for bound in [start, end, step].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            Expr::FString { parts } => {
+                for part in parts {
+                    if let FStringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            Expr::ListComprehension { expr, clauses } | Expr::SetComprehension { expr, clauses } => {
+                self.comprehension(clauses, &[expr]);
+            }
+            Expr::DictComprehension { key_expr, value_expr, clauses } => {
+                self.comprehension(clauses, &[key_expr, value_expr]);
+            }
+            Expr::DictLiteral { pairs } => {
+                for (key, value) in pairs {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Ternary { condition, then_expr, else_expr } => {
+                self.expr(condition);
+                self.expr(then_expr);
+                self.expr(else_expr);
+            }
+        }
+    }
+
+    /// Cada cláusula ve las variables de las anteriores; la expresión las ve todas
+    fn comprehension(&mut self, clauses: &[ComprehensionClause], exprs: &[&Spanned<Expr>]) {
+        let depth = self.bound.len();
+        for clause in clauses {
+            self.expr(&clause.iter);
+            self.bound.push(clause.var.clone());
+            for condition in &clause.conditions {
+                self.expr(condition);
+            }
+        }
+        for expr in exprs {
+            self.expr(expr);
+        }
+        self.bound.truncate(depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adead_parser::parse;
+    use adead_parser::Stmt;
+
+    fn lambda_free_names(src: &str) -> Vec<String> {
+        let program = parse(src).unwrap();
+        match &program.statements[0].node {
+            Stmt::Let { value: Spanned { node: Expr::Lambda { params, body }, .. }, .. } => free_names(params, body),
+            other => panic!("se esperaba una lambda: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_free_names_skip_bound_variables() {
+        assert_eq!(lambda_free_names("let f = lambda x: x + base * base\n"), vec!["base"]);
+        // Parámetros de lambdas anidadas y variables de comprehensions no son capturas
+        assert_eq!(lambda_free_names("let f = lambda a: lambda b: a * b + k\n"), vec!["k"]);
+        assert_eq!(lambda_free_names("let f = lambda xs: [x * n for x in xs if x > m]\n"), vec!["m", "n"]);
+        // Una llamada por nombre puede ser a una closure capturada
+        assert_eq!(lambda_free_names("let f = lambda x: g(x, y)\n"), vec!["g", "y"]);
+    }
+}
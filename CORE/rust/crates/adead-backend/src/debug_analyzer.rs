@@ -1,6 +1,8 @@
 // Sistema de Debug Inteligente - Estilo Python
 // Analiza todo el proceso de compilación y genera reportes detallados
 
+use adead_common::log::Phase;
+use adead_common::log_debug;
 use adead_parser::{Expr, Program, Stmt};
 use std::collections::HashMap;
 
@@ -86,12 +88,12 @@ impl DebugAnalyzer {
         
         // Debug inicial
         if self.verbose {
-            eprintln!("[DEBUG-ANALYZER] Analizando programa con {} statements", program.statements.len());
+            log_debug!(Phase::Backend, "DebugAnalyzer: analizando programa con {} statements", program.statements.len());
         }
 
         // Analizar cada statement
         for (i, stmt) in program.statements.iter().enumerate() {
-            match stmt {
+            match &stmt.node {
                 Stmt::Struct { name, fields, .. } => {
                     info.structs.push(name.clone());
                     let field_names: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
@@ -101,7 +103,7 @@ impl DebugAnalyzer {
                     let func_info = self.analyze_function(name, params, &info.struct_definitions);
                     info.functions.push(func_info);
                 }
-                Stmt::Let { name, mutable, value } => {
+                Stmt::Let { name, mutable, value, .. } => {
                     let stmt_info = self.analyze_let_statement(i, name, *mutable, value);
                     info.other_statements.push(stmt_info);
                 }
@@ -112,7 +114,7 @@ impl DebugAnalyzer {
                 _ => {
                     let stmt_info = StatementInfo {
                         index: i,
-                        stmt_type: format!("{:?}", stmt),
+                        stmt_type: format!("{:?}", stmt.node),
                         details: "Other statement".to_string(),
                         parsed_correctly: true,
                     };
@@ -126,7 +128,7 @@ impl DebugAnalyzer {
         
         // Debug final
         if self.verbose {
-            eprintln!("[DEBUG-ANALYZER] Análisis completo: {} structs, {} funciones, {} otros statements, {} problemas detectados", 
+            log_debug!(Phase::Backend, "DebugAnalyzer: análisis completo: {} structs, {} funciones, {} otros statements, {} problemas detectados", 
                 info.structs.len(), info.functions.len(), info.other_statements.len(), info.issues.len());
         }
 
@@ -463,10 +465,7 @@ impl DebugAnalyzer {
     /// Imprimir reporte de forma legible
     pub fn print_report(&self, info: &DebugInfo) {
         if self.enabled {
-            let report = self.generate_report(info);
-            // Forzar output a stderr (siempre visible)
-            eprintln!("\n{}", report);
-            eprintln!("\n"); // Línea en blanco para separar
+            println!("{}", self.generate_report(info));
         }
     }
 }
@@ -16,11 +16,11 @@ impl DependencyGraph {
         // DEPENDENCIAS DE ARRAYS
         // ============================================
         
-        // array_new: función base, no tiene dependencias
-        deps.insert("array_new".to_string(), vec![]);
+        // array_new: reserva el struct y los datos con heap_alloc
+        deps.insert("array_new".to_string(), vec!["heap_alloc".to_string()]);
         
         // array_from_values: necesita array_new
-        deps.insert("array_from_values".to_string(), vec!["array_new".to_string()]);
+        deps.insert("array_from_values".to_string(), vec!["array_new".to_string(), "heap_alloc".to_string()]);
         
         // array_get: necesita panic_out_of_bounds y panic_null_pointer
         deps.insert("array_get".to_string(), vec!["panic_out_of_bounds".to_string(), "panic_null_pointer".to_string()]);
@@ -31,14 +31,14 @@ impl DependencyGraph {
         // array_len: no tiene dependencias
         deps.insert("array_len".to_string(), vec![]);
         
-        // array_append: necesita array_new (para realloc) y panic_null_pointer
-        deps.insert("array_append".to_string(), vec!["panic_null_pointer".to_string()]);
+        // array_append: necesita heap_alloc/heap_free (para realloc) y panic_null_pointer
+        deps.insert("array_append".to_string(), vec!["heap_alloc".to_string(), "heap_free".to_string(), "panic_null_pointer".to_string()]);
         
         // array_pop: necesita panic_out_of_bounds y panic_null_pointer
         deps.insert("array_pop".to_string(), vec!["panic_out_of_bounds".to_string(), "panic_null_pointer".to_string()]);
         
         // array_insert: necesita array_append (para realloc) y panic_null_pointer
-        deps.insert("array_insert".to_string(), vec!["array_append".to_string(), "panic_null_pointer".to_string()]);
+        deps.insert("array_insert".to_string(), vec!["array_append".to_string(), "heap_alloc".to_string(), "heap_free".to_string(), "panic_null_pointer".to_string()]);
         
         // array_remove: necesita panic_out_of_bounds y panic_null_pointer
         deps.insert("array_remove".to_string(), vec!["panic_out_of_bounds".to_string(), "panic_null_pointer".to_string()]);
@@ -55,36 +55,133 @@ impl DependencyGraph {
         // array_reverse: necesita panic_null_pointer
         deps.insert("array_reverse".to_string(), vec!["panic_null_pointer".to_string()]);
         
-        // array_free: no tiene dependencias
-        deps.insert("array_free".to_string(), vec![]);
+        // array_free: devuelve datos y struct con heap_free
+        deps.insert("array_free".to_string(), vec!["heap_free".to_string()]);
         
         // ============================================
         // DEPENDENCIAS DE STRINGS
         // ============================================
         
-        // string_new: no tiene dependencias
-        deps.insert("string_new".to_string(), vec![]);
+        // string_new: reserva el struct y los datos con heap_alloc
+        deps.insert("string_new".to_string(), vec!["heap_alloc".to_string()]);
         
-        // string_from_literal: no tiene dependencias
-        deps.insert("string_from_literal".to_string(), vec![]);
+        // string_from_literal: reserva el struct y los datos con heap_alloc
+        deps.insert("string_from_literal".to_string(), vec!["heap_alloc".to_string()]);
         
         // string_len: necesita panic_null_pointer
         deps.insert("string_len".to_string(), vec!["panic_null_pointer".to_string()]);
         
         // string_concat: necesita string_from_literal y panic_null_pointer
-        deps.insert("string_concat".to_string(), vec!["string_from_literal".to_string(), "panic_null_pointer".to_string()]);
+        deps.insert("string_concat".to_string(), vec!["string_from_literal".to_string(), "heap_alloc".to_string(), "panic_null_pointer".to_string()]);
         
-        // string_slice: necesita panic_null_pointer y panic_out_of_bounds
-        deps.insert("string_slice".to_string(), vec!["panic_null_pointer".to_string(), "panic_out_of_bounds".to_string()]);
+        // string_slice: necesita heap_alloc, panic_null_pointer y panic_out_of_bounds
+        deps.insert("string_slice".to_string(), vec!["heap_alloc".to_string(), "panic_null_pointer".to_string(), "panic_out_of_bounds".to_string()]);
         
-        // string_upper: necesita panic_null_pointer
-        deps.insert("string_upper".to_string(), vec!["panic_null_pointer".to_string()]);
+        // string_upper: necesita heap_alloc y panic_null_pointer
+        deps.insert("string_upper".to_string(), vec!["heap_alloc".to_string(), "panic_null_pointer".to_string()]);
         
-        // string_lower: necesita panic_null_pointer
-        deps.insert("string_lower".to_string(), vec!["panic_null_pointer".to_string()]);
+        // string_lower: necesita heap_alloc y panic_null_pointer
+        deps.insert("string_lower".to_string(), vec!["heap_alloc".to_string(), "panic_null_pointer".to_string()]);
         
-        // string_free: no tiene dependencias
-        deps.insert("string_free".to_string(), vec![]);
+        // string_find_from: búsqueda de substring desde una posición, sin reservar memoria
+        deps.insert("string_find_from".to_string(), vec!["panic_null_pointer".to_string()]);
+        
+        // string_find: envoltorio de string_find_from desde la posición 0
+        deps.insert("string_find".to_string(), vec!["string_find_from".to_string()]);
+        
+        // string_startswith, string_endswith: solo comparan bytes
+        for func in ["string_startswith", "string_endswith"] {
+            deps.insert(func.to_string(), vec!["panic_null_pointer".to_string()]);
+        }
+        
+        // string_strip, string_char_at: el resultado se copia con string_from_literal
+        deps.insert("string_strip".to_string(), vec!["string_from_literal".to_string(), "panic_null_pointer".to_string()]);
+        deps.insert("string_char_at".to_string(), vec!["string_from_literal".to_string(), "panic_null_pointer".to_string(), "panic_out_of_bounds".to_string()]);
+        
+        // string_slice_step: reserva el resultado con heap_alloc; paso 0 es panic_out_of_bounds
+        deps.insert("string_slice_step".to_string(), vec!["heap_alloc".to_string(), "panic_null_pointer".to_string(), "panic_out_of_bounds".to_string()]);
+        
+        // string_replace: busca con string_find_from y reserva el resultado exacto
+        deps.insert("string_replace".to_string(), vec!["string_find_from".to_string(), "string_from_literal".to_string(), "heap_alloc".to_string(), "panic_null_pointer".to_string()]);
+        
+        // string_split: arma un Array de Strings con array_new/array_append
+        deps.insert("string_split".to_string(), vec!["string_find_from".to_string(), "string_from_literal".to_string(), "array_new".to_string(), "array_append".to_string(), "panic_null_pointer".to_string()]);
+        
+        // string_join: reserva el resultado exacto con heap_alloc
+        deps.insert("string_join".to_string(), vec!["heap_alloc".to_string(), "panic_null_pointer".to_string()]);
+        
+        // string_free: devuelve datos y struct con heap_free
+        deps.insert("string_free".to_string(), vec!["heap_free".to_string()]);
+        
+        // ============================================
+        // DEPENDENCIAS DE DICTS (tabla hash)
+        // ============================================
+        
+        // dict_hash_key: necesita panic_null_pointer (claves string)
+        deps.insert("dict_hash_key".to_string(), vec!["panic_null_pointer".to_string()]);
+        
+        // dict_keys_equal: no tiene dependencias
+        deps.insert("dict_keys_equal".to_string(), vec![]);
+        
+        // dict_find: necesita dict_hash_key y dict_keys_equal
+        deps.insert("dict_find".to_string(), vec!["dict_hash_key".to_string(), "dict_keys_equal".to_string()]);
+        
+        // dict_new y dict_resize: solo heap_alloc/heap_free
+        deps.insert("dict_new".to_string(), vec!["heap_alloc".to_string()]);
+        deps.insert("dict_resize".to_string(), vec!["heap_alloc".to_string(), "heap_free".to_string()]);
+        
+        // dict_set: necesita dict_find, dict_resize (crecer) y panic_null_pointer
+        deps.insert("dict_set".to_string(), vec!["dict_find".to_string(), "dict_resize".to_string(), "panic_null_pointer".to_string()]);
+        
+        // dict_get, dict_contains, dict_delete: necesitan dict_find y panic_null_pointer
+        for func in ["dict_get", "dict_contains", "dict_delete"] {
+            deps.insert(func.to_string(), vec!["dict_find".to_string(), "panic_null_pointer".to_string()]);
+        }
+        
+        // dict_index: además necesita panic_key_not_found
+        deps.insert("dict_index".to_string(), vec!["dict_find".to_string(), "panic_null_pointer".to_string(), "panic_key_not_found".to_string()]);
+        
+        // dict_len necesita panic_null_pointer; dict_collect además reserva el array resultado
+        deps.insert("dict_len".to_string(), vec!["panic_null_pointer".to_string()]);
+        deps.insert("dict_collect".to_string(), vec!["heap_alloc".to_string(), "panic_null_pointer".to_string()]);
+        
+        // dict_keys, dict_values, dict_items: envoltorios de dict_collect
+        for func in ["dict_keys", "dict_values", "dict_items"] {
+            deps.insert(func.to_string(), vec!["dict_collect".to_string()]);
+        }
+        
+        // ============================================
+        // DEPENDENCIAS DE SETS (Dict con valores sin usar)
+        // ============================================
+        
+        // set_combine: construye el resultado con dict_new/dict_set y consulta con dict_contains
+        deps.insert("set_combine".to_string(), vec!["dict_new".to_string(), "dict_set".to_string(), "dict_contains".to_string(), "panic_null_pointer".to_string()]);
+        
+        // set_union, set_intersection, set_difference: envoltorios de set_combine
+        for func in ["set_union", "set_intersection", "set_difference"] {
+            deps.insert(func.to_string(), vec!["set_combine".to_string()]);
+        }
+        
+        // set_from_array: necesita dict_new, dict_set y panic_null_pointer
+        deps.insert("set_from_array".to_string(), vec!["dict_new".to_string(), "dict_set".to_string(), "panic_null_pointer".to_string()]);
+        
+        // ============================================
+        // DEPENDENCIAS DE FLOATS
+        // ============================================
+        
+        // float_to_str: escribe en un buffer del caller, no tiene dependencias
+        deps.insert("float_to_str".to_string(), vec![]);
+        
+        // float_from_string: necesita panic_null_pointer
+        deps.insert("float_from_string".to_string(), vec!["panic_null_pointer".to_string()]);
+        
+        // ============================================
+        // DEPENDENCIAS DEL HEAP ALLOCATOR (memory_pool.rs)
+        // ============================================
+        
+        // heap_alloc y heap_free: solo VirtualAlloc/VirtualFree (arenas y bloques grandes)
+        deps.insert("heap_alloc".to_string(), vec![]);
+        deps.insert("heap_free".to_string(), vec![]);
         
         // ============================================
         // DEPENDENCIAS DEL SISTEMA DE PANIC
@@ -96,6 +193,9 @@ impl DependencyGraph {
         // panic_null_pointer: necesita WriteFile y ExitProcess (siempre se incluye si se usa panic)
         deps.insert("panic_null_pointer".to_string(), vec![]);
         
+        // panic_key_not_found: d[clave] con una clave ausente
+        deps.insert("panic_key_not_found".to_string(), vec![]);
+        
         // ============================================
         // DEPENDENCIAS DE FUNCIONES ESPECIALES
         // ============================================
@@ -147,10 +247,27 @@ impl DependencyGraph {
         self.used_functions.iter().any(|f| f.starts_with("string_"))
     }
     
+    /// Verificar si se usa alguna función de dicts
+    pub fn uses_dicts(&self) -> bool {
+        self.used_functions.iter().any(|f| f.starts_with("dict_"))
+    }
+    
+    /// Verificar si se usa alguna función de floats
+    pub fn uses_floats(&self) -> bool {
+        self.used_functions.iter().any(|f| f.starts_with("float_"))
+    }
+    
+    /// Verificar si se usa el heap allocator (heap_alloc/heap_free)
+    pub fn uses_heap(&self) -> bool {
+        self.used_functions.contains("heap_alloc") || self.used_functions.contains("heap_free")
+    }
+    
     /// Verificar si se usa el sistema de panic
     pub fn uses_panic(&self) -> bool {
         self.used_functions.contains("panic_out_of_bounds") || 
-        self.used_functions.contains("panic_null_pointer")
+        self.used_functions.contains("panic_null_pointer") ||
+        self.used_functions.contains("panic_match_failed") ||
+        self.used_functions.contains("panic_key_not_found")
     }
     
     /// Verificar si se usa alguna función específica (helper para evitar borrowing issues)
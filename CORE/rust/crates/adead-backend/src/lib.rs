@@ -1,6 +1,8 @@
-use adead_common::Result;
-use adead_parser::{BinOp, Expr, Pattern, Program, Stmt, StructMethod};
-use std::collections::HashMap;
+use adead_common::log::Phase;
+use adead_common::{log_debug, log_trace, Result, Type};
+use adead_parser::{BinOp, ComprehensionClause, EnumVariant, Expr, FnParam, ForIter, Pattern, Program, Spanned, Stmt, StructMethod};
+use adead_types::{ExprTypes, TypeChecker};
+use std::collections::{HashMap, HashSet};
 
 mod memory_pool;
 mod optimizer;
@@ -9,11 +11,20 @@ mod register_optimizer;
 mod dependency_graph;
 mod usage_analyzer;
 mod debug_analyzer;
+mod target;
+mod linux_runtime;
+mod oop;
+mod monomorphize;
+mod captures;
+pub use target::Target;
 use optimizer::CodeOptimizer;
 use stdlib::StdLib;
+use linux_runtime::LinuxRuntime;
+use memory_pool::MemoryPool;
 use dependency_graph::DependencyGraph;
+use oop::OOPGenerator;
 use usage_analyzer::UsageAnalyzer;
-use debug_analyzer::DebugAnalyzer;
+pub use debug_analyzer::DebugAnalyzer;
 
 /// Contexto para loops (NASM-Universal.md - Break/Continue)
 /// Permite manejar break y continue dentro de loops anidados
@@ -22,6 +33,56 @@ struct LoopContext {
     continue_label: String, // Label para saltar cuando se ejecuta continue
 }
 
+/// Argumento de una llamada a código de usuario (ver `emit_call`)
+enum CallArg<'e> {
+    Expr(&'e Expr),
+    /// Instrucción que deja el valor en RAX (p. ej. `self` = puntero al struct)
+    Asm(String),
+    /// Expresión pasada a un parámetro anotado: un struct se convierte en objeto
+    /// de trait si el parámetro es un trait
+    Coerced(&'e Expr, Type),
+}
+
+/// Método de un struct tal como aparece en el programa: propio, función global
+/// `Struct_metodo(self, ...)` asociada o de un `impl Trait for Struct`. Se toma
+/// prestado y no clonado porque `ExprTypes` indexa los tipos por dirección de nodo.
+#[derive(Clone, Copy)]
+struct MethodSource<'p> {
+    name: &'p str,
+    params: &'p [FnParam],
+    return_type: Option<&'p Type>,
+    body: &'p [Spanned<Stmt>],
+}
+
+impl<'p> From<&'p (String, StructMethod)> for MethodSource<'p> {
+    fn from((name, method): &'p (String, StructMethod)) -> Self {
+        MethodSource {
+            name,
+            params: &method.params,
+            return_type: method.return_type.as_ref(),
+            body: &method.body,
+        }
+    }
+}
+
+/// Contenedor que construye una comprehension; los sets y dicts llevan el tipo de clave
+enum ComprehensionTarget<'e> {
+    List(&'e Expr),
+    Set(&'e Expr, i64),
+    Dict(&'e Expr, &'e Expr, i64),
+}
+
+/// Destino de una llamada a código de usuario
+enum Callee {
+    Label(String),
+    /// Offset del método en la vtable de `self` (el primer argumento): dispatch dinámico
+    Virtual(i64),
+    /// Método de un objeto de trait: la vtable está en el slot `vtable_slot` del frame
+    TraitObject { vtable_slot: i64, offset: i64 },
+    /// Closure guardada en el slot del frame: código en [+0], entorno (a R10) en [+8]
+    Closure(i64),
+}
+
 pub struct CodeGenerator {
     data_section: Vec<String>,
     text_section: Vec<String>,
@@ -33,17 +94,29 @@ pub struct CodeGenerator {
     variables_to_destroy: Vec<(String, String)>, // (variable_name, struct_name) - RAII tracking
     source_lines: Vec<(usize, String)>, // (line_number, source_line) para debug symbols
     current_line: usize, // Línea actual del código fuente
+    source: Option<String>, // Código fuente (para convertir spans a línea/columna)
     loop_stack: Vec<LoopContext>, // Stack de contextos de loop para break/continue
     struct_definitions: HashMap<String, Vec<String>>, // Track struct field names for offset calculation
+    field_types: HashMap<String, HashMap<String, Type>>, // Tipos declarados de los campos (`edad: int32`)
+    enum_definitions: HashMap<String, Vec<EnumVariant>>, // Variantes de cada enum (tag = posición)
     variable_types: HashMap<String, String>, // Track variable types (for struct field access)
     current_struct: Option<String>, // Struct actual que se está procesando (para super.metodo())
-    struct_parents: HashMap<String, Option<String>>, // Mapeo de struct -> parent (para herencia)
-    struct_methods: HashMap<String, Vec<String>>, // Track métodos de cada struct (para vtables)
-    vtable_counter: usize, // Contador para nombres únicos de vtables
+    current_return_type: Option<Type>, // Tipo de retorno declarado de la función actual (para objetos de trait)
+    oop: OOPGenerator, // Jerarquía de clases, layout de objetos y vtables
+    param_types: HashMap<String, Vec<Option<Type>>>, // Tipos anotados de los parámetros de cada función
+    generic_instances: HashMap<String, String>, // Instancia de un genérico → descripción (`max_of<int32>: T = int32 (4 bytes)`)
+    function_values: HashSet<String>, // Funciones usadas como valor: tienen una closure estática `closure_fn_<nombre>`
+    target: Target, // Plataforma destino (Windows PE64 / Linux ELF64)
+    expr_types: ExprTypes, // Tipos inferidos por el type checker (solo durante generate)
 }
 
 impl CodeGenerator {
     pub fn new() -> Self {
+        Self::with_target(Target::host())
+    }
+
+    /// Crear generador para un target específico (independiente del host)
+    pub fn with_target(target: Target) -> Self {
         Self {
             data_section: Vec::new(),
             text_section: Vec::new(),
@@ -55,15 +128,31 @@ impl CodeGenerator {
             variables_to_destroy: Vec::new(),
             source_lines: Vec::new(),
             current_line: 0,
+            source: None,
             loop_stack: Vec::new(),
             struct_definitions: HashMap::new(),
+            field_types: HashMap::new(),
+            enum_definitions: HashMap::new(),
             variable_types: HashMap::new(),
             current_struct: None,
-            struct_parents: HashMap::new(),
-            struct_methods: HashMap::new(),
-            vtable_counter: 0,
+            current_return_type: None,
+            oop: OOPGenerator::new(),
+            param_types: HashMap::new(),
+            generic_instances: HashMap::new(),
+            function_values: HashSet::new(),
+            target,
+            expr_types: ExprTypes::default(),
         }
     }
+
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// Registrar el código fuente para que los comentarios de debug usen líneas reales
+    pub fn set_source(&mut self, source: &str) {
+        self.source = Some(source.to_string());
+    }
     
     /// Agregar comentario de debug con origen ADead
     fn add_debug_comment(&mut self, comment: &str) {
@@ -74,68 +163,239 @@ impl CodeGenerator {
         }
     }
 
+    /// Reporte del DebugAnalyzer (structs, funciones, problemas detectados).
+    /// Solo se genera a pedido (`adeadc --emit=analysis`), no en cada compilación.
+    pub fn analysis_report(program: &Program) -> String {
+        let analyzer = DebugAnalyzer::new(true, false);
+        analyzer.generate_report(&analyzer.analyze_program(program))
+    }
+
     pub fn generate(&mut self, program: &Program) -> Result<String> {
-        // Detectar si estamos en Windows (usaremos API de Windows)
-        #[cfg(target_os = "windows")]
-        {
-            self.generate_windows(program)
+        // El target se decide en runtime (--target), no por el host.
+        // Ambos targets comparten el mismo generador: en Linux las funciones de kernel32
+        // las provee LinuxRuntime con syscalls (ver generate_windows).
+        // Los genéricos se especializan antes de generar: una copia por cada instancia
+        let specialized = monomorphize::monomorphize(program)?;
+        let program = match &specialized {
+            Some((specialized, instances)) => {
+                for instance in instances {
+                    self.generic_instances.insert(instance.name.clone(), instance.description.clone());
+                }
+                specialized
+            }
+            None => program,
+        };
+        self.expr_types = TypeChecker::new().check(program)?.types;
+        let result = self.generate_windows(program);
+        // Los tipos están indexados por dirección de nodo: solo valen para este programa
+        self.expr_types = ExprTypes::default();
+        result
+    }
+
+    /// Tipo inferido de una expresión del programa (`None` si el type checker no lo conoce)
+    fn expr_type(&self, expr: &Expr) -> Option<&Type> {
+        Some(self.expr_types.get(expr)).filter(|ty| **ty != Type::Unknown)
+    }
+
+    /// Mover un float de XMM0 a RAX (bits crudos) para guardarlo en un slot de 8 bytes
+    fn float_to_gpr_if_needed(&mut self, expr: &Expr) {
+        if self.expr_type(expr).map_or(false, Type::is_float) {
+            self.text_section.push("    movq rax, xmm0  ; float64 → rax (bits) para guardar".to_string());
         }
-        #[cfg(not(target_os = "windows"))]
-        {
-            self.generate_linux(program)
+    }
+
+    /// Ajustar RAX al ancho de un entero anotado (`int8`, `uint16`, `int32`...):
+    /// trunca y extiende con signo o con ceros según `Type::nasm_register_hint`,
+    /// de modo que el slot de 8 bytes siempre guarda el valor ya representado en ese ancho
+    fn narrow_rax_to(&mut self, ty: &Type) {
+        if !ty.is_integer() || ty.size_bytes() >= 8 {
+            return;
         }
+        let (reg, width) = ty.nasm_register_hint();
+        let signed = matches!(ty, Type::Int8 | Type::Int16 | Type::Int32);
+        let instr = match (width, signed) {
+            ("dword", true) => "movsxd rax, eax".to_string(),
+            ("dword", false) => "mov eax, eax".to_string(),
+            (_, true) => format!("movsx rax, {}", reg),
+            (_, false) => format!("movzx eax, {}", reg),
+        };
+        self.text_section.push(format!("    {}  ; ajustar a {} ({})", instr, ty.to_string(), width));
     }
 
-    fn generate_linux(&mut self, program: &Program) -> Result<String> {
-        self.text_section.push("section .text".to_string());
-        self.text_section.push("global _start".to_string());
-        self.text_section.push("_start:".to_string());
-        self.text_section.push("    ; Setup stack frame".to_string());
-        self.text_section.push("    mov rbp, rsp".to_string());
-        self.text_section.push("    sub rsp, 1024  ; allocate stack space".to_string());
+    /// Ajustar al ancho declarado un parámetro ya guardado en su slot
+    fn narrow_param_slot(&mut self, param: &FnParam) {
+        let (Some(ty), Some(&offset)) = (&param.ty, self.variables.get(&param.name)) else {
+            return;
+        };
+        if !ty.is_integer() || ty.size_bytes() >= 8 {
+            return;
+        }
+        self.text_section.push(format!("    mov rax, [rbp - {}]  ; param {}: {}", offset + 8, param.name, ty.to_string()));
+        self.narrow_rax_to(ty);
+        self.text_section.push(format!("    mov [rbp - {}], rax", offset + 8));
+    }
 
-        for stmt in &program.statements {
-            self.generate_stmt(stmt)?;
+    /// Un parámetro anotado con un struct (`a: Animal`) permite llamar a sus métodos
+    /// y acceder a sus campos; los métodos se despachan por la vtable del objeto real
+    fn record_param_struct(&mut self, param: &FnParam) {
+        if let Some(Type::Struct(struct_name)) = &param.ty {
+            self.variable_types.insert(param.name.clone(), struct_name.clone());
         }
+    }
 
-        // Exit syscall
-        self.text_section.push("    ; Exit".to_string());
-        self.text_section.push("    mov rax, 60".to_string());
-        self.text_section.push("    mov rdi, 0".to_string());
-        self.text_section.push("    syscall".to_string());
+    /// Registrar las clases en el OOPGenerator, cada padre antes que sus hijos, con
+    /// sus campos (los heredados primero) y sus métodos de instancia (los del struct
+    /// y las funciones globales `Struct_metodo(self, ...)` asociadas)
+    fn register_classes(
+        &mut self,
+        structs: &[&Spanned<Stmt>],
+        associated: &HashMap<String, Vec<MethodSource>>,
+    ) -> Result<()> {
+        let mut pending: Vec<&Spanned<Stmt>> = structs.to_vec();
+        while !pending.is_empty() {
+            let before = pending.len();
+            let mut deferred = Vec::new();
+            for stmt in pending {
+                let Stmt::Struct { name, parent, fields, methods, .. } = &stmt.node else { continue };
+                if let Some(parent_name) = parent {
+                    if !self.struct_definitions.contains_key(parent_name) {
+                        let message = format!("Struct padre '{}' de '{}' no está definido.", parent_name, name);
+                        let error = adead_common::ADeadError::RuntimeError { message };
+                        return Err(error.at(stmt.span));
+                    }
+                    if !self.oop.classes.contains_key(parent_name) {
+                        deferred.push(stmt);
+                        continue;
+                    }
+                }
+                let own_fields: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
+                let instance_methods: Vec<String> = methods
+                    .iter()
+                    .map(MethodSource::from)
+                    .chain(associated.get(name).into_iter().flatten().copied())
+                    .filter(|method| method.params.first().map_or(false, |p| p.name == "self"))
+                    .map(|method| method.name.to_string())
+                    .collect();
+                let mut all_fields = parent
+                    .as_ref()
+                    .and_then(|parent_name| self.struct_definitions.get(parent_name).cloned())
+                    .unwrap_or_default();
+                all_fields.extend(own_fields.iter().cloned());
+                self.struct_definitions.insert(name.clone(), all_fields);
+                self.oop.register_class(name.clone(), parent.clone(), own_fields, instance_methods);
+            }
+            if deferred.len() == before {
+                // Solo quedan ciclos de herencia
+                let names: Vec<String> = deferred
+                    .iter()
+                    .filter_map(|stmt| match &stmt.node {
+                        Stmt::Struct { name, .. } => Some(name.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                return Err(adead_common::ADeadError::RuntimeError {
+                    message: format!("Herencia cíclica entre los structs: {}", names.join(", ")),
+                });
+            }
+            pending = deferred;
+        }
+        Ok(())
+    }
 
-        self.finish_generation()
+    /// Trait de un tipo anotado (`d: Drawable` llega del parser como nombre de struct)
+    fn trait_of_type(&self, ty: &Type) -> Option<String> {
+        match ty {
+            Type::Trait(name) => Some(name.clone()),
+            Type::Struct(name) if self.oop.is_trait(name) => Some(name.clone()),
+            _ => None,
+        }
     }
 
-    fn generate_windows(&mut self, program: &Program) -> Result<String> {
-        // ============================================
-        // DEBUG INTELIGENTE: Análisis Completo del Programa
-        // ============================================
-        // Activar debug inteligente (siempre activo para análisis completo)
-        eprintln!("\n[DEBUG] Iniciando análisis inteligente del programa...");
-        let debug_analyzer = DebugAnalyzer::new(true, true);
-        let debug_info = debug_analyzer.analyze_program(program);
-        eprintln!("[DEBUG] Análisis completo: {} statements, {} structs, {} funciones, {} otros statements", 
-            debug_info.total_statements, debug_info.structs.len(), debug_info.functions.len(), debug_info.other_statements.len());
-        
-        // Imprimir reporte detallado estilo Python
-        let report = debug_analyzer.generate_report(&debug_info);
-        if !report.is_empty() {
-            // Forzar output a ambos streams para máxima visibilidad
-            println!("\n{}", report);
-            eprintln!("\n{}", report);
-            // También escribir a archivo para debugging
-            if let Ok(mut debug_file) = std::fs::File::create("debug_analysis.txt") {
-                use std::io::Write;
-                let _ = writeln!(debug_file, "{}", report);
-            }
-            // Forzar flush inmediato
-            use std::io::Write;
-            std::io::stdout().flush().ok();
-            std::io::stderr().flush().ok();
+    /// Trait de una expresión que es un objeto de trait
+    fn trait_of_expr(&self, expr: &Expr) -> Option<String> {
+        if let Some(name) = self.expr_type(expr).and_then(|ty| self.trait_of_type(ty)) {
+            return Some(name);
+        }
+        self.get_struct_type_from_expr(expr).filter(|name| self.oop.is_trait(name))
+    }
+
+    /// Si `expected` es un trait y `value` un struct (puntero en RAX), construir el
+    /// objeto de trait `[datos, vtable]` en el heap y dejar su dirección en RAX: puede
+    /// guardarse en un array o retornarse sin depender del frame donde se creó
+    fn coerce_to(&mut self, expected: &Type, value: &Expr) {
+        let Some(trait_name) = self.trait_of_type(expected) else { return };
+        let struct_name = match self.expr_type(value) {
+            Some(Type::Struct(name)) => Some(name.clone()),
+            _ => self.get_struct_type_from_expr(value),
+        };
+        let Some(vtable) = struct_name
+            .as_ref()
+            .and_then(|name| self.oop.trait_vtable_label(&trait_name, name))
+        else {
+            return;
+        };
+        let data_slot = self.alloc_temp_slot();
+        let struct_name = struct_name.unwrap_or_default();
+        self.text_section.push(format!("    ; objeto de trait {} ({}) en el heap: [datos, vtable]", trait_name, struct_name));
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; datos", data_slot + 8));
+        self.text_section.push("    mov rcx, 16  ; tamaño en bytes".to_string());
+        self.emit_heap_alloc();
+        self.text_section.push(format!("    mov rcx, [rbp - {}]  ; datos", data_slot + 8));
+        self.text_section.push("    mov [rax], rcx".to_string());
+        self.text_section.push(format!("    lea rcx, [rel {}]", vtable));
+        self.text_section.push(format!("    mov [rax + 8], rcx  ; vtable de {} para {}", trait_name, struct_name));
+    }
+
+    /// Dejar en RAX el valor recién generado para guardarlo en un campo: los floats
+    /// pasan de XMM0 a RAX y los enteros se ajustan al ancho declarado del campo
+    fn prepare_field_value(&mut self, struct_name: Option<&str>, field: &str, value: &Expr) {
+        self.float_to_gpr_if_needed(value);
+        let mut current = struct_name.map(str::to_string);
+        while let Some(name) = current {
+            if let Some(ty) = self.field_types.get(&name).and_then(|fields| fields.get(field)).cloned() {
+                self.narrow_rax_to(&ty);
+                return;
+            }
+            current = self.oop.parent_of(&name).cloned();
+        }
+    }
+
+    /// Offset de un campo desde la dirección del objeto (después del puntero a la vtable)
+    fn field_offset(&self, struct_name: &str, field: &str) -> Option<i64> {
+        self.oop.get_field_offset(struct_name, field).or_else(|| {
+            self.struct_definitions
+                .get(struct_name)
+                .and_then(|fields| fields.iter().position(|f| f == field))
+                .map(|pos| 8 + pos as i64 * 8)
+        })
+    }
+
+    /// Reservar un objeto en el heap (heap_alloc) y escribir su puntero a la vtable.
+    /// Devuelve el offset del slot del frame que guarda su dirección (`[rbp - slot]`);
+    /// el campo en el offset `off` (ver `field_offset`) queda en `[dirección + off]`.
+    fn alloc_object(&mut self, struct_name: &str, description: &str) -> i64 {
+        let num_fields = self.struct_definitions.get(struct_name).map_or(0, |f| f.len());
+        let size = 8 + num_fields as i64 * 8;
+        self.stack_offset += 16;
+        let slot = self.stack_offset;
+        self.text_section.push(format!("    ; {}: {} ({} campos + vtable, {} bytes en el heap)", description, struct_name, num_fields, size));
+        self.text_section.push("    sub rsp, 16  ; slot para la dirección del objeto".to_string());
+        self.text_section.push(format!("    mov rcx, {}  ; tamaño en bytes", size));
+        self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
+        self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; dirección del objeto", slot));
+        if self.oop.classes.contains_key(struct_name) {
+            self.text_section.push(format!("    lea rcx, [rel vtable_{}]", struct_name));
+            self.text_section.push(format!("    mov [rax], rcx  ; puntero a la vtable de {}", struct_name));
         } else {
-            eprintln!("[DEBUG] WARNING: El reporte está vacío!");
+            self.text_section.push(format!("    mov qword [rax], 0  ; {} sin vtable", struct_name));
         }
+        slot
+    }
+
+    fn generate_windows(&mut self, program: &Program) -> Result<String> {
+        log_debug!(Phase::Backend, "Generando NASM para {} ({} statements)", self.target, program.statements.len());
         
         // Windows x64 calling convention:
         // - Parameters: RCX, RDX, R8, R9 (first 4 integer params)
@@ -144,19 +404,33 @@ impl CodeGenerator {
         // - Callee-saved: RBX, RBP, RDI, RSI, R12-R15, XMM6-XMM15
         self.text_section.push("default rel".to_string());  // Usar direccionamiento relativo para mejor compatibilidad
         self.text_section.push("section .text".to_string());
-        self.text_section.push("extern GetStdHandle".to_string());
-        self.text_section.push("extern WriteFile".to_string());
-        self.text_section.push("extern ExitProcess".to_string());
-        self.text_section.push("extern VirtualAlloc".to_string());
-        self.text_section.push("extern VirtualFree".to_string());
-        self.text_section.push("global main".to_string());
+        match self.target {
+            Target::X86_64Windows => {
+                self.text_section.push("extern GetStdHandle".to_string());
+                self.text_section.push("extern WriteFile".to_string());
+                self.text_section.push("extern ReadFile".to_string());
+                self.text_section.push("extern ExitProcess".to_string());
+                self.text_section.push("extern VirtualAlloc".to_string());
+                self.text_section.push("extern VirtualFree".to_string());
+                self.text_section.push("global main".to_string());
+            }
+            Target::X86_64Linux => {
+                // Linux (ELF64): sin kernel32, el runtime implementa las mismas funciones
+                // con syscalls y la misma convención de llamada, y _start llama a main
+                self.text_section.push("global _start".to_string());
+                self.text_section.push("global main".to_string());
+                for line in LinuxRuntime::generate_runtime_nasm() {
+                    self.text_section.push(line);
+                }
+            }
+        }
         
         // ============================================
         // DEAD CODE ELIMINATION: Análisis Estático
         // ============================================
         // Analizar el programa para detectar qué funciones del runtime se usan
         let mut deps = DependencyGraph::new();
-        UsageAnalyzer::analyze_program(program, &mut deps);
+        UsageAnalyzer::analyze_program(program, &self.expr_types, &mut deps);
         
         // ============================================
         // RUNTIME BOUNDARY: Funciones Helper del Runtime
@@ -174,6 +448,12 @@ impl CodeGenerator {
             self.generate_panic_system();
         }
         
+        // Generar el heap allocator solo si alguna estructura dinámica lo usa
+        if deps.uses_heap() {
+            self.text_section.extend(MemoryPool::generate_runtime_nasm());
+            self.data_section.extend(MemoryPool::generate_data_nasm());
+        }
+        
         // Generar funciones helper de Array solo si se usan
         if uses_arrays {
             self.generate_array_helpers_nasm_selective(&deps);
@@ -183,6 +463,16 @@ impl CodeGenerator {
         if uses_strings {
             self.generate_string_helpers_nasm_selective(&deps);
         }
+
+        // Generar la tabla hash de Dict solo si se usa
+        if deps.uses_dicts() {
+            self.generate_dict_helpers_nasm_selective(&deps);
+        }
+
+        // Generar formateo y parseo de float64 solo si se usan
+        if deps.uses_floats() {
+            self.generate_float_helpers_nasm_selective(&deps);
+        }
         
         // ============================================
         // RUNTIME BOUNDARY: Librería Estándar (Stdlib)
@@ -206,49 +496,56 @@ impl CodeGenerator {
         let mut user_functions = Vec::new();
         let mut other_statements = Vec::new();
         
-        // DEBUG: Contar statements totales
-        eprintln!("[DEBUG] Total statements parseados: {}", program.statements.len());
-        
         for (i, stmt) in program.statements.iter().enumerate() {
-            match stmt {
+            match &stmt.node {
                 Stmt::Struct { name, .. } => {
-                    eprintln!("[DEBUG] Statement {}: Struct '{}'", i, name);
+                    log_trace!(Phase::Backend, "Statement {}: Struct '{}'", i, name);
                     structs.push(stmt);
                 }
                 Stmt::Fn { name, .. } => {
-                    eprintln!("[DEBUG] Statement {}: Function '{}'", i, name);
+                    log_trace!(Phase::Backend, "Statement {}: Function '{}'", i, name);
                     user_functions.push(stmt);
                 }
                 Stmt::Let { name, .. } => {
-                    eprintln!("[DEBUG] Statement {}: Let '{}' -> other_statements", i, name);
+                    log_trace!(Phase::Backend, "Statement {}: Let '{}' -> other_statements", i, name);
                     other_statements.push(stmt);
                 }
                 Stmt::Print(_) => {
-                    eprintln!("[DEBUG] Statement {}: Print -> other_statements", i);
+                    log_trace!(Phase::Backend, "Statement {}: Print -> other_statements", i);
                     other_statements.push(stmt);
                 }
                 _ => {
-                    eprintln!("[DEBUG] Statement {}: Other -> other_statements", i);
+                    log_trace!(Phase::Backend, "Statement {}: Other -> other_statements", i);
                     other_statements.push(stmt);
                 }
             }
         }
         
-        eprintln!("[DEBUG] Structs: {}, Functions: {}, Other: {}", structs.len(), user_functions.len(), other_statements.len());
+        log_debug!(Phase::Backend, "Structs: {}, Functions: {}, Other: {}", structs.len(), user_functions.len(), other_statements.len());
         
-        // 1. Registrar structs primero (sin generar código, solo registrar tipos)
+        // 1. Registrar structs y enums primero (sin generar código, solo registrar tipos)
         for stmt in &structs {
-            if let Stmt::Struct { name, fields, .. } = stmt {
+            if let Stmt::Struct { name, fields, .. } = &stmt.node {
                 let field_names: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
                 self.struct_definitions.insert(name.clone(), field_names);
+                let declared = fields
+                    .iter()
+                    .filter_map(|f| Some((f.name.clone(), f.ty.clone()?)))
+                    .collect();
+                self.field_types.insert(name.clone(), declared);
+            }
+        }
+        for stmt in &program.statements {
+            if let Stmt::Enum { name, variants } = &stmt.node {
+                self.enum_definitions.insert(name.clone(), variants.clone());
             }
         }
         
         // 2. Asociar funciones globales con structs (si siguen patrón StructName_method)
         // Esto permite usar fn StructName_method(self, ...) como métodos
-        let mut struct_methods_from_functions: HashMap<String, Vec<(String, StructMethod)>> = HashMap::new();
+        let mut struct_methods_from_functions: HashMap<String, Vec<MethodSource>> = HashMap::new();
         for stmt in &user_functions {
-            if let Stmt::Fn { name, params, body, .. } = stmt {
+            if let Stmt::Fn { name, params, return_type, body, .. } = &stmt.node {
                 // Detectar patrón: StructName_methodName
                 if let Some(underscore_pos) = name.find('_') {
                     let struct_name = &name[..underscore_pos];
@@ -258,31 +555,54 @@ impl CodeGenerator {
                     if self.struct_definitions.contains_key(struct_name) {
                         // Si es 'new', es un constructor (puede o no tener 'self')
                         // Si tiene 'self' como primer parámetro, es un método de instancia
-                        // Si NO tiene 'self', es un método estático
+                        // Si NO tiene 'self', es un método estático: se genera como función
+                        // global (paso 3) con la misma etiqueta, no se registra en el struct
                         let is_constructor = method_name == "new";
                         let is_instance_method = params.first().map(|p| p.name == "self").unwrap_or(false);
-                        let is_static_method = !is_constructor && !is_instance_method;
                         
-                        // Registrar TODOS los métodos (constructores, instancia y estáticos)
-                        if is_constructor || is_instance_method || is_static_method {
-                            let method = StructMethod {
-                                visibility: adead_parser::Visibility::Public,
-                                params: params.clone(),
-                                body: body.clone(),
+                        if is_constructor || is_instance_method {
+                            let method = MethodSource {
+                                name: method_name,
+                                params,
+                                return_type: return_type.as_ref(),
+                                body,
                             };
                             struct_methods_from_functions
                                 .entry(struct_name.to_string())
                                 .or_insert_with(Vec::new)
-                                .push((method_name.to_string(), method));
+                                .push(method);
                         }
                     }
                 }
             }
         }
         
+        // 2b. Registrar la jerarquía de clases: layout de objetos y vtables
+        self.register_classes(&structs, &struct_methods_from_functions)?;
+        
+        // 2c. Traits e impls: los métodos de `impl Trait for Struct` se generan junto
+        // con los del struct (como los de funciones globales asociadas)
+        let mut impl_methods: HashMap<String, Vec<MethodSource>> = HashMap::new();
+        for stmt in &program.statements {
+            match &stmt.node {
+                Stmt::Trait { name, methods } => {
+                    self.oop.register_trait(name.clone(), methods.iter().map(|m| m.name.clone()).collect());
+                }
+                Stmt::Impl { trait_name, type_name, methods } => {
+                    let names = methods.iter().map(|(method_name, _)| method_name.clone()).collect();
+                    self.oop.register_impl(trait_name.clone(), type_name.clone(), names);
+                    impl_methods.entry(type_name.clone()).or_default().extend(methods.iter().map(MethodSource::from));
+                }
+                Stmt::Fn { name, params, .. } => {
+                    self.param_types.insert(name.clone(), params.iter().map(|p| p.ty.clone()).collect());
+                }
+                _ => {}
+            }
+        }
+        
         // 3. Generar funciones de usuario (que no son métodos de struct)
         for stmt in &user_functions {
-            if let Stmt::Fn { name, params, .. } = stmt {
+            if let Stmt::Fn { name, params, .. } = &stmt.node {
                 // Detectar si es método de struct
                 let is_struct_method = if let Some(underscore_pos) = name.find('_') {
                     let struct_name = &name[..underscore_pos];
@@ -303,42 +623,42 @@ impl CodeGenerator {
                 
                 if !is_struct_method {
                     // Generar función global (no es método de struct)
-            self.generate_stmt_windows(stmt)?;
+            self.generate_spanned_stmt_windows(stmt)?;
                 }
             } else {
                 // No es función, generar normalmente
-                self.generate_stmt_windows(stmt)?;
+                self.generate_spanned_stmt_windows(stmt)?;
             }
         }
         
         // 4. Generar structs (código de métodos y constructores)
         // Si hay métodos desde funciones globales, agregarlos a los structs
         for stmt in &structs {
-            match stmt {
-                Stmt::Struct { name, parent, fields, init, destroy, methods } => {
-                    let mut all_methods = methods.clone();
+            match &stmt.node {
+                Stmt::Struct { name, parent, init, destroy, methods, .. } => {
+                    let mut all_methods: Vec<MethodSource> = methods.iter().map(MethodSource::from).collect();
                     if let Some(additional_methods) = struct_methods_from_functions.get(name) {
                         // Agregar métodos adicionales desde funciones globales
-                        for (method_name, method) in additional_methods {
-                            all_methods.push((method_name.clone(), method.clone()));
-                        }
+                        all_methods.extend(additional_methods.iter().copied());
                     }
-                    let modified_stmt = Stmt::Struct {
-                        name: name.clone(),
-                        parent: parent.clone(),
-                        fields: fields.clone(),
-                        init: init.clone(),
-                        destroy: destroy.clone(),
-                        methods: all_methods,
-                    };
-                    self.generate_stmt_windows(&modified_stmt)?;
+                    if let Some(trait_methods) = impl_methods.get(name) {
+                        // Y los de sus `impl Trait for Struct`
+                        all_methods.extend(trait_methods.iter().copied());
+                    }
+                    self.generate_struct_windows(name, parent.as_deref(), init.as_ref(), destroy.is_some(), &all_methods)
+                        .map_err(|e| e.at(stmt.span))?;
                 }
                 _ => {
-                    self.generate_stmt_windows(stmt)?;
+                    self.generate_spanned_stmt_windows(stmt)?;
                 }
             }
         }
         
+        // Vtables (una por clase) en la sección de datos
+        if !self.oop.classes.is_empty() {
+            self.data_section.extend(self.oop.generate_vtables());
+        }
+        
         self.text_section.push("main:".to_string());
         
         // Setup stack frame (Windows x64)
@@ -349,7 +669,9 @@ impl CodeGenerator {
         self.text_section.push("    mov rbp, rsp".to_string());
         self.text_section.push("    ; Align stack to 16 bytes (Windows x64 requirement)".to_string());
         self.text_section.push("    and rsp, -16".to_string());
-        self.text_section.push("    sub rsp, 64  ; Allocate space for shadow space (32) + local vars (32)".to_string());
+        // El tamaño real se completa al final: depende de los slots que usen los statements
+        let frame_line = self.text_section.len();
+        self.text_section.push(String::new());
 
         // Obtener handle de stdout (Windows)
         // STD_OUTPUT_HANDLE = -11
@@ -361,20 +683,25 @@ impl CodeGenerator {
         // Generar otros statements (no funciones) dentro del main
         // El debug inteligente ya detectó problemas arriba si los hay
         for stmt in other_statements.iter() {
-            self.generate_stmt_windows(stmt)?;
+            self.generate_spanned_stmt_windows(stmt)?;
         }
 
+        // Frame de main: slots de variables y temporales + buffer de print (24) + shadow space (32)
+        let locals = (self.stack_offset + 24 + 15) / 16 * 16;
+        self.text_section[frame_line] = format!(
+            "    sub rsp, {}  ; Allocate space for shadow space (32) + local vars ({})",
+            locals + 32,
+            locals
+        );
+
         // RAII: Llamar destructores antes de salir (O2.1 - Drop Trait)
         // Llamar destructores en orden inverso (LIFO - Last In First Out)
-        for (var_name, struct_name) in self.variables_to_destroy.iter().rev() {
+        for (var_name, struct_name) in self.variables_to_destroy.clone().iter().rev() {
             if let Some(&offset) = self.variables.get(var_name) {
                 self.text_section.push(format!("    ; RAII: destruyendo {} (tipo {})", var_name, struct_name));
-                // Cargar dirección del struct
-                self.text_section.push(format!("    mov rcx, [rbp - {}]  ; cargar dirección de {}", offset + 8, var_name));
-                // Llamar destructor
-                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
-                self.text_section.push(format!("    call {}_destroy", struct_name));
-                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+                // Llamar destructor con self = dirección del struct
+                let self_arg = CallArg::Asm(format!("mov rax, [rbp - {}]  ; cargar dirección de {}", offset + 8, var_name));
+                self.emit_call(Callee::Label(format!("{}_destroy", struct_name)), &[self_arg], "destructor")?;
             }
         }
 
@@ -406,7 +733,11 @@ impl CodeGenerator {
         
         // Header con información del compilador
         output.push_str("; ═══════════════════════════════════════════════════════════════\n");
-        output.push_str("; ADead Compiler - Generated NASM x86_64 (Windows)\n");
+        let platform = match self.target {
+            Target::X86_64Windows => "Windows",
+            Target::X86_64Linux => "Linux",
+        };
+        output.push_str(&format!("; ADead Compiler - Generated NASM x86_64 ({})\n", platform));
         output.push_str("; ═══════════════════════════════════════════════════════════════\n\n");
         
         // Data section primero (necesario para Windows)
@@ -433,12 +764,24 @@ impl CodeGenerator {
         Ok(output)
     }
 
+    /// Generar un statement con ubicación: actualiza la línea actual (comentarios de debug)
+    /// y adjunta el span del statement a cualquier error generado dentro de él
+    fn generate_spanned_stmt_windows(&mut self, stmt: &Spanned<Stmt>) -> Result<()> {
+        if stmt.span.is_empty() {
+            return self.generate_stmt_windows(&stmt.node);
+        }
+        if let Some(source) = &self.source {
+            self.current_line = stmt.span.line_col(source).0;
+        }
+        self.generate_stmt_windows(&stmt.node).map_err(|e| e.at(stmt.span))
+    }
+
     fn generate_stmt_windows(&mut self, stmt: &Stmt) -> Result<()> {
         match stmt {
             Stmt::Print(expr) => {
                 self.add_debug_comment("print statement");
                 self.text_section.push("    ; print".to_string());
-                match expr {
+                match &expr.node {
                     Expr::Bool(b) => {
                         // Bool literal: convertir a string en compile-time
                         let bool_str = if *b { "true" } else { "false" };
@@ -470,6 +813,11 @@ impl CodeGenerator {
                                     self.text_section.push("    mov qword [rsp+32], 0".to_string());
                                     self.text_section.push("    call WriteFile".to_string());
                                 }
+                                FStringPart::Expr(inner_expr) if self.is_float_expr(inner_expr) => {
+                                    self.text_section.push("    ; Print expresión float interpolada".to_string());
+                                    self.generate_expr_windows(inner_expr)?;
+                                    self.emit_print_float(false);
+                                }
                                 FStringPart::Expr(inner_expr) => {
                                     // Evaluar expresión y convertir a string, luego imprimir
                                     self.text_section.push("    ; Print expresión interpolada".to_string());
@@ -642,6 +990,10 @@ impl CodeGenerator {
                                     message: format!("undefined variable: {} in print statement", name),
                                 });
                             }
+                        } else if self.is_float_expr(expr) {
+                            // Variable float64: formatear en runtime con float_to_str
+                            self.generate_expr_windows(expr)?;
+                            self.emit_print_float(true);
                         } else {
                             // Variable numérica: evaluar expresión y convertir a string
                             // Usar la misma lógica que para expresiones numéricas complejas
@@ -789,6 +1141,18 @@ impl CodeGenerator {
                             self.text_section.push(format!("{}_end:", conv_label));
                         }
                     }
+                    _ if self.expr_type(expr) == Some(&Type::String) => {
+                        // Expresión que produce un String (s[i], slices, built-ins y métodos de strings)
+                        self.generate_expr_windows(expr)?;
+                        self.text_section.push("    mov rdx, [rax + 0]  ; String->data".to_string());
+                        self.text_section.push("    mov r8, [rax + 8]  ; String->length".to_string());
+                        self.text_section.push("    ; Prepare WriteFile call for String expression".to_string());
+                        self.text_section.push("    mov rcx, [rbp+16]  ; stdout handle".to_string());
+                        self.text_section.push("    lea r9, [rbp+24]  ; lpNumberOfBytesWritten".to_string());
+                        self.text_section.push("    mov qword [r9], 0  ; inicializar".to_string());
+                        self.text_section.push("    mov qword [rsp+32], 0  ; lpOverlapped = NULL".to_string());
+                        self.text_section.push("    call WriteFile".to_string());
+                    }
                     _ => {
                         // Evaluar expresión numérica (ej: 2 + 5, 3.14 + 2.5) y convertir a string
                         // Intentar evaluación compile-time primero para expresiones float simples
@@ -811,11 +1175,10 @@ impl CodeGenerator {
                                 self.text_section.push("    call WriteFile".to_string());
                                 return Ok(());
                             } else {
-                                // Expresión float compleja que no puede evaluarse en compile-time
-                                // TODO: Implementar función helper float_to_str_runtime
-                                return Err(adead_common::ADeadError::RuntimeError {
-                                    message: format!("Printing complex float expressions with variables not yet implemented. Use simple constant expressions or assign to variable first: let x = expr; print x"),
-                                });
+                                // Expresión float con variables: formatear en runtime con float_to_str
+                                self.generate_expr_windows(expr)?;
+                                self.emit_print_float(true);
+                                return Ok(());
                             }
                         } else {
                             // Expresión entera: RAX contendrá el resultado después de generate_expr_windows
@@ -978,10 +1341,10 @@ impl CodeGenerator {
                     }
                 }
             },
-            Stmt::Let { mutable, name, value } => {
+            Stmt::Let { mutable, name, ty, value } => {
                 self.add_debug_comment(&format!("let {} = ...", if *mutable { format!("mut {}", name) } else { name.clone() }));
                 // Detectar tipo de valor y registrar
-                let struct_name = if let Expr::Call { module: Some(class_name), name: method_name, .. } = value {
+                let struct_name = if let Expr::Call { module: Some(class_name), name: method_name, .. } = &value.node {
                     if method_name == "new" && self.struct_definitions.contains_key(class_name) {
                         // Es un constructor, registrar tipo
                         if self.structs_with_destroy.contains_key(class_name) {
@@ -992,14 +1355,14 @@ impl CodeGenerator {
                     } else {
                         None
                     }
-                } else if let Expr::StructLiteral { name: struct_name, .. } = value {
+                } else if let Expr::StructLiteral { name: struct_name, .. } = &value.node {
                     // Struct literal
                     if self.structs_with_destroy.contains_key(struct_name) {
                         self.variables_to_destroy.push((name.clone(), struct_name.clone()));
                     }
                     self.variable_types.insert(name.clone(), struct_name.clone());
                     Some(struct_name.clone())
-                } else if matches!(value, Expr::Bool(_)) {
+                } else if matches!(&value.node, Expr::Bool(_)) {
                     // Booleano: registrar tipo
                     self.variable_types.insert(name.clone(), "bool".to_string());
                     None
@@ -1008,6 +1371,21 @@ impl CodeGenerator {
                 };
                 
                 self.generate_expr_windows(value)?;
+                if ty.as_ref().map_or(false, Type::is_float) && !self.is_float_expr(value) {
+                    // Variable anotada como float: convertir el entero inicial
+                    self.text_section.push("    cvtsi2sd xmm0, rax  ; convertir int a float64".to_string());
+                    self.text_section.push("    movq rax, xmm0".to_string());
+                } else {
+                    self.float_to_gpr_if_needed(value);
+                }
+                if let Some(ty) = ty {
+                    self.narrow_rax_to(ty);
+                    self.coerce_to(ty, value);
+                    // `let d: Drawable = ...`: el tipo anotado decide cómo se llaman sus métodos
+                    if let Type::Struct(type_name) = ty {
+                        self.variable_types.insert(name.clone(), type_name.clone());
+                    }
+                }
                 
                 // Si es un struct con constructor, llamarlo aquí
                 // Por ahora, solo guardamos la dirección del struct
@@ -1036,6 +1414,7 @@ impl CodeGenerator {
                     let value = if i < values.len() { &values[i] } else { &values[values.len() - 1] };
                     
                     self.generate_expr_windows(value)?;
+                    self.float_to_gpr_if_needed(value);
                     
                     let offset = if let Some(&existing_offset) = self.variables.get(name) {
                         existing_offset
@@ -1050,6 +1429,18 @@ impl CodeGenerator {
                             offset + 8, name, if *mutable { "mutable" } else { "immutable" }));
                 }
             }
+            Stmt::Trait { name, methods } => {
+                // Solo firmas: las vtables de cada impl se emiten en la sección de datos
+                self.text_section.push(format!("    ; trait {} ({} métodos)", name, methods.len()));
+            }
+            Stmt::Impl { trait_name, type_name, .. } => {
+                // Los métodos ya se generaron junto con los del struct
+                self.text_section.push(format!("    ; impl {} for {}", trait_name, type_name));
+            }
+            Stmt::Enum { name, variants } => {
+                // Las variantes se registran antes de generar código: el enum no genera instrucciones
+                self.text_section.push(format!("    ; enum {} ({} variantes)", name, variants.len()));
+            }
             Stmt::Import(_module_name) => {
                 // Import statements son procesados en tiempo de compilación
                 // No generan código directamente, solo información de módulos
@@ -1079,7 +1470,7 @@ impl CodeGenerator {
 
                 // Then body
                 for s in then_body {
-                    self.generate_stmt_windows(s)?;
+                    self.generate_spanned_stmt_windows(s)?;
                 }
                 self.text_section.push(format!("    jmp {}", end_label));
                 
@@ -1087,7 +1478,7 @@ impl CodeGenerator {
                 if let Some(else_body) = else_body {
                     self.text_section.push(format!("{}:", else_label));
                     for s in else_body {
-                        self.generate_stmt_windows(s)?;
+                        self.generate_spanned_stmt_windows(s)?;
                     }
                 }
                 self.text_section.push(format!("{}:", end_label));
@@ -1110,7 +1501,7 @@ impl CodeGenerator {
                 self.text_section.push(format!("    je {}", loop_end));
                 
                 for s in body {
-                    self.generate_stmt_windows(s)?;
+                    self.generate_spanned_stmt_windows(s)?;
                 }
                 self.text_section.push(format!("    jmp {}", loop_start));
                 self.text_section.push(format!("{}:", loop_end));
@@ -1118,8 +1509,8 @@ impl CodeGenerator {
                 // Pop loop context
                 self.loop_stack.pop();
             }
-            Stmt::For { var, start, end, body } => {
-                self.add_debug_comment(&format!("for {} in range", var));
+            Stmt::For { vars, iter, body } => {
+                self.add_debug_comment(&format!("for {} in ...", vars.join(", ")));
                 let loop_start = self.new_label("for_start");
                 let loop_end = self.new_label("for_end");
                 let loop_continue = self.new_label("for_continue");
@@ -1130,44 +1521,23 @@ impl CodeGenerator {
                     continue_label: loop_continue.clone(),
                 });
                 
-                // Evaluar start y guardar en variable
-                self.generate_expr_windows(start)?;
-                let var_offset = self.stack_offset;
-                self.stack_offset += 8;
-                self.variables.insert(var.clone(), var_offset);
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; {} (loop counter)", var_offset + 8, var));
-                
-                // Evaluar end y guardar en registro temporal (r13 preservado)
-                self.generate_expr_windows(end)?;
-                self.text_section.push("    mov r13, rax  ; end value (preserved)".to_string());
-                
-                // Loop start
-                self.text_section.push(format!("{}:", loop_start));
-                
-                // Comparar variable con end
-                self.text_section.push(format!("    mov rax, [rbp - {}]  ; cargar {}", var_offset + 8, var));
-                self.text_section.push("    cmp rax, r13  ; comparar con end".to_string());
-                self.text_section.push(format!("    jge {}  ; si >= end, salir", loop_end));
-                
-                // Body del loop
-                for s in body {
-                    self.generate_stmt_windows(s)?;
+                // Las variables del for solo existen dentro del loop: al salir se
+                // restauran las que tapaban a una variable exterior
+                let shadowed: Vec<(String, Option<i64>)> = vars.iter().map(|var| (var.clone(), self.variables.get(var).copied())).collect();
+                let result = match iter {
+                    ForIter::Range { start, end, step } => self.generate_for_range(&vars[0], start, end, step.as_ref(), body, &loop_start, &loop_continue, &loop_end),
+                    ForIter::Iterable(_) => self.generate_for_iterable(vars, iter, body, &loop_start, &loop_continue, &loop_end),
+                };
+                for (var, previous) in shadowed {
+                    match previous {
+                        Some(offset) => self.variables.insert(var, offset),
+                        None => self.variables.remove(&var),
+                    };
                 }
                 
-                // Continue label (para incrementar contador)
-                self.text_section.push(format!("{}:", loop_continue));
-                
-                // Incrementar contador
-                self.text_section.push(format!("    mov rax, [rbp - {}]  ; cargar {}", var_offset + 8, var));
-                self.text_section.push("    inc rax  ; incrementar".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; guardar", var_offset + 8));
-                
-                // Saltar al inicio
-                self.text_section.push(format!("    jmp {}", loop_start));
-                self.text_section.push(format!("{}:", loop_end));
-                
                 // Pop loop context
                 self.loop_stack.pop();
+                result?;
             }
             Stmt::Break => {
                 self.add_debug_comment("break");
@@ -1189,7 +1559,7 @@ impl CodeGenerator {
                     self.text_section.push("    ; ERROR: continue fuera de loop".to_string());
                 }
             }
-            Stmt::Fn { visibility: _, name, params, body } => {
+            Stmt::Fn { name, params, return_type, body, .. } => {
                 self.add_debug_comment(&format!("fn {} ({})", name, params.len()));
                 // Generate function with Windows x64 calling convention (ABI-safe)
                 // Visibility no afecta la generación de código (Sprint 1.3)
@@ -1197,65 +1567,32 @@ impl CodeGenerator {
                 // No necesitamos jmp si la función se genera antes del main
                 // (las funciones de usuario se generan antes del main)
                 self.text_section.push(format!("{}:", func_label));
+                if let Some(description) = self.generic_instances.get(name) {
+                    self.text_section.push(format!("    ; instancia de {}", description));
+                }
                 
-                // Guardar stack_offset inicial para restaurar después
+                // Guardar stack_offset inicial para restaurar después; los parámetros y
+                // locales de la función no deben verse desde el resto del programa
                 let saved_stack_offset = self.stack_offset;
+                let saved_variables = self.variables.clone();
+                let saved_variable_types = self.variable_types.clone();
+                let saved_return_type = std::mem::replace(&mut self.current_return_type, return_type.clone());
                 
                 // Prologue ABI-safe: preservar registros no volátiles y alinear stack
                 // Necesitamos shadow space si la función llama a otras funciones
                 // Por ahora, siempre reservamos shadow space para seguridad
                 self.generate_abi_prologue(true);
                 
-                // Calcular espacio necesario para variables locales
-                // Parámetros adicionales (> 4) ya están en stack del caller
-                // Necesitamos espacio para:
-                // - Parámetros locales (primeros 4 se guardan en stack local)
-                // - Variables locales de la función
-                let mut local_vars_size = 0;
-                
-                // Guardar parámetros en variables locales
-                // Parámetros en registros: RCX, RDX, R8, R9 (primeros 4)
-                // Parámetros adicionales: en stack del caller [rbp + 16 + (i-4)*8]
-                // Nota: Después del prologue ABI-safe, rbp apunta al frame del caller
-                // Los parámetros adicionales están en [rbp + 16 + shadow_space + (i-4)*8]
-                // Pero como ya hicimos push de registros, necesitamos ajustar
-                // Después de prologue: rbp original está en [rbp + 0], return address en [rbp + 8]
-                // Parámetros adicionales están en [rbp + 16 + (i-4)*8] (sin contar los push del prologue)
+                // Guardar parámetros en variables locales: los primeros llegan en registros
+                // (RCX, RDX, R8, R9 en Windows; RDI, RSI, RDX, RCX, R8, R9 en System V)
+                // y los demás en el stack del caller
                 for (i, param) in params.iter().enumerate() {
-                    let offset = self.stack_offset;
-                    self.stack_offset += 8;
-                    local_vars_size += 8;
-                    self.variables.insert(param.name.clone(), offset);
-                    
-                    match i {
-                        0 => {
-                            // RCX -> guardar en stack local
-                            self.text_section.push(format!("    mov [rbp - {}], rcx  ; guardar param0: {}", offset + 8, param.name));
-                        }
-                        1 => {
-                            // RDX -> guardar en stack local
-                            self.text_section.push(format!("    mov [rbp - {}], rdx  ; guardar param1: {}", offset + 8, param.name));
-                        }
-                        2 => {
-                            // R8 -> guardar en stack local
-                            self.text_section.push(format!("    mov [rbp - {}], r8  ; guardar param2: {}", offset + 8, param.name));
-                        }
-                        3 => {
-                            // R9 -> guardar en stack local
-                            self.text_section.push(format!("    mov [rbp - {}], r9  ; guardar param3: {}", offset + 8, param.name));
-                        }
-                        _ => {
-                            // Parámetros adicionales (> 4) están en stack del caller
-                            // Después del prologue ABI-safe:
-                            // - rbp apunta al frame del caller
-                            // - Los push del prologue están antes de rbp
-                            // - Parámetros adicionales están en [rbp + 16 + (i-4)*8]
-                            //   16 = return address (8) + saved rbp (8)
-                            let stack_offset = 16 + (i - 4) * 8;
-                            self.text_section.push(format!("    mov rax, [rbp + {}]  ; cargar param{} desde stack del caller", stack_offset, i));
-                            self.text_section.push(format!("    mov [rbp - {}], rax  ; guardar param{}: {}", offset + 8, i, param.name));
-                        }
-                    }
+                    self.store_incoming_param(i, &param.name);
+                }
+                
+                for param in params {
+                    self.narrow_param_slot(param);
+                    self.record_param_struct(param);
                 }
                 
                 // Reservar espacio adicional para variables locales si es necesario
@@ -1268,16 +1605,16 @@ impl CodeGenerator {
                 let mut has_explicit_return = false;
                 
                 for s in body {
-                    match s {
+                    match &s.node {
                         Stmt::Return(_) => {
                             // Return statement: evaluar expresión y saltar al epilogue
                             has_explicit_return = true;
-                            self.generate_stmt_windows(s)?;
+                            self.generate_spanned_stmt_windows(s)?;
                             // Después de return, saltar al epilogue
                             self.text_section.push(format!("    jmp {}", return_label));
                         }
                         _ => {
-                            self.generate_stmt_windows(s)?;
+                            self.generate_spanned_stmt_windows(s)?;
                         }
                     }
                 }
@@ -1286,14 +1623,19 @@ impl CodeGenerator {
                 self.text_section.push(format!("{}:", return_label));
                 if !has_explicit_return {
                     self.text_section.push("    mov rax, 0  ; return value por defecto".to_string());
+                } else if let Some(ty) = return_type {
+                    self.narrow_rax_to(ty);
                 }
                 // Si hay return explícito, el valor ya está en RAX
                 
                 // Epilogue ABI-safe: restaurar registros y limpiar stack
                 self.generate_abi_epilogue(true);
                 
-                // Restaurar stack_offset
+                // Restaurar stack_offset y el scope exterior
                 self.stack_offset = saved_stack_offset;
+                self.variables = saved_variables;
+                self.variable_types = saved_variable_types;
+                self.current_return_type = saved_return_type;
                 
                 self.text_section.push(format!("{}_end:", func_label));
             }
@@ -1303,337 +1645,266 @@ impl CodeGenerator {
                 // El epilogue se manejará en la función (no aquí)
                 if let Some(expr) = expr {
                     self.generate_expr_windows(expr)?;
-                    // El resultado ya está en RAX
+                    // El resultado ya está en RAX (un float64 se devuelve en RAX como bits)
+                    self.float_to_gpr_if_needed(expr);
+                    // `-> Drawable` devolviendo un struct: retornar el objeto de trait
+                    if let Some(ty) = self.current_return_type.clone() {
+                        self.coerce_to(&ty, expr);
+                    }
                 } else {
                     self.text_section.push("    mov rax, 0  ; return sin valor (default 0)".to_string());
                 }
                 // Nota: NO llamamos leave/ret aquí porque el epilogue de la función lo hará
                 // El código de la función saltará al epilogue después de return
             }
-            Stmt::Struct { name, parent, fields, init, destroy, methods } => {
-                // Registrar parent para super.metodo()
-                self.struct_parents.insert(name.clone(), parent.clone());
-                
-                // Registrar campos del struct para cálculo de offsets
-                // Si hay herencia, incluir campos del padre
-                let mut all_field_names: Vec<String> = Vec::new();
-                
-                // Si hay padre, agregar sus campos primero
-                if let Some(parent_name) = &parent {
-                    if let Some(parent_fields) = self.struct_definitions.get(parent_name) {
-                        all_field_names.extend(parent_fields.clone());
-                    } else {
-                        return Err(adead_common::ADeadError::RuntimeError {
-                            message: format!("Struct padre '{}' no está definido antes de '{}'. Los structs padre deben definirse antes que los hijos.", parent_name, name),
-                        });
+            Stmt::Struct { name, parent, init, destroy, methods, .. } => {
+                let methods: Vec<MethodSource> = methods.iter().map(MethodSource::from).collect();
+                self.generate_struct_windows(name, parent.as_deref(), init.as_ref(), destroy.is_some(), &methods)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Código de un struct: sus métodos (los propios y los asociados, ver `MethodSource`)
+    /// y su constructor. Los campos y la vtable ya se registraron en `register_classes`.
+    fn generate_struct_windows(
+        &mut self,
+        name: &str,
+        parent: Option<&str>,
+        init: Option<&StructMethod>,
+        has_destroy: bool,
+        methods: &[MethodSource],
+    ) -> Result<()> {
+        // Campos (con los heredados) y vtable ya registrados en register_classes
+        if let Some(description) = self.generic_instances.get(name) {
+            self.text_section.push(format!("    ; struct {}: instancia de {}", name, description));
+        }
+        
+        // Establecer struct actual para procesar métodos
+        let old_struct = self.current_struct.take();
+        self.current_struct = Some(name.to_string());
+        
+        // Registrar si tiene destructor (para RAII)
+        if has_destroy {
+            self.structs_with_destroy.insert(name.to_string(), true);
+        }
+        
+        // Generar métodos de instancia y estáticos
+        // Nota: Los métodos pueden venir del struct directamente o desde funciones globales asociadas
+        for method in methods {
+            let method_label = format!("fn_{}_{}", name, method.name);
+            
+            // Detectar si es método estático (no tiene 'self' como primer parámetro)
+            let is_static = method.params.is_empty() || method.params[0].name != "self";
+            
+            self.text_section.push(format!("    jmp {}_end", method_label));
+            self.text_section.push(format!("{}:", method_label));
+            
+            // Guardar stack_offset inicial para restaurar después
+            let saved_stack_offset = self.stack_offset;
+            let saved_return_type = std::mem::replace(&mut self.current_return_type, method.return_type.cloned());
+            
+            // Prologue ABI-safe
+            self.generate_abi_prologue(true);
+            
+            // Parámetros según la convención del target; en métodos de instancia
+            // `self` es el primero (puntero al struct)
+            for (i, param) in method.params.iter().enumerate() {
+                self.store_incoming_param(i, &param.name);
+            }
+            if !is_static {
+                self.variable_types.insert("self".to_string(), name.to_string());
+            }
+            
+            for param in method.params {
+                self.narrow_param_slot(param);
+                self.record_param_struct(param);
+            }
+            
+            // Generar cuerpo del método
+            let return_label = format!("{}_return", method_label);
+            let mut has_explicit_return = false;
+            
+            for s in method.body {
+                match &s.node {
+                    Stmt::Return(_) => {
+                        has_explicit_return = true;
+                        self.generate_spanned_stmt_windows(s)?;
+                        self.text_section.push(format!("    jmp {}", return_label));
+                    }
+                    _ => {
+                        self.generate_spanned_stmt_windows(s)?;
                     }
                 }
+            }
+            
+            // Si no hay return explícito, retornar 0 por defecto
+            self.text_section.push(format!("{}:", return_label));
+            if !has_explicit_return {
+                self.text_section.push("    mov rax, 0  ; return value por defecto".to_string());
+            } else if let Some(ty) = method.return_type {
+                self.narrow_rax_to(ty);
+            }
+            
+            // Epilogue ABI-safe
+            self.generate_abi_epilogue(true);
+            
+            // Restaurar stack_offset
+            self.stack_offset = saved_stack_offset;
+            self.current_return_type = saved_return_type;
+            
+            self.text_section.push(format!("{}_end:", method_label));
+            
+            // Limpiar variables locales del método
+            self.variables.remove("self");
+            for param in method.params {
+                self.variables.remove(&param.name);
+                self.variable_types.remove(&param.name);
+            }
+        }
+        
+        // Registrar struct y generar código para constructor si existe
+        if let Some(init_method) = init {
+            // Generar función de constructor: fn_StructName_new
+            let init_label = format!("fn_{}_new", name);
+            self.text_section.push(format!("    jmp {}_end", init_label));
+            self.text_section.push(format!("{}:", init_label));
+            self.text_section.push("    push rbp".to_string());
+            self.text_section.push("    mov rbp, rsp".to_string());
+            self.text_section.push("    sub rsp, 64  ; espacio para variables locales".to_string());
+            
+            // self (puntero al struct) es el primer parámetro implícito; los del usuario
+            // le siguen. Se guardan todos antes de llamar al constructor del padre,
+            // que pisa los registros de argumentos.
+            let self_offset = self.store_incoming_param(0, "self");
+            self.variable_types.insert("self".to_string(), name.to_string());
+            for (i, param) in init_method.params.iter().enumerate() {
+                self.store_incoming_param(i + 1, &param.name);
+            }
+            for param in &init_method.params {
+                self.narrow_param_slot(param);
+                self.record_param_struct(param);
+            }
+            
+            // Si hay herencia, llamar al constructor del padre primero
+            if let Some(parent_name) = parent {
+                self.text_section.push(format!("    ; Llamar constructor del padre: {}", parent_name));
+                let self_arg = CallArg::Asm(format!("mov rax, [rbp - {}]  ; self", self_offset + 8));
+                self.emit_call(Callee::Label(format!("fn_{}_new", parent_name)), &[self_arg], "constructor del padre")?;
+            }
+            
+            // Generar cuerpo del constructor
+            // Los FieldAssign a self.campo se manejarán automáticamente
+            for s in &init_method.body {
+                self.generate_spanned_stmt_windows(s)?;
+            }
+            
+            self.text_section.push("    leave".to_string());
+            self.text_section.push("    ret".to_string());
+            self.text_section.push(format!("{}_end:", init_label));
+            
+            // Limpiar variables locales del constructor
+            self.variables.remove("self");
+            for param in &init_method.params {
+                self.variables.remove(&param.name);
+            }
+        }
+        
+        // Restaurar struct anterior
+        self.current_struct = old_struct;
+        Ok(())
+    }
+
+    fn generate_expr_windows(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Number(n) => {
+                self.text_section.push(format!("    mov rax, {}", n));
+            }
+            Expr::Bool(b) => {
+                // Bool: 0 = false, 1 = true en RAX
+                // En x86-64, convención común: 0 = false, cualquier valor != 0 = true
+                let value = if *b { 1 } else { 0 };
+                self.text_section.push(format!("    mov rax, {}  ; bool {}", value, b));
+            }
+            Expr::Float(f) => {
+                // Cargar constante flotante en XMM0
+                // Estrategia: almacenar float en .data y cargar desde ahí
+                let label = self.add_float_data(*f);
+                self.text_section.push(format!("    movsd xmm0, [rel {}]  ; cargar float {}", label, f));
+            }
+            Expr::String(s) => {
+                // Strings como expresiones: crear estructura String dinámica usando string_from_literal()
+                // Similar a cómo ArrayLiteral usa array_from_values()
+                
+                // Crear literal temporal en .data para pasar a string_from_literal()
+                let label = self.add_string_data(s);
+                let length = s.len();
+                
+                // Preparar parámetros para string_from_literal(puntero_a_literal, longitud)
+                // RCX = puntero a literal, RDX = longitud
+                self.text_section.push(format!("    lea rcx, [rel {}]  ; puntero a literal", label));
+                self.text_section.push(format!("    mov rdx, {}  ; longitud", length));
+                
+                // Llamar a string_from_literal
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call string_from_literal".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
                 
-                // Agregar campos propios del struct
-                let own_field_names: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
-                all_field_names.extend(own_field_names.clone());
+                // RAX contiene el puntero al String (en heap)
+                // Este puntero debe ser almacenado en una variable para uso posterior
+            }
+            Expr::ArrayLiteral(elements) => {
+                // Array literal: [1, 2, 3]
+                // Estrategia: usar array_from_values (estructura Array dinámica en heap)
+                // 1. Crear array temporal en stack con los valores
+                // 2. Llamar a array_from_values(count, pointer)
+                // 3. Retornar puntero al Array (en heap)
                 
-                self.struct_definitions.insert(name.clone(), all_field_names);
+                let count = elements.len();
+                let array_size = count * 8; // 8 bytes por elemento (int64)
+                let base_offset = self.stack_offset;
                 
-                // Establecer struct actual para procesar métodos
-                let old_struct = self.current_struct.take();
-                self.current_struct = Some(name.clone());
+                // Reservar espacio en stack para valores temporales
+                self.stack_offset += array_size as i64;
+                self.text_section.push(format!("    ; Array literal: {} elementos", count));
+                self.text_section.push(format!("    sub rsp, {}  ; reservar espacio temporal para valores", array_size));
                 
-                // Registrar si tiene destructor (para RAII)
-                if destroy.is_some() {
-                    self.structs_with_destroy.insert(name.clone(), true);
-                }
+                // Un array de objetos de trait (`let v: [Drawable] = [c, s]`) guarda
+                // cada struct como objeto de trait
+                let element_type = match self.expr_type(expr) {
+                    Some(Type::Array { element_type, .. }) => Some((**element_type).clone()),
+                    _ => None,
+                };
                 
-                // Registrar métodos de instancia (no estáticos) para vtables
-                let mut instance_methods: Vec<String> = Vec::new();
-                if let Some(parent_name) = &parent {
-                    // Si hay padre, heredar métodos del padre
-                    if let Some(parent_methods) = self.struct_methods.get(parent_name) {
-                        instance_methods.extend(parent_methods.clone());
+                // Generar y almacenar cada elemento en el array temporal. array_from_values
+                // los lee hacia direcciones crecientes: el elemento 0 va en la más baja
+                let values_offset = base_offset + array_size as i64;
+                for (i, element) in elements.iter().enumerate() {
+                    self.generate_expr_windows(element)?;
+                    self.float_to_gpr_if_needed(element);
+                    if let Some(element_type) = &element_type {
+                        self.coerce_to(element_type, element);
                     }
-                }
-                
-                // Generar métodos de instancia y estáticos
-                // Nota: Los métodos pueden venir del struct directamente o desde funciones globales asociadas
-                for (method_name, method) in methods {
-                    let method_label = format!("fn_{}_{}", name, method_name);
-                    
-                    // Detectar si es método estático (no tiene 'self' como primer parámetro)
-                    let is_static = method.params.is_empty() || method.params[0].name != "self";
-                    
-                    // Registrar métodos de instancia para vtable (no estáticos)
-                    if !is_static {
-                        // Si el método ya existe (heredado), reemplazarlo (override)
-                        if let Some(pos) = instance_methods.iter().position(|m| m == method_name) {
-                            instance_methods[pos] = method_name.clone();
-                        } else {
-                            instance_methods.push(method_name.clone());
-                        }
-                    }
-                    
-                    self.text_section.push(format!("    jmp {}_end", method_label));
-                    self.text_section.push(format!("{}:", method_label));
-                    
-                    // Guardar stack_offset inicial para restaurar después
-                    let saved_stack_offset = self.stack_offset;
-                    
-                    // Prologue ABI-safe
-                    self.generate_abi_prologue(true);
-                    
-                    if !is_static {
-                        // Método de instancia: self viene en RCX (primer parámetro implícito)
-                    let self_offset = self.stack_offset;
-                    self.stack_offset += 8;
-                    self.variables.insert("self".to_string(), self_offset);
-                    self.variable_types.insert("self".to_string(), name.clone());
-                    self.text_section.push(format!("    mov [rbp - {}], rcx  ; guardar self", self_offset + 8));
-                    }
-                    
-                    // Parámetros del método vienen en RCX (si estático), RDX, R8, R9... (si instancia)
-                    let param_start_reg = if is_static { 0 } else { 1 }; // Si estático, RCX es primer param; si instancia, RCX es self
-                    for (i, param) in method.params.iter().enumerate() {
-                        if !is_static && i == 0 && param.name == "self" {
-                            // self ya fue manejado arriba, saltar
-                            continue;
-                        }
-                        let offset = self.stack_offset;
-                        self.stack_offset += 8;
-                        self.variables.insert(param.name.clone(), offset);
-                        
-                        // Calcular índice real del parámetro (ajustado si es método de instancia)
-                        let param_index = if is_static { i } else { i - 1 }; // Si instancia, self no cuenta
-                        
-                        match param_index {
-                            0 => {
-                                // RCX (si estático) o RDX (si instancia) -> guardar en stack local
-                                let reg = if is_static { "rcx" } else { "rdx" };
-                                self.text_section.push(format!("    mov [rbp - {}], {}  ; guardar param{}: {}", offset + 8, reg, param_index, param.name));
-                            }
-                            1 => {
-                                // RDX (si estático) o R8 (si instancia) -> guardar en stack local
-                                let reg = if is_static { "rdx" } else { "r8" };
-                                self.text_section.push(format!("    mov [rbp - {}], {}  ; guardar param{}: {}", offset + 8, reg, param_index, param.name));
-                            }
-                            2 => {
-                                // R8 (si estático) o R9 (si instancia) -> guardar en stack local
-                                let reg = if is_static { "r8" } else { "r9" };
-                                self.text_section.push(format!("    mov [rbp - {}], {}  ; guardar param{}: {}", offset + 8, reg, param_index, param.name));
-                            }
-                            3 => {
-                                // R9 (si estático) o stack (si instancia) -> guardar en stack local
-                                if is_static {
-                                    self.text_section.push(format!("    mov [rbp - {}], r9  ; guardar param{}: {}", offset + 8, param_index, param.name));
-                                } else {
-                                    let stack_offset = 16 + (param_index - 3) * 8;
-                                    self.text_section.push(format!("    mov rax, [rbp + {}]  ; cargar param{} desde stack del caller", stack_offset, param_index));
-                                    self.text_section.push(format!("    mov [rbp - {}], rax  ; guardar param{}: {}", offset + 8, param_index, param.name));
-                                }
-                            }
-                            _ => {
-                                // Parámetros adicionales están en stack del caller
-                                let stack_offset = 16 + (param_index - 4) * 8;
-                                self.text_section.push(format!("    mov rax, [rbp + {}]  ; cargar param{} desde stack del caller", stack_offset, param_index));
-                                self.text_section.push(format!("    mov [rbp - {}], rax  ; guardar param{}: {}", offset + 8, param_index, param.name));
-                            }
-                        }
-                    }
-                    
-                    // Generar cuerpo del método
-                    let return_label = format!("{}_return", method_label);
-                    let mut has_explicit_return = false;
-                    
-                    for s in &method.body {
-                        match s {
-                            Stmt::Return(_) => {
-                                has_explicit_return = true;
-                                self.generate_stmt_windows(s)?;
-                                self.text_section.push(format!("    jmp {}", return_label));
-                            }
-                            _ => {
-                                self.generate_stmt_windows(s)?;
-                            }
-                        }
-                    }
-                    
-                    // Si no hay return explícito, retornar 0 por defecto
-                    self.text_section.push(format!("{}:", return_label));
-                    if !has_explicit_return {
-                        self.text_section.push("    mov rax, 0  ; return value por defecto".to_string());
-                    }
-                    
-                    // Epilogue ABI-safe
-                    self.generate_abi_epilogue(true);
-                    
-                    // Restaurar stack_offset
-                    self.stack_offset = saved_stack_offset;
-                    
-                    self.text_section.push(format!("{}_end:", method_label));
-                    
-                    // Limpiar variables locales del método
-                    self.variables.remove("self");
-                    for param in &method.params {
-                        self.variables.remove(&param.name);
-                    }
-                }
-                
-                // Guardar métodos de instancia para este struct
-                self.struct_methods.insert(name.clone(), instance_methods.clone());
-                
-                // Generar vtable en sección .data
-                if !instance_methods.is_empty() {
-                    let vtable_label = format!("vtable_{}", name);
-                    self.data_section.push(format!("{}:", vtable_label));
-                    for method_name in &instance_methods {
-                        let method_ptr = format!("fn_{}_{}", name, method_name);
-                        self.data_section.push(format!("    dq {}  ; método {}", method_ptr, method_name));
-                    }
-                }
-                
-                // Registrar struct y generar código para constructor si existe
-                if let Some(init_method) = init {
-                    // Generar función de constructor: fn_StructName_new
-                    let init_label = format!("fn_{}_new", name);
-                    self.text_section.push(format!("    jmp {}_end", init_label));
-                    self.text_section.push(format!("{}:", init_label));
-                    self.text_section.push("    push rbp".to_string());
-                    self.text_section.push("    mov rbp, rsp".to_string());
-                    self.text_section.push("    sub rsp, 64  ; espacio para variables locales".to_string());
-                    
-                    // self viene en RCX (primer parámetro implícito)
-                    let self_offset = self.stack_offset;
-                    self.stack_offset += 8;
-                    self.variables.insert("self".to_string(), self_offset);
-                    self.variable_types.insert("self".to_string(), name.clone());
-                    self.text_section.push(format!("    mov [rbp - {}], rcx  ; guardar self", self_offset + 8));
-                    
-                    // Si hay herencia, llamar al constructor del padre primero
-                    if let Some(parent_name) = parent {
-                        self.text_section.push(format!("    ; Llamar constructor del padre: {}", parent_name));
-                        // self ya está en RCX, pasarlo al constructor del padre
-                        self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
-                        self.text_section.push(format!("    call fn_{}_new  ; constructor del padre", parent_name));
-                        self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
-                    }
-                    
-                    // Los parámetros del usuario vienen en RDX, R8, R9... (RCX tiene self)
-                    for (i, param) in init_method.params.iter().enumerate() {
-                        let offset = self.stack_offset;
-                        self.stack_offset += 8;
-                        self.variables.insert(param.name.clone(), offset);
-                        
-                        let reg = match i {
-                            0 => "rdx",   // Primer param usuario
-                            1 => "r8",    // Segundo param usuario
-                            2 => "r9",    // Tercer param usuario
-                            _ => {
-                                let stack_offset = 48 + (i - 3) * 8;  // Stack params después de shadow space
-                                self.text_section.push(format!("    mov rax, [rbp + {}]", stack_offset));
-                                self.text_section.push(format!("    mov [rbp - {}], rax  ; param {}", offset + 8, param.name));
-                                continue;
-                            }
-                        };
-                        self.text_section.push(format!("    mov [rbp - {}], {}  ; param {}", offset + 8, reg, param.name));
-                    }
-                    
-                    // Generar cuerpo del constructor
-                    // Los FieldAssign a self.campo se manejarán automáticamente
-                    for s in &init_method.body {
-                        self.generate_stmt_windows(s)?;
-                    }
-                    
-                    self.text_section.push("    leave".to_string());
-                    self.text_section.push("    ret".to_string());
-                    self.text_section.push(format!("{}_end:", init_label));
-                    
-                    // Limpiar variables locales del constructor
-                    self.variables.remove("self");
-                    for param in &init_method.params {
-                        self.variables.remove(&param.name);
-                    }
-                }
-                
-                // Restaurar struct anterior
-                self.current_struct = old_struct;
-                // Struct definitions are type information only, no code generation needed for the struct itself
-            }
-        }
-        Ok(())
-    }
-
-    fn generate_expr_windows(&mut self, expr: &Expr) -> Result<()> {
-        match expr {
-            Expr::Number(n) => {
-                self.text_section.push(format!("    mov rax, {}", n));
-            }
-            Expr::Bool(b) => {
-                // Bool: 0 = false, 1 = true en RAX
-                // En x86-64, convención común: 0 = false, cualquier valor != 0 = true
-                let value = if *b { 1 } else { 0 };
-                self.text_section.push(format!("    mov rax, {}  ; bool {}", value, b));
-            }
-            Expr::Float(f) => {
-                // Cargar constante flotante en XMM0
-                // Estrategia: almacenar float en .data y cargar desde ahí
-                let label = self.add_float_data(*f);
-                self.text_section.push(format!("    movsd xmm0, [rel {}]  ; cargar float {}", label, f));
-            }
-            Expr::String(s) => {
-                // Strings como expresiones: crear estructura String dinámica usando string_from_literal()
-                // Similar a cómo ArrayLiteral usa array_from_values()
-                
-                // Crear literal temporal en .data para pasar a string_from_literal()
-                let label = self.add_string_data(s);
-                let length = s.len();
-                
-                // Preparar parámetros para string_from_literal(puntero_a_literal, longitud)
-                // RCX = puntero a literal, RDX = longitud
-                self.text_section.push(format!("    lea rcx, [rel {}]  ; puntero a literal", label));
-                self.text_section.push(format!("    mov rdx, {}  ; longitud", length));
-                
-                // Llamar a string_from_literal
-                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
-                self.text_section.push("    call string_from_literal".to_string());
-                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
-                
-                // RAX contiene el puntero al String (en heap)
-                // Este puntero debe ser almacenado en una variable para uso posterior
-            }
-            Expr::ArrayLiteral(elements) => {
-                // Array literal: [1, 2, 3]
-                // Estrategia: usar array_from_values (estructura Array dinámica en heap)
-                // 1. Crear array temporal en stack con los valores
-                // 2. Llamar a array_from_values(count, pointer)
-                // 3. Retornar puntero al Array (en heap)
-                
-                let count = elements.len();
-                let array_size = count * 8; // 8 bytes por elemento (int64)
-                let base_offset = self.stack_offset;
-                
-                // Reservar espacio en stack para valores temporales
-                self.stack_offset += array_size as i64;
-                self.text_section.push(format!("    ; Array literal: {} elementos", count));
-                self.text_section.push(format!("    sub rsp, {}  ; reservar espacio temporal para valores", array_size));
-                
-                // Generar y almacenar cada elemento en el array temporal
-                for (i, element) in elements.iter().enumerate() {
-                    self.generate_expr_windows(element)?;
-                    let element_offset = base_offset + (i as i64 * 8);
-                    self.text_section.push(format!("    mov [rbp - {}], rax  ; valor temporal[{}]", element_offset + 8, i));
+                    let element_offset = values_offset - (i as i64 * 8);
+                    self.text_section.push(format!("    mov [rbp - {}], rax  ; valor temporal[{}]", element_offset, i));
                 }
                 
                 // Preparar parámetros para array_from_values(count, pointer)
                 // RCX = count, RDX = puntero a valores temporales
                 self.text_section.push(format!("    mov rcx, {}  ; count", count));
-                self.text_section.push(format!("    lea rdx, [rbp - {}]  ; puntero a valores temporales", base_offset + 8));
+                self.text_section.push(format!("    lea rdx, [rbp - {}]  ; puntero a valores temporales", values_offset));
                 
                 // Llamar a array_from_values
                 self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
                 self.text_section.push("    call array_from_values".to_string());
                 self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
                 
-                // Liberar espacio temporal del stack
-                self.text_section.push(format!("    add rsp, {}  ; liberar espacio temporal", array_size));
-                self.stack_offset -= array_size as i64;
+                // Liberar espacio temporal del stack, salvo que los elementos hayan
+                // reservado memoria detrás (structs, objetos de trait): seguirían vivos
+                if self.stack_offset == base_offset + array_size as i64 {
+                    self.text_section.push(format!("    add rsp, {}  ; liberar espacio temporal", array_size));
+                    self.stack_offset -= array_size as i64;
+                }
                 
                 // RAX contiene el puntero al Array (en heap)
                 // Este puntero debe ser almacenado en una variable para uso posterior
@@ -1660,177 +1931,60 @@ impl CodeGenerator {
             }
             Expr::Lambda { params, body } => {
                 // Lambda: lambda x, y: x + y
-                // Por ahora, generar una función anónima y retornar su dirección
-                let lambda_name = self.new_label("lambda");
-                
-                // Guardar contexto actual
-                let saved_vars = self.variables.clone();
-                let saved_offset = self.stack_offset;
-                
-                // Generar función lambda
-                self.text_section.push(format!("    jmp {}_end  ; saltar definición de lambda", lambda_name));
-                self.text_section.push(format!("{}:", lambda_name));
-                self.text_section.push("    push rbp".to_string());
-                self.text_section.push("    mov rbp, rsp".to_string());
-                self.text_section.push("    sub rsp, 64".to_string());
-                
-                // Mapear parámetros a registros (Windows x64: RCX, RDX, R8, R9)
-                let param_regs = ["rcx", "rdx", "r8", "r9"];
-                self.stack_offset = 0;
-                for (i, param) in params.iter().enumerate() {
-                    self.stack_offset += 8;
-                    self.variables.insert(param.clone(), self.stack_offset);
-                    if i < 4 {
-                        self.text_section.push(format!("    mov [rbp - {}], {}  ; param {}", self.stack_offset + 8, param_regs[i], param));
-                    }
-                }
-                
-                // Generar cuerpo de lambda
-                self.generate_expr_windows(body)?;
-                
-                // Retornar
-                self.text_section.push("    leave".to_string());
-                self.text_section.push("    ret".to_string());
-                self.text_section.push(format!("{}_end:", lambda_name));
-                
-                // Restaurar contexto
-                self.variables = saved_vars;
-                self.stack_offset = saved_offset;
-                
-                // Retornar dirección de la lambda
-                self.text_section.push(format!("    lea rax, [rel {}]  ; dirección de lambda", lambda_name));
+                // Closure = función fuera de línea + entorno con las variables capturadas (por valor)
+                self.generate_closure(params, body)?;
             }
-            Expr::ListComprehension { expr, var, iter, condition } => {
-                // List comprehension: [x * 2 for x in lista] o [x for x in lista if x > 0]
-                self.text_section.push("    ; List comprehension".to_string());
-                
-                // Evaluar el iterador
-                self.generate_expr_windows(iter)?;
-                self.text_section.push("    mov rsi, rax  ; array fuente".to_string());
-                self.text_section.push("    mov rcx, [rsi + 8]  ; length".to_string());
-                self.text_section.push("    mov rbx, [rsi + 0]  ; data".to_string());
-                
-                // Crear array resultado (mismo tamaño por ahora)
-                self.text_section.push("    push rcx  ; guardar length".to_string());
-                self.text_section.push("    push rbx  ; guardar data ptr".to_string());
-                self.text_section.push("    push rsi  ; guardar array".to_string());
-                
-                // Reservar espacio para resultado
-                let result_offset = self.stack_offset;
-                self.stack_offset += 256; // Espacio para hasta 32 elementos
-                self.text_section.push(format!("    lea r12, [rbp - {}]  ; resultado", result_offset + 8));
-                self.text_section.push("    xor r13, r13  ; índice resultado".to_string());
-                
-                let comp_loop = self.new_label("comp_loop");
-                let comp_skip = self.new_label("comp_skip");
-                let comp_end = self.new_label("comp_end");
-                
-                self.text_section.push("    pop rsi".to_string());
-                self.text_section.push("    pop rbx".to_string());
-                self.text_section.push("    pop rcx".to_string());
-                self.text_section.push("    xor r14, r14  ; índice fuente".to_string());
-                
-                self.text_section.push(format!("{}:", comp_loop));
-                self.text_section.push("    cmp r14, rcx".to_string());
-                self.text_section.push(format!("    jge {}", comp_end));
-                
-                // Cargar elemento actual en variable de iteración
-                self.text_section.push("    push rcx".to_string());
-                self.text_section.push("    push rbx".to_string());
-                self.text_section.push("    push r12".to_string());
-                self.text_section.push("    push r13".to_string());
-                self.text_section.push("    push r14".to_string());
-                
-                // Guardar elemento en variable temporal
-                let var_offset = self.stack_offset;
-                self.stack_offset += 8;
-                self.variables.insert(var.clone(), var_offset);
-                self.text_section.push("    mov rax, [rbx + r14*8]".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; {}", var_offset + 8, var));
-                
-                // Evaluar condición si existe
-                if let Some(cond) = condition {
-                    self.generate_expr_windows(cond)?;
-                    self.text_section.push("    cmp rax, 0".to_string());
-                    self.text_section.push(format!("    je {}_restore", comp_skip));
-                }
-                
-                // Evaluar expresión
-                self.generate_expr_windows(expr)?;
-                
-                // Guardar resultado
-                self.text_section.push("    pop r14".to_string());
-                self.text_section.push("    pop r13".to_string());
-                self.text_section.push("    pop r12".to_string());
-                self.text_section.push("    mov [r12 + r13*8], rax".to_string());
-                self.text_section.push("    inc r13".to_string());
-                self.text_section.push("    pop rbx".to_string());
-                self.text_section.push("    pop rcx".to_string());
-                self.text_section.push("    inc r14".to_string());
-                self.text_section.push(format!("    jmp {}", comp_loop));
-                
-                if condition.is_some() {
-                    self.text_section.push(format!("{}_restore:", comp_skip));
-                    self.text_section.push("    pop r14".to_string());
-                    self.text_section.push("    pop r13".to_string());
-                    self.text_section.push("    pop r12".to_string());
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push("    pop rcx".to_string());
-                    self.text_section.push("    inc r14".to_string());
-                    self.text_section.push(format!("    jmp {}", comp_loop));
-                }
-                
-                self.text_section.push(format!("{}:", comp_end));
-                self.text_section.push(format!("    lea rax, [rbp - {}]  ; retornar resultado", result_offset + 8));
-                
-                self.variables.remove(var);
+            Expr::ListComprehension { expr: element, clauses } => {
+                // List comprehension: [x * 2 for x in lista if x > 0] → array_new + array_append
+                self.generate_comprehension(ComprehensionTarget::List(element), clauses)?;
             }
             Expr::DictLiteral { pairs } => {
                 // Dict literal: {"key": value, ...}
-                // Estructura: [count, capacity, keys_ptr, values_ptr]
-                let count = pairs.len();
-                let dict_offset = self.stack_offset;
-                self.stack_offset += 32 + (count * 16) as i64; // 32 bytes header + 16 bytes por par
-                
-                self.text_section.push(format!("    ; Dict literal: {} pares", count));
-                self.text_section.push(format!("    lea rax, [rbp - {}]", dict_offset + 8));
-                self.text_section.push(format!("    mov qword [rax], {}  ; count", count));
-                self.text_section.push(format!("    mov qword [rax + 8], {}  ; capacity", count));
+                // Tabla hash en heap: dict_new con capacidad para todos los pares + dict_set por par
+                let key_kind = self.dict_key_kind(expr, pairs.first().map(|(key, _)| key));
+                self.text_section.push(format!("    ; Dict literal: {} pares", pairs.len()));
+                self.text_section.push(format!("    mov rcx, {}  ; entradas previstas", pairs.len()));
+                self.text_section.push(format!("    mov rdx, {}  ; tipo de clave", key_kind));
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call dict_new".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+                self.text_section.push("    push rax  ; guardar puntero al Dict".to_string());
                 
-                // Almacenar cada par key-value
-                for (i, (key, value)) in pairs.iter().enumerate() {
-                    self.text_section.push("    push rax".to_string());
+                for (key, value) in pairs {
                     self.generate_expr_windows(key)?;
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push(format!("    mov [rbx + {} + 16], rax  ; key[{}]", i * 16, i));
-                    self.text_section.push("    push rbx".to_string());
+                    self.text_section.push("    push rax  ; clave".to_string());
                     self.generate_expr_windows(value)?;
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push(format!("    mov [rbx + {} + 24], rax  ; value[{}]", i * 16, i));
+                    self.float_to_gpr_if_needed(value);
+                    // dict_set(dict, clave, valor, tipo de clave)
+                    self.text_section.push("    mov r8, rax  ; valor".to_string());
+                    self.text_section.push("    pop rdx  ; clave".to_string());
+                    self.text_section.push("    mov rcx, [rsp]  ; puntero al Dict".to_string());
+                    self.text_section.push(format!("    mov r9, {}  ; tipo de clave", key_kind));
+                    self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                    self.text_section.push("    call dict_set".to_string());
+                    self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
                 }
                 
-                self.text_section.push(format!("    lea rax, [rbp - {}]", dict_offset + 8));
+                self.text_section.push("    pop rax  ; puntero al Dict".to_string());
             }
             Expr::SetLiteral(elements) => {
                 // Set literal: {1, 2, 3}
-                // Similar a array pero sin duplicados (verificación en runtime)
-                let count = elements.len();
-                let set_offset = self.stack_offset;
-                self.stack_offset += 16 + (count * 8) as i64;
-                
-                self.text_section.push(format!("    ; Set literal: {} elementos", count));
-                self.text_section.push(format!("    lea rax, [rbp - {}]", set_offset + 8));
-                self.text_section.push(format!("    mov qword [rax], {}  ; count", count));
-                self.text_section.push(format!("    mov qword [rax + 8], {}  ; capacity", count));
+                // Tabla hash en heap (un Dict con valores en 1): dict_set descarta los duplicados
+                let key_kind = self.dict_key_kind(expr, elements.first());
+                self.text_section.push(format!("    ; Set literal: {} elementos", elements.len()));
+                self.text_section.push(format!("    mov rcx, {}  ; entradas previstas", elements.len()));
+                self.text_section.push(format!("    mov rdx, {}  ; tipo de elemento", key_kind));
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call dict_new".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+                self.text_section.push("    push rax  ; guardar puntero al Set".to_string());
                 
-                for (i, element) in elements.iter().enumerate() {
-                    self.text_section.push("    push rax".to_string());
+                for element in elements {
                     self.generate_expr_windows(element)?;
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push(format!("    mov [rbx + {} + 16], rax  ; elem[{}]", i * 8, i));
+                    self.emit_set_add_top(key_kind);
                 }
                 
-                self.text_section.push(format!("    lea rax, [rbp - {}]", set_offset + 8));
+                self.text_section.push("    pop rax  ; puntero al Set".to_string());
             }
             Expr::Ternary { condition, then_expr, else_expr } => {
                 // Operador ternario: value if condition else other
@@ -1851,135 +2005,15 @@ impl CodeGenerator {
                 
                 self.text_section.push(format!("{}:", end_label));
             }
-            Expr::DictComprehension { key_expr, value_expr, var, iter, condition } => {
-                // Dict comprehension: {k: v for k in items}
-                self.text_section.push("    ; Dict comprehension".to_string());
-                self.generate_expr_windows(iter)?;
-                self.text_section.push("    mov rsi, rax".to_string());
-                
-                let result_offset = self.stack_offset;
-                self.stack_offset += 512;
-                self.text_section.push(format!("    lea r12, [rbp - {}]", result_offset + 8));
-                self.text_section.push("    mov qword [r12], 0  ; count = 0".to_string());
-                
-                let var_offset = self.stack_offset;
-                self.stack_offset += 8;
-                self.variables.insert(var.clone(), var_offset);
-                
-                // Iterar (simplificado)
-                self.text_section.push("    mov rcx, [rsi + 8]".to_string());
-                self.text_section.push("    mov rbx, [rsi]".to_string());
-                let loop_label = self.new_label("dictcomp_loop");
-                let end_label = self.new_label("dictcomp_end");
-                self.text_section.push("    xor r14, r14".to_string());
-                self.text_section.push(format!("{}:", loop_label));
-                self.text_section.push("    cmp r14, rcx".to_string());
-                self.text_section.push(format!("    jge {}", end_label));
-                
-                self.text_section.push("    mov rax, [rbx + r14*8]".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax", var_offset + 8));
-                
-                if let Some(cond) = condition {
-                    self.text_section.push("    push rcx".to_string());
-                    self.text_section.push("    push rbx".to_string());
-                    self.text_section.push("    push r14".to_string());
-                    self.generate_expr_windows(cond)?;
-                    self.text_section.push("    pop r14".to_string());
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push("    pop rcx".to_string());
-                    self.text_section.push("    cmp rax, 0".to_string());
-                    self.text_section.push(format!("    je {}_skip", loop_label));
-                }
-                
-                // Generar key y value
-                self.text_section.push("    push rcx".to_string());
-                self.text_section.push("    push rbx".to_string());
-                self.text_section.push("    push r14".to_string());
-                self.generate_expr_windows(key_expr)?;
-                self.text_section.push("    push rax  ; key".to_string());
-                self.generate_expr_windows(value_expr)?;
-                self.text_section.push("    mov rdx, rax  ; value".to_string());
-                self.text_section.push("    pop rcx  ; key".to_string());
-                self.text_section.push("    pop r14".to_string());
-                self.text_section.push("    pop rbx".to_string());
-                self.text_section.push("    pop rcx".to_string());
-                
-                if condition.is_some() {
-                    self.text_section.push(format!("{}_skip:", loop_label));
-                }
-                
-                self.text_section.push("    inc r14".to_string());
-                self.text_section.push(format!("    jmp {}", loop_label));
-                self.text_section.push(format!("{}:", end_label));
-                self.text_section.push(format!("    lea rax, [rbp - {}]", result_offset + 8));
-                
-                self.variables.remove(var);
+            Expr::DictComprehension { key_expr, value_expr, clauses } => {
+                // Dict comprehension: {k: v for k in lista} → dict_new + dict_set
+                let key_kind = self.dict_key_kind(expr, Some(key_expr));
+                self.generate_comprehension(ComprehensionTarget::Dict(key_expr, value_expr, key_kind), clauses)?;
             }
-            Expr::SetComprehension { expr, var, iter, condition } => {
-                // Set comprehension: {x for x in items}
-                self.text_section.push("    ; Set comprehension".to_string());
-                self.generate_expr_windows(iter)?;
-                self.text_section.push("    mov rsi, rax".to_string());
-                
-                let result_offset = self.stack_offset;
-                self.stack_offset += 256;
-                self.text_section.push(format!("    lea r12, [rbp - {}]", result_offset + 8));
-                self.text_section.push("    mov qword [r12], 0".to_string());
-                
-                let var_offset = self.stack_offset;
-                self.stack_offset += 8;
-                self.variables.insert(var.clone(), var_offset);
-                
-                self.text_section.push("    mov rcx, [rsi + 8]".to_string());
-                self.text_section.push("    mov rbx, [rsi]".to_string());
-                let loop_label = self.new_label("setcomp_loop");
-                let end_label = self.new_label("setcomp_end");
-                self.text_section.push("    xor r14, r14".to_string());
-                self.text_section.push("    xor r13, r13  ; result index".to_string());
-                self.text_section.push(format!("{}:", loop_label));
-                self.text_section.push("    cmp r14, rcx".to_string());
-                self.text_section.push(format!("    jge {}", end_label));
-                
-                self.text_section.push("    mov rax, [rbx + r14*8]".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax", var_offset + 8));
-                
-                if let Some(cond) = condition {
-                    self.text_section.push("    push rcx".to_string());
-                    self.text_section.push("    push rbx".to_string());
-                    self.text_section.push("    push r14".to_string());
-                    self.text_section.push("    push r13".to_string());
-                    self.generate_expr_windows(cond)?;
-                    self.text_section.push("    pop r13".to_string());
-                    self.text_section.push("    pop r14".to_string());
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push("    pop rcx".to_string());
-                    self.text_section.push("    cmp rax, 0".to_string());
-                    self.text_section.push(format!("    je {}_skip", loop_label));
-                }
-                
-                self.text_section.push("    push rcx".to_string());
-                self.text_section.push("    push rbx".to_string());
-                self.text_section.push("    push r14".to_string());
-                self.text_section.push("    push r13".to_string());
-                self.generate_expr_windows(expr)?;
-                self.text_section.push("    pop r13".to_string());
-                self.text_section.push("    pop r14".to_string());
-                self.text_section.push("    pop rbx".to_string());
-                self.text_section.push("    pop rcx".to_string());
-                self.text_section.push("    mov [r12 + r13*8 + 16], rax".to_string());
-                self.text_section.push("    inc r13".to_string());
-                
-                if condition.is_some() {
-                    self.text_section.push(format!("{}_skip:", loop_label));
-                }
-                
-                self.text_section.push("    inc r14".to_string());
-                self.text_section.push(format!("    jmp {}", loop_label));
-                self.text_section.push(format!("{}:", end_label));
-                self.text_section.push("    mov [r12], r13  ; guardar count".to_string());
-                self.text_section.push(format!("    lea rax, [rbp - {}]", result_offset + 8));
-                
-                self.variables.remove(var);
+            Expr::SetComprehension { expr: element, clauses } => {
+                // Set comprehension: {x for x in lista} → dict_new + dict_set con valor 1
+                let key_kind = self.dict_key_kind(expr, Some(element));
+                self.generate_comprehension(ComprehensionTarget::Set(element, key_kind), clauses)?;
             }
             Expr::Borrow { expr, .. } => {
                 // Borrowing: generar dirección de la expresión
@@ -2007,12 +2041,25 @@ impl CodeGenerator {
                     // Variables are stored at negative offsets from rbp
                     // offset is the stack offset, we need to use it as negative
                     self.text_section.push(format!("    mov rax, [rbp - {}]  ; load variable {}", offset + 8, name));
+                    if self.is_float_expr(expr) {
+                        self.text_section.push(format!("    movq xmm0, rax  ; {} es float64", name));
+                    }
+                } else if self.param_types.contains_key(name) {
+                    // Función usada como valor: closure estática [código, entorno = 0]
+                    if self.function_values.insert(name.clone()) {
+                        self.data_section.push(format!("closure_fn_{}: dq fn_{}, 0  ; función {} como valor", name, name, name));
+                    }
+                    self.text_section.push(format!("    lea rax, [rel closure_fn_{}]  ; función {}", name, name));
                 } else {
                     return Err(adead_common::ADeadError::RuntimeError {
                         message: format!("undefined variable: {} (variables must be declared with 'let')", name),
                     });
                 }
             }
+            Expr::BinaryOp { left, op: BinOp::In, right } => {
+                // x in s -> dict_contains(s, x): pertenencia a un set o clave de un dict
+                self.emit_runtime_call("dict_contains", &[right, left], None)?;
+            }
             Expr::BinaryOp { left, op, right } => {
                 // Detectar si ambos operandos son strings (concatenación)
                 let is_string_op = self.is_string_expr(left) && self.is_string_expr(right) && *op == BinOp::Add;
@@ -2090,13 +2137,42 @@ impl CodeGenerator {
                             self.text_section.push("    divsd xmm1, xmm0  ; float64 division (XMM1 / XMM0, result in XMM1)".to_string());
                             self.text_section.push("    movsd xmm0, xmm1  ; mover resultado a XMM0".to_string());
                         }
+                        // Comparaciones con ucomisd (XMM1 = left, XMM0 = right): con NaN el
+                        // resultado es "unordered" (ZF = PF = CF = 1), así que solo `!=` es cierto.
+                        // `<` y `<=` se evalúan como `right > left` y `right >= left` para que
+                        // CF = 1 de unordered no los haga ciertos.
+                        BinOp::Eq => {
+                            self.text_section.push("    ucomisd xmm1, xmm0  ; comparar float64".to_string());
+                            self.text_section.push("    sete al".to_string());
+                            self.text_section.push("    setnp cl  ; NaN: unordered (PF = 1) nunca es igual".to_string());
+                            self.text_section.push("    and al, cl".to_string());
+                            self.text_section.push("    movzx rax, al".to_string());
+                        }
+                        BinOp::Ne => {
+                            self.text_section.push("    ucomisd xmm1, xmm0  ; comparar float64".to_string());
+                            self.text_section.push("    setne al".to_string());
+                            self.text_section.push("    setp cl  ; NaN: unordered (PF = 1) siempre es distinto".to_string());
+                            self.text_section.push("    or al, cl".to_string());
+                            self.text_section.push("    movzx rax, al".to_string());
+                        }
+                        BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                            let (first, second, set) = match op {
+                                BinOp::Lt => ("xmm0", "xmm1", "seta"),
+                                BinOp::Le => ("xmm0", "xmm1", "setae"),
+                                BinOp::Gt => ("xmm1", "xmm0", "seta"),
+                                _ => ("xmm1", "xmm0", "setae"),
+                            };
+                            self.text_section.push(format!("    ucomisd {}, {}  ; comparar float64 (falso con NaN)", first, second));
+                            self.text_section.push(format!("    {} al", set));
+                            self.text_section.push("    movzx rax, al".to_string());
+                        }
                         _ => {
                             return Err(adead_common::ADeadError::RuntimeError {
-                                message: format!("Comparison operators (==, !=, <, etc.) with floats not yet implemented"),
+                                message: format!("Operator {:?} with floats not yet implemented", op),
                             });
                         }
                     }
-                    // Resultado queda en XMM0 (convención para floats)
+                    // Resultado queda en XMM0 (convención para floats); las comparaciones dejan 0/1 en RAX
                 } else {
                     // Operaciones con enteros (código original)
                     self.generate_expr_windows(left)?;
@@ -2213,6 +2289,7 @@ impl CodeGenerator {
                             self.text_section.push("    or al, bl".to_string());
                             self.text_section.push("    movzx rax, al".to_string());
                         }
+                        BinOp::In => unreachable!("`in` se genera con dict_contains"),
                     }
                 }
                 }
@@ -2226,6 +2303,16 @@ impl CodeGenerator {
                 self.text_section.push("    movzx rax, al".to_string());
             }
             Expr::Call { module, name, args } => {
+                // Closure guardada en una variable local: llamada indirecta (las funciones
+                // del usuario tienen prioridad sobre una variable con el mismo nombre)
+                if module.is_none() && !self.param_types.contains_key(name) {
+                    if let Some(&slot) = self.variables.get(name) {
+                        let call_args: Vec<CallArg> = args.iter().map(|arg| CallArg::Expr(arg)).collect();
+                        self.emit_call(Callee::Closure(slot), &call_args, &format!("closure {}", name))?;
+                        self.float_result_if_needed(expr);
+                        return Ok(());
+                    }
+                }
                 // Detectar llamada a constructor PRIMERO: ClassName.new(...)
                 // DEBE ir ANTES de built-ins y otras llamadas para tener prioridad
                 if let Some(class_name) = &module {
@@ -2236,112 +2323,96 @@ impl CodeGenerator {
                             });
                     }
                             // CONSTRUCTOR: ClassName.new(args)
-                        // Layout simple: [campo0 (0)] [campo1 (8)] [campo2 (16)] ...
-                        // 1. Reservar espacio en stack para el struct (sin vtable para structs simples)
-                            let num_fields = self.struct_definitions.get(class_name)
-                                .map(|f| f.len())
-                                .unwrap_or(0);
-                        let struct_size = num_fields * 8; // Sin vtable para structs simples
-                            let struct_offset = self.stack_offset;
-                            self.stack_offset += struct_size as i64;
-                            
-                        self.text_section.push(format!("    ; Constructor: {}.new() ({} campos, {} bytes)", class_name, num_fields, struct_size));
-                        self.text_section.push(format!("    sub rsp, {}  ; reservar espacio para struct", struct_size));
-                            
-                            // Calcular dirección base del struct
-                            let struct_base_offset = struct_offset + 8;
-                            
-                            // 2. Cargar argumentos del constructor PRIMERO (antes de modificar RCX)
-                            let mut arg_values = Vec::new();
-                            for arg in args.iter() {
-                                self.generate_expr_windows(arg)?;
-                                self.text_section.push("    push rax  ; guardar arg temporalmente".to_string());
-                                arg_values.push(());
-                            }
-                            
-                            // 3. Restaurar argumentos a registros (en orden inverso)
-                            for i in (0..args.len()).rev() {
-                                let reg = match i {
-                                    0 => "rdx",  // Primer arg del usuario
-                                    1 => "r8",
-                                    2 => "r9",
-                                    _ => continue,  // TODO: pasar en stack
-                                };
-                                self.text_section.push(format!("    pop {}  ; arg{}", reg, i));
-                            }
-                            
-                            // 4. self = dirección base del struct
-                        self.text_section.push(format!("    lea rcx, [rbp - {}]  ; self = puntero al struct", struct_base_offset));
+                        // Layout: [vtable_ptr (0)] [campo0 (8)] [campo1 (16)] ...
+                        // 1. Reservar el objeto en el heap y apuntar a su vtable
+                            let struct_slot = self.alloc_object(class_name, "Constructor");
                             
-                            // 5. Llamar al constructor
-                        let constructor_name = format!("fn_{}_new", class_name);
-                            self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
-                        self.text_section.push(format!("    call {}  ; constructor", constructor_name));
-                            self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+                            // 2. Llamar al constructor: self = dirección base del struct, luego los args
+                            let mut call_args = vec![CallArg::Asm(format!("mov rax, [rbp - {}]  ; self = puntero al struct", struct_slot))];
+                            call_args.extend(args.iter().map(|arg| CallArg::Expr(arg)));
+                            self.emit_call(Callee::Label(format!("fn_{}_new", class_name)), &call_args, "constructor")?;
                             
-                            // 6. Retornar dirección del struct en RAX
-                            self.text_section.push(format!("    lea rax, [rbp - {}]  ; retornar puntero al struct", struct_base_offset));
+                            // 3. Retornar dirección del struct en RAX
+                            self.text_section.push(format!("    mov rax, [rbp - {}]  ; retornar puntero al struct", struct_slot));
                         return Ok(());
                         } else {
                             // Llamada a método estático: StructName.metodo(args)
-                            // Métodos estáticos no tienen 'self', los parámetros van directamente en RCX, RDX, R8, R9...
-                            let num_args = args.len();
-                            let stack_args_count = if num_args > 4 { num_args - 4 } else { 0 };
-                            let total_stack_space = 32 + (stack_args_count * 8);
-                            
+                            // Métodos estáticos no tienen 'self': los parámetros van directamente en registros/stack
                             self.text_section.push(format!("    ; Llamada a método estático {}.{}", class_name, name));
-                            self.text_section.push(format!("    sub rsp, {}  ; shadow space", 32));
-                            
-                            // Cargar argumentos en registros (RCX, RDX, R8, R9 para estáticos)
-                            for (i, arg) in args.iter().take(4).enumerate() {
-                                self.generate_expr_windows(arg)?;
-                                let reg = match i {
-                                    0 => "rcx",
-                                    1 => "rdx",
-                                    2 => "r8",
-                                    3 => "r9",
-                                    _ => unreachable!(),
-                                };
-                                self.text_section.push(format!("    mov {}, rax  ; param{}", reg, i));
-                            }
-                            
-                            // Argumentos adicionales en stack
-                            for (i, arg) in args.iter().skip(4).enumerate() {
-                                self.generate_expr_windows(arg)?;
-                                self.text_section.push("    push rax  ; arg en stack".to_string());
-                            }
-                            
-                            let function_name = format!("fn_{}_{}", class_name, name);
-                            self.text_section.push(format!("    call {}  ; método estático", function_name));
-                            
-                            // Limpiar argumentos del stack si los hay
-                            if stack_args_count > 0 {
-                                self.text_section.push(format!("    add rsp, {}  ; limpiar args del stack", stack_args_count * 8));
-                        }
-                            
-                            self.text_section.push(format!("    add rsp, {}  ; restaurar shadow space", 32));
+                            let call_args: Vec<CallArg> = args.iter().map(|arg| CallArg::Expr(arg)).collect();
+                            self.emit_call(Callee::Label(format!("fn_{}_{}", class_name, name)), &call_args, "método estático")?;
                             return Ok(());
                     }
                 }
                 
+                if module.is_none() && name == "range" && (1..=3).contains(&args.len()) {
+                    if let ForIter::Range { start, end, step } = ForIter::from_iterable(Spanned::from(expr.clone())) {
+                        return self.generate_range_array(&start, &end, step.as_ref());
+                    }
+                }
+                
+                if module.is_none() && name == "set" && args.is_empty() {
+                    // set() - crear set vacío (el primer add fija el tipo de elemento)
+                    self.text_section.push("    mov rcx, 0  ; entradas previstas".to_string());
+                    self.text_section.push("    mov rdx, -1  ; tipo de elemento sin determinar".to_string());
+                    self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                    self.text_section.push("    call dict_new".to_string());
+                    self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+                    return Ok(());
+                }
+                
+                if module.is_none() && name == "dict" && args.is_empty() {
+                    // dict() - crear diccionario vacío (el primer set fija el tipo de clave)
+                    self.text_section.push("    mov rcx, 0  ; entradas previstas".to_string());
+                    self.text_section.push("    mov rdx, -1  ; tipo de clave sin determinar".to_string());
+                    self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                    self.text_section.push("    call dict_new".to_string());
+                    self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+                    return Ok(());
+                }
+                
                 // Built-in functions de conversión de tipos
                 if module.is_none() && args.len() == 1 {
                     match name.as_str() {
+                        "upper" | "lower" | "strip" => {
+                            // upper(s) / lower(s) / strip(s) -> nuevo String
+                            self.emit_runtime_call(&format!("string_{}", name), &[&args[0]], None)?;
+                            return Ok(());
+                        }
+                        "split" => {
+                            // split(s) - dividir por espacios en blanco (separador NULL)
+                            self.emit_runtime_call("string_split", &[&args[0]], Some(0))?;
+                            return Ok(());
+                        }
                         "int" => {
                             // int(x) - convertir a entero
                             self.generate_expr_windows(&args[0])?;
-                            // Si es float, truncar; si es string, parsear; si es bool, 0/1
-                            // Por ahora, asumimos que ya es numérico o bool
-                            self.text_section.push("    ; int(x) - valor ya en rax".to_string());
+                            if self.is_float_expr(&args[0]) {
+                                // Float: truncar hacia cero
+                                self.text_section.push("    cvttsd2si rax, xmm0  ; int(x): truncar float64".to_string());
+                            } else {
+                                // Por ahora, asumimos que ya es numérico o bool
+                                self.text_section.push("    ; int(x) - valor ya en rax".to_string());
+                            }
                             return Ok(());
                         }
                         "float" => {
-                            // float(x) - convertir a float (por ahora solo retorna el valor)
+                            // float(x) - convertir a float64 (resultado en XMM0)
                             self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; float(x) - conversión pendiente".to_string());
-                            return Ok(());
-                        }
-                        "str" => {
+                            if self.is_float_expr(&args[0]) {
+                                self.text_section.push("    ; float(x) - valor ya es float64".to_string());
+                            } else if self.is_string_expr(&args[0]) {
+                                self.text_section.push("    mov rcx, rax  ; puntero al String".to_string());
+                                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                                self.text_section.push("    call float_from_string".to_string());
+                                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+                                self.text_section.push("    movq xmm0, rax  ; float(s)".to_string());
+                            } else {
+                                self.text_section.push("    cvtsi2sd xmm0, rax  ; float(x): int a float64".to_string());
+                            }
+                            return Ok(());
+                        }
+                        "str" => {
                             // str(x) - convertir a string (retorna puntero a string)
                             self.generate_expr_windows(&args[0])?;
                             self.text_section.push("    ; str(x) - valor numérico en rax".to_string());
@@ -2540,7 +2611,7 @@ impl CodeGenerator {
                         }
                         "open" => {
                             // open(filename) - abrir archivo (Windows CreateFileA)
-                            if let Expr::String(filename) = &args[0] {
+                            if let Expr::String(filename) = &args[0].node {
                                 let label = self.add_string_data(filename);
                                 self.text_section.push("    ; open(filename)".to_string());
                                 self.text_section.push("    sub rsp, 48  ; shadow space + params".to_string());
@@ -2550,7 +2621,9 @@ impl CodeGenerator {
                                 self.text_section.push("    xor r9, r9  ; lpSecurityAttributes = NULL".to_string());
                                 self.text_section.push("    mov qword [rsp+32], 3  ; OPEN_EXISTING".to_string());
                                 self.text_section.push("    mov qword [rsp+40], 0x80  ; FILE_ATTRIBUTE_NORMAL".to_string());
-                                self.text_section.push("    extern CreateFileA".to_string());
+                                if self.target.is_windows() {
+                                    self.text_section.push("    extern CreateFileA".to_string());
+                                }
                                 self.text_section.push("    call CreateFileA".to_string());
                                 self.text_section.push("    add rsp, 48".to_string());
                                 // RAX contiene el handle del archivo
@@ -2583,6 +2656,11 @@ impl CodeGenerator {
                             self.text_section.push("    ; setattr placeholder".to_string());
                             return Ok(());
                         }
+                        "array_free" | "string_free" => {
+                            // Liberación manual: devuelve los datos y el struct al heap allocator
+                            self.emit_runtime_call(name, &[&args[0]], None)?;
+                            return Ok(());
+                        }
                         "chr" => {
                             // chr(n) - convertir número a carácter
                             self.generate_expr_windows(&args[0])?;
@@ -2611,57 +2689,23 @@ impl CodeGenerator {
                             return Ok(());
                         }
                         "keys" => {
-                            // keys(dict) - retorna array con las claves del diccionario
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; keys(dict)".to_string());
-                            self.text_section.push("    mov rsi, rax  ; dict ptr".to_string());
-                            self.text_section.push("    mov rcx, [rsi]  ; count".to_string());
-                            // Crear array de claves
-                            let keys_offset = self.stack_offset;
-                            self.stack_offset += 256;
-                            self.text_section.push(format!("    lea rax, [rbp - {}]", keys_offset + 8));
-                            self.text_section.push("    mov [rax + 8], rcx  ; length".to_string());
-                            self.text_section.push("    xor r14, r14".to_string());
-                            let keys_loop = self.new_label("keys_loop");
-                            let keys_end = self.new_label("keys_end");
-                            self.text_section.push(format!("{}:", keys_loop));
-                            self.text_section.push("    cmp r14, rcx".to_string());
-                            self.text_section.push(format!("    jge {}", keys_end));
-                            self.text_section.push("    mov rdx, [rsi + r14*16 + 16]  ; key[i]".to_string());
-                            self.text_section.push("    mov [rax + r14*8 + 16], rdx".to_string());
-                            self.text_section.push("    inc r14".to_string());
-                            self.text_section.push(format!("    jmp {}", keys_loop));
-                            self.text_section.push(format!("{}:", keys_end));
+                            // keys(dict) - Array con las claves en orden de inserción
+                            self.emit_runtime_call("dict_keys", &[&args[0]], None)?;
                             return Ok(());
                         }
                         "values" => {
-                            // values(dict) - retorna array con los valores del diccionario
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; values(dict)".to_string());
-                            self.text_section.push("    mov rsi, rax".to_string());
-                            self.text_section.push("    mov rcx, [rsi]".to_string());
-                            let vals_offset = self.stack_offset;
-                            self.stack_offset += 256;
-                            self.text_section.push(format!("    lea rax, [rbp - {}]", vals_offset + 8));
-                            self.text_section.push("    mov [rax + 8], rcx".to_string());
-                            self.text_section.push("    xor r14, r14".to_string());
-                            let vals_loop = self.new_label("vals_loop");
-                            let vals_end = self.new_label("vals_end");
-                            self.text_section.push(format!("{}:", vals_loop));
-                            self.text_section.push("    cmp r14, rcx".to_string());
-                            self.text_section.push(format!("    jge {}", vals_end));
-                            self.text_section.push("    mov rdx, [rsi + r14*16 + 24]  ; value[i]".to_string());
-                            self.text_section.push("    mov [rax + r14*8 + 16], rdx".to_string());
-                            self.text_section.push("    inc r14".to_string());
-                            self.text_section.push(format!("    jmp {}", vals_loop));
-                            self.text_section.push(format!("{}:", vals_end));
+                            // values(dict) - Array con los valores en orden de inserción
+                            self.emit_runtime_call("dict_values", &[&args[0]], None)?;
                             return Ok(());
                         }
                         "items" => {
-                            // items(dict) - retorna array de tuplas (key, value)
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; items(dict) - retorna dict como está".to_string());
-                            // Por ahora retorna el dict original (ya tiene pares key-value)
+                            // items(dict) - Array de pares (clave, valor)
+                            self.emit_runtime_call("dict_items", &[&args[0]], None)?;
+                            return Ok(());
+                        }
+                        "list" if self.is_set_expr(&args[0]) => {
+                            // list(s) - Array con los elementos del set en orden de inserción
+                            self.emit_runtime_call("dict_keys", &[&args[0]], None)?;
                             return Ok(());
                         }
                         "list" => {
@@ -2670,27 +2714,10 @@ impl CodeGenerator {
                             self.text_section.push("    ; list(x) - retorna como está".to_string());
                             return Ok(());
                         }
-                        "dict" => {
-                            // dict() - crear diccionario vacío
-                            let dict_offset = self.stack_offset;
-                            self.stack_offset += 32;
-                            self.text_section.push(format!("    lea rax, [rbp - {}]", dict_offset + 8));
-                            self.text_section.push("    mov qword [rax], 0  ; count = 0".to_string());
-                            self.text_section.push("    mov qword [rax + 8], 0  ; capacity = 0".to_string());
-                            return Ok(());
-                        }
                         "set" => {
-                            // set() - crear set vacío o desde iterable
-                            if args.is_empty() {
-                                let set_offset = self.stack_offset;
-                                self.stack_offset += 16;
-                                self.text_section.push(format!("    lea rax, [rbp - {}]", set_offset + 8));
-                                self.text_section.push("    mov qword [rax], 0".to_string());
-                                self.text_section.push("    mov qword [rax + 8], 0".to_string());
-                            } else {
-                                self.generate_expr_windows(&args[0])?;
-                                self.text_section.push("    ; set(iterable) - convertir a set".to_string());
-                            }
+                            // set(lista) -> set_from_array(lista, tipo de elemento): descarta duplicados
+                            let key_kind = self.dict_key_kind(expr, None);
+                            self.emit_runtime_call("set_from_array", &[&args[0]], Some(key_kind))?;
                             return Ok(());
                         }
                         "tuple" => {
@@ -2713,7 +2740,7 @@ impl CodeGenerator {
                         "input" => {
                             // input(prompt) - leer línea de stdin
                             // Primero imprimir el prompt si es string
-                            if let Expr::String(prompt) = &args[0] {
+                            if let Expr::String(prompt) = &args[0].node {
                                 let label = self.add_string_data(prompt);
                                 self.text_section.push("    ; input(prompt)".to_string());
                                 self.text_section.push("    mov rcx, [rbp+16]  ; stdout".to_string());
@@ -2777,32 +2804,9 @@ impl CodeGenerator {
                             return Ok(());
                         }
                         "get" => {
-                            // get(dict, key, default) - obtener valor de dict con default
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    push rax  ; dict".to_string());
-                            self.generate_expr_windows(&args[1])?;
-                            self.text_section.push("    mov rdx, rax  ; key".to_string());
-                            self.text_section.push("    pop rsi  ; dict".to_string());
-                            self.text_section.push("    ; get(dict, key) - buscar key en dict".to_string());
-                            self.text_section.push("    mov rcx, [rsi]  ; count".to_string());
-                            self.text_section.push("    xor r14, r14".to_string());
-                            let get_loop = self.new_label("get_loop");
-                            let get_found = self.new_label("get_found");
-                            let get_end = self.new_label("get_end");
-                            self.text_section.push(format!("{}:", get_loop));
-                            self.text_section.push("    cmp r14, rcx".to_string());
-                            self.text_section.push(format!("    jge {}", get_end));
-                            self.text_section.push("    mov rax, [rsi + r14*16 + 16]  ; key[i]".to_string());
-                            self.text_section.push("    cmp rax, rdx".to_string());
-                            self.text_section.push(format!("    je {}", get_found));
-                            self.text_section.push("    inc r14".to_string());
-                            self.text_section.push(format!("    jmp {}", get_loop));
-                            self.text_section.push(format!("{}:", get_found));
-                            self.text_section.push("    mov rax, [rsi + r14*16 + 24]  ; value[i]".to_string());
-                            self.text_section.push(format!("    jmp {}_done", get_end));
-                            self.text_section.push(format!("{}:", get_end));
-                            self.text_section.push("    xor rax, rax  ; default = 0/None".to_string());
-                            self.text_section.push(format!("{}_done:", get_end));
+                            // get(dict, key) - valor de la clave o 0 si no está
+                            self.emit_runtime_call("dict_get", &[&args[0], &args[1]], Some(0))?;
+                            self.float_result_if_needed(expr);
                             return Ok(());
                         }
                         "append" => {
@@ -2911,59 +2915,22 @@ impl CodeGenerator {
                         }
                         "join" => {
                             // join(sep, lista) - unir strings con separador
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; join(sep, lista) - placeholder".to_string());
+                            self.emit_runtime_call("string_join", &[&args[0], &args[1]], None)?;
                             return Ok(());
                         }
                         "split" => {
-                            // split(string, sep) - dividir string
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; split(str, sep) - placeholder".to_string());
-                            return Ok(());
-                        }
-                        "replace" => {
-                            // replace(string, old, new) - reemplazar substring
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; replace(str, old, new) - placeholder".to_string());
-                            return Ok(());
-                        }
-                        "upper" => {
-                            // upper(string) - convertir a mayúsculas
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; upper(str) - placeholder".to_string());
-                            return Ok(());
-                        }
-                        "lower" => {
-                            // lower(string) - convertir a minúsculas
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; lower(str) - placeholder".to_string());
-                            return Ok(());
-                        }
-                        "strip" => {
-                            // strip(string) - eliminar espacios
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; strip(str) - placeholder".to_string());
-                            return Ok(());
-                        }
-                        "startswith" => {
-                            // startswith(string, prefix)
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; startswith - placeholder".to_string());
-                            self.text_section.push("    mov rax, 1".to_string());
+                            // split(string, sep) - dividir string en un array de strings
+                            self.emit_runtime_call("string_split", &[&args[0], &args[1]], None)?;
                             return Ok(());
                         }
-                        "endswith" => {
-                            // endswith(string, suffix)
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; endswith - placeholder".to_string());
-                            self.text_section.push("    mov rax, 1".to_string());
+                        "startswith" | "endswith" => {
+                            // startswith(string, prefijo) / endswith(string, sufijo) -> 1 o 0
+                            self.emit_runtime_call(&format!("string_{}", name), &[&args[0], &args[1]], None)?;
                             return Ok(());
                         }
                         "find" => {
-                            // find(string, substring) - encontrar posición
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    ; find - placeholder".to_string());
-                            self.text_section.push("    mov rax, -1".to_string());
+                            // find(string, substring) - posición de la primera aparición o -1
+                            self.emit_runtime_call("string_find", &[&args[0], &args[1]], None)?;
                             return Ok(());
                         }
                         "pow" => {
@@ -2996,81 +2963,9 @@ impl CodeGenerator {
                             self.text_section.push("    mov rax, rdi".to_string());
                             return Ok(());
                         }
-                        "map" => {
-                            // map(func, lista) - aplicar función a cada elemento
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    push rax  ; función".to_string());
-                            self.generate_expr_windows(&args[1])?;
-                            self.text_section.push("    mov rsi, rax  ; array".to_string());
-                            self.text_section.push("    pop rdi  ; función".to_string());
-                            // Implementación básica: iterar y llamar función
-                            self.text_section.push("    ; map(f, arr) - aplicar f a cada elemento".to_string());
-                            self.text_section.push("    mov rcx, [rsi + 8]  ; length".to_string());
-                            self.text_section.push("    mov rbx, [rsi + 0]  ; data".to_string());
-                            let map_loop = self.new_label("map_loop");
-                            let map_end = self.new_label("map_end");
-                            self.text_section.push("    push rsi  ; guardar array".to_string());
-                            self.text_section.push(format!("{}:", map_loop));
-                            self.text_section.push("    cmp rcx, 0".to_string());
-                            self.text_section.push(format!("    je {}", map_end));
-                            self.text_section.push("    push rcx".to_string());
-                            self.text_section.push("    push rbx".to_string());
-                            self.text_section.push("    push rdi".to_string());
-                            self.text_section.push("    mov rcx, [rbx]  ; elemento actual".to_string());
-                            self.text_section.push("    sub rsp, 32".to_string());
-                            self.text_section.push("    call rdi  ; llamar función".to_string());
-                            self.text_section.push("    add rsp, 32".to_string());
-                            self.text_section.push("    pop rdi".to_string());
-                            self.text_section.push("    pop rbx".to_string());
-                            self.text_section.push("    mov [rbx], rax  ; guardar resultado".to_string());
-                            self.text_section.push("    add rbx, 8".to_string());
-                            self.text_section.push("    pop rcx".to_string());
-                            self.text_section.push("    dec rcx".to_string());
-                            self.text_section.push(format!("    jmp {}", map_loop));
-                            self.text_section.push(format!("{}:", map_end));
-                            self.text_section.push("    pop rax  ; retornar array modificado".to_string());
-                            return Ok(());
-                        }
-                        "filter" => {
-                            // filter(func, lista) - filtrar elementos que cumplen condición
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    push rax  ; función".to_string());
-                            self.generate_expr_windows(&args[1])?;
-                            self.text_section.push("    mov rsi, rax  ; array".to_string());
-                            self.text_section.push("    pop rdi  ; función".to_string());
-                            self.text_section.push("    ; filter(f, arr)".to_string());
-                            self.text_section.push("    mov rcx, [rsi + 8]  ; length".to_string());
-                            self.text_section.push("    mov rbx, [rsi + 0]  ; data".to_string());
-                            self.text_section.push("    xor r12, r12  ; contador de elementos filtrados".to_string());
-                            let filter_loop = self.new_label("filter_loop");
-                            let filter_keep = self.new_label("filter_keep");
-                            let filter_end = self.new_label("filter_end");
-                            self.text_section.push("    push rsi".to_string());
-                            self.text_section.push(format!("{}:", filter_loop));
-                            self.text_section.push("    cmp rcx, 0".to_string());
-                            self.text_section.push(format!("    je {}", filter_end));
-                            self.text_section.push("    push rcx".to_string());
-                            self.text_section.push("    push rbx".to_string());
-                            self.text_section.push("    push rdi".to_string());
-                            self.text_section.push("    push r12".to_string());
-                            self.text_section.push("    mov rcx, [rbx]  ; elemento".to_string());
-                            self.text_section.push("    sub rsp, 32".to_string());
-                            self.text_section.push("    call rdi  ; llamar predicado".to_string());
-                            self.text_section.push("    add rsp, 32".to_string());
-                            self.text_section.push("    pop r12".to_string());
-                            self.text_section.push("    pop rdi".to_string());
-                            self.text_section.push("    pop rbx".to_string());
-                            self.text_section.push("    cmp rax, 0".to_string());
-                            self.text_section.push(format!("    je {}_skip", filter_keep));
-                            self.text_section.push("    inc r12  ; elemento pasa filtro".to_string());
-                            self.text_section.push(format!("{}:", filter_keep));
-                            self.text_section.push(format!("{}_skip:", filter_keep));
-                            self.text_section.push("    add rbx, 8".to_string());
-                            self.text_section.push("    pop rcx".to_string());
-                            self.text_section.push("    dec rcx".to_string());
-                            self.text_section.push(format!("    jmp {}", filter_loop));
-                            self.text_section.push(format!("{}:", filter_end));
-                            self.text_section.push("    pop rax  ; retornar array".to_string());
+                        "map" | "filter" => {
+                            // map(func, lista) / filter(func, lista): llamar a la closure por elemento
+                            self.generate_higher_order(name, args)?;
                             return Ok(());
                         }
                         _ => {}
@@ -3080,41 +2975,20 @@ impl CodeGenerator {
                 // Built-in functions con 3 argumentos
                 if module.is_none() && args.len() == 3 {
                     match name.as_str() {
+                        "replace" => {
+                            // replace(string, viejo, nuevo) - reemplazar todas las apariciones
+                            self.emit_runtime_call("string_replace", &[&args[0], &args[1], &args[2]], None)?;
+                            return Ok(());
+                        }
+                        "get" => {
+                            // get(dict, key, default) - valor de la clave o el valor por defecto
+                            self.emit_runtime_call("dict_get", &[&args[0], &args[1], &args[2]], None)?;
+                            self.float_result_if_needed(expr);
+                            return Ok(());
+                        }
                         "reduce" => {
                             // reduce(func, lista, init) - reducir lista a un valor
-                            self.generate_expr_windows(&args[0])?;
-                            self.text_section.push("    push rax  ; función".to_string());
-                            self.generate_expr_windows(&args[1])?;
-                            self.text_section.push("    push rax  ; array".to_string());
-                            self.generate_expr_windows(&args[2])?;
-                            self.text_section.push("    mov r12, rax  ; acumulador (init)".to_string());
-                            self.text_section.push("    pop rsi  ; array".to_string());
-                            self.text_section.push("    pop rdi  ; función".to_string());
-                            self.text_section.push("    ; reduce(f, arr, init)".to_string());
-                            self.text_section.push("    mov rcx, [rsi + 8]  ; length".to_string());
-                            self.text_section.push("    mov rbx, [rsi + 0]  ; data".to_string());
-                            let reduce_loop = self.new_label("reduce_loop");
-                            let reduce_end = self.new_label("reduce_end");
-                            self.text_section.push(format!("{}:", reduce_loop));
-                            self.text_section.push("    cmp rcx, 0".to_string());
-                            self.text_section.push(format!("    je {}", reduce_end));
-                            self.text_section.push("    push rcx".to_string());
-                            self.text_section.push("    push rbx".to_string());
-                            self.text_section.push("    push rdi".to_string());
-                            self.text_section.push("    mov rcx, r12  ; acumulador".to_string());
-                            self.text_section.push("    mov rdx, [rbx]  ; elemento actual".to_string());
-                            self.text_section.push("    sub rsp, 32".to_string());
-                            self.text_section.push("    call rdi  ; f(acc, elem)".to_string());
-                            self.text_section.push("    add rsp, 32".to_string());
-                            self.text_section.push("    mov r12, rax  ; nuevo acumulador".to_string());
-                            self.text_section.push("    pop rdi".to_string());
-                            self.text_section.push("    pop rbx".to_string());
-                            self.text_section.push("    add rbx, 8".to_string());
-                            self.text_section.push("    pop rcx".to_string());
-                            self.text_section.push("    dec rcx".to_string());
-                            self.text_section.push(format!("    jmp {}", reduce_loop));
-                            self.text_section.push(format!("{}:", reduce_end));
-                            self.text_section.push("    mov rax, r12  ; retornar resultado".to_string());
+                            self.generate_higher_order(name, args)?;
                             return Ok(());
                         }
                         _ => {}
@@ -3122,6 +2996,11 @@ impl CodeGenerator {
                 }
 
                 // Detectar built-ins como len(arr) o len(s)
+                if module.is_none() && name == "len" && args.len() == 1 && (self.is_dict_expr(&args[0]) || self.is_set_expr(&args[0])) {
+                    // len(d) / len(s) -> dict_len
+                    self.emit_runtime_call("dict_len", &[&args[0]], None)?;
+                    return Ok(());
+                }
                 if module.is_none() && name == "len" && args.len() == 1 {
                     // Detectar si el argumento es string o array
                     let is_string = self.is_string_expr(&args[0]);
@@ -3147,42 +3026,32 @@ impl CodeGenerator {
                             return Ok(());
                         }
                         
-                // Llamada a función normal (ABI-safe)
-                        let num_args = args.len();
-                        let stack_args_count = if num_args > 4 { num_args - 4 } else { 0 };
-                        let total_stack_space = 32 + (stack_args_count * 8);
-                        
-                        self.text_section.push(format!("    sub rsp, {}  ; shadow space", 32));
-                        
-                        for arg in args.iter().skip(4).rev() {
-                            self.generate_expr_windows(arg)?;
-                            self.text_section.push("    push rax  ; parámetro adicional en stack".to_string());
-                        }
-                        
-                        for (i, arg) in args.iter().take(4).enumerate() {
-                            self.generate_expr_windows(arg)?;
-                            let reg = match i {
-                                0 => "rcx",
-                                1 => "rdx",
-                                2 => "r8",
-                                3 => "r9",
-                                _ => unreachable!(),
-                            };
-                            self.text_section.push(format!("    mov {}, rax  ; param{}", reg, i));
-                        }
-                        
-                        let function_name = format!("fn_{}", name);
-                        self.text_section.push(format!("    call {}", function_name));
-                        self.text_section.push(format!("    add rsp, {}  ; restaurar shadow space", total_stack_space));
-                    
-                    // Valor de retorno está en RAX
+                // Llamada a función normal (ABI-safe, cualquier cantidad de argumentos)
+                let param_types = self.param_types.get(name).cloned().unwrap_or_default();
+                let call_args: Vec<CallArg> = args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| match param_types.get(i).cloned().flatten() {
+                        Some(ty) => CallArg::Coerced(arg, ty),
+                        None => CallArg::Expr(arg),
+                    })
+                    .collect();
+                self.emit_call(Callee::Label(format!("fn_{}", name)), &call_args, name)?;
+                // Valor de retorno está en RAX (un float64 también pasa a XMM0)
+                self.float_result_if_needed(expr);
             }
             Expr::Assign { name, value } => {
                 // Verificar si es asignación a índice de array: arr[0] = value
                 // El parser marca esto con name = "_array_set" y value es BinaryOp con Index
                 if name == "_array_set" {
-                    if let Expr::BinaryOp { left, right, .. } = value.as_ref() {
-                        if let Expr::Index { array, index } = left.as_ref() {
+                    if let Expr::BinaryOp { left, right, .. } = &value.node {
+                        if let Expr::Index { array, index } = &left.node {
+                            if self.is_dict_expr(array) {
+                                // d[clave] = valor -> dict_set(d, clave, valor, tipo de clave)
+                                let key_kind = self.dict_key_kind(array, Some(index));
+                                self.emit_runtime_call("dict_set", &[array, index, right], Some(key_kind))?;
+                                return Ok(());
+                            }
                             // Generar expresión del array (puntero al Array)
                             self.generate_expr_windows(array)?;
                             self.text_section.push("    push rax  ; guardar puntero al Array".to_string());
@@ -3214,6 +3083,16 @@ impl CodeGenerator {
                 
                 // Asignación normal: variable = value
                 self.generate_expr_windows(value)?;
+                if self.expr_type(expr).map_or(false, Type::is_float) && !self.is_float_expr(value) {
+                    // Variable float: convertir el entero asignado
+                    self.text_section.push("    cvtsi2sd xmm0, rax  ; convertir int a float64".to_string());
+                    self.text_section.push("    movq rax, xmm0".to_string());
+                } else {
+                    self.float_to_gpr_if_needed(value);
+                }
+                if let Some(ty) = self.expr_type(expr).cloned() {
+                    self.narrow_rax_to(&ty);
+                }
                 // Store in variable
                 if let Some(&offset) = self.variables.get(name) {
                     self.text_section.push(format!("    mov [rbp - {}], rax  ; asignar a variable {}", offset + 8, name));
@@ -3324,6 +3203,21 @@ impl CodeGenerator {
                             self.text_section.push(format!("{}:", copy_done));
                             self.text_section.push("    pop rdi".to_string());
                         }
+                        FStringPart::Expr(expr) if self.is_float_expr(expr) => {
+                            // Float: float_to_str escribe directo en el buffer (preserva RDI y R12)
+                            self.text_section.push("    ; Evaluar expresión float interpolada".to_string());
+                            self.text_section.push("    push rdi".to_string());
+                            self.text_section.push("    push r12".to_string());
+                            self.generate_expr_windows(expr)?;
+                            self.text_section.push("    pop r12".to_string());
+                            self.text_section.push("    pop rdi".to_string());
+                            self.text_section.push("    movq rcx, xmm0  ; float64 (bits)".to_string());
+                            self.text_section.push("    lea rdx, [rdi + r12]  ; posición en buffer".to_string());
+                            self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                            self.text_section.push("    call float_to_str".to_string());
+                            self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+                            self.text_section.push("    add r12, rax  ; longitud escrita".to_string());
+                        }
                         FStringPart::Expr(expr) => {
                             // Evaluar expresión y convertir a string
                             self.text_section.push("    ; Evaluar expresión interpolada".to_string());
@@ -3387,65 +3281,38 @@ impl CodeGenerator {
             
             Expr::Some(expr) => {
                 // Some(valor): tag = 1, valor = expr
-                // Resultado en stack: [rbp - offset] = tag (1), [rbp - offset + 8] = valor
-                self.generate_expr_windows(expr)?;
-                // Guardar valor en rbx temporalmente
-                self.text_section.push("    push rax  ; guardar valor de Some".to_string());
-                // Tag = 1 para Some
-                self.text_section.push("    mov rax, 1  ; tag Some = 1".to_string());
-                // El resultado es la dirección del tagged union en stack
-                // Necesitamos espacio para tag + valor = 16 bytes
-                let offset = self.stack_offset;
-                self.stack_offset += 16;  // Tag (8) + valor (8)
-                self.text_section.push("    sub rsp, 16  ; espacio para Option (tag + valor)".to_string());
-                // Guardar tag
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; tag = 1 (Some)", offset + 8));
-                // Guardar valor
-                self.text_section.push("    pop rax  ; recuperar valor".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; valor", offset + 16));
-                // Dejar dirección del tagged union en rax (dirección del tag)
-                self.text_section.push(format!("    lea rax, [rbp - {}]  ; dirección del Option", offset + 8));
+                self.generate_tagged_union(1, "Some", &[expr.as_ref()], 1, "Option (tag + valor)")?;
             }
             Expr::Ok(expr) => {
                 // Ok(valor): tag = 0, valor = expr
-                self.generate_expr_windows(expr)?;
-                self.text_section.push("    push rax  ; guardar valor de Ok".to_string());
-                // Tag = 0 para Ok
-                self.text_section.push("    mov rax, 0  ; tag Ok = 0".to_string());
-                let offset = self.stack_offset;
-                self.stack_offset += 16;
-                self.text_section.push("    sub rsp, 16  ; espacio para Result (tag + valor)".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; tag = 0 (Ok)", offset + 8));
-                self.text_section.push("    pop rax  ; recuperar valor".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; valor", offset + 16));
-                self.text_section.push(format!("    lea rax, [rbp - {}]  ; dirección del Result", offset + 8));
+                self.generate_tagged_union(0, "Ok", &[expr.as_ref()], 1, "Result (tag + valor)")?;
             }
             Expr::Err(expr) => {
                 // Err(error): tag = 1, error = expr
-                self.generate_expr_windows(expr)?;
-                self.text_section.push("    push rax  ; guardar error de Err".to_string());
-                // Tag = 1 para Err
-                self.text_section.push("    mov rax, 1  ; tag Err = 1".to_string());
-                let offset = self.stack_offset;
-                self.stack_offset += 16;
-                self.text_section.push("    sub rsp, 16  ; espacio para Result (tag + valor)".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; tag = 1 (Err)", offset + 8));
-                self.text_section.push("    pop rax  ; recuperar error".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; error", offset + 16));
-                self.text_section.push(format!("    lea rax, [rbp - {}]  ; dirección del Result", offset + 8));
+                self.generate_tagged_union(1, "Err", &[expr.as_ref()], 1, "Result (tag + valor)")?;
             }
             Expr::None => {
                 // None: tag = 0, valor = 0
-                let offset = self.stack_offset;
-                self.stack_offset += 16;
-                self.text_section.push("    sub rsp, 16  ; espacio para Option (tag + valor)".to_string());
-                // Tag = 0 para None
-                self.text_section.push("    mov rax, 0  ; tag None = 0".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; tag = 0 (None)", offset + 8));
-                // Valor = 0
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; valor = 0", offset + 16));
-                // Dejar dirección en rax
-                self.text_section.push(format!("    lea rax, [rbp - {}]  ; dirección del Option", offset + 8));
+                self.generate_tagged_union(0, "None", &[], 1, "Option (tag + valor)")?;
+            }
+            Expr::EnumVariant { enum_name, variant, args } => {
+                // Enum de usuario: tag = índice de la variante en la declaración.
+                // Todas las variantes ocupan lo mismo: tag + el payload más grande del enum.
+                let Some(variants) = self.enum_definitions.get(enum_name) else {
+                    return Err(adead_common::ADeadError::RuntimeError {
+                        message: format!("Enum '{}' no definido", enum_name),
+                    });
+                };
+                let slots = variants.iter().map(|v| v.fields.len()).max().unwrap_or(0);
+                let Some(tag) = variants.iter().position(|v| v.name == *variant) else {
+                    return Err(adead_common::ADeadError::RuntimeError {
+                        message: format!("'{}' no es una variante del enum '{}'", variant, enum_name),
+                    });
+                };
+                let tag = tag as i64;
+                let description = format!("enum {} (tag + {} slots de payload)", enum_name, slots);
+                let payload: Vec<&Expr> = args.iter().map(|arg| &arg.node).collect();
+                self.generate_tagged_union(tag, &format!("{}.{}", enum_name, variant), &payload, slots, &description)?;
             }
             // Structs (Fase 1.2 - O1, O3, O4)
             Expr::StructLiteral { name, fields } => {
@@ -3455,28 +3322,22 @@ impl CodeGenerator {
                     self.struct_definitions.insert(name.clone(), field_names);
                 }
                 
-                // Generar struct literal en stack
-                // Layout en memoria (stack crece hacia abajo):
-                // [rbp - 8]  = campo0 (offset 0 desde base)
-                // [rbp - 16] = campo1 (offset 8 desde base)
-                // [rbp - 24] = campo2 (offset 16 desde base)
-                // La dirección base apunta a [rbp - 8]
-                let struct_size = fields.len() * 8;
-                let base_offset = self.stack_offset;
-                self.stack_offset += struct_size as i64;
-                self.text_section.push(format!("    ; Struct literal: {} ({} campos, {} bytes)", name, fields.len(), struct_size));
-                self.text_section.push(format!("    sub rsp, {}  ; reservar espacio para struct", struct_size));
+                // Generar struct literal en el heap, con el mismo layout que un objeto
+                // creado con `.new()`: [base] = vtable_ptr, campos en [base + 8], [base + 16], ...
+                let slot = self.alloc_object(name, "Struct literal");
                 
                 // Generar valores de campos en orden
-                for (i, (field_name, value)) in fields.iter().enumerate() {
+                for (field_name, value) in fields {
                     self.generate_expr_windows(value)?;
-                    let field_offset = base_offset + (i as i64 * 8);
-                    self.text_section.push(format!("    mov [rbp - {}], rax  ; {}.{} = {}", field_offset + 8, name, field_name, 
-                        if let Expr::Number(n) = value { n.to_string() } else { "expr".to_string() }));
+                    self.prepare_field_value(Some(name), field_name, value);
+                    let field_offset = self.field_offset(name, field_name).unwrap_or(8);
+                    self.text_section.push(format!("    mov rcx, [rbp - {}]  ; dirección del struct", slot));
+                    self.text_section.push(format!("    mov [rcx + {}], rax  ; {}.{} = {}", field_offset, name, field_name, 
+                        if let Expr::Number(n) = &value.node { n.to_string() } else { "expr".to_string() }));
                 }
                 
-                // Retornar dirección base del struct (apunta al primer campo)
-                self.text_section.push(format!("    lea rax, [rbp - {}]  ; dirección del struct {}", base_offset + 8, name));
+                // Retornar dirección base del struct (apunta al puntero a la vtable)
+                self.text_section.push(format!("    mov rax, [rbp - {}]  ; dirección del struct {}", slot, name));
             }
             Expr::FieldAccess { object, field } => {
                 // Determinar el tipo del objeto para calcular el offset correcto
@@ -3484,31 +3345,17 @@ impl CodeGenerator {
                 
                 self.generate_expr_windows(object)?;
                 
-                // Calcular offset del campo
-                // Layout en memoria: base apunta a campo0, campo1 está 8 bytes ABAJO en el stack
-                // Pero como usamos direcciones, campo1 está en [base - 8], NO [base + 8]
-                // CORRECCIÓN: En realidad, cuando hacemos lea rax, [rbp - X], rax apunta a esa dirección
-                // y los campos siguientes están en direcciones MENORES (stack crece hacia abajo)
-                // Por lo tanto: campo0 en [base], campo1 en [base - 8], campo2 en [base - 16]
-                let field_offset = if let Some(ref type_name) = struct_type {
-                    if let Some(fields) = self.struct_definitions.get(type_name) {
-                        // Buscar el campo y calcular offset NEGATIVO (stack crece hacia abajo)
-                        let pos = fields.iter().position(|f| f == field).unwrap_or(0);
-                        -(pos as i64 * 8)
-                    } else {
-                        0
-                    }
-                } else {
-                    0
-                };
+                // Calcular offset del campo: [base] es el puntero a la vtable y los
+                // campos (los heredados primero) le siguen en [base + 8], [base + 16], ...
+                // Sin tipo conocido se asume el primer campo
+                let field_offset = struct_type
+                    .and_then(|type_name| self.field_offset(&type_name, field))
+                    .unwrap_or(8);
                 
                 self.text_section.push(format!("    ; accediendo campo '{}' (offset: {})", field, field_offset));
-                if field_offset == 0 {
-                    self.text_section.push("    mov rax, [rax]  ; cargar campo".to_string());
-                } else if field_offset < 0 {
-                    self.text_section.push(format!("    mov rax, [rax - {}]  ; cargar campo", -field_offset));
-                } else {
-                    self.text_section.push(format!("    mov rax, [rax + {}]  ; cargar campo", field_offset));
+                self.text_section.push(format!("    mov rax, [rax + {}]  ; cargar campo", field_offset));
+                if self.is_float_expr(expr) {
+                    self.text_section.push(format!("    movq xmm0, rax  ; campo {} es float64", field));
                 }
             }
             Expr::FieldAssign { object, field, value } => {
@@ -3521,46 +3368,36 @@ impl CodeGenerator {
                 
                 // SEGUNDO: generar el valor a asignar
                 self.generate_expr_windows(value)?;
+                self.prepare_field_value(struct_type.as_deref(), field, value);
                 self.text_section.push("    mov rbx, rax  ; mover valor a rbx".to_string());
                 
                 // TERCERO: restaurar dirección del objeto
                 self.text_section.push("    pop rax  ; restaurar dirección del objeto".to_string());
                 
-                // Calcular offset del campo (negativo porque stack crece hacia abajo)
-                let field_offset = if let Some(ref type_name) = struct_type {
-                    if let Some(fields) = self.struct_definitions.get(type_name) {
-                        let pos = fields.iter().position(|f| f == field).unwrap_or(0);
-                        -(pos as i64 * 8)
-                    } else {
-                        0
-                    }
-                } else {
-                    0
-                };
+                // Calcular offset del campo (mismo layout que FieldAccess)
+                let field_offset = struct_type
+                    .and_then(|type_name| self.field_offset(&type_name, field))
+                    .unwrap_or(8);
                 
                 self.text_section.push(format!("    ; asignando a campo '{}' (offset: {})", field, field_offset));
-                if field_offset == 0 {
-                    self.text_section.push("    mov [rax], rbx  ; asignar campo".to_string());
-                } else if field_offset < 0 {
-                    self.text_section.push(format!("    mov [rax - {}], rbx  ; asignar campo", -field_offset));
-                } else {
-                    self.text_section.push(format!("    mov [rax + {}], rbx  ; asignar campo", field_offset));
-                }
+                self.text_section.push(format!("    mov [rax + {}], rbx  ; asignar campo", field_offset));
             }
             Expr::Index { array, index } => {
                 // Indexación: arr[0] o s[0] (para strings, solo lectura de carácter)
-                // Detectar si es string o array
-                let is_string = self.is_string_expr(array);
+                if self.is_dict_expr(array) {
+                    // d[clave] -> dict_index(d, clave), panic si la clave no está
+                    self.emit_runtime_call("dict_index", &[array, index], None)?;
+                    self.float_result_if_needed(expr);
+                    return Ok(());
+                }
+                // Detectar si es string o array (solo con tipo conocido: un array sin tipo
+                // cuyo nombre parece de string no debe pasar por string_char_at)
+                let is_string = self.expr_type(array) == Some(&Type::String);
                 
                 if is_string {
-                    // Para strings, por ahora solo soportamos lectura de carácter individual
-                    // TODO: Implementar string_get_char() helper si se necesita
-                    // Por ahora, retornar error o usar slicing con start=index, end=index+1
-                    return Err(adead_common::ADeadError::RuntimeError {
-                        message: format!("String indexing not yet implemented. Use slicing instead: s[{}:{}]", 
-                            if let Expr::Number(n) = index.as_ref() { *n } else { 0 },
-                            if let Expr::Number(n) = index.as_ref() { *n + 1 } else { 1 }),
-                    });
+                    // s[i] -> string_char_at(s, i): String nuevo con un carácter; un índice
+                    // negativo cuenta desde el final y uno fuera de rango es panic
+                    self.emit_runtime_call("string_char_at", &[array, index], None)?;
                 } else {
                     // Indexación de array: arr[0] o arr[-1] (negative indexing)
                     // Estrategia: usar array_get (estructura Array dinámica)
@@ -3596,42 +3433,51 @@ impl CodeGenerator {
                     self.text_section.push("    call array_get".to_string());
                     
                     // RAX contiene el valor del elemento
+                    self.float_result_if_needed(expr);
                 }
             }
-            Expr::Slice { object, start, end } => {
-                // Slicing: s[0:4]
-                // Estrategia: usar string_slice (estructura String dinámica)
-                // 1. Generar expresión del string (puntero al String en rax)
-                // 2. Generar expresión del inicio
-                // 3. Generar expresión del fin
-                // 4. Llamar a string_slice(string_ptr, start, end)
-                // 5. Retornar puntero al nuevo String en RAX
-                
+            Expr::Slice { object, start, end, step } => {
+                // Slicing: s[0:4], s[-3:], s[::-1]
+                // Estrategia: string_slice_step(string_ptr, start, end, step) con semántica de
+                // Python: índices negativos cuentan desde el final, los límites se recortan al
+                // String, un límite omitido se pasa como 0x8000000000000000 y el paso por defecto es 1
                 self.generate_expr_windows(object)?;
-                // Guardar puntero al String en stack
                 self.text_section.push("    push rax  ; guardar puntero al String".to_string());
                 
-                self.generate_expr_windows(start)?;
-                // rax contiene el índice inicio
-                self.text_section.push("    push rax  ; guardar start".to_string());
+                for (bound, omitted, what) in [(start, "0x8000000000000000", "start"), (end, "0x8000000000000000", "end"), (step, "1", "step")] {
+                    match bound {
+                        Some(bound) => self.generate_expr_windows(bound)?,
+                        None => self.text_section.push(format!("    mov rax, {}  ; {} omitido", omitted, what)),
+                    }
+                    self.text_section.push(format!("    push rax  ; guardar {}", what));
+                }
                 
-                self.generate_expr_windows(end)?;
-                // rax contiene el índice fin
-                // Preparar parámetros: RCX = puntero al String, RDX = start, R8 = end
-                self.text_section.push("    mov r8, rax  ; end".to_string());
+                // Preparar parámetros: RCX = String, RDX = start, R8 = end, R9 = step
+                self.text_section.push("    pop r9  ; step".to_string());
+                self.text_section.push("    pop r8  ; end".to_string());
                 self.text_section.push("    pop rdx  ; start".to_string());
                 self.text_section.push("    pop rcx  ; puntero al String".to_string());
                 
-                // Llamar a string_slice
                 self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
-                self.text_section.push("    call string_slice".to_string());
+                self.text_section.push("    call string_slice_step".to_string());
                 self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
                 
                 // RAX contiene el puntero al nuevo String (slice)
             }
+            Expr::MethodCall { object, method, args } if self.is_dict_expr(object) => {
+                self.generate_dict_method(expr, object, method, args)?;
+            }
+            Expr::MethodCall { object, method, args } if self.is_set_expr(object) => {
+                self.generate_set_method(object, method, args)?;
+            }
             Expr::MethodCall { object, method, args } => {
-                // Detectar métodos de arrays y llamar a funciones helper específicas
-                match method.as_str() {
+                // Detectar métodos de arrays y llamar a funciones helper específicas; un
+                // método del struct con el mismo nombre (`Stack.pop`) tiene prioridad
+                let user_method = self
+                    .get_struct_type_from_expr(object)
+                    .is_some_and(|type_name| self.oop.has_method(&type_name, method));
+                let builtin = if user_method { "" } else { method.as_str() };
+                match builtin {
                     "append" if args.len() == 1 => {
                         // arr.append(x) -> array_append(arr, x)
                         // Generar expresión del array (puntero al Array)
@@ -3827,143 +3673,97 @@ impl CodeGenerator {
                         
                         // string_lower retorna puntero al nuevo String en RAX (ya está ahí)
                     }
-                    _ => {
-                        // Método de struct/clase: obj.metodo(args)
-                        // Determinar tipo del objeto
-                        let struct_type = self.get_struct_type_from_expr(object);
-                        
-                        if let Some(ref type_name) = struct_type {
-                            // Es un método de struct/clase
-                            // Evaluar objeto (puntero al struct en stack)
-                            self.generate_expr_windows(object)?;
-                            self.text_section.push("    push rax  ; guardar puntero al struct".to_string());
-                            
-                            // Evaluar argumentos
-                            for (i, arg) in args.iter().enumerate() {
-                                self.generate_expr_windows(arg)?;
-                                match i {
-                                    0 => {
-                                        self.text_section.push("    mov rdx, rax  ; arg0".to_string());
-                                    }
-                                    1 => {
-                                        self.text_section.push("    mov r8, rax  ; arg1".to_string());
-                                    }
-                                    2 => {
-                                        self.text_section.push("    mov r9, rax  ; arg2".to_string());
-                                    }
-                                    _ => {
-                                        self.text_section.push("    push rax  ; arg adicional en stack".to_string());
-                                    }
-                                }
-                            }
-                            
-                            // Preparar self (RCX) desde el objeto guardado
-                            self.text_section.push("    pop rcx  ; self (puntero al struct)".to_string());
-                            
-                            // Llamar a método: fn_StructName_method
-                            let method_label = format!("fn_{}_{}", type_name, method);
-                            self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
-                            self.text_section.push(format!("    call {}", method_label));
-                            self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
-                            
-                            // RAX contiene el valor de retorno (si hay)
-                        } else {
-                            // No se pudo determinar el tipo del objeto
-                            // Intentar obtener el nombre de la variable para dar un error más claro
-                            let var_name = if let Expr::Ident(name) = object.as_ref() {
-                                name.clone()
-                            } else {
-                                "objeto".to_string()
-                            };
-                            
+                    "strip" if args.is_empty() && self.is_string_expr(object) => {
+                        // s.strip() -> string_strip(s)
+                        self.emit_runtime_call("string_strip", &[object], None)?;
+                    }
+                    "find" | "startswith" | "endswith" if args.len() == 1 && self.is_string_expr(object) => {
+                        // s.find(sub) -> índice o -1; s.startswith(p) / s.endswith(p) -> 1 o 0
+                        self.emit_runtime_call(&format!("string_{}", method), &[object, &args[0]], None)?;
+                    }
+                    "split" if args.len() <= 1 && self.is_string_expr(object) => {
+                        // s.split(sep) -> string_split(s, sep); s.split() separa por espacios en blanco
+                        let call_args: Vec<&Expr> = std::iter::once(object.as_ref()).chain(args.iter()).map(|arg| &arg.node).collect();
+                        self.emit_runtime_call("string_split", &call_args, args.is_empty().then_some(0))?;
+                    }
+                    "replace" if args.len() == 2 && self.is_string_expr(object) => {
+                        // s.replace(viejo, nuevo) -> string_replace(s, viejo, nuevo)
+                        self.emit_runtime_call("string_replace", &[object, &args[0], &args[1]], None)?;
+                    }
+                    "join" if args.len() == 1 && self.is_string_expr(object) => {
+                        // sep.join(lista) -> string_join(sep, lista)
+                        self.emit_runtime_call("string_join", &[object, &args[0]], None)?;
+                    }
+                    _ if matches!(&object.node, Expr::Ident(name)
+                        if self.struct_definitions.contains_key(name) && !self.variables.contains_key(name)) =>
+                    {
+                        // Método estático: Tipo.metodo(args) es la misma llamada que Call con módulo
+                        let Expr::Ident(class_name) = &object.node else { unreachable!() };
+                        let call = Expr::Call { module: Some(class_name.clone()), name: method.clone(), args: args.clone() };
+                        self.generate_expr_windows(&call)?;
+                    }
+                    _ if self.trait_of_expr(object).is_some() => {
+                        // Objeto de trait: [datos, vtable]. self = datos y el método sale
+                        // de la vtable del impl con que se creó (dispatch dinámico)
+                        let trait_name = self.trait_of_expr(object).unwrap_or_default();
+                        let Some(offset) = self.oop.get_trait_method_offset(&trait_name, method) else {
                             return Err(adead_common::ADeadError::RuntimeError {
-                                message: format!(
-                                    "No se pudo determinar el tipo de '{}' para llamar al método '{}'. Asegúrate de que la variable esté correctamente tipada.",
-                                    var_name, method
-                                ),
+                                message: format!("El trait '{}' no tiene el método '{}'", trait_name, method),
                             });
-                        }
+                        };
+                        self.generate_expr_windows(object)?;
+                        let data_slot = self.alloc_temp_slot();
+                        let vtable_slot = self.alloc_temp_slot();
+                        self.text_section.push("    mov rcx, [rax]  ; datos del objeto de trait".to_string());
+                        self.text_section.push(format!("    mov [rbp - {}], rcx", data_slot + 8));
+                        self.text_section.push("    mov rcx, [rax + 8]  ; vtable del objeto de trait".to_string());
+                        self.text_section.push(format!("    mov [rbp - {}], rcx", vtable_slot + 8));
+                        let mut call_args = vec![CallArg::Asm(format!("mov rax, [rbp - {}]  ; self = datos del objeto de trait", data_slot + 8))];
+                        call_args.extend(args.iter().map(|arg| CallArg::Expr(arg)));
+                        let comment = format!("llamada a {}.{} (objeto de trait)", trait_name, method);
+                        self.emit_call(Callee::TraitObject { vtable_slot, offset }, &call_args, &comment)?;
                     }
                     _ => {
                         // Método de struct/clase: obj.metodo(args)
                         // Determinar tipo del objeto
                         let struct_type = self.get_struct_type_from_expr(object);
                         
-                        if let Some(ref type_name) = struct_type {
+                        if let Some(type_name) = struct_type.as_ref().filter(|t| {
+                            self.oop.get_field_offset(t, method).is_some() && !self.oop.has_method(t, method)
+                        }) {
+                            // Campo con una función guardada: obj.campo(args) llama a la closure
+                            let field = Expr::FieldAccess { object: object.clone(), field: method.clone() };
+                            self.generate_expr_windows(&field)?;
+                            let slot = self.alloc_temp_slot();
+                            self.text_section.push(format!("    mov [rbp - {}], rax  ; {}.{}", slot + 8, type_name, method));
+                            let call_args: Vec<CallArg> = args.iter().map(|arg| CallArg::Expr(arg)).collect();
+                            self.emit_call(Callee::Closure(slot), &call_args, &format!("closure {}.{}", type_name, method))?;
+                        } else if let Some(ref type_name) = struct_type {
                             // Es un método de struct/clase
-                            // Evaluar objeto (puntero al struct en stack)
-                            self.generate_expr_windows(object)?;
-                            self.text_section.push("    push rax  ; guardar puntero al struct".to_string());
-                            
-                            // Evaluar argumentos
-                            for (i, arg) in args.iter().enumerate() {
-                                self.generate_expr_windows(arg)?;
-                                match i {
-                                    0 => {
-                                        self.text_section.push("    mov rdx, rax  ; arg0".to_string());
-                                    }
-                                    1 => {
-                                        self.text_section.push("    mov r8, rax  ; arg1".to_string());
-                                    }
-                                    2 => {
-                                        self.text_section.push("    mov r9, rax  ; arg2".to_string());
-                                    }
-                                    _ => {
-                                        self.text_section.push("    push rax  ; arg adicional en stack".to_string());
-                                    }
-                                }
-                            }
+                            // self (puntero al struct) es el primer argumento
+                            let mut call_args = vec![CallArg::Expr(object.as_ref())];
+                            call_args.extend(args.iter().map(|arg| CallArg::Expr(arg)));
                             
-                            // Preparar self (RCX) desde el objeto guardado
-                            self.text_section.push("    pop rcx  ; self (puntero al struct)".to_string());
-                            
-                            // Verificar si el método está en la vtable (dispatch dinámico)
-                            let use_virtual_dispatch = if let Some(methods) = self.struct_methods.get(type_name) {
-                                methods.contains(method)
-                            } else {
-                                false
-                            };
+                            // Verificar si el método está en la vtable (dispatch dinámico): el
+                            // objeto puede ser de una clase derivada que lo sobreescribe
+                            let vtable_offset = self.oop.get_method_vtable_offset(type_name, method);
                             
-                            if use_virtual_dispatch {
-                                // DISPATCH DINÁMICO: Usar vtable para llamada virtual
-                                // 1. Cargar puntero a vtable desde [self + 0]
-                                self.text_section.push("    mov rax, [rcx]  ; cargar vtable_ptr desde [self + 0]".to_string());
-                                
-                                // 2. Calcular offset del método en la vtable
-                                if let Some(methods) = self.struct_methods.get(type_name) {
-                                    if let Some(method_index) = methods.iter().position(|m| m == method) {
-                                        let vtable_offset = method_index * 8; // Cada puntero es 8 bytes
-                                        
-                                        // 3. Cargar puntero al método desde [vtable + offset]
-                                        self.text_section.push(format!("    mov rax, [rax + {}]  ; cargar puntero al método desde vtable[{}]", vtable_offset, method_index));
-                                        
-                                        // 4. Llamar indirectamente usando el puntero
-                                        self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
-                                        self.text_section.push("    call rax  ; llamada virtual (dispatch dinámico)".to_string());
-                                        self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
-                                    } else {
-                                        return Err(adead_common::ADeadError::RuntimeError {
-                                            message: format!("Método '{}' no encontrado en vtable de '{}'.", method, type_name),
-                                        });
-                                    }
-                                } else {
-                                    return Err(adead_common::ADeadError::RuntimeError {
-                                        message: format!("No se pudo encontrar vtable para '{}'.", type_name),
-                                    });
+                            match vtable_offset {
+                                // DISPATCH DINÁMICO: puntero al método desde [vtable_ptr + offset]
+                                Some(offset) => self.emit_call(Callee::Virtual(offset), &call_args, &format!("llamada virtual a {}.{} (dispatch dinámico)", type_name, method))?,
+                                // LLAMADA ESTÁTICA: método no virtual (p. ej. de un impl de trait
+                                // con el struct conocido), llamar directamente a su implementación
+                                None => {
+                                    let label = self.oop.resolve_method_label(type_name, method);
+                                    self.emit_call(Callee::Label(label), &call_args, "llamada estática")?
                                 }
-                            } else {
-                                // LLAMADA ESTÁTICA: Método no virtual, llamar directamente
-                            let method_label = format!("fn_{}_{}", type_name, method);
-                                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
-                                self.text_section.push(format!("    call {}  ; llamada estática", method_label));
-                                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
                             }
                             
                             // RAX contiene el valor de retorno (si hay)
                         } else {
                             // No se pudo determinar el tipo del objeto
                             // Intentar obtener el nombre de la variable para dar un error más claro
-                            let var_name = if let Expr::Ident(name) = object.as_ref() {
+                            let var_name = if let Expr::Ident(name) = &object.node {
                                 name.clone()
                             } else {
                                 "objeto".to_string()
@@ -3983,40 +3783,22 @@ impl CodeGenerator {
                 // super.metodo(args) - llamada a método del padre
                 // Obtener struct padre desde current_struct
                 if let Some(current_struct_name) = &self.current_struct {
-                    if let Some(parent_name) = self.struct_parents.get(current_struct_name) {
-                        if let Some(parent) = parent_name.clone() {
-                            // Evaluar argumentos
-                            for (i, arg) in args.iter().enumerate() {
-                                self.generate_expr_windows(arg)?;
-                                match i {
-                                    0 => {
-                                        self.text_section.push("    mov rdx, rax  ; arg0".to_string());
-                                    }
-                                    1 => {
-                                        self.text_section.push("    mov r8, rax  ; arg1".to_string());
-                                    }
-                                    2 => {
-                                        self.text_section.push("    mov r9, rax  ; arg2".to_string());
-                                    }
-                                    _ => {
-                                        self.text_section.push("    push rax  ; arg adicional en stack".to_string());
-                                    }
-                                }
-                            }
-                            
-                            // self ya está en el contexto (debe estar en RCX o en stack)
-                            // Si self está en variables, cargarlo; si no, asumir que está en RCX
-                            if let Some(&self_offset) = self.variables.get("self") {
-                                self.text_section.push(format!("    mov rcx, [rbp - {}]  ; self desde stack", self_offset + 8));
-                            }
-                            // Si no está en variables, asumir que ya está en RCX (contexto del método)
+                    if self.oop.classes.contains_key(current_struct_name) {
+                        if let Some(parent) = self.oop.parent_of(current_struct_name).cloned() {
+                            // self (ya guardado en el frame del método) es el primer argumento
+                            let Some(&self_offset) = self.variables.get("self") else {
+                                return Err(adead_common::ADeadError::RuntimeError {
+                                    message: "'super.metodo()' solo puede usarse dentro de métodos de instancia.".to_string(),
+                                });
+                            };
+                            let mut call_args = vec![CallArg::Asm(format!("mov rax, [rbp - {}]  ; self desde stack", self_offset + 8))];
+                            call_args.extend(args.iter().map(|arg| CallArg::Expr(arg)));
                             
-                            // Llamar a método del padre: fn_ParentName_method
-                            let method_label = format!("fn_{}_{}", parent, method);
+                            // Enlace estático (sin vtable): la implementación que ve el padre,
+                            // que puede venir de un ancestro más arriba en la cadena de extends
+                            let label = self.oop.resolve_method_label(&parent, method);
                             self.text_section.push(format!("    ; Llamada a super.{}(), método del padre {}", method, parent));
-                            self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
-                            self.text_section.push(format!("    call {}", method_label));
-                            self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+                            self.emit_call(Callee::Label(label), &call_args, "método del padre")?;
                             
                             // RAX contiene el valor de retorno (si hay)
                         } else {
@@ -4036,77 +3818,78 @@ impl CodeGenerator {
                 }
             }
             Expr::Match { expr, arms } => {
-                // Generar match exhaustivo con saltos condicionales
-                // Evalúa expr (debe ser Option o Result, resultado es dirección del tagged union)
+                // El valor se evalúa una sola vez y se guarda en un slot: cada brazo lo
+                // compara con su patrón (literales, rangos, tags de Option/Result, tuplas,
+                // structs, alternativas) y su guarda; si ninguno encaja se hace panic.
+                // La exhaustividad ya la verificó el type checker: el panic es el respaldo.
                 self.generate_expr_windows(expr)?;
-                // rax contiene la dirección del tagged union
-                // Cargar tag
-                self.text_section.push("    mov rbx, [rax]  ; cargar tag del tagged union".to_string());
+                let value_slot = self.alloc_temp_slot();
+                self.text_section.push(format!("    mov [rbp - {}], rax  ; valor del match", value_slot + 8));
                 
-                // Generar labels para cada brazo
-                let mut arm_labels = Vec::new();
                 let end_label = self.new_label("match_end");
+                let no_match_label = self.new_label("match_no_match");
+                let arm_labels: Vec<String> = (0..arms.len())
+                    .map(|i| self.new_label(&format!("match_arm_{}", i)))
+                    .collect();
+                let saved_vars = self.variables.clone();
+                let mut arm_bindings: Vec<HashMap<String, i64>> = Vec::new();
+                // Payload a enlazar al entrar al brazo (camino rápido de tags)
+                let mut fast_payloads: Vec<Option<&String>> = Vec::new();
+                let mut tag_in_rbx = false;
                 
-                // Generar código para cada brazo
                 for (i, arm) in arms.iter().enumerate() {
-                    let arm_label = self.new_label(&format!("match_arm_{}", i));
-                    arm_labels.push(arm_label.clone());
+                    let mut bindings = HashMap::new();
+                    let fast_tag = if arm.guard.is_none() { Self::simple_tag_pattern(&arm.pattern) } else { None };
                     
-                    // Comparar tag con el patrón
-                    match &arm.pattern {
-                        Pattern::Some => {
-                            // Tag debe ser 1 para Some
-                                self.text_section.push(format!("    cmp rbx, 1  ; comparar tag con Some"));
-                                self.text_section.push(format!("    je {}", arm_label));
+                    if let Some((tag, variant, payload)) = fast_tag {
+                        // Camino rápido: Some/None/Ok/Err con payload `_` o `x` solo miran el tag
+                        if !tag_in_rbx {
+                            self.text_section.push(format!("    mov rax, [rbp - {}]", value_slot + 8));
+                            self.text_section.push("    mov rbx, [rax]  ; cargar tag del tagged union".to_string());
+                            tag_in_rbx = true;
                         }
-                        Pattern::None => {
-                            // Tag debe ser 0 para None
-                                self.text_section.push(format!("    cmp rbx, 0  ; comparar tag con None"));
-                                self.text_section.push(format!("    je {}", arm_label));
+                        self.text_section.push(format!("    cmp rbx, {}  ; comparar tag con {}", tag, variant));
+                        self.text_section.push(format!("    je {}", arm_labels[i]));
+                        if let Some(name) = payload {
+                            let slot = self.alloc_temp_slot();
+                            bindings.insert(name.clone(), slot);
                         }
-                        Pattern::Ok => {
-                            // Tag debe ser 0 para Ok
-                                self.text_section.push(format!("    cmp rbx, 0  ; comparar tag con Ok"));
-                                self.text_section.push(format!("    je {}", arm_label));
-                        }
-                        Pattern::Err => {
-                            // Tag debe ser 1 para Err
-                                self.text_section.push(format!("    cmp rbx, 1  ; comparar tag con Err"));
-                                self.text_section.push(format!("    je {}", arm_label));
-                        }
-                        Pattern::Wildcard => {
-                            // Wildcard siempre coincide (salto incondicional al final)
-                                self.text_section.push(format!("    jmp {}", arm_label));
-                        }
-                        _ => {
-                            // Otros patrones (literales, ident) no son compatibles con Option/Result
-                            // Por ahora, tratar como wildcard
-                                self.text_section.push(format!("    jmp {}", arm_label));
+                        fast_payloads.push(payload);
+                    } else {
+                        tag_in_rbx = false;
+                        let next_label = self.new_label("match_next");
+                        self.generate_pattern_test(&arm.pattern, value_slot, &next_label, &mut bindings)?;
+                        if let Some(guard) = &arm.guard {
+                            // Las variables del patrón son visibles en la guarda
+                            self.variables.extend(bindings.iter().map(|(k, v)| (k.clone(), *v)));
+                            self.generate_expr_windows(guard)?;
+                            self.variables = saved_vars.clone();
+                            self.text_section.push("    cmp rax, 0  ; guarda del brazo".to_string());
+                            self.text_section.push(format!("    je {}", next_label));
                         }
+                        self.text_section.push(format!("    jmp {}", arm_labels[i]));
+                        self.text_section.push(format!("{}:", next_label));
+                        fast_payloads.push(None);
                     }
+                    arm_bindings.push(bindings);
                 }
                 
-                // Si no coincide ningún patrón, saltar al final (o panic si no hay wildcard)
-                self.text_section.push(format!("    jmp {}", end_label));
+                // Ningún brazo coincide: panic en runtime
+                self.text_section.push(format!("{}:", no_match_label));
+                self.text_section.push("    jmp panic_match_failed  ; ningún brazo del match coincide".to_string());
                 
                 // Generar código de cada brazo
                 for (i, arm) in arms.iter().enumerate() {
                     self.text_section.push(format!("{}:", arm_labels[i]));
-                    
-                    // Si el patrón tiene binding (Some, Ok, Err), cargar el valor
-                    match &arm.pattern {
-                        Pattern::Some | Pattern::Ok | Pattern::Err => {
-                            // Cargar valor desde [rax + 8]
-                            self.text_section.push("    mov rax, [rax + 8]  ; cargar valor del tagged union".to_string());
-                        }
-                        _ => {
-                            // No hay binding, mantener rax como está
-                        }
+                    if let Some(name) = fast_payloads[i] {
+                        let slot = arm_bindings[i][name];
+                        self.text_section.push(format!("    mov rax, [rbp - {}]", value_slot + 8));
+                        self.text_section.push("    mov rax, [rax + 8]  ; cargar valor del tagged union".to_string());
+                        self.text_section.push(format!("    mov [rbp - {}], rax  ; binding {}", slot + 8, name));
                     }
-                    
-                    // Generar código del cuerpo del brazo
+                    self.variables.extend(arm_bindings[i].iter().map(|(k, v)| (k.clone(), *v)));
                     self.generate_expr_windows(&arm.body)?;
-                    // Saltar al final
+                    self.variables = saved_vars.clone();
                     self.text_section.push(format!("    jmp {}", end_label));
                 }
                 
@@ -4157,1211 +3940,11 @@ impl CodeGenerator {
         Ok(())
     }
 
-    fn generate_stmt(&mut self, stmt: &Stmt) -> Result<()> {
-        match stmt {
-            Stmt::Print(expr) => {
-                self.text_section.push("    ; print".to_string());
-                match expr {
-                    Expr::String(s) => {
-                        let label = self.add_string_data(s);
-                            self.text_section.push(format!("    mov rax, 1  ; sys_write",));
-                            self.text_section.push(format!("    mov rdi, 1  ; stdout",));
-                            self.text_section.push(format!("    lea rsi, [rel {}]", label));
-                            self.text_section.push(format!("    mov rdx, {}_len", label));
-                            self.text_section.push(format!("    syscall",));
-                    }
-                    Expr::Number(n) => {
-                        // Print número: convertir a string en tiempo de compilación
-                        let num_str = format!("{}", n);
-                        let label = self.add_string_data(&num_str);
-                            self.text_section.push(format!("    mov rax, 1  ; sys_write"));
-                            self.text_section.push(format!("    mov rdi, 1  ; stdout"));
-                            self.text_section.push(format!("    lea rsi, [rel {}]", label));
-                            self.text_section.push(format!("    mov rdx, {}_len", label));
-                            self.text_section.push(format!("    syscall"));
-                    }
-                    Expr::Float(f) => {
-                        // Print float: convertir a string en tiempo de compilación
-                        let float_str = format!("{}", f);
-                        let label = self.add_string_data(&float_str);
-                            self.text_section.push(format!("    mov rax, 1  ; sys_write"));
-                            self.text_section.push(format!("    mov rdi, 1  ; stdout"));
-                            self.text_section.push(format!("    lea rsi, [rel {}]", label));
-                            self.text_section.push(format!("    mov rdx, {}_len", label));
-                            self.text_section.push(format!("    syscall"));
-                    }
-                    _ => {
-                        // Evaluar expresión numérica (ej: 2 + 5) y convertir a string
-                        // Para Linux, usar generate_expr (método genérico)
-                        self.generate_expr(expr)?;
-                        // RAX ahora contiene el resultado numérico
-                        // TODO: Implementar conversión runtime completa para Linux
-                        // Por ahora, solo soportamos literales y variables
-                        return Err(adead_common::ADeadError::RuntimeError {
-                            message: "Complex expressions in print not yet fully supported on Linux. Use Windows target or assign to variable first: let x = expr; print x".to_string(),
-                        });
-                    }
-                }
-            }
-            Stmt::Let { mutable, name, value } => {
-                // Si el valor es un StructLiteral, registrar para destrucción RAII si tiene destroy
-                // y también registrar el tipo de la variable
-                let struct_name = if let Expr::StructLiteral { name: struct_name, .. } = value {
-                    if self.structs_with_destroy.contains_key(struct_name) {
-                        self.variables_to_destroy.push((name.clone(), struct_name.clone()));
-                    }
-                    // Registrar el tipo de la variable para acceso a campos
-                    self.variable_types.insert(name.clone(), struct_name.clone());
-                    Some(struct_name.clone())
-                } else {
-                    None
-                };
-                
-                self.generate_expr(value)?;
-                
-                // Si es un struct con constructor, llamarlo aquí
-                // Por ahora, solo guardamos la dirección del struct
-                
-                // Store in stack (simplified: just track as variable)
-                // Note: mutability is tracked but NASM code is the same (difference is in borrow checker)
-                let offset = if let Some(&existing_offset) = self.variables.get(name) {
-                    existing_offset
-                } else {
-                    let offset = self.stack_offset;
-                    self.stack_offset += 8;
-                    self.variables.insert(name.clone(), offset);
-                    offset
-                };
-                self.text_section
-                    .push(format!("    mov [rbp - {}], rax  ; variable {} ({})", 
-                        offset + 8, name, if *mutable { "mutable" } else { "immutable" }));
-            }
-            Stmt::MultiLet { mutable, names, values } => {
-                // Múltiple asignación Python-style (Linux)
-                for (i, name) in names.iter().enumerate() {
-                    let value = if i < values.len() { &values[i] } else { &values[values.len() - 1] };
-                    self.generate_expr(value)?;
-                    let offset = if let Some(&existing_offset) = self.variables.get(name) {
-                        existing_offset
-                    } else {
-                        let offset = self.stack_offset;
-                        self.stack_offset += 8;
-                        self.variables.insert(name.clone(), offset);
-                        offset
-                    };
-                    self.text_section
-                        .push(format!("    mov [rbp - {}], rax  ; variable {} ({})", 
-                            offset + 8, name, if *mutable { "mutable" } else { "immutable" }));
-                }
-            }
-            Stmt::Import(_module_name) => {
-                // Import statements son procesados en tiempo de compilación
-                // No generan código directamente, solo información de módulos
-                // TODO: Implementar resolución de módulos
-                self.text_section.push(format!("    ; import {} (pendiente resolución)", _module_name));
-            }
-            Stmt::Expr(expr) => {
-                self.generate_expr(expr)?;
-            }
-            Stmt::If {
-                condition,
-                then_body,
-                else_body,
-            } => {
-                self.generate_expr(condition)?;
-                let else_label = self.new_label("else");
-                let end_label = self.new_label("endif");
-                
-                // Check condition: if 0, jump to else/end
-                self.text_section.push("    cmp rax, 0".to_string());
-                if else_body.is_some() {
-                    self.text_section.push(format!("    je {}", else_label));
-                } else {
-                    self.text_section.push(format!("    je {}", end_label));
-                }
-
-                // Then body
-                for s in then_body {
-                    self.generate_stmt(s)?;
-                }
-                self.text_section.push(format!("    jmp {}", end_label));
-                
-                // Else body
-                if let Some(else_body) = else_body {
-                    self.text_section.push(format!("{}:", else_label));
-                    for s in else_body {
-                        self.generate_stmt(s)?;
-                    }
-                }
-                self.text_section.push(format!("{}:", end_label));
-            }
-            Stmt::While { condition, body } => {
-                let loop_start = self.new_label("while_start");
-                let loop_end = self.new_label("while_end");
-                let loop_continue = loop_start.clone();
-                
-                self.loop_stack.push(LoopContext {
-                    break_label: loop_end.clone(),
-                    continue_label: loop_continue,
-                });
-                
-                    self.text_section.push(format!("{}:", loop_start));
-                self.generate_expr(condition)?;
-                self.text_section.push("    cmp rax, 0".to_string());
-                self.text_section.push(format!("    je {}", loop_end));
-                
-                for s in body {
-                    self.generate_stmt(s)?;
-                }
-                self.text_section.push(format!("    jmp {}", loop_start));
-                self.text_section.push(format!("{}:", loop_end));
-                
-                self.loop_stack.pop();
-            }
-            Stmt::For { var, start, end, body } => {
-                let loop_start = self.new_label("for_start");
-                let loop_end = self.new_label("for_end");
-                let loop_continue = self.new_label("for_continue");
-                
-                self.loop_stack.push(LoopContext {
-                    break_label: loop_end.clone(),
-                    continue_label: loop_continue.clone(),
-                });
-                
-                // Evaluar start y guardar en variable
-                self.generate_expr(start)?;
-                let var_offset = self.stack_offset;
-                self.stack_offset += 8;
-                self.variables.insert(var.clone(), var_offset);
-                self.text_section.push(format!("    mov [rbp - {}], rax  ; {} (loop counter)", var_offset + 8, var));
-                
-                // Evaluar end y guardar en r13
-                self.generate_expr(end)?;
-                self.text_section.push("    mov r13, rax  ; end value".to_string());
-                
-                // Loop start
-                self.text_section.push(format!("{}:", loop_start));
-                self.text_section.push(format!("    mov rax, [rbp - {}]", var_offset + 8));
-                self.text_section.push("    cmp rax, r13".to_string());
-                self.text_section.push(format!("    jge {}", loop_end));
-                
-                for s in body {
-                    self.generate_stmt(s)?;
-                }
-                
-                self.text_section.push(format!("{}:", loop_continue));
-                self.text_section.push(format!("    mov rax, [rbp - {}]", var_offset + 8));
-                self.text_section.push("    inc rax".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax", var_offset + 8));
-                self.text_section.push(format!("    jmp {}", loop_start));
-                self.text_section.push(format!("{}:", loop_end));
-                
-                self.loop_stack.pop();
-            }
-            Stmt::Break => {
-                if let Some(ctx) = self.loop_stack.last() {
-                    let label = ctx.break_label.clone();
-                    self.text_section.push(format!("    jmp {}", label));
-                }
-            }
-            Stmt::Continue => {
-                if let Some(ctx) = self.loop_stack.last() {
-                    let label = ctx.continue_label.clone();
-                    self.text_section.push(format!("    jmp {}", label));
-                }
-            }
-            Stmt::Fn { visibility: _, name, params, body } => {
-                // Generate function
-                // Visibility no afecta la generación de código (Sprint 1.3)
-                let func_label = format!("fn_{}", name);
-                self.text_section.push(format!("    jmp {}_end", func_label));
-                self.text_section.push(format!("{}:", func_label));
-                self.text_section.push("    push rbp".to_string());
-                self.text_section.push("    mov rbp, rsp".to_string());
-                
-                // Store params in local variables
-                for (i, param) in params.iter().enumerate() {
-                    // System V ABI: rdi, rsi, rdx, rcx, r8, r9
-                    let reg = match i {
-                        0 => "rdi",
-                        1 => "rsi",
-                        2 => "rdx",
-                        3 => "rcx",
-                        4 => "r8",
-                        5 => "r9",
-                        _ => break, // More params would be on stack
-                    };
-                    let offset = self.stack_offset;
-                    self.stack_offset += 8;
-                    self.variables.insert(param.name.clone(), offset);
-                    self.text_section
-                        .push(format!("    mov [rbp - {}], {}", offset + 8, reg));
-                }
-                
-                for s in body {
-                    self.generate_stmt(s)?;
-                }
-                
-                // Return (if no explicit return, return 0)
-                self.text_section.push("    mov rax, 0".to_string());
-                self.text_section.push("    pop rbp".to_string());
-                self.text_section.push("    ret".to_string());
-                self.text_section.push(format!("{}_end:", func_label));
-            }
-            Stmt::Return(expr) => {
-                if let Some(expr) = expr {
-                    self.generate_expr(expr)?;
-                } else {
-                    self.text_section.push("    mov rax, 0".to_string());
-                }
-                self.text_section.push("    pop rbp".to_string());
-                self.text_section.push("    ret".to_string());
-            }
-            Stmt::Struct { name, parent: _, fields, init, destroy: _, methods: _ } => {
-                // Registrar campos del struct para cálculo de offsets
-                let field_names: Vec<String> = fields.iter().map(|f| f.name.clone()).collect();
-                self.struct_definitions.insert(name.clone(), field_names);
-                
-                // Registrar struct y generar código para constructor si existe
-                if let Some(init_method) = init {
-                    // Generar función de constructor: StructName_init
-                    let init_label = format!("{}_init", name);
-                    self.text_section.push(format!("    jmp {}_end", init_label));
-                    self.text_section.push(format!("{}:", init_label));
-                    self.text_section.push("    push rbp".to_string());
-                    self.text_section.push("    mov rbp, rsp".to_string());
-                    
-                    // Guardar parámetros en stack (System V AMD64 calling convention)
-                    for (i, param) in init_method.params.iter().enumerate() {
-                        let offset = self.stack_offset;
-                        self.stack_offset += 8;
-                        self.variables.insert(param.name.clone(), offset);
-                        
-                        let reg = match i {
-                            0 => "rdi",
-                            1 => "rsi",
-                            2 => "rdx",
-                            3 => "rcx",
-                            4 => "r8",
-                            5 => "r9",
-                            _ => {
-                                let stack_offset = 16 + (i - 6) * 8;
-                                    self.text_section.push(format!("    mov rax, [rbp + {}]", stack_offset));
-                                    self.text_section.push(format!("    mov [rbp - {}], rax", offset + 8));
-                                continue;
-                            }
-                        };
-                            self.text_section.push(format!("    mov [rbp - {}], {}", offset + 8, reg));
-                    }
-                    
-                    // Generar cuerpo del constructor
-                    for s in &init_method.body {
-                        self.generate_stmt(s)?;
-                    }
-                    
-                    self.text_section.push("    leave".to_string());
-                    self.text_section.push("    ret".to_string());
-                    self.text_section.push(format!("{}_end:", init_label));
-                }
-                // Struct definitions are type information only, no code generation needed for the struct itself
-            }
-        }
-        Ok(())
-    }
-
-    fn generate_expr(&mut self, expr: &Expr) -> Result<()> {
-        match expr {
-            Expr::Number(n) => {
-                self.text_section.push(format!("    mov rax, {}", n));
-            }
-            Expr::Bool(b) => {
-                // Bool: 0 = false, 1 = true en RAX
-                let value = if *b { 1 } else { 0 };
-                self.text_section.push(format!("    mov rax, {}  ; bool {}", value, b));
-            }
-            Expr::Float(f) => {
-                // Cargar constante flotante en XMM0 (Linux)
-                let label = self.add_float_data(*f);
-                self.text_section.push(format!("    movsd xmm0, [rel {}]  ; cargar float {}", label, f));
-            }
-            Expr::String(_) => {
-                // Strings handled separately in print
-                return Err(adead_common::ADeadError::RuntimeError {
-                    message: "cannot use string in expression yet".to_string(),
-                });
-            }
-            Expr::ArrayLiteral(elements) => {
-                // Array literal: [1, 2, 3] (Linux)
-                // Similar a Windows pero con convenciones de System V
-                let array_size = elements.len() * 8; // 8 bytes por elemento (int64)
-                let base_offset = self.stack_offset;
-                
-                self.stack_offset += array_size as i64;
-                self.text_section.push(format!("    ; Array literal: {} elementos ({} bytes)", elements.len(), array_size));
-                self.text_section.push(format!("    sub rsp, {}  ; reservar espacio para array", array_size));
-                
-                for (i, element) in elements.iter().enumerate() {
-                    self.generate_expr(element)?;
-                    let element_offset = base_offset + (i as i64 * 8);
-                    self.text_section.push(format!("    mov [rbp - {}], rax  ; array[{}]", element_offset + 8, i));
-                }
-                
-                    self.text_section.push(format!("    lea rax, [rbp - {}]  ; dirección base del array", base_offset + 8));
-            }
-            Expr::Borrow { expr, .. } => {
-                // Borrowing: generar dirección de la expresión
-                // Por ahora, solo soportamos borrowing de variables
-                self.generate_expr(expr)?;
-                // TODO: Generar código para obtener dirección (lea)
-            }
-            Expr::Deref(expr) => {
-                // Dereferenciar: cargar valor desde la dirección
-                self.generate_expr(expr)?;
-                // Si el valor está en rax (dirección), cargar desde esa dirección
-                self.text_section.push("    mov rax, [rax]".to_string());
-            }
-            // Option/Result constructors (O0.4) - TODO: Implementar generación de código
-            Expr::Some(expr) | Expr::Ok(expr) => {
-                // Por ahora, simplemente evaluar la expresión interna
-                // TODO: Implementar tagged union en NASM
-                self.generate_expr(expr)?;
-            }
-            Expr::Err(expr) => {
-                // Por ahora, simplemente evaluar la expresión interna
-                // TODO: Implementar tagged union en NASM
-                self.generate_expr(expr)?;
-            }
-            Expr::None => {
-                // None/Err sin valor: poner 0 en rax como marcador
-                // TODO: Implementar tag apropiado en NASM
-                self.text_section.push("    mov rax, 0".to_string());
-            }
-            // Structs (Fase 1.2 - O1, O3, O4)
-            Expr::StructLiteral { name, fields } => {
-                // Registrar los campos del struct para poder calcular offsets después
-                let field_names: Vec<String> = fields.iter().map(|(n, _)| n.clone()).collect();
-                if !self.struct_definitions.contains_key(name) {
-                    self.struct_definitions.insert(name.clone(), field_names);
-                }
-                
-                // Generar struct literal en stack
-                let struct_size = fields.len() * 8;
-                let offset = self.stack_offset;
-                self.stack_offset += struct_size as i64;
-                self.text_section.push(format!("    sub rsp, {}  ; espacio para struct {} ({} campos)", struct_size, name, fields.len()));
-                
-                // Generar valores de campos
-                for (i, (field_name, value)) in fields.iter().enumerate() {
-                    self.generate_expr(value)?;
-                    let field_offset = offset + (i as i64 * 8) + 8;
-                    self.text_section.push(format!("    mov [rbp - {}], rax  ; {}.{}", field_offset, name, field_name));
-                }
-                
-                self.text_section.push(format!("    lea rax, [rbp - {}]  ; dirección del struct {}", offset + 8, name));
-            }
-            Expr::FieldAccess { object, field } => {
-                // Determinar el tipo del objeto para calcular el offset correcto
-                let struct_type = self.get_struct_type_from_expr(object);
-                
-                self.generate_expr(object)?;
-                
-                // Calcular offset del campo (negativo porque stack crece hacia abajo)
-                let field_offset = if let Some(ref type_name) = struct_type {
-                    if let Some(fields) = self.struct_definitions.get(type_name) {
-                        fields.iter().position(|f| f == field).unwrap_or(0) * 8
-                    } else {
-                        0
-                    }
-                } else {
-                    0
-                };
-                
-                self.text_section.push(format!("    ; accediendo campo '{}' (offset: -{})", field, field_offset));
-                if field_offset == 0 {
-                    self.text_section.push("    mov rax, [rax]  ; cargar campo (offset 0)".to_string());
-                } else {
-                    self.text_section.push(format!("    mov rax, [rax - {}]  ; cargar campo", field_offset));
-                }
-            }
-            Expr::FieldAssign { object, field, value } => {
-                // Asignación a campo de struct: obj.field = value (Linux)
-                let struct_type = self.get_struct_type_from_expr(object);
-                
-                // Primero generar el valor a asignar
-                self.generate_expr(value)?;
-                self.text_section.push("    push rax  ; guardar valor a asignar".to_string());
-                
-                // Luego obtener la dirección del objeto
-                self.generate_expr(object)?;
-                
-                // Calcular offset del campo
-                let field_offset = if let Some(ref type_name) = struct_type {
-                    if let Some(fields) = self.struct_definitions.get(type_name) {
-                        fields.iter().position(|f| f == field).unwrap_or(0) * 8
-                    } else {
-                        0
-                    }
-                } else {
-                    0
-                };
-                
-                self.text_section.push("    pop rbx  ; restaurar valor a asignar".to_string());
-                self.text_section.push(format!("    ; asignando a campo '{}' (offset: -{})", field, field_offset));
-                if field_offset == 0 {
-                    self.text_section.push("    mov [rax], rbx  ; asignar campo (offset 0)".to_string());
-                } else {
-                    self.text_section.push(format!("    mov [rax - {}], rbx  ; asignar campo", field_offset));
-                }
-            }
-            Expr::Index { array, index } => {
-                // Indexación: arr[0] (Linux)
-                self.generate_expr(array)?;
-                self.text_section.push("    push rax  ; guardar dirección base del array".to_string());
-                
-                self.generate_expr(index)?;
-                self.text_section.push("    mov rcx, rax  ; rcx = índice".to_string());
-                self.text_section.push("    mov rax, 8".to_string());
-                self.text_section.push("    imul rax, rcx  ; rax = índice * 8 (offset en bytes)".to_string());
-                
-                self.text_section.push("    pop rbx  ; restaurar dirección base".to_string());
-                self.text_section.push("    add rax, rbx  ; rax = dirección base + offset".to_string());
-                
-                self.text_section.push("    mov rax, [rax]  ; cargar array[index]".to_string());
-            }
-            Expr::Slice { object: _, start: _, end: _ } => {
-                // Slicing: s[0:4] (Linux)
-                // Por ahora, retornar error ya que strings avanzados están implementados principalmente para Windows
-                return Err(adead_common::ADeadError::RuntimeError {
-                    message: "String slicing not yet implemented for Linux. Use Windows backend.".to_string(),
-                });
-            }
-            Expr::MethodCall { object, method, args } => {
-                // Llamada a método (simplificado)
-                self.generate_expr(object)?;
-                self.text_section.push("    push rax  ; self".to_string());
-                for arg in args {
-                    self.generate_expr(arg)?;
-                    self.text_section.push("    push rax".to_string());
-                }
-                self.text_section.push(format!("    call fn_{}", method));
-                let cleanup = (args.len() + 1) * 8;
-                if cleanup > 0 {
-                    self.text_section.push(format!("    add rsp, {}", cleanup));
-                }
-            }
-            Expr::Match { expr, arms } => {
-                // Generar match expression
-                // TODO: Implementar match exhaustivo con saltos condicionales
-                // Por ahora, generar el código de la expresión base
-                self.generate_expr(expr)?;
-                // TODO: Implementar lógica de matching real
-                if let Some(first_arm) = arms.first() {
-                    self.generate_expr(&first_arm.body)?;
-                }
-            }
-            Expr::SuperCall { method, args } => {
-                // super.metodo(args) - llamada a método del padre (Linux)
-                // Similar a Windows pero con convenciones System V
-                if let Some(current_struct_name) = &self.current_struct {
-                    if let Some(parent_name) = self.struct_parents.get(current_struct_name) {
-                        if let Some(parent) = parent_name.clone() {
-                            // Evaluar argumentos (System V: rdi, rsi, rdx, rcx, r8, r9)
-                            for (i, arg) in args.iter().enumerate() {
-                                self.generate_expr(arg)?;
-                                let reg = match i {
-                                    0 => "rdi",
-                                    1 => "rsi",
-                                    2 => "rdx",
-                                    3 => "rcx",
-                                    4 => "r8",
-                                    5 => "r9",
-                                    _ => {
-                                        self.text_section.push("    push rax  ; arg en stack".to_string());
-                                        continue;
-                                    }
-                                };
-                                self.text_section.push(format!("    mov {}, rax  ; arg{}", reg, i));
-                            }
-                            
-                            // self debe estar en rdi (primer parámetro en System V)
-                            if let Some(&self_offset) = self.variables.get("self") {
-                                self.text_section.push(format!("    mov rdi, [rbp - {}]  ; self desde stack", self_offset + 8));
-                            }
-                            
-                            // Llamar a método del padre
-                            let method_label = format!("fn_{}_{}", parent, method);
-                            self.text_section.push(format!("    ; Llamada a super.{}(), método del padre {}", method, parent));
-                            self.text_section.push(format!("    call {}", method_label));
-                            
-                            // RAX contiene el valor de retorno (si hay)
-                        } else {
-                            return Err(adead_common::ADeadError::RuntimeError {
-                                message: format!("'{}' no tiene struct padre. 'super.metodo()' solo puede usarse dentro de structs con herencia.", current_struct_name),
-                            });
-                        }
-                    } else {
-                        return Err(adead_common::ADeadError::RuntimeError {
-                            message: format!("No se pudo encontrar información del struct '{}' para 'super.metodo()'.", current_struct_name),
-                        });
-                    }
-                } else {
-                    return Err(adead_common::ADeadError::RuntimeError {
-                        message: "'super.metodo()' solo puede usarse dentro de métodos de structs.".to_string(),
-                    });
-                }
-            }
-            Expr::PropagateError(expr) => {
-                // Operador ?: propaga error automáticamente (versión Linux)
-                // Misma lógica que Windows pero con syscalls de Linux si es necesario
-                // Evaluar la expresión (debe ser Result<T, E>)
-                self.generate_expr(expr)?;
-                // rax contiene la dirección del tagged union Result
-                
-                // Guardar dirección en rbx para poder acceder después
-                self.text_section.push("    mov rbx, rax  ; guardar dirección del Result".to_string());
-                
-                // Cargar tag
-                self.text_section.push("    mov rax, [rbx]  ; cargar tag (0=Ok, 1=Err)".to_string());
-                
-                // Crear labels
-                let ok_label = self.new_label("propagate_ok");
-                let error_label = self.new_label("propagate_error");
-                
-                // Verificar si es Ok (tag == 0)
-                self.text_section.push("    cmp rax, 0  ; comparar tag con 0 (Ok)".to_string());
-                self.text_section.push(format!("    je {}  ; si es Ok, desenvolver valor", ok_label));
-                
-                // Si llegamos aquí, es Err (tag == 1) -> propagar error
-                self.text_section.push(format!("    jmp {}  ; si es Err, propagar", error_label));
-                
-                // Label para Ok: desenvolver valor
-                self.text_section.push(format!("{}:", ok_label));
-                self.text_section.push("    mov rax, [rbx + 8]  ; cargar valor de Ok desde Result".to_string());
-                
-                // Label para error: dejar error en rax (dirección del error)
-                self.text_section.push(format!("{}:", error_label));
-                self.text_section.push("    mov rax, [rbx + 8]  ; cargar error de Err".to_string());
-                // TODO: En una función con tipo de retorno Result, deberíamos:
-                // 1. Construir un nuevo Result con el error
-                // 2. Retornar temprano con "ret"
-                // Por ahora, el error queda en rax para que el caller lo maneje
-                self.text_section.push("    ; Nota: En función Result, debería retornar temprano".to_string());
-            }
-            Expr::Ident(name) => {
-                if let Some(&offset) = self.variables.get(name) {
-                    self.text_section
-                        .push(format!("    mov rax, [rbp - {}]", offset + 8));
-                } else {
-                    return Err(adead_common::ADeadError::RuntimeError {
-                        message: format!("undefined variable: {}", name),
-                    });
-                }
-            }
-            Expr::Assign { name, value } => {
-                // Generate the value first
-                self.generate_expr(value)?;
-                // Store in variable location
-                if let Some(&offset) = self.variables.get(name) {
-                    self.text_section
-                        .push(format!("    mov [rbp - {}], rax", offset + 8));
-                } else {
-                    // Create new variable if it doesn't exist
-                    let offset = self.stack_offset;
-                    self.stack_offset += 8;
-                    self.variables.insert(name.clone(), offset);
-                    self.text_section
-                        .push(format!("    mov [rbp - {}], rax", offset + 8));
-                }
-                // Result of assignment is the value
-            }
-            // Operadores compuestos: x += 5, x -= 3, x *= 2, x /= 4 (Sprint 1 - Python-like) - Linux
-            Expr::CompoundAssign { name, op, value } => {
-                // Cargar valor actual de la variable
-                if let Some(&offset) = self.variables.get(name) {
-                    self.text_section.push(format!("    mov rbx, [rbp - {}]  ; cargar {} actual", offset + 8, name));
-                } else {
-                    return Err(adead_common::ADeadError::RuntimeError {
-                        message: format!("Variable '{}' no definida para operador compuesto", name),
-                    });
-                }
-                
-                // Evaluar la expresión del valor
-                self.generate_expr(value)?;
-                // RAX contiene el valor, RBX contiene el valor actual de la variable
-                
-                // Aplicar operación
-                match op {
-                    BinOp::Add => {
-                        self.text_section.push("    add rbx, rax  ; += operación".to_string());
-                    }
-                    BinOp::Sub => {
-                        self.text_section.push("    sub rbx, rax  ; -= operación".to_string());
-                    }
-                    BinOp::Mul => {
-                        self.text_section.push("    imul rbx, rax  ; *= operación".to_string());
-                    }
-                    BinOp::Div => {
-                        self.text_section.push("    push rax  ; guardar divisor".to_string());
-                        self.text_section.push("    mov rax, rbx  ; dividendo en rax".to_string());
-                        self.text_section.push("    cqo  ; sign-extend".to_string());
-                        self.text_section.push("    pop rbx  ; divisor en rbx".to_string());
-                        self.text_section.push("    idiv rbx  ; /= operación".to_string());
-                        self.text_section.push("    mov rbx, rax  ; resultado en rbx".to_string());
-                    }
-                    _ => {
-                        return Err(adead_common::ADeadError::RuntimeError {
-                            message: format!("Operador compuesto no soportado: {:?}", op),
-                        });
-                    }
-                }
-                
-                // Guardar resultado en la variable
-                if let Some(&offset) = self.variables.get(name) {
-                    self.text_section.push(format!("    mov [rbp - {}], rbx  ; guardar resultado en {}", offset + 8, name));
-                    self.text_section.push("    mov rax, rbx  ; resultado en rax".to_string());
-                }
-            }
-            // F-strings: f"Hola {nombre}" (Sprint 2.3 - Python-like) - Linux
-            Expr::FString { parts } => {
-                use adead_parser::FStringPart;
-                // Implementación Linux similar a Windows
-                let buffer_size = 256;
-                let buffer_offset = self.stack_offset;
-                self.stack_offset += buffer_size;
-                
-                self.text_section.push(format!("    ; F-string con {} partes (Linux)", parts.len()));
-                self.text_section.push(format!("    sub rsp, {}", buffer_size));
-                self.text_section.push(format!("    lea rdi, [rbp - {}]", buffer_offset + 8));
-                self.text_section.push("    xor r12, r12".to_string());
-                
-                for part in parts {
-                    match part {
-                        FStringPart::Literal(s) => {
-                            let label = self.add_string_data(s);
-                            self.text_section.push(format!("    lea rsi, [rel {}]", label));
-                            self.text_section.push(format!("    mov rcx, {}_len", label));
-                            self.text_section.push("    push rdi".to_string());
-                            self.text_section.push("    add rdi, r12".to_string());
-                            let copy_loop = self.new_label("fstr_copy_lnx");
-                            let copy_done = self.new_label("fstr_copy_done_lnx");
-                            self.text_section.push(format!("{}:", copy_loop));
-                            self.text_section.push("    cmp rcx, 0".to_string());
-                            self.text_section.push(format!("    je {}", copy_done));
-                            self.text_section.push("    mov al, [rsi]".to_string());
-                            self.text_section.push("    mov [rdi], al".to_string());
-                            self.text_section.push("    inc rsi".to_string());
-                            self.text_section.push("    inc rdi".to_string());
-                            self.text_section.push("    inc r12".to_string());
-                            self.text_section.push("    dec rcx".to_string());
-                            self.text_section.push(format!("    jmp {}", copy_loop));
-                            self.text_section.push(format!("{}:", copy_done));
-                            self.text_section.push("    pop rdi".to_string());
-                        }
-                        FStringPart::Expr(expr) => {
-                            self.text_section.push("    push rdi".to_string());
-                            self.text_section.push("    push r12".to_string());
-                            self.generate_expr(expr)?;
-                            self.text_section.push("    pop r12".to_string());
-                            self.text_section.push("    pop rdi".to_string());
-                            self.text_section.push("    push rdi".to_string());
-                            self.text_section.push("    add rdi, r12".to_string());
-                            self.text_section.push("    mov rbx, rdi".to_string());
-                            self.text_section.push("    mov rcx, 10".to_string());
-                            let num_loop = self.new_label("fstr_num_lnx");
-                            let num_done = self.new_label("fstr_num_done_lnx");
-                            self.text_section.push(format!("{}:", num_loop));
-                            self.text_section.push("    xor rdx, rdx".to_string());
-                            self.text_section.push("    div rcx".to_string());
-                            self.text_section.push("    add dl, '0'".to_string());
-                            self.text_section.push("    mov [rdi], dl".to_string());
-                            self.text_section.push("    inc rdi".to_string());
-                            self.text_section.push("    inc r12".to_string());
-                            self.text_section.push("    cmp rax, 0".to_string());
-                            self.text_section.push(format!("    jne {}", num_loop));
-                            self.text_section.push(format!("{}:", num_done));
-                            self.text_section.push("    mov rsi, rbx".to_string());
-                            self.text_section.push("    mov rcx, rdi".to_string());
-                            self.text_section.push("    dec rcx".to_string());
-                            let rev_loop = self.new_label("fstr_rev_lnx");
-                            let rev_done = self.new_label("fstr_rev_done_lnx");
-                            self.text_section.push(format!("{}:", rev_loop));
-                            self.text_section.push("    cmp rsi, rcx".to_string());
-                            self.text_section.push(format!("    jge {}", rev_done));
-                            self.text_section.push("    mov al, [rsi]".to_string());
-                            self.text_section.push("    mov bl, [rcx]".to_string());
-                            self.text_section.push("    mov [rsi], bl".to_string());
-                            self.text_section.push("    mov [rcx], al".to_string());
-                            self.text_section.push("    inc rsi".to_string());
-                            self.text_section.push("    dec rcx".to_string());
-                            self.text_section.push(format!("    jmp {}", rev_loop));
-                            self.text_section.push(format!("{}:", rev_done));
-                            self.text_section.push("    pop rdi".to_string());
-                        }
-                    }
-                }
-                
-                self.text_section.push(format!("    lea rax, [rbp - {}]", buffer_offset + 8));
-                self.text_section.push("    mov [rax + 8], r12".to_string());
-            }
-            Expr::TupleLiteral(elements) => {
-                // Tuple literal: (1, 2, 3) - Linux
-                let count = elements.len();
-                let tuple_size = count * 8;
-                let base_offset = self.stack_offset;
-                
-                self.stack_offset += tuple_size as i64;
-                self.text_section.push(format!("    ; Tuple literal: {} elementos (Linux)", count));
-                
-                for (i, element) in elements.iter().enumerate() {
-                    self.generate_expr(element)?;
-                    let element_offset = base_offset + (i as i64 * 8);
-                    self.text_section.push(format!("    mov [rbp - {}], rax  ; tuple[{}]", element_offset + 8, i));
-                }
-                
-                self.text_section.push(format!("    lea rax, [rbp - {}]", base_offset + 8));
-            }
-            Expr::Lambda { params, body } => {
-                // Lambda: lambda x, y: x + y - Linux
-                let lambda_name = self.new_label("lambda");
-                
-                let saved_vars = self.variables.clone();
-                let saved_offset = self.stack_offset;
-                
-                self.text_section.push(format!("    jmp {}_end", lambda_name));
-                self.text_section.push(format!("{}:", lambda_name));
-                self.text_section.push("    push rbp".to_string());
-                self.text_section.push("    mov rbp, rsp".to_string());
-                self.text_section.push("    sub rsp, 64".to_string());
-                
-                // System V ABI: RDI, RSI, RDX, RCX, R8, R9
-                let param_regs = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
-                self.stack_offset = 0;
-                for (i, param) in params.iter().enumerate() {
-                    self.stack_offset += 8;
-                    self.variables.insert(param.clone(), self.stack_offset);
-                    if i < 6 {
-                        self.text_section.push(format!("    mov [rbp - {}], {}  ; param {}", self.stack_offset + 8, param_regs[i], param));
-                    }
-                }
-                
-                self.generate_expr(body)?;
-                
-                self.text_section.push("    leave".to_string());
-                self.text_section.push("    ret".to_string());
-                self.text_section.push(format!("{}_end:", lambda_name));
-                
-                self.variables = saved_vars;
-                self.stack_offset = saved_offset;
-                
-                self.text_section.push(format!("    lea rax, [rel {}]", lambda_name));
-            }
-            Expr::ListComprehension { expr, var, iter, condition } => {
-                // List comprehension - Linux (similar a Windows)
-                self.text_section.push("    ; List comprehension (Linux)".to_string());
-                self.generate_expr(iter)?;
-                self.text_section.push("    mov rsi, rax".to_string());
-                self.text_section.push("    mov rcx, [rsi + 8]".to_string());
-                self.text_section.push("    mov rbx, [rsi + 0]".to_string());
-                
-                let result_offset = self.stack_offset;
-                self.stack_offset += 256;
-                self.text_section.push(format!("    lea r12, [rbp - {}]", result_offset + 8));
-                self.text_section.push("    xor r13, r13".to_string());
-                
-                let comp_loop = self.new_label("comp_loop");
-                let comp_end = self.new_label("comp_end");
-                
-                self.text_section.push("    xor r14, r14".to_string());
-                self.text_section.push(format!("{}:", comp_loop));
-                self.text_section.push("    cmp r14, rcx".to_string());
-                self.text_section.push(format!("    jge {}", comp_end));
-                
-                self.text_section.push("    push rcx".to_string());
-                self.text_section.push("    push rbx".to_string());
-                self.text_section.push("    push r12".to_string());
-                self.text_section.push("    push r13".to_string());
-                self.text_section.push("    push r14".to_string());
-                
-                let var_offset = self.stack_offset;
-                self.stack_offset += 8;
-                self.variables.insert(var.clone(), var_offset);
-                self.text_section.push("    mov rax, [rbx + r14*8]".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax", var_offset + 8));
-                
-                if let Some(cond) = condition {
-                    self.generate_expr(cond)?;
-                    self.text_section.push("    cmp rax, 0".to_string());
-                    self.text_section.push(format!("    je {}_skip", comp_loop));
-                }
-                
-                self.generate_expr(expr)?;
-                
-                self.text_section.push("    pop r14".to_string());
-                self.text_section.push("    pop r13".to_string());
-                self.text_section.push("    pop r12".to_string());
-                self.text_section.push("    mov [r12 + r13*8], rax".to_string());
-                self.text_section.push("    inc r13".to_string());
-                self.text_section.push("    pop rbx".to_string());
-                self.text_section.push("    pop rcx".to_string());
-                self.text_section.push("    inc r14".to_string());
-                self.text_section.push(format!("    jmp {}", comp_loop));
-                
-                if condition.is_some() {
-                    self.text_section.push(format!("{}_skip:", comp_loop));
-                    self.text_section.push("    pop r14".to_string());
-                    self.text_section.push("    pop r13".to_string());
-                    self.text_section.push("    pop r12".to_string());
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push("    pop rcx".to_string());
-                    self.text_section.push("    inc r14".to_string());
-                    self.text_section.push(format!("    jmp {}", comp_loop));
-                }
-                
-                self.text_section.push(format!("{}:", comp_end));
-                self.text_section.push(format!("    lea rax, [rbp - {}]", result_offset + 8));
-                
-                self.variables.remove(var);
-            }
-            Expr::DictLiteral { pairs } => {
-                // Dict literal - Linux
-                let count = pairs.len();
-                let dict_offset = self.stack_offset;
-                self.stack_offset += 32 + (count * 16) as i64;
-                
-                self.text_section.push(format!("    ; Dict literal: {} pares (Linux)", count));
-                self.text_section.push(format!("    lea rax, [rbp - {}]", dict_offset + 8));
-                self.text_section.push(format!("    mov qword [rax], {}", count));
-                self.text_section.push(format!("    mov qword [rax + 8], {}", count));
-                
-                for (i, (key, value)) in pairs.iter().enumerate() {
-                    self.text_section.push("    push rax".to_string());
-                    self.generate_expr(key)?;
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push(format!("    mov [rbx + {} + 16], rax", i * 16));
-                    self.text_section.push("    push rbx".to_string());
-                    self.generate_expr(value)?;
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push(format!("    mov [rbx + {} + 24], rax", i * 16));
-                }
-                
-                self.text_section.push(format!("    lea rax, [rbp - {}]", dict_offset + 8));
-            }
-            Expr::SetLiteral(elements) => {
-                // Set literal - Linux
-                let count = elements.len();
-                let set_offset = self.stack_offset;
-                self.stack_offset += 16 + (count * 8) as i64;
-                
-                self.text_section.push(format!("    ; Set literal: {} elementos (Linux)", count));
-                self.text_section.push(format!("    lea rax, [rbp - {}]", set_offset + 8));
-                self.text_section.push(format!("    mov qword [rax], {}", count));
-                self.text_section.push(format!("    mov qword [rax + 8], {}", count));
-                
-                for (i, element) in elements.iter().enumerate() {
-                    self.text_section.push("    push rax".to_string());
-                    self.generate_expr(element)?;
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push(format!("    mov [rbx + {} + 16], rax", i * 8));
-                }
-                
-                self.text_section.push(format!("    lea rax, [rbp - {}]", set_offset + 8));
-            }
-            Expr::Ternary { condition, then_expr, else_expr } => {
-                // Operador ternario - Linux
-                let else_label = self.new_label("ternary_else");
-                let end_label = self.new_label("ternary_end");
-                
-                self.generate_expr(condition)?;
-                self.text_section.push("    cmp rax, 0".to_string());
-                self.text_section.push(format!("    je {}", else_label));
-                self.generate_expr(then_expr)?;
-                self.text_section.push(format!("    jmp {}", end_label));
-                self.text_section.push(format!("{}:", else_label));
-                self.generate_expr(else_expr)?;
-                self.text_section.push(format!("{}:", end_label));
-            }
-            Expr::DictComprehension { key_expr, value_expr, var, iter, condition } => {
-                // Dict comprehension - Linux
-                self.text_section.push("    ; Dict comprehension (Linux)".to_string());
-                self.generate_expr(iter)?;
-                self.text_section.push("    mov rsi, rax".to_string());
-                
-                let result_offset = self.stack_offset;
-                self.stack_offset += 512;
-                self.text_section.push(format!("    lea r12, [rbp - {}]", result_offset + 8));
-                self.text_section.push("    mov qword [r12], 0".to_string());
-                
-                let var_offset = self.stack_offset;
-                self.stack_offset += 8;
-                self.variables.insert(var.clone(), var_offset);
-                
-                self.text_section.push("    mov rcx, [rsi + 8]".to_string());
-                self.text_section.push("    mov rbx, [rsi]".to_string());
-                let loop_label = self.new_label("dictcomp_loop");
-                let end_label = self.new_label("dictcomp_end");
-                self.text_section.push("    xor r14, r14".to_string());
-                self.text_section.push(format!("{}:", loop_label));
-                self.text_section.push("    cmp r14, rcx".to_string());
-                self.text_section.push(format!("    jge {}", end_label));
-                
-                self.text_section.push("    mov rax, [rbx + r14*8]".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax", var_offset + 8));
-                
-                if let Some(cond) = condition {
-                    self.text_section.push("    push rcx".to_string());
-                    self.text_section.push("    push rbx".to_string());
-                    self.text_section.push("    push r14".to_string());
-                    self.generate_expr(cond)?;
-                    self.text_section.push("    pop r14".to_string());
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push("    pop rcx".to_string());
-                    self.text_section.push("    cmp rax, 0".to_string());
-                    self.text_section.push(format!("    je {}_skip", loop_label));
-                }
-                
-                self.text_section.push("    push rcx".to_string());
-                self.text_section.push("    push rbx".to_string());
-                self.text_section.push("    push r14".to_string());
-                self.generate_expr(key_expr)?;
-                self.text_section.push("    push rax".to_string());
-                self.generate_expr(value_expr)?;
-                self.text_section.push("    pop rdx".to_string());
-                self.text_section.push("    pop r14".to_string());
-                self.text_section.push("    pop rbx".to_string());
-                self.text_section.push("    pop rcx".to_string());
-                
-                if condition.is_some() {
-                    self.text_section.push(format!("{}_skip:", loop_label));
-                }
-                
-                self.text_section.push("    inc r14".to_string());
-                self.text_section.push(format!("    jmp {}", loop_label));
-                self.text_section.push(format!("{}:", end_label));
-                self.text_section.push(format!("    lea rax, [rbp - {}]", result_offset + 8));
-                
-                self.variables.remove(var);
-            }
-            Expr::SetComprehension { expr, var, iter, condition } => {
-                // Set comprehension - Linux
-                self.text_section.push("    ; Set comprehension (Linux)".to_string());
-                self.generate_expr(iter)?;
-                self.text_section.push("    mov rsi, rax".to_string());
-                
-                let result_offset = self.stack_offset;
-                self.stack_offset += 256;
-                self.text_section.push(format!("    lea r12, [rbp - {}]", result_offset + 8));
-                self.text_section.push("    mov qword [r12], 0".to_string());
-                
-                let var_offset = self.stack_offset;
-                self.stack_offset += 8;
-                self.variables.insert(var.clone(), var_offset);
-                
-                self.text_section.push("    mov rcx, [rsi + 8]".to_string());
-                self.text_section.push("    mov rbx, [rsi]".to_string());
-                let loop_label = self.new_label("setcomp_loop");
-                let end_label = self.new_label("setcomp_end");
-                self.text_section.push("    xor r14, r14".to_string());
-                self.text_section.push("    xor r13, r13".to_string());
-                self.text_section.push(format!("{}:", loop_label));
-                self.text_section.push("    cmp r14, rcx".to_string());
-                self.text_section.push(format!("    jge {}", end_label));
-                
-                self.text_section.push("    mov rax, [rbx + r14*8]".to_string());
-                self.text_section.push(format!("    mov [rbp - {}], rax", var_offset + 8));
-                
-                if let Some(cond) = condition {
-                    self.text_section.push("    push rcx".to_string());
-                    self.text_section.push("    push rbx".to_string());
-                    self.text_section.push("    push r14".to_string());
-                    self.text_section.push("    push r13".to_string());
-                    self.generate_expr(cond)?;
-                    self.text_section.push("    pop r13".to_string());
-                    self.text_section.push("    pop r14".to_string());
-                    self.text_section.push("    pop rbx".to_string());
-                    self.text_section.push("    pop rcx".to_string());
-                    self.text_section.push("    cmp rax, 0".to_string());
-                    self.text_section.push(format!("    je {}_skip", loop_label));
-                }
-                
-                self.text_section.push("    push rcx".to_string());
-                self.text_section.push("    push rbx".to_string());
-                self.text_section.push("    push r14".to_string());
-                self.text_section.push("    push r13".to_string());
-                self.generate_expr(expr)?;
-                self.text_section.push("    pop r13".to_string());
-                self.text_section.push("    pop r14".to_string());
-                self.text_section.push("    pop rbx".to_string());
-                self.text_section.push("    pop rcx".to_string());
-                self.text_section.push("    mov [r12 + r13*8 + 16], rax".to_string());
-                self.text_section.push("    inc r13".to_string());
-                
-                if condition.is_some() {
-                    self.text_section.push(format!("{}_skip:", loop_label));
-                }
-                
-                self.text_section.push("    inc r14".to_string());
-                self.text_section.push(format!("    jmp {}", loop_label));
-                self.text_section.push(format!("{}:", end_label));
-                self.text_section.push("    mov [r12], r13".to_string());
-                self.text_section.push(format!("    lea rax, [rbp - {}]", result_offset + 8));
-                
-                self.variables.remove(var);
-            }
-            Expr::BinaryOp { op, left, right } => {
-                // Generate right side first, push to stack
-                self.generate_expr(left)?;
-                self.text_section.push("    push rax".to_string());
-                self.generate_expr(right)?;
-                self.text_section.push("    mov rbx, rax".to_string());
-                self.text_section.push("    pop rax".to_string());
-
-                match op {
-                    BinOp::Add => {
-                        self.text_section.push("    add rax, rbx".to_string());
-                    }
-                    BinOp::Sub => {
-                        self.text_section.push("    sub rax, rbx".to_string());
-                    }
-                    BinOp::Mul => {
-                        self.text_section.push("    imul rax, rbx".to_string());
-                    }
-                    BinOp::Div => {
-                        self.text_section.push("    cqo".to_string()); // sign extend rax to rdx:rax
-                        self.text_section.push("    idiv rbx".to_string());
-                    }
-                    BinOp::FloorDiv => {
-                        // División entera (//): igual que Div
-                        self.text_section.push("    cqo".to_string());
-                        self.text_section.push("    idiv rbx  ; división entera //".to_string());
-                    }
-                    BinOp::Mod => {
-                        // Módulo: RAX = RAX % RBX (resto de división)
-                        self.text_section.push("    cqo".to_string()); // sign extend rax to rdx:rax
-                        self.text_section.push("    idiv rbx".to_string());
-                        self.text_section.push("    mov rax, rdx  ; resto (módulo) en RAX".to_string());
-                    }
-                    BinOp::Pow => {
-                        // Potencia: base ** exponente (Linux)
-                        // Después de evaluar: rax = left (base), rbx = right (exponente)
-                        
-                        let label_not_zero = self.new_label("pow_not_zero");
-                        let label_loop = self.new_label("pow_loop");
-                        let label_end = self.new_label("pow_end");
-                        
-                        // rcx = exponente (rbx), rdx = base (rax)
-                        self.text_section.push("    mov rcx, rbx  ; rcx = exponente".to_string());
-                        self.text_section.push("    mov rdx, rax  ; rdx = base".to_string());
-                        
-                        self.text_section.push("    cmp rcx, 0".to_string());
-                        self.text_section.push(format!("    jne {}  ; si exp != 0", label_not_zero));
-                        self.text_section.push("    mov rax, 1  ; x^0 = 1".to_string());
-                        self.text_section.push(format!("    jmp {}", label_end));
-                        
-                        self.text_section.push(format!("{}:", label_not_zero));
-                        self.text_section.push("    mov rax, 1  ; resultado = 1".to_string());
-                        self.text_section.push(format!("{}:", label_loop));
-                        self.text_section.push("    cmp rcx, 0".to_string());
-                        self.text_section.push(format!("    jle {}  ; si exp <= 0, terminar", label_end));
-                        self.text_section.push("    imul rax, rdx  ; resultado *= base".to_string());
-                        self.text_section.push("    dec rcx  ; exp--".to_string());
-                        self.text_section.push(format!("    jmp {}", label_loop));
-                        self.text_section.push(format!("{}:", label_end));
-                    }
-                    BinOp::Eq => {
-                        self.text_section.push("    cmp rax, rbx".to_string());
-                        self.text_section.push("    sete al".to_string());
-                        self.text_section.push("    movzx rax, al".to_string());
-                    }
-                    BinOp::Ne => {
-                        self.text_section.push("    cmp rax, rbx".to_string());
-                        self.text_section.push("    setne al".to_string());
-                        self.text_section.push("    movzx rax, al".to_string());
-                    }
-                    BinOp::Lt => {
-                        self.text_section.push("    cmp rax, rbx".to_string());
-                        self.text_section.push("    setl al".to_string());
-                        self.text_section.push("    movzx rax, al".to_string());
-                    }
-                    BinOp::Le => {
-                        self.text_section.push("    cmp rax, rbx".to_string());
-                        self.text_section.push("    setle al".to_string());
-                        self.text_section.push("    movzx rax, al".to_string());
-                    }
-                    BinOp::Gt => {
-                        self.text_section.push("    cmp rax, rbx".to_string());
-                        self.text_section.push("    setg al".to_string());
-                        self.text_section.push("    movzx rax, al".to_string());
-                    }
-                    BinOp::Ge => {
-                        self.text_section.push("    cmp rax, rbx".to_string());
-                        self.text_section.push("    setge al".to_string());
-                        self.text_section.push("    movzx rax, al".to_string());
-                    }
-                    BinOp::And => {
-                        // AND lógico
-                        self.text_section.push("    test rax, rax".to_string());
-                        self.text_section.push("    setnz al".to_string());
-                        self.text_section.push("    test rbx, rbx".to_string());
-                        self.text_section.push("    setnz bl".to_string());
-                        self.text_section.push("    and al, bl".to_string());
-                        self.text_section.push("    movzx rax, al".to_string());
-                    }
-                    BinOp::Or => {
-                        // OR lógico
-                        self.text_section.push("    test rax, rax".to_string());
-                        self.text_section.push("    setnz al".to_string());
-                        self.text_section.push("    test rbx, rbx".to_string());
-                        self.text_section.push("    setnz bl".to_string());
-                        self.text_section.push("    or al, bl".to_string());
-                        self.text_section.push("    movzx rax, al".to_string());
-                    }
-                }
-            }
-            Expr::Not(inner) => {
-                // Negación lógica: !expr
-                self.generate_expr(inner)?;
-                self.text_section.push("    test rax, rax".to_string());
-                self.text_section.push("    setz al".to_string());
-                self.text_section.push("    movzx rax, al".to_string());
-            }
-            Expr::Call { module, name, args } => {
-                // Generate args in reverse order (for System V: rdi, rsi, rdx, ...)
-                for (i, arg) in args.iter().enumerate().rev() {
-                    self.generate_expr(arg)?;
-                    let reg = match i {
-                        0 => "rdi",
-                        1 => "rsi",
-                        2 => "rdx",
-                        3 => "rcx",
-                        4 => "r8",
-                        5 => "r9",
-                        _ => {
-                            // More args on stack
-                            self.text_section.push("    push rax".to_string());
-                            continue;
-                        }
-                    };
-                    self.text_section.push(format!("    mov {}, rax", reg));
-                }
-                
-                // Llamar función (con namespace si existe) (Sprint 1.3)
-                let function_name = if let Some(module_name) = module {
-                    format!("fn_{}_{}", module_name, name)
-                } else {
-                    format!("fn_{}", name)
-                };
-                
-                    self.text_section.push(format!("    call {}", function_name));
-                // Return value is in rax
-            }
-        }
-        Ok(())
-    }
-
     fn add_float_data(&mut self, value: f64) -> String {
         let label = format!("float_{}", self.string_counter);
         self.string_counter += 1;
-        self.data_section.push(format!("{}: dq {}  ; float64 literal", label, value));
+        // Bits exactos: `dq 3` sería el entero 3, y NaN/inf no tienen literal en NASM
+        self.data_section.push(format!("{}: dq 0x{:016X}  ; float64 literal {}", label, value.to_bits(), value));
         label
     }
     
@@ -5370,6 +3953,13 @@ impl CodeGenerator {
     /// - Versión completa: precisión completa cuando sea necesario
     /// Respetando siempre el cálculo correcto
     fn format_float_smart(&self, value: f64, use_precise: bool) -> String {
+        // Mismo texto que float_to_str en runtime
+        if value.is_nan() {
+            return "nan".to_string();
+        }
+        if value.is_infinite() {
+            return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+        }
         if use_precise {
             // Versión completa: mostrar toda la precisión disponible
             // Útil para cálculos científicos o cuando se necesita precisión exacta
@@ -5403,6 +3993,9 @@ impl CodeGenerator {
 
     // Helper para detectar si una expresión es float
     fn is_float_expr(&self, expr: &Expr) -> bool {
+        if let Some(ty) = self.expr_type(expr) {
+            return ty.is_float();
+        }
         match expr {
             Expr::Float(_) => true,
             Expr::BinaryOp { left, op: _, right } => {
@@ -5419,7 +4012,476 @@ impl CodeGenerator {
     }
 
     // Helper para detectar si una expresión es string
+    fn is_dict_expr(&self, expr: &Expr) -> bool {
+        matches!(self.expr_type(expr), Some(Type::Dict { .. }))
+    }
+
+    fn is_set_expr(&self, expr: &Expr) -> bool {
+        matches!(self.expr_type(expr), Some(Type::Set(_)))
+    }
+
+    /// Tipo de clave para el runtime de Dict: 1 = string, 0 = entero, -1 = sin determinar.
+    /// Se usa el tipo del dict (o del set) si se conoce y si no el de la clave
+    fn dict_key_kind(&self, dict: &Expr, key: Option<&Spanned<Expr>>) -> i64 {
+        let key_type = match self.expr_type(dict) {
+            Some(Type::Dict { key, .. }) => Some(&**key),
+            Some(Type::Set(element)) => Some(&**element),
+            _ => None,
+        };
+        match key_type {
+            Some(Type::String) => 1,
+            Some(ty) if *ty != Type::Unknown => 0,
+            _ => match key {
+                Some(key) if self.is_string_expr(key) => 1,
+                Some(_) => 0,
+                None => -1,
+            },
+        }
+    }
+
+    /// Llamar a un helper del runtime (dicts, sets, strings): evalúa `args` en orden y los pasa en
+    /// RCX, RDX, R8...; `extra` (inmediato) va en el registro siguiente
+    fn emit_runtime_call(&mut self, helper: &str, args: &[&Expr], extra: Option<i64>) -> Result<()> {
+        const ARG_REGS: [&str; 4] = ["rcx", "rdx", "r8", "r9"];
+        for arg in args {
+            self.generate_expr_windows(arg)?;
+            self.float_to_gpr_if_needed(arg);
+            self.text_section.push("    push rax".to_string());
+        }
+        if let Some(value) = extra {
+            self.text_section.push(format!("    mov {}, {}", ARG_REGS[args.len()], value));
+        }
+        for reg in ARG_REGS[..args.len()].iter().rev() {
+            self.text_section.push(format!("    pop {}", reg));
+        }
+        self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+        self.text_section.push(format!("    call {}", helper));
+        self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+        Ok(())
+    }
+
+    /// Los valores del dict se guardan como qword: un float vuelve de RAX a XMM0
+    fn float_result_if_needed(&mut self, expr: &Expr) {
+        if self.expr_type(expr).map_or(false, Type::is_float) {
+            self.text_section.push("    movq xmm0, rax  ; valor float64 (bits) → xmm0".to_string());
+        }
+    }
+
+    /// Imprimir el float64 de XMM0: float_to_str lo formatea en un buffer del frame;
+    /// `print x` agrega un newline igual que con los enteros en runtime, una parte de
+    /// un f-string no
+    fn emit_print_float(&mut self, newline: bool) {
+        let buffer = self.stack_offset + 40;
+        self.stack_offset += 40; // 32 bytes de texto + newline, alineado
+        self.text_section.push("    movq rcx, xmm0  ; float64 (bits)".to_string());
+        self.text_section.push(format!("    lea rdx, [rbp - {}]  ; buffer", buffer));
+        self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+        self.text_section.push("    call float_to_str".to_string());
+        self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+        self.text_section.push(format!("    lea rdx, [rbp - {}]  ; buffer", buffer));
+        if newline {
+            self.text_section.push("    mov byte [rdx + rax], 0xA  ; newline".to_string());
+            self.text_section.push("    lea r8, [rax + 1]  ; longitud + newline".to_string());
+        } else {
+            self.text_section.push("    mov r8, rax  ; longitud".to_string());
+        }
+        self.text_section.push("    mov rcx, [rbp+16]  ; stdout handle".to_string());
+        self.text_section.push("    lea r9, [rbp+24]  ; lpNumberOfBytesWritten".to_string());
+        self.text_section.push("    mov qword [rsp+32], 0  ; lpOverlapped = NULL".to_string());
+        self.text_section.push("    call WriteFile".to_string());
+    }
+
+    /// Métodos de dict: d.get(k), d.get(k, defecto), d.keys(), d.values(), d.items(),
+    /// d.len(), d.contains(k) y d.remove(k)
+    fn generate_dict_method(&mut self, expr: &Expr, object: &Expr, method: &str, args: &[Spanned<Expr>]) -> Result<()> {
+        match (method, args) {
+            ("get", [key]) => self.emit_runtime_call("dict_get", &[object, key], Some(0))?,
+            ("get", [key, default]) => self.emit_runtime_call("dict_get", &[object, key, default], None)?,
+            ("keys", []) => self.emit_runtime_call("dict_keys", &[object], None)?,
+            ("values", []) => self.emit_runtime_call("dict_values", &[object], None)?,
+            ("items", []) => self.emit_runtime_call("dict_items", &[object], None)?,
+            ("len", []) => self.emit_runtime_call("dict_len", &[object], None)?,
+            ("contains", [key]) => self.emit_runtime_call("dict_contains", &[object, key], None)?,
+            ("remove", [key]) => self.emit_runtime_call("dict_delete", &[object, key], None)?,
+            _ => {
+                return Err(adead_common::ADeadError::RuntimeError {
+                    message: format!("método de dict no soportado: {}() con {} argumentos", method, args.len()),
+                });
+            }
+        }
+        self.float_result_if_needed(expr);
+        Ok(())
+    }
+
+    /// `for i in start..end` / `range(start, end, step)`
+    fn generate_for_range(&mut self, var: &str, start: &Expr, end: &Expr, step: Option<&Spanned<Expr>>, body: &[Spanned<Stmt>],
+                          loop_start: &str, loop_continue: &str, loop_end: &str) -> Result<()> {
+        self.generate_range_loop(var, start, end, step, loop_start, loop_continue, loop_end, |gen| {
+            body.iter().try_for_each(|s| gen.generate_spanned_stmt_windows(s))
+        })
+    }
+
+    /// Bucle sobre un rango: el contador, el fin y el paso viven en slots del frame (el cuerpo
+    /// puede llamar a cualquier función). Con un paso literal el sentido de la comparación se
+    /// decide al compilar; si no, según el signo en runtime. Lo usan el `for`, las
+    /// comprehensions y `range(...)` como valor
+    fn generate_range_loop(&mut self, var: &str, start: &Expr, end: &Expr, step: Option<&Spanned<Expr>>,
+                           loop_start: &str, loop_continue: &str, loop_end: &str,
+                           body: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let var_offset = self.alloc_temp_slot();
+        let end_offset = self.alloc_temp_slot();
+        
+        // Evaluar start y guardar en variable
+        self.generate_expr_windows(start)?;
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; {} (loop counter)", var_offset + 8, var));
+        self.variables.insert(var.to_string(), var_offset);
+        
+        // Evaluar end y guardar en su slot
+        self.generate_expr_windows(end)?;
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; end value", end_offset + 8));
+        
+        let step_offset = match step.map(|step| &step.node) {
+            None | Some(Expr::Number(_)) => None,
+            Some(step) => {
+                let offset = self.alloc_temp_slot();
+                self.generate_expr_windows(step)?;
+                self.text_section.push(format!("    mov [rbp - {}], rax  ; step", offset + 8));
+                Some(offset)
+            }
+        };
+        let step_value = match step.map(|step| &step.node) {
+            Some(Expr::Number(n)) => *n,
+            _ => 1,
+        };
+        
+        // Loop start: comparar variable con end según el sentido del recorrido
+        self.text_section.push(format!("{}:", loop_start));
+        self.text_section.push(format!("    mov rax, [rbp - {}]  ; cargar {}", var_offset + 8, var));
+        match step_offset {
+            Some(offset) => {
+                let down_label = self.new_label("for_down");
+                let body_label = self.new_label("for_body");
+                self.text_section.push(format!("    cmp qword [rbp - {}], 0  ; signo del step", offset + 8));
+                self.text_section.push(format!("    jl {}", down_label));
+                self.text_section.push(format!("    cmp rax, [rbp - {}]  ; comparar con end", end_offset + 8));
+                self.text_section.push(format!("    jge {}  ; si >= end, salir", loop_end));
+                self.text_section.push(format!("    jmp {}", body_label));
+                self.text_section.push(format!("{}:", down_label));
+                self.text_section.push(format!("    cmp rax, [rbp - {}]  ; comparar con end", end_offset + 8));
+                self.text_section.push(format!("    jle {}  ; si <= end, salir", loop_end));
+                self.text_section.push(format!("{}:", body_label));
+            }
+            None => {
+                self.text_section.push(format!("    cmp rax, [rbp - {}]  ; comparar con end", end_offset + 8));
+                if step_value < 0 {
+                    self.text_section.push(format!("    jle {}  ; si <= end, salir (rango descendente)", loop_end));
+                } else {
+                    self.text_section.push(format!("    jge {}  ; si >= end, salir", loop_end));
+                }
+            }
+        }
+        
+        // Body del loop
+        body(self)?;
+        
+        // Continue label (para avanzar el contador)
+        self.text_section.push(format!("{}:", loop_continue));
+        self.text_section.push(format!("    mov rax, [rbp - {}]  ; cargar {}", var_offset + 8, var));
+        match step_offset {
+            Some(offset) => self.text_section.push(format!("    add rax, [rbp - {}]  ; sumar step", offset + 8)),
+            None if step_value == 1 => self.text_section.push("    inc rax  ; incrementar".to_string()),
+            None => self.text_section.push(format!("    add rax, {}  ; sumar step", step_value)),
+        }
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; guardar", var_offset + 8));
+        
+        // Saltar al inicio
+        self.text_section.push(format!("    jmp {}", loop_start));
+        self.text_section.push(format!("{}:", loop_end));
+        Ok(())
+    }
+
+    /// `for x in iterable`: arrays se recorren por índice, strings por carácter (string_slice
+    /// de 1 byte) y dicts/sets por sus claves (copiadas con dict_keys). `enumerate(a)` agrega el
+    /// índice, `zip(a, b)` avanza ambos hasta el más corto y varias variables sobre un solo
+    /// iterable desestructuran cada par (`d.items()`)
+    fn generate_for_iterable(&mut self, vars: &[String], iter: &ForIter, body: &[Spanned<Stmt>],
+                             loop_start: &str, loop_continue: &str, loop_end: &str) -> Result<()> {
+        let (sources, numbered) = iter.sources();
+        let index_offset = self.alloc_temp_slot();
+        
+        // Evaluar cada colección una sola vez y guardarla en su slot
+        let mut source_slots = Vec::new();
+        for source in &sources {
+            let offset = self.alloc_temp_slot();
+            self.generate_expr_windows(source)?;
+            if self.is_dict_expr(source) || self.is_set_expr(source) {
+                self.text_section.push("    mov rcx, rax  ; puntero al Dict".to_string());
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call dict_keys  ; recorrer una copia de las claves".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            }
+            self.text_section.push(format!("    mov [rbp - {}], rax  ; colección iterada", offset + 8));
+            source_slots.push((offset, matches!(self.expr_type(source), Some(Type::String))));
+        }
+        self.text_section.push(format!("    mov qword [rbp - {}], 0  ; índice", index_offset + 8));
+        
+        let var_offsets: Vec<i64> = vars.iter().map(|_| self.alloc_temp_slot()).collect();
+        for (var, &offset) in vars.iter().zip(&var_offsets) {
+            self.variables.insert(var.clone(), offset);
+        }
+        
+        // Loop start: salir cuando el índice llega a la length de cualquiera de las colecciones
+        // (Array y String guardan la length en [ptr + 8])
+        self.text_section.push(format!("{}:", loop_start));
+        self.text_section.push(format!("    mov rcx, [rbp - {}]  ; índice", index_offset + 8));
+        for &(offset, _) in &source_slots {
+            self.text_section.push(format!("    mov rax, [rbp - {}]", offset + 8));
+            self.text_section.push("    cmp rcx, [rax + 8]  ; comparar con length".to_string());
+            self.text_section.push(format!("    jge {}", loop_end));
+        }
+        
+        // Enlazar las variables de la iteración
+        let mut targets = var_offsets.iter().zip(vars);
+        if numbered {
+            let (offset, var) = targets.next().unwrap();
+            self.text_section.push(format!("    mov [rbp - {}], rcx  ; {} (índice)", offset + 8, var));
+        }
+        for &(offset, is_string) in &source_slots {
+            if is_string {
+                self.text_section.push(format!("    mov rcx, [rbp - {}]  ; String", offset + 8));
+                self.text_section.push(format!("    mov rdx, [rbp - {}]  ; inicio", index_offset + 8));
+                self.text_section.push("    lea r8, [rdx + 1]  ; fin (un carácter)".to_string());
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call string_slice".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            } else {
+                self.text_section.push(format!("    mov rax, [rbp - {}]  ; Array", offset + 8));
+                self.text_section.push("    mov rax, [rax]  ; data".to_string());
+                self.text_section.push(format!("    mov rcx, [rbp - {}]  ; índice", index_offset + 8));
+                self.text_section.push("    mov rax, [rax + rcx*8]  ; elemento".to_string());
+            }
+            if sources.len() == 1 && !numbered && vars.len() > 1 {
+                // Destructurar el par [a, b] del elemento
+                for (i, (offset, var)) in targets.by_ref().enumerate() {
+                    self.text_section.push(format!("    mov rcx, [rax + {}]", i * 8));
+                    self.text_section.push(format!("    mov [rbp - {}], rcx  ; {}", offset + 8, var));
+                }
+            } else if let Some((offset, var)) = targets.next() {
+                self.text_section.push(format!("    mov [rbp - {}], rax  ; {}", offset + 8, var));
+            }
+        }
+        
+        // Body del loop
+        for s in body {
+            self.generate_spanned_stmt_windows(s)?;
+        }
+        
+        // Continue label (para avanzar el índice)
+        self.text_section.push(format!("{}:", loop_continue));
+        self.text_section.push(format!("    inc qword [rbp - {}]  ; siguiente elemento", index_offset + 8));
+        self.text_section.push(format!("    jmp {}", loop_start));
+        self.text_section.push(format!("{}:", loop_end));
+        Ok(())
+    }
+
+    /// Comprehension de list, set o dict: el resultado se construye en heap (array_new/array_append
+    /// o dict_new/dict_set) y vive en un slot del frame, así que no tiene límite de tamaño y
+    /// puede retornarse desde una función
+    fn generate_comprehension(&mut self, target: ComprehensionTarget, clauses: &[ComprehensionClause]) -> Result<()> {
+        let result_slot = self.stack_offset;
+        self.stack_offset += 8;
+        self.text_section.push(format!("    ; Comprehension: {} cláusula(s) for", clauses.len()));
+        match target {
+            ComprehensionTarget::List(_) => {
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call array_new".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            }
+            ComprehensionTarget::Set(_, key_kind) | ComprehensionTarget::Dict(_, _, key_kind) => {
+                self.text_section.push("    mov rcx, 0  ; entradas previstas".to_string());
+                self.text_section.push(format!("    mov rdx, {}  ; tipo de clave", key_kind));
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call dict_new".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            }
+        }
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; resultado de la comprehension", result_slot + 8));
+        
+        // Las variables de iteración solo existen dentro de la comprehension
+        let saved_vars = self.variables.clone();
+        let result = self.generate_comprehension_clauses(&target, clauses, result_slot);
+        self.variables = saved_vars;
+        result?;
+        
+        self.text_section.push(format!("    mov rax, [rbp - {}]  ; retornar resultado", result_slot + 8));
+        Ok(())
+    }
+
+    /// Bucle de la primera cláusula con las siguientes anidadas dentro. El array, el índice y la
+    /// variable viven en slots del frame: el cuerpo puede llamar a cualquier función
+    fn generate_comprehension_clauses(&mut self, target: &ComprehensionTarget, clauses: &[ComprehensionClause], result_slot: i64) -> Result<()> {
+        let (clause, rest) = match clauses.split_first() {
+            Some(split) => split,
+            None => return self.generate_comprehension_item(target, result_slot),
+        };
+        // `for x in range(...)`: bucle con contador, sin construir el array del rango
+        if let ForIter::Range { start, end, step } = ForIter::from_iterable(clause.iter.as_ref().clone()) {
+            let loop_label = self.new_label("comp_loop");
+            let next_label = self.new_label("comp_next");
+            let end_label = self.new_label("comp_end");
+            return self.generate_range_loop(&clause.var, &start, &end, step.as_ref(), &loop_label, &next_label, &end_label, |gen| {
+                for condition in &clause.conditions {
+                    gen.generate_expr_windows(condition)?;
+                    gen.text_section.push("    cmp rax, 0".to_string());
+                    gen.text_section.push(format!("    je {}", next_label));
+                }
+                gen.generate_comprehension_clauses(target, rest, result_slot)
+            });
+        }
+        let array_slot = self.stack_offset;
+        let index_slot = array_slot + 8;
+        let var_slot = array_slot + 16;
+        self.stack_offset += 24;
+        
+        self.generate_expr_windows(&clause.iter)?;
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; array iterado", array_slot + 8));
+        self.text_section.push(format!("    mov qword [rbp - {}], 0  ; índice", index_slot + 8));
+        self.variables.insert(clause.var.clone(), var_slot);
+        
+        let loop_label = self.new_label("comp_loop");
+        let next_label = self.new_label("comp_next");
+        let end_label = self.new_label("comp_end");
+        self.text_section.push(format!("{}:", loop_label));
+        self.text_section.push(format!("    mov rbx, [rbp - {}]  ; array", array_slot + 8));
+        self.text_section.push(format!("    mov rcx, [rbp - {}]  ; índice", index_slot + 8));
+        self.text_section.push("    cmp rcx, [rbx + 8]  ; comparar con length".to_string());
+        self.text_section.push(format!("    jge {}", end_label));
+        self.text_section.push("    mov rbx, [rbx]  ; data".to_string());
+        self.text_section.push("    mov rax, [rbx + rcx*8]".to_string());
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; {}", var_slot + 8, clause.var));
+        
+        for condition in &clause.conditions {
+            self.generate_expr_windows(condition)?;
+            self.text_section.push("    cmp rax, 0".to_string());
+            self.text_section.push(format!("    je {}", next_label));
+        }
+        
+        self.generate_comprehension_clauses(target, rest, result_slot)?;
+        
+        self.text_section.push(format!("{}:", next_label));
+        self.text_section.push(format!("    inc qword [rbp - {}]", index_slot + 8));
+        self.text_section.push(format!("    jmp {}", loop_label));
+        self.text_section.push(format!("{}:", end_label));
+        Ok(())
+    }
+
+    /// `range(...)` como valor: Array en heap con los enteros del rango
+    fn generate_range_array(&mut self, start: &Expr, end: &Expr, step: Option<&Spanned<Expr>>) -> Result<()> {
+        let array_slot = self.alloc_temp_slot();
+        self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+        self.text_section.push("    call array_new".to_string());
+        self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; array del range", array_slot + 8));
+        
+        // El contador es interno: su nombre no choca con variables del programa
+        let counter = "#range";
+        let saved_vars = self.variables.clone();
+        let loop_label = self.new_label("range_loop");
+        let next_label = self.new_label("range_next");
+        let end_label = self.new_label("range_end");
+        let result = self.generate_range_loop(counter, start, end, step, &loop_label, &next_label, &end_label, |gen| {
+            let counter_offset = gen.variables[counter];
+            gen.text_section.push(format!("    mov rcx, [rbp - {}]  ; puntero al Array", array_slot + 8));
+            gen.text_section.push(format!("    mov rdx, [rbp - {}]  ; valor", counter_offset + 8));
+            gen.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+            gen.text_section.push("    call array_append".to_string());
+            gen.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            Ok(())
+        });
+        self.variables = saved_vars;
+        result?;
+        
+        self.text_section.push(format!("    mov rax, [rbp - {}]  ; retornar array", array_slot + 8));
+        Ok(())
+    }
+
+    /// Agregar el elemento de la iteración actual al resultado
+    fn generate_comprehension_item(&mut self, target: &ComprehensionTarget, result_slot: i64) -> Result<()> {
+        self.text_section.push(format!("    push qword [rbp - {}]  ; resultado", result_slot + 8));
+        match *target {
+            ComprehensionTarget::List(element) => {
+                self.generate_expr_windows(element)?;
+                self.float_to_gpr_if_needed(element);
+                self.text_section.push("    mov rdx, rax  ; valor".to_string());
+                self.text_section.push("    mov rcx, [rsp]  ; puntero al Array".to_string());
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call array_append".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            }
+            ComprehensionTarget::Set(element, key_kind) => {
+                self.generate_expr_windows(element)?;
+                self.emit_set_add_top(key_kind);
+            }
+            ComprehensionTarget::Dict(key, value, key_kind) => {
+                self.generate_expr_windows(key)?;
+                self.text_section.push("    push rax  ; clave".to_string());
+                self.generate_expr_windows(value)?;
+                self.float_to_gpr_if_needed(value);
+                self.text_section.push("    mov r8, rax  ; valor".to_string());
+                self.text_section.push("    pop rdx  ; clave".to_string());
+                self.text_section.push("    mov rcx, [rsp]  ; puntero al Dict".to_string());
+                self.text_section.push(format!("    mov r9, {}  ; tipo de clave", key_kind));
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call dict_set".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            }
+        }
+        self.text_section.push("    add rsp, 8".to_string());
+        Ok(())
+    }
+
+    /// Agregar RAX al set que está en [rsp] (dict_set con valor 1); el set sigue en la pila
+    fn emit_set_add_top(&mut self, key_kind: i64) {
+        self.text_section.push("    mov rdx, rax  ; elemento".to_string());
+        self.text_section.push("    mov rcx, [rsp]  ; puntero al Set".to_string());
+        self.text_section.push("    mov r8, 1  ; valor presente".to_string());
+        self.text_section.push(format!("    mov r9, {}  ; tipo de elemento", key_kind));
+        self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+        self.text_section.push("    call dict_set".to_string());
+        self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+    }
+
+    /// Métodos de set: s.add(x), s.remove(x), s.contains(x), s.len(),
+    /// s.union(t), s.intersection(t) y s.difference(t)
+    fn generate_set_method(&mut self, object: &Expr, method: &str, args: &[Spanned<Expr>]) -> Result<()> {
+        match (method, args) {
+            ("add", [element]) => {
+                let key_kind = self.dict_key_kind(object, Some(element));
+                self.generate_expr_windows(object)?;
+                self.text_section.push("    push rax  ; puntero al Set".to_string());
+                self.generate_expr_windows(element)?;
+                self.emit_set_add_top(key_kind);
+                self.text_section.push("    add rsp, 8".to_string());
+            }
+            ("remove", [element]) => self.emit_runtime_call("dict_delete", &[object, element], None)?,
+            ("contains", [element]) => self.emit_runtime_call("dict_contains", &[object, element], None)?,
+            ("len", []) => self.emit_runtime_call("dict_len", &[object], None)?,
+            ("union", [other]) => self.emit_runtime_call("set_union", &[object, other], None)?,
+            ("intersection", [other]) => self.emit_runtime_call("set_intersection", &[object, other], None)?,
+            ("difference", [other]) => self.emit_runtime_call("set_difference", &[object, other], None)?,
+            _ => {
+                return Err(adead_common::ADeadError::RuntimeError {
+                    message: format!("método de set no soportado: {}() con {} argumentos", method, args.len()),
+                });
+            }
+        }
+        Ok(())
+    }
+
     fn is_string_expr(&self, expr: &Expr) -> bool {
+        if let Some(ty) = self.expr_type(expr) {
+            return *ty == Type::String;
+        }
         match expr {
             Expr::String(_) => true,
             Expr::Ident(name) => {
@@ -5441,7 +4503,7 @@ impl CodeGenerator {
             Expr::MethodCall { object, method, args: _ } => {
                 // Si el objeto es String, entonces es string method
                 // También métodos de strings retornan strings
-                self.is_string_expr(object) || matches!(method.as_str(), "upper" | "lower" | "slice" | "substring")
+                self.is_string_expr(object) || matches!(method.as_str(), "upper" | "lower" | "strip" | "replace" | "join" | "slice" | "substring")
             }
             Expr::BinaryOp { left, op: BinOp::Add, right } => {
                 // Concatenación: si ambos operandos son strings
@@ -5453,11 +4515,11 @@ impl CodeGenerator {
                 self.is_string_expr(object)
             }
             Expr::Call { module: _, name, args } => {
-                // len(s) donde s es string
+                // len(s) donde s es string; los built-ins de strings retornan un String nuevo
                 if name == "len" && args.len() == 1 {
                     self.is_string_expr(&args[0])
                 } else {
-                    false
+                    matches!(name.as_str(), "upper" | "lower" | "strip" | "replace" | "join")
                 }
             }
             _ => false,
@@ -5466,6 +4528,9 @@ impl CodeGenerator {
     
     // Helper para detectar si una expresión es booleana
     fn is_bool_expr(&self, expr: &Expr) -> bool {
+        if let Some(ty) = self.expr_type(expr) {
+            return *ty == Type::Bool;
+        }
         match expr {
             Expr::Bool(_) => true,
             Expr::Ident(name) => {
@@ -5496,6 +4561,10 @@ impl CodeGenerator {
                 if let Some(type_name) = self.variable_types.get(name) {
                     return Some(type_name.clone());
                 }
+                // Tipo inferido (p. ej. el valor de una función que retorna un struct)
+                if let Some(Type::Struct(type_name)) = self.expr_type(expr) {
+                    return Some(type_name.clone());
+                }
                 // Fallback: buscar en struct_definitions para ver si existe un struct con ese nombre
                 // Solo si el nombre de la variable coincide exactamente (case-insensitive) con el struct
                 for (struct_name, _) in &self.struct_definitions {
@@ -5506,7 +4575,12 @@ impl CodeGenerator {
                 None
             }
             Expr::StructLiteral { name, .. } => Some(name.clone()),
-            _ => None,
+            _ => match self.expr_type(expr) {
+                Some(Type::Struct(type_name)) if self.struct_definitions.contains_key(type_name) => {
+                    Some(type_name.clone())
+                }
+                _ => None,
+            },
         }
     }
     
@@ -5570,44 +4644,521 @@ impl CodeGenerator {
                     }
                     parts.push("0xD".to_string());
                 }
-                '\0' => {
-                    if !current.is_empty() {
-                        parts.push(format!("\"{}\"", current.replace('"', "\\\"")));
-                        current.clear();
-                    }
-                    parts.push("0x0".to_string());
+                '\0' => {
+                    if !current.is_empty() {
+                        parts.push(format!("\"{}\"", current.replace('"', "\\\"")));
+                        current.clear();
+                    }
+                    parts.push("0x0".to_string());
+                }
+                _ => current.push(c),
+            }
+        }
+        
+        // Agregar último segmento si existe
+        if !current.is_empty() {
+            parts.push(format!("\"{}\"", current.replace('"', "\\\"")));
+        }
+        
+        // Si no hay partes, crear string vacío
+        if parts.is_empty() {
+            parts.push("\"\"".to_string());
+        }
+        
+        // Generar línea db con todas las partes
+        self.data_section.push(format!(
+            "{}: db {}",
+            label, parts.join(", ")
+        ));
+        
+        self.data_section.push(format!(
+            "{}_len: equ $ - {}",
+            label, label
+        ));
+        label
+    }
+
+    fn new_label(&mut self, prefix: &str) -> String {
+        let label = format!("{}_{}", prefix, self.label_counter);
+        self.label_counter += 1;
+        label
+    }
+
+    /// Construir un tagged union en el heap (Option, Result y enums de usuario): puede
+    /// retornarse desde una función y evaluarse en un bucle sin crecer el stack.
+    /// Layout: [base] = tag, [base + 8 + 8*i] = valor i del payload (slots de 8 bytes).
+    /// Los slots sin valor quedan en 0. Deja en RAX la dirección base.
+    fn generate_tagged_union(
+        &mut self,
+        tag: i64,
+        variant: &str,
+        payload: &[&Expr],
+        slots: usize,
+        description: &str,
+    ) -> Result<()> {
+        let size = 8 * (slots as i64 + 1);
+        let slot = self.alloc_temp_slot();
+        self.text_section.push(format!("    ; espacio para {} en el heap ({} bytes)", description, size));
+        self.text_section.push(format!("    mov rcx, {}  ; tamaño en bytes", size));
+        self.emit_heap_alloc();
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; dirección del {}", slot + 8, variant));
+        for i in 0..slots {
+            let offset = 8 * (i + 1);
+            match payload.get(i) {
+                Some(value) => {
+                    self.generate_expr_windows(value)?;
+                    self.text_section.push(format!("    mov rcx, [rbp - {}]  ; dirección del {}", slot + 8, variant));
+                    self.text_section.push(format!("    mov [rcx + {}], rax  ; valor {}", offset, i));
+                }
+                None => {
+                    self.text_section.push(format!("    mov rcx, [rbp - {}]  ; dirección del {}", slot + 8, variant));
+                    self.text_section.push(format!("    mov qword [rcx + {}], 0  ; valor = 0", offset));
+                }
+            }
+        }
+        self.text_section.push(format!("    mov rcx, [rbp - {}]  ; dirección del {}", slot + 8, variant));
+        self.text_section.push(format!("    mov rax, {}  ; tag {} = {}", tag, variant, tag));
+        self.text_section.push(format!("    mov [rcx], rax  ; tag = {} ({})", tag, variant));
+        self.text_section.push("    mov rax, rcx  ; retornar dirección".to_string());
+        Ok(())
+    }
+
+    /// Crear la closure de `lambda params: body` (RAX = puntero a la closure)
+    /// - El cuerpo se emite como función fuera de línea (saltada con jmp) que recibe el
+    ///   entorno en R10, fuera de los registros de argumentos de ambas convenciones
+    /// - Las variables del frame que usa el cuerpo se copian a un entorno en el heap (captura
+    ///   por valor): la closure puede sobrevivir al frame donde se creó
+    /// - Layout de la closure (16 bytes en el heap): [+0] código, [+8] entorno (0 sin capturas)
+    fn generate_closure(&mut self, params: &[String], body: &Expr) -> Result<()> {
+        let captured: Vec<(String, i64)> = captures::free_names(params, body)
+            .into_iter()
+            .filter_map(|name| self.variables.get(&name).map(|&offset| (name, offset)))
+            .collect();
+        let lambda_name = self.new_label("lambda");
+
+        // Función de la lambda: frame propio, solo ve sus parámetros y las capturas
+        let saved_vars = std::mem::take(&mut self.variables);
+        let saved_offset = self.stack_offset;
+        self.text_section.push(format!("    jmp {}_end  ; saltar definición de lambda", lambda_name));
+        self.text_section.push(format!("{}:", lambda_name));
+        self.text_section.push("    push rbp".to_string());
+        self.text_section.push("    mov rbp, rsp".to_string());
+        let frame_line = self.text_section.len();
+        self.text_section.push(String::new()); // `sub rsp` cuando se conozca el tamaño del frame
+        self.stack_offset = 0;
+        for (i, param) in params.iter().enumerate() {
+            self.store_incoming_param(i, param);
+        }
+        for (i, (name, _)) in captured.iter().enumerate() {
+            let offset = self.alloc_temp_slot();
+            self.variables.insert(name.clone(), offset);
+            self.text_section.push(format!("    mov rax, [r10 + {}]  ; captura {}", i * 8, name));
+            self.text_section.push(format!("    mov [rbp - {}], rax", offset + 8));
+        }
+        let result = self.generate_expr_windows(body);
+        let locals = (self.stack_offset + 15) / 16 * 16;
+        self.text_section[frame_line] = format!("    sub rsp, {}  ; locales ({}) + shadow space", locals + 32, locals);
+        self.text_section.push("    leave".to_string());
+        self.text_section.push("    ret".to_string());
+        self.text_section.push(format!("{}_end:", lambda_name));
+        self.variables = saved_vars;
+        self.stack_offset = saved_offset;
+        result?;
+
+        // Entorno con una copia de cada captura
+        let env_slot = self.alloc_temp_slot();
+        if captured.is_empty() {
+            self.text_section.push(format!("    mov qword [rbp - {}], 0  ; closure sin capturas", env_slot + 8));
+        } else {
+            self.text_section.push(format!("    mov rcx, {}  ; entorno: {} captura(s)", captured.len() * 8, captured.len()));
+            self.emit_heap_alloc();
+            self.text_section.push(format!("    mov [rbp - {}], rax  ; entorno", env_slot + 8));
+            for (i, (name, offset)) in captured.iter().enumerate() {
+                self.text_section.push(format!("    mov rax, [rbp - {}]  ; capturar {}", offset + 8, name));
+                self.text_section.push(format!("    mov rcx, [rbp - {}]", env_slot + 8));
+                self.text_section.push(format!("    mov [rcx + {}], rax", i * 8));
+            }
+        }
+        self.text_section.push("    mov rcx, 16  ; closure: código + entorno".to_string());
+        self.emit_heap_alloc();
+        self.text_section.push(format!("    lea rcx, [rel {}]", lambda_name));
+        self.text_section.push("    mov [rax], rcx  ; código".to_string());
+        self.text_section.push(format!("    mov rcx, [rbp - {}]", env_slot + 8));
+        self.text_section.push("    mov [rax + 8], rcx  ; entorno".to_string());
+        Ok(())
+    }
+
+    /// heap_alloc(RCX = tamaño) → RAX; el allocator usa la convención Windows en ambos targets
+    fn emit_heap_alloc(&mut self) {
+        self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
+        self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+    }
+
+    /// `map(f, arr)`, `filter(f, arr)` y `reduce(f, arr, init)`: recorrer el array llamando a
+    /// la closure `f` en cada elemento. map y filter construyen un array nuevo
+    fn generate_higher_order(&mut self, builtin: &str, args: &[Spanned<Expr>]) -> Result<()> {
+        let func_slot = self.alloc_temp_slot();
+        let array_slot = self.alloc_temp_slot();
+        let index_slot = self.alloc_temp_slot();
+        let element_slot = self.alloc_temp_slot();
+        let result_slot = self.alloc_temp_slot();
+        self.text_section.push(format!("    ; {}(f, arr) con llamada indirecta a la closure", builtin));
+        self.generate_expr_windows(&args[0])?;
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; closure", func_slot + 8));
+        self.generate_expr_windows(&args[1])?;
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; array", array_slot + 8));
+        if builtin == "reduce" {
+            self.generate_expr_windows(&args[2])?;
+            self.text_section.push(format!("    mov [rbp - {}], rax  ; acumulador (init)", result_slot + 8));
+        } else {
+            self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+            self.text_section.push("    call array_new".to_string());
+            self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            self.text_section.push(format!("    mov [rbp - {}], rax  ; resultado", result_slot + 8));
+        }
+        self.text_section.push(format!("    mov qword [rbp - {}], 0  ; índice", index_slot + 8));
+
+        let loop_label = self.new_label(&format!("{}_loop", builtin));
+        let next_label = self.new_label(&format!("{}_next", builtin));
+        let end_label = self.new_label(&format!("{}_end", builtin));
+        self.text_section.push(format!("{}:", loop_label));
+        self.text_section.push(format!("    mov rax, [rbp - {}]  ; array", array_slot + 8));
+        self.text_section.push(format!("    mov rcx, [rbp - {}]  ; índice", index_slot + 8));
+        self.text_section.push("    cmp rcx, [rax + 8]  ; comparar con length".to_string());
+        self.text_section.push(format!("    jge {}", end_label));
+        self.text_section.push("    mov rax, [rax]  ; data".to_string());
+        self.text_section.push("    mov rax, [rax + rcx*8]".to_string());
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; elemento", element_slot + 8));
+
+        let element = CallArg::Asm(format!("mov rax, [rbp - {}]  ; elemento", element_slot + 8));
+        let call_args = if builtin == "reduce" {
+            vec![CallArg::Asm(format!("mov rax, [rbp - {}]  ; acumulador", result_slot + 8)), element]
+        } else {
+            vec![element]
+        };
+        self.emit_call(Callee::Closure(func_slot), &call_args, builtin)?;
+        match builtin {
+            "map" => {
+                self.text_section.push("    mov rdx, rax  ; valor".to_string());
+                self.text_section.push(format!("    mov rcx, [rbp - {}]  ; puntero al Array", result_slot + 8));
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call array_append".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            }
+            "filter" => {
+                self.text_section.push("    cmp rax, 0".to_string());
+                self.text_section.push(format!("    je {}  ; no cumple el predicado", next_label));
+                self.text_section.push(format!("    mov rdx, [rbp - {}]  ; elemento", element_slot + 8));
+                self.text_section.push(format!("    mov rcx, [rbp - {}]  ; puntero al Array", result_slot + 8));
+                self.text_section.push("    sub rsp, 32  ; shadow space".to_string());
+                self.text_section.push("    call array_append".to_string());
+                self.text_section.push("    add rsp, 32  ; restaurar shadow space".to_string());
+            }
+            _ => {
+                self.text_section.push(format!("    mov [rbp - {}], rax  ; nuevo acumulador", result_slot + 8));
+            }
+        }
+        self.text_section.push(format!("{}:", next_label));
+        self.text_section.push(format!("    inc qword [rbp - {}]", index_slot + 8));
+        self.text_section.push(format!("    jmp {}", loop_label));
+        self.text_section.push(format!("{}:", end_label));
+        self.text_section.push(format!("    mov rax, [rbp - {}]  ; resultado", result_slot + 8));
+        Ok(())
+    }
+
+    /// Reservar un slot de 8 bytes en el frame actual (temporales de match, bindings)
+    fn alloc_temp_slot(&mut self) -> i64 {
+        let offset = self.stack_offset;
+        self.stack_offset += 8;
+        offset
+    }
+
+    /// Patrón de match que solo depende del tag de un Option/Result:
+    /// devuelve (tag, variante, variable del payload si la hay)
+    fn simple_tag_pattern(pattern: &Pattern) -> Option<(i64, &'static str, Option<&String>)> {
+        let (tag, variant, payload) = match pattern {
+            Pattern::Some(payload) => (1, "Some", Some(payload)),
+            Pattern::None => (0, "None", None),
+            Pattern::Ok(payload) => (0, "Ok", Some(payload)),
+            Pattern::Err(payload) => (1, "Err", Some(payload)),
+            _ => return None,
+        };
+        match payload.map(|p| p.as_ref()) {
+            None | Some(Pattern::Wildcard) => Some((tag, variant, None)),
+            Some(Pattern::Ident(name)) => Some((tag, variant, Some(name))),
+            Some(_) => None,
+        }
+    }
+
+    /// Generar la comprobación de `pattern` contra el valor guardado en el slot `slot`.
+    /// Salta a `fail` si no encaja; si encaja sigue de largo con las variables del patrón
+    /// copiadas a sus slots (`bindings`: nombre → slot, compartido entre alternativas `|`).
+    /// Solo usa RAX, RCX, RSI y RDI.
+    fn generate_pattern_test(
+        &mut self,
+        pattern: &Pattern,
+        slot: i64,
+        fail: &str,
+        bindings: &mut HashMap<String, i64>,
+    ) -> Result<()> {
+        let load = format!("    mov rax, [rbp - {}]", slot + 8);
+        match pattern {
+            Pattern::Wildcard => {}
+            Pattern::Ident(name) => self.bind_pattern_var(name, slot, bindings),
+            Pattern::Binding { name, pattern } => {
+                self.bind_pattern_var(name, slot, bindings);
+                self.generate_pattern_test(pattern, slot, fail, bindings)?;
+            }
+            Pattern::LiteralNumber(n) => {
+                self.text_section.push(load);
+                self.emit_cmp_rax_imm(*n);
+                self.text_section.push(format!("    jne {}  ; patrón {}", fail, n));
+            }
+            Pattern::LiteralBool(b) => {
+                self.text_section.push(load);
+                self.text_section.push("    cmp rax, 0".to_string());
+                let jump = if *b { "je" } else { "jne" };
+                self.text_section.push(format!("    {} {}  ; patrón {}", jump, fail, b));
+            }
+            Pattern::Range { start, end, inclusive } => {
+                self.text_section.push(load);
+                self.emit_cmp_rax_imm(*start);
+                self.text_section.push(format!("    jl {}  ; menor que {}", fail, start));
+                self.emit_cmp_rax_imm(*end);
+                let (jump, op) = if *inclusive { ("jg", "..=") } else { ("jge", "..") };
+                self.text_section.push(format!("    {} {}  ; fuera del rango {}{}{}", jump, fail, start, op, end));
+            }
+            Pattern::LiteralString(text) => {
+                // String struct: [data, length, capacity, hash] - comparar longitud y bytes
+                let label = self.add_string_data(text);
+                self.text_section.push(load);
+                self.text_section.push(format!("    cmp qword [rax + 8], {}  ; longitud de \"{}\"", text.len(), text.escape_default()));
+                self.text_section.push(format!("    jne {}", fail));
+                if !text.is_empty() {
+                    self.text_section.push("    mov rsi, [rax]  ; String->data".to_string());
+                    self.text_section.push(format!("    lea rdi, [rel {}]", label));
+                    self.text_section.push(format!("    mov rcx, {}", text.len()));
+                    self.text_section.push("    repe cmpsb  ; comparar bytes".to_string());
+                    self.text_section.push(format!("    jne {}", fail));
+                }
+            }
+            Pattern::Some(inner) => self.generate_tag_test(slot, 1, "Some", Some(inner), fail, bindings)?,
+            Pattern::None => self.generate_tag_test(slot, 0, "None", None, fail, bindings)?,
+            Pattern::Ok(inner) => self.generate_tag_test(slot, 0, "Ok", Some(inner), fail, bindings)?,
+            Pattern::Err(inner) => self.generate_tag_test(slot, 1, "Err", Some(inner), fail, bindings)?,
+            Pattern::Tuple(items) => {
+                // tupla[i] está en [puntero - i*8] (ver TupleLiteral)
+                for (i, item) in items.iter().enumerate() {
+                    self.generate_sub_pattern_test(item, slot, -(i as i64 * 8), fail, bindings)?;
+                }
+            }
+            Pattern::Struct { name, fields } => {
+                let Some(all_fields) = self.struct_definitions.get(name).cloned() else {
+                    return Err(adead_common::ADeadError::RuntimeError {
+                        message: format!("Struct '{}' no definido en patrón de match", name),
+                    });
+                };
+                for (field, field_pattern) in fields {
+                    let Some(offset) = all_fields.contains(field).then(|| self.field_offset(name, field)).flatten() else {
+                        return Err(adead_common::ADeadError::RuntimeError {
+                            message: format!("El struct '{}' no tiene campo '{}'", name, field),
+                        });
+                    };
+                    // Mismo layout que FieldAccess: campo en [base + offset] (tras la vtable)
+                    self.generate_sub_pattern_test(field_pattern, slot, offset, fail, bindings)?;
+                }
+            }
+            Pattern::Variant { enum_name, variant, fields } => {
+                let tag = self
+                    .enum_definitions
+                    .get(enum_name)
+                    .and_then(|variants| variants.iter().position(|v| v.name == *variant))
+                    .unwrap_or(0) as i64;
+                self.text_section.push(format!("    mov rax, [rbp - {}]", slot + 8));
+                self.text_section.push(format!("    cmp qword [rax], {}  ; comparar tag con {}.{}", tag, enum_name, variant));
+                self.text_section.push(format!("    jne {}", fail));
+                for (i, field) in fields.iter().enumerate() {
+                    self.generate_sub_pattern_test(field, slot, 8 * (i as i64 + 1), fail, bindings)?;
+                }
+            }
+            Pattern::Or(alternatives) => {
+                let matched = self.new_label("match_or_ok");
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i + 1 == alternatives.len() {
+                        self.generate_pattern_test(alternative, slot, fail, bindings)?;
+                    } else {
+                        let next = self.new_label("match_or_next");
+                        self.generate_pattern_test(alternative, slot, &next, bindings)?;
+                        self.text_section.push(format!("    jmp {}", matched));
+                        self.text_section.push(format!("{}:", next));
+                    }
+                }
+                self.text_section.push(format!("{}:", matched));
+            }
+        }
+        Ok(())
+    }
+
+    /// Comparar el tag de un Option/Result y luego su payload (en [ptr + 8])
+    fn generate_tag_test(
+        &mut self,
+        slot: i64,
+        tag: i64,
+        variant: &str,
+        payload: Option<&Pattern>,
+        fail: &str,
+        bindings: &mut HashMap<String, i64>,
+    ) -> Result<()> {
+        self.text_section.push(format!("    mov rax, [rbp - {}]", slot + 8));
+        self.text_section.push(format!("    cmp qword [rax], {}  ; comparar tag con {}", tag, variant));
+        self.text_section.push(format!("    jne {}", fail));
+        if let Some(payload) = payload {
+            self.generate_sub_pattern_test(payload, slot, 8, fail, bindings)?;
+        }
+        Ok(())
+    }
+
+    /// Cargar `[valor + offset]` en un slot nuevo y comprobar ahí el sub-patrón
+    fn generate_sub_pattern_test(
+        &mut self,
+        pattern: &Pattern,
+        slot: i64,
+        offset: i64,
+        fail: &str,
+        bindings: &mut HashMap<String, i64>,
+    ) -> Result<()> {
+        if *pattern == Pattern::Wildcard {
+            return Ok(());
+        }
+        let sub_slot = self.alloc_temp_slot();
+        self.text_section.push(format!("    mov rax, [rbp - {}]", slot + 8));
+        if offset < 0 {
+            self.text_section.push(format!("    mov rax, [rax - {}]", -offset));
+        } else {
+            self.text_section.push(format!("    mov rax, [rax + {}]", offset));
+        }
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; sub-valor del patrón", sub_slot + 8));
+        self.generate_pattern_test(pattern, sub_slot, fail, bindings)
+    }
+
+    /// Copiar el valor del slot a la variable `name` del patrón
+    fn bind_pattern_var(&mut self, name: &str, slot: i64, bindings: &mut HashMap<String, i64>) {
+        let target = match bindings.get(name) {
+            Some(&target) => target,
+            None => {
+                let target = self.alloc_temp_slot();
+                bindings.insert(name.to_string(), target);
+                target
+            }
+        };
+        self.text_section.push(format!("    mov rax, [rbp - {}]", slot + 8));
+        self.text_section.push(format!("    mov [rbp - {}], rax  ; binding {}", target + 8, name));
+    }
+
+    /// `cmp rax, n` (con un registro auxiliar si `n` no entra en 32 bits)
+    fn emit_cmp_rax_imm(&mut self, n: i64) {
+        if i32::try_from(n).is_ok() {
+            self.text_section.push(format!("    cmp rax, {}", n));
+        } else {
+            self.text_section.push(format!("    mov rcx, {}", n));
+            self.text_section.push("    cmp rax, rcx".to_string());
+        }
+    }
+
+    /// Llamar a una función, método, constructor o lambda del usuario con la convención
+    /// del target (Microsoft x64 o System V):
+    /// 1. Evalúa los argumentos de izquierda a derecha y los apila (así una llamada
+    ///    anidada en un argumento no pisa los registros de otro)
+    /// 2. Reserva shadow space + argumentos en stack, con padding para que el total
+    ///    movido sea múltiplo de 16 y RSP quede alineado igual que antes de la llamada
+    /// 3. Copia los argumentos extra al área de stack y carga los registros
+    fn emit_call(&mut self, callee: Callee, args: &[CallArg], comment: &str) -> Result<()> {
+        let regs = self.target.arg_registers();
+        let shadow = self.target.shadow_space();
+        let n = args.len();
+
+        for (i, arg) in args.iter().enumerate() {
+            match arg {
+                CallArg::Expr(expr) => {
+                    self.generate_expr_windows(expr)?;
+                    self.float_to_gpr_if_needed(expr);
+                }
+                CallArg::Asm(instr) => self.text_section.push(format!("    {}", instr)),
+                CallArg::Coerced(expr, ty) => {
+                    self.generate_expr_windows(expr)?;
+                    self.float_to_gpr_if_needed(expr);
+                    self.coerce_to(ty, expr);
                 }
-                _ => current.push(c),
             }
+            self.text_section.push(format!("    push rax  ; arg{}", i));
         }
-        
-        // Agregar último segmento si existe
-        if !current.is_empty() {
-            parts.push(format!("\"{}\"", current.replace('"', "\\\"")));
+
+        let stack_args = n.saturating_sub(regs.len());
+        let mut area = shadow + stack_args * 8;
+        if (area + n * 8) % 16 != 0 {
+            area += 8;
         }
-        
-        // Si no hay partes, crear string vacío
-        if parts.is_empty() {
-            parts.push("\"\"".to_string());
+        if area > 0 {
+            self.text_section.push(format!(
+                "    sub rsp, {}  ; shadow space ({}) + {} args en stack, alineado a 16",
+                area, shadow, stack_args
+            ));
         }
-        
-        // Generar línea db con todas las partes
-        self.data_section.push(format!(
-            "{}: db {}",
-            label, parts.join(", ")
-        ));
-        
-        self.data_section.push(format!(
-            "{}_len: equ $ - {}",
-            label, label
-        ));
-        label
+        // arg i quedó en [rsp + area + (n - 1 - i) * 8]
+        for k in 0..stack_args {
+            let i = regs.len() + k;
+            self.text_section.push(format!("    mov rax, [rsp + {}]", area + (n - 1 - i) * 8));
+            self.text_section.push(format!("    mov [rsp + {}], rax  ; arg{} en stack", shadow + k * 8, i));
+        }
+        for (i, reg) in regs.iter().enumerate().take(n) {
+            self.text_section.push(format!("    mov {}, [rsp + {}]  ; arg{}", reg, area + (n - 1 - i) * 8, i));
+        }
+
+        match callee {
+            Callee::Label(label) => {
+                self.text_section.push(format!("    call {}  ; {}", label, comment));
+            }
+            Callee::Virtual(offset) => {
+                self.text_section.push(format!("    mov rax, [{}]  ; cargar vtable_ptr desde [self + 0]", regs[0]));
+                self.text_section.push(format!("    mov rax, [rax + {}]  ; cargar puntero al método desde vtable[{}]", offset, offset / 8));
+                self.text_section.push(format!("    call rax  ; {}", comment));
+            }
+            Callee::TraitObject { vtable_slot, offset } => {
+                self.text_section.push(format!("    mov rax, [rbp - {}]  ; vtable del objeto de trait", vtable_slot + 8));
+                self.text_section.push(format!("    mov rax, [rax + {}]  ; cargar puntero al método desde vtable[{}]", offset, offset / 8));
+                self.text_section.push(format!("    call rax  ; {}", comment));
+            }
+            Callee::Closure(slot) => {
+                self.text_section.push(format!("    mov rax, [rbp - {}]  ; closure", slot + 8));
+                self.text_section.push("    mov r10, [rax + 8]  ; entorno".to_string());
+                self.text_section.push(format!("    call qword [rax]  ; {}", comment));
+            }
+        }
+
+        if area + n * 8 > 0 {
+            self.text_section.push(format!("    add rsp, {}  ; liberar args y shadow space", area + n * 8));
+        }
+        Ok(())
     }
 
-    fn new_label(&mut self, prefix: &str) -> String {
-        let label = format!("{}_{}", prefix, self.label_counter);
-        self.label_counter += 1;
-        label
+    /// Guardar en un slot local el parámetro `index` de la función que se está generando.
+    /// Los primeros llegan en los registros del target; el resto está en el stack del
+    /// caller, en `[rbp + 16 + shadow + k*8]` (16 = rbp guardado + dirección de retorno)
+    fn store_incoming_param(&mut self, index: usize, name: &str) -> i64 {
+        let offset = self.stack_offset;
+        self.stack_offset += 8;
+        self.variables.insert(name.to_string(), offset);
+
+        let regs = self.target.arg_registers();
+        if let Some(reg) = regs.get(index) {
+            self.text_section.push(format!("    mov [rbp - {}], {}  ; guardar param{}: {}", offset + 8, reg, index, name));
+        } else {
+            let caller_offset = 16 + self.target.shadow_space() + (index - regs.len()) * 8;
+            self.text_section.push(format!("    mov rax, [rbp + {}]  ; cargar param{} desde stack del caller", caller_offset, index));
+            self.text_section.push(format!("    mov [rbp - {}], rax  ; guardar param{}: {}", offset + 8, index, name));
+        }
+        offset
     }
 
     /// Generar prologue ABI-safe para funciones helper
@@ -5725,6 +5276,50 @@ impl CodeGenerator {
         self.text_section.push("    call ExitProcess".to_string());
         self.text_section.push("".to_string());
         
+        // panic_match_failed: ningún brazo del match coincidió con el valor
+        self.text_section.push("panic_match_failed:".to_string());
+        self.text_section.push("    push rbp".to_string());
+        self.text_section.push("    mov rbp, rsp".to_string());
+        self.text_section.push("    sub rsp, 64".to_string());
+        
+        self.text_section.push("    mov ecx, -11".to_string());
+        self.text_section.push("    call GetStdHandle".to_string());
+        self.text_section.push("    mov r12, rax".to_string());
+        
+        self.text_section.push("    mov rcx, r12".to_string());
+        self.text_section.push("    lea rdx, [rel panic_msg_match_failed]".to_string());
+        self.text_section.push("    mov r8, panic_msg_match_failed_len".to_string());
+        self.text_section.push("    lea r9, [rbp - 8]".to_string());
+        self.text_section.push("    mov qword [r9], 0".to_string());
+        self.text_section.push("    mov qword [rsp + 32], 0".to_string());
+        self.text_section.push("    call WriteFile".to_string());
+        
+        self.text_section.push("    mov ecx, 1".to_string());
+        self.text_section.push("    call ExitProcess".to_string());
+        self.text_section.push("".to_string());
+        
+        // panic_key_not_found: d[clave] con una clave que no está en el dict
+        self.text_section.push("panic_key_not_found:".to_string());
+        self.text_section.push("    push rbp".to_string());
+        self.text_section.push("    mov rbp, rsp".to_string());
+        self.text_section.push("    sub rsp, 64".to_string());
+        
+        self.text_section.push("    mov ecx, -11".to_string());
+        self.text_section.push("    call GetStdHandle".to_string());
+        self.text_section.push("    mov r12, rax".to_string());
+        
+        self.text_section.push("    mov rcx, r12".to_string());
+        self.text_section.push("    lea rdx, [rel panic_msg_key_not_found]".to_string());
+        self.text_section.push("    mov r8, panic_msg_key_not_found_len".to_string());
+        self.text_section.push("    lea r9, [rbp - 8]".to_string());
+        self.text_section.push("    mov qword [r9], 0".to_string());
+        self.text_section.push("    mov qword [rsp + 32], 0".to_string());
+        self.text_section.push("    call WriteFile".to_string());
+        
+        self.text_section.push("    mov ecx, 1".to_string());
+        self.text_section.push("    call ExitProcess".to_string());
+        self.text_section.push("".to_string());
+        
         // Agregar mensajes de error en data section
         self.data_section.push("".to_string());
         self.data_section.push("; Mensajes de error para panic system".to_string());
@@ -5734,6 +5329,12 @@ impl CodeGenerator {
         self.data_section.push("panic_msg_null_pointer: db \"Error: Null pointer dereference\", 0xA, 0".to_string());
         self.data_section.push("panic_msg_null_pointer_len equ $ - panic_msg_null_pointer".to_string());
         self.data_section.push("".to_string());
+        self.data_section.push("panic_msg_match_failed: db \"Error: No match arm matched the value\", 0xA, 0".to_string());
+        self.data_section.push("panic_msg_match_failed_len equ $ - panic_msg_match_failed".to_string());
+        self.data_section.push("".to_string());
+        self.data_section.push("panic_msg_key_not_found: db \"Error: Key not found in dict\", 0xA, 0".to_string());
+        self.data_section.push("panic_msg_key_not_found_len equ $ - panic_msg_key_not_found".to_string());
+        self.data_section.push("".to_string());
     }
     
     /// Generar funciones helper de Array en NASM (versión completa - todas las funciones)
@@ -5775,16 +5376,13 @@ impl CodeGenerator {
         // Retorna: RAX = puntero al Array (en heap)
         if deps.should_generate("array_new") {
         self.text_section.push("array_new:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
         
         // Allocar memoria para Array (24 bytes)
         self.text_section.push("    ; Allocar memoria para Array (24 bytes)".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc");
-        self.text_section.push("    mov rcx, 0  ; lpAddress (NULL = auto)".to_string());
-        self.text_section.push("    mov rdx, 24  ; dwSize (24 bytes para Array struct)".to_string());
-        self.text_section.push("    mov r8, 0x1000  ; flAllocationType (MEM_COMMIT)".to_string());
-        self.text_section.push("    mov r9, 0x04  ; flProtect (PAGE_READWRITE)".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc");
+        self.text_section.push("    mov rcx, 24  ; 24 bytes para Array struct".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         
         // Inicializar Array: length=0, capacity=4, data=NULL (se asignará después)
         self.text_section.push("    ; Inicializar Array".to_string());
@@ -5795,12 +5393,9 @@ impl CodeGenerator {
         // Allocar memoria para data (capacity * 8 bytes)
         self.text_section.push("    ; Allocar memoria para data (capacity * 8 bytes = 32 bytes)".to_string());
         self.text_section.push("    mov rbx, rax  ; guardar puntero al Array en rbx (preservado)".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (segunda llamada)");
-        self.text_section.push("    mov rcx, 0  ; lpAddress".to_string());
-        self.text_section.push("    mov rdx, 32  ; dwSize (4 elementos * 8 bytes)".to_string());
-        self.text_section.push("    mov r8, 0x1000  ; MEM_COMMIT".to_string());
-        self.text_section.push("    mov r9, 0x04  ; PAGE_READWRITE".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (segunda llamada)");
+        self.text_section.push("    mov rcx, 32  ; 4 elementos * 8 bytes".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         
         // Asignar data al Array
         self.text_section.push("    mov [rbx + 0], rax  ; data = puntero a memoria".to_string());
@@ -5816,7 +5411,7 @@ impl CodeGenerator {
         // Parámetros: RCX = count, RDX = puntero a valores (int64_t*)
         // Retorna: RAX = puntero al Array
         self.text_section.push("array_from_values:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
         self.text_section.push("    mov r12, rcx  ; guardar count en r12 (preservado)".to_string());
         self.text_section.push("    mov r13, rdx  ; guardar puntero a valores en r13 (preservado)".to_string());
         
@@ -5832,24 +5427,18 @@ impl CodeGenerator {
         
         // Allocar memoria para Array (24 bytes)
         self.text_section.push("    ; Allocar memoria para Array".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (Array struct)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, 24".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (Array struct)");
+        self.text_section.push("    mov rcx, 24".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov r15, rax  ; guardar puntero al Array en r15 (preservado)".to_string());
         
         // Allocar memoria para data (capacity * 8 bytes)
         self.text_section.push("    ; Allocar memoria para data".to_string());
         self.text_section.push("    mov rax, r14  ; capacity".to_string());
         self.text_section.push("    shl rax, 3  ; capacity * 8 bytes".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (data)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, rax  ; size".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    mov rcx, rax  ; size".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rdi, rax  ; puntero a data en rdi (preservado)".to_string());
         
         // Configurar Array struct
@@ -6011,7 +5600,7 @@ impl CodeGenerator {
         // Parámetros: RCX = puntero al Array, RDX = valor
         // Retorna: RAX = 0 (éxito) o -1 (error: fallo de memoria)
         self.text_section.push("array_append:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc/VirtualFree
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc/heap_free
         self.text_section.push("    mov r12, rcx  ; preservar puntero al Array".to_string());
         self.text_section.push("    mov r13, rdx  ; preservar valor".to_string());
         
@@ -6029,14 +5618,11 @@ impl CodeGenerator {
         self.text_section.push("    mov r14, rax  ; preservar nueva capacity".to_string());
         self.text_section.push("    shl r14, 3  ; capacity * 8 bytes".to_string());
         
-        // VirtualAlloc nuevo bloque
-        self.text_section.push("    ; VirtualAlloc nuevo bloque".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, r14  ; nuevo size".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        // heap_alloc nuevo bloque
+        self.text_section.push("    ; heap_alloc nuevo bloque".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc");
+        self.text_section.push("    mov rcx, r14  ; nuevo size".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov r15, rax  ; preservar nuevo puntero".to_string());
         
         // Copiar datos antiguos (OPTIMIZADO: usar rep movsq para copia rápida)
@@ -6050,14 +5636,12 @@ impl CodeGenerator {
         self.text_section.push("    rep movsq  ; copiar 8 bytes a la vez (qword) - MUCHO MÁS RÁPIDO".to_string());
         self.text_section.push(".copy_done_append:".to_string());
         
-        // VirtualFree bloque antiguo
-        self.text_section.push("    ; VirtualFree bloque antiguo".to_string());
+        // heap_free bloque antiguo
+        self.text_section.push("    ; heap_free bloque antiguo".to_string());
         self.text_section.push("    mov r14, [r12 + 0]  ; preservar puntero antiguo".to_string());
-        self.ensure_stack_alignment_before_call("VirtualFree");
-        self.text_section.push("    mov rcx, r14  ; lpAddress".to_string());
-        self.text_section.push("    mov rdx, 0  ; dwSize (0 = liberar todo)".to_string());
-        self.text_section.push("    mov r8, 0x8000  ; MEM_RELEASE".to_string());
-        self.text_section.push("    call VirtualFree".to_string());
+        self.ensure_stack_alignment_before_call("heap_free");
+        self.text_section.push("    mov rcx, r14  ; bloque".to_string());
+        self.text_section.push("    call heap_free".to_string());
         
         // Actualizar data pointer
         self.text_section.push("    ; Actualizar data pointer".to_string());
@@ -6142,7 +5726,7 @@ impl CodeGenerator {
         // Parámetros: RCX = puntero al Array, RDX = índice, R8 = valor
         // Retorna: RAX = 0 (éxito) o -1 (error: índice fuera de rango)
         self.text_section.push("array_insert:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc/VirtualFree
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc/heap_free
         self.text_section.push("    mov r12, rcx  ; preservar puntero al Array".to_string());
         self.text_section.push("    mov r13, rdx  ; preservar índice".to_string());
         self.text_section.push("    mov r14, r8  ; preservar valor".to_string());
@@ -6166,14 +5750,11 @@ impl CodeGenerator {
         self.text_section.push("    mov r15, rax  ; preservar nueva capacity".to_string());
         self.text_section.push("    shl r15, 3  ; capacity * 8 bytes".to_string());
         
-        // VirtualAlloc nuevo bloque
-        self.text_section.push("    ; VirtualAlloc nuevo bloque".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, r15  ; nuevo size".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        // heap_alloc nuevo bloque
+        self.text_section.push("    ; heap_alloc nuevo bloque".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc");
+        self.text_section.push("    mov rcx, r15  ; nuevo size".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rdi, rax  ; preservar nuevo puntero (rdi preservado)".to_string());
         
         // Copiar datos antiguos hasta el índice
@@ -6212,14 +5793,12 @@ impl CodeGenerator {
         self.text_section.push("    jnz .copy_loop_insert2".to_string());
         self.text_section.push(".copy_done_insert2:".to_string());
         
-        // VirtualFree bloque antiguo
-        self.text_section.push("    ; VirtualFree bloque antiguo".to_string());
+        // heap_free bloque antiguo
+        self.text_section.push("    ; heap_free bloque antiguo".to_string());
         self.text_section.push("    mov r15, [r12 + 0]  ; preservar puntero antiguo".to_string());
-        self.ensure_stack_alignment_before_call("VirtualFree");
-        self.text_section.push("    mov rcx, r15  ; lpAddress".to_string());
-        self.text_section.push("    mov rdx, 0  ; dwSize (0 = liberar todo)".to_string());
-        self.text_section.push("    mov r8, 0x8000  ; MEM_RELEASE".to_string());
-        self.text_section.push("    call VirtualFree".to_string());
+        self.ensure_stack_alignment_before_call("heap_free");
+        self.text_section.push("    mov rcx, r15  ; bloque".to_string());
+        self.text_section.push("    call heap_free".to_string());
         
         // Actualizar data pointer y length
         self.text_section.push("    ; Actualizar data pointer y length".to_string());
@@ -6513,7 +6092,7 @@ impl CodeGenerator {
         // Nota: Libera tanto el Array struct como su data buffer
         //       Liberar NULL es seguro (no-op, retorna 0)
         self.text_section.push("array_free:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualFree
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_free
         self.text_section.push("    mov r12, rcx  ; preservar puntero al Array".to_string());
         
         // Verificar si el puntero es NULL (liberar NULL es seguro, no-op)
@@ -6526,19 +6105,15 @@ impl CodeGenerator {
         self.text_section.push("    mov rcx, [r12 + 0]  ; data pointer".to_string());
         self.text_section.push("    test rcx, rcx  ; verificar si es NULL".to_string());
         self.text_section.push("    jz .free_array_struct  ; si es NULL, saltar".to_string());
-        self.ensure_stack_alignment_before_call("VirtualFree (data)");
-        self.text_section.push("    mov rdx, 0  ; dwSize (0 = liberar todo)".to_string());
-        self.text_section.push("    mov r8, 0x8000  ; MEM_RELEASE".to_string());
-        self.text_section.push("    call VirtualFree".to_string());
+        self.ensure_stack_alignment_before_call("heap_free (data)");
+        self.text_section.push("    call heap_free".to_string());
         
         // Liberar Array struct
         self.text_section.push(".free_array_struct:".to_string());
         self.text_section.push("    ; Liberar Array struct".to_string());
-        self.ensure_stack_alignment_before_call("VirtualFree (Array struct)");
+        self.ensure_stack_alignment_before_call("heap_free (Array struct)");
         self.text_section.push("    mov rcx, r12  ; puntero al Array struct".to_string());
-        self.text_section.push("    mov rdx, 0  ; dwSize (0 = liberar todo)".to_string());
-        self.text_section.push("    mov r8, 0x8000  ; MEM_RELEASE".to_string());
-        self.text_section.push("    call VirtualFree".to_string());
+        self.text_section.push("    call heap_free".to_string());
         
         // Retornar éxito
         self.text_section.push(".free_array_done:".to_string());
@@ -6562,6 +6137,10 @@ impl CodeGenerator {
         deps.mark_used("string_slice");
         deps.mark_used("string_upper");
         deps.mark_used("string_lower");
+        for func in ["string_find", "string_startswith", "string_endswith", "string_strip", "string_char_at",
+                     "string_slice_step", "string_replace", "string_split", "string_join"] {
+            deps.mark_used(func);
+        }
         deps.mark_used("string_free");
         self.generate_string_helpers_nasm_selective(&deps);
     }
@@ -6582,16 +6161,13 @@ impl CodeGenerator {
         // Retorna: RAX = puntero al String (en heap)
         if deps.should_generate("string_new") {
         self.text_section.push("string_new:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
         
         // Allocar memoria para String struct (32 bytes)
         self.text_section.push("    ; Allocar memoria para String struct (32 bytes)".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (String struct)");
-        self.text_section.push("    mov rcx, 0  ; lpAddress (NULL = auto)".to_string());
-        self.text_section.push("    mov rdx, 32  ; dwSize (32 bytes para String struct)".to_string());
-        self.text_section.push("    mov r8, 0x1000  ; flAllocationType (MEM_COMMIT)".to_string());
-        self.text_section.push("    mov r9, 0x04  ; flProtect (PAGE_READWRITE)".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (String struct)");
+        self.text_section.push("    mov rcx, 32  ; 32 bytes para String struct".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov r12, rax  ; preservar puntero al String".to_string());
         
         // Inicializar String: length=0, capacity=16, data=NULL (se asignará después), hash=0
@@ -6603,12 +6179,9 @@ impl CodeGenerator {
         
         // Allocar memoria para data (capacity bytes)
         self.text_section.push("    ; Allocar memoria para data (16 bytes)".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (data)");
-        self.text_section.push("    mov rcx, 0  ; lpAddress".to_string());
-        self.text_section.push("    mov rdx, 16  ; dwSize (16 bytes)".to_string());
-        self.text_section.push("    mov r8, 0x1000  ; MEM_COMMIT".to_string());
-        self.text_section.push("    mov r9, 0x04  ; PAGE_READWRITE".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    mov rcx, 16  ; 16 bytes".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         
         // Asignar data al String
         self.text_section.push("    mov [r12 + 0], rax  ; data = puntero a memoria".to_string());
@@ -6625,7 +6198,7 @@ impl CodeGenerator {
         // Retorna: RAX = puntero al String (en heap)
         if deps.should_generate("string_from_literal") {
         self.text_section.push("string_from_literal:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
         self.text_section.push("    mov r12, rcx  ; preservar puntero a literal".to_string());
         self.text_section.push("    mov r13, rdx  ; preservar longitud".to_string());
         
@@ -6641,22 +6214,16 @@ impl CodeGenerator {
         
         // Allocar memoria para String struct (32 bytes)
         self.text_section.push("    ; Allocar memoria para String struct".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (String struct)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, 32".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (String struct)");
+        self.text_section.push("    mov rcx, 32".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov r15, rax  ; preservar puntero al String".to_string());
         
         // Allocar memoria para data (capacity bytes)
         self.text_section.push("    ; Allocar memoria para data".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (data)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, r14  ; capacity".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    mov rcx, r14  ; capacity".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rdi, rax  ; preservar puntero a data (rdi preservado)".to_string());
         
         // Configurar String struct
@@ -6705,7 +6272,7 @@ impl CodeGenerator {
         // Parámetros: RCX = puntero al String 1, RDX = puntero al String 2
         // Retorna: RAX = puntero al nuevo String (concatenado)
         self.text_section.push("string_concat:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
         self.text_section.push("    mov r12, rcx  ; preservar String 1".to_string());
         self.text_section.push("    mov r13, rdx  ; preservar String 2".to_string());
         
@@ -6727,22 +6294,16 @@ impl CodeGenerator {
         
         // Allocar memoria para nuevo String struct (32 bytes)
         self.text_section.push("    ; Allocar memoria para nuevo String struct".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (String struct)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, 32".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (String struct)");
+        self.text_section.push("    mov rcx, 32".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rbx, rax  ; preservar puntero al nuevo String (rbx preservado)".to_string());
         
         // Allocar memoria para data
         self.text_section.push("    ; Allocar memoria para data".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (data)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, r15  ; capacity".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    mov rcx, r15  ; capacity".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rdi, rax  ; preservar puntero a data (rdi preservado)".to_string());
         
         // Configurar String struct
@@ -6795,7 +6356,7 @@ impl CodeGenerator {
         // Parámetros: RCX = puntero al String, RDX = índice inicio, R8 = índice fin (exclusivo)
         // Retorna: RAX = puntero al nuevo String (slice), o NULL (0) si error (índices inválidos)
         self.text_section.push("string_slice:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
         self.text_section.push("    mov r12, rcx  ; preservar String".to_string());
         self.text_section.push("    mov r13, rdx  ; preservar start".to_string());
         self.text_section.push("    mov r14, r8  ; preservar end".to_string());
@@ -6828,23 +6389,18 @@ impl CodeGenerator {
         
         // Allocar memoria para nuevo String struct
         self.text_section.push("    ; Allocar memoria para nuevo String struct".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (String struct)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, 32".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (String struct)");
+        self.text_section.push("    mov rcx, 32".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rbx, rax  ; preservar puntero al nuevo String (rbx preservado)".to_string());
         
         // Allocar memoria para data
         self.text_section.push("    ; Allocar memoria para data".to_string());
         self.text_section.push("    pop rdx  ; capacity".to_string());
         self.text_section.push("    push rdx  ; guardar capacity de nuevo".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (data)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    mov rcx, rdx  ; tamaño en bytes".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rdi, rax  ; preservar puntero a data (rdi preservado)".to_string());
         
         // Configurar String struct
@@ -6894,7 +6450,7 @@ impl CodeGenerator {
         // Retorna: RAX = puntero al nuevo String (mayúsculas)
         if deps.should_generate("string_upper") {
         self.text_section.push("string_upper:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
         self.text_section.push("    mov r12, rcx  ; preservar String".to_string());
         
         // Obtener longitud
@@ -6915,22 +6471,16 @@ impl CodeGenerator {
         
         // Allocar memoria para nuevo String struct
         self.text_section.push("    ; Allocar memoria para nuevo String struct".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (String struct)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, 32".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (String struct)");
+        self.text_section.push("    mov rcx, 32".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rbx, rax  ; preservar puntero al nuevo String (rbx preservado)".to_string());
         
         // Allocar memoria para data
         self.text_section.push("    ; Allocar memoria para data".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (data)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, r14  ; capacity".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    mov rcx, r14  ; capacity".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rdi, rax  ; preservar puntero a data (rdi preservado)".to_string());
         
         // Configurar nuevo String
@@ -6975,7 +6525,7 @@ impl CodeGenerator {
         // Retorna: RAX = puntero al nuevo String (minúsculas)
         if deps.should_generate("string_lower") {
         self.text_section.push("string_lower:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualAlloc
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
         self.text_section.push("    mov r12, rcx  ; preservar String".to_string());
         
         // Obtener longitud
@@ -6996,22 +6546,16 @@ impl CodeGenerator {
         
         // Allocar memoria para nuevo String struct
         self.text_section.push("    ; Allocar memoria para nuevo String struct".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (String struct)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, 32".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (String struct)");
+        self.text_section.push("    mov rcx, 32".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rbx, rax  ; preservar puntero al nuevo String (rbx preservado)".to_string());
         
         // Allocar memoria para data
         self.text_section.push("    ; Allocar memoria para data".to_string());
-        self.ensure_stack_alignment_before_call("VirtualAlloc (data)");
-        self.text_section.push("    mov rcx, 0".to_string());
-        self.text_section.push("    mov rdx, r14  ; capacity".to_string());
-        self.text_section.push("    mov r8, 0x1000".to_string());
-        self.text_section.push("    mov r9, 0x04".to_string());
-        self.text_section.push("    call VirtualAlloc".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    mov rcx, r14  ; capacity".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
         self.text_section.push("    mov rdi, rax  ; preservar puntero a data (rdi preservado)".to_string());
         
         // Configurar nuevo String
@@ -7051,6 +6595,575 @@ impl CodeGenerator {
         self.text_section.push("".to_string());
         }
         
+        // string_find_from: Buscar un substring a partir de una posición
+        // Parámetros: RCX = puntero al String, RDX = puntero al substring, R8 = posición inicial
+        // Retorna: RAX = índice de la primera aparición, o -1 si no aparece
+        if deps.should_generate("string_find_from") {
+        self.text_section.push("string_find_from:".to_string());
+        self.generate_abi_prologue(false);
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    test rdx, rdx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    mov rsi, [rcx + 0]  ; String->data".to_string());
+        self.text_section.push("    mov rdi, [rdx + 0]  ; substring->data".to_string());
+        self.text_section.push("    mov r13, [rdx + 8]  ; longitud del substring".to_string());
+        self.text_section.push("    mov r15, [rcx + 8]  ; length".to_string());
+        self.text_section.push("    sub r15, r13  ; última posición donde cabe el substring".to_string());
+        self.text_section.push("    mov r14, r8  ; posición actual".to_string());
+        self.text_section.push("    test r14, r14".to_string());
+        self.text_section.push("    jns .find_outer".to_string());
+        self.text_section.push("    xor r14, r14  ; una posición negativa empieza en 0".to_string());
+        
+        // Probar cada posición y comparar byte a byte
+        self.text_section.push(".find_outer:".to_string());
+        self.text_section.push("    cmp r14, r15".to_string());
+        self.text_section.push("    jg .find_not_found".to_string());
+        self.text_section.push("    xor rcx, rcx  ; índice dentro del substring".to_string());
+        self.text_section.push(".find_inner:".to_string());
+        self.text_section.push("    cmp rcx, r13".to_string());
+        self.text_section.push("    jge .find_found".to_string());
+        self.text_section.push("    lea rax, [r14 + rcx]".to_string());
+        self.text_section.push("    mov al, [rsi + rax]".to_string());
+        self.text_section.push("    cmp al, [rdi + rcx]".to_string());
+        self.text_section.push("    jne .find_next".to_string());
+        self.text_section.push("    inc rcx".to_string());
+        self.text_section.push("    jmp .find_inner".to_string());
+        self.text_section.push(".find_next:".to_string());
+        self.text_section.push("    inc r14".to_string());
+        self.text_section.push("    jmp .find_outer".to_string());
+        self.text_section.push(".find_found:".to_string());
+        self.text_section.push("    mov rax, r14  ; índice de la aparición".to_string());
+        self.text_section.push("    jmp .find_done".to_string());
+        self.text_section.push(".find_not_found:".to_string());
+        self.text_section.push("    mov rax, -1  ; no encontrado".to_string());
+        self.text_section.push(".find_done:".to_string());
+        self.generate_abi_epilogue(false);
+        self.text_section.push("".to_string());
+        }
+        
+        // string_find: Buscar un substring desde el inicio
+        // Parámetros: RCX = puntero al String, RDX = puntero al substring
+        // Retorna: RAX = índice de la primera aparición, o -1 si no aparece
+        if deps.should_generate("string_find") {
+        self.text_section.push("string_find:".to_string());
+        self.text_section.push("    xor r8, r8  ; posición inicial 0".to_string());
+        self.text_section.push("    jmp string_find_from".to_string());
+        self.text_section.push("".to_string());
+        }
+        
+        // string_startswith: ¿El String empieza con el prefijo?
+        // Parámetros: RCX = puntero al String, RDX = puntero al prefijo
+        // Retorna: RAX = 1 (sí) o 0 (no)
+        // Nota: Función hoja, solo usa registros volátiles (no necesita prologue)
+        if deps.should_generate("string_startswith") {
+        self.text_section.push("string_startswith:".to_string());
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    test rdx, rdx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    mov r8, [rdx + 8]  ; longitud del prefijo".to_string());
+        self.text_section.push("    cmp r8, [rcx + 8]".to_string());
+        self.text_section.push("    jg .startswith_no  ; prefijo más largo que el String".to_string());
+        self.text_section.push("    mov r10, [rcx + 0]  ; String->data".to_string());
+        self.text_section.push("    mov r11, [rdx + 0]  ; prefijo->data".to_string());
+        self.text_section.push("    xor r9, r9".to_string());
+        self.text_section.push(".startswith_loop:".to_string());
+        self.text_section.push("    cmp r9, r8".to_string());
+        self.text_section.push("    jge .startswith_yes".to_string());
+        self.text_section.push("    mov al, [r10 + r9]".to_string());
+        self.text_section.push("    cmp al, [r11 + r9]".to_string());
+        self.text_section.push("    jne .startswith_no".to_string());
+        self.text_section.push("    inc r9".to_string());
+        self.text_section.push("    jmp .startswith_loop".to_string());
+        self.text_section.push(".startswith_yes:".to_string());
+        self.text_section.push("    mov rax, 1".to_string());
+        self.text_section.push("    ret".to_string());
+        self.text_section.push(".startswith_no:".to_string());
+        self.text_section.push("    xor rax, rax".to_string());
+        self.text_section.push("    ret".to_string());
+        self.text_section.push("".to_string());
+        }
+        
+        // string_endswith: ¿El String termina con el sufijo?
+        // Parámetros: RCX = puntero al String, RDX = puntero al sufijo
+        // Retorna: RAX = 1 (sí) o 0 (no)
+        // Nota: Función hoja, solo usa registros volátiles (no necesita prologue)
+        if deps.should_generate("string_endswith") {
+        self.text_section.push("string_endswith:".to_string());
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    test rdx, rdx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    mov r8, [rdx + 8]  ; longitud del sufijo".to_string());
+        self.text_section.push("    cmp r8, [rcx + 8]".to_string());
+        self.text_section.push("    jg .endswith_no  ; sufijo más largo que el String".to_string());
+        self.text_section.push("    mov r10, [rcx + 0]  ; String->data".to_string());
+        self.text_section.push("    add r10, [rcx + 8]".to_string());
+        self.text_section.push("    sub r10, r8  ; inicio del sufijo dentro del String".to_string());
+        self.text_section.push("    mov r11, [rdx + 0]  ; sufijo->data".to_string());
+        self.text_section.push("    xor r9, r9".to_string());
+        self.text_section.push(".endswith_loop:".to_string());
+        self.text_section.push("    cmp r9, r8".to_string());
+        self.text_section.push("    jge .endswith_yes".to_string());
+        self.text_section.push("    mov al, [r10 + r9]".to_string());
+        self.text_section.push("    cmp al, [r11 + r9]".to_string());
+        self.text_section.push("    jne .endswith_no".to_string());
+        self.text_section.push("    inc r9".to_string());
+        self.text_section.push("    jmp .endswith_loop".to_string());
+        self.text_section.push(".endswith_yes:".to_string());
+        self.text_section.push("    mov rax, 1".to_string());
+        self.text_section.push("    ret".to_string());
+        self.text_section.push(".endswith_no:".to_string());
+        self.text_section.push("    xor rax, rax".to_string());
+        self.text_section.push("    ret".to_string());
+        self.text_section.push("".to_string());
+        }
+        
+        // string_strip: Quitar espacios en blanco (' ', \t, \n, \v, \f, \r) al inicio y al final
+        // Parámetros: RCX = puntero al String
+        // Retorna: RAX = puntero al nuevo String
+        if deps.should_generate("string_strip") {
+        self.text_section.push("string_strip:".to_string());
+        self.generate_abi_prologue(true);  // Necesita shadow space para string_from_literal
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    mov rsi, [rcx + 0]  ; String->data".to_string());
+        self.text_section.push("    xor r12, r12  ; inicio".to_string());
+        self.text_section.push("    mov r13, [rcx + 8]  ; fin (exclusivo)".to_string());
+        self.text_section.push(".strip_start:".to_string());
+        self.text_section.push("    cmp r12, r13".to_string());
+        self.text_section.push("    jge .strip_build".to_string());
+        self.text_section.push("    mov al, [rsi + r12]".to_string());
+        self.emit_jump_if_space(".strip_start_next");
+        self.text_section.push("    jmp .strip_end".to_string());
+        self.text_section.push(".strip_start_next:".to_string());
+        self.text_section.push("    inc r12".to_string());
+        self.text_section.push("    jmp .strip_start".to_string());
+        self.text_section.push(".strip_end:".to_string());
+        self.text_section.push("    cmp r13, r12".to_string());
+        self.text_section.push("    jle .strip_build".to_string());
+        self.text_section.push("    mov al, [rsi + r13 - 1]".to_string());
+        self.emit_jump_if_space(".strip_end_next");
+        self.text_section.push("    jmp .strip_build".to_string());
+        self.text_section.push(".strip_end_next:".to_string());
+        self.text_section.push("    dec r13".to_string());
+        self.text_section.push("    jmp .strip_end".to_string());
+        
+        // Copiar data[inicio..fin] a un String nuevo
+        self.text_section.push(".strip_build:".to_string());
+        self.ensure_stack_alignment_before_call("string_from_literal");
+        self.text_section.push("    lea rcx, [rsi + r12]  ; puntero al primer carácter".to_string());
+        self.text_section.push("    mov rdx, r13".to_string());
+        self.text_section.push("    sub rdx, r12  ; longitud".to_string());
+        self.text_section.push("    call string_from_literal".to_string());
+        self.generate_abi_epilogue(true);
+        self.text_section.push("".to_string());
+        }
+        
+        // string_char_at: Carácter en una posición (s[i])
+        // Parámetros: RCX = puntero al String, RDX = índice (negativo cuenta desde el final)
+        // Retorna: RAX = puntero a un String nuevo de longitud 1; panic si el índice está fuera de rango
+        if deps.should_generate("string_char_at") {
+        self.text_section.push("string_char_at:".to_string());
+        self.generate_abi_prologue(true);  // Necesita shadow space para string_from_literal
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    mov rax, [rcx + 8]  ; length".to_string());
+        self.text_section.push("    test rdx, rdx".to_string());
+        self.text_section.push("    jns .char_at_check".to_string());
+        self.text_section.push("    add rdx, rax  ; índice negativo: length + índice".to_string());
+        self.text_section.push(".char_at_check:".to_string());
+        self.text_section.push("    cmp rdx, 0".to_string());
+        self.text_section.push("    jl panic_out_of_bounds".to_string());
+        self.text_section.push("    cmp rdx, rax".to_string());
+        self.text_section.push("    jge panic_out_of_bounds".to_string());
+        self.ensure_stack_alignment_before_call("string_from_literal");
+        self.text_section.push("    mov rcx, [rcx + 0]  ; String->data".to_string());
+        self.text_section.push("    add rcx, rdx  ; dirección del carácter".to_string());
+        self.text_section.push("    mov rdx, 1  ; un carácter".to_string());
+        self.text_section.push("    call string_from_literal".to_string());
+        self.generate_abi_epilogue(true);
+        self.text_section.push("".to_string());
+        }
+        
+        // string_slice_step: Slice con semántica de Python (s[inicio:fin:paso])
+        // Parámetros: RCX = puntero al String, RDX = inicio, R8 = fin, R9 = paso
+        //             (un límite omitido llega como 0x8000000000000000)
+        // Retorna: RAX = puntero al nuevo String; los límites se recortan al String y paso 0 es panic
+        if deps.should_generate("string_slice_step") {
+        self.text_section.push("string_slice_step:".to_string());
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    test r9, r9".to_string());
+        self.text_section.push("    jz panic_out_of_bounds  ; paso 0".to_string());
+        self.text_section.push("    mov r12, rcx  ; preservar String".to_string());
+        self.text_section.push("    mov r13, rdx  ; inicio".to_string());
+        self.text_section.push("    mov r14, r8  ; fin".to_string());
+        self.text_section.push("    mov r15, r9  ; paso".to_string());
+        self.text_section.push("    mov rbx, [r12 + 8]  ; length".to_string());
+        
+        // Rango válido [rsi, rdi] y límites por defecto (r8 = inicio, r9 = fin) según el signo del paso
+        self.text_section.push("    test r15, r15".to_string());
+        self.text_section.push("    js .slice_step_negative".to_string());
+        self.text_section.push("    xor rsi, rsi  ; paso > 0: [0, length]".to_string());
+        self.text_section.push("    mov rdi, rbx".to_string());
+        self.text_section.push("    mov r8, rsi  ; inicio por defecto: 0".to_string());
+        self.text_section.push("    mov r9, rdi  ; fin por defecto: length".to_string());
+        self.text_section.push("    jmp .slice_step_bounds".to_string());
+        self.text_section.push(".slice_step_negative:".to_string());
+        self.text_section.push("    mov rsi, -1  ; paso < 0: [-1, length - 1]".to_string());
+        self.text_section.push("    lea rdi, [rbx - 1]".to_string());
+        self.text_section.push("    mov r8, rdi  ; inicio por defecto: length - 1".to_string());
+        self.text_section.push("    mov r9, rsi  ; fin por defecto: -1 (antes del primer carácter)".to_string());
+        self.text_section.push(".slice_step_bounds:".to_string());
+        self.text_section.push("    mov rax, 0x8000000000000000  ; marca de límite omitido".to_string());
+        self.text_section.push("    cmp r13, rax".to_string());
+        self.text_section.push("    jne .slice_step_start_given".to_string());
+        self.text_section.push("    mov r13, r8".to_string());
+        self.text_section.push("    jmp .slice_step_start_ready".to_string());
+        self.text_section.push(".slice_step_start_given:".to_string());
+        self.text_section.push("    test r13, r13".to_string());
+        self.text_section.push("    jns .slice_step_start_low".to_string());
+        self.text_section.push("    add r13, rbx  ; índice negativo: length + índice".to_string());
+        self.text_section.push(".slice_step_start_low:".to_string());
+        self.text_section.push("    cmp r13, rsi".to_string());
+        self.text_section.push("    jge .slice_step_start_high".to_string());
+        self.text_section.push("    mov r13, rsi".to_string());
+        self.text_section.push(".slice_step_start_high:".to_string());
+        self.text_section.push("    cmp r13, rdi".to_string());
+        self.text_section.push("    jle .slice_step_start_ready".to_string());
+        self.text_section.push("    mov r13, rdi".to_string());
+        self.text_section.push(".slice_step_start_ready:".to_string());
+        self.text_section.push("    cmp r14, rax".to_string());
+        self.text_section.push("    jne .slice_step_end_given".to_string());
+        self.text_section.push("    mov r14, r9".to_string());
+        self.text_section.push("    jmp .slice_step_end_ready".to_string());
+        self.text_section.push(".slice_step_end_given:".to_string());
+        self.text_section.push("    test r14, r14".to_string());
+        self.text_section.push("    jns .slice_step_end_low".to_string());
+        self.text_section.push("    add r14, rbx  ; índice negativo: length + índice".to_string());
+        self.text_section.push(".slice_step_end_low:".to_string());
+        self.text_section.push("    cmp r14, rsi".to_string());
+        self.text_section.push("    jge .slice_step_end_high".to_string());
+        self.text_section.push("    mov r14, rsi".to_string());
+        self.text_section.push(".slice_step_end_high:".to_string());
+        self.text_section.push("    cmp r14, rdi".to_string());
+        self.text_section.push("    jle .slice_step_end_ready".to_string());
+        self.text_section.push("    mov r14, rdi".to_string());
+        self.text_section.push(".slice_step_end_ready:".to_string());
+        
+        // Cantidad de caracteres: ceil((fin - inicio) / paso), o 0 si el rango está vacío
+        self.text_section.push("    mov rax, r14".to_string());
+        self.text_section.push("    sub rax, r13  ; fin - inicio".to_string());
+        self.text_section.push("    test r15, r15".to_string());
+        self.text_section.push("    js .slice_step_count_negative".to_string());
+        self.text_section.push("    test rax, rax".to_string());
+        self.text_section.push("    jle .slice_step_empty".to_string());
+        self.text_section.push("    add rax, r15".to_string());
+        self.text_section.push("    dec rax".to_string());
+        self.text_section.push("    cqo".to_string());
+        self.text_section.push("    idiv r15".to_string());
+        self.text_section.push("    jmp .slice_step_count_ready".to_string());
+        self.text_section.push(".slice_step_count_negative:".to_string());
+        self.text_section.push("    neg rax  ; inicio - fin".to_string());
+        self.text_section.push("    test rax, rax".to_string());
+        self.text_section.push("    jle .slice_step_empty".to_string());
+        self.text_section.push("    mov rcx, r15".to_string());
+        self.text_section.push("    neg rcx  ; |paso|".to_string());
+        self.text_section.push("    add rax, rcx".to_string());
+        self.text_section.push("    dec rax".to_string());
+        self.text_section.push("    cqo".to_string());
+        self.text_section.push("    idiv rcx".to_string());
+        self.text_section.push("    jmp .slice_step_count_ready".to_string());
+        self.text_section.push(".slice_step_empty:".to_string());
+        self.text_section.push("    xor rax, rax".to_string());
+        self.text_section.push(".slice_step_count_ready:".to_string());
+        self.text_section.push("    mov rsi, rax  ; preservar cantidad".to_string());
+        
+        // Allocar String struct y data (cantidad + null terminator)
+        self.ensure_stack_alignment_before_call("heap_alloc (String struct)");
+        self.text_section.push("    mov rcx, 32".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
+        self.text_section.push("    mov rbx, rax  ; preservar puntero al nuevo String".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    lea rcx, [rsi + 1]".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
+        self.text_section.push("    mov rdi, rax  ; destino".to_string());
+        self.text_section.push("    mov [rbx + 0], rdi  ; data = puntero".to_string());
+        self.text_section.push("    mov [rbx + 8], rsi  ; length".to_string());
+        self.text_section.push("    lea rax, [rsi + 1]".to_string());
+        self.text_section.push("    mov [rbx + 16], rax  ; capacity".to_string());
+        self.text_section.push("    mov qword [rbx + 24], 0  ; hash = 0".to_string());
+        
+        // Copiar data[inicio], data[inicio + paso], ...
+        self.text_section.push("    mov rdx, [r12 + 0]  ; String->data".to_string());
+        self.text_section.push(".slice_step_copy:".to_string());
+        self.text_section.push("    test rsi, rsi".to_string());
+        self.text_section.push("    jz .slice_step_done".to_string());
+        self.text_section.push("    mov al, [rdx + r13]".to_string());
+        self.text_section.push("    mov [rdi], al".to_string());
+        self.text_section.push("    inc rdi".to_string());
+        self.text_section.push("    add r13, r15  ; siguiente posición".to_string());
+        self.text_section.push("    dec rsi".to_string());
+        self.text_section.push("    jmp .slice_step_copy".to_string());
+        self.text_section.push(".slice_step_done:".to_string());
+        self.text_section.push("    mov byte [rdi], 0  ; null terminator".to_string());
+        self.text_section.push("    mov rax, rbx  ; retornar puntero al nuevo String".to_string());
+        self.generate_abi_epilogue(true);
+        self.text_section.push("".to_string());
+        }
+        
+        // string_replace: Reemplazar todas las apariciones (sin solaparse) de un substring
+        // Parámetros: RCX = puntero al String, RDX = substring a buscar, R8 = reemplazo
+        // Retorna: RAX = puntero al nuevo String
+        // Nota: Con un substring vacío se retorna una copia del String
+        if deps.should_generate("string_replace") {
+        self.text_section.push("string_replace:".to_string());
+        self.generate_abi_prologue(true);  // Necesita shadow space para string_find_from y heap_alloc
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    test rdx, rdx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    test r8, r8".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    mov r12, rcx  ; preservar String".to_string());
+        self.text_section.push("    mov r13, rdx  ; preservar substring".to_string());
+        self.text_section.push("    mov r14, r8  ; preservar reemplazo".to_string());
+        self.text_section.push("    cmp qword [r13 + 8], 0".to_string());
+        self.text_section.push("    je .replace_copy".to_string());
+        
+        // Primera pasada: contar apariciones
+        self.text_section.push("    xor r15, r15  ; apariciones".to_string());
+        self.text_section.push("    xor rsi, rsi  ; posición de búsqueda".to_string());
+        self.text_section.push(".replace_count:".to_string());
+        self.text_section.push("    mov rcx, r12".to_string());
+        self.text_section.push("    mov rdx, r13".to_string());
+        self.text_section.push("    mov r8, rsi".to_string());
+        self.text_section.push("    call string_find_from".to_string());
+        self.text_section.push("    cmp rax, -1".to_string());
+        self.text_section.push("    je .replace_counted".to_string());
+        self.text_section.push("    inc r15".to_string());
+        self.text_section.push("    add rax, [r13 + 8]".to_string());
+        self.text_section.push("    mov rsi, rax  ; seguir después de la aparición".to_string());
+        self.text_section.push("    jmp .replace_count".to_string());
+        self.text_section.push(".replace_counted:".to_string());
+        self.text_section.push("    ; Nueva longitud: length + apariciones * (len(reemplazo) - len(substring))".to_string());
+        self.text_section.push("    mov rax, [r14 + 8]".to_string());
+        self.text_section.push("    sub rax, [r13 + 8]".to_string());
+        self.text_section.push("    imul rax, r15".to_string());
+        self.text_section.push("    add rax, [r12 + 8]".to_string());
+        self.text_section.push("    mov r15, rax  ; preservar nueva longitud".to_string());
+        
+        // Allocar String struct y data del tamaño exacto
+        self.ensure_stack_alignment_before_call("heap_alloc (String struct)");
+        self.text_section.push("    mov rcx, 32".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
+        self.text_section.push("    mov rbx, rax  ; preservar puntero al nuevo String".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    lea rcx, [r15 + 1]".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
+        self.text_section.push("    mov rdi, rax  ; destino".to_string());
+        self.text_section.push("    mov [rbx + 0], rdi  ; data = puntero".to_string());
+        self.text_section.push("    mov [rbx + 8], r15  ; length".to_string());
+        self.text_section.push("    lea rax, [r15 + 1]".to_string());
+        self.text_section.push("    mov [rbx + 16], rax  ; capacity".to_string());
+        self.text_section.push("    mov qword [rbx + 24], 0  ; hash = 0".to_string());
+        
+        // Segunda pasada: copiar cada tramo seguido del reemplazo
+        self.text_section.push("    xor rsi, rsi  ; posición en el String original".to_string());
+        self.text_section.push(".replace_scan:".to_string());
+        self.text_section.push("    mov rcx, r12".to_string());
+        self.text_section.push("    mov rdx, r13".to_string());
+        self.text_section.push("    mov r8, rsi".to_string());
+        self.text_section.push("    call string_find_from".to_string());
+        self.text_section.push("    mov r15, rax  ; aparición (o -1)".to_string());
+        self.text_section.push("    cmp rax, -1".to_string());
+        self.text_section.push("    jne .replace_segment".to_string());
+        self.text_section.push("    mov rax, [r12 + 8]  ; sin más apariciones: copiar hasta el final".to_string());
+        self.text_section.push(".replace_segment:".to_string());
+        self.text_section.push("    mov rcx, rax".to_string());
+        self.text_section.push("    sub rcx, rsi  ; bytes del tramo".to_string());
+        self.text_section.push("    mov rdx, [r12 + 0]".to_string());
+        self.text_section.push("    add rdx, rsi  ; inicio del tramo".to_string());
+        self.text_section.push("    mov rsi, rax".to_string());
+        self.emit_copy_bytes("replace_segment_copy");
+        self.text_section.push("    cmp r15, -1".to_string());
+        self.text_section.push("    je .replace_done".to_string());
+        self.text_section.push("    mov rdx, [r14 + 0]  ; reemplazo->data".to_string());
+        self.text_section.push("    mov rcx, [r14 + 8]  ; longitud del reemplazo".to_string());
+        self.emit_copy_bytes("replace_new_copy");
+        self.text_section.push("    add rsi, [r13 + 8]  ; saltar el substring reemplazado".to_string());
+        self.text_section.push("    jmp .replace_scan".to_string());
+        self.text_section.push(".replace_done:".to_string());
+        self.text_section.push("    mov byte [rdi], 0  ; null terminator".to_string());
+        self.text_section.push("    mov rax, rbx  ; retornar puntero al nuevo String".to_string());
+        self.generate_abi_epilogue(true);
+        
+        // Substring vacío: copia del String original
+        self.text_section.push(".replace_copy:".to_string());
+        self.ensure_stack_alignment_before_call("string_from_literal");
+        self.text_section.push("    mov rcx, [r12 + 0]".to_string());
+        self.text_section.push("    mov rdx, [r12 + 8]".to_string());
+        self.text_section.push("    call string_from_literal".to_string());
+        self.generate_abi_epilogue(true);
+        self.text_section.push("".to_string());
+        }
+        
+        // string_split: Dividir un String en un Array de Strings
+        // Parámetros: RCX = puntero al String, RDX = separador (NULL o vacío: espacios en blanco)
+        // Retorna: RAX = puntero al Array de Strings
+        // Nota: Con separador se conservan los tramos vacíos ("a,,b" -> ["a", "", "b"]);
+        //       sin separador se ignoran los espacios repetidos y los de los extremos
+        if deps.should_generate("string_split") {
+        self.text_section.push("string_split:".to_string());
+        self.generate_abi_prologue(true);  // Necesita shadow space para array_new, array_append y string_from_literal
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    mov r12, rcx  ; preservar String".to_string());
+        self.text_section.push("    mov r13, rdx  ; preservar separador".to_string());
+        self.text_section.push("    mov rsi, [r12 + 0]  ; String->data".to_string());
+        self.ensure_stack_alignment_before_call("array_new");
+        self.text_section.push("    call array_new".to_string());
+        self.text_section.push("    mov rbx, rax  ; preservar Array resultado".to_string());
+        self.text_section.push("    xor r14, r14  ; inicio del tramo actual".to_string());
+        self.text_section.push("    test r13, r13".to_string());
+        self.text_section.push("    jz .split_ws_skip".to_string());
+        self.text_section.push("    cmp qword [r13 + 8], 0".to_string());
+        self.text_section.push("    je .split_ws_skip".to_string());
+        
+        // Con separador: cortar en cada aparición
+        self.text_section.push(".split_next:".to_string());
+        self.text_section.push("    mov rcx, r12".to_string());
+        self.text_section.push("    mov rdx, r13".to_string());
+        self.text_section.push("    mov r8, r14".to_string());
+        self.text_section.push("    call string_find_from".to_string());
+        self.text_section.push("    mov r15, rax  ; fin del tramo".to_string());
+        self.text_section.push("    cmp r15, -1".to_string());
+        self.text_section.push("    jne .split_piece".to_string());
+        self.text_section.push("    mov r15, [r12 + 8]  ; último tramo: hasta el final".to_string());
+        self.text_section.push(".split_piece:".to_string());
+        self.text_section.push("    lea rcx, [rsi + r14]".to_string());
+        self.text_section.push("    mov rdx, r15".to_string());
+        self.text_section.push("    sub rdx, r14".to_string());
+        self.text_section.push("    call string_from_literal".to_string());
+        self.text_section.push("    mov rcx, rbx".to_string());
+        self.text_section.push("    mov rdx, rax".to_string());
+        self.text_section.push("    call array_append".to_string());
+        self.text_section.push("    cmp r15, [r12 + 8]".to_string());
+        self.text_section.push("    jge .split_done".to_string());
+        self.text_section.push("    mov r14, r15".to_string());
+        self.text_section.push("    add r14, [r13 + 8]  ; saltar el separador".to_string());
+        self.text_section.push("    jmp .split_next".to_string());
+        
+        // Sin separador: palabras separadas por espacios en blanco
+        self.text_section.push(".split_ws_skip:".to_string());
+        self.text_section.push("    cmp r14, [r12 + 8]".to_string());
+        self.text_section.push("    jge .split_done".to_string());
+        self.text_section.push("    mov al, [rsi + r14]".to_string());
+        self.emit_jump_if_space(".split_ws_skip_next");
+        self.text_section.push("    mov r15, r14  ; inicio de la palabra".to_string());
+        self.text_section.push("    jmp .split_ws_word".to_string());
+        self.text_section.push(".split_ws_skip_next:".to_string());
+        self.text_section.push("    inc r14".to_string());
+        self.text_section.push("    jmp .split_ws_skip".to_string());
+        self.text_section.push(".split_ws_word:".to_string());
+        self.text_section.push("    cmp r15, [r12 + 8]".to_string());
+        self.text_section.push("    jge .split_ws_piece".to_string());
+        self.text_section.push("    mov al, [rsi + r15]".to_string());
+        self.emit_jump_if_space(".split_ws_piece");
+        self.text_section.push("    inc r15".to_string());
+        self.text_section.push("    jmp .split_ws_word".to_string());
+        self.text_section.push(".split_ws_piece:".to_string());
+        self.text_section.push("    lea rcx, [rsi + r14]".to_string());
+        self.text_section.push("    mov rdx, r15".to_string());
+        self.text_section.push("    sub rdx, r14".to_string());
+        self.text_section.push("    call string_from_literal".to_string());
+        self.text_section.push("    mov rcx, rbx".to_string());
+        self.text_section.push("    mov rdx, rax".to_string());
+        self.text_section.push("    call array_append".to_string());
+        self.text_section.push("    mov r14, r15".to_string());
+        self.text_section.push("    jmp .split_ws_skip".to_string());
+        self.text_section.push(".split_done:".to_string());
+        self.text_section.push("    mov rax, rbx  ; retornar puntero al Array".to_string());
+        self.generate_abi_epilogue(true);
+        self.text_section.push("".to_string());
+        }
+        
+        // string_join: Unir un Array de Strings con un separador
+        // Parámetros: RCX = puntero al separador, RDX = puntero al Array de Strings
+        // Retorna: RAX = puntero al nuevo String
+        if deps.should_generate("string_join") {
+        self.text_section.push("string_join:".to_string());
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    test rdx, rdx".to_string());
+        self.text_section.push("    jz panic_null_pointer".to_string());
+        self.text_section.push("    mov r12, rcx  ; preservar separador".to_string());
+        self.text_section.push("    mov r13, rdx  ; preservar Array".to_string());
+        self.text_section.push("    mov r14, [r13 + 8]  ; cantidad de elementos".to_string());
+        self.text_section.push("    mov rsi, [r13 + 0]  ; Array->data".to_string());
+        
+        // Longitud total: suma de longitudes + len(separador) * (n - 1)
+        self.text_section.push("    xor r15, r15".to_string());
+        self.text_section.push("    xor rcx, rcx".to_string());
+        self.text_section.push(".join_len:".to_string());
+        self.text_section.push("    cmp rcx, r14".to_string());
+        self.text_section.push("    jge .join_len_done".to_string());
+        self.text_section.push("    mov rax, [rsi + rcx*8]  ; String del elemento".to_string());
+        self.text_section.push("    add r15, [rax + 8]".to_string());
+        self.text_section.push("    inc rcx".to_string());
+        self.text_section.push("    jmp .join_len".to_string());
+        self.text_section.push(".join_len_done:".to_string());
+        self.text_section.push("    test r14, r14".to_string());
+        self.text_section.push("    jz .join_alloc".to_string());
+        self.text_section.push("    mov rax, r14".to_string());
+        self.text_section.push("    dec rax".to_string());
+        self.text_section.push("    imul rax, [r12 + 8]".to_string());
+        self.text_section.push("    add r15, rax".to_string());
+        
+        // Allocar String struct y data del tamaño exacto
+        self.text_section.push(".join_alloc:".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (String struct)");
+        self.text_section.push("    mov rcx, 32".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
+        self.text_section.push("    mov rbx, rax  ; preservar puntero al nuevo String".to_string());
+        self.ensure_stack_alignment_before_call("heap_alloc (data)");
+        self.text_section.push("    lea rcx, [r15 + 1]".to_string());
+        self.text_section.push("    call heap_alloc".to_string());
+        self.text_section.push("    mov rdi, rax  ; destino".to_string());
+        self.text_section.push("    mov [rbx + 0], rdi  ; data = puntero".to_string());
+        self.text_section.push("    mov [rbx + 8], r15  ; length".to_string());
+        self.text_section.push("    lea rax, [r15 + 1]".to_string());
+        self.text_section.push("    mov [rbx + 16], rax  ; capacity".to_string());
+        self.text_section.push("    mov qword [rbx + 24], 0  ; hash = 0".to_string());
+        
+        // Copiar elementos con el separador entre ellos
+        self.text_section.push("    xor r15, r15  ; índice del elemento".to_string());
+        self.text_section.push(".join_loop:".to_string());
+        self.text_section.push("    cmp r15, r14".to_string());
+        self.text_section.push("    jge .join_done".to_string());
+        self.text_section.push("    test r15, r15".to_string());
+        self.text_section.push("    jz .join_item  ; sin separador antes del primero".to_string());
+        self.text_section.push("    mov rdx, [r12 + 0]  ; separador->data".to_string());
+        self.text_section.push("    mov rcx, [r12 + 8]  ; longitud del separador".to_string());
+        self.emit_copy_bytes("join_sep_copy");
+        self.text_section.push(".join_item:".to_string());
+        self.text_section.push("    mov rax, [rsi + r15*8]  ; String del elemento".to_string());
+        self.text_section.push("    mov rdx, [rax + 0]".to_string());
+        self.text_section.push("    mov rcx, [rax + 8]".to_string());
+        self.emit_copy_bytes("join_item_copy");
+        self.text_section.push("    inc r15".to_string());
+        self.text_section.push("    jmp .join_loop".to_string());
+        self.text_section.push(".join_done:".to_string());
+        self.text_section.push("    mov byte [rdi], 0  ; null terminator".to_string());
+        self.text_section.push("    mov rax, rbx  ; retornar puntero al nuevo String".to_string());
+        self.generate_abi_epilogue(true);
+        self.text_section.push("".to_string());
+        }
+        
         // string_free: Liberar memoria de un String
         // Parámetros: RCX = puntero al String
         // Retorna: RAX = 0 (éxito) o -4 (error: puntero inválido)
@@ -7058,7 +7171,7 @@ impl CodeGenerator {
         //       Liberar NULL es seguro (no-op, retorna 0)
         if deps.should_generate("string_free") {
         self.text_section.push("string_free:".to_string());
-        self.generate_abi_prologue(true);  // Necesita shadow space para VirtualFree
+        self.generate_abi_prologue(true);  // Necesita shadow space para heap_free
         self.text_section.push("    mov r12, rcx  ; preservar puntero al String".to_string());
         
         // Verificar si el puntero es NULL (liberar NULL es seguro, no-op)
@@ -7071,19 +7184,15 @@ impl CodeGenerator {
         self.text_section.push("    mov rcx, [r12 + 0]  ; data pointer".to_string());
         self.text_section.push("    test rcx, rcx  ; verificar si es NULL".to_string());
         self.text_section.push("    jz .free_string_struct  ; si es NULL, saltar".to_string());
-        self.ensure_stack_alignment_before_call("VirtualFree (data)");
-        self.text_section.push("    mov rdx, 0  ; dwSize (0 = liberar todo)".to_string());
-        self.text_section.push("    mov r8, 0x8000  ; MEM_RELEASE".to_string());
-        self.text_section.push("    call VirtualFree".to_string());
+        self.ensure_stack_alignment_before_call("heap_free (data)");
+        self.text_section.push("    call heap_free".to_string());
         
         // Liberar String struct
         self.text_section.push(".free_string_struct:".to_string());
         self.text_section.push("    ; Liberar String struct".to_string());
-        self.ensure_stack_alignment_before_call("VirtualFree (String struct)");
+        self.ensure_stack_alignment_before_call("heap_free (String struct)");
         self.text_section.push("    mov rcx, r12  ; puntero al String struct".to_string());
-        self.text_section.push("    mov rdx, 0  ; dwSize (0 = liberar todo)".to_string());
-        self.text_section.push("    mov r8, 0x8000  ; MEM_RELEASE".to_string());
-        self.text_section.push("    call VirtualFree".to_string());
+        self.text_section.push("    call heap_free".to_string());
         
         // Retornar éxito
         self.text_section.push(".free_string_done:".to_string());
@@ -7092,6 +7201,969 @@ impl CodeGenerator {
         self.text_section.push("".to_string());
         }
     }
+
+    /// Saltar a `target` si el byte en AL es un espacio en blanco (' ', \t, \n, \v, \f, \r).
+    /// Destruye AL
+    fn emit_jump_if_space(&mut self, target: &str) {
+        self.text_section.push("    cmp al, ' '".to_string());
+        self.text_section.push(format!("    je {}", target));
+        self.text_section.push("    sub al, 9  ; \\t..\\r son 9..13".to_string());
+        self.text_section.push("    cmp al, 4".to_string());
+        self.text_section.push(format!("    jbe {}", target));
+    }
+
+    /// Copiar RCX bytes de [RDX] a [RDI], avanzando ambos punteros (etiquetas locales `.{label}`)
+    fn emit_copy_bytes(&mut self, label: &str) {
+        self.text_section.push(format!(".{}:", label));
+        self.text_section.push("    test rcx, rcx".to_string());
+        self.text_section.push(format!("    jz .{}_done", label));
+        self.text_section.push("    mov al, [rdx]".to_string());
+        self.text_section.push("    mov [rdi], al".to_string());
+        self.text_section.push("    inc rdx".to_string());
+        self.text_section.push("    inc rdi".to_string());
+        self.text_section.push("    dec rcx".to_string());
+        self.text_section.push(format!("    jmp .{}", label));
+        self.text_section.push(format!(".{}_done:", label));
+    }
+
+    /// Generar funciones helper de Dict y Set en NASM (solo las que se usan)
+    /// Tabla hash de direccionamiento abierto en heap, con entradas en orden de inserción;
+    /// un Set es un Dict cuyos valores no se usan
+    fn generate_dict_helpers_nasm_selective(&mut self, deps: &DependencyGraph) {
+        // ============================================
+        // Estructura Dict en NASM (48 bytes):
+        // - [rax + 0]  : entries (qword) - entradas [hash, key, value] de 24 bytes en orden de inserción
+        // - [rax + 8]  : count (qword) - entradas vivas
+        // - [rax + 16] : used (qword) - entradas ocupadas (incluye las borradas)
+        // - [rax + 24] : capacity (qword) - slots del índice (potencia de 2)
+        // - [rax + 32] : index (qword) - slots: 0 = vacío, -1 = borrado, i + 1 = entrada i
+        // - [rax + 40] : key_kind (qword) - 0 = int, 1 = string, -1 = sin determinar
+        // Una entrada borrada tiene hash -1 (los hashes válidos tienen el bit 63 en 0)
+        // ============================================
+
+        // dict_hash_key: hash de una clave (entero o String)
+        if deps.should_generate("dict_hash_key") {
+            // Parámetros: RCX = clave, RDX = tipo de clave (1 = string, otro = entero)
+            // Retorna: RAX = hash con el bit 63 en 0
+            self.text_section.push("dict_hash_key:".to_string());
+            self.generate_abi_prologue(false);
+            self.text_section.push("    cmp rdx, 1".to_string());
+            self.text_section.push("    je .string_key".to_string());
+            self.text_section.push("    ; Entero: hash multiplicativo (Fibonacci) mezclando los bits altos".to_string());
+            self.text_section.push("    mov rax, rcx".to_string());
+            self.text_section.push("    mov r8, 0x9E3779B97F4A7C15".to_string());
+            self.text_section.push("    imul rax, r8".to_string());
+            self.text_section.push("    mov r8, rax".to_string());
+            self.text_section.push("    shr r8, 29".to_string());
+            self.text_section.push("    xor rax, r8".to_string());
+            self.text_section.push("    btr rax, 63".to_string());
+            self.text_section.push("    jmp .done".to_string());
+            self.text_section.push(".string_key:".to_string());
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov rax, [rcx + 24]  ; hash cacheado en el String".to_string());
+            self.text_section.push("    test rax, rax".to_string());
+            self.text_section.push("    jnz .done".to_string());
+            self.text_section.push("    ; FNV-1a sobre los bytes del string".to_string());
+            self.text_section.push("    mov rsi, [rcx + 0]  ; data".to_string());
+            self.text_section.push("    mov r9, [rcx + 8]  ; length".to_string());
+            self.text_section.push("    mov rax, 0xCBF29CE484222325  ; FNV offset basis".to_string());
+            self.text_section.push("    mov r8, 0x100000001B3  ; FNV prime".to_string());
+            self.text_section.push(".fnv_loop:".to_string());
+            self.text_section.push("    test r9, r9".to_string());
+            self.text_section.push("    jz .fnv_done".to_string());
+            self.text_section.push("    movzx r10, byte [rsi]".to_string());
+            self.text_section.push("    xor rax, r10".to_string());
+            self.text_section.push("    imul rax, r8".to_string());
+            self.text_section.push("    inc rsi".to_string());
+            self.text_section.push("    dec r9".to_string());
+            self.text_section.push("    jmp .fnv_loop".to_string());
+            self.text_section.push(".fnv_done:".to_string());
+            self.text_section.push("    btr rax, 63".to_string());
+            self.text_section.push("    test rax, rax".to_string());
+            self.text_section.push("    jnz .cache".to_string());
+            self.text_section.push("    mov rax, 1  ; 0 significa \"no calculado\"".to_string());
+            self.text_section.push(".cache:".to_string());
+            self.text_section.push("    mov [rcx + 24], rax  ; cachear hash".to_string());
+            self.text_section.push(".done:".to_string());
+            self.generate_abi_epilogue(false);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_keys_equal: comparar dos claves
+        if deps.should_generate("dict_keys_equal") {
+            // Parámetros: RCX = clave a, RDX = clave b, R8 = tipo de clave
+            // Retorna: RAX = 1 si son iguales, 0 si no
+            self.text_section.push("dict_keys_equal:".to_string());
+            self.generate_abi_prologue(false);
+            self.text_section.push("    xor rax, rax".to_string());
+            self.text_section.push("    cmp rcx, rdx".to_string());
+            self.text_section.push("    je .equal  ; mismo entero o mismo String".to_string());
+            self.text_section.push("    cmp r8, 1".to_string());
+            self.text_section.push("    jne .done  ; enteros distintos".to_string());
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz .done".to_string());
+            self.text_section.push("    test rdx, rdx".to_string());
+            self.text_section.push("    jz .done".to_string());
+            self.text_section.push("    mov r9, [rcx + 8]".to_string());
+            self.text_section.push("    cmp r9, [rdx + 8]  ; comparar longitudes".to_string());
+            self.text_section.push("    jne .done".to_string());
+            self.text_section.push("    mov rsi, [rcx + 0]".to_string());
+            self.text_section.push("    mov rdi, [rdx + 0]".to_string());
+            self.text_section.push("    mov rcx, r9".to_string());
+            self.text_section.push("    cld".to_string());
+            self.text_section.push("    repe cmpsb  ; comparar bytes".to_string());
+            self.text_section.push("    jne .done".to_string());
+            self.text_section.push(".equal:".to_string());
+            self.text_section.push("    mov rax, 1".to_string());
+            self.text_section.push(".done:".to_string());
+            self.generate_abi_epilogue(false);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_find: buscar una clave con sondeo lineal
+        if deps.should_generate("dict_find") {
+            // Parámetros: RCX = puntero al Dict, RDX = clave
+            // Retorna: RAX = slot del índice con la clave (-1 si no está),
+            // RDX = primer slot libre de la secuencia de sondeo, R8 = hash de la clave
+            self.text_section.push("dict_find:".to_string());
+            self.generate_abi_prologue(true);
+            self.text_section.push("    mov r12, rcx  ; dict".to_string());
+            self.text_section.push("    mov r13, rdx  ; clave".to_string());
+            self.text_section.push("    mov rcx, r13".to_string());
+            self.text_section.push("    mov rdx, [r12 + 40]  ; key_kind".to_string());
+            self.text_section.push("    call dict_hash_key".to_string());
+            self.text_section.push("    mov r14, rax  ; hash".to_string());
+            self.text_section.push("    mov r15, [r12 + 24]".to_string());
+            self.text_section.push("    dec r15  ; máscara = capacity - 1".to_string());
+            self.text_section.push("    mov rbx, r14".to_string());
+            self.text_section.push("    and rbx, r15  ; slot inicial".to_string());
+            self.text_section.push("    mov rdi, -1  ; primer slot libre (ninguno todavía)".to_string());
+            self.text_section.push(".probe:".to_string());
+            self.text_section.push("    mov rsi, [r12 + 32]  ; index".to_string());
+            self.text_section.push("    mov rax, [rsi + rbx*8]".to_string());
+            self.text_section.push("    test rax, rax".to_string());
+            self.text_section.push("    jz .empty".to_string());
+            self.text_section.push("    cmp rax, -1".to_string());
+            self.text_section.push("    je .deleted".to_string());
+            self.text_section.push("    dec rax".to_string());
+            self.text_section.push("    imul rax, rax, 24".to_string());
+            self.text_section.push("    add rax, [r12 + 0]  ; entrada".to_string());
+            self.text_section.push("    cmp [rax], r14  ; comparar hash antes que la clave".to_string());
+            self.text_section.push("    jne .next".to_string());
+            self.text_section.push("    mov rcx, [rax + 8]".to_string());
+            self.text_section.push("    mov rdx, r13".to_string());
+            self.text_section.push("    mov r8, [r12 + 40]".to_string());
+            self.text_section.push("    call dict_keys_equal".to_string());
+            self.text_section.push("    test rax, rax".to_string());
+            self.text_section.push("    jnz .found".to_string());
+            self.text_section.push("    jmp .next".to_string());
+            self.text_section.push(".deleted:".to_string());
+            self.text_section.push("    cmp rdi, -1".to_string());
+            self.text_section.push("    jne .next".to_string());
+            self.text_section.push("    mov rdi, rbx  ; un slot borrado se puede reutilizar".to_string());
+            self.text_section.push(".next:".to_string());
+            self.text_section.push("    inc rbx".to_string());
+            self.text_section.push("    and rbx, r15  ; sondeo circular".to_string());
+            self.text_section.push("    jmp .probe".to_string());
+            self.text_section.push(".empty:".to_string());
+            self.text_section.push("    cmp rdi, -1".to_string());
+            self.text_section.push("    jne .missing".to_string());
+            self.text_section.push("    mov rdi, rbx".to_string());
+            self.text_section.push(".missing:".to_string());
+            self.text_section.push("    mov rax, -1".to_string());
+            self.text_section.push("    jmp .done".to_string());
+            self.text_section.push(".found:".to_string());
+            self.text_section.push("    mov rax, rbx".to_string());
+            self.text_section.push(".done:".to_string());
+            self.text_section.push("    mov rdx, rdi  ; slot libre".to_string());
+            self.text_section.push("    mov r8, r14  ; hash".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_new: crear un Dict vacío
+        if deps.should_generate("dict_new") {
+            // Parámetros: RCX = número de entradas previsto, RDX = tipo de clave (0 = int, 1 = string, -1 = sin determinar)
+            // Retorna: RAX = puntero al Dict (en heap)
+            self.text_section.push("dict_new:".to_string());
+            self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
+            self.text_section.push("    mov r12, rcx  ; entradas previstas".to_string());
+            self.text_section.push("    mov r13, rdx  ; key_kind".to_string());
+            self.text_section.push("    ; capacity: potencia de 2 (mínimo 8) con carga <= 2/3".to_string());
+            self.text_section.push("    mov r14, 8".to_string());
+            self.text_section.push("    lea rax, [r12 + r12*2]  ; entradas * 3".to_string());
+            self.text_section.push(".grow:".to_string());
+            self.text_section.push("    mov rcx, r14".to_string());
+            self.text_section.push("    shl rcx, 1  ; capacity * 2".to_string());
+            self.text_section.push("    cmp rcx, rax".to_string());
+            self.text_section.push("    jae .capacity_ok".to_string());
+            self.text_section.push("    shl r14, 1".to_string());
+            self.text_section.push("    jmp .grow".to_string());
+            self.text_section.push(".capacity_ok:".to_string());
+            self.text_section.push("    ; Allocar cabecera (48 bytes)".to_string());
+            self.ensure_stack_alignment_before_call("heap_alloc (Dict)");
+            self.text_section.push("    mov rcx, 48".to_string());
+            self.text_section.push("    call heap_alloc".to_string());
+            self.text_section.push("    mov r15, rax  ; puntero al Dict".to_string());
+            self.text_section.push("    ; Allocar índice (capacity * 8 bytes, en cero = slots vacíos)".to_string());
+            self.text_section.push("    mov rcx, r14".to_string());
+            self.text_section.push("    shl rcx, 3  ; tamaño en bytes".to_string());
+            self.ensure_stack_alignment_before_call("heap_alloc (índice)");
+            self.text_section.push("    call heap_alloc".to_string());
+            self.text_section.push("    mov [r15 + 32], rax  ; index".to_string());
+            self.text_section.push("    ; Allocar entradas (capacity * 24 bytes)".to_string());
+            self.text_section.push("    imul rcx, r14, 24  ; tamaño en bytes".to_string());
+            self.ensure_stack_alignment_before_call("heap_alloc (entradas)");
+            self.text_section.push("    call heap_alloc".to_string());
+            self.text_section.push("    mov [r15 + 0], rax  ; entries".to_string());
+            self.text_section.push("    mov qword [r15 + 8], 0  ; count = 0".to_string());
+            self.text_section.push("    mov qword [r15 + 16], 0  ; used = 0".to_string());
+            self.text_section.push("    mov [r15 + 24], r14  ; capacity".to_string());
+            self.text_section.push("    mov [r15 + 40], r13  ; key_kind".to_string());
+            self.text_section.push("    mov rax, r15".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_resize: reconstruir la tabla para una entrada más
+        if deps.should_generate("dict_resize") {
+            // Parámetros: RCX = puntero al Dict
+            // Compacta las entradas borradas conservando el orden de inserción
+            // y deja la carga en <= 1/2 con count + 1 entradas
+            self.text_section.push("dict_resize:".to_string());
+            self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc/heap_free
+            self.text_section.push("    mov r12, rcx  ; dict".to_string());
+            self.text_section.push("    ; nueva capacity: potencia de 2 (mínimo 8) >= (count + 1) * 2".to_string());
+            self.text_section.push("    mov rax, [r12 + 8]".to_string());
+            self.text_section.push("    inc rax".to_string());
+            self.text_section.push("    shl rax, 1".to_string());
+            self.text_section.push("    mov r13, 8".to_string());
+            self.text_section.push(".grow:".to_string());
+            self.text_section.push("    cmp r13, rax".to_string());
+            self.text_section.push("    jae .capacity_ok".to_string());
+            self.text_section.push("    shl r13, 1".to_string());
+            self.text_section.push("    jmp .grow".to_string());
+            self.text_section.push(".capacity_ok:".to_string());
+            self.text_section.push("    mov rcx, r13".to_string());
+            self.text_section.push("    shl rcx, 3  ; tamaño en bytes".to_string());
+            self.ensure_stack_alignment_before_call("heap_alloc (índice nuevo)");
+            self.text_section.push("    call heap_alloc".to_string());
+            self.text_section.push("    mov r14, rax  ; índice nuevo".to_string());
+            self.text_section.push("    imul rcx, r13, 24  ; tamaño en bytes".to_string());
+            self.ensure_stack_alignment_before_call("heap_alloc (entradas nuevas)");
+            self.text_section.push("    call heap_alloc".to_string());
+            self.text_section.push("    mov r15, rax  ; entradas nuevas".to_string());
+            self.text_section.push("    ; Copiar las entradas vivas en orden y reinsertarlas en el índice".to_string());
+            self.text_section.push("    mov rsi, [r12 + 0]  ; entradas antiguas".to_string());
+            self.text_section.push("    mov rcx, [r12 + 16]  ; used".to_string());
+            self.text_section.push("    xor rdi, rdi  ; entradas copiadas".to_string());
+            self.text_section.push("    mov r9, r13".to_string());
+            self.text_section.push("    dec r9  ; máscara".to_string());
+            self.text_section.push(".copy:".to_string());
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz .copy_done".to_string());
+            self.text_section.push("    mov rax, [rsi]  ; hash".to_string());
+            self.text_section.push("    cmp rax, -1".to_string());
+            self.text_section.push("    je .skip  ; entrada borrada".to_string());
+            self.text_section.push("    imul rdx, rdi, 24".to_string());
+            self.text_section.push("    mov [r15 + rdx], rax".to_string());
+            self.text_section.push("    mov r8, [rsi + 8]".to_string());
+            self.text_section.push("    mov [r15 + rdx + 8], r8  ; key".to_string());
+            self.text_section.push("    mov r8, [rsi + 16]".to_string());
+            self.text_section.push("    mov [r15 + rdx + 16], r8  ; value".to_string());
+            self.text_section.push("    mov rbx, rax".to_string());
+            self.text_section.push("    and rbx, r9  ; slot inicial".to_string());
+            self.text_section.push(".slot:".to_string());
+            self.text_section.push("    cmp qword [r14 + rbx*8], 0".to_string());
+            self.text_section.push("    je .slot_found".to_string());
+            self.text_section.push("    inc rbx".to_string());
+            self.text_section.push("    and rbx, r9".to_string());
+            self.text_section.push("    jmp .slot".to_string());
+            self.text_section.push(".slot_found:".to_string());
+            self.text_section.push("    lea r8, [rdi + 1]".to_string());
+            self.text_section.push("    mov [r14 + rbx*8], r8  ; slot → entrada + 1".to_string());
+            self.text_section.push("    inc rdi".to_string());
+            self.text_section.push(".skip:".to_string());
+            self.text_section.push("    add rsi, 24".to_string());
+            self.text_section.push("    dec rcx".to_string());
+            self.text_section.push("    jmp .copy".to_string());
+            self.text_section.push(".copy_done:".to_string());
+            self.text_section.push("    mov [r12 + 16], rdi  ; used = count (sin borradas)".to_string());
+            self.text_section.push("    mov [r12 + 24], r13  ; capacity".to_string());
+            self.text_section.push("    ; Liberar índice y entradas antiguos".to_string());
+            self.ensure_stack_alignment_before_call("heap_free (índice antiguo)");
+            self.text_section.push("    mov rcx, [r12 + 32]".to_string());
+            self.text_section.push("    call heap_free".to_string());
+            self.ensure_stack_alignment_before_call("heap_free (entradas antiguas)");
+            self.text_section.push("    mov rcx, [r12 + 0]".to_string());
+            self.text_section.push("    call heap_free".to_string());
+            self.text_section.push("    mov [r12 + 32], r14  ; index = índice nuevo".to_string());
+            self.text_section.push("    mov [r12 + 0], r15  ; entries = entradas nuevas".to_string());
+            self.text_section.push("    mov rax, r12".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_set: insertar o reemplazar un valor
+        if deps.should_generate("dict_set") {
+            // Parámetros: RCX = puntero al Dict, RDX = clave, R8 = valor, R9 = tipo de la clave
+            // Retorna: RAX = puntero al Dict
+            self.text_section.push("dict_set:".to_string());
+            self.generate_abi_prologue(true);
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov r12, rcx  ; dict".to_string());
+            self.text_section.push("    mov r13, rdx  ; clave".to_string());
+            self.text_section.push("    mov r14, r8  ; valor".to_string());
+            self.text_section.push("    cmp qword [r12 + 40], -1".to_string());
+            self.text_section.push("    jne .kind_ok".to_string());
+            self.text_section.push("    mov [r12 + 40], r9  ; la primera clave fija el tipo de clave".to_string());
+            self.text_section.push(".kind_ok:".to_string());
+            self.text_section.push("    mov rcx, r12".to_string());
+            self.text_section.push("    mov rdx, r13".to_string());
+            self.text_section.push("    call dict_find".to_string());
+            self.text_section.push("    cmp rax, -1".to_string());
+            self.text_section.push("    jne .update".to_string());
+            self.text_section.push("    ; Clave nueva: crecer si (used + 1) * 3 > capacity * 2".to_string());
+            self.text_section.push("    mov rcx, [r12 + 16]".to_string());
+            self.text_section.push("    inc rcx".to_string());
+            self.text_section.push("    imul rcx, rcx, 3".to_string());
+            self.text_section.push("    mov r9, [r12 + 24]".to_string());
+            self.text_section.push("    shl r9, 1".to_string());
+            self.text_section.push("    cmp rcx, r9".to_string());
+            self.text_section.push("    jbe .insert".to_string());
+            self.text_section.push("    mov rcx, r12".to_string());
+            self.text_section.push("    call dict_resize".to_string());
+            self.text_section.push("    mov rcx, r12".to_string());
+            self.text_section.push("    mov rdx, r13".to_string());
+            self.text_section.push("    call dict_find  ; slot libre en la tabla nueva".to_string());
+            self.text_section.push(".insert:".to_string());
+            self.text_section.push("    mov rbx, rdx  ; slot libre".to_string());
+            self.text_section.push("    mov r15, r8  ; hash".to_string());
+            self.text_section.push("    mov rax, [r12 + 16]  ; la entrada nueva va después de las ocupadas".to_string());
+            self.text_section.push("    imul rdi, rax, 24".to_string());
+            self.text_section.push("    add rdi, [r12 + 0]".to_string());
+            self.text_section.push("    mov [rdi], r15  ; hash".to_string());
+            self.text_section.push("    mov [rdi + 8], r13  ; key".to_string());
+            self.text_section.push("    mov [rdi + 16], r14  ; value".to_string());
+            self.text_section.push("    inc rax".to_string());
+            self.text_section.push("    mov rsi, [r12 + 32]".to_string());
+            self.text_section.push("    mov [rsi + rbx*8], rax  ; slot → entrada + 1".to_string());
+            self.text_section.push("    mov [r12 + 16], rax  ; used++".to_string());
+            self.text_section.push("    inc qword [r12 + 8]  ; count++".to_string());
+            self.text_section.push("    jmp .done".to_string());
+            self.text_section.push(".update:".to_string());
+            self.text_section.push("    mov rsi, [r12 + 32]".to_string());
+            self.text_section.push("    mov rax, [rsi + rax*8]".to_string());
+            self.text_section.push("    dec rax".to_string());
+            self.text_section.push("    imul rax, rax, 24".to_string());
+            self.text_section.push("    add rax, [r12 + 0]".to_string());
+            self.text_section.push("    mov [rax + 16], r14  ; reemplazar valor (la clave conserva su posición)".to_string());
+            self.text_section.push(".done:".to_string());
+            self.text_section.push("    mov rax, r12".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_get: valor de una clave o un valor por defecto
+        if deps.should_generate("dict_get") {
+            // Parámetros: RCX = puntero al Dict, RDX = clave, R8 = valor por defecto
+            // Retorna: RAX = valor
+            self.text_section.push("dict_get:".to_string());
+            self.generate_abi_prologue(true);
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov r12, rcx  ; dict".to_string());
+            self.text_section.push("    mov r14, r8  ; valor por defecto".to_string());
+            self.text_section.push("    call dict_find".to_string());
+            self.text_section.push("    cmp rax, -1".to_string());
+            self.text_section.push("    je .missing".to_string());
+            self.text_section.push("    mov rsi, [r12 + 32]".to_string());
+            self.text_section.push("    mov rax, [rsi + rax*8]".to_string());
+            self.text_section.push("    dec rax".to_string());
+            self.text_section.push("    imul rax, rax, 24".to_string());
+            self.text_section.push("    add rax, [r12 + 0]".to_string());
+            self.text_section.push("    mov rax, [rax + 16]  ; value".to_string());
+            self.text_section.push("    jmp .done".to_string());
+            self.text_section.push(".missing:".to_string());
+            self.text_section.push("    mov rax, r14".to_string());
+            self.text_section.push(".done:".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_index: d[clave], la clave tiene que existir
+        if deps.should_generate("dict_index") {
+            // Parámetros: RCX = puntero al Dict, RDX = clave
+            // Retorna: RAX = valor (panic si la clave no está)
+            self.text_section.push("dict_index:".to_string());
+            self.generate_abi_prologue(true);
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov r12, rcx  ; dict".to_string());
+            self.text_section.push("    call dict_find".to_string());
+            self.text_section.push("    cmp rax, -1".to_string());
+            self.text_section.push("    je panic_key_not_found".to_string());
+            self.text_section.push("    mov rsi, [r12 + 32]".to_string());
+            self.text_section.push("    mov rax, [rsi + rax*8]".to_string());
+            self.text_section.push("    dec rax".to_string());
+            self.text_section.push("    imul rax, rax, 24".to_string());
+            self.text_section.push("    add rax, [r12 + 0]".to_string());
+            self.text_section.push("    mov rax, [rax + 16]  ; value".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_contains: ¿está la clave?
+        if deps.should_generate("dict_contains") {
+            // Parámetros: RCX = puntero al Dict, RDX = clave
+            // Retorna: RAX = 1 si está, 0 si no
+            self.text_section.push("dict_contains:".to_string());
+            self.generate_abi_prologue(true);
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    call dict_find".to_string());
+            self.text_section.push("    cmp rax, -1".to_string());
+            self.text_section.push("    setne al".to_string());
+            self.text_section.push("    movzx rax, al".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_delete: borrar una clave
+        if deps.should_generate("dict_delete") {
+            // Parámetros: RCX = puntero al Dict, RDX = clave
+            // Retorna: RAX = 1 si la clave estaba, 0 si no
+            self.text_section.push("dict_delete:".to_string());
+            self.generate_abi_prologue(true);
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov r12, rcx  ; dict".to_string());
+            self.text_section.push("    call dict_find".to_string());
+            self.text_section.push("    cmp rax, -1".to_string());
+            self.text_section.push("    je .missing".to_string());
+            self.text_section.push("    mov rsi, [r12 + 32]".to_string());
+            self.text_section.push("    mov rdx, [rsi + rax*8]".to_string());
+            self.text_section.push("    mov qword [rsi + rax*8], -1  ; slot borrado (el sondeo sigue de largo)".to_string());
+            self.text_section.push("    dec rdx".to_string());
+            self.text_section.push("    imul rdx, rdx, 24".to_string());
+            self.text_section.push("    add rdx, [r12 + 0]".to_string());
+            self.text_section.push("    mov qword [rdx], -1  ; entrada borrada (se omite al iterar)".to_string());
+            self.text_section.push("    dec qword [r12 + 8]  ; count--".to_string());
+            self.text_section.push("    mov rax, 1".to_string());
+            self.text_section.push("    jmp .done".to_string());
+            self.text_section.push(".missing:".to_string());
+            self.text_section.push("    xor rax, rax".to_string());
+            self.text_section.push(".done:".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_len: número de entradas
+        if deps.should_generate("dict_len") {
+            // Parámetros: RCX = puntero al Dict
+            // Retorna: RAX = count
+            self.text_section.push("dict_len:".to_string());
+            self.generate_abi_prologue(false);
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov rax, [rcx + 8]  ; count".to_string());
+            self.generate_abi_epilogue(false);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_collect: copiar claves, valores o pares a un Array nuevo
+        if deps.should_generate("dict_collect") {
+            // Parámetros: RCX = puntero al Dict, RDX = qué copiar (8 = claves, 16 = valores, 0 = pares [clave, valor])
+            // Retorna: RAX = puntero al Array, en orden de inserción
+            self.text_section.push("dict_collect:".to_string());
+            self.generate_abi_prologue(true);  // Necesita shadow space para heap_alloc
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov r12, rcx  ; dict".to_string());
+            self.text_section.push("    mov r13, rdx  ; offset dentro de la entrada (0 = pares)".to_string());
+            self.text_section.push("    ; capacity del Array: max(count, 4)".to_string());
+            self.text_section.push("    mov r14, [r12 + 8]".to_string());
+            self.text_section.push("    cmp r14, 4".to_string());
+            self.text_section.push("    jge .capacity_ok".to_string());
+            self.text_section.push("    mov r14, 4".to_string());
+            self.text_section.push(".capacity_ok:".to_string());
+            self.ensure_stack_alignment_before_call("heap_alloc (Array struct)");
+            self.text_section.push("    mov rcx, 24".to_string());
+            self.text_section.push("    call heap_alloc".to_string());
+            self.text_section.push("    mov r15, rax  ; puntero al Array".to_string());
+            self.text_section.push("    mov rcx, r14".to_string());
+            self.text_section.push("    shl rcx, 3  ; tamaño en bytes".to_string());
+            self.ensure_stack_alignment_before_call("heap_alloc (data)");
+            self.text_section.push("    call heap_alloc".to_string());
+            self.text_section.push("    mov [r15 + 0], rax  ; data".to_string());
+            self.text_section.push("    mov rax, [r12 + 8]".to_string());
+            self.text_section.push("    mov [r15 + 8], rax  ; length = count".to_string());
+            self.text_section.push("    mov [r15 + 16], r14  ; capacity".to_string());
+            self.text_section.push("    ; Los pares [clave, valor] comparten un bloque de capacity * 16 bytes".to_string());
+            self.text_section.push("    xor rbx, rbx".to_string());
+            self.text_section.push("    test r13, r13".to_string());
+            self.text_section.push("    jnz .pairs_ok".to_string());
+            self.text_section.push("    mov rcx, r14".to_string());
+            self.text_section.push("    shl rcx, 4  ; tamaño en bytes".to_string());
+            self.ensure_stack_alignment_before_call("heap_alloc (pares)");
+            self.text_section.push("    call heap_alloc".to_string());
+            self.text_section.push("    mov rbx, rax  ; siguiente par libre".to_string());
+            self.text_section.push(".pairs_ok:".to_string());
+            self.text_section.push("    mov rsi, [r12 + 0]  ; entradas".to_string());
+            self.text_section.push("    mov rcx, [r12 + 16]  ; used".to_string());
+            self.text_section.push("    mov rdi, [r15 + 0]  ; destino".to_string());
+            self.text_section.push(".copy:".to_string());
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz .done".to_string());
+            self.text_section.push("    cmp qword [rsi], -1".to_string());
+            self.text_section.push("    je .next  ; entrada borrada".to_string());
+            self.text_section.push("    test r13, r13".to_string());
+            self.text_section.push("    jz .pair".to_string());
+            self.text_section.push("    mov rax, [rsi + r13]  ; clave o valor".to_string());
+            self.text_section.push("    jmp .store".to_string());
+            self.text_section.push(".pair:".to_string());
+            self.text_section.push("    mov rax, [rsi + 8]".to_string());
+            self.text_section.push("    mov [rbx], rax  ; par[0] = clave".to_string());
+            self.text_section.push("    mov rax, [rsi + 16]".to_string());
+            self.text_section.push("    mov [rbx + 8], rax  ; par[1] = valor".to_string());
+            self.text_section.push("    mov rax, rbx".to_string());
+            self.text_section.push("    add rbx, 16".to_string());
+            self.text_section.push(".store:".to_string());
+            self.text_section.push("    mov [rdi], rax".to_string());
+            self.text_section.push("    add rdi, 8".to_string());
+            self.text_section.push(".next:".to_string());
+            self.text_section.push("    add rsi, 24".to_string());
+            self.text_section.push("    dec rcx".to_string());
+            self.text_section.push("    jmp .copy".to_string());
+            self.text_section.push(".done:".to_string());
+            self.text_section.push("    mov rax, r15".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // dict_keys / dict_values / dict_items: envoltorios de dict_collect
+        // Parámetros: RCX = puntero al Dict. Retorna: RAX = puntero al Array
+        for (name, what, offset) in [("dict_keys", "claves", 8), ("dict_values", "valores", 16), ("dict_items", "pares", 0)] {
+            if deps.should_generate(name) {
+                self.text_section.push(format!("{}:", name));
+                self.text_section.push(format!("    mov rdx, {}  ; {}", offset, what));
+                self.text_section.push("    jmp dict_collect".to_string());
+                self.text_section.push("".to_string());
+            }
+        }
+
+        // set_combine: unión, intersección o diferencia de dos sets (un Set es un Dict con valores en 1)
+        if deps.should_generate("set_combine") {
+            // Parámetros: RCX = set a, RDX = set b, R8 = operación (0 = unión, 1 = intersección, 2 = diferencia)
+            // Retorna: RAX = puntero al Set nuevo (orden: elementos de a y luego los nuevos de b)
+            self.text_section.push("set_combine:".to_string());
+            self.generate_abi_prologue(true);
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    test rdx, rdx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov r12, rcx  ; set a".to_string());
+            self.text_section.push("    mov r13, rdx  ; set b".to_string());
+            self.text_section.push("    mov r14, r8  ; operación".to_string());
+            self.text_section.push("    ; Set resultado con capacidad para count(a) + count(b)".to_string());
+            self.text_section.push("    mov rcx, [r12 + 8]".to_string());
+            self.text_section.push("    add rcx, [r13 + 8]".to_string());
+            self.text_section.push("    mov rdx, [r12 + 40]  ; key_kind de a".to_string());
+            self.text_section.push("    cmp rdx, -1".to_string());
+            self.text_section.push("    jne .kind_ok".to_string());
+            self.text_section.push("    mov rdx, [r13 + 40]  ; a vacío desde set(): usar el de b".to_string());
+            self.text_section.push(".kind_ok:".to_string());
+            self.text_section.push("    call dict_new".to_string());
+            self.text_section.push("    mov r15, rax  ; resultado".to_string());
+            self.text_section.push("    xor rbx, rbx  ; entrada de a".to_string());
+            self.text_section.push(".loop_a:".to_string());
+            self.text_section.push("    cmp rbx, [r12 + 16]  ; used".to_string());
+            self.text_section.push("    jae .a_done".to_string());
+            self.text_section.push("    imul rsi, rbx, 24".to_string());
+            self.text_section.push("    add rsi, [r12 + 0]".to_string());
+            self.text_section.push("    cmp qword [rsi], -1".to_string());
+            self.text_section.push("    je .next_a  ; entrada borrada".to_string());
+            self.text_section.push("    mov rdi, [rsi + 8]  ; elemento".to_string());
+            self.text_section.push("    test r14, r14".to_string());
+            self.text_section.push("    jz .add_a  ; unión: todos los de a".to_string());
+            self.text_section.push("    mov rcx, r13".to_string());
+            self.text_section.push("    mov rdx, rdi".to_string());
+            self.text_section.push("    call dict_contains".to_string());
+            self.text_section.push("    cmp r14, 1".to_string());
+            self.text_section.push("    jne .difference".to_string());
+            self.text_section.push("    test rax, rax".to_string());
+            self.text_section.push("    jz .next_a  ; intersección: solo si está en b".to_string());
+            self.text_section.push("    jmp .add_a".to_string());
+            self.text_section.push(".difference:".to_string());
+            self.text_section.push("    test rax, rax".to_string());
+            self.text_section.push("    jnz .next_a  ; diferencia: solo si no está en b".to_string());
+            self.text_section.push(".add_a:".to_string());
+            self.text_section.push("    mov rcx, r15".to_string());
+            self.text_section.push("    mov rdx, rdi".to_string());
+            self.text_section.push("    mov r8, 1".to_string());
+            self.text_section.push("    mov r9, [r12 + 40]".to_string());
+            self.text_section.push("    call dict_set".to_string());
+            self.text_section.push(".next_a:".to_string());
+            self.text_section.push("    inc rbx".to_string());
+            self.text_section.push("    jmp .loop_a".to_string());
+            self.text_section.push(".a_done:".to_string());
+            self.text_section.push("    test r14, r14".to_string());
+            self.text_section.push("    jnz .done".to_string());
+            self.text_section.push("    ; Unión: agregar los elementos de b (los repetidos no se duplican)".to_string());
+            self.text_section.push("    xor rbx, rbx".to_string());
+            self.text_section.push(".loop_b:".to_string());
+            self.text_section.push("    cmp rbx, [r13 + 16]".to_string());
+            self.text_section.push("    jae .done".to_string());
+            self.text_section.push("    imul rsi, rbx, 24".to_string());
+            self.text_section.push("    add rsi, [r13 + 0]".to_string());
+            self.text_section.push("    cmp qword [rsi], -1".to_string());
+            self.text_section.push("    je .next_b".to_string());
+            self.text_section.push("    mov rcx, r15".to_string());
+            self.text_section.push("    mov rdx, [rsi + 8]".to_string());
+            self.text_section.push("    mov r8, 1".to_string());
+            self.text_section.push("    mov r9, [r13 + 40]".to_string());
+            self.text_section.push("    call dict_set".to_string());
+            self.text_section.push(".next_b:".to_string());
+            self.text_section.push("    inc rbx".to_string());
+            self.text_section.push("    jmp .loop_b".to_string());
+            self.text_section.push(".done:".to_string());
+            self.text_section.push("    mov rax, r15".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+
+        // set_union / set_intersection / set_difference: envoltorios de set_combine
+        // Parámetros: RCX = set a, RDX = set b. Retorna: RAX = puntero al Set nuevo
+        for (name, op) in [("set_union", 0), ("set_intersection", 1), ("set_difference", 2)] {
+            if deps.should_generate(name) {
+                self.text_section.push(format!("{}:", name));
+                self.text_section.push(format!("    mov r8, {}  ; operación", op));
+                self.text_section.push("    jmp set_combine".to_string());
+                self.text_section.push("".to_string());
+            }
+        }
+
+        // set_from_array: set(lista), sin duplicados y en el orden de la lista
+        if deps.should_generate("set_from_array") {
+            // Parámetros: RCX = puntero al Array, RDX = tipo de los elementos (0 = int, 1 = string)
+            // Retorna: RAX = puntero al Set nuevo
+            self.text_section.push("set_from_array:".to_string());
+            self.generate_abi_prologue(true);
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov r12, rcx  ; array".to_string());
+            self.text_section.push("    mov r13, rdx  ; key_kind".to_string());
+            self.text_section.push("    mov rcx, [r12 + 8]  ; length como capacidad prevista".to_string());
+            self.text_section.push("    mov rdx, r13".to_string());
+            self.text_section.push("    call dict_new".to_string());
+            self.text_section.push("    mov r15, rax  ; resultado".to_string());
+            self.text_section.push("    xor rbx, rbx".to_string());
+            self.text_section.push(".loop:".to_string());
+            self.text_section.push("    cmp rbx, [r12 + 8]".to_string());
+            self.text_section.push("    jge .done".to_string());
+            self.text_section.push("    mov rsi, [r12 + 0]  ; data".to_string());
+            self.text_section.push("    mov rcx, r15".to_string());
+            self.text_section.push("    mov rdx, [rsi + rbx*8]".to_string());
+            self.text_section.push("    mov r8, 1".to_string());
+            self.text_section.push("    mov r9, r13".to_string());
+            self.text_section.push("    call dict_set".to_string());
+            self.text_section.push("    inc rbx".to_string());
+            self.text_section.push("    jmp .loop".to_string());
+            self.text_section.push(".done:".to_string());
+            self.text_section.push("    mov rax, r15".to_string());
+            self.generate_abi_epilogue(true);
+            self.text_section.push("".to_string());
+        }
+    }
+
+    /// Generar funciones helper de float64 en NASM (solo las que se usan)
+    /// Los floats viajan como bits crudos en registros generales (convención de los helpers)
+    fn generate_float_helpers_nasm_selective(&mut self, deps: &DependencyGraph) {
+        // Constantes de los helpers (bits exactos: el ensamblador no redondea)
+        for (label, value) in [
+            ("float_rt_one", 1.0f64),
+            ("float_rt_ten", 10.0),
+            ("float_rt_scale", 1.0e6),
+            ("float_rt_big", 1.0e15),
+            ("float_rt_small", 1.0e-5),
+        ] {
+            self.data_section.push(format!("{}: dq 0x{:016X}  ; {:e}", label, value.to_bits(), value));
+        }
+
+        // float_to_str: formatear un float64 en un buffer
+        if deps.should_generate("float_to_str") {
+            // Parámetros: RCX = float64 (bits), RDX = buffer (al menos 32 bytes)
+            // Retorna: RAX = longitud escrita (sin newline)
+            // Formato: hasta 6 decimales sin ceros finales (`2.5`, `3`, `-0.125`); notación
+            // científica fuera de [1e-5, 1e15) (`1.5e20`, `2e-7`); `nan`, `inf` y `-inf`
+            self.text_section.push("float_to_str:".to_string());
+            self.generate_abi_prologue(false);
+            self.text_section.push("    mov r12, rdx  ; inicio del buffer".to_string());
+            self.text_section.push("    mov rdi, rdx  ; cursor de escritura".to_string());
+            self.text_section.push("    movq xmm0, rcx".to_string());
+            self.text_section.push("    ucomisd xmm0, xmm0  ; NaN es el único valor distinto de sí mismo".to_string());
+            self.text_section.push("    jp .nan".to_string());
+            self.text_section.push("    btr rcx, 63  ; valor absoluto: el bit 63 es el signo (también en -0.0)".to_string());
+            self.text_section.push("    jnc .abs".to_string());
+            self.text_section.push("    mov byte [rdi], '-'".to_string());
+            self.text_section.push("    inc rdi".to_string());
+            self.text_section.push(".abs:".to_string());
+            self.text_section.push("    movq xmm0, rcx".to_string());
+            self.text_section.push("    mov rax, 0x7FF0000000000000  ; exponente todo a 1 y mantisa 0: infinito".to_string());
+            self.text_section.push("    cmp rcx, rax".to_string());
+            self.text_section.push("    je .inf".to_string());
+            self.text_section.push("    xor r13, r13  ; exponente decimal (notación científica)".to_string());
+            self.text_section.push("    ucomisd xmm0, [rel float_rt_big]".to_string());
+            self.text_section.push("    jae .scale_down".to_string());
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz .fixed  ; 0.0".to_string());
+            self.text_section.push("    ucomisd xmm0, [rel float_rt_small]".to_string());
+            self.text_section.push("    jb .scale_up".to_string());
+            self.text_section.push("    jmp .fixed".to_string());
+            self.text_section.push(".scale_down:  ; dividir hasta quedar en [1, 10)".to_string());
+            self.text_section.push("    divsd xmm0, [rel float_rt_ten]".to_string());
+            self.text_section.push("    inc r13".to_string());
+            self.text_section.push("    ucomisd xmm0, [rel float_rt_ten]".to_string());
+            self.text_section.push("    jae .scale_down".to_string());
+            self.text_section.push("    jmp .fixed".to_string());
+            self.text_section.push(".scale_up:  ; multiplicar hasta quedar en [1, 10)".to_string());
+            self.text_section.push("    mulsd xmm0, [rel float_rt_ten]".to_string());
+            self.text_section.push("    dec r13".to_string());
+            self.text_section.push("    ucomisd xmm0, [rel float_rt_one]".to_string());
+            self.text_section.push("    jb .scale_up".to_string());
+            self.text_section.push(".fixed:".to_string());
+            self.text_section.push("    ; Parte entera truncada y 6 decimales redondeados al más cercano".to_string());
+            self.text_section.push("    cvttsd2si rax, xmm0".to_string());
+            self.text_section.push("    cvtsi2sd xmm1, rax".to_string());
+            self.text_section.push("    subsd xmm0, xmm1  ; fracción en [0, 1)".to_string());
+            self.text_section.push("    mulsd xmm0, [rel float_rt_scale]".to_string());
+            self.text_section.push("    cvtsd2si rbx, xmm0  ; decimales como entero".to_string());
+            self.text_section.push("    cmp rbx, 1000000".to_string());
+            self.text_section.push("    jb .carry_done".to_string());
+            self.text_section.push("    sub rbx, 1000000  ; el redondeo pasa a la parte entera (x.9999996)".to_string());
+            self.text_section.push("    inc rax".to_string());
+            self.text_section.push("    test r13, r13".to_string());
+            self.text_section.push("    jz .carry_done".to_string());
+            self.text_section.push("    cmp rax, 10".to_string());
+            self.text_section.push("    jb .carry_done".to_string());
+            self.text_section.push("    mov rax, 1  ; 9.9999999e20 -> 1e21".to_string());
+            self.text_section.push("    inc r13".to_string());
+            self.text_section.push(".carry_done:".to_string());
+            self.text_section.push("    mov rsi, 10".to_string());
+            self.text_section.push("    call .write_uint".to_string());
+            self.text_section.push("    test rbx, rbx".to_string());
+            self.text_section.push("    jz .exponent".to_string());
+            self.text_section.push("    mov byte [rdi], '.'".to_string());
+            self.text_section.push("    inc rdi".to_string());
+            self.text_section.push("    mov rax, rbx".to_string());
+            self.text_section.push("    mov rcx, 6".to_string());
+            self.text_section.push(".frac_loop:  ; 6 dígitos con ceros a la izquierda, de derecha a izquierda".to_string());
+            self.text_section.push("    xor rdx, rdx".to_string());
+            self.text_section.push("    div rsi".to_string());
+            self.text_section.push("    add dl, '0'".to_string());
+            self.text_section.push("    mov [rdi + rcx - 1], dl".to_string());
+            self.text_section.push("    dec rcx".to_string());
+            self.text_section.push("    jnz .frac_loop".to_string());
+            self.text_section.push("    add rdi, 6".to_string());
+            self.text_section.push(".trim:  ; quitar ceros finales (hay al menos un dígito distinto de 0)".to_string());
+            self.text_section.push("    cmp byte [rdi - 1], '0'".to_string());
+            self.text_section.push("    jne .exponent".to_string());
+            self.text_section.push("    dec rdi".to_string());
+            self.text_section.push("    jmp .trim".to_string());
+            self.text_section.push(".exponent:".to_string());
+            self.text_section.push("    test r13, r13".to_string());
+            self.text_section.push("    jz .done".to_string());
+            self.text_section.push("    mov byte [rdi], 'e'".to_string());
+            self.text_section.push("    inc rdi".to_string());
+            self.text_section.push("    mov rax, r13".to_string());
+            self.text_section.push("    test rax, rax".to_string());
+            self.text_section.push("    jns .exponent_digits".to_string());
+            self.text_section.push("    mov byte [rdi], '-'".to_string());
+            self.text_section.push("    inc rdi".to_string());
+            self.text_section.push("    neg rax".to_string());
+            self.text_section.push(".exponent_digits:".to_string());
+            self.text_section.push("    call .write_uint".to_string());
+            self.text_section.push("    jmp .done".to_string());
+            self.text_section.push(".nan:".to_string());
+            self.text_section.push("    mov byte [rdi], 'n'".to_string());
+            self.text_section.push("    mov byte [rdi + 1], 'a'".to_string());
+            self.text_section.push("    mov byte [rdi + 2], 'n'".to_string());
+            self.text_section.push("    add rdi, 3".to_string());
+            self.text_section.push("    jmp .done".to_string());
+            self.text_section.push(".inf:".to_string());
+            self.text_section.push("    mov byte [rdi], 'i'".to_string());
+            self.text_section.push("    mov byte [rdi + 1], 'n'".to_string());
+            self.text_section.push("    mov byte [rdi + 2], 'f'".to_string());
+            self.text_section.push("    add rdi, 3".to_string());
+            self.text_section.push(".done:".to_string());
+            self.text_section.push("    mov rax, rdi".to_string());
+            self.text_section.push("    sub rax, r12  ; longitud".to_string());
+            self.generate_abi_epilogue(false);
+            self.text_section.push(".write_uint:  ; RAX = entero sin signo, RSI = 10; escribe en [rdi] y avanza".to_string());
+            self.text_section.push("    xor rcx, rcx".to_string());
+            self.text_section.push(".digit_loop:".to_string());
+            self.text_section.push("    xor rdx, rdx".to_string());
+            self.text_section.push("    div rsi".to_string());
+            self.text_section.push("    add dl, '0'".to_string());
+            self.text_section.push("    push rdx".to_string());
+            self.text_section.push("    inc rcx".to_string());
+            self.text_section.push("    test rax, rax".to_string());
+            self.text_section.push("    jnz .digit_loop".to_string());
+            self.text_section.push(".digit_write:".to_string());
+            self.text_section.push("    pop rax".to_string());
+            self.text_section.push("    mov [rdi], al".to_string());
+            self.text_section.push("    inc rdi".to_string());
+            self.text_section.push("    dec rcx".to_string());
+            self.text_section.push("    jnz .digit_write".to_string());
+            self.text_section.push("    ret".to_string());
+            self.text_section.push("".to_string());
+        }
+
+        // float_from_string: float(s) - parsear un String
+        if deps.should_generate("float_from_string") {
+            // Parámetros: RCX = puntero al String
+            // Retorna: RAX = float64 (bits)
+            // Acepta espacios iniciales, signo, dígitos con punto decimal y exponente
+            // (`-1.5e3`), además de `nan` e `inf`; un texto sin dígitos da NaN
+            self.text_section.push("float_from_string:".to_string());
+            self.generate_abi_prologue(false);
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz panic_null_pointer".to_string());
+            self.text_section.push("    mov rsi, [rcx + 0]  ; data".to_string());
+            self.text_section.push("    mov r8, [rcx + 8]  ; length".to_string());
+            self.text_section.push("    xor r9, r9  ; índice".to_string());
+            self.text_section.push("    xor r10, r10  ; 1 = negativo".to_string());
+            self.text_section.push(".spaces:".to_string());
+            self.text_section.push("    cmp r9, r8".to_string());
+            self.text_section.push("    jae .nan".to_string());
+            self.text_section.push("    cmp byte [rsi + r9], ' '".to_string());
+            self.text_section.push("    jne .sign".to_string());
+            self.text_section.push("    inc r9".to_string());
+            self.text_section.push("    jmp .spaces".to_string());
+            self.text_section.push(".sign:".to_string());
+            self.text_section.push("    movzx eax, byte [rsi + r9]".to_string());
+            self.text_section.push("    cmp al, '-'".to_string());
+            self.text_section.push("    jne .plus".to_string());
+            self.text_section.push("    mov r10, 1".to_string());
+            self.text_section.push("    inc r9".to_string());
+            self.text_section.push("    jmp .special".to_string());
+            self.text_section.push(".plus:".to_string());
+            self.text_section.push("    cmp al, '+'".to_string());
+            self.text_section.push("    jne .special".to_string());
+            self.text_section.push("    inc r9".to_string());
+            self.text_section.push(".special:  ; `nan` / `inf` (sin distinguir mayúsculas)".to_string());
+            self.text_section.push("    cmp r9, r8".to_string());
+            self.text_section.push("    jae .nan".to_string());
+            self.text_section.push("    movzx eax, byte [rsi + r9]".to_string());
+            self.text_section.push("    or al, 0x20".to_string());
+            self.text_section.push("    cmp al, 'n'".to_string());
+            self.text_section.push("    je .nan".to_string());
+            self.text_section.push("    cmp al, 'i'".to_string());
+            self.text_section.push("    je .inf".to_string());
+            self.text_section.push("    xorpd xmm0, xmm0  ; mantisa acumulada".to_string());
+            self.text_section.push("    xor r11, r11  ; dígitos leídos".to_string());
+            self.text_section.push("    xor rdx, rdx  ; exponente decimal".to_string());
+            self.text_section.push("    xor rcx, rcx  ; 1 = después del punto".to_string());
+            self.text_section.push(".digit:".to_string());
+            self.text_section.push("    cmp r9, r8".to_string());
+            self.text_section.push("    jae .end".to_string());
+            self.text_section.push("    movzx eax, byte [rsi + r9]".to_string());
+            self.text_section.push("    cmp al, '.'".to_string());
+            self.text_section.push("    jne .number".to_string());
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jnz .end  ; un segundo punto termina el número".to_string());
+            self.text_section.push("    mov rcx, 1".to_string());
+            self.text_section.push("    inc r9".to_string());
+            self.text_section.push("    jmp .digit".to_string());
+            self.text_section.push(".number:".to_string());
+            self.text_section.push("    sub al, '0'".to_string());
+            self.text_section.push("    cmp al, 9".to_string());
+            self.text_section.push("    ja .exponent  ; no es un dígito".to_string());
+            self.text_section.push("    mulsd xmm0, [rel float_rt_ten]".to_string());
+            self.text_section.push("    cvtsi2sd xmm1, rax".to_string());
+            self.text_section.push("    addsd xmm0, xmm1".to_string());
+            self.text_section.push("    inc r11".to_string());
+            self.text_section.push("    sub rdx, rcx  ; cada decimal baja el exponente".to_string());
+            self.text_section.push("    inc r9".to_string());
+            self.text_section.push("    jmp .digit".to_string());
+            self.text_section.push(".exponent:".to_string());
+            self.text_section.push("    movzx eax, byte [rsi + r9]".to_string());
+            self.text_section.push("    or al, 0x20".to_string());
+            self.text_section.push("    cmp al, 'e'".to_string());
+            self.text_section.push("    jne .end".to_string());
+            self.text_section.push("    inc r9".to_string());
+            self.text_section.push("    xor rbx, rbx  ; valor del exponente".to_string());
+            self.text_section.push("    xor rdi, rdi  ; 1 = exponente negativo".to_string());
+            self.text_section.push("    cmp r9, r8".to_string());
+            self.text_section.push("    jae .end".to_string());
+            self.text_section.push("    movzx eax, byte [rsi + r9]".to_string());
+            self.text_section.push("    cmp al, '-'".to_string());
+            self.text_section.push("    jne .exponent_plus".to_string());
+            self.text_section.push("    mov rdi, 1".to_string());
+            self.text_section.push("    inc r9".to_string());
+            self.text_section.push("    jmp .exponent_digit".to_string());
+            self.text_section.push(".exponent_plus:".to_string());
+            self.text_section.push("    cmp al, '+'".to_string());
+            self.text_section.push("    jne .exponent_digit".to_string());
+            self.text_section.push("    inc r9".to_string());
+            self.text_section.push(".exponent_digit:".to_string());
+            self.text_section.push("    cmp r9, r8".to_string());
+            self.text_section.push("    jae .exponent_apply".to_string());
+            self.text_section.push("    movzx eax, byte [rsi + r9]".to_string());
+            self.text_section.push("    sub al, '0'".to_string());
+            self.text_section.push("    cmp al, 9".to_string());
+            self.text_section.push("    ja .exponent_apply".to_string());
+            self.text_section.push("    imul rbx, rbx, 10".to_string());
+            self.text_section.push("    add rbx, rax".to_string());
+            self.text_section.push("    inc r9".to_string());
+            self.text_section.push("    jmp .exponent_digit".to_string());
+            self.text_section.push(".exponent_apply:".to_string());
+            self.text_section.push("    test rdi, rdi".to_string());
+            self.text_section.push("    jz .exponent_add".to_string());
+            self.text_section.push("    neg rbx".to_string());
+            self.text_section.push(".exponent_add:".to_string());
+            self.text_section.push("    add rdx, rbx".to_string());
+            self.text_section.push(".end:".to_string());
+            self.text_section.push("    test r11, r11".to_string());
+            self.text_section.push("    jz .nan  ; sin dígitos: no es un número".to_string());
+            self.text_section.push("    ; Escala 10^|exp| exacta hasta 1e22, aplicada con una sola operación".to_string());
+            self.text_section.push("    movsd xmm1, [rel float_rt_one]".to_string());
+            self.text_section.push("    mov rcx, rdx".to_string());
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jns .power".to_string());
+            self.text_section.push("    neg rcx".to_string());
+            self.text_section.push(".power:".to_string());
+            self.text_section.push("    test rcx, rcx".to_string());
+            self.text_section.push("    jz .power_done".to_string());
+            self.text_section.push("    mulsd xmm1, [rel float_rt_ten]".to_string());
+            self.text_section.push("    dec rcx".to_string());
+            self.text_section.push("    jmp .power".to_string());
+            self.text_section.push(".power_done:".to_string());
+            self.text_section.push("    test rdx, rdx".to_string());
+            self.text_section.push("    js .power_div".to_string());
+            self.text_section.push("    mulsd xmm0, xmm1".to_string());
+            self.text_section.push("    jmp .apply_sign".to_string());
+            self.text_section.push(".power_div:".to_string());
+            self.text_section.push("    divsd xmm0, xmm1".to_string());
+            self.text_section.push(".apply_sign:".to_string());
+            self.text_section.push("    movq rax, xmm0".to_string());
+            self.text_section.push("    jmp .signed".to_string());
+            self.text_section.push(".nan:".to_string());
+            self.text_section.push("    mov rax, 0x7FF8000000000000  ; NaN silencioso".to_string());
+            self.text_section.push("    jmp .done".to_string());
+            self.text_section.push(".inf:".to_string());
+            self.text_section.push("    mov rax, 0x7FF0000000000000  ; infinito".to_string());
+            self.text_section.push(".signed:".to_string());
+            self.text_section.push("    test r10, r10".to_string());
+            self.text_section.push("    jz .done".to_string());
+            self.text_section.push("    bts rax, 63  ; signo negativo".to_string());
+            self.text_section.push(".done:".to_string());
+            self.generate_abi_epilogue(false);
+            self.text_section.push("".to_string());
+        }
+    }
 }
 
 impl Default for CodeGenerator {
@@ -7114,5 +8186,43 @@ mod tests {
         assert!(asm.contains("sys_write"));
         assert!(asm.contains("Hello"));
     }
+
+    #[test]
+    fn test_generate_respects_explicit_target() {
+        let program = parse(r#"print "Hi""#).unwrap();
+
+        let linux = CodeGenerator::with_target(Target::X86_64Linux).generate(&program).unwrap();
+        assert!(linux.contains("_start"));
+        assert!(!linux.contains("extern WriteFile"));
+
+        let windows = CodeGenerator::with_target(Target::X86_64Windows).generate(&program).unwrap();
+        assert!(windows.contains("extern WriteFile"));
+        assert!(windows.contains("global main"));
+    }
+
+    #[test]
+    fn test_linux_target_supports_windows_features() {
+        // Features que antes solo existían en el backend de Windows
+        let src = r#"
+let arr = [1, 2, 3]
+let x = arr[1]
+print x
+let s = "hola"
+print s
+"#;
+        let program = parse(src).unwrap();
+        let asm = CodeGenerator::with_target(Target::X86_64Linux).generate(&program).unwrap();
+
+        // Entry point ELF que llama a main
+        assert!(asm.contains("global _start"));
+        assert!(asm.contains("call main"));
+        // kernel32 implementado con syscalls, sin externs
+        assert!(asm.contains("WriteFile:"));
+        assert!(asm.contains("VirtualAlloc:"));
+        assert!(asm.contains("ExitProcess:"));
+        assert!(!asm.contains("extern "));
+        // Runtime de arrays disponible también en Linux
+        assert!(asm.contains("array_get"));
+    }
 }
 
@@ -0,0 +1,219 @@
+// Runtime de Linux para ADead - Capa de compatibilidad sobre syscalls
+// El generador principal emite código con la convención Windows x64 (RCX, RDX, R8, R9 +
+// shadow space) y llama a funciones de kernel32. En Linux no existe kernel32, así que
+// este módulo implementa esas mismas funciones con syscalls, manteniendo la misma
+// convención de llamada. Así todo lo que soporta Windows funciona igual en Linux (ELF64).
+//
+// Convención de las funciones de este runtime (igual que kernel32):
+//   - Parámetros: RCX, RDX, R8, R9, resto en stack (después de 32 bytes de shadow space)
+//   - Retorno: RAX
+//   - Preservan RBX, RBP, RDI, RSI, R12-R15 (syscall solo destruye RCX y R11)
+
+pub struct LinuxRuntime;
+
+impl LinuxRuntime {
+    /// Generar entry point `_start` y las funciones de compatibilidad en NASM
+    pub fn generate_runtime_nasm() -> Vec<String> {
+        let mut code = Vec::new();
+
+        code.push("; ============================================".to_string());
+        code.push("; Runtime Linux ADead - Entry point y compatibilidad kernel32 via syscalls".to_string());
+        code.push("; ============================================".to_string());
+        code.push("".to_string());
+
+        // _start: entry point del ELF, prepara el stack como lo haría Windows y llama a main
+        code.push("_start:".to_string());
+        code.push("    ; Entry point ELF: alinear stack y reservar shadow space para main".to_string());
+        code.push("    ; (main guarda el handle de stdout en [rbp+16] como en Windows)".to_string());
+        code.push("    xor rbp, rbp  ; marcar fin de la cadena de frames".to_string());
+        code.push("    and rsp, -16".to_string());
+        code.push("    sub rsp, 32  ; shadow space".to_string());
+        code.push("    call main".to_string());
+        code.push("    mov edi, eax  ; main no retorna (ExitProcess), pero por si acaso".to_string());
+        code.push("    mov eax, 60  ; sys_exit".to_string());
+        code.push("    syscall".to_string());
+        code.push("".to_string());
+
+        // GetStdHandle(nStdHandle): -10 = stdin, -11 = stdout, -12 = stderr → fd 0, 1, 2
+        code.push("GetStdHandle:".to_string());
+        code.push("    ; HANDLE GetStdHandle(DWORD nStdHandle)".to_string());
+        code.push("    ; Parámetros: ECX = -10 (stdin), -11 (stdout), -12 (stderr)".to_string());
+        code.push("    ; Retorna: RAX = file descriptor (0, 1, 2)".to_string());
+        code.push("    movsxd rax, ecx".to_string());
+        code.push("    neg rax".to_string());
+        code.push("    sub rax, 10  ; -10 → 0, -11 → 1, -12 → 2".to_string());
+        code.push("    ret".to_string());
+        code.push("".to_string());
+
+        // WriteFile(hFile, lpBuffer, nNumberOfBytesToWrite, lpNumberOfBytesWritten, lpOverlapped)
+        code.push("WriteFile:".to_string());
+        code.push("    ; BOOL WriteFile(hFile, lpBuffer, nBytes, lpBytesWritten, lpOverlapped)".to_string());
+        code.push("    ; Parámetros: RCX = fd, RDX = buffer, R8 = bytes, R9 = puntero a DWORD (o NULL)".to_string());
+        code.push("    ; Retorna: RAX = 1 si éxito, 0 si error".to_string());
+        code.push("    push rdi".to_string());
+        code.push("    push rsi".to_string());
+        code.push("    mov rdi, rcx  ; fd".to_string());
+        code.push("    mov rsi, rdx  ; buffer".to_string());
+        code.push("    mov rdx, r8  ; count".to_string());
+        code.push("    mov eax, 1  ; sys_write".to_string());
+        code.push("    syscall".to_string());
+        code.push("    jmp linux_rw_result".to_string());
+        code.push("".to_string());
+
+        // ReadFile(hFile, lpBuffer, nNumberOfBytesToRead, lpNumberOfBytesRead, lpOverlapped)
+        code.push("ReadFile:".to_string());
+        code.push("    ; BOOL ReadFile(hFile, lpBuffer, nBytes, lpBytesRead, lpOverlapped)".to_string());
+        code.push("    ; Parámetros: RCX = fd, RDX = buffer, R8 = bytes, R9 = puntero a DWORD (o NULL)".to_string());
+        code.push("    ; Retorna: RAX = 1 si éxito, 0 si error".to_string());
+        code.push("    push rdi".to_string());
+        code.push("    push rsi".to_string());
+        code.push("    mov rdi, rcx  ; fd".to_string());
+        code.push("    mov rsi, rdx  ; buffer".to_string());
+        code.push("    mov rdx, r8  ; count".to_string());
+        code.push("    xor eax, eax  ; sys_read".to_string());
+        code.push("    syscall".to_string());
+        code.push("".to_string());
+
+        // Resultado común de WriteFile/ReadFile: escribir bytes transferidos en *R9 y retornar BOOL
+        code.push("linux_rw_result:".to_string());
+        code.push("    ; RAX = resultado de la syscall (bytes o -errno), R9 = lpBytes (o NULL)".to_string());
+        code.push("    test rax, rax".to_string());
+        code.push("    js .error".to_string());
+        code.push("    test r9, r9".to_string());
+        code.push("    jz .ok".to_string());
+        code.push("    mov [r9], eax  ; DWORD bytes transferidos".to_string());
+        code.push(".ok:".to_string());
+        code.push("    mov eax, 1  ; TRUE".to_string());
+        code.push("    jmp .done".to_string());
+        code.push(".error:".to_string());
+        code.push("    test r9, r9".to_string());
+        code.push("    jz .fail".to_string());
+        code.push("    mov dword [r9], 0".to_string());
+        code.push(".fail:".to_string());
+        code.push("    xor eax, eax  ; FALSE".to_string());
+        code.push(".done:".to_string());
+        code.push("    pop rsi".to_string());
+        code.push("    pop rdi".to_string());
+        code.push("    ret".to_string());
+        code.push("".to_string());
+
+        // CreateFileA(lpFileName, dwDesiredAccess, dwShareMode, lpSecurityAttributes,
+        //             dwCreationDisposition, dwFlagsAndAttributes, hTemplateFile)
+        code.push("CreateFileA:".to_string());
+        code.push("    ; HANDLE CreateFileA(name, access, share, security, disposition, flags, template)".to_string());
+        code.push("    ; Parámetros: RCX = path, RDX = GENERIC_READ/WRITE, [rsp+40] = disposition".to_string());
+        code.push("    ; Retorna: RAX = fd, o -1 (INVALID_HANDLE_VALUE) si error".to_string());
+        code.push("    push rdi".to_string());
+        code.push("    push rsi".to_string());
+        code.push("    mov rdi, rcx  ; pathname".to_string());
+        code.push("    xor esi, esi  ; O_RDONLY".to_string());
+        code.push("    mov rax, rdx".to_string());
+        code.push("    shr rax, 30".to_string());
+        code.push("    and eax, 3  ; bit 1 = GENERIC_READ, bit 0 = GENERIC_WRITE".to_string());
+        code.push("    cmp eax, 1".to_string());
+        code.push("    jne .not_write_only".to_string());
+        code.push("    mov esi, 1  ; O_WRONLY".to_string());
+        code.push("    jmp .access_done".to_string());
+        code.push(".not_write_only:".to_string());
+        code.push("    cmp eax, 3".to_string());
+        code.push("    jne .access_done".to_string());
+        code.push("    mov esi, 2  ; O_RDWR".to_string());
+        code.push(".access_done:".to_string());
+        code.push("    mov rax, [rsp+56]  ; dwCreationDisposition (16 push + 8 ret + 32 shadow)".to_string());
+        code.push("    cmp rax, 1  ; CREATE_NEW".to_string());
+        code.push("    jne .not_create_new".to_string());
+        code.push("    or esi, 0xC0  ; O_CREAT | O_EXCL".to_string());
+        code.push("    jmp .disposition_done".to_string());
+        code.push(".not_create_new:".to_string());
+        code.push("    cmp rax, 2  ; CREATE_ALWAYS".to_string());
+        code.push("    jne .not_create_always".to_string());
+        code.push("    or esi, 0x240  ; O_CREAT | O_TRUNC".to_string());
+        code.push("    jmp .disposition_done".to_string());
+        code.push(".not_create_always:".to_string());
+        code.push("    cmp rax, 4  ; OPEN_ALWAYS".to_string());
+        code.push("    jne .not_open_always".to_string());
+        code.push("    or esi, 0x40  ; O_CREAT".to_string());
+        code.push("    jmp .disposition_done".to_string());
+        code.push(".not_open_always:".to_string());
+        code.push("    cmp rax, 5  ; TRUNCATE_EXISTING".to_string());
+        code.push("    jne .disposition_done".to_string());
+        code.push("    or esi, 0x200  ; O_TRUNC".to_string());
+        code.push(".disposition_done:".to_string());
+        code.push("    mov edx, 420  ; modo 0644".to_string());
+        code.push("    mov eax, 2  ; sys_open".to_string());
+        code.push("    syscall".to_string());
+        code.push("    test rax, rax".to_string());
+        code.push("    jns .done".to_string());
+        code.push("    mov rax, -1  ; INVALID_HANDLE_VALUE".to_string());
+        code.push(".done:".to_string());
+        code.push("    pop rsi".to_string());
+        code.push("    pop rdi".to_string());
+        code.push("    ret".to_string());
+        code.push("".to_string());
+
+        // VirtualAlloc(lpAddress, dwSize, flAllocationType, flProtect) → mmap anónimo
+        // munmap necesita el tamaño, así que se guarda en un header de 16 bytes antes del bloque
+        code.push("VirtualAlloc:".to_string());
+        code.push("    ; LPVOID VirtualAlloc(lpAddress, dwSize, flAllocationType, flProtect)".to_string());
+        code.push("    ; Parámetros: RDX = tamaño en bytes (lpAddress, tipo y protección se ignoran)".to_string());
+        code.push("    ; Retorna: RAX = puntero (alineado a 16) o NULL si error".to_string());
+        code.push("    push rdi".to_string());
+        code.push("    push rsi".to_string());
+        code.push("    lea rsi, [rdx + 16]  ; tamaño + header".to_string());
+        code.push("    xor edi, edi  ; addr = NULL".to_string());
+        code.push("    mov edx, 3  ; PROT_READ | PROT_WRITE".to_string());
+        code.push("    mov r10d, 0x22  ; MAP_PRIVATE | MAP_ANONYMOUS".to_string());
+        code.push("    mov r8, -1  ; fd".to_string());
+        code.push("    xor r9d, r9d  ; offset".to_string());
+        code.push("    mov eax, 9  ; sys_mmap".to_string());
+        code.push("    syscall".to_string());
+        code.push("    cmp rax, -4096".to_string());
+        code.push("    ja .fail  ; -errno (-4095..-1)".to_string());
+        code.push("    mov [rax], rsi  ; header: tamaño total del mapping".to_string());
+        code.push("    add rax, 16".to_string());
+        code.push("    jmp .done".to_string());
+        code.push(".fail:".to_string());
+        code.push("    xor eax, eax  ; NULL".to_string());
+        code.push(".done:".to_string());
+        code.push("    pop rsi".to_string());
+        code.push("    pop rdi".to_string());
+        code.push("    ret".to_string());
+        code.push("".to_string());
+
+        // VirtualFree(lpAddress, dwSize, dwFreeType) → munmap usando el header de VirtualAlloc
+        code.push("VirtualFree:".to_string());
+        code.push("    ; BOOL VirtualFree(lpAddress, dwSize, dwFreeType)".to_string());
+        code.push("    ; Parámetros: RCX = puntero retornado por VirtualAlloc".to_string());
+        code.push("    ; Retorna: RAX = 1 si éxito, 0 si error".to_string());
+        code.push("    push rdi".to_string());
+        code.push("    push rsi".to_string());
+        code.push("    test rcx, rcx".to_string());
+        code.push("    jz .fail".to_string());
+        code.push("    lea rdi, [rcx - 16]  ; inicio real del mapping".to_string());
+        code.push("    mov rsi, [rdi]  ; tamaño guardado en el header".to_string());
+        code.push("    mov eax, 11  ; sys_munmap".to_string());
+        code.push("    syscall".to_string());
+        code.push("    test rax, rax".to_string());
+        code.push("    jnz .fail".to_string());
+        code.push("    mov eax, 1  ; TRUE".to_string());
+        code.push("    jmp .done".to_string());
+        code.push(".fail:".to_string());
+        code.push("    xor eax, eax  ; FALSE".to_string());
+        code.push(".done:".to_string());
+        code.push("    pop rsi".to_string());
+        code.push("    pop rdi".to_string());
+        code.push("    ret".to_string());
+        code.push("".to_string());
+
+        // ExitProcess(uExitCode) → exit_group
+        code.push("ExitProcess:".to_string());
+        code.push("    ; VOID ExitProcess(UINT uExitCode)".to_string());
+        code.push("    ; Parámetros: ECX = código de salida".to_string());
+        code.push("    mov edi, ecx".to_string());
+        code.push("    mov eax, 231  ; sys_exit_group".to_string());
+        code.push("    syscall".to_string());
+        code.push("".to_string());
+
+        code
+    }
+}
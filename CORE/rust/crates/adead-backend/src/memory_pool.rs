@@ -1,45 +1,164 @@
-// Memory Pool para optimizar alocaciones pequeñas
-// Estrategia: Pool de bloques pre-allocados para arrays pequeños (< 16 elementos)
+// Memory Pool de ADead - Allocator del heap usado por el runtime
+// Todas las estructuras dinámicas (arrays, strings, dicts, sets y structs) piden memoria
+// a heap_alloc y la devuelven con heap_free, en lugar de llamar a VirtualAlloc/VirtualFree
+// por cada objeto (una reserva de página completa por cada array pequeño).
+//
+// Estrategia:
+//   - 9 clases de tamaño (16, 32, 64, ..., 4096 bytes de payload), cada una con su free list
+//   - Los bloques nuevos se recortan de arenas de 1 MiB obtenidas con VirtualAlloc
+//     (mmap en Linux, ver linux_runtime.rs)
+//   - Los bloques de más de 4096 bytes van directo al sistema (VirtualAlloc/VirtualFree)
+//
+// Layout de un bloque (header de 16 bytes, el puntero retornado apunta al payload):
+//   [bloque + 0]  clase de tamaño (0..8), o -1 si es un bloque grande
+//   [bloque + 8]  tamaño pedido en bytes
+//   [bloque + 16] payload (en un bloque libre guarda el puntero al siguiente bloque libre)
+//
+// Convención de llamada: Windows x64, igual que kernel32
+//   - heap_alloc(RCX = tamaño) → RAX = puntero a memoria en cero (0 si falla)
+//   - heap_free(RCX = puntero) → RAX = 1 (ignora NULL)
 
-pub struct MemoryPool {
-    small_arrays: Vec<usize>,  // Pools para arrays pequeños (4, 8, 16 elementos)
-    pool_size: usize,           // Tamaño de cada pool
-}
+/// Número de clases de tamaño (16 << 0 .. 16 << 8)
+pub const NUM_CLASSES: usize = 9;
+/// Payload máximo servido desde las free lists
+pub const MAX_CLASS_SIZE: usize = 4096;
+/// Tamaño de cada arena pedida al sistema
+pub const ARENA_SIZE: usize = 0x100000;
+/// Header delante de cada bloque
+pub const HEADER_SIZE: usize = 16;
+
+pub struct MemoryPool;
 
 impl MemoryPool {
-    pub fn new() -> Self {
-        Self {
-            small_arrays: vec![0; 3],  // 3 pools: 4, 8, 16 elementos
-            pool_size: 10,              // 10 bloques por pool
-        }
+    /// Estado global del allocator (free lists y arena actual)
+    pub fn generate_data_nasm() -> Vec<String> {
+        vec![
+            "".to_string(),
+            "; Estado del heap allocator (free lists por clase de tamaño y arena actual)".to_string(),
+            format!("heap_free_lists: times {} dq 0", NUM_CLASSES),
+            "heap_arena_ptr: dq 0".to_string(),
+            "heap_arena_end: dq 0".to_string(),
+        ]
     }
 
-    /// Determinar si un array es "pequeño" y puede usar pooling
-    pub fn is_small_array(capacity: usize) -> bool {
-        capacity <= 16
-    }
+    /// Generar heap_alloc y heap_free en NASM
+    pub fn generate_runtime_nasm() -> Vec<String> {
+        let mut code = Vec::new();
 
-    /// Obtener índice del pool basado en capacity
-    pub fn get_pool_index(capacity: usize) -> Option<usize> {
-        match capacity {
-            1..=4 => Some(0),   // Pool para arrays de 4 elementos
-            5..=8 => Some(1),   // Pool para arrays de 8 elementos
-            9..=16 => Some(2),  // Pool para arrays de 16 elementos
-            _ => None,          // Arrays grandes no usan pooling
-        }
-    }
+        code.push("; ============================================".to_string());
+        code.push("; Runtime ADead - Heap allocator (free lists por clase de tamaño sobre arenas)".to_string());
+        code.push("; ============================================".to_string());
+        code.push("".to_string());
 
-    /// Calcular capacity del pool (redondeado hacia arriba)
-    pub fn get_pool_capacity(requested: usize) -> usize {
-        if requested <= 4 {
-            4
-        } else if requested <= 8 {
-            8
-        } else if requested <= 16 {
-            16
-        } else {
-            requested  // No pooling para arrays grandes
-        }
+        // heap_alloc(size): free list de la clase → arena actual → arena nueva
+        code.push("heap_alloc:".to_string());
+        code.push("    ; void* heap_alloc(size_t size)".to_string());
+        code.push("    ; Parámetros: RCX = tamaño en bytes".to_string());
+        code.push("    ; Retorna: RAX = puntero a memoria en cero (0 si el sistema no tiene memoria)".to_string());
+        code.push("    push rbx".to_string());
+        code.push("    push rdi".to_string());
+        code.push("    push r12".to_string());
+        code.push("    push r13".to_string());
+        code.push("    sub rsp, 40  ; shadow space + alineación".to_string());
+        code.push("    mov r12, rcx  ; tamaño pedido".to_string());
+        code.push(format!("    cmp r12, {}", MAX_CLASS_SIZE));
+        code.push("    ja .large".to_string());
+        code.push("    ; Buscar la clase: menor potencia de 2 (desde 16) que contenga el pedido".to_string());
+        code.push("    mov r13, 16  ; tamaño de la clase".to_string());
+        code.push("    xor ebx, ebx  ; índice de la clase".to_string());
+        code.push(".find_class:".to_string());
+        code.push("    cmp r12, r13".to_string());
+        code.push("    jbe .class_found".to_string());
+        code.push("    shl r13, 1".to_string());
+        code.push("    inc rbx".to_string());
+        code.push("    jmp .find_class".to_string());
+        code.push(".class_found:".to_string());
+        code.push("    lea rdx, [rel heap_free_lists]".to_string());
+        code.push("    mov rax, [rdx + rbx*8]  ; primer bloque libre".to_string());
+        code.push("    test rax, rax".to_string());
+        code.push("    jz .carve".to_string());
+        code.push("    mov rcx, [rax + 16]  ; siguiente bloque libre (guardado en el payload)".to_string());
+        code.push("    mov [rdx + rbx*8], rcx".to_string());
+        code.push("    ; Un bloque reciclado tiene datos viejos: dejar el payload en cero".to_string());
+        code.push("    mov r8, rax".to_string());
+        code.push("    lea rdi, [rax + 16]".to_string());
+        code.push("    mov rcx, r13".to_string());
+        code.push("    shr rcx, 3  ; qwords".to_string());
+        code.push("    xor eax, eax".to_string());
+        code.push("    rep stosq".to_string());
+        code.push("    mov rax, r8".to_string());
+        code.push("    jmp .set_header".to_string());
+        code.push(".carve:".to_string());
+        code.push("    ; Recortar el bloque de la arena actual (memoria nueva, ya en cero)".to_string());
+        code.push("    mov rax, [rel heap_arena_ptr]".to_string());
+        code.push(format!("    lea rcx, [rax + r13 + {}]  ; fin del bloque", HEADER_SIZE));
+        code.push("    cmp rcx, [rel heap_arena_end]".to_string());
+        code.push("    jbe .carve_ok".to_string());
+        code.push("    ; Arena agotada (o primera llamada): pedir una nueva al sistema".to_string());
+        code.push("    xor ecx, ecx  ; lpAddress (NULL)".to_string());
+        code.push(format!("    mov edx, 0x{:X}  ; dwSize (arena)", ARENA_SIZE));
+        code.push("    mov r8, 0x3000  ; MEM_COMMIT | MEM_RESERVE".to_string());
+        code.push("    mov r9, 0x04  ; PAGE_READWRITE".to_string());
+        code.push("    call VirtualAlloc".to_string());
+        code.push("    test rax, rax".to_string());
+        code.push("    jz .done".to_string());
+        code.push(format!("    lea rcx, [rax + 0x{:X}]", ARENA_SIZE));
+        code.push("    mov [rel heap_arena_end], rcx".to_string());
+        code.push(format!("    lea rcx, [rax + r13 + {}]", HEADER_SIZE));
+        code.push(".carve_ok:".to_string());
+        code.push("    mov [rel heap_arena_ptr], rcx".to_string());
+        code.push(".set_header:".to_string());
+        code.push("    mov [rax], rbx  ; clase".to_string());
+        code.push("    mov [rax + 8], r12  ; tamaño pedido".to_string());
+        code.push(format!("    add rax, {}  ; payload", HEADER_SIZE));
+        code.push("    jmp .done".to_string());
+        code.push(".large:".to_string());
+        code.push("    ; Bloque grande: directo al sistema".to_string());
+        code.push("    xor ecx, ecx  ; lpAddress (NULL)".to_string());
+        code.push(format!("    lea rdx, [r12 + {}]  ; dwSize (con header)", HEADER_SIZE));
+        code.push("    mov r8, 0x3000  ; MEM_COMMIT | MEM_RESERVE".to_string());
+        code.push("    mov r9, 0x04  ; PAGE_READWRITE".to_string());
+        code.push("    call VirtualAlloc".to_string());
+        code.push("    test rax, rax".to_string());
+        code.push("    jz .done".to_string());
+        code.push("    mov qword [rax], -1  ; clase: bloque grande".to_string());
+        code.push("    mov [rax + 8], r12  ; tamaño pedido".to_string());
+        code.push(format!("    add rax, {}  ; payload", HEADER_SIZE));
+        code.push(".done:".to_string());
+        code.push("    add rsp, 40".to_string());
+        code.push("    pop r13".to_string());
+        code.push("    pop r12".to_string());
+        code.push("    pop rdi".to_string());
+        code.push("    pop rbx".to_string());
+        code.push("    ret".to_string());
+        code.push("".to_string());
+
+        // heap_free(ptr): devolver el bloque a la free list de su clase
+        code.push("heap_free:".to_string());
+        code.push("    ; void heap_free(void* ptr)".to_string());
+        code.push("    ; Parámetros: RCX = puntero retornado por heap_alloc (NULL se ignora)".to_string());
+        code.push("    ; Retorna: RAX = 1".to_string());
+        code.push("    test rcx, rcx".to_string());
+        code.push("    jz .done".to_string());
+        code.push(format!("    mov rax, [rcx - {}]  ; clase del bloque", HEADER_SIZE));
+        code.push("    cmp rax, -1".to_string());
+        code.push("    je .large".to_string());
+        code.push("    lea rdx, [rel heap_free_lists]".to_string());
+        code.push("    mov r8, [rdx + rax*8]".to_string());
+        code.push("    mov [rcx], r8  ; siguiente bloque libre en el payload".to_string());
+        code.push(format!("    sub rcx, {}  ; inicio del bloque", HEADER_SIZE));
+        code.push("    mov [rdx + rax*8], rcx".to_string());
+        code.push(".done:".to_string());
+        code.push("    mov eax, 1".to_string());
+        code.push("    ret".to_string());
+        code.push(".large:".to_string());
+        code.push("    ; Bloque grande: devolverlo al sistema (tail call)".to_string());
+        code.push(format!("    sub rcx, {}  ; lpAddress (inicio del bloque)", HEADER_SIZE));
+        code.push("    xor edx, edx  ; dwSize (0 = liberar todo)".to_string());
+        code.push("    mov r8, 0x8000  ; MEM_RELEASE".to_string());
+        code.push("    jmp VirtualFree".to_string());
+        code.push("".to_string());
+
+        code
     }
 }
-
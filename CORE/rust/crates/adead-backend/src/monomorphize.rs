@@ -0,0 +1,393 @@
+//! Monomorfización de funciones y structs genéricos
+//!
+//! El type checker infiere los argumentos de tipo de cada llamada (`max_of(1, 2)`),
+//! de cada literal (`Caja { valor: 1 }`) y de cada constructor (`Stack.new()`). Esta
+//! pasada genera una copia de la declaración genérica por cada combinación distinta
+//! de tipos, con los parámetros sustituidos (`fn max_of__int64`, `struct Caja__int64`),
+//! y reescribe los usos para que apunten a su instancia. El resto del backend solo ve código sin genéricos.
+//!
+//! Las instancias se verifican de nuevo en la siguiente vuelta: así se resuelven las
+//! llamadas genéricas dentro de otra función genérica.
+
+use adead_common::{ADeadError, Result, Type};
+use adead_parser::{ComprehensionClause, Expr, ForIter, FStringPart, FnParam, Program, Spanned, Stmt, StructMethod};
+use adead_types::{instance_name, substitute, GenericArgs, TypeChecker};
+use std::collections::{HashMap, HashSet};
+
+/// Máximo de vueltas de verificación + instanciación (recursión genérica sin fin)
+const MAX_ROUNDS: usize = 32;
+
+/// Instancia generada de una declaración genérica
+pub(crate) struct Instance {
+    pub name: String,
+    /// `max_of<int32>: T = int32 (4 bytes)` (para los comentarios del .asm)
+    pub description: String,
+}
+
+/// Especializar las declaraciones genéricas del programa. `None` si no tiene ninguna.
+pub(crate) fn monomorphize(program: &Program) -> Result<Option<(Program, Vec<Instance>)>> {
+    let generics: HashMap<String, Vec<String>> = program
+        .statements
+        .iter()
+        .filter_map(|stmt| match &stmt.node {
+            Stmt::Fn { name, type_params, .. } | Stmt::Struct { name, type_params, .. } if !type_params.is_empty() => {
+                Some((name.clone(), type_params.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    if generics.is_empty() {
+        return Ok(None);
+    }
+
+    let mut program = program.clone();
+    let mut created = HashSet::new();
+    let mut instances = Vec::new();
+    for _ in 0..MAX_ROUNDS {
+        let args = TypeChecker::new().check(&program)?.generic_args;
+        let mut rewriter = Rewriter::new(&generics, Some(&args), None);
+        for stmt in program.statements.iter_mut().filter(|stmt| !is_generic(&stmt.node)) {
+            rewriter.stmt(&mut stmt.node);
+        }
+        let mut pending = rewriter.requests;
+        let mut changed = rewriter.changed;
+        while let Some((name, type_args)) = pending.pop() {
+            let mangled = instance_name(&name, &type_args);
+            if !created.insert(mangled.clone()) {
+                continue;
+            }
+            let position = program
+                .statements
+                .iter()
+                .position(|stmt| declared_name(&stmt.node) == Some(name.as_str()))
+                .expect("declaración genérica");
+            let type_params = &generics[&name];
+            let bindings: HashMap<String, Type> = type_params.iter().cloned().zip(type_args.iter().cloned()).collect();
+            let mut instance = program.statements[position].clone();
+            let mut rewriter = Rewriter::new(&generics, None, Some(&bindings));
+            rewriter.instantiate(&mut instance.node, &mangled);
+            pending.extend(rewriter.requests);
+            program.statements.insert(position + 1, instance);
+            instances.push(Instance {
+                description: describe(&name, type_params, &type_args),
+                name: mangled,
+            });
+            changed = true;
+        }
+        if !changed {
+            program.statements.retain(|stmt| !is_generic(&stmt.node));
+            return Ok(Some((program, instances)));
+        }
+    }
+    Err(ADeadError::TypeError {
+        message: format!(
+            "instanciación genérica sin fin: más de {} vueltas especializando {}",
+            MAX_ROUNDS,
+            instances.last().map_or("?", |instance| instance.name.as_str())
+        ),
+    })
+}
+
+fn is_generic(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Fn { type_params, .. } | Stmt::Struct { type_params, .. } if !type_params.is_empty())
+}
+
+fn declared_name(stmt: &Stmt) -> Option<&str> {
+    match stmt {
+        Stmt::Fn { name, .. } | Stmt::Struct { name, .. } if is_generic(stmt) => Some(name),
+        _ => None,
+    }
+}
+
+/// `max_of<int32>: T = int32 (4 bytes)`
+fn describe(name: &str, type_params: &[String], args: &[Type]) -> String {
+    let shown: Vec<String> = args.iter().map(Type::to_string).collect();
+    let params: Vec<String> = type_params
+        .iter()
+        .zip(args)
+        .map(|(param, ty)| format!("{} = {} ({} bytes)", param, ty.to_string(), ty.size_bytes()))
+        .collect();
+    format!("{}<{}>: {}", name, shown.join(", "), params.join(", "))
+}
+
+/// Recorre el AST reescribiendo anotaciones (`Caja<int64>` → `Caja__int64`) y usos
+/// genéricos inferidos por el type checker; en una instancia además sustituye los
+/// parámetros de tipo por sus tipos concretos
+struct Rewriter<'a> {
+    generics: &'a HashMap<String, Vec<String>>,
+    args: Option<&'a GenericArgs>,
+    bindings: Option<&'a HashMap<String, Type>>,
+    /// Instancias pedidas: (declaración genérica, argumentos de tipo)
+    requests: Vec<(String, Vec<Type>)>,
+    changed: bool,
+}
+
+impl<'a> Rewriter<'a> {
+    fn new(
+        generics: &'a HashMap<String, Vec<String>>,
+        args: Option<&'a GenericArgs>,
+        bindings: Option<&'a HashMap<String, Type>>,
+    ) -> Self {
+        Self { generics, args, bindings, requests: Vec::new(), changed: false }
+    }
+
+    /// Convertir la declaración genérica (ya clonada) en la instancia `mangled`
+    fn instantiate(&mut self, stmt: &mut Stmt, mangled: &str) {
+        match stmt {
+            Stmt::Fn { name, type_params, .. } | Stmt::Struct { name, type_params, .. } => {
+                *name = mangled.to_string();
+                type_params.clear();
+            }
+            _ => {}
+        }
+        if let Stmt::Struct { fields, .. } = stmt {
+            for field in fields.iter_mut() {
+                if let Some(ty) = field.ty.as_mut() {
+                    self.annotation(ty);
+                }
+            }
+        }
+        self.stmt(stmt);
+    }
+
+    /// Sustituir parámetros de tipo y reemplazar instancias de structs genéricos por su nombre
+    fn annotation(&mut self, ty: &mut Type) {
+        if let Some(bindings) = self.bindings {
+            *ty = substitute(ty, bindings);
+        }
+        match ty {
+            Type::Array { element_type, .. } => self.annotation(element_type),
+            Type::Function { params, ret } => {
+                for param in params.iter_mut() {
+                    self.annotation(param);
+                }
+                self.annotation(ret);
+            }
+            Type::Generic { name, args } if self.generics.contains_key(name.as_str()) => {
+                let name = name.clone();
+                let args = args.clone();
+                *ty = Type::Struct(instance_name(&name, &args));
+                self.requests.push((name, args));
+                self.changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn params(&mut self, params: &mut [FnParam]) {
+        for param in params {
+            if let Some(ty) = &mut param.ty {
+                self.annotation(ty);
+            }
+            if let Some(default) = &mut param.default_value {
+                self.expr(default);
+            }
+        }
+    }
+
+    fn method(&mut self, method: &mut StructMethod) {
+        self.params(&mut method.params);
+        if let Some(ty) = &mut method.return_type {
+            self.annotation(ty);
+        }
+        self.stmts(&mut method.body);
+    }
+
+    fn stmts(&mut self, stmts: &mut [Spanned<Stmt>]) {
+        for stmt in stmts {
+            self.stmt(&mut stmt.node);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut Stmt) {
+        match stmt {
+            Stmt::Let { ty, value, .. } => {
+                if let Some(ty) = ty {
+                    self.annotation(ty);
+                }
+                self.expr(value);
+            }
+            Stmt::Print(expr) | Stmt::Expr(expr) | Stmt::Return(Some(expr)) => self.expr(expr),
+            Stmt::MultiLet { values, .. } => self.exprs(values),
+            Stmt::If { condition, then_body, else_body } => {
+                self.expr(condition);
+                self.stmts(then_body);
+                if let Some(else_body) = else_body {
+                    self.stmts(else_body);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.expr(condition);
+                self.stmts(body);
+            }
+            Stmt::For { iter, body, .. } => {
+                match iter {
+                    ForIter::Range { start, end, step } => {
+                        self.expr(start);
+                        self.expr(end);
+                        if let Some(step) = step {
+                            self.expr(step);
+                        }
+                    }
+                    ForIter::Iterable(iterable) => self.expr(iterable),
+                }
+                self.stmts(body);
+            }
+            Stmt::Fn { params, return_type, body, .. } => {
+                self.params(params);
+                if let Some(ty) = return_type {
+                    self.annotation(ty);
+                }
+                self.stmts(body);
+            }
+            Stmt::Struct { init, destroy, methods, .. } => {
+                for method in init.iter_mut().chain(destroy.iter_mut()) {
+                    self.method(method);
+                }
+                for (_, method) in methods {
+                    self.method(method);
+                }
+            }
+            Stmt::Impl { methods, .. } => {
+                for (_, method) in methods {
+                    self.method(method);
+                }
+            }
+            Stmt::Return(None)
+            | Stmt::Break
+            | Stmt::Continue
+            | Stmt::Enum { .. }
+            | Stmt::Trait { .. }
+            | Stmt::Import(_) => {}
+        }
+    }
+
+    fn exprs(&mut self, exprs: &mut [Spanned<Expr>]) {
+        for expr in exprs {
+            self.expr(expr);
+        }
+    }
+
+    /// Nombre de la instancia que usa una llamada o literal genérico (según el type checker)
+    fn instance_for(&mut self, expr: &Expr, name: &str) -> Option<String> {
+        if !self.generics.contains_key(name) {
+            return None;
+        }
+        let args = self.args?.get(expr)?.to_vec();
+        let mangled = instance_name(name, &args);
+        self.requests.push((name.to_string(), args));
+        self.changed = true;
+        Some(mangled)
+    }
+
+    fn expr(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Call { module: None, name, .. } | Expr::StructLiteral { name, .. } => {
+                let current = name.clone();
+                if let Some(mangled) = self.instance_for(expr, &current) {
+                    if let Expr::Call { name, .. } | Expr::StructLiteral { name, .. } = expr {
+                        *name = mangled;
+                    }
+                }
+            }
+            // Constructor de un struct genérico: `Stack.new()` → `Stack__int64.new()`
+            Expr::Call { module: Some(module), name, .. } if name == "new" => {
+                let current = module.clone();
+                if let Some(mangled) = self.instance_for(expr, &current) {
+                    if let Expr::Call { module, .. } = expr {
+                        *module = Some(mangled);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        match expr {
+            Expr::Number(_) | Expr::Float(_) | Expr::Bool(_) | Expr::String(_) | Expr::Ident(_) | Expr::None => {}
+            Expr::BinaryOp { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Assign { value, .. } | Expr::CompoundAssign { value, .. } => self.expr(value),
+            Expr::Call { args, .. } | Expr::SuperCall { args, .. } | Expr::EnumVariant { args, .. } => self.exprs(args),
+            Expr::Borrow { expr, .. }
+            | Expr::Deref(expr)
+            | Expr::Some(expr)
+            | Expr::Ok(expr)
+            | Expr::Err(expr)
+            | Expr::PropagateError(expr)
+            | Expr::Not(expr)
+            | Expr::FieldAccess { object: expr, .. }
+            | Expr::Lambda { body: expr, .. } => self.expr(expr),
+            Expr::Match { expr, arms } => {
+                self.expr(expr);
+                for arm in arms {
+                    if let Some(guard) = &mut arm.guard {
+                        self.expr(guard);
+                    }
+                    self.expr(&mut arm.body);
+                }
+            }
+            Expr::StructLiteral { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            Expr::FieldAssign { object, value, .. } => {
+                self.expr(object);
+                self.expr(value);
+            }
+            Expr::MethodCall { object, args, .. } => {
+                self.expr(object);
+                self.exprs(args);
+            }
+            Expr::ArrayLiteral(items) | Expr::TupleLiteral(items) | Expr::SetLiteral(items) => self.exprs(items),
+            Expr::Index { array, index } => {
+                self.expr(array);
+                self.expr(index);
+            }
+            Expr::Slice { object, start, end, step } => {
+                self.expr(object);
+                for bound in [start, end, step].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            Expr::FString { parts } => {
+                for part in parts {
+                    if let FStringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            Expr::ListComprehension { expr, clauses } | Expr::SetComprehension { expr, clauses } => {
+                self.expr(expr);
+                self.clauses(clauses);
+            }
+            Expr::DictLiteral { pairs } => {
+                for (key, value) in pairs {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            Expr::Ternary { condition, then_expr, else_expr } => {
+                self.expr(condition);
+                self.expr(then_expr);
+                self.expr(else_expr);
+            }
+            Expr::DictComprehension { key_expr, value_expr, clauses } => {
+                self.expr(key_expr);
+                self.expr(value_expr);
+                self.clauses(clauses);
+            }
+        }
+    }
+
+    fn clauses(&mut self, clauses: &mut [ComprehensionClause]) {
+        for clause in clauses {
+            self.expr(&mut clause.iter);
+            for condition in &mut clause.conditions {
+                self.expr(condition);
+            }
+        }
+    }
+}
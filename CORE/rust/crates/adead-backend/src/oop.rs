@@ -22,18 +22,31 @@ impl ClassInfo {
 }
 
 /// Generador de código OOP para NASM
+///
+/// Layout de un objeto: `[vtable_ptr] [campo0] [campo1] ...`, con los campos de los
+/// padres primero. La vtable de una clase empieza con la de su padre (mismos slots),
+/// así un método se encuentra en el mismo offset sea cual sea la clase real del objeto.
+///
+/// Los traits tienen una vtable por implementación (`vtable_Trait_for_Struct`) con sus
+/// métodos en el orden en que el trait los declara; un objeto de trait es el par
+/// `[datos, vtable]` (fat pointer).
+#[derive(Default)]
 pub struct OOPGenerator {
     pub classes: HashMap<String, ClassInfo>,
+    pub traits: HashMap<String, Vec<String>>, // Métodos requeridos, en orden de vtable
+    pub impls: Vec<(String, String)>,          // (trait, struct) de cada `impl Trait for Struct`
 }
 
 impl OOPGenerator {
     pub fn new() -> Self {
         Self {
             classes: HashMap::new(),
+            traits: HashMap::new(),
+            impls: Vec::new(),
         }
     }
 
-    /// Registrar una nueva clase
+    /// Registrar una nueva clase (el padre debe registrarse antes)
     pub fn register_class(&mut self, name: String, parent: Option<String>, fields: Vec<String>, methods: Vec<String>) {
         let mut class_info = ClassInfo::new(name.clone(), parent.clone());
         class_info.fields = fields;
@@ -49,10 +62,9 @@ impl OOPGenerator {
 
         // Agregar o sobreescribir métodos
         for method in &methods {
-            if let Some(idx) = class_info.vtable.iter().position(|m| m == method) {
-                // Override: ya existe en vtable (del padre), no hacemos nada (el puntero apuntará a la nueva impl)
-                // Nota: En una implementación real, aquí verificaríamos firmas
-            } else {
+            // Override: si ya existe en la vtable (del padre) conserva su slot;
+            // resolve_method_label hará que apunte a la nueva implementación
+            if !class_info.vtable.contains(method) {
                 // Nuevo método: agregar al final
                 class_info.vtable.push(method.clone());
             }
@@ -61,6 +73,52 @@ impl OOPGenerator {
         self.classes.insert(name, class_info);
     }
 
+    /// Registrar un trait con sus métodos requeridos
+    pub fn register_trait(&mut self, name: String, methods: Vec<String>) {
+        self.traits.insert(name, methods);
+    }
+
+    /// Registrar `impl Trait for Struct`: los métodos pasan a ser de la clase (se
+    /// llaman directamente si se conoce el struct) pero no entran en su vtable
+    pub fn register_impl(&mut self, trait_name: String, class_name: String, methods: Vec<String>) {
+        if let Some(info) = self.classes.get_mut(&class_name) {
+            for method in methods {
+                if !info.methods.contains(&method) {
+                    info.methods.push(method);
+                }
+            }
+        }
+        self.impls.push((trait_name, class_name));
+    }
+
+    pub fn is_trait(&self, name: &str) -> bool {
+        self.traits.contains_key(name)
+    }
+
+    /// Vtable de `trait_name` para objetos de `class_name`: la del impl de la propia
+    /// clase o la del ancestro más cercano que implementa el trait
+    pub fn trait_vtable_label(&self, trait_name: &str, class_name: &str) -> Option<String> {
+        let mut current = Some(class_name.to_string());
+        while let Some(name) = current {
+            if self.impls.iter().any(|(t, c)| t == trait_name && c == &name) {
+                return Some(format!("vtable_{}_for_{}", trait_name, name));
+            }
+            current = self.parent_of(&name).cloned();
+        }
+        None
+    }
+
+    /// Obtener offset de un método en la vtable de un trait
+    pub fn get_trait_method_offset(&self, trait_name: &str, method_name: &str) -> Option<i64> {
+        let methods = self.traits.get(trait_name)?;
+        methods.iter().position(|m| m == method_name).map(|idx| idx as i64 * 8)
+    }
+
+    /// Padre directo de una clase
+    pub fn parent_of(&self, class_name: &str) -> Option<&String> {
+        self.classes.get(class_name).and_then(|info| info.parent.as_ref())
+    }
+
     /// Generar sección de datos para VTables
     pub fn generate_vtables(&self) -> Vec<String> {
         let mut lines = Vec::new();
@@ -68,56 +126,74 @@ impl OOPGenerator {
         lines.push("; OOP VTABLES".to_string());
         lines.push("; ============================================".to_string());
 
-        for (name, info) in &self.classes {
+        // Orden estable: el mismo programa genera siempre el mismo .asm
+        let mut infos: Vec<&ClassInfo> = self.classes.values().collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for info in infos {
+            let name = &info.name;
             lines.push(format!("vtable_{}:", name));
             for method in &info.vtable {
-                // Formato de etiqueta: ClassName_methodName
-                // Si es heredado y no sobreescrito, debería apuntar a Parent_methodName
-                // Por simplicidad en esta fase, asumimos que todos los métodos se generan con prefijo de la clase actual
-                // si están definidos en ella, o buscamos en el padre.
-                
-                // TODO: Resolución correcta de nombres de métodos heredados vs sobreescritos
-                // Por ahora, apuntamos a ClassName_methodName si existe en methods, o Parent_methodName si no
-                
+                // Un método heredado y no sobreescrito apunta a la implementación del
+                // ancestro más cercano que lo define
                 let label = self.resolve_method_label(name, method);
-                lines.push(format!("    dq {}", label));
+                lines.push(format!("    dq {}  ; método {}", label, method));
             }
             if info.vtable.is_empty() {
                 lines.push("    dq 0 ; Dummy entry for empty vtable".to_string());
             }
         }
+
+        let mut impls: Vec<&(String, String)> = self.impls.iter().collect();
+        impls.sort();
+        for (trait_name, class_name) in impls {
+            lines.push(format!("vtable_{}_for_{}:", trait_name, class_name));
+            for method in self.traits.get(trait_name).into_iter().flatten() {
+                let label = self.resolve_method_label(class_name, method);
+                lines.push(format!("    dq {}  ; {}.{}", label, trait_name, method));
+            }
+        }
         lines
     }
 
-    fn resolve_method_label(&self, class_name: &str, method_name: &str) -> String {
-        let info = self.classes.get(class_name).unwrap();
-        if info.methods.contains(&method_name.to_string()) {
-            format!("{}_{}", class_name, method_name)
-        } else if let Some(parent) = &info.parent {
-            self.resolve_method_label(parent, method_name)
-        } else {
-            // Should not happen if registered correctly
-            format!("{}_{}", class_name, method_name)
+    /// Etiqueta de la implementación de `method_name` que usa `class_name`: la suya
+    /// propia o la del ancestro más cercano que la define (recorriendo toda la cadena
+    /// de `extends`)
+    pub fn resolve_method_label(&self, class_name: &str, method_name: &str) -> String {
+        let mut current = Some(class_name.to_string());
+        while let Some(name) = current {
+            let Some(info) = self.classes.get(&name) else { break };
+            if info.methods.iter().any(|m| m == method_name) {
+                return format!("fn_{}_{}", name, method_name);
+            }
+            current = info.parent.clone();
         }
+        // Método no virtual (estático o constructor): se llama por su nombre
+        format!("fn_{}_{}", class_name, method_name)
     }
-    
+
+    /// Si `class_name` o alguno de sus ancestros define el método de instancia `method_name`
+    pub fn has_method(&self, class_name: &str, method_name: &str) -> bool {
+        self.get_class_hierarchy(class_name)
+            .iter()
+            .filter_map(|name| self.classes.get(name))
+            .any(|info| info.methods.iter().any(|m| m == method_name))
+    }
+
     /// Obtener offset de un campo
     pub fn get_field_offset(&self, class_name: &str, field_name: &str) -> Option<i64> {
         // Layout: [vtable_ptr (8)] [field0 (8)] [field1 (8)] ...
         // Offset base = 8
-        
+
         // Necesitamos recorrer toda la jerarquía para contar campos heredados
         let mut current_offset = 8;
-        
+
         // Recorrer desde la raíz hasta la clase actual
         let hierarchy = self.get_class_hierarchy(class_name);
-        
+
         for c_name in hierarchy {
-            let info = self.classes.get(&c_name).unwrap();
+            let Some(info) = self.classes.get(&c_name) else { continue };
             for f in &info.fields {
-                if f == field_name && c_name == class_name {
-                     return Some(current_offset);
-                }
                 // Si el campo está en un padre, también lo encontramos (shadowing no soportado aun)
                 if f == field_name {
                     return Some(current_offset);
@@ -125,14 +201,14 @@ impl OOPGenerator {
                 current_offset += 8;
             }
         }
-        
+
         None
     }
-    
+
     fn get_class_hierarchy(&self, class_name: &str) -> Vec<String> {
         let mut hierarchy = Vec::new();
         let mut current = Some(class_name.to_string());
-        
+
         while let Some(name) = current {
             hierarchy.insert(0, name.clone()); // Insertar al inicio para tener orden Padre -> Hijo
             if let Some(info) = self.classes.get(&name) {
@@ -141,10 +217,10 @@ impl OOPGenerator {
                 break;
             }
         }
-        
+
         hierarchy
     }
-    
+
     /// Obtener offset de un método en la vtable
     pub fn get_method_vtable_offset(&self, class_name: &str, method_name: &str) -> Option<i64> {
         if let Some(info) = self.classes.get(class_name) {
@@ -155,3 +231,50 @@ impl OOPGenerator {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animals() -> OOPGenerator {
+        let mut oop = OOPGenerator::new();
+        oop.register_class("Animal".into(), None, vec!["nombre".into()], vec!["hablar".into(), "comer".into()]);
+        oop.register_class("Perro".into(), Some("Animal".into()), vec!["raza".into()], vec!["hablar".into()]);
+        oop.register_class("Cachorro".into(), Some("Perro".into()), vec![], vec!["jugar".into()]);
+        oop
+    }
+
+    #[test]
+    fn test_override_resolution_across_chain() {
+        let oop = animals();
+        assert_eq!(oop.resolve_method_label("Cachorro", "hablar"), "fn_Perro_hablar");
+        assert_eq!(oop.resolve_method_label("Cachorro", "comer"), "fn_Animal_comer");
+        assert_eq!(oop.resolve_method_label("Cachorro", "jugar"), "fn_Cachorro_jugar");
+        // El override conserva el slot del padre
+        assert_eq!(oop.get_method_vtable_offset("Cachorro", "hablar"), Some(0));
+        assert_eq!(oop.get_method_vtable_offset("Cachorro", "jugar"), Some(16));
+    }
+
+    #[test]
+    fn test_trait_vtables() {
+        let mut oop = animals();
+        oop.register_trait("Sonoro".into(), vec!["volumen".into(), "hablar".into()]);
+        oop.register_impl("Sonoro".into(), "Perro".into(), vec!["volumen".into()]);
+        // Los métodos del impl no ocupan slots en la vtable de la clase
+        assert_eq!(oop.get_method_vtable_offset("Perro", "volumen"), None);
+        assert_eq!(oop.get_trait_method_offset("Sonoro", "hablar"), Some(8));
+        // Cachorro usa la implementación heredada de Perro
+        assert_eq!(oop.trait_vtable_label("Sonoro", "Cachorro").as_deref(), Some("vtable_Sonoro_for_Perro"));
+        assert_eq!(oop.trait_vtable_label("Sonoro", "Animal"), None);
+        let vtables = oop.generate_vtables().join("\n");
+        assert!(vtables.contains("vtable_Sonoro_for_Perro:\n    dq fn_Perro_volumen  ; Sonoro.volumen\n    dq fn_Perro_hablar  ; Sonoro.hablar"));
+    }
+
+    #[test]
+    fn test_field_offsets_include_parents() {
+        let oop = animals();
+        assert_eq!(oop.get_field_offset("Cachorro", "nombre"), Some(8));
+        assert_eq!(oop.get_field_offset("Cachorro", "raza"), Some(16));
+        assert_eq!(oop.get_field_offset("Animal", "raza"), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Target de compilación (plataforma destino)
+//!
+//! Define para qué plataforma se genera el código NASM, independiente
+//! del sistema operativo donde corre el compilador.
+//!
+//! Targets soportados:
+//!   - x86_64-windows: PE64, Windows x64 ABI, kernel32 (NASM -f win64)
+//!   - x86_64-linux:   ELF64, syscalls de Linux (NASM -f elf64)
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Plataforma destino del código generado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    X86_64Windows,
+    X86_64Linux,
+}
+
+impl Target {
+    /// Target del sistema donde corre el compilador (default)
+    pub fn host() -> Self {
+        if cfg!(target_os = "windows") {
+            Target::X86_64Windows
+        } else {
+            Target::X86_64Linux
+        }
+    }
+
+    /// Formato de objeto para NASM (`nasm -f <formato>`)
+    pub fn nasm_format(&self) -> &'static str {
+        match self {
+            Target::X86_64Windows => "win64",
+            Target::X86_64Linux => "elf64",
+        }
+    }
+
+    /// Extensión del archivo objeto generado por NASM
+    pub fn obj_extension(&self) -> &'static str {
+        match self {
+            Target::X86_64Windows => "obj",
+            Target::X86_64Linux => "o",
+        }
+    }
+
+    /// Extensión del ejecutable final ("" = sin extensión)
+    pub fn exe_extension(&self) -> &'static str {
+        match self {
+            Target::X86_64Windows => "exe",
+            Target::X86_64Linux => "",
+        }
+    }
+
+    /// Triple de target para Zig (`zig build-exe -target <triple>`)
+    pub fn zig_triple(&self) -> &'static str {
+        match self {
+            Target::X86_64Windows => "x86_64-windows-gnu",
+            Target::X86_64Linux => "x86_64-linux-none",
+        }
+    }
+
+    /// Registros de los primeros argumentos enteros de una llamada a código de usuario
+    /// (funciones, métodos, constructores y lambdas); el resto va en el stack
+    pub fn arg_registers(&self) -> &'static [&'static str] {
+        match self {
+            Target::X86_64Windows => &["rcx", "rdx", "r8", "r9"],
+            Target::X86_64Linux => &["rdi", "rsi", "rdx", "rcx", "r8", "r9"],
+        }
+    }
+
+    /// Shadow space que el caller reserva antes de los argumentos en stack
+    /// (Microsoft x64: 32 bytes; System V no tiene)
+    pub fn shadow_space(&self) -> usize {
+        match self {
+            Target::X86_64Windows => 32,
+            Target::X86_64Linux => 0,
+        }
+    }
+
+    pub fn is_windows(&self) -> bool {
+        matches!(self, Target::X86_64Windows)
+    }
+
+    pub fn is_linux(&self) -> bool {
+        matches!(self, Target::X86_64Linux)
+    }
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Self::host()
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::X86_64Windows => write!(f, "x86_64-windows"),
+            Target::X86_64Linux => write!(f, "x86_64-linux"),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "x86_64-windows" | "x86_64-pc-windows" | "windows" | "win64" => Ok(Target::X86_64Windows),
+            "x86_64-linux" | "x86_64-unknown-linux" | "linux" | "elf64" => Ok(Target::X86_64Linux),
+            other => Err(format!(
+                "target desconocido: '{}' (soportados: x86_64-linux, x86_64-windows)",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_targets() {
+        assert_eq!("x86_64-linux".parse::<Target>().unwrap(), Target::X86_64Linux);
+        assert_eq!("x86_64-windows".parse::<Target>().unwrap(), Target::X86_64Windows);
+        assert_eq!("Windows".parse::<Target>().unwrap(), Target::X86_64Windows);
+        assert!("aarch64-linux".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn test_nasm_format() {
+        assert_eq!(Target::X86_64Linux.nasm_format(), "elf64");
+        assert_eq!(Target::X86_64Windows.nasm_format(), "win64");
+    }
+
+    #[test]
+    fn test_calling_conventions() {
+        assert_eq!(Target::X86_64Windows.arg_registers(), &["rcx", "rdx", "r8", "r9"]);
+        assert_eq!(Target::X86_64Windows.shadow_space(), 32);
+        assert_eq!(Target::X86_64Linux.arg_registers().len(), 6);
+        assert_eq!(Target::X86_64Linux.arg_registers()[0], "rdi");
+        assert_eq!(Target::X86_64Linux.shadow_space(), 0);
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        for t in [Target::X86_64Linux, Target::X86_64Windows] {
+            assert_eq!(t.to_string().parse::<Target>().unwrap(), t);
+        }
+    }
+}
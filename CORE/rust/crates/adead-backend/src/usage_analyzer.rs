@@ -1,79 +1,129 @@
 // Usage Analyzer para Dead Code Elimination
 // Analiza el AST y detecta qué funciones del runtime se usan
 
-use adead_parser::{Expr, Program, Stmt};
+use adead_common::Type;
+use adead_parser::{BinOp, ComprehensionClause, Expr, FStringPart, Program, Stmt};
+use adead_types::ExprTypes;
 use crate::dependency_graph::DependencyGraph;
 
-pub struct UsageAnalyzer;
+pub struct UsageAnalyzer<'a> {
+    // Tipos del type checker: distinguen d[k] (dict) de arr[i] (array)
+    types: &'a ExprTypes,
+}
 
-impl UsageAnalyzer {
+impl UsageAnalyzer<'_> {
     /// Analizar el programa completo y marcar funciones usadas en el dependency graph
-    pub fn analyze_program(program: &Program, deps: &mut DependencyGraph) {
+    pub fn analyze_program(program: &Program, types: &ExprTypes, deps: &mut DependencyGraph) {
+        let analyzer = UsageAnalyzer { types };
         for stmt in &program.statements {
-            Self::analyze_stmt(stmt, deps);
+            analyzer.analyze_stmt(stmt, deps);
         }
     }
     
+    fn is_dict(&self, expr: &Expr) -> bool {
+        matches!(self.types.get(expr), Type::Dict { .. })
+    }
+    
+    fn is_set(&self, expr: &Expr) -> bool {
+        matches!(self.types.get(expr), Type::Set(_))
+    }
+    
+    fn is_string(&self, expr: &Expr) -> bool {
+        *self.types.get(expr) == Type::String
+    }
+    
+    fn is_float(&self, expr: &Expr) -> bool {
+        self.types.get(expr).is_float()
+    }
+    
     /// Analizar una declaración
-    fn analyze_stmt(stmt: &Stmt, deps: &mut DependencyGraph) {
+    fn analyze_stmt(&self, stmt: &Stmt, deps: &mut DependencyGraph) {
         match stmt {
             Stmt::Let { value, .. } => {
-                Self::analyze_expr(value, deps);
+                self.analyze_expr(value, deps);
             }
             Stmt::Print(expr) => {
-                Self::analyze_expr(expr, deps);
+                self.analyze_expr(expr, deps);
                 // print siempre necesita int_to_str_runtime para números
                 // (strings se detectan en analyze_expr)
                 deps.mark_used("int_to_str_runtime");
+                // Un float que no es un literal se formatea en runtime
+                if !matches!(&expr.node, Expr::Float(_)) && self.is_float(expr) {
+                    deps.mark_used("float_to_str");
+                }
             }
             Stmt::Return(expr) => {
                 if let Some(expr) = expr {
-                    Self::analyze_expr(expr, deps);
+                    self.analyze_expr(expr, deps);
                 }
             }
             Stmt::If { condition, then_body, else_body } => {
-                Self::analyze_expr(condition, deps);
+                self.analyze_expr(condition, deps);
                 for stmt in then_body {
-                    Self::analyze_stmt(stmt, deps);
+                    self.analyze_stmt(stmt, deps);
                 }
                 if let Some(else_body) = else_body {
                     for stmt in else_body {
-                        Self::analyze_stmt(stmt, deps);
+                        self.analyze_stmt(stmt, deps);
                     }
                 }
             }
             Stmt::While { condition, body } => {
-                Self::analyze_expr(condition, deps);
+                self.analyze_expr(condition, deps);
                 for stmt in body {
-                    Self::analyze_stmt(stmt, deps);
+                    self.analyze_stmt(stmt, deps);
                 }
             }
-            Stmt::For { start, end, body, .. } => {
-                Self::analyze_expr(start, deps);
-                Self::analyze_expr(end, deps);
+            Stmt::For { iter, body, .. } => {
+                for expr in iter.exprs() {
+                    self.analyze_expr(expr, deps);
+                }
+                // Strings se recorren por carácter (string_slice); dicts y sets por sus claves
+                for source in iter.sources().0 {
+                    match self.types.get(source) {
+                        Type::String => deps.mark_used("string_slice"),
+                        Type::Dict { .. } | Type::Set(_) => deps.mark_used("dict_keys"),
+                        _ => {}
+                    }
+                }
                 for stmt in body {
-                    Self::analyze_stmt(stmt, deps);
+                    self.analyze_stmt(stmt, deps);
                 }
             }
             Stmt::Break | Stmt::Continue => {
                 // No necesitan funciones del runtime
             }
             Stmt::Expr(expr) => {
-                Self::analyze_expr(expr, deps);
+                self.analyze_expr(expr, deps);
             }
             Stmt::Fn { body, .. } => {
                 for stmt in body {
-                    Self::analyze_stmt(stmt, deps);
+                    self.analyze_stmt(stmt, deps);
+                }
+            }
+            Stmt::Struct { init, destroy, methods, .. } => {
+                let bodies = init.iter().chain(destroy.iter()).chain(methods.iter().map(|(_, m)| m));
+                for method in bodies {
+                    for stmt in &method.body {
+                        self.analyze_stmt(stmt, deps);
+                    }
+                }
+            }
+            Stmt::Impl { methods, .. } => {
+                for (_, method) in methods {
+                    for stmt in &method.body {
+                        self.analyze_stmt(stmt, deps);
+                    }
                 }
             }
             _ => {
-                // Otros tipos de statements (Struct, Import, etc.) no necesitan análisis especial
+                // Otros tipos de statements (Enum, Trait, Import, etc.) no necesitan análisis especial
             }
         }
     }
     
     /// Analizar una expresión
-    fn analyze_expr(expr: &Expr, deps: &mut DependencyGraph) {
+    fn analyze_expr(&self, expr: &Expr, deps: &mut DependencyGraph) {
         match expr {
             Expr::Number(_) | Expr::Float(_) | Expr::Bool(_) => {
                 // Literales no necesitan funciones del runtime
@@ -85,21 +135,46 @@ impl UsageAnalyzer {
             Expr::Ident(_) => {
                 // Variables simples no necesitan funciones del runtime
             }
-            Expr::BinaryOp { left, right, .. } => {
-                Self::analyze_expr(left, deps);
-                Self::analyze_expr(right, deps);
+            Expr::BinaryOp { left, op, right } => {
+                self.analyze_expr(left, deps);
+                self.analyze_expr(right, deps);
+                if *op == BinOp::In {
+                    deps.mark_used("dict_contains");
+                }
+            }
+            Expr::Assign { name, value } if name == "_array_set" => {
+                // arr[i] = v o d[k] = v (el parser lo codifica como `Index == valor`)
+                if let Expr::BinaryOp { left, right, .. } = &value.node {
+                    if let Expr::Index { array, index } = &left.node {
+                        self.analyze_expr(array, deps);
+                        self.analyze_expr(index, deps);
+                        deps.mark_used(if self.is_dict(array) { "dict_set" } else { "array_set" });
+                    }
+                    self.analyze_expr(right, deps);
+                }
             }
             Expr::Assign { value, .. } => {
-                Self::analyze_expr(value, deps);
+                self.analyze_expr(value, deps);
             }
-            Expr::Call { name, args, .. } => {
+            Expr::Call { module, name, args } => {
                 // Detectar llamadas a funciones del runtime
                 match name.as_str() {
+                    // Constructor Struct.new(...): el objeto vive en el heap
+                    "new" if module.is_some() => deps.mark_used("heap_alloc"),
+                    
                     // Arrays
                     "array_new" => deps.mark_used("array_new"),
                     "array_from_values" => deps.mark_used("array_from_values"),
                     "array_get" => deps.mark_used("array_get"),
                     "array_set" => deps.mark_used("array_set"),
+                    "len" if args.first().map_or(false, |arg| self.is_dict(arg) || self.is_set(arg)) => deps.mark_used("dict_len"),
+                    "list" if args.first().map_or(false, |arg| self.is_set(arg)) => deps.mark_used("dict_keys"),
+                    // map/filter arman un array nuevo con los resultados de la closure;
+                    // range(...) fuera de un for o una comprehension, con los enteros del rango
+                    "map" | "filter" | "range" => {
+                        deps.mark_used("array_new");
+                        deps.mark_used("array_append");
+                    }
                     "array_len" | "len" => deps.mark_used("array_len"),
                     "array_append" | "append" => deps.mark_used("array_append"),
                     "array_pop" | "pop" => deps.mark_used("array_pop"),
@@ -119,8 +194,31 @@ impl UsageAnalyzer {
                     "string_slice" | "slice" => deps.mark_used("string_slice"),
                     "string_upper" | "upper" => deps.mark_used("string_upper"),
                     "string_lower" | "lower" => deps.mark_used("string_lower"),
+                    "string_strip" | "strip" => deps.mark_used("string_strip"),
+                    "string_replace" | "replace" => deps.mark_used("string_replace"),
+                    "string_split" | "split" => deps.mark_used("string_split"),
+                    "string_join" | "join" => deps.mark_used("string_join"),
+                    "string_find" | "find" => deps.mark_used("string_find"),
+                    "string_startswith" | "startswith" => deps.mark_used("string_startswith"),
+                    "string_endswith" | "endswith" => deps.mark_used("string_endswith"),
                     "string_free" => deps.mark_used("string_free"),
                     
+                    // Dicts
+                    "dict" => deps.mark_used("dict_new"),
+                    "get" => deps.mark_used("dict_get"),
+                    "keys" => deps.mark_used("dict_keys"),
+                    "values" => deps.mark_used("dict_values"),
+                    "items" => deps.mark_used("dict_items"),
+                    
+                    // float(s) parsea el String en runtime
+                    "float" if args.first().map_or(false, |arg| *self.types.get(arg) == Type::String) => {
+                        deps.mark_used("float_from_string");
+                    }
+                    
+                    // Sets (tablas hash del runtime de Dict)
+                    "set" if args.is_empty() => deps.mark_used("dict_new"),
+                    "set" => deps.mark_used("set_from_array"),
+                    
                     // Otras funciones (stdlib, etc.)
                     _ => {
                         // Funciones definidas por el usuario o stdlib
@@ -130,37 +228,216 @@ impl UsageAnalyzer {
                 
                 // Analizar argumentos
                 for arg in args {
-                    Self::analyze_expr(arg, deps);
+                    self.analyze_expr(arg, deps);
                 }
             }
             Expr::Index { array, index } => {
-                Self::analyze_expr(array, deps);
-                Self::analyze_expr(index, deps);
-                // Acceso a array: necesita array_get
-                deps.mark_used("array_get");
+                self.analyze_expr(array, deps);
+                self.analyze_expr(index, deps);
+                // Acceso a dict: dict_index; a string: string_char_at; a array: array_get
+                deps.mark_used(if self.is_dict(array) {
+                    "dict_index"
+                } else if self.is_string(array) {
+                    "string_char_at"
+                } else {
+                    "array_get"
+                });
+            }
+            Expr::DictLiteral { pairs } => {
+                // Dict literal: tabla hash creada con dict_new y llenada con dict_set
+                deps.mark_used("dict_new");
+                deps.mark_used("dict_set");
+                for (key, value) in pairs {
+                    self.analyze_expr(key, deps);
+                    self.analyze_expr(value, deps);
+                }
+            }
+            Expr::StructLiteral { fields, .. } => {
+                // Struct literal: el objeto se reserva con heap_alloc
+                deps.mark_used("heap_alloc");
+                for (_, value) in fields {
+                    self.analyze_expr(value, deps);
+                }
+            }
+            Expr::SetLiteral(elements) => {
+                // Set literal: Dict con valores en 1, dict_set descarta duplicados
+                deps.mark_used("dict_new");
+                deps.mark_used("dict_set");
+                for element in elements {
+                    self.analyze_expr(element, deps);
+                }
+            }
+            Expr::ListComprehension { expr, clauses } => {
+                // List comprehension: Array en heap llenado con array_append
+                deps.mark_used("array_new");
+                deps.mark_used("array_append");
+                self.analyze_expr(expr, deps);
+                self.analyze_clauses(clauses, deps);
+            }
+            Expr::SetComprehension { expr, clauses } => {
+                deps.mark_used("dict_new");
+                deps.mark_used("dict_set");
+                self.analyze_expr(expr, deps);
+                self.analyze_clauses(clauses, deps);
+            }
+            Expr::DictComprehension { key_expr, value_expr, clauses } => {
+                deps.mark_used("dict_new");
+                deps.mark_used("dict_set");
+                self.analyze_expr(key_expr, deps);
+                self.analyze_expr(value_expr, deps);
+                self.analyze_clauses(clauses, deps);
+            }
+            Expr::MethodCall { object, method, args } => {
+                self.analyze_expr(object, deps);
+                for arg in args {
+                    self.analyze_expr(arg, deps);
+                }
+                if self.is_dict(object) {
+                    let helper = match method.as_str() {
+                        "get" => "dict_get",
+                        "keys" => "dict_keys",
+                        "values" => "dict_values",
+                        "items" => "dict_items",
+                        "len" => "dict_len",
+                        "contains" => "dict_contains",
+                        "remove" => "dict_delete",
+                        _ => return,
+                    };
+                    deps.mark_used(helper);
+                } else if self.is_set(object) {
+                    let helper = match method.as_str() {
+                        "add" => "dict_set",
+                        "remove" => "dict_delete",
+                        "contains" => "dict_contains",
+                        "len" => "dict_len",
+                        "union" => "set_union",
+                        "intersection" => "set_intersection",
+                        "difference" => "set_difference",
+                        _ => return,
+                    };
+                    deps.mark_used(helper);
+                } else {
+                    // El backend elige el helper por el nombre del método: los de arrays
+                    // con cualquier receptor (salvo un método propio del struct) y los de
+                    // strings si el receptor es o puede ser un string (is_string_expr).
+                    // Se marcan los que puede llamar aunque el receptor no tenga tipo.
+                    let array_helper = match method.as_str() {
+                        "append" => Some("array_append"),
+                        "pop" => Some("array_pop"),
+                        "insert" => Some("array_insert"),
+                        "remove" => Some("array_remove"),
+                        "index" => Some("array_index"),
+                        "count" => Some("array_count"),
+                        "sort" => Some("array_sort"),
+                        "reverse" => Some("array_reverse"),
+                        "len" => Some("array_len"),
+                        _ => None,
+                    };
+                    let string_helper = match method.as_str() {
+                        "upper" => Some("string_upper"),
+                        "lower" => Some("string_lower"),
+                        "strip" => Some("string_strip"),
+                        "replace" => Some("string_replace"),
+                        "split" => Some("string_split"),
+                        "join" => Some("string_join"),
+                        "find" => Some("string_find"),
+                        "startswith" => Some("string_startswith"),
+                        "endswith" => Some("string_endswith"),
+                        _ => None,
+                    };
+                    if let Some(helper) = array_helper {
+                        deps.mark_used(helper);
+                    }
+                    if let Some(helper) = string_helper.filter(|_| matches!(self.types.get(object), Type::String | Type::Unknown)) {
+                        deps.mark_used(helper);
+                    }
+                }
             }
             Expr::ArrayLiteral(elements) => {
                 // Array literal: necesita array_from_values
                 deps.mark_used("array_from_values");
                 for elem in elements {
-                    Self::analyze_expr(elem, deps);
+                    self.analyze_expr(elem, deps);
+                }
+            }
+            Expr::Slice { object, start, end, step } => {
+                // Slice (límites omitidos o negativos y paso): necesita string_slice_step
+                self.analyze_expr(object, deps);
+                for bound in [start, end, step].into_iter().flatten() {
+                    self.analyze_expr(bound, deps);
                 }
+                deps.mark_used("string_slice_step");
             }
-            Expr::Slice { object, .. } => {
-                // Slice: necesita string_slice
-                Self::analyze_expr(object, deps);
-                deps.mark_used("string_slice");
+            // Option, Result y enums de usuario: el tagged union vive en el heap
+            Expr::Some(inner) | Expr::Ok(inner) | Expr::Err(inner) => {
+                deps.mark_used("heap_alloc");
+                self.analyze_expr(inner, deps);
+            }
+            Expr::None => deps.mark_used("heap_alloc"),
+            Expr::EnumVariant { args, .. } => {
+                deps.mark_used("heap_alloc");
+                for arg in args {
+                    self.analyze_expr(arg, deps);
+                }
+            }
+            Expr::FString { parts } => {
+                // Las partes float se formatean con float_to_str
+                for part in parts {
+                    if let FStringPart::Expr(expr) = part {
+                        self.analyze_expr(expr, deps);
+                        if self.is_float(expr) {
+                            deps.mark_used("float_to_str");
+                        }
+                    }
+                }
+            }
+            Expr::Lambda { body, .. } => {
+                // La closure y su entorno viven en el heap
+                deps.mark_used("heap_alloc");
+                self.analyze_expr(body, deps);
             }
             Expr::Match { expr, arms } => {
-                Self::analyze_expr(expr, deps);
+                self.analyze_expr(expr, deps);
+                // Respaldo en runtime si ningún brazo coincide
+                deps.mark_used("panic_match_failed");
                 for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.analyze_expr(guard, deps);
+                    }
                     // MatchArm.body es Box<Expr>, necesitamos dereferenciar
-                    Self::analyze_expr(&*arm.body, deps);
+                    self.analyze_expr(&*arm.body, deps);
                 }
             }
-            _ => {
-                // Otras expresiones (Borrow, Deref, Some, None, Ok, Err, StructLiteral, FieldAccess, MethodCall, etc.)
-                // No necesitan funciones del runtime directamente
+            // Sin helpers propios: solo se analizan sus subexpresiones (`self.items = []`)
+            Expr::FieldAssign { object, value, .. } => {
+                self.analyze_expr(object, deps);
+                self.analyze_expr(value, deps);
+            }
+            Expr::FieldAccess { object: inner, .. }
+            | Expr::Borrow { expr: inner, .. }
+            | Expr::Deref(inner)
+            | Expr::Not(inner)
+            | Expr::PropagateError(inner) => self.analyze_expr(inner, deps),
+            Expr::CompoundAssign { value, .. } => self.analyze_expr(value, deps),
+            Expr::TupleLiteral(items) | Expr::SuperCall { args: items, .. } => {
+                for item in items {
+                    self.analyze_expr(item, deps);
+                }
+            }
+            Expr::Ternary { condition, then_expr, else_expr } => {
+                self.analyze_expr(condition, deps);
+                self.analyze_expr(then_expr, deps);
+                self.analyze_expr(else_expr, deps);
+            }
+        }
+    }
+    
+    /// Analizar los iteradores y filtros de las cláusulas de una comprehension
+    fn analyze_clauses(&self, clauses: &[ComprehensionClause], deps: &mut DependencyGraph) {
+        for clause in clauses {
+            self.analyze_expr(&clause.iter, deps);
+            for condition in &clause.conditions {
+                self.analyze_expr(condition, deps);
             }
         }
     }
@@ -0,0 +1,122 @@
+//! Tests de la convención de llamada: argumentos en registros y en stack
+//! (Microsoft x64 en Windows, System V en Linux)
+
+mod common;
+
+use adead_backend::Target;
+use common::generate_for as asm_for;
+
+const SEIS_PARAMS: &str = r#"
+    fn suma6(a, b, c, d, e, f) {
+        return a + b + c + d + e + f
+    }
+    let x = suma6(1, 2, 3, 4, 5, 6)
+"#;
+
+#[test]
+fn test_windows_passes_extra_args_after_shadow_space() {
+    let asm = asm_for(Target::X86_64Windows, SEIS_PARAMS);
+
+    // Caller: 4 en registros, 2 en stack después de los 32 bytes de shadow space
+    assert!(asm.contains("sub rsp, 48  ; shadow space (32) + 2 args en stack, alineado a 16"));
+    assert!(asm.contains("mov [rsp + 32], rax  ; arg4 en stack"));
+    assert!(asm.contains("mov [rsp + 40], rax  ; arg5 en stack"));
+    assert!(asm.contains("mov r9, [rsp + 64]  ; arg3"));
+    assert!(asm.contains("add rsp, 96  ; liberar args y shadow space"));
+
+    // Callee: los parámetros 5 y 6 se leen del stack del caller
+    assert!(asm.contains("mov rax, [rbp + 48]  ; cargar param4 desde stack del caller"));
+    assert!(asm.contains("mov rax, [rbp + 56]  ; cargar param5 desde stack del caller"));
+}
+
+#[test]
+fn test_system_v_uses_six_registers_without_shadow_space() {
+    let asm = asm_for(Target::X86_64Linux, SEIS_PARAMS);
+
+    assert!(asm.contains("mov rdi, [rsp + 40]  ; arg0"));
+    assert!(asm.contains("mov r9, [rsp + 0]  ; arg5"));
+    assert!(asm.contains("mov [rbp - 8], rdi  ; guardar param0: a"));
+    assert!(asm.contains("guardar param5: f"));
+    assert!(!asm.contains("desde stack del caller"));
+
+    let siete = "fn f(a, b, c, d, e, g, h) {\n    return h\n}\nlet x = f(1, 2, 3, 4, 5, 6, 7)\n";
+    let asm = asm_for(Target::X86_64Linux, siete);
+    // 7 args apilados (56) + 1 en stack (8) = 64: ya alineado
+    assert!(asm.contains("sub rsp, 8  ; shadow space (0) + 1 args en stack, alineado a 16"));
+    assert!(asm.contains("mov [rsp + 0], rax  ; arg6 en stack"));
+    assert!(asm.contains("mov rax, [rbp + 16]  ; cargar param6 desde stack del caller"));
+}
+
+#[test]
+fn test_call_keeps_stack_aligned_for_odd_arg_counts() {
+    let src = "fn id(n) {\n    return n\n}\nlet x = id(7)\n";
+    let asm = asm_for(Target::X86_64Windows, src);
+    // 1 arg apilado (8) + shadow space (32) + 8 de padding = 48
+    assert!(asm.contains("sub rsp, 40  ; shadow space (32) + 0 args en stack, alineado a 16"));
+    assert!(asm.contains("add rsp, 48  ; liberar args y shadow space"));
+}
+
+#[test]
+fn test_constructor_and_methods_accept_more_than_four_args() {
+    let src = r#"
+        struct Caja {
+            a
+            b
+            c
+            d
+            init(a, b, c, d) {
+                self.a = a
+                self.b = b
+                self.c = c
+                self.d = d
+            }
+            fn total(self, x, y, z, w) {
+                return x + y + z + w
+            }
+        }
+        let caja = Caja.new(1, 2, 3, 4)
+        let t = caja.total(5, 6, 7, 8)
+    "#;
+    let asm = asm_for(Target::X86_64Windows, src);
+
+    // self + 4 args: el cuarto argumento del usuario ya no se descarta
+    assert!(asm.contains("mov [rsp + 32], rax  ; arg4 en stack"));
+    assert!(asm.contains("call fn_Caja_new  ; constructor"));
+    assert!(asm.contains("mov rax, [rbp + 48]  ; cargar param4 desde stack del caller"));
+    assert!(asm.contains("guardar param4: d"));
+    assert!(asm.contains("guardar param4: w"));
+}
+
+#[test]
+fn test_stack_passed_args_run() {
+    let src = r#"
+        fn suma6(a, b, c, d, e, f) {
+            return a + b + c + d + e + f
+        }
+        print suma6(1, 2, 3, 4, 5, 6)
+        fn ultimo(a, b, c, d, e, g, h) {
+            return h * 10 + g
+        }
+        print ultimo(1, 2, 3, 4, 5, 6, 7)
+        struct Caja {
+            a
+            b
+            c
+            d
+            init(a, b, c, d) {
+                self.a = a
+                self.b = b
+                self.c = c
+                self.d = d
+            }
+            fn total(self, x, y, z, w) {
+                return self.d + x + y + z + w
+            }
+        }
+        let caja = Caja.new(1, 2, 3, 4)
+        print caja.total(5, 6, 7, 8)
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["21", "76", "30"]);
+}
+
@@ -0,0 +1,83 @@
+//! Tests para closures: capturas en un entorno del heap y llamadas indirectas
+
+mod common;
+
+#[test]
+fn test_closure_captures_environment() {
+    let asm = common::generate("let base = 10\nlet sin_uso = 1\nlet sumar = lambda x: x + base\nlet r = sumar(5)\nprint r\n");
+    // Solo se captura lo que usa el cuerpo, copiado a un entorno en el heap
+    assert!(asm.contains("    mov rcx, 8  ; entorno: 1 captura(s)\n    sub rsp, 32  ; shadow space\n    call heap_alloc"));
+    assert!(asm.contains("mov rax, [rbp - 8]  ; capturar base"));
+    assert!(!asm.contains("capturar sin_uso"));
+    // La lambda lee la captura desde el entorno (R10) a su propio frame
+    assert!(asm.contains("mov rax, [r10 + 0]  ; captura base"));
+    // Closure = [código, entorno]
+    assert!(asm.contains("    mov rcx, 16  ; closure: código + entorno"));
+    assert!(asm.contains("    lea rcx, [rel lambda_0]\n    mov [rax], rcx  ; código"));
+    assert!(asm.contains("    mov [rax + 8], rcx  ; entorno"));
+    assert!(asm.contains("\nheap_alloc:"));
+}
+
+#[test]
+fn test_closure_call_is_indirect() {
+    let asm = common::generate("let k = 2\nlet f = lambda x: x * k\nlet r = f(3)\nprint r\n");
+    assert!(asm.contains("    mov rax, [rbp - 24]  ; closure\n    mov r10, [rax + 8]  ; entorno\n    call qword [rax]  ; closure f"));
+    // Una función del usuario tiene prioridad sobre una variable con su nombre
+    let asm = common::generate("fn doble(x) {\n    return x * 2\n}\nlet doble = doble(3)\nlet r = doble(4)\nprint r\n");
+    assert!(asm.contains("call fn_doble"));
+    assert!(!asm.contains("call qword [rax]"));
+}
+
+#[test]
+fn test_nested_closure_and_no_captures() {
+    let asm = common::generate("let k = 1\nlet f = lambda a: lambda b: a * b + k\nlet g = lambda x: x + 1\n");
+    // La lambda interna captura el parámetro de la externa y la captura de esta
+    assert!(asm.contains("mov rcx, 16  ; entorno: 2 captura(s)"));
+    assert!(asm.contains("mov rax, [r10 + 0]  ; captura a"));
+    assert!(asm.contains("mov rax, [r10 + 8]  ; captura k"));
+    assert!(asm.contains("mov qword [rbp - 32], 0  ; closure sin capturas"));
+}
+
+#[test]
+fn test_map_filter_reduce_call_closures() {
+    let src = "let xs = [1, 2, 3]\nlet k = 10\nlet ys = map(lambda x: x * k, xs)\nlet zs = filter(lambda y: y > k, ys)\nlet total = reduce(lambda a, b: a + b, zs, 0)\nprint total\n";
+    let asm = common::generate(src);
+    for builtin in ["map", "filter", "reduce"] {
+        assert!(asm.contains(&format!("call qword [rax]  ; {}", builtin)), "{} debe llamar a la closure", builtin);
+    }
+    // map y filter construyen un array nuevo
+    assert!(asm.contains("je filter_next_6  ; no cumple el predicado"));
+    assert_eq!(asm.matches("call array_append").count(), 2);
+    assert!(asm.contains("\narray_new:"));
+    // El frame de main alcanza para todos los slots
+    assert!(asm.contains("sub rsp, 240  ; Allocate space for shadow space (32) + local vars (208)"));
+}
+
+#[test]
+fn test_closures_run() {
+    let src = r#"
+        let base = 10
+        let sumar = lambda x: x + base
+        print sumar(5)
+        let k = 1
+        let f = lambda a: lambda b: a * b + k
+        let por3 = f(3)
+        print por3(4)
+        fn contador(inicio: int64) {
+            let paso = 2
+            return lambda x: x * paso + inicio
+        }
+        let c = contador(100)
+        print c(7)
+        let xs = [1, 2, 3]
+        let m = 10
+        let ys = map(lambda x: x * m, xs)
+        print ys[2]
+        let zs = filter(lambda y: y > m, ys)
+        print len(zs)
+        print reduce(lambda a, b: a + b, zs, 1)
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    // contador(100) retorna una closure que sobrevive al frame donde se creó
+    assert_eq!(common::values(&stdout), ["15", "13", "114", "30", "2", "51"]);
+}
@@ -0,0 +1,122 @@
+//! Utilidades compartidas por los tests del backend
+//!
+//! - `generate`: ASM para el target del host (tests sobre el código emitido)
+//! - `run`: compila para Linux, ensambla con nasm, linkea con ld y ejecuta el programa
+//!   (tests de comportamiento). Retorna `None` si no se puede ejecutar en esta máquina
+//!   (host que no es Linux, o sin nasm/ld en el PATH); el test se omite
+
+#![allow(dead_code)] // cada archivo de tests usa solo una parte
+
+use adead_backend::{CodeGenerator, Target};
+use adead_parser::parse;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Tiempo máximo de ejecución de un programa de prueba (un bucle infinito no cuelga los tests)
+const RUN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// ASM generado para el target del host
+pub fn generate(src: &str) -> String {
+    let program = parse(src).unwrap();
+    let mut gen = CodeGenerator::new();
+    gen.generate(&program).unwrap()
+}
+
+/// ASM generado para un target concreto
+pub fn generate_for(target: Target, src: &str) -> String {
+    let program = parse(src).unwrap();
+    let mut gen = CodeGenerator::with_target(target);
+    gen.generate(&program).unwrap()
+}
+
+/// Compilar y ejecutar el programa: (stdout, código de salida). Si terminó por una señal,
+/// el código es 128 + señal, como en la shell
+pub fn run(src: &str) -> Option<(String, i32)> {
+    if !Target::host().is_linux() || !has_tool("nasm") || !has_tool("ld") {
+        eprintln!("(ejecución omitida: se necesita Linux con nasm y ld)");
+        return None;
+    }
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "adead-backend-test-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let result = build_and_run(&dir, src);
+    let _ = std::fs::remove_dir_all(&dir);
+    Some(result)
+}
+
+/// Salida estándar del programa; falla si no terminó con código 0
+pub fn run_ok(src: &str) -> Option<String> {
+    let (stdout, code) = run(src)?;
+    assert_eq!(code, 0, "el programa terminó con código {}; salida: {:?}", code, stdout);
+    Some(stdout)
+}
+
+/// Valores impresos por el programa, separados por espacios o saltos de línea
+pub fn values(stdout: &str) -> Vec<&str> {
+    stdout.split_whitespace().collect()
+}
+
+fn build_and_run(dir: &Path, src: &str) -> (String, i32) {
+    let asm_file = dir.join("prog.asm");
+    let obj_file = dir.join("prog.o");
+    let exe_file = dir.join("prog");
+    std::fs::write(&asm_file, generate_for(Target::X86_64Linux, src)).unwrap();
+
+    let nasm = Command::new("nasm").arg("-f").arg("elf64").arg(&asm_file).arg("-o").arg(&obj_file).output().unwrap();
+    assert!(nasm.status.success(), "nasm falló:\n{}", String::from_utf8_lossy(&nasm.stderr));
+    let ld = Command::new("ld").args(["-e", "_start", "-static"]).arg(&obj_file).arg("-o").arg(&exe_file).output().unwrap();
+    assert!(ld.status.success(), "ld falló:\n{}", String::from_utf8_lossy(&ld.stderr));
+
+    let mut child = Command::new(&exe_file).stdout(Stdio::piped()).spawn().unwrap();
+    // La salida se lee en paralelo: un programa que escribe mucho no se bloquea en el pipe
+    let mut stdout = child.stdout.take().unwrap();
+    let reader = std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = stdout.read_to_end(&mut bytes);
+        bytes
+    });
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if start.elapsed() > RUN_TIMEOUT {
+            let _ = child.kill();
+            panic!("el programa no terminó en {:?}", RUN_TIMEOUT);
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    };
+    let stdout = reader.join().unwrap();
+    (String::from_utf8_lossy(&stdout).into_owned(), exit_code(&status))
+}
+
+fn exit_code(status: &std::process::ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    1
+}
+
+fn has_tool(program: &str) -> bool {
+    Command::new(program)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
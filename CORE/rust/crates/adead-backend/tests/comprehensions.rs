@@ -0,0 +1,90 @@
+//! Tests para comprehensions: el resultado se construye en heap, sin límite de tamaño
+
+mod common;
+
+#[test]
+fn test_list_comprehension_grows_in_heap() {
+    let asm = common::generate("let xs = [1, 2, 3]\nlet ys = [x * 2 for x in xs if x > 1]\nprint len(ys)\n");
+    assert!(asm.contains("array_new:"));
+    assert!(asm.contains("array_append:"));
+    assert!(asm.contains("    call array_new\n    add rsp, 32  ; restaurar shadow space\n    mov [rbp - "));
+    assert!(asm.contains("    mov rcx, [rsp]  ; puntero al Array\n    sub rsp, 32  ; shadow space\n    call array_append"));
+    // Ya no se reserva un buffer fijo en el frame
+    assert!(!asm.contains("mov [r12 + r13*8], rax"));
+}
+
+#[test]
+fn test_nested_clauses_and_filters() {
+    let asm = common::generate("let xs = [1, 2, 3]\nlet ps = [x * y for x in xs if x > 1 if x < 3 for y in xs if y != x]\n");
+    // Un bucle por cláusula y un salto al siguiente elemento por filtro
+    assert_eq!(asm.matches("    cmp rcx, [rbx + 8]  ; comparar con length").count(), 2);
+    assert_eq!(asm.matches("    je comp_next_1\n").count(), 2);
+    assert_eq!(asm.matches("    je comp_next_4\n").count(), 1);
+    assert_eq!(asm.matches("call array_append").count(), 1);
+}
+
+#[test]
+fn test_comprehension_returned_from_function() {
+    let asm = common::generate("fn dobles(xs: [int64]) -> [int64] {\n    return [x * 2 for x in xs]\n}\nlet d = dobles([1, 2])\nprint len(d)\n");
+    // El resultado se lee del slot (puntero al heap), no es una dirección del frame
+    assert!(asm.contains("  ; retornar resultado"));
+    assert!(!asm.contains("lea rax, [rbp - 16]  ; retornar resultado"));
+}
+
+#[test]
+fn test_dict_and_set_comprehensions_use_hash_table() {
+    let asm = common::generate("let xs = [1, 2, 3]\nlet d = {x: x * x for x in xs}\nlet s = {x % 2 for x in xs}\nprint d[2]\n");
+    assert_eq!(asm.matches("    call dict_new").count(), 2);
+    assert!(asm.contains("    mov r9, 0  ; tipo de clave\n    sub rsp, 32  ; shadow space\n    call dict_set"));
+    assert!(asm.contains("    mov r8, 1  ; valor presente\n    mov r9, 0  ; tipo de elemento\n    sub rsp, 32  ; shadow space\n    call dict_set"));
+    assert!(!asm.contains("array_append:"));
+}
+
+#[test]
+fn test_range_is_lowered_without_fn_range() {
+    // En una comprehension, range(...) es un bucle con contador: no se construye el array
+    let asm = common::generate("let sq = [x * x for x in range(1, 6, 2)]\n");
+    assert!(!asm.contains("fn_range"));
+    assert!(asm.contains("    add rax, 2  ; sumar step"));
+    assert_eq!(asm.matches("call array_append").count(), 1);
+
+    // Como valor, range(...) arma un Array en heap con los enteros del rango
+    let asm = common::generate("let r = range(4)\nprint len(r)\n");
+    assert!(!asm.contains("fn_range"));
+    assert!(asm.contains("; array del range"));
+    assert!(asm.contains("\narray_append:"));
+}
+
+
+#[test]
+fn test_comprehensions_run() {
+    let src = r#"
+        let xs = [1, 2, 3]
+        let ys = [x * 2 for x in xs if x > 1]
+        print len(ys)
+        print ys[0] + ys[1]
+        let ps = [x * y for x in xs if x > 1 if x < 3 for y in xs if y != x]
+        print ps[0]
+        print ps[1]
+        fn dobles(v: [int64]) -> [int64] {
+            return [x * 2 for x in v]
+        }
+        print dobles([5, 6])[1]
+        let sq = [x * x for x in range(1, 6, 2)]
+        print sq[2]
+        let m = {x: x * x for x in xs}
+        print m[3]
+        let s = {x % 2 for x in xs}
+        print len(s)
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["2", "10", "2", "6", "12", "25", "9", "2"]);
+}
+
+#[test]
+fn test_comprehension_beyond_old_stack_cap() {
+    // Antes el resultado vivía en un buffer de 32 elementos en el frame
+    let src = "let grandes = [i * 3 for i in range(100)]\nprint len(grandes)\nprint grandes[99]\n";
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["100", "297"]);
+}
@@ -0,0 +1,102 @@
+//! Tests para dicts: tabla hash en heap del runtime, emitida solo si se usa
+
+mod common;
+
+const EDADES: &str = r#"
+    let edades = {"ana": 31, "luis": 27}
+    edades["eva"] = 40
+    print edades["ana"]
+    print edades.get("zoe", 0)
+    print len(edades)
+    let borrado = edades.remove("luis")
+    let nombres = edades.keys()
+"#;
+
+#[test]
+fn test_dict_runtime_only_when_used() {
+    let asm = common::generate("let x = 1\nprint x\n");
+    assert!(!asm.contains("dict_find:"));
+    assert!(!asm.contains("dict_new:"));
+
+    let asm = common::generate(EDADES);
+    for helper in ["dict_new:", "dict_set:", "dict_resize:", "dict_find:", "dict_index:", "dict_get:", "dict_delete:", "dict_keys:"] {
+        assert!(asm.contains(helper), "falta {}", helper);
+    }
+    // No se usan: no se emiten
+    assert!(!asm.contains("dict_values:"));
+    assert!(!asm.contains("dict_contains:"));
+}
+
+#[test]
+fn test_dict_literal_lives_in_heap() {
+    let asm = common::generate(EDADES);
+    assert!(asm.contains("    mov rcx, 2  ; entradas previstas\n    mov rdx, 1  ; tipo de clave\n    sub rsp, 32  ; shadow space\n    call dict_new"));
+    assert_eq!(asm.matches("    mov r9, 1  ; tipo de clave\n    sub rsp, 32  ; shadow space\n    call dict_set").count(), 2);
+    // Ya no hay cabecera [count, capacity] en el frame
+    assert!(!asm.contains("; Dict literal: 2 pares\n    lea rax"));
+}
+
+#[test]
+fn test_dict_operations_call_runtime() {
+    let asm = common::generate(EDADES);
+    // d["eva"] = 40 inserta con claves string
+    assert!(asm.contains("    mov r9, 1\n    pop r8\n    pop rdx\n    pop rcx\n    sub rsp, 32  ; shadow space\n    call dict_set"));
+    assert!(asm.contains("call dict_index"));
+    // get con valor por defecto explícito: tres argumentos evaluados
+    assert!(asm.contains("    pop r8\n    pop rdx\n    pop rcx\n    sub rsp, 32  ; shadow space\n    call dict_get"));
+    assert!(asm.contains("call dict_len"));
+    assert!(asm.contains("call dict_delete"));
+    assert!(asm.contains("call dict_keys"));
+    assert!(!asm.contains("call array_get"));
+}
+
+#[test]
+fn test_int_keys_and_empty_dict() {
+    let asm = common::generate("let d = {1: 10, 2: 20}\nlet e = dict()\ne[7] = 1\nprint e.get(7)\n");
+    assert!(asm.contains("    mov rcx, 2  ; entradas previstas\n    mov rdx, 0  ; tipo de clave"));
+    // dict() no fija el tipo de clave: lo fija el primer set
+    assert!(asm.contains("    mov rdx, -1  ; tipo de clave sin determinar\n    sub rsp, 32  ; shadow space\n    call dict_new"));
+    assert!(asm.contains("    mov r9, 0\n    pop r8\n    pop rdx\n    pop rcx\n    sub rsp, 32  ; shadow space\n    call dict_set"));
+    assert!(asm.contains("    mov r8, 0\n    pop rdx\n    pop rcx\n    sub rsp, 32  ; shadow space\n    call dict_get"));
+}
+
+#[test]
+fn test_dict_operations_run() {
+    let src = r#"
+        let edades = {"ana": 31, "luis": 27}
+        edades["eva"] = 40
+        print edades["ana"]
+        print edades.get("zoe", 5)
+        print len(edades)
+        let borrado = edades.remove("luis")
+        print len(edades)
+        print edades["eva"]
+        if !edades.contains("luis") {
+            print edades["ana"] + 1
+        }
+        let d = {1: 10, 2: 20}
+        d[1] = 11
+        print d[1] + d[2]
+        for k in d.keys() {
+            print k
+        }
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["31", "5", "3", "2", "40", "32", "31", "1", "2"]);
+}
+
+#[test]
+fn test_dict_grows_past_initial_capacity() {
+    // dict() empieza con 8 slots: 39 claves obligan a varias redimensiones
+    let src = r#"
+        let e = dict()
+        for i in 1..40 {
+            e[i] = i * 2
+        }
+        print len(e)
+        print e[33]
+        print e.get(50, 7)
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["39", "66", "7"]);
+}
@@ -0,0 +1,84 @@
+//! Tests para float64 en runtime: formateo, comparaciones con ucomisd y conversiones
+
+mod common;
+
+#[test]
+fn test_print_float_uses_runtime_formatting() {
+    let asm = common::generate("let x = 1.5\nlet y = x * 2.0\nprint y\nprint f\"x={x}\"\n");
+    assert!(asm.contains("    movq rcx, xmm0  ; float64 (bits)"));
+    assert_eq!(asm.matches("call float_to_str").count(), 2);
+    assert!(asm.contains("mov byte [rdx + rax], 0xA  ; newline"));
+    assert!(asm.contains("\nfloat_to_str:"));
+    // NaN e infinito tienen su propio texto
+    assert!(asm.contains("jp .nan"));
+    assert!(asm.contains("mov rax, 0x7FF0000000000000  ; exponente todo a 1 y mantisa 0: infinito"));
+    // Solo se genera lo que se usa
+    assert!(!asm.contains("\nfloat_from_string:"));
+    // Un literal se formatea al compilar
+    let asm = common::generate("print 2.5\n");
+    assert!(!asm.contains("float_to_str"));
+}
+
+#[test]
+fn test_float_literals_are_exact_bits() {
+    let asm = common::generate("let x = 3.0\n");
+    // `dq 3` sería el entero 3
+    assert!(asm.contains("dq 0x4008000000000000  ; float64 literal 3"));
+}
+
+#[test]
+fn test_float_comparisons_use_ucomisd() {
+    let src = "let a = 1.5\nlet b = 2.5\nlet lt = a < b\nlet ge = a >= b\nlet eq = a == b\nlet ne = a != b\n";
+    let asm = common::generate(src);
+    // `<` se evalúa como `b > a`: con NaN (CF = 1) queda en falso
+    assert!(asm.contains("    ucomisd xmm0, xmm1  ; comparar float64 (falso con NaN)\n    seta al"));
+    assert!(asm.contains("    ucomisd xmm1, xmm0  ; comparar float64 (falso con NaN)\n    setae al"));
+    assert!(asm.contains("setnp cl  ; NaN: unordered (PF = 1) nunca es igual"));
+    assert!(asm.contains("setp cl  ; NaN: unordered (PF = 1) siempre es distinto"));
+}
+
+#[test]
+fn test_float_and_int_conversions() {
+    let asm = common::generate("let n = 7\nlet s = \"2.5\"\nlet a = float(n)\nlet b = float(s)\nlet c = int(a * b)\n");
+    assert!(asm.contains("cvtsi2sd xmm0, rax  ; float(x): int a float64"));
+    assert!(asm.contains("    call float_from_string\n    add rsp, 32  ; restaurar shadow space\n    movq xmm0, rax  ; float(s)"));
+    assert!(asm.contains("cvttsd2si rax, xmm0  ; int(x): truncar float64"));
+    assert!(asm.contains("\nfloat_from_string:"));
+}
+
+#[test]
+fn test_floats_run() {
+    let src = r#"
+        let x = 1.5
+        let y = x * 2.0
+        print y
+        print x / 4.0
+        let z = 0.1 + 0.2
+        print z
+        let n = 7
+        let a = float(n)
+        print a / 2.0
+        let b = float("2.5")
+        print b * 2.0
+        let cero = 0.0
+        print 1.0 / cero
+        let c = int(a * b)
+        print c
+        if x < y {
+            print c + 1
+        }
+        if x >= y {
+            print c + 2
+        }
+        let nan = cero / cero
+        if nan == nan {
+            print c + 3
+        }
+        if nan != nan {
+            print c + 4
+        }
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    // int(17.5) trunca; NaN nunca es igual a sí mismo
+    assert_eq!(common::values(&stdout), ["3", "0.375", "0.3", "3.5", "5", "inf", "17", "18", "21"]);
+}
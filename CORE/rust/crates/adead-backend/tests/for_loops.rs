@@ -0,0 +1,101 @@
+//! Tests para `for ... in`: rangos con paso, arrays, strings, dicts y desestructuración
+
+mod common;
+
+#[test]
+fn test_for_range_with_step() {
+    let src = "let paso = 2\nlet mut t = 0\nfor i in range(10, 0, -3) {\n    t = t + i\n}\nfor i in range(1, 8, paso) {\n    t = t + i\n}\n";
+    let asm = common::generate(src);
+    // Paso literal negativo: el sentido se decide al compilar
+    assert!(asm.contains("jle for_end_1  ; si <= end, salir (rango descendente)"));
+    assert!(asm.contains("add rax, -3  ; sumar step"));
+    // Paso en variable: el sentido se decide en runtime según su signo
+    assert!(asm.contains("cmp qword [rbp - 56], 0  ; signo del step\n    jl for_down_6"));
+    assert!(asm.contains("add rax, [rbp - 56]  ; sumar step"));
+}
+
+#[test]
+fn test_for_over_collections() {
+    let asm = common::generate("let mut t = 0\nfor c in \"abc\" {\n    t = t + 1\n}\nlet d = {1: 5}\nfor k in d {\n    t = t + k\n}\n");
+    // Strings carácter a carácter con string_slice
+    assert!(asm.contains("lea r8, [rdx + 1]  ; fin (un carácter)"));
+    assert!(asm.contains("call string_slice"));
+    assert!(asm.contains("\nstring_slice:"));
+    // Dicts: se recorre una copia de las claves
+    assert!(asm.contains("call dict_keys  ; recorrer una copia de las claves"));
+    assert!(asm.contains("\ndict_keys:"));
+}
+
+#[test]
+fn test_for_enumerate_and_zip() {
+    let asm = common::generate("let a = [1]\nlet b = [2]\nlet mut t = 0\nfor (i, x) in enumerate(a) {\n    t = t + x\n}\nfor (x, y) in zip(a, b) {\n    t = t + y\n}\n");
+    assert!(asm.contains("mov [rbp - 48], rcx  ; i (índice)"));
+    assert!(asm.contains("mov [rbp - 56], rax  ; x"));
+    // zip termina con la colección más corta
+    assert!(asm.contains("mov rax, [rbp - 72]\n    cmp rcx, [rax + 8]  ; comparar con length\n    jge for_end_4\n    mov rax, [rbp - 80]\n    cmp rcx, [rax + 8]  ; comparar con length\n    jge for_end_4"));
+    assert!(asm.contains("mov [rbp - 96], rax  ; y"));
+}
+
+#[test]
+fn test_for_break_and_continue() {
+    let asm = common::generate("let xs = [1]\nfor x in xs {\n    if x == 2 {\n        continue\n    }\n    if x == 3 {\n        break\n    }\n}\n");
+    assert!(asm.contains("jmp for_continue_2  ; continue"));
+    assert!(asm.contains("jmp for_end_1  ; break"));
+    assert!(asm.contains("for_continue_2:\n    inc qword [rbp - 16]  ; siguiente elemento\n    jmp for_start_0"));
+}
+
+#[test]
+fn test_for_loops_run() {
+    let src = r#"
+        let paso = 2
+        let mut t = 0
+        for i in range(10, 0, -3) {
+            t = t + i
+        }
+        print t
+        for i in range(1, 8, paso) {
+            t = t + i
+        }
+        print t
+        let mut n = 0
+        for c in "abc" {
+            n = n + 1
+        }
+        print n
+        let d = {1: 5, 7: 6}
+        let mut claves = 0
+        for k in d {
+            claves = claves + k
+        }
+        print claves
+        let a = [10, 20, 30]
+        let b = [1, 2]
+        let mut e = 0
+        for (i, x) in enumerate(a) {
+            e = e + i * x
+        }
+        print e
+        let mut z = 0
+        for (x, y) in zip(a, b) {
+            z = z + x * y
+        }
+        print z
+        let mut s = 0
+        for x in [1, 2, 3, 4, 5] {
+            if x == 2 {
+                continue
+            }
+            if x == 4 {
+                break
+            }
+            s = s + x
+        }
+        print s
+        for i in 9..12 {
+            print i
+        }
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    // 10+7+4+1; +1+3+5+7; "abc"; 1+7; 0*10+1*20+2*30; zip corta en b; 1+3; 9..12
+    assert_eq!(common::values(&stdout), ["22", "38", "3", "8", "80", "50", "4", "9", "10", "11"]);
+}
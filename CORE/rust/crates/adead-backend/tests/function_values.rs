@@ -0,0 +1,66 @@
+//! Tests para funciones como valores: closures estáticas, arrays, campos y parámetros
+
+mod common;
+
+const CUADRADO: &str = "fn cuadrado(x) {\n    return x * x\n}\n";
+
+#[test]
+fn test_function_as_value_uses_static_closure() {
+    let asm = common::generate(&format!("{}let f = cuadrado\nlet g = cuadrado\nlet r = f(7)\nprint r\n", CUADRADO));
+    // Una sola closure estática por función, con entorno 0
+    assert_eq!(asm.matches("closure_fn_cuadrado: dq fn_cuadrado, 0").count(), 1);
+    assert_eq!(asm.matches("lea rax, [rel closure_fn_cuadrado]  ; función cuadrado").count(), 2);
+    assert!(asm.contains("call qword [rax]  ; closure f"));
+}
+
+#[test]
+fn test_function_passed_as_parameter() {
+    let src = format!("{}fn aplicar(f, v) {{\n    return f(v)\n}}\nlet r = aplicar(cuadrado, 3)\nlet ys = map(cuadrado, [1])\n", CUADRADO);
+    let asm = common::generate(&src);
+    // Dentro de `aplicar` el parámetro se llama de forma indirecta
+    assert!(asm.contains("call qword [rax]  ; closure f"));
+    assert!(asm.contains("call fn_aplicar"));
+    assert!(asm.contains("call qword [rax]  ; map"));
+}
+
+#[test]
+fn test_functions_in_arrays_and_struct_fields() {
+    let src = format!("{}struct Op {{\n    aplicar\n}}\nlet fs = [cuadrado]\nlet g = fs[0]\nlet op = Op {{ aplicar: cuadrado }}\nlet r = g(2) + op.aplicar(3)\n", CUADRADO);
+    let asm = common::generate(&src);
+    assert!(asm.contains("call qword [rax]  ; closure g"));
+    // Un campo sin método con ese nombre se llama como closure
+    assert!(asm.contains("; Op.aplicar\n"));
+    assert!(asm.contains("call qword [rax]  ; closure Op.aplicar"));
+    assert!(!asm.contains("fn_Op_aplicar"));
+}
+
+#[test]
+fn test_function_values_run() {
+    let src = format!("{}{}", CUADRADO, r#"
+        fn doble(x) {
+            return x * 2
+        }
+        fn aplicar(f, v) {
+            return f(v)
+        }
+        fn componer(a: fn(int64) -> int64, b: fn(int64) -> int64, v: int64) -> int64 {
+            return a(b(v))
+        }
+        struct Op {
+            aplicar
+        }
+        let f = cuadrado
+        print f(7)
+        print aplicar(cuadrado, 3)
+        print aplicar(doble, 3)
+        let fs = [cuadrado, doble]
+        let g = fs[1]
+        print g(21)
+        let op = Op { aplicar: cuadrado }
+        print op.aplicar(5)
+        print map(doble, [4, 5])[1]
+        print componer(cuadrado, doble, 3)
+    "#);
+    let Some(stdout) = common::run_ok(&src) else { return };
+    assert_eq!(common::values(&stdout), ["49", "9", "6", "42", "25", "10", "36"]);
+}
@@ -0,0 +1,118 @@
+//! Tests para genéricos: una copia especializada por instancia, con nombres distintos
+
+mod common;
+
+const STACK: &str = r#"
+    struct Stack<T> {
+        items: [T]
+        init(primero: T) {
+            self.items = [primero]
+        }
+        fn push(self, x: T) {
+            self.items.append(x)
+        }
+        fn pop(self) -> T {
+            return self.items.pop()
+        }
+        fn size(self) -> int64 {
+            return len(self.items)
+        }
+    }
+"#;
+
+const GENERICOS: &str = r#"
+    fn max_of<T>(a: T, b: T) -> T {
+        if a > b {
+            return a
+        }
+        return b
+    }
+    struct Caja<T> {
+        valor: T
+        fn get(self) -> T {
+            return self.valor
+        }
+    }
+    fn envolver<T>(x: T) -> Caja<T> {
+        return Caja { valor: max_of(x, x) }
+    }
+    let a: int32 = 3
+    let b: int32 = 9
+    print max_of(a, b)
+    print max_of(2, 11)
+    let c = Caja { valor: 1.5 }
+    let e = envolver(42)
+    print e.get()
+"#;
+
+#[test]
+fn test_one_instance_per_type() {
+    let asm = common::generate(GENERICOS);
+    assert!(asm.contains("fn_max_of__int32:\n    ; instancia de max_of<int32>: T = int32 (4 bytes)"));
+    assert!(asm.contains("fn_max_of__int64:\n    ; instancia de max_of<int64>: T = int64 (8 bytes)"));
+    assert!(asm.contains("call fn_max_of__int32"));
+    assert!(asm.contains("call fn_max_of__int64"));
+    // La declaración genérica no genera código
+    assert!(!asm.contains("fn_max_of:"));
+    assert!(!asm.contains("vtable_Caja:"));
+}
+
+#[test]
+fn test_instance_uses_concrete_size() {
+    let asm = common::generate(GENERICOS);
+    let body = &asm[asm.find("fn_max_of__int32:").unwrap()..asm.find("fn_max_of__int32_end:").unwrap()];
+    assert!(body.contains("movsxd rax, eax  ; ajustar a int32 (dword)"));
+    let body = &asm[asm.find("fn_max_of__int64:").unwrap()..asm.find("fn_max_of__int64_end:").unwrap()];
+    assert!(!body.contains("ajustar a"));
+}
+
+#[test]
+fn test_generic_struct_instances() {
+    let asm = common::generate(GENERICOS);
+    assert!(asm.contains("vtable_Caja__float64:\n    dq fn_Caja__float64_get  ; método get"));
+    assert!(asm.contains("vtable_Caja__int64:\n    dq fn_Caja__int64_get  ; método get"));
+    assert!(asm.contains("; struct Caja__int64: instancia de Caja<int64>: T = int64 (8 bytes)"));
+    // El campo float64 se guarda con sus bits
+    assert!(asm.contains("movq rax, xmm0  ; float64 → rax (bits) para guardar\n    mov rcx, [rbp - 32]  ; dirección del struct\n    mov [rcx + 8], rax  ; Caja__float64.valor = expr"));
+}
+
+#[test]
+fn test_generic_calls_inside_instances() {
+    let asm = common::generate(GENERICOS);
+    // envolver<int64> llama a max_of<int64> y construye una Caja<int64>
+    let body = &asm[asm.find("fn_envolver__int64:").unwrap()..asm.find("fn_envolver__int64_end:").unwrap()];
+    assert!(body.contains("call fn_max_of__int64"));
+    assert!(body.contains("lea rcx, [rel vtable_Caja__int64]"));
+    assert!(asm.contains("call fn_envolver__int64"));
+}
+
+#[test]
+fn test_generic_instances_run() {
+    let Some(stdout) = common::run_ok(GENERICOS) else { return };
+    assert_eq!(common::values(&stdout), ["9", "11", "42"]);
+}
+
+#[test]
+fn test_generic_stack_with_init() {
+    // T sale del argumento del init o de la anotación; pop() es el método del struct,
+    // no el de los arrays
+    let src = format!("{}{}", STACK, r#"
+        let enteros = Stack.new(7)
+        let reales = Stack.new(2.5)
+        enteros.push(8)
+        enteros.push(9)
+        reales.push(1.5)
+        print reales.pop()
+        print enteros.pop()
+        print enteros.size()
+        let vacia: Stack<int64> = Stack.new(3)
+        print vacia.pop()
+    "#);
+    let asm = common::generate(&src);
+    assert!(asm.contains("fn_Stack__int64_new:"));
+    assert!(asm.contains("fn_Stack__float64_new:"));
+    assert!(asm.contains("call rax  ; llamada virtual a Stack__int64.pop"));
+    assert!(!asm.contains("fn_Stack_new"));
+    let Some(stdout) = common::run_ok(&src) else { return };
+    assert_eq!(common::values(&stdout), ["1.5", "9", "2", "3"]);
+}
@@ -0,0 +1,106 @@
+//! Tests para el heap allocator del runtime: free lists por clase de tamaño sobre arenas
+
+mod common;
+
+const ESTRUCTURAS: &str = r#"
+    struct Punto {
+        x: int64
+        y: int64
+    }
+    let a = [1, 2, 3]
+    append(a, 4)
+    array_free(a)
+    let s = "hola"
+    let u = upper(s)
+    string_free(s)
+    let d = {1: 2}
+    let p = Punto { x: 1, y: 2 }
+    print p.y
+"#;
+
+fn function_body<'a>(asm: &'a str, label: &str) -> &'a str {
+    let start = asm.find(&format!("\n{}:\n", label)).unwrap();
+    let body = &asm[start + 1..];
+    &body[..body.find("\n\n").unwrap_or(body.len())]
+}
+
+#[test]
+fn test_heap_allocator_only_when_used() {
+    let asm = common::generate("let x = 1\nprint x\n");
+    assert!(!asm.contains("heap_alloc:"));
+    assert!(!asm.contains("heap_free_lists"));
+
+    let asm = common::generate(ESTRUCTURAS);
+    assert!(asm.contains("heap_alloc:"));
+    assert!(asm.contains("heap_free:"));
+    assert!(asm.contains("heap_free_lists: times 9 dq 0"));
+}
+
+#[test]
+fn test_only_allocator_talks_to_the_system() {
+    let asm = common::generate(ESTRUCTURAS);
+    // Arenas y bloques grandes: las únicas llamadas a VirtualAlloc/VirtualFree
+    let alloc = function_body(&asm, "heap_alloc");
+    assert_eq!(asm.matches("call VirtualAlloc").count(), 2);
+    assert_eq!(alloc.matches("call VirtualAlloc").count(), 2);
+    assert!(alloc.contains("mov edx, 0x100000  ; dwSize (arena)"));
+    assert!(!asm.contains("call VirtualFree"));
+    assert!(function_body(&asm, "heap_free").contains("jmp VirtualFree"));
+}
+
+#[test]
+fn test_free_helpers_return_memory_to_allocator() {
+    let asm = common::generate(ESTRUCTURAS);
+    for helper in ["array_free", "string_free"] {
+        let body = function_body(&asm, helper);
+        assert_eq!(body.matches("call heap_free").count(), 2, "{} debe liberar datos y struct", helper);
+        // array_free(a) / string_free(s) llaman a la rutina del runtime, no a una función del usuario
+        assert!(asm.contains(&format!("    call {}\n", helper)), "{}", helper);
+        assert!(!asm.contains(&format!("fn_{}", helper)), "{}", helper);
+    }
+    for helper in ["array_new", "array_append", "string_from_literal", "string_upper", "dict_new"] {
+        assert!(function_body(&asm, helper).contains("call heap_alloc"), "{} debe usar heap_alloc", helper);
+    }
+}
+
+#[test]
+fn test_structs_live_in_heap() {
+    let asm = common::generate(ESTRUCTURAS);
+    assert!(asm.contains("; Struct literal: Punto (2 campos + vtable, 24 bytes en el heap)"));
+    assert!(asm.contains("    mov rcx, 24  ; tamaño en bytes\n    sub rsp, 32  ; shadow space\n    call heap_alloc"));
+    assert!(asm.contains("mov [rcx + 16], rax  ; Punto.y = 2"));
+}
+
+#[test]
+fn test_allocations_run() {
+    // Muchos objetos pequeños (free lists y arenas), un array que supera una arena
+    // (bloque grande) y liberaciones explícitas que se reutilizan
+    let src = r#"
+        struct Punto {
+            x: int64
+            y: int64
+        }
+        let s = "hola"
+        print upper(s)
+        string_free(s)
+        let mut total = 0
+        for i in 0..20000 {
+            let p = Punto { x: i, y: 1 }
+            total = total + p.y
+        }
+        print total
+        for i in 0..500 {
+            let a = [1, 2, 3]
+            append(a, i)
+            array_free(a)
+        }
+        let grande = []
+        for i in 0..200000 {
+            grande.append(i)
+        }
+        print len(grande)
+        print grande[199999]
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["HOLA", "20000", "200000", "199999"]);
+}
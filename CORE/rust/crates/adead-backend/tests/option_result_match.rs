@@ -1,5 +1,7 @@
 //! Tests para generación de código NASM de Option/Result/match
 
+mod common;
+
 use adead_backend::CodeGenerator;
 use adead_parser::parse;
 
@@ -18,6 +20,12 @@ fn test_generate_some() {
     assert!(asm.contains("tag = 1 (Some)"));
     assert!(asm.contains("mov rax, 1  ; tag Some = 1"));
     assert!(asm.contains("espacio para Option"));
+    // En el heap, para que sobreviva al frame que lo creó.
+    // Layout: tag en [base], valor en [base + 8] (donde lo leen match y `?`)
+    assert!(asm.contains("    mov rcx, 16  ; tamaño en bytes\n    sub rsp, 32  ; shadow space\n    call heap_alloc"));
+    assert!(asm.contains("mov [rcx + 8], rax  ; valor 0"));
+    assert!(asm.contains("mov [rcx], rax  ; tag = 1 (Some)"));
+    assert!(!asm.contains("lea rax, [rbp"));
 }
 
 #[test]
@@ -118,6 +126,66 @@ fn test_generate_match_with_wildcard() {
     assert!(asm.contains("jmp match_arm_"));
 }
 
+#[test]
+fn test_generate_match_rich_patterns() {
+    let src = r#"
+        let x = Some(Ok(7))
+        let r = match x {
+            Some(Ok(n)) if n > 5 => n,
+            Some(Ok(1 | 2)) => 2,
+            Some(Ok(3..=4)) => 3,
+            Some(Err(_)) => 4,
+            _ => 0
+        }
+        let s = match "hola" {
+            "hola" => 1,
+            _ => 0
+        }
+    "#;
+    let program = parse(src).unwrap();
+    let mut gen = CodeGenerator::new();
+    let asm = gen.generate(&program).unwrap();
+    
+    // Tags anidados, guarda, alternativas, rangos y strings
+    assert!(asm.contains("cmp qword [rax], 1  ; comparar tag con Some"));
+    assert!(asm.contains("cmp qword [rax], 0  ; comparar tag con Ok"));
+    assert!(asm.contains("binding n"));
+    assert!(asm.contains("guarda del brazo"));
+    assert!(asm.contains("match_or_ok"));
+    assert!(asm.contains("fuera del rango 3..=4"));
+    assert!(asm.contains("repe cmpsb"));
+    // Respaldo en runtime si ningún brazo coincide
+    assert!(asm.contains("jmp panic_match_failed"));
+    assert!(asm.contains("panic_match_failed:"));
+}
+
+#[test]
+fn test_match_rich_patterns_run() {
+    let src = r#"
+        fn clasificar(v) {
+            return match v {
+                Some(Ok(n)) if n > 5 => n,
+                Some(Ok(1 | 2)) => 2,
+                Some(Ok(3..=4)) => 3,
+                Some(Err(_)) => 4,
+                _ => 9
+            }
+        }
+        print clasificar(Some(Ok(7)))
+        print clasificar(Some(Ok(1)))
+        print clasificar(Some(Ok(4)))
+        print clasificar(Some(Err(1)))
+        print clasificar(None)
+        let s = match "hola" {
+            "hola" => 1,
+            _ => 5
+        }
+        print s
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["7", "2", "3", "4", "9", "1"]);
+}
+
 // ========== Tests para operador ? (PropagateError) ==========
 
 #[test]
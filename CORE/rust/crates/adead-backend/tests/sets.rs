@@ -0,0 +1,91 @@
+//! Tests para sets: tablas hash del runtime de Dict con valores en 1
+
+mod common;
+
+const NUMEROS: &str = r#"
+    let a = {1, 2, 2, 3}
+    a.add(4)
+    let quitado = a.remove(1)
+    let b = {x * 2 for x in [1, 2, 3] if x > 1}
+    let u = a.union(b)
+    let i = a.intersection(b)
+    let d = a.difference(b)
+    if 2 in a {
+        print len(u)
+    }
+"#;
+
+#[test]
+fn test_set_runtime_only_when_used() {
+    let asm = common::generate("let d = {1: 2}\nprint d[1]\n");
+    assert!(!asm.contains("set_combine:"));
+    assert!(!asm.contains("set_from_array:"));
+
+    let asm = common::generate(NUMEROS);
+    for helper in ["set_combine:", "set_union:", "set_intersection:", "set_difference:", "dict_contains:", "dict_delete:", "dict_len:"] {
+        assert!(asm.contains(helper), "falta {}", helper);
+    }
+    assert!(!asm.contains("set_from_array:"));
+}
+
+#[test]
+fn test_set_literal_deduplicates_in_heap() {
+    let asm = common::generate(NUMEROS);
+    // Cada elemento pasa por dict_set: los duplicados reemplazan la misma entrada
+    assert!(asm.contains("    mov rcx, 4  ; entradas previstas\n    mov rdx, 0  ; tipo de elemento\n    sub rsp, 32  ; shadow space\n    call dict_new"));
+    assert!(asm.contains("    mov r8, 1  ; valor presente\n    mov r9, 0  ; tipo de elemento\n    sub rsp, 32  ; shadow space\n    call dict_set"));
+    // Sin cabecera [count, capacity] en el frame
+    assert!(!asm.contains("mov qword [rax + 8], 4  ; capacity"));
+    // La comprehension ya no comparte el buffer fijo de arrays
+    assert!(!asm.contains("mov [r12 + r13*8 + 16], rax"));
+}
+
+#[test]
+fn test_set_operations_call_runtime() {
+    let asm = common::generate(NUMEROS);
+    assert!(asm.contains("call set_union"));
+    assert!(asm.contains("call set_intersection"));
+    assert!(asm.contains("call set_difference"));
+    assert!(asm.contains("call dict_delete"));
+    assert!(asm.contains("call dict_len"));
+    // `2 in a` evalúa el set primero y el elemento después: dict_contains(set, elemento)
+    assert!(asm.contains("    pop rdx\n    pop rcx\n    sub rsp, 32  ; shadow space\n    call dict_contains"));
+}
+
+#[test]
+fn test_set_from_list_and_string_elements() {
+    let asm = common::generate("let s = set([\"x\", \"y\", \"x\"])\nlet e = set()\ne.add(\"z\")\nprint \"y\" in s\nlet l = list(s)\n");
+    assert!(asm.contains("    mov rdx, 1\n    pop rcx\n    sub rsp, 32  ; shadow space\n    call set_from_array"));
+    assert!(asm.contains("    mov rdx, -1  ; tipo de elemento sin determinar\n    sub rsp, 32  ; shadow space\n    call dict_new"));
+    assert!(asm.contains("    mov r9, 1  ; tipo de elemento\n    sub rsp, 32  ; shadow space\n    call dict_set"));
+    assert!(asm.contains("call dict_keys"));
+}
+
+#[test]
+fn test_set_operations_run() {
+    let src = r#"
+        let a = {1, 2, 2, 3}
+        print len(a)
+        a.add(4)
+        a.add(4)
+        let quitado = a.remove(1)
+        print len(a)
+        let b = {x * 2 for x in [1, 2, 3] if x > 1}
+        print len(b)
+        print len(a.union(b))
+        let i = a.intersection(b)
+        for x in i {
+            print x
+        }
+        let d = a.difference(b)
+        print len(list(d))
+        if 2 in a {
+            print len(a) + 10
+        }
+        let s = set(["x", "y", "x"])
+        print len(s)
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    // {1, 2, 3}; {2, 3, 4}; {4, 6}; unión de 4; intersección {4}; diferencia {2, 3}
+    assert_eq!(common::values(&stdout), ["3", "3", "2", "4", "4", "2", "13", "2"]);
+}
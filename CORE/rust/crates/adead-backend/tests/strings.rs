@@ -0,0 +1,114 @@
+//! Tests para los built-ins de strings del runtime, la indexación de strings y los slices con paso
+
+mod common;
+
+#[test]
+fn test_string_builtins_call_runtime() {
+    let src = "let s = \"a,b\"\nlet p = split(s, \",\")\nlet j = join(\"-\", p)\nlet r = replace(s, \"a\", \"x\")\nlet u = upper(s)\nlet t = strip(s)\nlet n = find(s, \"b\")\nlet b = startswith(s, \"a\")\nlet e = endswith(s, \"b\")\n";
+    let asm = common::generate(src);
+    for helper in ["string_split", "string_join", "string_replace", "string_upper", "string_strip", "string_find", "string_startswith", "string_endswith"] {
+        assert!(asm.contains(&format!("call {}\n", helper)), "{}", helper);
+        assert!(asm.contains(&format!("\n{}:", helper)), "{}", helper);
+    }
+    assert!(!asm.contains("placeholder"));
+    // split y join trabajan sobre Arrays; replace busca con string_find_from
+    assert!(asm.contains("\narray_append:"));
+    assert!(asm.contains("\nstring_find_from:"));
+}
+
+#[test]
+fn test_string_methods_call_runtime() {
+    let src = "let s = \" a b \"\nlet p = s.split()\nlet t = s.strip()\nlet r = s.replace(\"a\", \"b\")\nlet n = s.find(\"b\")\nlet j = \",\".join(p)\n";
+    let asm = common::generate(src);
+    assert!(asm.contains("mov rdx, 0\n"), "split() sin separador pasa NULL");
+    for helper in ["string_split", "string_strip", "string_replace", "string_find", "string_join"] {
+        assert!(asm.contains(&format!("call {}\n", helper)), "{}", helper);
+    }
+}
+
+#[test]
+fn test_string_indexing_and_stepped_slices() {
+    let asm = common::generate("let s = \"hola\"\nlet c = s[-1]\nlet r = s[::-1]\nlet t = s[1:]\n");
+    assert!(asm.contains("call string_char_at"));
+    assert!(asm.contains("\nstring_char_at:"));
+    assert!(asm.contains("call string_slice_step"));
+    // Límites omitidos y paso por defecto
+    assert!(asm.contains("mov rax, 0x8000000000000000  ; start omitido"));
+    assert!(asm.contains("mov rax, 0x8000000000000000  ; end omitido"));
+    assert!(asm.contains("mov rax, 1  ; step omitido"));
+    assert!(!asm.contains("call array_get"));
+}
+
+#[test]
+fn test_string_helpers_are_emitted_selectively() {
+    // Solo find: ni split, ni join, ni replace, ni el slice con paso
+    let asm = common::generate("let s = \"hola\"\nlet n = find(s, \"la\")\n");
+    assert!(asm.contains("\nstring_find:"));
+    assert!(asm.contains("\nstring_find_from:"));
+    for helper in ["string_split", "string_join", "string_replace", "string_slice_step", "string_char_at", "array_append"] {
+        assert!(!asm.contains(&format!("\n{}:", helper)), "{}", helper);
+    }
+
+    // Sin strings no se genera ningún helper nuevo
+    let asm = common::generate("let x = 1 + 2\nprint x\n");
+    assert!(!asm.contains("string_find"));
+    assert!(!asm.contains("string_slice_step"));
+}
+
+#[test]
+fn test_untyped_receivers_link_their_helpers() {
+    // Parámetros y campos sin tipo: el backend llama a los helpers por el nombre del método
+    let src = r#"
+        fn add(xs, v) {
+            xs.append(v)
+        }
+        fn grita(s) {
+            return s.upper()
+        }
+        struct Pila {
+            items
+            fn push(self, x) {
+                self.items.append(x)
+            }
+        }
+        let a = [1, 2]
+        add(a, 3)
+        let p = Pila { items: [4] }
+        p.push(5)
+        let g = grita("hola")
+        print len(p.items)
+    "#;
+    let asm = common::generate(src);
+    for helper in ["array_append", "string_upper"] {
+        assert!(asm.contains(&format!("call {}\n", helper)), "{}", helper);
+        assert!(asm.contains(&format!("\n{}:", helper)), "{}", helper);
+    }
+
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["2"]);
+}
+
+#[test]
+fn test_string_builtins_run() {
+    // Los strings se imprimen sin salto de línea: se separan con " "
+    let src = r#"
+        let s = " hola,mundo "
+        let t = strip(s)
+        print upper(t)
+        print " "
+        let p = split(t, ",")
+        print join("-", p)
+        print " "
+        print replace(t, "o", "0")
+        print " "
+        let r = t[::-1]
+        print r
+        let n = find(t, "mundo")
+        print n
+        print " "
+        let b = startswith(t, "hola")
+        print b
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["HOLA,MUNDO", "hola-mundo", "h0la,mund0", "odnum,aloh", "5", "true"]);
+}
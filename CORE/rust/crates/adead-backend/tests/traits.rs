@@ -0,0 +1,88 @@
+//! Tests para traits: vtables por impl, objetos de trait y dispatch estático/dinámico
+
+mod common;
+
+const FORMAS: &str = r#"
+    trait Drawable {
+        fn area(self) -> int64
+        fn lados(self) -> int64
+    }
+    struct Circulo {
+        r: int64
+    }
+    struct Cuadrado {
+        l: int64
+    }
+    impl Drawable for Circulo {
+        fn area(self) -> int64 { return 3 * self.r * self.r }
+        fn lados(self) -> int64 { return 1 }
+    }
+    impl Drawable for Cuadrado {
+        fn area(self) -> int64 { return self.l * self.l }
+        fn lados(self) -> int64 { return 4 }
+    }
+    fn total(d: Drawable) -> int64 { return d.area() + d.lados() }
+    let c = Circulo { r: 2 }
+    let q = Cuadrado { l: 3 }
+    print c.area()
+    print total(q)
+    let formas: [Drawable] = [c, q]
+    print formas[1].lados()
+"#;
+
+#[test]
+fn test_trait_vtable_per_impl() {
+    let asm = common::generate(FORMAS);
+    // Slots en el orden en que el trait declara sus métodos
+    assert!(asm.contains("vtable_Drawable_for_Circulo:\n    dq fn_Circulo_area  ; Drawable.area\n    dq fn_Circulo_lados  ; Drawable.lados"));
+    assert!(asm.contains("vtable_Drawable_for_Cuadrado:\n    dq fn_Cuadrado_area  ; Drawable.area\n    dq fn_Cuadrado_lados  ; Drawable.lados"));
+    assert!(asm.contains("fn_Circulo_area:"));
+    assert!(asm.contains("fn_Cuadrado_lados:"));
+}
+
+#[test]
+fn test_concrete_receiver_calls_impl_directly() {
+    let asm = common::generate(FORMAS);
+    assert!(asm.contains("call fn_Circulo_area  ; llamada estática"));
+}
+
+#[test]
+fn test_trait_objects_live_in_the_heap() {
+    let asm = common::generate(FORMAS);
+    // Argumento de `total(d: Drawable)` y cada elemento del array heterogéneo
+    assert!(asm.contains("; objeto de trait Drawable (Cuadrado) en el heap: [datos, vtable]"));
+    assert!(asm.contains("; objeto de trait Drawable (Circulo) en el heap: [datos, vtable]"));
+    assert!(!asm.contains("dirección del objeto de trait"));
+}
+
+#[test]
+fn test_dispatch_through_trait_object_argument_and_array() {
+    let Some(stdout) = common::run_ok(FORMAS) else { return };
+    // c.area() = 12, total(q) = 9 + 4, formas[1].lados() = 4
+    assert_eq!(common::values(&stdout), ["12", "13", "4"]);
+}
+
+#[test]
+fn test_dispatch_through_returned_trait_objects() {
+    // Los objetos de trait sobreviven al frame de la función que los creó
+    let src = format!("{}{}", FORMAS, r#"
+        fn cuadrado(l: int64) -> Drawable {
+            let q = Cuadrado { l: l }
+            return q
+        }
+        fn todas() -> [Drawable] {
+            let c = Circulo { r: 1 }
+            let q = Cuadrado { l: 2 }
+            let xs: [Drawable] = [c, q]
+            return xs
+        }
+        let d = cuadrado(5)
+        print d.area()
+        for f in todas() {
+            print total(f)
+        }
+        print formas[0].area()
+    "#);
+    let Some(stdout) = common::run_ok(&src) else { return };
+    assert_eq!(common::values(&stdout), ["12", "13", "4", "25", "4", "8", "12"]);
+}
@@ -0,0 +1,108 @@
+//! Tests de generación de código guiada por los tipos del type checker
+
+mod common;
+
+use adead_backend::CodeGenerator;
+use adead_parser::parse;
+
+#[test]
+fn test_float_variables_use_xmm() {
+    let src = r#"
+        let x = 1.5
+        let y = x + 2.0
+    "#;
+    let asm = common::generate(src);
+
+    // x se guarda como bits de float64 y se recarga en XMM0 para operar con SSE
+    assert!(asm.contains("movq rax, xmm0"));
+    assert!(asm.contains("movq xmm0, rax  ; x es float64"));
+    assert!(asm.contains("addsd"));
+}
+
+#[test]
+fn test_int_variable_named_like_string_is_not_string() {
+    // Antes cualquier variable que empezara con 's' se trataba como string
+    let src = r#"
+        let suma = 1 + 2
+        print suma
+    "#;
+    let asm = common::generate(src);
+
+    assert!(asm.contains("numeric variable"));
+    assert!(!asm.contains("String variable"));
+}
+
+#[test]
+fn test_type_mismatch_is_reported() {
+    let src = "let n = 1\nlet s = \"a\" + n\n";
+    let program = parse(src).unwrap();
+    let mut gen = CodeGenerator::new();
+    let err = gen.generate(&program).unwrap_err();
+    assert_eq!(err.line_col(src), Some((2, 9)));
+}
+
+#[test]
+fn test_annotated_widths_use_narrow_registers() {
+    let src = r#"
+        let a: int8 = 200
+        let b: uint16 = 70000
+        fn doble(n: int32) -> int32 {
+            return n * 2
+        }
+    "#;
+    let asm = common::generate(src);
+
+    assert!(asm.contains("movsx rax, al  ; ajustar a int8 (byte)"));
+    assert!(asm.contains("movzx eax, ax  ; ajustar a uint16 (word)"));
+    // Parámetro y valor de retorno anotados como int32
+    assert!(asm.matches("movsxd rax, eax  ; ajustar a int32 (dword)").count() >= 2);
+}
+
+#[test]
+fn test_annotated_widths_run() {
+    let src = r#"
+        let b: uint8 = 300
+        print b
+        fn doble(n: int32) -> int32 {
+            return n * 2
+        }
+        print doble(21)
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    // uint8 trunca 300 a 44
+    assert_eq!(common::values(&stdout), ["44", "42"]);
+}
+
+
+#[test]
+fn test_float_locals_in_struct_methods() {
+    let src = r#"
+        struct Rect {
+            w: float64
+            fn doble(self) {
+                let k = 2.0
+                let d = k * self.w
+                return d
+            }
+        }
+        struct Circulo {
+            r: float64
+        }
+        fn Circulo_area(self) {
+            let pi = 3.0
+            let a = pi * self.r
+            return a
+        }
+        let q = Rect { w: 1.25 }
+        print q.doble()
+        let c = Circulo { r: 1.5 }
+        print c.area()
+    "#;
+    let asm = common::generate(src);
+    // Locales float de los métodos (propios y asociados): se guardan como bits
+    assert!(asm.matches("movq rax, xmm0  ; float64 → rax (bits) para guardar").count() >= 4);
+    assert!(asm.contains("movq xmm0, rax  ; campo w es float64"));
+
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["2.5", "4.5"]);
+}
@@ -0,0 +1,113 @@
+//! Tests para generación de código NASM de enums de usuario (tagged unions)
+
+mod common;
+
+use adead_backend::CodeGenerator;
+use adead_parser::{parse, EnumVariant, Expr, Program, Spanned, Stmt};
+
+#[test]
+fn test_generate_enum_variants() {
+    let src = r#"
+        enum Shape {
+            Circle(radius),
+            Rect(w, h),
+            Empty
+        }
+        let c = Shape.Circle(5)
+        let r = Shape.Rect(3, 4)
+        let e = Shape.Empty
+    "#;
+    let program = parse(src).unwrap();
+    let mut gen = CodeGenerator::new();
+    let asm = gen.generate(&program).unwrap();
+
+    // Todas las variantes ocupan tag + el payload más grande (Rect: 2 campos), en el heap
+    assert_eq!(asm.matches("espacio para enum Shape (tag + 2 slots de payload) en el heap (24 bytes)").count(), 3);
+    assert_eq!(asm.matches("    mov rcx, 24  ; tamaño en bytes\n    sub rsp, 32  ; shadow space\n    call heap_alloc").count(), 3);
+    assert!(asm.contains("mov [rcx + 16], rax  ; valor 1"));
+    assert!(asm.contains("mov rax, 0  ; tag Shape.Circle = 0"));
+    assert!(asm.contains("mov rax, 1  ; tag Shape.Rect = 1"));
+    assert!(asm.contains("mov rax, 2  ; tag Shape.Empty = 2"));
+    // Los slots de payload que la variante no usa quedan en 0
+    assert!(asm.contains("valor = 0"));
+}
+
+#[test]
+fn test_generate_match_on_enum() {
+    let src = r#"
+        enum Shape {
+            Circle(radius),
+            Rect(w, h),
+            Empty
+        }
+        let s = Shape.Rect(3, 4)
+        let area = match s {
+            Circle(r) => 3 * r * r,
+            Shape.Rect(w, h) => w * h,
+            Empty => 0
+        }
+    "#;
+    let program = parse(src).unwrap();
+    let mut gen = CodeGenerator::new();
+    let asm = gen.generate(&program).unwrap();
+
+    assert!(asm.contains("cmp qword [rax], 0  ; comparar tag con Shape.Circle"));
+    assert!(asm.contains("cmp qword [rax], 1  ; comparar tag con Shape.Rect"));
+    assert!(asm.contains("cmp qword [rax], 2  ; comparar tag con Shape.Empty"));
+    // Payload de Rect: campo 0 en [base + 8], campo 1 en [base + 16]
+    assert!(asm.contains("mov rax, [rax + 8]"));
+    assert!(asm.contains("mov rax, [rax + 16]"));
+    assert!(asm.contains("binding w"));
+    assert!(asm.contains("binding h"));
+}
+
+#[test]
+fn test_enum_returned_from_function_runs() {
+    let src = r#"
+        enum Shape {
+            Circle(r)
+            Rect(w, h)
+            Empty
+        }
+        fn make(n) {
+            return Shape.Rect(n, 2)
+        }
+        fn area(s) {
+            return match s {
+                Shape.Circle(r) => r * r * 3,
+                Shape.Rect(w, h) => w * h,
+                Shape.Empty => 1
+            }
+        }
+        print area(make(3))
+        let mut total = 5
+        for i in 0..1000 {
+            total = total + area(Shape.Circle(1)) - 3
+        }
+        print total
+    "#;
+    let asm = common::generate(src);
+    // El payload no se reserva en el frame: un bucle no hace crecer el stack
+    assert!(!asm.contains("sub rsp, 24"));
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["6", "5"]);
+}
+
+#[test]
+fn test_unknown_variant_is_an_error() {
+    // AST construido a mano: el parser ya rechaza la variante, el backend no la convierte en tag 0
+    let variant = |name: &str| EnumVariant { name: name.to_string(), fields: Vec::new() };
+    let program = Program {
+        statements: vec![
+            Spanned::dummy(Stmt::Enum { name: "Color".to_string(), variants: vec![variant("Rojo"), variant("Verde")] }),
+            Spanned::dummy(Stmt::Expr(Expr::EnumVariant {
+                enum_name: "Color".to_string(),
+                variant: "Azul".to_string(),
+                args: Vec::new(),
+            }.into())),
+        ],
+    };
+    let err = CodeGenerator::new().generate(&program).unwrap_err();
+    assert!(err.to_string().contains("Azul"), "{}", err);
+}
+
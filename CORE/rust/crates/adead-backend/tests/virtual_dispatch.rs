@@ -0,0 +1,103 @@
+//! Tests para dispatch virtual: vtables, overrides en cadenas de herencia y super
+
+mod common;
+
+const ANIMALES: &str = r#"
+    struct Animal {
+        nombre: int64
+        fn hablar(self) -> int64 { return 1 }
+        fn comer(self) -> int64 { return 10 }
+    }
+    struct Perro extends Animal {
+        raza: int64
+        fn hablar(self) -> int64 { return 2 + super.hablar() }
+    }
+    struct Cachorro extends Perro {
+        fn hablar(self) -> int64 { return super.comer() }
+    }
+    fn oir(a: Animal) -> int64 { return a.hablar() }
+    let c = Cachorro { nombre: 1, raza: 2 }
+    print oir(c)
+    print c.raza
+"#;
+
+#[test]
+fn test_vtables_resolve_overrides_across_chain() {
+    let asm = common::generate(ANIMALES);
+    // Cada clase conserva los slots de su padre; los métodos heredados apuntan
+    // al ancestro más cercano que los define
+    assert!(asm.contains("vtable_Animal:\n    dq fn_Animal_hablar  ; método hablar\n    dq fn_Animal_comer  ; método comer"));
+    assert!(asm.contains("vtable_Perro:\n    dq fn_Perro_hablar  ; método hablar\n    dq fn_Animal_comer  ; método comer"));
+    assert!(asm.contains("vtable_Cachorro:\n    dq fn_Cachorro_hablar  ; método hablar\n    dq fn_Animal_comer  ; método comer"));
+    assert!(!asm.contains("fn_Cachorro_comer"));
+}
+
+#[test]
+fn test_objects_carry_vtable_pointer() {
+    let asm = common::generate(ANIMALES);
+    // Objeto en el heap: [base] = vtable, campos (los heredados primero) en [base + 8], [base + 16]
+    assert!(asm.contains("    mov rcx, 24  ; tamaño en bytes\n    sub rsp, 32  ; shadow space\n    call heap_alloc"));
+    assert!(asm.contains("    lea rcx, [rel vtable_Cachorro]\n    mov [rax], rcx  ; puntero a la vtable de Cachorro"));
+    assert!(asm.contains("mov [rcx + 8], rax  ; Cachorro.nombre = 1"));
+    assert!(asm.contains("mov [rcx + 16], rax  ; Cachorro.raza = 2"));
+    assert!(asm.contains("mov rax, [rbp - 16]  ; dirección del struct Cachorro"));
+    assert!(asm.contains("mov rax, [rax + 16]  ; cargar campo"));
+}
+
+#[test]
+fn test_base_typed_reference_dispatches_through_vtable() {
+    let asm = common::generate(ANIMALES);
+    let body = &asm[asm.find("fn_oir:").unwrap()..];
+    let body = &body[..body.find("fn_oir_return:").unwrap()];
+    assert!(body.contains("cargar vtable_ptr desde [self + 0]"));
+    assert!(body.contains("mov rax, [rax + 0]  ; cargar puntero al método desde vtable[0]"));
+    assert!(body.contains("call rax  ; llamada virtual a Animal.hablar (dispatch dinámico)"));
+    assert!(!body.contains("call fn_Animal_hablar"));
+}
+
+#[test]
+fn test_super_binds_statically_to_parent_implementation() {
+    let asm = common::generate(ANIMALES);
+    let perro = &asm[asm.find("fn_Perro_hablar:").unwrap()..asm.find("fn_Perro_hablar_end:").unwrap()];
+    assert!(perro.contains("call fn_Animal_hablar  ; método del padre"));
+    // Perro no define comer: super.comer() desde Cachorro llega a Animal
+    let cachorro = &asm[asm.find("fn_Cachorro_hablar:").unwrap()..asm.find("fn_Cachorro_hablar_end:").unwrap()];
+    assert!(cachorro.contains("call fn_Animal_comer  ; método del padre"));
+    assert!(!cachorro.contains("call rax"));
+}
+
+#[test]
+fn test_dispatch_runs_through_chain() {
+    // oir(c) despacha a Cachorro.hablar, que llama a Animal.comer vía super
+    let Some(stdout) = common::run_ok(ANIMALES) else { return };
+    assert_eq!(common::values(&stdout), ["10", "2"]);
+}
+
+#[test]
+fn test_array_of_subclasses_dispatches_each_element() {
+    // [a, p, g] se unifica a [Animal]; cada elemento despacha a su propio override
+    let src = r#"
+        struct Animal {
+            nombre: int64
+            fn hablar(self) -> int64 { return 1 }
+        }
+        struct Perro extends Animal {
+            fn hablar(self) -> int64 { return 2 }
+        }
+        struct Gato extends Animal {
+            fn hablar(self) -> int64 { return 3 }
+        }
+        let a = Animal { nombre: 4 }
+        let p = Perro { nombre: 5 }
+        let g = Gato { nombre: 6 }
+        let todos = [a, p, g]
+        for x in todos {
+            print x.hablar()
+        }
+        let mascotas: [Animal] = [p, g]
+        print mascotas[1].hablar()
+        print mascotas[0].nombre
+    "#;
+    let Some(stdout) = common::run_ok(src) else { return };
+    assert_eq!(common::values(&stdout), ["1", "2", "3", "3", "5"]);
+}
@@ -7,9 +7,9 @@
 //! - Lifetime tracking básico
 //! - Verificación de mutabilidad
 
-use adead_common::{ADeadError, Result};
-use adead_parser::{BorrowType, Expr, Program, Stmt, Visibility};
-use std::collections::HashMap;
+use adead_common::{ADeadError, Result, Type};
+use adead_parser::{BorrowType, ComprehensionClause, Expr, FnParam, Program, Spanned, Stmt, Visibility};
+use std::collections::{HashMap, HashSet};
 
 /// Estado de ownership de una variable
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,15 +25,30 @@ pub enum OwnershipState {
 struct VariableInfo {
     ownership: OwnershipState,
     mutable: bool,  // true = variable mutable, false = inmutable
+    copy: bool,  // true = asignarla o pasarla la copia; false = la mueve
     borrowed_by: Vec<String>,  // Variables que tienen referencias a esta
 }
 
+/// Variables globales y scopes locales, para analizar las ramas de un if
+type Ownership = (HashMap<String, VariableInfo>, Vec<HashMap<String, VariableInfo>>);
+
+/// Un parámetro por valor con tipo anotado de array, dict, set o struct es dueño
+/// de su valor; sin anotación no se sabe y se trata como Copy
+fn param_is_copy(param: &FnParam) -> bool {
+    let owns_heap = matches!(
+        param.ty,
+        Some(Type::Array { .. } | Type::Dict { .. } | Type::Set(_) | Type::Struct(_) | Type::Generic { .. })
+    );
+    param.borrow_type != BorrowType::Owned || !owns_heap
+}
+
 /// Información sobre un struct (O5 - Encapsulación)
 #[derive(Debug, Clone)]
 struct StructInfo {
     name: String,
     fields: HashMap<String, Visibility>,  // Nombre del campo -> visibilidad
     methods: HashMap<String, Visibility>, // Nombre del método -> visibilidad
+    parent: Option<String>,               // Struct del que hereda (extends)
 }
 
 /// Borrow Checker - Verifica reglas de ownership y borrowing + acceso (O5)
@@ -46,6 +61,11 @@ pub struct BorrowChecker {
     structs: HashMap<String, StructInfo>,
     /// Variable que contiene cada tipo de struct (para verificar acceso)
     variable_types: HashMap<String, String>,  // Nombre de variable -> nombre de struct
+    /// Funciones del programa con el modo de paso de cada parámetro (también se
+    /// pueden usar como valor: `let f = cuadrado`)
+    functions: HashMap<String, Vec<BorrowType>>,
+    /// Structs, enums y traits declarados (se usan como valor en `Tipo.metodo(...)`)
+    type_names: HashSet<String>,
 }
 
 impl BorrowChecker {
@@ -56,6 +76,8 @@ impl BorrowChecker {
             scope_stack: Vec::new(),
             structs: HashMap::new(),
             variable_types: HashMap::new(),
+            functions: HashMap::new(),
+            type_names: HashSet::new(),
         }
     }
 
@@ -63,7 +85,16 @@ impl BorrowChecker {
     pub fn check(&mut self, program: &Program) -> Result<()> {
         // Primera pasada: registrar structs con su información de visibilidad (O5)
         for stmt in &program.statements {
-            if let Stmt::Struct { name, fields, init, destroy, .. } = stmt {
+            match &stmt.node {
+                Stmt::Fn { name, params, .. } => {
+                    self.functions.insert(name.clone(), params.iter().map(|p| p.borrow_type).collect());
+                }
+                Stmt::Struct { name, .. } | Stmt::Enum { name, .. } | Stmt::Trait { name, .. } => {
+                    self.type_names.insert(name.clone());
+                }
+                _ => {}
+            }
+            if let Stmt::Struct { name, parent, fields, init, destroy, .. } = &stmt.node {
                 let mut field_visibility = HashMap::new();
                 for field in fields {
                     field_visibility.insert(field.name.clone(), field.visibility);
@@ -83,6 +114,7 @@ impl BorrowChecker {
                         name: name.clone(),
                         fields: field_visibility,
                         methods: method_visibility,
+                        parent: parent.clone(),
                     },
                 );
             }
@@ -90,29 +122,33 @@ impl BorrowChecker {
         
         // Segunda pasada: registrar todas las variables y sus tipos
         for stmt in &program.statements {
-            if let Stmt::Let { mutable, name, value } = stmt {
+            if let Stmt::Let { mutable, name, value, .. } = &stmt.node {
+                let copy = self.is_copy(value);
                 self.variables.insert(
                     name.clone(),
                     VariableInfo {
                         ownership: OwnershipState::Owned,
                         mutable: *mutable,
+                        copy,
                         borrowed_by: Vec::new(),
                     },
                 );
                 
                 // Rastrear tipo de struct si es un StructLiteral (O5)
-                if let Expr::StructLiteral { name: struct_name, .. } = value {
+                if let Expr::StructLiteral { name: struct_name, .. } = &value.node {
                     self.variable_types.insert(name.clone(), struct_name.clone());
                 }
             }
             // Múltiple asignación Python-style
-            if let Stmt::MultiLet { mutable, names, values: _ } = stmt {
-                for name in names {
+            if let Stmt::MultiLet { mutable, names, values } = &stmt.node {
+                for (name, value) in names.iter().zip(values) {
+                    let copy = self.is_copy(value);
                     self.variables.insert(
                         name.clone(),
                         VariableInfo {
                             ownership: OwnershipState::Owned,
                             mutable: *mutable,
+                            copy,
                             borrowed_by: Vec::new(),
                         },
                     );
@@ -122,28 +158,42 @@ impl BorrowChecker {
         
         // Tercera pasada: verificar statements completos (ahora las variables y structs están registrados)
         for stmt in &program.statements {
-            self.check_stmt(stmt)?;
+            self.check_spanned_stmt(stmt)?;
         }
         Ok(())
     }
 
+    /// Verificar un statement con ubicación: los errores llevan el span del statement
+    fn check_spanned_stmt(&mut self, stmt: &Spanned<Stmt>) -> Result<()> {
+        self.check_stmt(&stmt.node).map_err(|e| e.at(stmt.span))
+    }
+
     /// Verificar un statement
     fn check_stmt(&mut self, stmt: &Stmt) -> Result<()> {
         match stmt {
-            Stmt::Let { mutable: _, name: _, value } => {
+            Stmt::Let { mutable, name, value, .. } => {
                 // Verificar que el valor puede ser movido/owned
-                // Nota: La variable ya está registrada en la primera pasada de check()
+                // Nota: Las variables globales ya están registradas en la primera pasada de check()
                 self.check_expr(value)?;
                 // Verificar borrowing en el valor (ahora que todas las variables están registradas)
                 self.check_expr_borrowing(value)?;
+                // `let b = a`: si el valor de `a` no es Copy, pasa a ser de `b`
+                let copy = self.is_copy(value);
+                self.move_out(value);
+                self.declare_value(name, *mutable, copy);
                 Ok(())
             }
-            Stmt::MultiLet { mutable: _, names: _, values } => {
+            Stmt::MultiLet { mutable, names, values } => {
                 // Verificar cada valor en la múltiple asignación
                 for value in values {
                     self.check_expr(value)?;
                     self.check_expr_borrowing(value)?;
                 }
+                for (name, value) in names.iter().zip(values) {
+                    let copy = self.is_copy(value);
+                    self.move_out(value);
+                    self.declare_value(name, *mutable, copy);
+                }
                 Ok(())
             }
             Stmt::Print(expr) => {
@@ -157,21 +207,17 @@ impl BorrowChecker {
                 else_body,
             } => {
                 self.check_expr(condition)?;
-                // Verificar then_body en nuevo scope
-                self.push_scope();
-                for s in then_body {
-                    self.check_stmt(s)?;
-                }
-                self.pop_scope();
-
-                // Verificar else_body en nuevo scope
-                if let Some(else_body) = else_body {
-                    self.push_scope();
-                    for s in else_body {
-                        self.check_stmt(s)?;
-                    }
-                    self.pop_scope();
-                }
+                // Cada rama parte del estado previo al if; después del if queda movido
+                // lo que se mueve en alguna de las ramas que no terminan en return
+                let before = self.ownership();
+                self.check_branch(then_body)?;
+                let after_then = self.ownership_after(then_body, &before);
+                self.restore_ownership(before.clone());
+                let else_body = else_body.as_deref().unwrap_or_default();
+                self.check_branch(else_body)?;
+                let after_else = self.ownership_after(else_body, &before);
+                self.restore_ownership(after_then);
+                self.merge_moves(&after_else);
                 Ok(())
             }
             Stmt::While { condition, body } => {
@@ -180,17 +226,21 @@ impl BorrowChecker {
                 // Por ahora, verificamos normalmente
                 self.push_scope();
                 for s in body {
-                    self.check_stmt(s)?;
+                    self.check_spanned_stmt(s)?;
                 }
                 self.pop_scope();
                 Ok(())
             }
-            Stmt::For { start, end, body, .. } => {
-                self.check_expr(start)?;
-                self.check_expr(end)?;
+            Stmt::For { vars, iter, body } => {
+                for expr in iter.exprs() {
+                    self.check_expr(expr)?;
+                }
                 self.push_scope();
+                for var in vars {
+                    self.declare_local(var, false);
+                }
                 for s in body {
-                    self.check_stmt(s)?;
+                    self.check_spanned_stmt(s)?;
                 }
                 self.pop_scope();
                 Ok(())
@@ -205,7 +255,16 @@ impl BorrowChecker {
                 // Constructores y destructores se verifican como funciones normales
                 Ok(())
             }
-            Stmt::Fn { visibility: _, name: _, params, body } => {
+            Stmt::Enum { .. } => {
+                // Enums solo declaran variantes: no hay valores que verificar
+                Ok(())
+            }
+            Stmt::Trait { .. } | Stmt::Impl { .. } => {
+                // Traits solo declaran firmas; los métodos de un impl se tratan como
+                // los de un struct
+                Ok(())
+            }
+            Stmt::Fn { params, body, .. } => {
                 // Registrar función (los parámetros se verifican cuando se llama)
                 // Por ahora, solo verificamos el cuerpo
                 self.push_scope();
@@ -224,15 +283,17 @@ impl BorrowChecker {
                         .insert(param.name.clone(), VariableInfo {
                             ownership,
                             mutable: matches!(param.borrow_type, BorrowType::MutBorrowed),
+                            copy: param_is_copy(param),
                             borrowed_by: Vec::new(),
                         });
                 }
                 
-                for s in body {
-                    self.check_stmt(s)?;
-                }
+                // Lo que el cuerpo mueve (p. ej. una global) solo vale dentro de la función
+                let before = self.variables.clone();
+                let result = body.iter().try_for_each(|s| self.check_spanned_stmt(s));
+                self.variables = before;
                 self.pop_scope();
-                Ok(())
+                result
             }
             Stmt::Expr(expr) => {
                 self.check_expr(expr)?;
@@ -241,6 +302,7 @@ impl BorrowChecker {
             Stmt::Return(expr) => {
                 if let Some(expr) = expr {
                     self.check_expr(expr)?;
+                    self.move_out(expr);
                 }
                 Ok(())
             }
@@ -253,20 +315,24 @@ impl BorrowChecker {
         }
     }
 
-    /// Verificar una expresión
-    fn check_expr(&self, expr: &Expr) -> Result<()> {
+    /// Verificar una expresión: los errores llevan el span de la subexpresión más interna
+    fn check_expr(&mut self, expr: &Spanned<Expr>) -> Result<()> {
+        self.check_expr_node(&expr.node).map_err(|e| e.at(expr.span))
+    }
+
+    fn check_expr_node(&mut self, expr: &Expr) -> Result<()> {
         match expr {
             Expr::Number(_) | Expr::String(_) | Expr::Float(_) | Expr::Bool(_) => Ok(()), // Literales no necesitan verificación
             Expr::Ident(name) => {
                 // Verificar que la variable existe y no fue movida
                 if let Some(info) = self.find_variable(name) {
                     if info.ownership == OwnershipState::Moved {
-                        return Err(ADeadError::TypeError {
+                        return Err(ADeadError::BorrowError {
                             message: format!("Variable '{}' fue movida y ya no es válida", name),
                         });
                     }
-                } else {
-                    return Err(ADeadError::TypeError {
+                } else if !self.functions.contains_key(name) && !self.type_names.contains(name) {
+                    return Err(ADeadError::BorrowError {
                         message: format!("Variable '{}' no definida", name),
                     });
                 }
@@ -277,18 +343,17 @@ impl BorrowChecker {
                 self.check_expr(right)?;
                 Ok(())
             }
+            Expr::Assign { name, value } if name == "_array_set" => {
+                // arr[i] = v: el contenedor se lee (igual que en una asignación a campo)
+                self.check_expr(value)
+            }
             Expr::Assign { name, value } => {
                 self.check_expr(value)?;
                 // Verificar que la variable existe y puede ser asignada
                 if let Some(info) = self.find_variable(name) {
-                    if info.ownership == OwnershipState::Moved {
-                        return Err(ADeadError::TypeError {
-                            message: format!("Variable '{}' fue movida y ya no es válida", name),
-                        });
-                    }
                     // Verificar que la variable es mutable
                     if !info.mutable {
-                        return Err(ADeadError::TypeError {
+                        return Err(ADeadError::BorrowError {
                             message: format!(
                                 "Variable '{}' es inmutable y no puede ser modificada. Usa 'let mut' para crear una variable mutable",
                                 name
@@ -296,16 +361,34 @@ impl BorrowChecker {
                         });
                     }
                 } else {
-                    return Err(ADeadError::TypeError {
+                    return Err(ADeadError::BorrowError {
                         message: format!("Variable '{}' no definida", name),
                     });
                 }
+                // `b = a` mueve igual que `let`; asignar un valor nuevo a una variable
+                // movida la vuelve a hacer válida
+                let copy = self.is_copy(value);
+                self.move_out(value);
+                if let Some(info) = self.find_variable_mut(name) {
+                    if info.ownership == OwnershipState::Moved {
+                        info.ownership = OwnershipState::Owned;
+                    }
+                    info.copy = copy;
+                }
                 Ok(())
             }
-            Expr::Call { module: _, name: _, args } => {
-                // Verificar argumentos (namespace se verifica en otro lugar)
-                for arg in args {
+            Expr::Call { module, name, args } => {
+                // Verificar argumentos (namespace se verifica en otro lugar). Un argumento
+                // pasado por valor a una función del programa se mueve a la llamada.
+                let modes = match module {
+                    None => self.functions.get(name).cloned().unwrap_or_default(),
+                    Some(_) => Vec::new(),
+                };
+                for (i, arg) in args.iter().enumerate() {
                     self.check_expr(arg)?;
+                    if modes.get(i) == Some(&BorrowType::Owned) {
+                        self.move_out(arg);
+                    }
                 }
                 Ok(())
             }
@@ -317,10 +400,10 @@ impl BorrowChecker {
                 if *mutable {
                     // TODO: Verificar que no hay otros borrows activos
                     // Por ahora, solo verificamos que si es &mut, la variable debe ser mutable
-                    if let Expr::Ident(name) = expr.as_ref() {
+                    if let Expr::Ident(name) = &expr.node {
                         if let Some(info) = self.find_variable(name) {
                             if !info.mutable {
-                                return Err(ADeadError::TypeError {
+                                return Err(ADeadError::BorrowError {
                                     message: format!(
                                         "No se puede tomar una referencia mutable (&mut) de '{}' porque es inmutable. Usa 'let mut' para crear una variable mutable",
                                         name
@@ -354,8 +437,19 @@ impl BorrowChecker {
                 self.check_expr(expr)?;
                 // Verificar cada brazo del match
                 for arm in arms {
-                    self.check_expr(&arm.body)?;
-                    // TODO: Verificar que los patrones son exhaustivos y compatibles
+                    // Las variables del patrón (`x`, `Some(x)`, `n @ 1..5`...) son visibles
+                    // solo en la guarda y el cuerpo del brazo
+                    self.push_scope();
+                    for binding in arm.pattern.bindings() {
+                        self.declare_local(binding, false);
+                    }
+                    let result = match &arm.guard {
+                        Some(guard) => self.check_expr(guard).and_then(|_| self.check_expr(&arm.body)),
+                        None => self.check_expr(&arm.body),
+                    };
+                    self.pop_scope();
+                    result?;
+                    // La exhaustividad la verifica el type checker (adead-types)
                 }
                 Ok(())
             }
@@ -367,6 +461,13 @@ impl BorrowChecker {
                 }
                 Ok(())
             }
+            Expr::EnumVariant { args, .. } => {
+                // El payload se mueve dentro del valor del enum
+                for arg in args {
+                    self.check_expr(arg)?;
+                }
+                Ok(())
+            }
             Expr::FieldAccess { object, field } => {
                 // Verificar que el objeto puede ser accedido
                 self.check_expr(object)?;
@@ -419,11 +520,12 @@ impl BorrowChecker {
                 self.check_expr(index)?;
                 Ok(())
             }
-            Expr::Slice { object, start, end } => {
-                // Verificar objeto, inicio y fin del slice
+            Expr::Slice { object, start, end, step } => {
+                // Verificar objeto, inicio, fin y paso del slice (los que estén presentes)
                 self.check_expr(object)?;
-                self.check_expr(start)?;
-                self.check_expr(end)?;
+                for bound in [start, end, step].into_iter().flatten() {
+                    self.check_expr(bound)?;
+                }
                 Ok(())
             }
             Expr::SuperCall { method, args } => {
@@ -439,12 +541,12 @@ impl BorrowChecker {
                 // Verificar que la variable existe y es mutable
                 if let Some(info) = self.find_variable(name) {
                     if info.ownership == OwnershipState::Moved {
-                        return Err(ADeadError::TypeError {
+                        return Err(ADeadError::BorrowError {
                             message: format!("Variable '{}' fue movida y ya no es válida", name),
                         });
                     }
                     if !info.mutable {
-                        return Err(ADeadError::TypeError {
+                        return Err(ADeadError::BorrowError {
                             message: format!(
                                 "Variable '{}' es inmutable. Usa 'let mut' para operadores compuestos",
                                 name
@@ -452,7 +554,7 @@ impl BorrowChecker {
                         });
                     }
                 } else {
-                    return Err(ADeadError::TypeError {
+                    return Err(ADeadError::BorrowError {
                         message: format!("Variable '{}' no definida", name),
                     });
                 }
@@ -474,19 +576,19 @@ impl BorrowChecker {
                 }
                 Ok(())
             }
-            Expr::Lambda { params: _, body } => {
-                // Lambda: verificar el cuerpo
-                self.check_expr(body)?;
-                Ok(())
-            }
-            Expr::ListComprehension { expr, var: _, iter, condition } => {
-                // List comprehension: verificar expresión, iterador y condición
-                self.check_expr(expr)?;
-                self.check_expr(iter)?;
-                if let Some(cond) = condition {
-                    self.check_expr(cond)?;
+            Expr::Lambda { params, body } => {
+                // Lambda: verificar el cuerpo con los parámetros en scope
+                self.push_scope();
+                for param in params {
+                    self.declare_local(param, false);
                 }
-                Ok(())
+                let result = self.check_expr(body);
+                self.pop_scope();
+                result
+            }
+            Expr::ListComprehension { expr, clauses } => {
+                // List comprehension: verificar cada iterador, y filtros y expresión con las variables en scope
+                self.check_comprehension(clauses, &[expr])
             }
             Expr::DictLiteral { pairs } => {
                 // Dict: verificar cada par key-value
@@ -510,23 +612,10 @@ impl BorrowChecker {
                 self.check_expr(else_expr)?;
                 Ok(())
             }
-            Expr::DictComprehension { key_expr, value_expr, var: _, iter, condition } => {
-                self.check_expr(key_expr)?;
-                self.check_expr(value_expr)?;
-                self.check_expr(iter)?;
-                if let Some(cond) = condition {
-                    self.check_expr(cond)?;
-                }
-                Ok(())
-            }
-            Expr::SetComprehension { expr, var: _, iter, condition } => {
-                self.check_expr(expr)?;
-                self.check_expr(iter)?;
-                if let Some(cond) = condition {
-                    self.check_expr(cond)?;
-                }
-                Ok(())
+            Expr::DictComprehension { key_expr, value_expr, clauses } => {
+                self.check_comprehension(clauses, &[key_expr, value_expr])
             }
+            Expr::SetComprehension { expr, clauses } => self.check_comprehension(clauses, &[expr]),
         }
     }
 
@@ -542,6 +631,85 @@ impl BorrowChecker {
         self.variables.get(name)
     }
     
+    fn find_variable_mut(&mut self, name: &str) -> Option<&mut VariableInfo> {
+        match self.scope_stack.iter_mut().rev().find_map(|scope| scope.get_mut(name)) {
+            Some(info) => Some(info),
+            None => self.variables.get_mut(name),
+        }
+    }
+
+    /// Un valor es Copy salvo que se sepa que es dueño de datos en el heap: arrays,
+    /// dicts, sets y structs, o una variable que guarda uno. Lo que no se puede
+    /// saber sin tipos (retornos de funciones, parámetros sin anotar) se trata como Copy.
+    fn is_copy(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::ArrayLiteral(_)
+            | Expr::DictLiteral { .. }
+            | Expr::SetLiteral(_)
+            | Expr::ListComprehension { .. }
+            | Expr::DictComprehension { .. }
+            | Expr::SetComprehension { .. }
+            | Expr::StructLiteral { .. } => false,
+            Expr::Call { module: Some(type_name), name, .. } => !(name == "new" && self.structs.contains_key(type_name)),
+            Expr::Ident(name) => self.find_variable(name).map_or(true, |info| info.copy),
+            _ => true,
+        }
+    }
+
+    /// Mover el valor de una variable que no es Copy: deja de ser válida hasta que
+    /// se le asigne otro valor
+    fn move_out(&mut self, expr: &Expr) {
+        if let Expr::Ident(name) = expr {
+            if let Some(info) = self.find_variable_mut(name) {
+                if !info.copy {
+                    info.ownership = OwnershipState::Moved;
+                }
+            }
+        }
+    }
+
+    /// Estado de ownership de las variables globales y de cada scope
+    fn ownership(&self) -> Ownership {
+        (self.variables.clone(), self.scope_stack.clone())
+    }
+
+    fn restore_ownership(&mut self, (variables, scope_stack): Ownership) {
+        self.variables = variables;
+        self.scope_stack = scope_stack;
+    }
+
+    /// Estado al salir de una rama: si termina en `return`, lo que movió no
+    /// llega a lo que sigue
+    fn ownership_after(&self, body: &[Spanned<Stmt>], before: &Ownership) -> Ownership {
+        match body.last().map(|s| &s.node) {
+            Some(Stmt::Return(_)) => before.clone(),
+            _ => self.ownership(),
+        }
+    }
+
+    /// Marcar como movidas las variables movidas en `other` (mismos scopes que el actual)
+    fn merge_moves(&mut self, (variables, scope_stack): &Ownership) {
+        let scopes = std::iter::once((&mut self.variables, variables))
+            .chain(self.scope_stack.iter_mut().zip(scope_stack));
+        for (scope, other) in scopes {
+            for (name, info) in other {
+                if info.ownership == OwnershipState::Moved {
+                    if let Some(current) = scope.get_mut(name) {
+                        current.ownership = OwnershipState::Moved;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Verificar el cuerpo de una rama en un scope nuevo
+    fn check_branch(&mut self, body: &[Spanned<Stmt>]) -> Result<()> {
+        self.push_scope();
+        let result = body.iter().try_for_each(|s| self.check_spanned_stmt(s));
+        self.pop_scope();
+        result
+    }
+
     /// Verificar acceso a un campo (O5 - Encapsulación)
     fn check_field_access(&self, object: &Expr, field_name: &str) -> Result<()> {
         // Obtener el tipo del objeto (nombre del struct)
@@ -554,8 +722,8 @@ impl BorrowChecker {
         };
         
         if let Some(struct_name) = struct_name {
-            if let Some(struct_info) = self.structs.get(&struct_name) {
-                if let Some(&_field_visibility) = struct_info.fields.get(field_name) {
+            if self.structs.contains_key(&struct_name) {
+                if let Some(_field_visibility) = self.field_visibility(&struct_name, field_name) {
                     // Por ahora, siempre permitimos acceso (verificación dentro del mismo módulo)
                     // En el futuro, necesitaremos rastrear el scope actual para verificar acceso
                     // TODO: Verificar que el acceso es desde el mismo módulo o que el campo es público
@@ -564,7 +732,7 @@ impl BorrowChecker {
                     // La verificación completa de visibilidad requiere sistema de módulos
                     Ok(())
                 } else {
-                    Err(ADeadError::TypeError {
+                    Err(ADeadError::BorrowError {
                         message: format!(
                             "Campo '{}' no existe en struct '{}'",
                             field_name, struct_name
@@ -581,6 +749,19 @@ impl BorrowChecker {
         }
     }
     
+    /// Visibilidad de un campo propio o heredado de algún struct padre
+    fn field_visibility(&self, struct_name: &str, field_name: &str) -> Option<Visibility> {
+        let mut current = Some(struct_name.to_string());
+        while let Some(name) = current {
+            let info = self.structs.get(&name)?;
+            if let Some(&visibility) = info.fields.get(field_name) {
+                return Some(visibility);
+            }
+            current = info.parent.clone();
+        }
+        None
+    }
+    
     /// Verificar acceso a un método (O5 - Encapsulación)
     fn check_method_access(&self, object: &Expr, method_name: &str) -> Result<()> {
         // Similar a check_field_access
@@ -609,6 +790,53 @@ impl BorrowChecker {
         }
     }
 
+    /// Registrar una variable en el scope actual (las globales ya se registraron en check())
+    fn declare_local(&mut self, name: &str, mutable: bool) {
+        self.declare_value(name, mutable, true);
+    }
+
+    /// Registrar una variable con un valor nuevo; una global redeclarada vuelve a
+    /// ser válida aunque su valor anterior se haya movido
+    fn declare_value(&mut self, name: &str, mutable: bool, copy: bool) {
+        match self.scope_stack.last_mut() {
+            Some(scope) => {
+                scope.insert(name.to_string(), VariableInfo {
+                    ownership: OwnershipState::Owned,
+                    mutable,
+                    copy,
+                    borrowed_by: Vec::new(),
+                });
+            }
+            None => {
+                if let Some(info) = self.variables.get_mut(name) {
+                    info.ownership = OwnershipState::Owned;
+                    info.copy = copy;
+                }
+            }
+        }
+    }
+
+    /// Verificar las expresiones de una comprehension con sus variables en scopes propios
+    fn check_comprehension(&mut self, clauses: &[ComprehensionClause], exprs: &[&Spanned<Expr>]) -> Result<()> {
+        let depth = self.scope_stack.len();
+        let result = self.check_clauses(clauses, exprs);
+        self.scope_stack.truncate(depth);
+        result
+    }
+
+    /// Cada cláusula abre un scope: su iterador ve las variables de las cláusulas anteriores
+    fn check_clauses(&mut self, clauses: &[ComprehensionClause], exprs: &[&Spanned<Expr>]) -> Result<()> {
+        for clause in clauses {
+            self.check_expr(&clause.iter)?;
+            self.push_scope();
+            self.declare_local(&clause.var, false);
+            for condition in &clause.conditions {
+                self.check_expr(condition)?;
+            }
+        }
+        exprs.iter().try_for_each(|expr| self.check_expr(expr))
+    }
+
     /// Crear un nuevo scope
     fn push_scope(&mut self) {
         self.scope_stack.push(HashMap::new());
@@ -620,16 +848,20 @@ impl BorrowChecker {
     }
 
     /// Verificar borrowing en expresiones (después de que todas las variables están registradas)
-    fn check_expr_borrowing(&self, expr: &Expr) -> Result<()> {
+    fn check_expr_borrowing(&self, expr: &Spanned<Expr>) -> Result<()> {
+        self.check_borrowing_node(&expr.node).map_err(|e| e.at(expr.span))
+    }
+
+    fn check_borrowing_node(&self, expr: &Expr) -> Result<()> {
         match expr {
             Expr::Borrow { expr, mutable } => {
                 // Si es mutable borrow, verificar que la variable es mutable
                 if *mutable {
-                    match expr.as_ref() {
+                    match &expr.node {
                         Expr::Ident(name) => {
                             if let Some(info) = self.find_variable(name) {
                                 if !info.mutable {
-                                    return Err(ADeadError::TypeError {
+                                    return Err(ADeadError::BorrowError {
                                         message: format!(
                                             "No se puede tomar una referencia mutable (&mut) de '{}' porque es inmutable. Usa 'let mut' para crear una variable mutable",
                                             name
@@ -638,7 +870,7 @@ impl BorrowChecker {
                                 }
                             } else {
                                 // Variable no encontrada - esto debería ser un error también
-                                return Err(ADeadError::TypeError {
+                                return Err(ADeadError::BorrowError {
                                     message: format!("Variable '{}' no definida", name),
                                 });
                             }
@@ -684,6 +916,9 @@ impl BorrowChecker {
             Expr::Match { expr, arms } => {
                 self.check_expr_borrowing(expr)?;
                 for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.check_expr_borrowing(guard)?;
+                    }
                     self.check_expr_borrowing(&arm.body)?;
                 }
                 Ok(())
@@ -694,6 +929,12 @@ impl BorrowChecker {
                 }
                 Ok(())
             }
+            Expr::EnumVariant { args, .. } => {
+                for arg in args {
+                    self.check_expr_borrowing(arg)?;
+                }
+                Ok(())
+            }
             Expr::FieldAccess { object, .. } => {
                 self.check_expr_borrowing(object)?;
                 Ok(())
@@ -776,7 +1017,7 @@ mod tests {
         let mut checker = BorrowChecker::new();
         let result = checker.check(&program);
         assert!(result.is_err(), "Debe fallar: variable inmutable no puede ser modificada");
-        if let Err(ADeadError::TypeError { message }) = result {
+        if let Err(ADeadError::BorrowError { message }) = result.as_ref().map_err(ADeadError::inner) {
             assert!(message.contains("inmutable"), "Mensaje debe mencionar 'inmutable'");
         }
     }
@@ -803,10 +1044,10 @@ mod tests {
         let mut checker = BorrowChecker::new();
         let result = checker.check(&program);
         assert!(result.is_err(), "Debe fallar: no se puede tomar &mut de variable inmutable");
-        if let Err(ADeadError::TypeError { message }) = result {
+        if let Err(ADeadError::BorrowError { message }) = result.as_ref().map_err(ADeadError::inner) {
             assert!(message.contains("inmutable"), "Mensaje debe mencionar 'inmutable', pero fue: {}", message);
         } else {
-            panic!("Debe ser TypeError, pero fue: {:?}", result);
+            panic!("Debe ser BorrowError, pero fue: {:?}", result);
         }
     }
 
@@ -833,4 +1074,97 @@ mod tests {
         let result = checker.check(&program);
         assert!(result.is_ok(), "Debe pasar: se puede tomar & de variable inmutable");
     }
+
+    #[test]
+    fn test_error_reports_expression_location() {
+        let src = "let x = 10\nif x > 1 {\n    let r = &mut x\n}\n";
+        let program = parse(src).unwrap();
+        let mut checker = BorrowChecker::new();
+        let err = checker.check(&program).unwrap_err();
+        assert_eq!(err.line_col(src), Some((3, 13)));
+    }
+
+    #[test]
+    fn test_locals_are_scoped_to_their_block() {
+        let src = r#"
+            fn f(a) {
+                let b = a + 1
+                return b
+            }
+            for i in 0..3 {
+                let y = i * 2
+                print y
+            }
+            let sq = [x * x for x in [1, 2, 3]]
+            let g = lambda z: z + 1
+        "#;
+        let program = parse(src).unwrap();
+        let mut checker = BorrowChecker::new();
+        assert!(checker.check(&program).is_ok(), "{:?}", checker.check(&program));
+    }
+
+    #[test]
+    fn test_local_not_visible_outside_block() {
+        let src = "if 1 > 0 {\n    let t = 1\n}\nprint t\n";
+        let program = parse(src).unwrap();
+        let mut checker = BorrowChecker::new();
+        let err = checker.check(&program).unwrap_err();
+        assert!(err.to_string().contains("'t' no definida"), "{}", err);
+        assert_eq!(err.line_col(src), Some((4, 7)));
+    }
+
+    #[test]
+    fn test_type_names_are_known_identifiers() {
+        // `Tipo.metodo(...)`: el nombre del struct/enum se usa como valor
+        let src = "struct Calculadora {\n}\nfn Calculadora_sumar(a, b) {\n    return a + b\n}\nlet r = Calculadora.sumar(1, 2)\nenum Color {\n    Rojo\n}\nlet c = Color.Rojo\n";
+        let program = parse(src).unwrap();
+        let mut checker = BorrowChecker::new();
+        assert!(checker.check(&program).is_ok(), "{:?}", checker.check(&program));
+    }
+
+    #[test]
+    fn test_moved_values_are_invalid() {
+        let cases = [
+            ("let xs = [1, 2]\nlet b = xs\nprint xs\n", "xs", (3, 7)),
+            ("fn f(v) {\n    return v\n}\nlet p = Punto { x: 1 }\nlet r = f(p)\nprint p\n", "p", (6, 7)),
+            ("let xs = [1]\nif 1 > 0 {\n    let b = xs\n}\nprint xs\n", "xs", (5, 7)),
+        ];
+        for (src, name, location) in cases {
+            let src = format!("struct Punto {{\n    x\n}}\n{}", src);
+            let program = parse(&src).unwrap();
+            let err = BorrowChecker::new().check(&program).unwrap_err();
+            assert!(matches!(err.inner(), ADeadError::BorrowError { .. }), "{:?}", err);
+            assert_eq!(err.to_string(), format!("Borrow error: Variable '{}' fue movida y ya no es válida", name));
+            assert_eq!(err.line_col(&src), Some((location.0 + 3, location.1)), "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_copy_values_and_returns_do_not_move() {
+        let src = r#"
+            let n = 1
+            let m = n
+            print n
+            fn tomar(ys) {
+                if len(ys) > 1 {
+                    return ys
+                }
+                return ys
+            }
+            let xs = [1]
+            if len(xs) > 0 {
+                print xs
+            } else {
+                let b = xs
+            }
+            let mut zs = [1]
+            let w = zs
+            zs = [2]
+            print zs
+        "#;
+        let program = parse(src).unwrap();
+        let mut checker = BorrowChecker::new();
+        let result = checker.check(&program);
+        assert!(result.is_ok(), "{:?}", result);
+    }
 }
@@ -3,18 +3,27 @@
 //! Integración con linkers externos para generar ejecutables desde archivos objeto.
 //! 
 //! Pipeline:
-//!   ASM Virgen → NASM → .obj → [LINKER] → .exe
+//!   ASM Virgen → NASM → .obj → [LINKER] → .exe      (x86_64-windows, PE64)
+//!   ASM Virgen → NASM → .o   → [LINKER] → ejecutable (x86_64-linux, ELF64)
 //! 
 //! Linkers soportados (en orden de preferencia):
-//!   1. Zig (Recomendado): Fácil de instalar, optimización ReleaseSmall
-//!   2. GCC: Tradicional, incluido con MinGW
-//!   3. Clang: Alternativa LLVM
+//!   Windows:
+//!     1. Zig (Recomendado): Fácil de instalar, optimización ReleaseSmall
+//!     2. GCC: Tradicional, incluido con MinGW
+//!     3. Clang: Alternativa LLVM
+//!   Linux:
+//!     1. ld (binutils): Linkeo directo del entry point _start, sin libc
+//!     2. GCC / Clang con -nostdlib -static
+//!     3. Zig con target x86_64-linux-none
 //! 
-//! El linker se detecta automáticamente o se puede especificar manualmente.
+//! El linker se detecta automáticamente según el target o se puede especificar manualmente.
 //! 
 //! Autor: Eddi Andreé Salazar Matos
 //! Fecha: Diciembre 2025
 
+use adead_backend::Target;
+use adead_common::log::Phase;
+use adead_common::log_debug;
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -25,38 +34,59 @@ pub enum LinkerType {
     Zig,
     Gcc,
     Clang,
+    Ld,
     None,
 }
 
-/// Detectar qué linker está disponible en el sistema
-pub fn detect_linker() -> LinkerType {
-    // Intentar Zig primero (recomendado para mejor optimización)
-    if let Ok(output) = Command::new("zig")
-        .arg("version")
-        .output()
-    {
-        if output.status.success() {
-            return LinkerType::Zig;
+impl LinkerType {
+    /// Indica si este linker puede producir ejecutables para el target dado.
+    /// Zig hace cross-linking a cualquier target; GCC, Clang y ld solo producen
+    /// ejecutables del formato nativo del host (MinGW en Windows, ELF en Linux).
+    pub fn supports_target(&self, target: Target) -> bool {
+        match self {
+            LinkerType::Zig => true,
+            LinkerType::Gcc | LinkerType::Clang => target == Target::host(),
+            LinkerType::Ld => target.is_linux() && Target::host().is_linux(),
+            LinkerType::None => false,
         }
     }
-    
-    // Intentar GCC (mejor para tamaño mínimo)
-    if let Ok(output) = Command::new("g++")
-        .arg("--version")
-        .output()
-    {
-        if output.status.success() {
-            return LinkerType::Gcc;
+
+    /// Orden de preferencia de linkers para un target
+    fn preference_order(target: Target) -> &'static [LinkerType] {
+        match target {
+            Target::X86_64Windows => &[LinkerType::Zig, LinkerType::Gcc, LinkerType::Clang],
+            Target::X86_64Linux => &[LinkerType::Ld, LinkerType::Gcc, LinkerType::Clang, LinkerType::Zig],
         }
     }
-    
-    // Intentar Clang (alternativa)
-    if let Ok(output) = Command::new("clang++")
-        .arg("--version")
-        .output()
-    {
-        if output.status.success() {
-            return LinkerType::Clang;
+
+    /// Comando y argumento para verificar si el linker está instalado
+    fn probe_command(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            LinkerType::Zig => Some(("zig", "version")),
+            LinkerType::Gcc => Some(("g++", "--version")),
+            LinkerType::Clang => Some(("clang++", "--version")),
+            LinkerType::Ld => Some(("ld", "--version")),
+            LinkerType::None => None,
+        }
+    }
+
+    fn is_installed(&self) -> bool {
+        match self.probe_command() {
+            Some((program, arg)) => Command::new(program)
+                .arg(arg)
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false),
+            None => false,
+        }
+    }
+}
+
+/// Detectar qué linker está disponible en el sistema para el target dado
+pub fn detect_linker(target: Target) -> LinkerType {
+    for linker in LinkerType::preference_order(target) {
+        if linker.supports_target(target) && linker.is_installed() {
+            return linker.clone();
         }
     }
     
@@ -64,7 +94,8 @@ pub fn detect_linker() -> LinkerType {
 }
 
 /// Ensamblar archivo .asm a .obj usando NASM
-pub fn assemble_asm_to_obj(asm_file: &Path, obj_file: &Path) -> Result<()> {
+/// El formato de objeto depende del target (win64 para Windows, elf64 para Linux)
+pub fn assemble_asm_to_obj(asm_file: &Path, obj_file: &Path, target: Target) -> Result<()> {
     // Verificar que NASM está disponible
     let nasm_output = Command::new("nasm")
         .arg("--version")
@@ -76,12 +107,14 @@ pub fn assemble_asm_to_obj(asm_file: &Path, obj_file: &Path) -> Result<()> {
     }
     
     // Ensamblar .asm → .obj
-    let output = Command::new("nasm")
-        .arg("-f")
-        .arg("win64")
+    let mut cmd = Command::new("nasm");
+    cmd.arg("-f")
+        .arg(target.nasm_format())
         .arg(asm_file)
         .arg("-o")
-        .arg(obj_file)
+        .arg(obj_file);
+    log_debug!(Phase::Linker, "Ejecutando: {:?}", cmd);
+    let output = cmd
         .output()
         .with_context(|| format!("Error al ensamblar {} con NASM", asm_file.display()))?;
     
@@ -97,8 +130,8 @@ pub fn assemble_asm_to_obj(asm_file: &Path, obj_file: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Linkear archivos .obj a .exe usando Zig
-pub fn link_with_zig(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
+/// Linkear archivos objeto a ejecutable usando Zig
+pub fn link_with_zig(obj_files: &[PathBuf], exe_file: &Path, target: Target) -> Result<()> {
     // Verificar que Zig está disponible
     let zig_version = Command::new("zig")
         .arg("version")
@@ -117,13 +150,15 @@ pub fn link_with_zig(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
     // OPTIMIZACIÓN AGRESIVA: Flags para reducir tamaño del ejecutable al máximo
     // Opciones de Zig primero
     cmd.arg("-target")
-        .arg("x86_64-windows-gnu")  // Usar gnu para mejor compatibilidad
+        .arg(target.zig_triple())  // x86_64-windows-gnu o x86_64-linux-none según --target
         .arg("-O")
         .arg("ReleaseSmall")  // Optimización para tamaño mínimo
         .arg("-fstrip")  // Eliminar símbolos (sintaxis correcta de Zig)
         .arg("-fsingle-threaded")  // Sin threading overhead (sintaxis correcta)
-        .arg("-fno-unwind-tables")  // Sin unwind tables (reduce tamaño)
-        .arg("-lc");  // Linkear con C runtime
+        .arg("-fno-unwind-tables");  // Sin unwind tables (reduce tamaño)
+    if target.is_windows() {
+        cmd.arg("-lc");  // Linkear con C runtime (Linux no lo necesita: el runtime usa syscalls)
+    }
     
     // -femit-bin necesita el signo = para el path
     // Convertir a ruta absoluta para evitar problemas con espacios y rutas relativas
@@ -141,10 +176,16 @@ pub fn link_with_zig(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
     }
     
     // Ejecutar linking
+    log_debug!(Phase::Linker, "Ejecutando: {:?}", cmd);
     let output = cmd
         .output()
         .with_context(|| format!("Error al linkear con Zig"))?;
     
+    if !output.status.success() && target.is_linux() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Error al linkear con Zig: {}", error_msg);
+    }
+    
     // Si falla con -lc, intentar sin -lc
     if !output.status.success() {
         let mut cmd_retry = Command::new("zig");
@@ -153,7 +194,7 @@ pub fn link_with_zig(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
         // OPTIMIZACIÓN AGRESIVA: Flags para reducir tamaño (retry sin -lc)
         // Opciones de Zig primero
         cmd_retry.arg("-target")
-            .arg("x86_64-windows-gnu")
+            .arg(target.zig_triple())
             .arg("-O")
             .arg("ReleaseSmall")
             .arg("-fstrip")  // Eliminar símbolos
@@ -188,7 +229,7 @@ pub fn link_with_zig(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
         let exe_file_abs = exe_file.canonicalize()
             .unwrap_or_else(|_| exe_file.to_path_buf());
         if !exe_file_abs.exists() {
-            anyhow::bail!("Archivo .exe no fue generado en: {} ni en: {}. Verifica permisos de escritura.", exe_file.display(), exe_file_abs.display());
+            anyhow::bail!("Ejecutable no fue generado en: {} ni en: {}. Verifica permisos de escritura.", exe_file.display(), exe_file_abs.display());
         }
     }
     
@@ -203,14 +244,14 @@ pub fn link_with_zig(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
         .with_context(|| format!("Error al verificar tamaño del ejecutable"))?
         .len();
     if exe_size == 0 {
-        anyhow::bail!("El archivo .exe generado está vacío. Posible error en el proceso de linking.");
+        anyhow::bail!("El ejecutable generado está vacío. Posible error en el proceso de linking.");
     }
     
     Ok(())
 }
 
-/// Linkear archivos .obj a .exe usando GCC
-pub fn link_with_gcc(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
+/// Linkear archivos objeto a ejecutable usando GCC
+pub fn link_with_gcc(obj_files: &[PathBuf], exe_file: &Path, target: Target) -> Result<()> {
     let mut cmd = Command::new("g++");
     
     // OPTIMIZACIÓN AGRESIVA: Flags para reducir tamaño del ejecutable al máximo
@@ -221,9 +262,7 @@ pub fn link_with_gcc(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
     // -Wl,--file-alignment=16: Alineación mínima (reduce padding)
     // -Wl,--section-alignment=16: Alineación de secciones mínima
     // -Wl,--no-seh: Deshabilitar Structured Exception Handling (reduce overhead)
-    cmd.arg("-nostdlib")
-        .arg("-s")
-        .arg("-Wl,--strip-all,--gc-sections,--file-alignment=16,--section-alignment=16,--no-seh");
+    cmd.args(gnu_link_flags(target));
     
     // Agregar todos los archivos .obj
     for obj_file in obj_files {
@@ -235,9 +274,10 @@ pub fn link_with_gcc(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
     
     cmd.arg("-o").arg(exe_file);
     
+    log_debug!(Phase::Linker, "Ejecutando: {:?}", cmd);
     let output = cmd
         .output()
-        .with_context(|| format!("Error al ejecutar GCC. Verifica que GCC (MinGW) esté instalado y en PATH."))?;
+        .with_context(|| format!("Error al ejecutar GCC. Verifica que GCC (MinGW en Windows) esté instalado y en PATH."))?;
     
     if !output.status.success() {
         let error_msg = String::from_utf8_lossy(&output.stderr);
@@ -246,7 +286,7 @@ pub fn link_with_gcc(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
     }
     
     if !exe_file.exists() {
-        anyhow::bail!("Archivo .exe no fue generado: {}. Verifica permisos de escritura.", exe_file.display());
+        anyhow::bail!("Ejecutable no fue generado: {}. Verifica permisos de escritura.", exe_file.display());
     }
     
     // Verificar que el archivo no esté vacío
@@ -254,14 +294,14 @@ pub fn link_with_gcc(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
         .with_context(|| format!("Error al verificar tamaño del ejecutable"))?
         .len();
     if exe_size == 0 {
-        anyhow::bail!("El archivo .exe generado está vacío. Posible error en el proceso de linking.");
+        anyhow::bail!("El ejecutable generado está vacío. Posible error en el proceso de linking.");
     }
     
     Ok(())
 }
 
-/// Linkear archivos .obj a .exe usando Clang
-pub fn link_with_clang(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
+/// Linkear archivos objeto a ejecutable usando Clang
+pub fn link_with_clang(obj_files: &[PathBuf], exe_file: &Path, target: Target) -> Result<()> {
     let mut cmd = Command::new("clang++");
     
     // OPTIMIZACIÓN AGRESIVA: Flags para reducir tamaño del ejecutable al máximo
@@ -272,9 +312,7 @@ pub fn link_with_clang(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
     // -Wl,--file-alignment=16: Alineación mínima (reduce padding)
     // -Wl,--section-alignment=16: Alineación de secciones mínima
     // -Wl,--no-seh: Deshabilitar Structured Exception Handling (reduce overhead)
-    cmd.arg("-nostdlib")
-        .arg("-s")
-        .arg("-Wl,--strip-all,--gc-sections,--file-alignment=16,--section-alignment=16,--no-seh");
+    cmd.args(gnu_link_flags(target));
     
     // Agregar todos los archivos .obj
     for obj_file in obj_files {
@@ -286,6 +324,7 @@ pub fn link_with_clang(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
     
     cmd.arg("-o").arg(exe_file);
     
+    log_debug!(Phase::Linker, "Ejecutando: {:?}", cmd);
     let output = cmd
         .output()
         .with_context(|| format!("Error al ejecutar Clang. Verifica que Clang esté instalado y en PATH."))?;
@@ -297,7 +336,7 @@ pub fn link_with_clang(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
     }
     
     if !exe_file.exists() {
-        anyhow::bail!("Archivo .exe no fue generado: {}. Verifica permisos de escritura.", exe_file.display());
+        anyhow::bail!("Ejecutable no fue generado: {}. Verifica permisos de escritura.", exe_file.display());
     }
     
     // Verificar que el archivo no esté vacío
@@ -305,28 +344,115 @@ pub fn link_with_clang(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
         .with_context(|| format!("Error al verificar tamaño del ejecutable"))?
         .len();
     if exe_size == 0 {
-        anyhow::bail!("El archivo .exe generado está vacío. Posible error en el proceso de linking.");
+        anyhow::bail!("El ejecutable generado está vacío. Posible error en el proceso de linking.");
+    }
+    
+    Ok(())
+}
+
+/// Linkear archivos objeto ELF64 a ejecutable Linux usando ld (binutils)
+pub fn link_with_ld(obj_files: &[PathBuf], exe_file: &Path) -> Result<()> {
+    let mut cmd = Command::new("ld");
+    
+    // -e _start: entry point generado por el backend (runtime Linux)
+    // -static: sin dynamic loader (no usamos libc, solo syscalls)
+    // -s: Strip symbols
+    // --gc-sections: Eliminar secciones no usadas
+    cmd.arg("-e")
+        .arg("_start")
+        .arg("-static")
+        .arg("-s")
+        .arg("--gc-sections");
+    
+    // Agregar todos los archivos objeto
+    for obj_file in obj_files {
+        if !obj_file.exists() {
+            anyhow::bail!("Archivo objeto no encontrado: {}", obj_file.display());
+        }
+        cmd.arg(obj_file);
+    }
+    
+    cmd.arg("-o").arg(exe_file);
+    
+    log_debug!(Phase::Linker, "Ejecutando: {:?}", cmd);
+    let output = cmd
+        .output()
+        .with_context(|| format!("Error al ejecutar ld. Verifica que binutils esté instalado y en PATH."))?;
+    
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Error al linkear con ld:\nSTDERR: {}", error_msg);
+    }
+    
+    if !exe_file.exists() {
+        anyhow::bail!("Ejecutable no fue generado: {}. Verifica permisos de escritura.", exe_file.display());
     }
     
     Ok(())
 }
 
-/// Linkear archivos .obj a .exe usando el linker disponible
-pub fn link_objs_to_exe(obj_files: &[PathBuf], exe_file: &Path, preferred_linker: Option<LinkerType>) -> Result<()> {
-    let linker = preferred_linker.unwrap_or_else(detect_linker);
+/// Flags de linkeo para GCC/Clang según el target
+fn gnu_link_flags(target: Target) -> &'static [&'static str] {
+    match target {
+        // -Wl,--file-alignment / --section-alignment / --no-seh solo existen para PE (MinGW)
+        Target::X86_64Windows => &[
+            "-nostdlib",
+            "-s",
+            "-Wl,--strip-all,--gc-sections,--file-alignment=16,--section-alignment=16,--no-seh",
+        ],
+        // ELF: binario estático sin libc, entry point _start generado por el backend
+        Target::X86_64Linux => &[
+            "-nostdlib",
+            "-static",
+            "-s",
+            "-Wl,--strip-all,--gc-sections",
+        ],
+    }
+}
+
+/// Linkear archivos objeto a ejecutable usando el linker disponible para el target
+pub fn link_objs_to_exe(
+    obj_files: &[PathBuf],
+    exe_file: &Path,
+    preferred_linker: Option<LinkerType>,
+    target: Target,
+) -> Result<()> {
+    let linker = preferred_linker.unwrap_or_else(|| detect_linker(target));
+    
+    if linker != LinkerType::None && !linker.supports_target(target) {
+        anyhow::bail!(
+            "El linker {:?} no puede generar ejecutables para {} desde este sistema. \
+            Usa --linker auto o --linker zig (soporta cross-linking).",
+            linker, target
+        );
+    }
     
     match linker {
         LinkerType::Zig => {
-            println!("   🔗 Linkeando con Zig...");
-            link_with_zig(obj_files, exe_file)
+            status!("   🔗 Linkeando con Zig...");
+            link_with_zig(obj_files, exe_file, target)
         }
         LinkerType::Gcc => {
-            println!("   🔗 Linkeando con GCC...");
-            link_with_gcc(obj_files, exe_file)
+            status!("   🔗 Linkeando con GCC...");
+            link_with_gcc(obj_files, exe_file, target)
         }
         LinkerType::Clang => {
-            println!("   🔗 Linkeando con Clang...");
-            link_with_clang(obj_files, exe_file)
+            status!("   🔗 Linkeando con Clang...");
+            link_with_clang(obj_files, exe_file, target)
+        }
+        LinkerType::Ld => {
+            status!("   🔗 Linkeando con ld...");
+            link_with_ld(obj_files, exe_file)
+        }
+        LinkerType::None if target.is_linux() => {
+            anyhow::bail!(
+                "No se encontró ningún linker disponible para {} (ld, GCC, Clang o Zig).\n\
+                Por favor instala uno de ellos:\n\
+                - binutils (ld): apt install binutils\n\
+                - GCC: apt install gcc\n\
+                - Zig: https://ziglang.org/download/",
+                target
+            );
         }
         LinkerType::None => {
             anyhow::bail!(
@@ -340,60 +466,84 @@ pub fn link_objs_to_exe(obj_files: &[PathBuf], exe_file: &Path, preferred_linker
     }
 }
 
-/// Compilar y linkear completo: .ad → .asm → .obj → .exe
+/// Compilar y linkear completo: .ad → .asm → .obj → .exe (o .o → ejecutable en Linux)
 pub fn compile_and_link(
     source_file: &Path,
     output_exe: Option<PathBuf>,
     backend: &str,
     linker_preference: Option<LinkerType>,
+    target: Target,
+) -> Result<PathBuf> {
+    // Archivos intermedios (.asm, .obj) junto al archivo fuente
+    let work_dir = source_file.parent().unwrap_or_else(|| Path::new(""));
+    compile_and_link_in_dir(source_file, work_dir, output_exe, backend, linker_preference, target)
+}
+
+/// Igual que `compile_and_link`, pero escribe los archivos intermedios (.asm, .obj)
+/// y el ejecutable por defecto en `work_dir` (usado por `adeadc run` con un directorio temporal)
+pub fn compile_and_link_in_dir(
+    source_file: &Path,
+    work_dir: &Path,
+    output_exe: Option<PathBuf>,
+    backend: &str,
+    linker_preference: Option<LinkerType>,
+    target: Target,
 ) -> Result<PathBuf> {
     use std::fs;
     
+    let stem = source_file.file_stem().unwrap_or_else(|| source_file.as_os_str());
+    let base = work_dir.join(stem);
+    
     // Paso 1: Compilar .ad → .asm
-    let asm_file = source_file.with_extension("asm");
+    let asm_file = base.with_extension("asm");
     let source = fs::read_to_string(source_file)
         .with_context(|| format!("Error al leer archivo: {}", source_file.display()))?;
     
-    println!("   📝 Compilando {} → {}", source_file.display(), asm_file.display());
-    
-    match backend {
-        "nasm" | "direct" => {
-            let program = adead_parser::parse(&source)
-                .map_err(|e| anyhow::anyhow!("Parser error: {:?}", e))?;
-            
-            let mut generator = adead_backend::CodeGenerator::new();
-            let nasm_code = generator.generate(&program)
-                .map_err(|e| anyhow::anyhow!("NASM generation error: {:?}", e))?;
-            
-            fs::write(&asm_file, nasm_code)
-                .with_context(|| format!("Error al escribir {}", asm_file.display()))?;
-        }
-        "auto" | _ => {
-            // PRIORIDAD ALTA: Usar NASM directo siempre (evita conversión GAS)
-            let program = adead_parser::parse(&source)
-                .map_err(|e| anyhow::anyhow!("Parser error: {:?}", e))?;
-            
-            let mut generator = adead_backend::CodeGenerator::new();
-            let nasm_code = generator.generate(&program)
-                .map_err(|e| anyhow::anyhow!("NASM generation error: {:?}", e))?;
-            
-            fs::write(&asm_file, nasm_code)
-                .with_context(|| format!("Error al escribir {}", asm_file.display()))?;
-        }
-    }
+    status!("   📝 Compilando {} → {}", source_file.display(), asm_file.display());
+    
+    // Todos los backends usan NASM directo aquí (evita conversión GAS)
+    log_debug!(Phase::Linker, "Backend '{}' → NASM directo", backend);
+    crate::compile_nasm_direct(source_file, &source, &asm_file, target)?;
     
     // Paso 2: Ensamblar .asm → .obj
-    let obj_file = source_file.with_extension("obj");
-    println!("   🔧 Ensamblando {} → {}", asm_file.display(), obj_file.display());
-    assemble_asm_to_obj(&asm_file, &obj_file)?;
+    let obj_file = base.with_extension(target.obj_extension());
+    status!("   🔧 Ensamblando {} → {}", asm_file.display(), obj_file.display());
+    assemble_asm_to_obj(&asm_file, &obj_file, target)?;
     
     // Paso 3: Linkear .obj → .exe
-    let exe_file = output_exe.unwrap_or_else(|| source_file.with_extension("exe"));
-    println!("   🔗 Linkeando {} → {}", obj_file.display(), exe_file.display());
-    link_objs_to_exe(&[obj_file], &exe_file, linker_preference)?;
+    let exe_file = output_exe.unwrap_or_else(|| base.with_extension(target.exe_extension()));
+    status!("   🔗 Linkeando {} → {}", obj_file.display(), exe_file.display());
+    link_objs_to_exe(&[obj_file], &exe_file, linker_preference, target)?;
     
-    println!("   ✅ Ejecutable generado: {}", exe_file.display());
+    status!("   ✅ Ejecutable generado: {}", exe_file.display());
     
     Ok(exe_file)
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ld_only_links_linux() {
+        assert!(!LinkerType::Ld.supports_target(Target::X86_64Windows));
+        assert_eq!(
+            LinkerType::Ld.supports_target(Target::X86_64Linux),
+            Target::host().is_linux()
+        );
+    }
+
+    #[test]
+    fn test_zig_cross_links_every_target() {
+        assert!(LinkerType::Zig.supports_target(Target::X86_64Linux));
+        assert!(LinkerType::Zig.supports_target(Target::X86_64Windows));
+        assert!(!LinkerType::None.supports_target(Target::host()));
+    }
+
+    #[test]
+    fn test_linux_prefers_ld() {
+        assert_eq!(LinkerType::preference_order(Target::X86_64Linux)[0], LinkerType::Ld);
+        assert_eq!(LinkerType::preference_order(Target::X86_64Windows)[0], LinkerType::Zig);
+    }
+}
@@ -14,11 +14,25 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use adead_backend::Target;
+use adead_common::log::{self, Phase};
+use adead_common::{log_debug, log_error, log_warn};
+
+/// Mensaje de progreso para el usuario (stderr, para no mezclarse con la salida de
+/// `adeadc run`); se oculta con --quiet
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if adead_common::log::level() >= Some(adead_common::log::Level::Info) {
+            eprintln!($($arg)*);
+        }
+    };
+}
 
 mod linker;
-use linker::{LinkerType, compile_and_link, link_objs_to_exe, assemble_asm_to_obj};
+use linker::{LinkerType, compile_and_link, compile_and_link_in_dir, link_objs_to_exe, assemble_asm_to_obj};
 
 // ============================================================================
 // CLI DEFINITION
@@ -29,10 +43,40 @@ use linker::{LinkerType, compile_and_link, link_objs_to_exe, assemble_asm_to_obj
 #[command(author = "Eddi Andreé Salazar Matos")]
 #[command(version = "0.9.0")]
 #[command(about = "ADead Compiler - Compila código ADead a ejecutables nativos", long_about = None)]
-#[command(after_help = "Pipeline: ADead → Parser → NASM → ASM Virgen → Linker → .exe\n\nEjemplos:\n  adeadc compile programa.ad -o programa.asm\n  adeadc build programa.ad -o programa.exe\n  adeadc build programa.ad --linker zig")]
+#[command(after_help = "Pipeline: ADead → Parser → NASM → ASM Virgen → Linker → .exe\n\nEjemplos:\n  adeadc compile programa.ad -o programa.asm\n  adeadc build programa.ad -o programa.exe\n  adeadc build programa.ad --linker zig\n  adeadc build programa.ad --target x86_64-linux\n  adeadc run programa.ad -- arg1 arg2\n  adeadc -vv --log parser,backend compile programa.ad\n  adeadc compile programa.ad --emit=analysis\n  adeadc build programa.ad --no-borrow-check")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    
+    /// Más detalle en stderr: -v (cada fase), -vv (cada statement)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+    
+    /// Solo mostrar errores
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    
+    /// Fases a loguear, separadas por coma: cli, parser, backend, linker (default: todas)
+    #[arg(long, value_delimiter = ',', value_parser = parse_phase, global = true)]
+    log: Vec<Phase>,
+    
+    /// Salidas extra: analysis (reporte del DebugAnalyzer en stdout)
+    #[arg(long, value_delimiter = ',', value_parser = ["analysis"], global = true)]
+    emit: Vec<String>,
+    
+    /// Omitir la verificación de ownership/borrowing (no recomendado)
+    #[arg(long, global = true)]
+    no_borrow_check: bool,
+}
+
+/// `--emit=analysis`: imprimir el reporte del DebugAnalyzer al compilar
+static EMIT_ANALYSIS: AtomicBool = AtomicBool::new(false);
+/// Verificación de ownership/borrowing obligatoria salvo `--no-borrow-check`
+static BORROW_CHECK: AtomicBool = AtomicBool::new(true);
+
+fn parse_phase(name: &str) -> std::result::Result<Phase, String> {
+    Phase::from_name(name)
+        .ok_or_else(|| format!("fase desconocida '{}' (cli, parser, backend, linker)", name))
 }
 
 #[derive(Subcommand)]
@@ -49,9 +93,13 @@ enum Commands {
         /// Archivo de salida (.asm)
         #[arg(short, long)]
         output: Option<PathBuf>,
+        
+        /// Plataforma destino: x86_64-linux (ELF64) o x86_64-windows (PE64). Default: host
+        #[arg(long)]
+        target: Option<Target>,
     },
     
-    /// Compila y linkea código ADead a ejecutable (.exe)
+    /// Compila y linkea código ADead a ejecutable (.exe en Windows, ELF en Linux)
     Build {
         /// Archivo de entrada (.ad)
         input: PathBuf,
@@ -60,32 +108,58 @@ enum Commands {
         #[arg(long, default_value = "nasm")]
         backend: String,
         
-        /// Archivo de salida (.exe)
+        /// Archivo de salida (default: <input>.exe en Windows, <input> en Linux)
         #[arg(short, long)]
         output: Option<PathBuf>,
         
-        /// Linker a usar: auto (default), zig, gcc, clang
+        /// Linker a usar: auto (default), zig, gcc, clang, ld
         #[arg(long, default_value = "auto")]
         linker: String,
         
         /// Solo ensamblar a .obj (no linkear)
         #[arg(long)]
         assemble_only: bool,
+        
+        /// Plataforma destino: x86_64-linux (ELF64) o x86_64-windows (PE64). Default: host
+        #[arg(long)]
+        target: Option<Target>,
     },
     
-    /// Linkea archivos .obj a .exe
+    /// Compila, linkea y ejecuta código ADead en un solo paso
+    Run {
+        /// Archivo de entrada (.ad)
+        input: PathBuf,
+        
+        /// Backend a usar: nasm (default), cpp, c
+        #[arg(long, default_value = "nasm")]
+        backend: String,
+        
+        /// Linker a usar: auto (default), zig, gcc, clang, ld
+        #[arg(long, default_value = "auto")]
+        linker: String,
+        
+        /// Argumentos para el programa (después de --)
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    
+    /// Linkea archivos objeto (.obj / .o) a ejecutable
     Link {
-        /// Archivos .obj a linkear
+        /// Archivos objeto a linkear
         #[arg(required = true)]
         obj_files: Vec<PathBuf>,
         
-        /// Archivo de salida (.exe)
+        /// Ejecutable de salida
         #[arg(short, long, required = true)]
         output: PathBuf,
         
-        /// Linker a usar: auto (default), zig, gcc, clang
+        /// Linker a usar: auto (default), zig, gcc, clang, ld
         #[arg(long, default_value = "auto")]
         linker: String,
+        
+        /// Plataforma destino: x86_64-linux (ELF64) o x86_64-windows (PE64). Default: host
+        #[arg(long)]
+        target: Option<Target>,
     },
     
     /// Ensambla archivo .asm a .obj
@@ -96,114 +170,127 @@ enum Commands {
         /// Archivo de salida (.obj)
         #[arg(short, long)]
         output: Option<PathBuf>,
+        
+        /// Plataforma destino: x86_64-linux (ELF64) o x86_64-windows (PE64). Default: host
+        #[arg(long)]
+        target: Option<Target>,
     },
 }
 
 fn main() {
-    // Forzar flush inmediato de stderr y stdout para debugging
-    let _ = io::stderr().flush();
-    let _ = io::stdout().flush();
-    
-    eprintln!("[CLI-DEBUG] Iniciando CLI...");
-    io::stderr().flush().ok();
-    
     let cli = Cli::parse();
     
+    log::set_level(Some(if cli.quiet {
+        log::Level::Error
+    } else {
+        log::level_from_verbosity(cli.verbose)
+    }));
+    if !cli.log.is_empty() {
+        log::set_phases(&cli.log);
+    }
+    EMIT_ANALYSIS.store(cli.emit.iter().any(|e| e == "analysis"), Ordering::Relaxed);
+    BORROW_CHECK.store(!cli.no_borrow_check, Ordering::Relaxed);
+    
     match &cli.command {
-        Commands::Compile { input, backend, output } => {
-            eprintln!("[CLI-DEBUG] Comando: compile, input: {:?}, backend: {}", input, backend);
-            io::stderr().flush().ok();
+        Commands::Compile { input, backend, output, target } => {
+            let target = target.unwrap_or_else(Target::host);
+            log_debug!(Phase::Cli, "Comando: compile, input: {:?}, backend: {}, target: {}", input, backend, target);
             
-            if let Err(e) = cmd_compile(input, backend, output.clone()) {
-                eprintln!("[CLI-ERROR] Error en compile: {}", e);
-                io::stderr().flush().ok();
+            if let Err(e) = cmd_compile(input, backend, output.clone(), target) {
+                log_error!(Phase::Cli, "Error en compile: {}", e);
                 std::process::exit(1);
             }
         }
         
-        Commands::Build { input, backend, output, linker, assemble_only } => {
-            eprintln!("[CLI-DEBUG] Comando: build, input: {:?}, backend: {}", input, backend);
-            io::stderr().flush().ok();
+        Commands::Build { input, backend, output, linker, assemble_only, target } => {
+            let target = target.unwrap_or_else(Target::host);
+            log_debug!(Phase::Cli, "Comando: build, input: {:?}, backend: {}, target: {}", input, backend, target);
             
-            if let Err(e) = cmd_build(input, backend, output.clone(), linker, *assemble_only) {
-                eprintln!("[CLI-ERROR] Error en build: {}", e);
-                io::stderr().flush().ok();
+            if let Err(e) = cmd_build(input, backend, output.clone(), linker, *assemble_only, target) {
+                log_error!(Phase::Cli, "Error en build: {}", e);
                 std::process::exit(1);
             }
         }
         
-        Commands::Link { obj_files, output, linker } => {
-            eprintln!("[CLI-DEBUG] Comando: link, obj_files: {:?}, output: {:?}", obj_files, output);
-            io::stderr().flush().ok();
+        Commands::Run { input, backend, linker, args } => {
+            log_debug!(Phase::Cli, "Comando: run, input: {:?}, backend: {}, args: {:?}", input, backend, args);
+            
+            match cmd_run(input, backend, linker, args) {
+                Ok(code) => {
+                    io::stdout().flush().ok();
+                    std::process::exit(code);
+                }
+                Err(e) => {
+                    log_error!(Phase::Cli, "Error en run: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        
+        Commands::Link { obj_files, output, linker, target } => {
+            let target = target.unwrap_or_else(Target::host);
+            log_debug!(Phase::Cli, "Comando: link, obj_files: {:?}, output: {:?}, target: {}", obj_files, output, target);
             
-            if let Err(e) = cmd_link(obj_files, output, linker) {
-                eprintln!("[CLI-ERROR] Error en link: {}", e);
-                io::stderr().flush().ok();
+            if let Err(e) = cmd_link(obj_files, output, linker, target) {
+                log_error!(Phase::Cli, "Error en link: {}", e);
                 std::process::exit(1);
             }
         }
         
-        Commands::Assemble { input, output } => {
-            eprintln!("[CLI-DEBUG] Comando: assemble, input: {:?}", input);
-            io::stderr().flush().ok();
+        Commands::Assemble { input, output, target } => {
+            let target = target.unwrap_or_else(Target::host);
+            log_debug!(Phase::Cli, "Comando: assemble, input: {:?}, target: {}", input, target);
             
-            if let Err(e) = cmd_assemble(input, output.clone()) {
-                eprintln!("[CLI-ERROR] Error en assemble: {}", e);
-                io::stderr().flush().ok();
+            if let Err(e) = cmd_assemble(input, output.clone(), target) {
+                log_error!(Phase::Cli, "Error en assemble: {}", e);
                 std::process::exit(1);
+            }
+        }
     }
 }
-    }
-    
-    eprintln!("[CLI-DEBUG] CLI terminado exitosamente");
-    io::stderr().flush().ok();
-}
 
 /// Comando: compile - Compila .ad a .asm
-fn cmd_compile(input: &PathBuf, backend: &str, output: Option<PathBuf>) -> Result<()> {
+fn cmd_compile(input: &PathBuf, backend: &str, output: Option<PathBuf>, target: Target) -> Result<()> {
     let output_path = output.unwrap_or_else(|| {
         let mut path = input.clone();
         path.set_extension("asm");
         path
     });
     
-    eprintln!("[CLI-DEBUG] Leyendo archivo: {:?}", input);
-    io::stderr().flush().ok();
+    log_debug!(Phase::Cli, "Leyendo archivo: {:?}", input);
     
     let source = fs::read_to_string(input)
         .with_context(|| format!("Error leyendo archivo: {}", input.display()))?;
     
-    eprintln!("[CLI-DEBUG] Archivo leído: {} caracteres", source.len());
-    io::stderr().flush().ok();
+    log_debug!(Phase::Cli, "Archivo leído: {} caracteres", source.len());
     
-    println!("🔄 Compilando: {} → {}", input.display(), output_path.display());
-    io::stdout().flush().ok();
+    status!("🔄 Compilando: {} → {}", input.display(), output_path.display());
     
     match backend {
         "nasm" | "direct" | "auto" => {
             // PRIORIDAD: Backend NASM directo
-            eprintln!("[CLI-DEBUG] Usando backend NASM directo");
-            io::stderr().flush().ok();
+            log_debug!(Phase::Cli, "Usando backend NASM directo");
             
-            compile_nasm_direct(&source, &output_path)?;
+            compile_nasm_direct(input, &source, &output_path, target)?;
             
-            println!("✅ Compilado (NASM directo): {}", output_path.display());
-            io::stdout().flush().ok();
+            status!("✅ Compilado (NASM directo): {}", output_path.display());
         }
         "cpp" | "c++" => {
-            // Fallback: Pipeline C++
+            // Fallback: Pipeline C++ (el pipeline parsea por su cuenta; aquí solo el análisis semántico)
+            parse_and_check(input, &source)?;
             compile_cpp_fallback(&source, &output_path)?;
-            println!("✅ Compilado (C++ fallback): {}", output_path.display());
+            status!("✅ Compilado (C++ fallback): {}", output_path.display());
         }
         "c" => {
             // Fallback: Pipeline C
+            parse_and_check(input, &source)?;
             compile_c_fallback(&source, &output_path)?;
-            println!("✅ Compilado (C fallback): {}", output_path.display());
+            status!("✅ Compilado (C fallback): {}", output_path.display());
         }
         _ => {
             // Default: NASM directo
-            compile_nasm_direct(&source, &output_path)?;
-            println!("✅ Compilado (NASM directo): {}", output_path.display());
+            compile_nasm_direct(input, &source, &output_path, target)?;
+            status!("✅ Compilado (NASM directo): {}", output_path.display());
         }
     }
     
@@ -217,55 +304,100 @@ fn cmd_build(
     output: Option<PathBuf>, 
     linker: &str, 
     assemble_only: bool,
+    target: Target,
 ) -> Result<()> {
     let linker_type = parse_linker_type(linker);
     
     if assemble_only {
         // Solo ensamblar: .ad → .asm → .obj
         let asm_file = input.with_extension("asm");
-        let obj_file = input.with_extension("obj");
+        let obj_file = input.with_extension(target.obj_extension());
         
         let source = fs::read_to_string(input)
             .with_context(|| format!("Error leyendo archivo: {}", input.display()))?;
         
-        println!("📝 Compilando: {} → {}", input.display(), asm_file.display());
-        compile_nasm_direct(&source, &asm_file)?;
+        status!("📝 Compilando: {} → {}", input.display(), asm_file.display());
+        compile_nasm_direct(input, &source, &asm_file, target)?;
         
-        println!("🔧 Ensamblando: {} → {}", asm_file.display(), obj_file.display());
-        assemble_asm_to_obj(&asm_file, &obj_file)?;
+        status!("🔧 Ensamblando: {} → {}", asm_file.display(), obj_file.display());
+        assemble_asm_to_obj(&asm_file, &obj_file, target)?;
         
-        println!("✅ Objeto generado: {}", obj_file.display());
+        status!("✅ Objeto generado: {}", obj_file.display());
     } else {
         // Build completo: .ad → .asm → .obj → .exe
-        let exe_file = compile_and_link(input, output, backend, linker_type)?;
-        println!("✅ Build completo: {} → {}", input.display(), exe_file.display());
+        let exe_file = compile_and_link(input, output, backend, linker_type, target)?;
+        status!("✅ Build completo: {} → {}", input.display(), exe_file.display());
     }
     
     Ok(())
 }
 
+/// Comando: run - Compila y linkea en un directorio temporal y ejecuta el programa
+/// Retorna el código de salida del programa (stdin/stdout/stderr se heredan)
+fn cmd_run(input: &PathBuf, backend: &str, linker: &str, args: &[String]) -> Result<i32> {
+    // Solo se puede ejecutar código del sistema actual
+    let target = Target::host();
+    let linker_type = parse_linker_type(linker);
+    
+    let work_dir = std::env::temp_dir().join(format!("adeadc-run-{}", std::process::id()));
+    fs::create_dir_all(&work_dir)
+        .with_context(|| format!("Error creando directorio temporal: {}", work_dir.display()))?;
+    
+    let result = compile_and_link_in_dir(input, &work_dir, None, backend, linker_type, target)
+        .and_then(|exe_file| execute(&exe_file, args));
+    
+    // Limpiar directorio temporal (aunque falle la compilación o la ejecución)
+    let _ = fs::remove_dir_all(&work_dir);
+    
+    result
+}
+
+/// Ejecutar el programa generado con los argumentos de después de `--`
+fn execute(exe_file: &Path, args: &[String]) -> Result<i32> {
+    let status = std::process::Command::new(exe_file)
+        .args(args)
+        .status()
+        .with_context(|| format!("Error ejecutando: {}", exe_file.display()))?;
+    Ok(exit_code_of(&status))
+}
+
+/// Código de salida del proceso hijo (en Unix, 128 + señal si terminó por señal)
+fn exit_code_of(status: &std::process::ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    1
+}
+
 /// Comando: link - Linkea .obj a .exe
-fn cmd_link(obj_files: &[PathBuf], output: &PathBuf, linker: &str) -> Result<()> {
+fn cmd_link(obj_files: &[PathBuf], output: &PathBuf, linker: &str, target: Target) -> Result<()> {
     let linker_type = parse_linker_type(linker);
     
-    println!("🔗 Linkeando {} archivo(s) → {}", obj_files.len(), output.display());
-    link_objs_to_exe(obj_files, output, linker_type)?;
-    println!("✅ Ejecutable generado: {}", output.display());
+    status!("🔗 Linkeando {} archivo(s) → {}", obj_files.len(), output.display());
+    link_objs_to_exe(obj_files, output, linker_type, target)?;
+    status!("✅ Ejecutable generado: {}", output.display());
     
     Ok(())
 }
 
 /// Comando: assemble - Ensambla .asm a .obj
-fn cmd_assemble(input: &PathBuf, output: Option<PathBuf>) -> Result<()> {
+fn cmd_assemble(input: &PathBuf, output: Option<PathBuf>, target: Target) -> Result<()> {
     let obj_file = output.unwrap_or_else(|| {
         let mut path = input.clone();
-        path.set_extension("obj");
+        path.set_extension(target.obj_extension());
         path
     });
     
-    println!("🔧 Ensamblando: {} → {}", input.display(), obj_file.display());
-    assemble_asm_to_obj(input, &obj_file)?;
-    println!("✅ Objeto generado: {}", obj_file.display());
+    status!("🔧 Ensamblando: {} → {}", input.display(), obj_file.display());
+    assemble_asm_to_obj(input, &obj_file, target)?;
+    status!("✅ Objeto generado: {}", obj_file.display());
     
     Ok(())
 }
@@ -274,41 +406,56 @@ fn cmd_assemble(input: &PathBuf, output: Option<PathBuf>) -> Result<()> {
 // COMPILATION BACKENDS
 // ============================================================================
 
+/// Parsear y ejecutar los análisis semánticos obligatorios (tipos y ownership/borrowing).
+/// Los errores se renderizan con el código fuente, igual que los de sintaxis.
+fn parse_and_check(input: &Path, source: &str) -> Result<adead_parser::Program> {
+    log_debug!(Phase::Cli, "Iniciando parse...");
+    
+    // Parsear código ADead (todos los errores de sintaxis, renderizados con el código fuente)
+    let file_name = input.display().to_string();
+    let program = adead_parser::parse(source)
+        .map_err(|e| anyhow::anyhow!("Error de parser:\n{}", e.render(&file_name, source)))?;
+    
+    log_debug!(Phase::Cli, "Verificando tipos...");
+    adead_types::TypeChecker::new()
+        .check(&program)
+        .map_err(|e| anyhow::anyhow!("Error de tipos:\n{}", e.render(&file_name, source)))?;
+    
+    if BORROW_CHECK.load(Ordering::Relaxed) {
+        log_debug!(Phase::Cli, "Verificando ownership/borrowing...");
+        adead_borrow::BorrowChecker::new()
+            .check(&program)
+            .map_err(|e| anyhow::anyhow!("Error de ownership/borrowing:\n{}", e.render(&file_name, source)))?;
+    } else {
+        log_warn!(Phase::Cli, "Verificación de ownership/borrowing desactivada (--no-borrow-check)");
+    }
+    
+    Ok(program)
+}
+
 /// Compila usando backend NASM directo (PRIORIDAD)
 /// Genera ASM virgen y limpio sin dependencias externas
-fn compile_nasm_direct(source: &str, output_path: &PathBuf) -> Result<()> {
-    eprintln!("[CLI-DEBUG] Iniciando parse...");
-    io::stderr().flush().ok();
+pub(crate) fn compile_nasm_direct(input: &Path, source: &str, output_path: &Path, target: Target) -> Result<()> {
+    let file_name = input.display().to_string();
+    let program = parse_and_check(input, source)?;
     
-    // Parsear código ADead
-    let program = adead_parser::parse(source)
-        .map_err(|e| {
-            eprintln!("[CLI-ERROR] Error de parser: {:?}", e);
-            io::stderr().flush().ok();
-            anyhow::anyhow!("Error de parser: {:?}", e)
-        })?;
+    if EMIT_ANALYSIS.load(Ordering::Relaxed) {
+        println!("{}", adead_backend::CodeGenerator::analysis_report(&program));
+    }
     
-    eprintln!("[CLI-DEBUG] Parse exitoso, iniciando generación NASM...");
-    io::stderr().flush().ok();
+    log_debug!(Phase::Cli, "Parse exitoso, iniciando generación NASM...");
     
-    // Generar NASM usando el backend directo
-    let mut generator = adead_backend::CodeGenerator::new();
+    // Generar NASM usando el backend directo (para el target pedido, no el host)
+    let mut generator = adead_backend::CodeGenerator::with_target(target);
+    generator.set_source(source);
     let nasm_code = generator.generate(&program)
-        .map_err(|e| {
-            eprintln!("[CLI-ERROR] Error generando NASM: {:?}", e);
-            io::stderr().flush().ok();
-            anyhow::anyhow!("Error generando NASM: {:?}", e)
-        })?;
-    
-    eprintln!("[CLI-DEBUG] Generación NASM exitosa, escribiendo archivo...");
-    io::stderr().flush().ok();
+        .map_err(|e| anyhow::anyhow!("Error generando NASM:\n{}", e.render(&file_name, source)))?;
     
     // Escribir archivo ASM
     fs::write(output_path, nasm_code)
         .with_context(|| format!("Error escribiendo: {}", output_path.display()))?;
     
-    eprintln!("[CLI-DEBUG] Archivo escrito exitosamente");
-    io::stderr().flush().ok();
+    log_debug!(Phase::Cli, "ASM escrito: {}", output_path.display());
     
     Ok(())
 }
@@ -354,6 +501,71 @@ fn parse_linker_type(linker: &str) -> Option<LinkerType> {
         "zig" => Some(LinkerType::Zig),
         "gcc" => Some(LinkerType::Gcc),
         "clang" => Some(LinkerType::Clang),
+        "ld" => Some(LinkerType::Ld),
         "auto" | _ => None, // Auto-detect
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static_method_call_passes_checks() {
+        let src = "struct Calculadora {\n}\nfn Calculadora_sumar(a, b) {\n    return a + b\n}\nlet resultado = Calculadora.sumar(10, 20)\nprint resultado\n";
+        let program = parse_and_check(Path::new("estatico.ad"), src).unwrap();
+        let asm = adead_backend::CodeGenerator::new().generate(&program).unwrap();
+        assert!(asm.contains("call fn_Calculadora_sumar"));
+        assert_eq!(asm.matches("\nfn_Calculadora_sumar:").count(), 1);
+    }
+
+    #[test]
+    fn test_use_after_move_is_rejected() {
+        let src = "let xs = [1, 2, 3]\nlet b = xs\nprint xs\n";
+        let err = parse_and_check(Path::new("movido.ad"), src).unwrap_err().to_string();
+        assert!(err.starts_with("Error de ownership/borrowing:"), "{}", err);
+        assert!(err.contains("Borrow error: Variable 'xs' fue movida y ya no es válida"), "{}", err);
+        assert!(err.contains("movido.ad:3:7"), "{}", err);
+    }
+
+    #[test]
+    fn test_run_forwards_args_after_double_dash() {
+        let cli = Cli::try_parse_from(["adeadc", "run", "prog.ad", "--", "a", "-b", "--c"]).unwrap();
+        match cli.command {
+            Commands::Run { input, args, .. } => {
+                assert_eq!(input, PathBuf::from("prog.ad"));
+                assert_eq!(args, ["a", "-b", "--c"]);
+            }
+            _ => panic!("se esperaba el subcomando run"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_passes_args_and_exit_code() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Programa de prueba: termina con el número de argumentos recibidos
+        let dir = std::env::temp_dir().join(format!("adeadc-test-exec-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("args.sh");
+        fs::write(&script, "#!/bin/sh\nexit $#\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let args: Vec<String> = ["uno", "dos tres", "-x"].iter().map(|s| s.to_string()).collect();
+        let code = execute(&script, &args).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(code, 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_code_of_signal() {
+        let status = std::process::Command::new("sh").args(["-c", "exit 7"]).status().unwrap();
+        assert_eq!(exit_code_of(&status), 7);
+
+        // Terminado por SIGKILL (9): 128 + señal, como en la shell
+        let status = std::process::Command::new("sh").args(["-c", "kill -9 $$"]).status().unwrap();
+        assert_eq!(exit_code_of(&status), 137);
+    }
+}
@@ -0,0 +1,123 @@
+//! Diagnósticos renderizados estilo rustc
+//!
+//! Un `Diagnostic` describe un error en una ubicación del código fuente.
+//! `render` lo muestra con archivo:línea:columna, la línea de código y un
+//! subrayado con `^` bajo el texto que falló:
+//!
+//! ```text
+//! error: token inesperado ')'
+//!  --> programa.ad:2:11
+//!   |
+//! 2 | print (2 +)
+//!   |           ^
+//!   = esperado: identificador, número, '('
+//! ```
+
+use crate::span::{line_col, Span};
+
+/// Error con ubicación, listo para mostrar al usuario
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Rango de bytes en el código fuente
+    pub span: Span,
+    pub message: String,
+    /// Tokens que el parser esperaba encontrar (hint)
+    pub expected: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(span: Span, message: impl Into<String>) -> Self {
+        Self {
+            span,
+            message: message.into(),
+            expected: Vec::new(),
+        }
+    }
+
+    pub fn with_expected(mut self, expected: Vec<String>) -> Self {
+        self.expected = expected;
+        self
+    }
+
+    /// Renderizar el diagnóstico (sin salto de línea final)
+    pub fn render(&self, file: &str, source: &str) -> String {
+        let (line, col) = line_col(source, self.span.start);
+        let line_text = source.lines().nth(line - 1).unwrap_or("");
+        let gutter = " ".repeat(line.to_string().len());
+
+        // Subrayar hasta el fin del span o de la línea (al menos un caracter)
+        let remaining = line_text.chars().count().saturating_sub(col - 1);
+        let span_chars = source
+            .get(self.span.start..self.span.end.min(source.len()))
+            .map(|text| text.chars().take_while(|c| *c != '\n').count())
+            .unwrap_or(0);
+        let carets = span_chars.min(remaining).max(1);
+
+        let mut out = format!("error: {}\n", self.message);
+        out.push_str(&format!("{}--> {}:{}:{}\n", gutter, file, line, col));
+        out.push_str(&format!("{} |\n", gutter));
+        out.push_str(&format!("{} | {}\n", line, line_text));
+        out.push_str(&format!("{} | {}{}", gutter, " ".repeat(col - 1), "^".repeat(carets)));
+        if !self.expected.is_empty() {
+            out.push_str(&format!("\n{} = esperado: {}", gutter, self.expected.join(", ")));
+        }
+        out
+    }
+}
+
+/// Renderizar varios diagnósticos separados por una línea en blanco,
+/// con un resumen al final si hay más de uno
+pub fn render_diagnostics(diagnostics: &[Diagnostic], file: &str, source: &str) -> String {
+    let mut out = diagnostics
+        .iter()
+        .map(|d| d.render(file, source))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if diagnostics.len() > 1 {
+        out.push_str(&format!("\n\nerror: se encontraron {} errores", diagnostics.len()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_snippet_and_caret() {
+        let src = "let x = 1\nprint (2 +)\n";
+        let start = src.find(')').unwrap();
+        let diag = Diagnostic::new(Span::new(start, start + 1), "token inesperado ')'")
+            .with_expected(vec!["número".to_string(), "'('".to_string()]);
+        assert_eq!(
+            diag.render("prog.ad", src),
+            "error: token inesperado ')'\n\
+             \x20--> prog.ad:2:11\n\
+             \x20 |\n\
+             2 | print (2 +)\n\
+             \x20 |           ^\n\
+             \x20 = esperado: número, '('"
+        );
+    }
+
+    #[test]
+    fn test_render_caret_stops_at_line_end() {
+        let src = "while x {\n  print x\n";
+        let diag = Diagnostic::new(Span::new(0, src.len()), "bloque sin cerrar");
+        let rendered = diag.render("a.ad", src);
+        assert!(rendered.ends_with("\n  | ^^^^^^^^^"), "{}", rendered);
+    }
+
+    #[test]
+    fn test_render_diagnostics_summary() {
+        let src = "a\nb\n";
+        let diags = vec![
+            Diagnostic::new(Span::new(0, 1), "primero"),
+            Diagnostic::new(Span::new(2, 3), "segundo"),
+        ];
+        let rendered = render_diagnostics(&diags, "a.ad", src);
+        assert!(rendered.contains("a.ad:1:1"));
+        assert!(rendered.contains("a.ad:2:1"));
+        assert!(rendered.ends_with("error: se encontraron 2 errores"));
+    }
+}
@@ -1,5 +1,11 @@
 use thiserror::Error;
 
+mod diagnostic;
+pub mod log;
+mod span;
+pub use diagnostic::{render_diagnostics, Diagnostic};
+pub use span::{line_col, offset_of, Span, Spanned};
+
 /// Sistema de tipos robusto compatible con NASM x86-64
 /// Cada tipo mapea correctamente a representaciones en ASM
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -31,6 +37,11 @@ pub enum Type {
         size: Option<usize>,  // Some(n) = tamaño fijo, None = dinámico
     },
     Tuple(Vec<Type>),
+    Dict {     // Tabla hash en heap: puntero a la cabecera (8 bytes)
+        key: Box<Type>,
+        value: Box<Type>,
+    },
+    Set(Box<Type>),  // Tabla hash en heap sin valores: puntero (8 bytes)
     
     // ========== TIPOS OPCIONALES Y ERRORES (preparación O0.4) ==========
     Option(Box<Type>),
@@ -39,6 +50,19 @@ pub enum Type {
         err: Box<Type>,
     },
     
+    // ========== TIPOS DEFINIDOS POR EL USUARIO ==========
+    Struct(String),  // Instancia de struct: puntero al objeto (8 bytes)
+    Enum(String),    // Valor de enum (tagged union): puntero a [tag, payload...] (8 bytes)
+    Trait(String),   // Objeto de trait (fat pointer): puntero a [datos, vtable] (8 bytes)
+    Generic {        // Instancia de un struct genérico: `Caja<int64>` (puntero, 8 bytes)
+        name: String,
+        args: Vec<Type>,
+    },
+    Function {       // Función o closure: `fn(int64) -> int64` (puntero a [código, entorno], 8 bytes)
+        params: Vec<Type>,
+        ret: Box<Type>,
+    },
+    
     // ========== REFERENCIAS (preparación O0.2 - Ownership) ==========
     Ref {
         inner: Box<Type>,
@@ -58,14 +82,146 @@ pub enum ADeadError {
     #[error("Parse error at {line}:{col}: {message}")]
     ParseError { line: usize, col: usize, message: String },
 
+    /// Todos los errores de sintaxis encontrados en una pasada del parser
+    #[error("{} syntax error(s), first: {}", .0.len(), .0.first().map(|d| d.message.as_str()).unwrap_or(""))]
+    SyntaxErrors(Vec<Diagnostic>),
+
     #[error("Type error: {message}")]
     TypeError { message: String },
 
+    /// Violación de las reglas de ownership/borrowing (adead-borrow)
+    #[error("Borrow error: {message}")]
+    BorrowError { message: String },
+
     #[error("Runtime error: {message}")]
     RuntimeError { message: String },
 
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+
+    /// Error con ubicación en el código fuente (backend, borrow checker)
+    #[error("{error}")]
+    At { span: Span, error: Box<ADeadError> },
+}
+
+impl ADeadError {
+    /// Adjuntar ubicación al error. Si ya tiene una, se conserva la más interna
+    /// (la del statement más específico donde ocurrió). Un span vacío (statement
+    /// generado o construido a mano) no ubica nada y deja el error sin ubicación.
+    pub fn at(self, span: Span) -> Self {
+        match self {
+            ADeadError::At { .. } | ADeadError::ParseError { .. } | ADeadError::SyntaxErrors(_) => self,
+            error if span.is_empty() => error,
+            error => ADeadError::At { span, error: Box::new(error) },
+        }
+    }
+
+    /// Ubicación del error, si la tiene
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ADeadError::At { span, .. } => Some(*span),
+            ADeadError::SyntaxErrors(diagnostics) => diagnostics.first().map(|d| d.span),
+            _ => None,
+        }
+    }
+
+    /// Línea y columna (1-based) del error, calculadas con el código fuente
+    pub fn line_col(&self, source: &str) -> Option<(usize, usize)> {
+        match self {
+            ADeadError::ParseError { line, col, .. } => Some((*line, *col)),
+            ADeadError::At { .. } | ADeadError::SyntaxErrors(_) => {
+                self.span().map(|span| span.line_col(source))
+            }
+            _ => None,
+        }
+    }
+
+    /// Error sin la ubicación
+    pub fn inner(&self) -> &ADeadError {
+        match self {
+            ADeadError::At { error, .. } => error.inner(),
+            error => error,
+        }
+    }
+
+    /// Mensaje para el usuario con línea/columna reales (si el error tiene ubicación)
+    pub fn describe(&self, source: &str) -> String {
+        match self {
+            ADeadError::At { span, error } => {
+                let (line, col) = span.line_col(source);
+                format!("línea {}, columna {}: {}", line, col, error.inner())
+            }
+            error => error.to_string(),
+        }
+    }
+
+    /// Renderizar el error estilo rustc (archivo:línea:columna, código y subrayado).
+    /// Los errores sin ubicación se muestran como `error: <mensaje>`.
+    pub fn render(&self, file: &str, source: &str) -> String {
+        match self {
+            ADeadError::SyntaxErrors(diagnostics) => render_diagnostics(diagnostics, file, source),
+            ADeadError::At { span, error } => {
+                Diagnostic::new(*span, error.inner().to_string()).render(file, source)
+            }
+            ADeadError::ParseError { line, col, message } => {
+                let start = offset_of(source, *line, *col);
+                Diagnostic::new(Span::new(start, start), message.clone()).render(file, source)
+            }
+            error => format!("error: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_at_keeps_innermost_span() {
+        let err = ADeadError::TypeError { message: "x".to_string() }
+            .at(Span::new(10, 12))
+            .at(Span::new(0, 30));
+        assert_eq!(err.span(), Some(Span::new(10, 12)));
+    }
+
+    #[test]
+    fn test_at_ignores_empty_span() {
+        // Statement sin ubicación: no se inventa línea 1, columna 1
+        let err = ADeadError::TypeError { message: "x".to_string() }.at(Span::default());
+        assert_eq!(err.span(), None);
+        assert_eq!(err.line_col("let a = 1\n"), None);
+    }
+
+    #[test]
+    fn test_describe_reports_line_col() {
+        let src = "let a = 1\n  print b\n";
+        let err = ADeadError::RuntimeError { message: "variable no definida: b".to_string() }
+            .at(Span::new(12, 19));
+        assert_eq!(err.line_col(src), Some((2, 3)));
+        assert_eq!(
+            err.describe(src),
+            "línea 2, columna 3: Runtime error: variable no definida: b"
+        );
+    }
+
+    #[test]
+    fn test_type_from_name() {
+        assert_eq!(Type::from_name("int"), Some(Type::Int64));
+        assert_eq!(Type::from_name("float32"), Some(Type::Float32));
+        assert_eq!(Type::from_name("string"), Some(Type::String));
+        assert_eq!(Type::from_name("Persona"), None);
+        assert_eq!(Type::Struct("Persona".to_string()).size_bytes(), 8);
+    }
+
+    #[test]
+    fn test_render_located_error() {
+        let src = "let a = 1\n  print b\n";
+        let err = ADeadError::RuntimeError { message: "variable no definida: b".to_string() }
+            .at(Span::new(12, 19));
+        let rendered = err.render("main.ad", src);
+        assert!(rendered.starts_with("error: Runtime error: variable no definida: b\n --> main.ad:2:3"));
+        assert!(rendered.ends_with("  |   ^^^^^^^"), "{}", rendered);
+    }
 }
 
 /// Errores estándar para usar en programas ADead
@@ -155,8 +311,10 @@ impl Type {
                 ok.size_bytes().max(err.size_bytes()) + 1
             }
             
-            // Referencias: siempre 8 bytes (puntero en x86-64)
-            Type::Ref { .. } => 8,
+            // Referencias e instancias de struct: siempre 8 bytes (puntero en x86-64)
+            Type::Ref { .. } | Type::Struct(_) | Type::Enum(_) | Type::Trait(_) | Type::Generic { .. } => 8,
+            Type::Function { .. } => 8, // Puntero a la closure
+            Type::Dict { .. } | Type::Set(_) => 8, // Puntero a la tabla hash
             
             // Void/Never: 0 bytes (no hay representación)
             Type::Void | Type::Never => 0,
@@ -187,9 +345,11 @@ impl Type {
             Type::Float32 => ("xmm0", "dword"),
             Type::Float64 => ("xmm0", "qword"),
             Type::String => ("rax", "qword"), // Puntero (primer elemento)
-            Type::Ref { .. } => ("rax", "qword"), // Punteros son 64 bits
+            Type::Ref { .. } | Type::Struct(_) | Type::Enum(_) | Type::Trait(_) | Type::Generic { .. } => ("rax", "qword"), // Punteros son 64 bits
             Type::Array { .. } => ("rax", "qword"), // Puntero al array
             Type::Tuple(_) => ("rax", "qword"), // Puntero a la tupla
+            Type::Dict { .. } | Type::Set(_) => ("rax", "qword"), // Puntero a la tabla hash
+            Type::Function { .. } => ("rax", "qword"), // Puntero a la closure
             Type::Option(_) | Type::Result { .. } => ("rax", "qword"), // Tagged union
             Type::Void | Type::Never => ("", ""), // Sin registro
             Type::Unknown => ("rax", "qword"), // Asumir 64 bits
@@ -257,6 +417,7 @@ impl Type {
             Type::Array { size: Some(_), .. } => true,
             Type::Array { size: None, .. } => false,  // Array dinámico
             Type::String => false,  // String es dinámico (heap)
+            Type::Dict { .. } | Type::Set(_) => false,  // Dict y Set crecen en el heap
             _ => true,
         }
     }
@@ -306,6 +467,11 @@ impl Type {
             Type::Void => "void".to_string(),
             Type::Never => "!".to_string(),
             Type::Unknown => "_".to_string(),
+            Type::Struct(name) | Type::Enum(name) | Type::Trait(name) => name.clone(),
+            Type::Generic { name, args } => {
+                let args_str: Vec<String> = args.iter().map(|t| t.to_string()).collect();
+                format!("{}<{}>", name, args_str.join(", "))
+            }
             
             // Tipos compuestos
             Type::Array { element_type, size } => {
@@ -321,6 +487,12 @@ impl Type {
                     .collect();
                 format!("({})", types_str.join(", "))
             }
+            Type::Dict { key, value } => format!("dict<{}, {}>", key.to_string(), value.to_string()),
+            Type::Function { params, ret } => {
+                let params_str: Vec<String> = params.iter().map(|t| t.to_string()).collect();
+                format!("fn({}) -> {}", params_str.join(", "), ret.to_string())
+            }
+            Type::Set(element) => format!("set<{}>", element.to_string()),
             
             // Option/Result
             Type::Option(inner) => format!("Option<{}>", inner.to_string()),
@@ -339,6 +511,29 @@ impl Type {
         }
     }
     
+    /// Tipo primitivo a partir de su nombre en el código fuente (`int64`, `float`, `string`, ...).
+    /// Los nombres de structs no se resuelven aquí (retorna `None`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let ty = match name {
+            "int8" | "i8" => Type::Int8,
+            "int16" | "i16" => Type::Int16,
+            "int32" | "i32" => Type::Int32,
+            "int" | "int64" | "i64" => Type::Int64,
+            "uint8" | "u8" => Type::UInt8,
+            "uint16" | "u16" => Type::UInt16,
+            "uint32" | "u32" => Type::UInt32,
+            "uint64" | "u64" => Type::UInt64,
+            "float32" | "f32" => Type::Float32,
+            "float" | "float64" | "f64" => Type::Float64,
+            "bool" => Type::Bool,
+            "char" => Type::Char,
+            "string" | "str" => Type::String,
+            "void" => Type::Void,
+            _ => return None,
+        };
+        Some(ty)
+    }
+    
     /// Tipo por defecto para literales numéricos enteros (int64 - estándar x86-64)
     pub fn default_int() -> Self {
         Type::Int64
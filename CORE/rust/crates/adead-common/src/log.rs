@@ -0,0 +1,209 @@
+//! Logging por niveles y por fase del compilador
+//!
+//! Reemplaza los `eprintln!` de depuración incondicionales. El nivel y las
+//! fases activas son globales (los fija adeadc a partir de `-v`, `-vv`,
+//! `--quiet` y `--log`) y todos los mensajes van a stderr con el formato
+//! clásico de ADead:
+//!
+//! ```text
+//! [PARSER-DEBUG] Parseando 12 statements
+//! ```
+//!
+//! Uso desde los crates del compilador:
+//!
+//! ```ignore
+//! use adead_common::log::Phase;
+//! adead_common::log_debug!(Phase::Parser, "Parseando {} statements", n);
+//! ```
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Nivel de detalle de un mensaje (de menor a mayor verbosidad)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl Level {
+    fn tag(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARNING",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+/// Fase del compilador que emite el mensaje
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Cli,
+    Parser,
+    Backend,
+    Linker,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [Phase::Cli, Phase::Parser, Phase::Backend, Phase::Linker];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            Phase::Cli => "CLI",
+            Phase::Parser => "PARSER",
+            Phase::Backend => "BACKEND",
+            Phase::Linker => "LINKER",
+        }
+    }
+
+    /// Parsear el nombre de una fase (`cli`, `parser`, `backend`, `linker`)
+    pub fn from_name(name: &str) -> Option<Phase> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "cli" => Some(Phase::Cli),
+            "parser" => Some(Phase::Parser),
+            "backend" => Some(Phase::Backend),
+            "linker" => Some(Phase::Linker),
+            _ => None,
+        }
+    }
+}
+
+/// 0 = silencio total (`--quiet`)
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+static PHASES: AtomicU8 = AtomicU8::new(0b1111);
+
+/// Nivel máximo que se muestra; `None` silencia todo
+pub fn set_level(level: Option<Level>) {
+    LEVEL.store(level.map_or(0, |l| l as u8), Ordering::Relaxed);
+}
+
+/// Nivel actual (`None` si todo está silenciado)
+pub fn level() -> Option<Level> {
+    match LEVEL.load(Ordering::Relaxed) {
+        1 => Some(Level::Error),
+        2 => Some(Level::Warn),
+        3 => Some(Level::Info),
+        4 => Some(Level::Debug),
+        5 => Some(Level::Trace),
+        _ => None,
+    }
+}
+
+/// Nivel de adeadc según la cantidad de `-v`: sin flags se muestra el progreso
+/// (info), `-v` agrega el detalle de cada fase y `-vv` el de cada statement
+pub fn level_from_verbosity(verbose: u8) -> Level {
+    match verbose {
+        0 => Level::Info,
+        1 => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+/// Restringir los mensajes a las fases indicadas (los errores se muestran siempre)
+pub fn set_phases(phases: &[Phase]) {
+    let mask = phases.iter().fold(0, |mask, phase| mask | phase.bit());
+    PHASES.store(mask, Ordering::Relaxed);
+}
+
+/// ¿Se mostraría un mensaje de esta fase y nivel?
+pub fn enabled(phase: Phase, level: Level) -> bool {
+    if level as u8 > LEVEL.load(Ordering::Relaxed) {
+        return false;
+    }
+    level == Level::Error || PHASES.load(Ordering::Relaxed) & phase.bit() != 0
+}
+
+/// Formato de una línea de log (sin salto de línea)
+pub fn format_line(phase: Phase, level: Level, message: &str) -> String {
+    format!("[{}-{}] {}", phase.tag(), level.tag(), message)
+}
+
+#[doc(hidden)]
+pub fn write(phase: Phase, level: Level, args: std::fmt::Arguments) {
+    if enabled(phase, level) {
+        eprintln!("{}", format_line(phase, level, &args.to_string()));
+    }
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ($phase:expr, $($arg:tt)*) => {
+        $crate::log::write($phase, $crate::log::Level::Error, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ($phase:expr, $($arg:tt)*) => {
+        $crate::log::write($phase, $crate::log::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ($phase:expr, $($arg:tt)*) => {
+        $crate::log::write($phase, $crate::log::Level::Info, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($phase:expr, $($arg:tt)*) => {
+        $crate::log::write($phase, $crate::log::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! log_trace {
+    ($phase:expr, $($arg:tt)*) => {
+        $crate::log::write($phase, $crate::log::Level::Trace, format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // El estado es global: un único test para no competir con otros hilos
+    #[test]
+    fn test_levels_and_phases() {
+        set_level(Some(Level::Warn));
+        set_phases(&Phase::ALL);
+        assert!(enabled(Phase::Parser, Level::Warn));
+        assert!(!enabled(Phase::Parser, Level::Info));
+
+        set_level(Some(level_from_verbosity(1)));
+        assert_eq!(level(), Some(Level::Debug));
+        assert!(enabled(Phase::Backend, Level::Debug));
+        assert!(!enabled(Phase::Backend, Level::Trace));
+
+        set_phases(&[Phase::Linker]);
+        assert!(!enabled(Phase::Parser, Level::Debug));
+        assert!(enabled(Phase::Linker, Level::Debug));
+        assert!(enabled(Phase::Parser, Level::Error));
+
+        set_level(None);
+        assert_eq!(level(), None);
+        assert!(!enabled(Phase::Parser, Level::Error));
+
+        set_level(Some(Level::Warn));
+        set_phases(&Phase::ALL);
+    }
+
+    #[test]
+    fn test_format_line_and_phase_names() {
+        assert_eq!(format_line(Phase::Parser, Level::Debug, "hola"), "[PARSER-DEBUG] hola");
+        assert_eq!(format_line(Phase::Cli, Level::Warn, "x"), "[CLI-WARNING] x");
+        assert_eq!(Phase::from_name(" Backend "), Some(Phase::Backend));
+        assert_eq!(Phase::from_name("nasm"), None);
+    }
+}
@@ -0,0 +1,211 @@
+//! Ubicaciones en el código fuente (spans)
+//!
+//! Un `Span` es un rango de bytes `[start, end)` dentro del código fuente.
+//! El parser adjunta un `Span` a cada statement (`Spanned<Stmt>`) y a cada
+//! expresión (`Spanned<Expr>`). Los errores del type checker y del borrow
+//! checker se ubican en la subexpresión más interna donde ocurrieron; los del
+//! backend, en el statement más interno (los errores de sintaxis tienen la
+//! posición exacta del parser).
+
+use std::ops::{Deref, DerefMut, Range};
+
+/// Rango de bytes `[start, end)` en el código fuente
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Span que cubre ambos spans
+    pub fn merge(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    /// Recortar whitespace y comentarios (`#`) al inicio y whitespace al final.
+    /// Los parsers con `.padded()` incluyen el whitespace previo en el span.
+    pub fn trim(&self, source: &str) -> Span {
+        let end = self.end.min(source.len());
+        let mut start = self.start.min(end);
+        let bytes = source.as_bytes();
+
+        loop {
+            while start < end && bytes[start].is_ascii_whitespace() {
+                start += 1;
+            }
+            if start < end && bytes[start] == b'#' {
+                while start < end && bytes[start] != b'\n' {
+                    start += 1;
+                }
+                continue;
+            }
+            break;
+        }
+
+        let mut trimmed_end = end;
+        while trimmed_end > start && bytes[trimmed_end - 1].is_ascii_whitespace() {
+            trimmed_end -= 1;
+        }
+
+        Span::new(start, trimmed_end)
+    }
+
+    /// Línea y columna (1-based) del inicio del span
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        line_col(source, self.start)
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span::new(range.start, range.end)
+    }
+}
+
+/// Convertir un offset de bytes a línea y columna (1-based, columna en caracteres)
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let col = source[line_start..offset].chars().count() + 1;
+    (line, col)
+}
+
+/// Convertir línea y columna (1-based, columna en caracteres) a offset de bytes
+pub fn offset_of(source: &str, line: usize, col: usize) -> usize {
+    let line_start = if line <= 1 {
+        0
+    } else {
+        match source.match_indices('\n').nth(line - 2) {
+            Some((i, _)) => i + 1,
+            None => return source.len(),
+        }
+    };
+    source[line_start..]
+        .char_indices()
+        .take_while(|(_, c)| *c != '\n')
+        .nth(col.saturating_sub(1))
+        .map(|(i, _)| line_start + i)
+        .unwrap_or_else(|| source[line_start..].find('\n').map_or(source.len(), |i| line_start + i))
+}
+
+/// Nodo del AST con su ubicación en el código fuente
+///
+/// La igualdad compara solo el nodo: dos ASTs iguales en distintas posiciones son iguales.
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+
+    /// Nodo sin ubicación (AST construido a mano o generado)
+    pub fn dummy(node: T) -> Self {
+        Self { node, span: Span::default() }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.node
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned { node: f(self.node), span: self.span }
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+impl<T> DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.node
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T: PartialEq> PartialEq<T> for Spanned<T> {
+    fn eq(&self, other: &T) -> bool {
+        &self.node == other
+    }
+}
+
+impl<T> From<T> for Spanned<T> {
+    fn from(node: T) -> Self {
+        Spanned::dummy(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_col() {
+        let src = "let x = 1\nprint x\n";
+        assert_eq!(line_col(src, 0), (1, 1));
+        assert_eq!(line_col(src, 4), (1, 5));
+        assert_eq!(line_col(src, 10), (2, 1));
+        assert_eq!(line_col(src, 16), (2, 7));
+    }
+
+    #[test]
+    fn test_line_col_multibyte() {
+        let src = "# año\nprint 1";
+        assert_eq!(line_col(src, src.find("print").unwrap()), (2, 1));
+        assert_eq!(line_col(src, 5), (1, 5));
+    }
+
+    #[test]
+    fn test_offset_of_roundtrip() {
+        let src = "# año\nprint 1\n";
+        for offset in [0, 3, 7, 10] {
+            let (line, col) = line_col(src, offset);
+            assert_eq!(offset_of(src, line, col), offset);
+        }
+        assert_eq!(offset_of(src, 9, 1), src.len());
+    }
+
+    #[test]
+    fn test_trim_skips_whitespace_and_comments() {
+        let src = "\n  # comentario\n  print 1  \n";
+        let span = Span::new(0, src.len()).trim(src);
+        assert_eq!(&src[span.start..span.end], "print 1");
+        assert_eq!(span.line_col(src), (3, 3));
+    }
+
+    #[test]
+    fn test_spanned_eq_ignores_span() {
+        assert_eq!(Spanned::new(1, Span::new(0, 1)), Spanned::new(1, Span::new(5, 9)));
+        assert_eq!(Spanned::new("a", Span::new(0, 1)), "a");
+    }
+}
@@ -2,7 +2,7 @@
 //! Este módulo convierte el AST de ADead a código C equivalente
 //! que luego puede ser compilado con GCC/Clang a ASM optimizado
 
-use crate::{Program, Stmt, Expr, BinOp};
+use crate::{Program, Stmt, Expr, BinOp, Spanned};
 
 /// Generador de código C
 pub struct CGenerator {
@@ -200,7 +200,7 @@ impl CGenerator {
         let mut main_statements = Vec::new();
 
         for stmt in &program.statements {
-            match stmt {
+            match &stmt.node {
                 Stmt::Fn { .. } => {
                     functions.push(stmt);
                 }
@@ -238,7 +238,7 @@ impl CGenerator {
         match stmt {
             Stmt::Print(expr) => {
                 self.indent();
-                match expr {
+                match &expr.node {
                     Expr::String(s) => {
                         self.output.push_str(&format!("printf(\"{}\\n\"); fflush(stdout);\n", s.replace('"', "\\\"")));
                     }
@@ -263,7 +263,7 @@ impl CGenerator {
             Stmt::Let { name, value, .. } => {
                 self.indent();
                 // Determinar tipo basado en el valor
-                match value {
+                match &value.node {
                     Expr::ArrayLiteral(elements) => {
                         if elements.is_empty() {
                             self.output.push_str(&format!("Array {} = array_new();\n", name));
@@ -335,13 +335,13 @@ impl CGenerator {
                 self.indent();
                 self.output.push_str("}\n");
             }
-            Stmt::Expr(Expr::Assign { name, value }) => {
+            Stmt::Expr(Spanned { node: Expr::Assign { name, value }, .. }) => {
                 self.indent();
                 // Verificar si es asignación a índice de array: arr[0] = value
                 // Detectamos esto cuando name == "_array_set" (marcador especial del parser)
                 if name == "_array_set" {
-                    if let Expr::BinaryOp { left, right, .. } = value.as_ref() {
-                        if let Expr::Index { array, index } = left.as_ref() {
+                    if let Expr::BinaryOp { left, right, .. } = &value.node {
+                        if let Expr::Index { array, index } = &left.node {
                             let array_code = self.generate_expr(array);
                             let index_code = self.generate_expr(index);
                             let value_code = self.generate_expr(right);
@@ -375,7 +375,7 @@ impl CGenerator {
                 self.indent();
                 let expr_code = self.generate_expr(expr);
                 // Si es MethodCall (como arr.append()), generar como statement con punto y coma
-                if matches!(expr, Expr::MethodCall { .. }) {
+                if matches!(&expr.node, Expr::MethodCall { .. }) {
                     self.output.push_str(&format!("{};\n", expr_code));
                 }
                 // Otras expresiones se evalúan pero no se usan (pueden tener side effects)
@@ -415,6 +415,9 @@ impl CGenerator {
                 if matches!(op, BinOp::FloorDiv) {
                     return format!("({} / {})", left_code, right_code);
                 }
+                if matches!(op, BinOp::In) {
+                    return format!("0 /* TODO: {} in {} */", left_code, right_code);
+                }
                 
                 let op_str = match op {
                     BinOp::Add => "+",
@@ -430,7 +433,7 @@ impl CGenerator {
                     BinOp::Ge => ">=",
                     BinOp::And => "&&",
                     BinOp::Or => "||",
-                    BinOp::Pow | BinOp::FloorDiv => unreachable!(),
+                    BinOp::Pow | BinOp::FloorDiv | BinOp::In => unreachable!(),
                 };
                 format!("({} {} {})", left_code, op_str, right_code)
             }
@@ -563,7 +566,7 @@ mod tests {
     fn test_generate_simple_print() {
         let program = Program {
             statements: vec![
-                Stmt::Print(Expr::String("Hello, World!".to_string())),
+                Stmt::Print(Expr::String("Hello, World!".to_string()).into()).into(),
             ],
         };
         let c_code = generate_c_code(&program);
@@ -578,12 +581,13 @@ mod tests {
                 Stmt::Let {
                     mutable: false,
                     name: "x".to_string(),
+                    ty: None,
                     value: Expr::BinaryOp {
                         op: BinOp::Add,
-                        left: Box::new(Expr::Number(10)),
-                        right: Box::new(Expr::Number(20)),
-                    },
-                },
+                        left: Box::new(Expr::Number(10).into()),
+                        right: Box::new(Expr::Number(20).into()),
+                    }.into(),
+                }.into(),
             ],
         };
         let c_code = generate_c_code(&program);
@@ -2,7 +2,7 @@
 // Reemplaza Tree-sitter con parsing directo y simple
 // Enfocado en while/if y estructuras básicas
 
-use crate::{Stmt, Expr, BinOp, Program};
+use crate::{Stmt, Expr, BinOp, Program, Spanned};
 
 pub struct CManualParser {
     source: String,
@@ -41,11 +41,11 @@ impl CManualParser {
                 if expr_text.starts_with('"') && expr_text.ends_with('"') {
                     // String literal
                     let string_val = expr_text[1..expr_text.len()-1].to_string();
-                    statements.push(Stmt::Print(Expr::String(string_val)));
+                    statements.push(Stmt::Print(Expr::String(string_val).into()));
                 } else {
                     // Número o identificador
                     if let Ok(expr) = Self::parse_expr_from_text(expr_text) {
-                        statements.push(Stmt::Print(expr));
+                        statements.push(Stmt::Print(expr.into()));
                     }
                 }
                 i += 1;
@@ -59,7 +59,8 @@ impl CManualParser {
                         statements.push(Stmt::Let {
                             mutable: false,
                             name: var_name,
-                            value: value_expr,
+                            ty: None,
+                            value: value_expr.into(),
                         });
                     }
                 }
@@ -96,14 +97,14 @@ impl CManualParser {
                 
                 let while_text = while_lines.join("\n");
                 if let Ok((condition, body)) = Self::parse_while_from_text(&while_text) {
-                    statements.push(Stmt::While { condition, body });
+                    statements.push(Stmt::While { condition: condition.into(), body: body.into_iter().map(Spanned::from).collect() });
                 }
             } else if line.contains(".") && line.contains("(") && line.contains(")") && !line.starts_with("print ") {
                 // Método: arr.append(x) - parsear como statement
                 match Self::parse_expr_from_text(line) {
                     Ok(expr) => {
                         if matches!(expr, Expr::MethodCall { .. }) {
-                            statements.push(Stmt::Expr(expr));
+                            statements.push(Stmt::Expr(expr.into()));
                             i += 1;
                             continue;
                         }
@@ -134,10 +135,10 @@ impl CManualParser {
                                             left: Box::new(Expr::Index {
                                                 array: array.clone(),
                                                 index: index.clone(),
-                                            }),
-                                            right: Box::new(value_expr),
-                                        }),
-                                    }));
+                                            }.into()),
+                                            right: Box::new(value_expr.into()),
+                                        }.into()),
+                                    }.into()));
                                 }
                             }
                         }
@@ -147,8 +148,8 @@ impl CManualParser {
                         if let Ok(value_expr) = Self::parse_expr_from_text(right_side) {
                             statements.push(Stmt::Expr(Expr::Assign {
                                 name: var_name,
-                                value: Box::new(value_expr),
-                            }));
+                                value: Box::new(value_expr.into()),
+                            }.into()));
                         }
                     }
                 }
@@ -159,7 +160,7 @@ impl CManualParser {
                 if let Ok(expr) = Self::parse_expr_from_text(line) {
                     if matches!(expr, Expr::MethodCall { .. }) {
                         // Es un método call, agregar como statement
-                        statements.push(Stmt::Expr(expr));
+                        statements.push(Stmt::Expr(expr.into()));
                     }
                     // Si no es MethodCall, lo ignoramos (podría ser solo un identificador)
                 }
@@ -167,7 +168,8 @@ impl CManualParser {
             }
         }
         
-        Ok(Program { statements })
+        // El parser manual no rastrea posiciones: statements sin span
+        Ok(Program { statements: statements.into_iter().map(Spanned::from).collect() })
     }
 
     /// Parsear while loop completo desde texto
@@ -299,15 +301,15 @@ impl CManualParser {
                 let if_text = if_lines.join("\n");
                 if let Ok((cond, body)) = Self::parse_if_from_text(&if_text) {
                     statements.push(Stmt::If {
-                        condition: cond,
-                        then_body: body,
+                        condition: cond.into(),
+                        then_body: body.into_iter().map(Spanned::from).collect(),
                         else_body: None,
                     });
                 }
             } else if line.starts_with("print ") {
                 let expr_text = line[6..].trim();
                 if let Ok(expr) = Self::parse_expr_from_text(expr_text) {
-                    statements.push(Stmt::Print(expr));
+                    statements.push(Stmt::Print(expr.into()));
                 }
                 i += 1;
             } else if line.contains(".") && line.contains("(") && line.contains(")") && !line.starts_with("print ") {
@@ -315,7 +317,7 @@ impl CManualParser {
                 match Self::parse_expr_from_text(line) {
                     Ok(expr) => {
                         if matches!(expr, Expr::MethodCall { .. }) {
-                            statements.push(Stmt::Expr(expr));
+                            statements.push(Stmt::Expr(expr.into()));
                             i += 1;
                             continue;
                         }
@@ -346,10 +348,10 @@ impl CManualParser {
                                             left: Box::new(Expr::Index {
                                                 array: array.clone(),
                                                 index: index.clone(),
-                                            }),
-                                            right: Box::new(value_expr),
-                                        }),
-                                    }));
+                                            }.into()),
+                                            right: Box::new(value_expr.into()),
+                                        }.into()),
+                                    }.into()));
                                 }
                             }
                         }
@@ -359,8 +361,8 @@ impl CManualParser {
                         if let Ok(value_expr) = Self::parse_expr_from_text(right_side) {
                             statements.push(Stmt::Expr(Expr::Assign {
                                 name: var_name,
-                                value: Box::new(value_expr),
-                            }));
+                                value: Box::new(value_expr.into()),
+                            }.into()));
                         }
                     }
                 }
@@ -371,7 +373,7 @@ impl CManualParser {
                 if let Ok(expr) = Self::parse_expr_from_text(line) {
                     if matches!(expr, Expr::MethodCall { .. }) {
                         // Es un método call, agregar como statement
-                        statements.push(Stmt::Expr(expr));
+                        statements.push(Stmt::Expr(expr.into()));
                     }
                     // Si no es MethodCall, lo ignoramos (podría ser solo un identificador)
                 }
@@ -412,12 +414,12 @@ impl CManualParser {
                         Vec::new()
                     } else {
                         args_text.split(',')
-                            .map(|a| Self::parse_expr_from_text(a.trim()))
+                            .map(|a| Self::parse_expr_from_text(a.trim()).map(Spanned::from))
                             .collect::<Result<Vec<_>, _>>()?
                     };
                     
                     return Ok(Expr::MethodCall {
-                        object: Box::new(object_expr),
+                        object: Box::new(object_expr.into()),
                         method: method_name.to_string(),
                         args,
                     });
@@ -434,7 +436,7 @@ impl CManualParser {
                 if !args_text.is_empty() {
                     // Parsear argumentos separados por comas
                     let args: Result<Vec<_>, _> = args_text.split(',')
-                        .map(|a| Self::parse_expr_from_text(a.trim()))
+                        .map(|a| Self::parse_expr_from_text(a.trim()).map(Spanned::from))
                         .collect();
                     return Ok(Expr::Call {
                         module: None,
@@ -459,7 +461,7 @@ impl CManualParser {
                 return Ok(Expr::ArrayLiteral(Vec::new()));
             }
             let elements: Result<Vec<_>, _> = inner.split(',')
-                .map(|e| Self::parse_expr_from_text(e.trim()))
+                .map(|e| Self::parse_expr_from_text(e.trim()).map(Spanned::from))
                 .collect();
             return Ok(Expr::ArrayLiteral(elements?));
         }
@@ -474,8 +476,8 @@ impl CManualParser {
                 let index_expr = Self::parse_expr_from_text(index_str)?;
                 
                 return Ok(Expr::Index {
-                    array: Box::new(array_expr),
-                    index: Box::new(index_expr),
+                    array: Box::new(array_expr.into()),
+                    index: Box::new(index_expr.into()),
                 });
             }
         }
@@ -509,8 +511,8 @@ impl CManualParser {
                 let right = Self::parse_expr_from_text(right_text)?;
                 return Ok(Expr::BinaryOp {
                     op: *op,
-                    left: Box::new(left),
-                    right: Box::new(right),
+                    left: Box::new(left.into()),
+                    right: Box::new(right.into()),
                 });
             }
         }
@@ -536,8 +538,8 @@ impl CManualParser {
                 let right = Self::parse_expr_from_text(right_text)?;
                 return Ok(Expr::BinaryOp {
                     op: *op,
-                    left: Box::new(left),
-                    right: Box::new(right),
+                    left: Box::new(left.into()),
+                    right: Box::new(right.into()),
                 });
             }
         }
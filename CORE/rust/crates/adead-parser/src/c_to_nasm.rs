@@ -3,7 +3,7 @@
 //! 
 //! Este módulo toma código C generado y lo convierte a ASM NASM limpio y optimizado
 
-use crate::{Program, Stmt, Expr, BinOp};
+use crate::{Program, Stmt, Expr, BinOp, Spanned};
 
 /// Generador de ASM NASM desde código C (intermediario)
 /// Estrategia: Parsear el código C generado y convertirlo directamente a NASM limpio
@@ -74,7 +74,7 @@ impl CToNASMGenerator {
         let mut var_offset = 128; // Comenzar desde rbp - 128
         
         for stmt in &program.statements {
-            if let Stmt::Let { name, .. } = stmt {
+            if let Stmt::Let { name, .. } = &stmt.node {
                 var_map.insert(name.clone(), var_offset);
                 var_offset += 8; // int64_t = 8 bytes
             }
@@ -89,7 +89,7 @@ impl CToNASMGenerator {
         
         // Segunda pasada: Generar código dentro de main
         for stmt in &program.statements {
-            match stmt {
+            match &stmt.node {
                 Stmt::Let { name, value, .. } => {
                     // Inicializar variable
                     self.generate_expression_to_rax_with_map(value, &var_map);
@@ -106,7 +106,7 @@ impl CToNASMGenerator {
                 Stmt::If { condition, then_body, else_body } => {
                     self.generate_if_clean_with_map(condition, then_body, else_body.as_ref(), &var_map);
                 }
-                Stmt::Expr(Expr::Assign { name, value }) => {
+                Stmt::Expr(Spanned { node: Expr::Assign { name, value }, .. }) => {
                     // Asignación: variable = valor
                     if let Some(offset) = var_map.get(name) {
                         self.generate_expression_to_rax_with_map(value, &var_map);
@@ -224,7 +224,7 @@ impl CToNASMGenerator {
         }
     }
     
-    fn generate_while_clean_with_map(&mut self, condition: &Expr, body: &[Stmt], var_map: &std::collections::HashMap<String, i32>) {
+    fn generate_while_clean_with_map(&mut self, condition: &Expr, body: &[Spanned<Stmt>], var_map: &std::collections::HashMap<String, i32>) {
         use std::fmt::Write;
         let loop_start = format!("loop_start_{}", self.label_count);
         let loop_end = format!("loop_end_{}", self.label_count);
@@ -239,12 +239,12 @@ impl CToNASMGenerator {
         
         // Generar body
         for stmt in body {
-            match stmt {
+            match &stmt.node {
                 Stmt::Print(expr) => self.generate_print_clean_with_map(expr, var_map),
                 Stmt::If { condition, then_body, else_body } => {
                     self.generate_if_clean_with_map(condition, then_body, else_body.as_ref(), var_map);
                 }
-                Stmt::Expr(Expr::Assign { name, value }) => {
+                Stmt::Expr(Spanned { node: Expr::Assign { name, value }, .. }) => {
                     if let Some(offset) = var_map.get(name) {
                         self.generate_expression_to_rax_with_map(value, var_map);
                         writeln!(self.text_section, "    mov [rbp - {}], rax  ; {} = ...", offset, name).unwrap();
@@ -258,7 +258,7 @@ impl CToNASMGenerator {
         writeln!(self.text_section, "{}:", loop_end).unwrap();
     }
     
-    fn generate_if_clean_with_map(&mut self, condition: &Expr, then_body: &[Stmt], else_body: Option<&Vec<Stmt>>, var_map: &std::collections::HashMap<String, i32>) {
+    fn generate_if_clean_with_map(&mut self, condition: &Expr, then_body: &[Spanned<Stmt>], else_body: Option<&Vec<Spanned<Stmt>>>, var_map: &std::collections::HashMap<String, i32>) {
         use std::fmt::Write;
         let if_end = format!("if_end_{}", self.label_count);
         let if_else = format!("if_else_{}", self.label_count);
@@ -276,7 +276,7 @@ impl CToNASMGenerator {
         
         // Generar then_body
         for stmt in then_body {
-            match stmt {
+            match &stmt.node {
                 Stmt::Print(expr) => self.generate_print_clean_with_map(expr, var_map),
                 Stmt::While { condition, body } => {
                     self.generate_while_clean_with_map(condition, body, var_map);
@@ -292,7 +292,7 @@ impl CToNASMGenerator {
             writeln!(self.text_section, "    jmp {}", if_end).unwrap();
             writeln!(self.text_section, "{}:", if_else).unwrap();
             for stmt in else_body_stmts {
-                match stmt {
+                match &stmt.node {
                     Stmt::Print(expr) => self.generate_print_clean_with_map(expr, var_map),
                     _ => {}
                 }
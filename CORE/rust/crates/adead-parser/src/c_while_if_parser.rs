@@ -1,7 +1,7 @@
 // Parser especializado para While e If en el flujo C → Rust
 // Prioriza el parsing robusto de estas estructuras usando extracción de texto + parser Rust
 
-use crate::{Stmt, Expr};
+use crate::{Stmt, Expr, Spanned};
 
 /// Parser especializado para While: extrae el body completo desde el texto
 pub fn parse_while_from_text(while_text: &str, source: &str) -> Result<(Expr, Vec<Stmt>), Box<dyn std::error::Error>> {
@@ -118,8 +118,8 @@ fn parse_expression_from_text(text: &str) -> Result<Expr, Box<dyn std::error::Er
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Le,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     } else if text.contains(">=") {
@@ -129,8 +129,8 @@ fn parse_expression_from_text(text: &str) -> Result<Expr, Box<dyn std::error::Er
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Ge,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     } else if text.contains("==") {
@@ -140,8 +140,8 @@ fn parse_expression_from_text(text: &str) -> Result<Expr, Box<dyn std::error::Er
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Eq,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     } else if text.contains("!=") {
@@ -151,8 +151,8 @@ fn parse_expression_from_text(text: &str) -> Result<Expr, Box<dyn std::error::Er
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Ne,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     } else if text.contains("<") && !text.contains("<=") {
@@ -162,8 +162,8 @@ fn parse_expression_from_text(text: &str) -> Result<Expr, Box<dyn std::error::Er
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Lt,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     } else if text.contains(">") && !text.contains(">=") {
@@ -173,8 +173,8 @@ fn parse_expression_from_text(text: &str) -> Result<Expr, Box<dyn std::error::Er
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Gt,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     }
@@ -188,8 +188,8 @@ fn parse_expression_from_text(text: &str) -> Result<Expr, Box<dyn std::error::Er
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Mod,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     }
@@ -214,8 +214,8 @@ pub fn parse_simple_expr(text: &str) -> Result<Expr, Box<dyn std::error::Error>>
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Add,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     } else if text.contains('-') && !text.starts_with('-') {
@@ -225,8 +225,8 @@ pub fn parse_simple_expr(text: &str) -> Result<Expr, Box<dyn std::error::Error>>
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Sub,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     } else if text.contains('*') {
@@ -236,8 +236,8 @@ pub fn parse_simple_expr(text: &str) -> Result<Expr, Box<dyn std::error::Error>>
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Mul,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     } else if text.contains('/') {
@@ -247,8 +247,8 @@ pub fn parse_simple_expr(text: &str) -> Result<Expr, Box<dyn std::error::Error>>
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Div,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     } else if text.contains('%') {
@@ -258,8 +258,8 @@ pub fn parse_simple_expr(text: &str) -> Result<Expr, Box<dyn std::error::Error>>
             let right = parse_simple_expr(parts[1].trim())?;
             return Ok(Expr::BinaryOp {
                 op: crate::BinOp::Mod,
-                left: Box::new(left),
-                right: Box::new(right),
+                left: Box::new(left.into()),
+                right: Box::new(right.into()),
             });
         }
     }
@@ -280,8 +280,8 @@ fn parse_statements_from_text(body_text: &str) -> Result<Vec<Stmt>, Box<dyn std:
         Ok(program) => {
             // Extraer statements del body de la función temporal
             for stmt in program.statements {
-                if let Stmt::Fn { body, .. } = stmt {
-                    return Ok(body);
+                if let Stmt::Fn { body, .. } = stmt.node {
+                    return Ok(body.into_iter().map(Spanned::into_inner).collect());
                 }
             }
             Ok(Vec::new())
@@ -331,9 +331,9 @@ fn parse_statements_manual(body_text: &str) -> Result<Vec<Stmt>, Box<dyn std::er
                             // Parsear if completo
                             if let Ok((condition, then_body, else_body)) = parse_if_from_text(&if_text) {
                                 statements.push(Stmt::If {
-                                    condition,
-                                    then_body,
-                                    else_body,
+                                    condition: condition.into(),
+                                    then_body: then_body.into_iter().map(Spanned::from).collect(),
+                                    else_body: else_body.map(|b| b.into_iter().map(Spanned::from).collect()),
                                 });
                             }
                             i = j + 1;
@@ -353,7 +353,7 @@ fn parse_statements_manual(body_text: &str) -> Result<Vec<Stmt>, Box<dyn std::er
         if line.starts_with("print ") {
             let expr_text = &line[6..].trim();
             if let Ok(expr) = parse_simple_expr(expr_text) {
-                statements.push(Stmt::Print(expr));
+                statements.push(Stmt::Print(expr.into()));
             }
             i += 1;
             continue;
@@ -367,8 +367,8 @@ fn parse_statements_manual(body_text: &str) -> Result<Vec<Stmt>, Box<dyn std::er
                 if let Ok(value_expr) = parse_simple_expr(value_text) {
                     statements.push(Stmt::Expr(Expr::Assign {
                         name: var_name,
-                        value: Box::new(value_expr),
-                    }));
+                        value: Box::new(value_expr.into()),
+                    }.into()));
                 }
             }
             i += 1;
@@ -18,7 +18,7 @@
 //! - constinit (para inicialización compile-time)
 //! - std::format (para mejor formateo de strings)
 
-use crate::{Program, Stmt, Expr, BinOp, StructField, StructMethod};
+use crate::{Program, Stmt, Expr, BinOp, ForIter, Spanned, StructField, StructMethod};
 use std::collections::HashMap;
 
 /// Información de una clase/struct para generación de código
@@ -68,7 +68,7 @@ impl CppGenerator {
         let mut main_statements = Vec::new();
 
         for stmt in &program.statements {
-            match stmt {
+            match &stmt.node {
                 Stmt::Struct { .. } => {
                     structs.push(stmt);
                 }
@@ -114,7 +114,7 @@ impl CppGenerator {
         match stmt {
             Stmt::Print(expr) => {
                 self.indent();
-                match expr {
+                match &expr.node {
                     Expr::String(s) => {
                         // Usar printf para ASM limpio
                         self.output.push_str(&format!("printf(\"{}\\n\");\n", s.replace('"', "\\\"")));
@@ -152,7 +152,7 @@ impl CppGenerator {
             Stmt::Let { name, value, .. } => {
                 self.indent();
                 // Determinar tipo basado en el valor
-                match value {
+                match &value.node {
                     Expr::ArrayLiteral(elements) => {
                         if elements.is_empty() {
                             // Array vacío: std::vector<int64_t> arr;
@@ -257,12 +257,12 @@ impl CppGenerator {
                 self.indent();
                 self.output.push_str("}\n");
             }
-            Stmt::Expr(Expr::Assign { name, value }) => {
+            Stmt::Expr(Spanned { node: Expr::Assign { name, value }, .. }) => {
                 self.indent();
                 // Verificar si es asignación a índice de array: arr[0] = value
                 if name == "_array_set" {
-                    if let Expr::BinaryOp { left, right, .. } = value.as_ref() {
-                        if let Expr::Index { array, index } = left.as_ref() {
+                    if let Expr::BinaryOp { left, right, .. } = &value.node {
+                        if let Expr::Index { array, index } = &left.node {
                             let array_code = self.generate_expr(array);
                             let index_code = self.generate_expr(index);
                             let value_code = self.generate_expr(right);
@@ -295,7 +295,7 @@ impl CppGenerator {
             }
             Stmt::Expr(expr) => {
                 // Manejar MethodCall - por ahora simplificado para OOP básico
-                if let Expr::MethodCall { object: _, method, args: _ } = expr {
+                if let Expr::MethodCall { object: _, method, args: _ } = &expr.node {
                     // TODO: Implementar métodos de arrays cuando sea necesario
                     if method == "sort" || method == "reverse" {
                         self.indent();
@@ -307,7 +307,7 @@ impl CppGenerator {
                 self.indent();
                 let expr_code = self.generate_expr(expr);
                 // Si es MethodCall (como arr.append()), generar como statement con punto y coma
-                if matches!(expr, Expr::MethodCall { .. }) {
+                if matches!(&expr.node, Expr::MethodCall { .. }) {
                     self.output.push_str(&format!("{};\n", expr_code));
                 }
                 // Otras expresiones se evalúan pero no se usan (pueden tener side effects)
@@ -325,13 +325,32 @@ impl CppGenerator {
                 // Generar clase C++ desde struct ADead
                 self.generate_class(name, fields, init, destroy);
             }
-            Stmt::For { var, start, end, body } => {
-                // For loop: for (int64_t var = start; var < end; var++)
+            Stmt::For { vars, iter, body } => {
                 self.indent();
-                let start_code = self.generate_expr(start);
-                let end_code = self.generate_expr(end);
-                self.output.push_str(&format!("for (int64_t {} = {}; {} < {}; {}++) {{\n", 
-                    var, start_code, var, end_code, var));
+                let var = vars.join(", ");
+                match iter {
+                    ForIter::Range { start, end, step: None } => {
+                        // For loop: for (int64_t var = start; var < end; var++)
+                        let start_code = self.generate_expr(start);
+                        let end_code = self.generate_expr(end);
+                        self.output.push_str(&format!("for (int64_t {} = {}; {} < {}; {}++) {{\n", 
+                            var, start_code, var, end_code, var));
+                    }
+                    ForIter::Range { start, end, step: Some(step) } => {
+                        // range(start, end, step): el sentido depende del signo de step
+                        let start_code = self.generate_expr(start);
+                        let end_code = self.generate_expr(end);
+                        let step_code = self.generate_expr(step);
+                        self.output.push_str(&format!("for (int64_t {v} = {}; ({s}) > 0 ? {v} < {e} : {v} > {e}; {v} += ({s})) {{\n",
+                            start_code, v = var, e = end_code, s = step_code));
+                    }
+                    ForIter::Iterable(iterable) => {
+                        // Range-based for de C++ (structured bindings para destructurar)
+                        let iter_code = self.generate_expr(iterable);
+                        let binding = if vars.len() > 1 { format!("[{}]", var) } else { var };
+                        self.output.push_str(&format!("for (auto&& {} : {}) {{\n", binding, iter_code));
+                    }
+                }
                 self.indent_level += 1;
                 for stmt in body {
                     self.generate_stmt(stmt);
@@ -421,7 +440,7 @@ impl CppGenerator {
     /// Generar statement dentro de un constructor (maneja self.campo = valor)
     fn generate_constructor_stmt(&mut self, stmt: &Stmt) {
         match stmt {
-            Stmt::Expr(Expr::Assign { name, value }) => {
+            Stmt::Expr(Spanned { node: Expr::Assign { name, value }, .. }) => {
                 self.indent();
                 // Convertir self.campo a this->campo (o simplemente campo en C++)
                 let cpp_name = if name.starts_with("self.") {
@@ -456,6 +475,9 @@ impl CppGenerator {
                 if matches!(op, BinOp::FloorDiv) {
                     return format!("({} / {})", left_code, right_code);
                 }
+                if matches!(op, BinOp::In) {
+                    return format!("0 /* TODO: {} in {} */", left_code, right_code);
+                }
                 
                 let op_str = match op {
                     BinOp::Add => "+",
@@ -471,7 +493,7 @@ impl CppGenerator {
                     BinOp::Ge => ">=",
                     BinOp::And => "&&",
                     BinOp::Or => "||",
-                    BinOp::Pow | BinOp::FloorDiv => unreachable!(),
+                    BinOp::Pow | BinOp::FloorDiv | BinOp::In => unreachable!(),
                 };
                 format!("({} {} {})", left_code, op_str, right_code)
             }
@@ -585,14 +607,20 @@ impl CppGenerator {
                 // C++: arr[index] (directo, sin función helper)
                 format!("{}[{}]", array_code, index_code)
             }
-            Expr::Slice { object, start, end } => {
+            Expr::Slice { object, start, end, .. } => {
                 // Slicing de strings: s[0:4] -> s.substr(0, 4)
                 // En C++, substr(start, length) donde length = end - start
+                // Sin inicio se parte de 0 y sin fin se toma hasta el final (el paso no se traduce)
                 let obj_code = self.generate_expr(object);
-                let start_code = self.generate_expr(start);
-                let end_code = self.generate_expr(end);
-                // Generar: obj.substr(start, end - start)
-                format!("{}.substr({}, {} - {})", obj_code, start_code, end_code, start_code)
+                let start_code = start.as_ref().map_or_else(|| "0".to_string(), |start| self.generate_expr(start));
+                match end {
+                    // Generar: obj.substr(start, end - start)
+                    Some(end) => {
+                        let end_code = self.generate_expr(end);
+                        format!("{}.substr({}, {} - {})", obj_code, start_code, end_code, start_code)
+                    }
+                    None => format!("{}.substr({})", obj_code, start_code),
+                }
             }
             Expr::Assign { name, value } => {
                 let value_code = self.generate_expr(value);
@@ -659,7 +687,7 @@ impl CppGenerator {
                 // Concatenación de strings: si al menos uno de los operandos es string,
                 // asumimos que es concatenación de strings (C++ permite string + string)
                 // Verificamos ambos lados para mayor precisión
-                match (left.as_ref(), right.as_ref()) {
+                match (&left.node, &right.node) {
                     (Expr::String(_), _) | (_, Expr::String(_)) => true,
                     (Expr::Ident(_), Expr::Ident(_)) => {
                         // Si ambos son identificadores, verificamos si alguno parece string
@@ -715,7 +743,7 @@ mod tests {
     fn test_generate_simple_print() {
         let program = Program {
             statements: vec![
-                Stmt::Print(Expr::String("Hello, World!".to_string())),
+                Stmt::Print(Expr::String("Hello, World!".to_string()).into()).into(),
             ],
         };
         let cpp_code = generate_cpp_code(&program);
@@ -730,12 +758,13 @@ mod tests {
                 Stmt::Let {
                     mutable: false,
                     name: "arr".to_string(),
+                    ty: None,
                     value: Expr::ArrayLiteral(vec![
-                        Expr::Number(1),
-                        Expr::Number(2),
-                        Expr::Number(3),
-                    ]),
-                },
+                        Expr::Number(1).into(),
+                        Expr::Number(2).into(),
+                        Expr::Number(3).into(),
+                    ]).into(),
+                }.into(),
             ],
         };
         let cpp_code = generate_cpp_code(&program);
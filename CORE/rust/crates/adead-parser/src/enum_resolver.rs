@@ -0,0 +1,303 @@
+//! Resolución de enums de usuario (post-procesador del parser)
+//!
+//! El parser no sabe al leer `Shape.Circle(5)` si `Shape` es un enum o una variable
+//! con un método `Circle`: lo parsea como `MethodCall` (o `FieldAccess` si no lleva
+//! argumentos). Una vez parseado el programa completo se conocen todos los `enum`, y
+//! esta pasada reescribe esos accesos como `Expr::EnumVariant`.
+//!
+//! En los patrones de match se completa el enum de las variantes sin calificar
+//! (`Circle(r)`, `Empty`) y se expanden `Rect` a `Rect(_, _)`. También se verifica
+//! que cada variante exista y reciba tantos valores como campos declara.
+
+use crate::{ComprehensionClause, EnumVariant, Expr, ForIter, FStringPart, MatchArm, Pattern, Program, Spanned, Stmt, StructMethod};
+use adead_common::{ADeadError, Result};
+use std::collections::HashMap;
+
+/// Reescribir construcciones y patrones de variantes de enum en todo el programa
+pub(crate) fn resolve_enum_variants(program: &mut Program) -> Result<()> {
+    let mut enums: HashMap<String, Vec<EnumVariant>> = HashMap::new();
+    for stmt in &program.statements {
+        if let Stmt::Enum { name, variants } = &stmt.node {
+            enums.insert(name.clone(), variants.clone());
+        }
+    }
+    if enums.is_empty() {
+        return Ok(());
+    }
+    let resolver = EnumResolver { enums };
+    resolver.resolve_stmts(&mut program.statements)
+}
+
+struct EnumResolver {
+    enums: HashMap<String, Vec<EnumVariant>>,
+}
+
+fn enum_error(message: String) -> ADeadError {
+    ADeadError::TypeError { message }
+}
+
+impl EnumResolver {
+    fn variant(&self, enum_name: &str, variant: &str) -> Result<&EnumVariant> {
+        self.enums[enum_name]
+            .iter()
+            .find(|v| v.name == variant)
+            .ok_or_else(|| enum_error(format!("el enum '{}' no tiene la variante '{}'", enum_name, variant)))
+    }
+
+    /// Enum al que pertenece una variante sin calificar (`None` si no es de ningún enum)
+    fn owner_of(&self, variant: &str) -> Result<Option<String>> {
+        let owners: Vec<&String> = self
+            .enums
+            .iter()
+            .filter(|(_, variants)| variants.iter().any(|v| v.name == variant))
+            .map(|(name, _)| name)
+            .collect();
+        match owners.as_slice() {
+            [] => Ok(None),
+            [owner] => Ok(Some((*owner).clone())),
+            _ => {
+                let mut names: Vec<&str> = owners.iter().map(|name| name.as_str()).collect();
+                names.sort();
+                Err(enum_error(format!(
+                    "la variante '{}' es ambigua (existe en {}): califícala con el nombre del enum",
+                    variant,
+                    names.join(", ")
+                )))
+            }
+        }
+    }
+
+    fn resolve_stmts(&self, stmts: &mut [Spanned<Stmt>]) -> Result<()> {
+        for stmt in stmts.iter_mut() {
+            let span = stmt.span;
+            self.resolve_stmt(&mut stmt.node).map_err(|e| e.at(span))?;
+        }
+        Ok(())
+    }
+
+    fn resolve_stmt(&self, stmt: &mut Stmt) -> Result<()> {
+        match stmt {
+            Stmt::Print(expr) | Stmt::Expr(expr) | Stmt::Let { value: expr, .. } | Stmt::Return(Some(expr)) => {
+                self.resolve_expr(expr)
+            }
+            Stmt::MultiLet { values, .. } => self.resolve_exprs(values),
+            Stmt::If { condition, then_body, else_body } => {
+                self.resolve_expr(condition)?;
+                self.resolve_stmts(then_body)?;
+                if let Some(else_body) = else_body {
+                    self.resolve_stmts(else_body)?;
+                }
+                Ok(())
+            }
+            Stmt::While { condition, body } => {
+                self.resolve_expr(condition)?;
+                self.resolve_stmts(body)
+            }
+            Stmt::For { iter, body, .. } => {
+                match iter {
+                    ForIter::Range { start, end, step } => {
+                        self.resolve_expr(start)?;
+                        self.resolve_expr(end)?;
+                        if let Some(step) = step {
+                            self.resolve_expr(step)?;
+                        }
+                    }
+                    ForIter::Iterable(iterable) => self.resolve_expr(iterable)?,
+                }
+                self.resolve_stmts(body)
+            }
+            Stmt::Fn { params, body, .. } => {
+                for default in params.iter_mut().filter_map(|p| p.default_value.as_mut()) {
+                    self.resolve_expr(default)?;
+                }
+                self.resolve_stmts(body)
+            }
+            Stmt::Struct { init, destroy, methods, .. } => {
+                let bodies = init
+                    .iter_mut()
+                    .chain(destroy.iter_mut())
+                    .chain(methods.iter_mut().map(|(_, method)| method));
+                self.resolve_methods(bodies)
+            }
+            Stmt::Impl { methods, .. } => self.resolve_methods(methods.iter_mut().map(|(_, method)| method)),
+            Stmt::Return(None)
+            | Stmt::Break
+            | Stmt::Continue
+            | Stmt::Enum { .. }
+            | Stmt::Trait { .. }
+            | Stmt::Import(_) => Ok(()),
+        }
+    }
+
+    fn resolve_methods<'m>(&self, bodies: impl Iterator<Item = &'m mut StructMethod>) -> Result<()> {
+        for method in bodies {
+            for default in method.params.iter_mut().filter_map(|p| p.default_value.as_mut()) {
+                self.resolve_expr(default)?;
+            }
+            self.resolve_stmts(&mut method.body)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_exprs(&self, exprs: &mut [Spanned<Expr>]) -> Result<()> {
+        exprs.iter_mut().try_for_each(|expr| self.resolve_expr(expr))
+    }
+
+    /// Los errores se ubican en la subexpresión más interna donde ocurrieron
+    fn resolve_expr(&self, expr: &mut Spanned<Expr>) -> Result<()> {
+        let span = expr.span;
+        self.resolve_node(&mut expr.node).map_err(|e| e.at(span))
+    }
+
+    fn resolve_node(&self, expr: &mut Expr) -> Result<()> {
+        // Shape.Circle(5) → MethodCall sobre `Shape`; Shape.Empty → FieldAccess
+        let rewrite = match expr {
+            Expr::MethodCall { object, method, args } => match &object.node {
+                Expr::Ident(name) if self.enums.contains_key(name) => {
+                    Some((name.clone(), method.clone(), std::mem::take(args)))
+                }
+                _ => None,
+            },
+            Expr::FieldAccess { object, field } => match &object.node {
+                Expr::Ident(name) if self.enums.contains_key(name) => Some((name.clone(), field.clone(), Vec::new())),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some((enum_name, variant, args)) = rewrite {
+            let expected = self.variant(&enum_name, &variant)?.fields.len();
+            if args.len() != expected {
+                return Err(enum_error(format!(
+                    "la variante '{}.{}' espera {} valor(es), se pasaron {}",
+                    enum_name,
+                    variant,
+                    expected,
+                    args.len()
+                )));
+            }
+            *expr = Expr::EnumVariant { enum_name, variant, args };
+        }
+
+        match expr {
+            Expr::Number(_) | Expr::Float(_) | Expr::Bool(_) | Expr::String(_) | Expr::Ident(_) | Expr::None => Ok(()),
+            Expr::BinaryOp { left, right, .. } => {
+                self.resolve_expr(left)?;
+                self.resolve_expr(right)
+            }
+            Expr::Assign { value, .. } | Expr::CompoundAssign { value, .. } => self.resolve_expr(value),
+            Expr::Call { args, .. } | Expr::SuperCall { args, .. } | Expr::EnumVariant { args, .. } => {
+                self.resolve_exprs(args)
+            }
+            Expr::Borrow { expr, .. }
+            | Expr::Deref(expr)
+            | Expr::Some(expr)
+            | Expr::Ok(expr)
+            | Expr::Err(expr)
+            | Expr::PropagateError(expr)
+            | Expr::Not(expr)
+            | Expr::FieldAccess { object: expr, .. }
+            | Expr::Lambda { body: expr, .. } => self.resolve_expr(expr),
+            Expr::Match { expr, arms } => {
+                self.resolve_expr(expr)?;
+                arms.iter_mut().try_for_each(|arm| self.resolve_arm(arm))
+            }
+            Expr::StructLiteral { fields, .. } => fields.iter_mut().try_for_each(|(_, value)| self.resolve_expr(value)),
+            Expr::FieldAssign { object, value, .. } => {
+                self.resolve_expr(object)?;
+                self.resolve_expr(value)
+            }
+            Expr::MethodCall { object, args, .. } => {
+                self.resolve_expr(object)?;
+                self.resolve_exprs(args)
+            }
+            Expr::ArrayLiteral(items) | Expr::TupleLiteral(items) | Expr::SetLiteral(items) => {
+                self.resolve_exprs(items)
+            }
+            Expr::Index { array, index } => {
+                self.resolve_expr(array)?;
+                self.resolve_expr(index)
+            }
+            Expr::Slice { object, start, end, step } => {
+                self.resolve_expr(object)?;
+                [start, end, step].into_iter().flatten().try_for_each(|bound| self.resolve_expr(bound))
+            }
+            Expr::FString { parts } => parts.iter_mut().try_for_each(|part| match part {
+                FStringPart::Expr(expr) => self.resolve_expr(expr),
+                FStringPart::Literal(_) => Ok(()),
+            }),
+            Expr::ListComprehension { expr, clauses } | Expr::SetComprehension { expr, clauses } => {
+                self.resolve_expr(expr)?;
+                self.resolve_clauses(clauses)
+            }
+            Expr::DictLiteral { pairs } => pairs.iter_mut().try_for_each(|(key, value)| {
+                self.resolve_expr(key)?;
+                self.resolve_expr(value)
+            }),
+            Expr::Ternary { condition, then_expr, else_expr } => {
+                self.resolve_expr(condition)?;
+                self.resolve_expr(then_expr)?;
+                self.resolve_expr(else_expr)
+            }
+            Expr::DictComprehension { key_expr, value_expr, clauses } => {
+                self.resolve_expr(key_expr)?;
+                self.resolve_expr(value_expr)?;
+                self.resolve_clauses(clauses)
+            }
+        }
+    }
+
+    fn resolve_clauses(&self, clauses: &mut [ComprehensionClause]) -> Result<()> {
+        clauses.iter_mut().try_for_each(|clause| {
+            self.resolve_expr(&mut clause.iter)?;
+            clause.conditions.iter_mut().try_for_each(|c| self.resolve_expr(c))
+        })
+    }
+
+    fn resolve_arm(&self, arm: &mut MatchArm) -> Result<()> {
+        self.resolve_pattern(&mut arm.pattern)?;
+        if let Some(guard) = &mut arm.guard {
+            self.resolve_expr(guard)?;
+        }
+        self.resolve_expr(&mut arm.body)
+    }
+
+    fn resolve_pattern(&self, pattern: &mut Pattern) -> Result<()> {
+        // `Empty` sin calificar es una variante si algún enum la declara
+        if let Pattern::Ident(name) = pattern {
+            if let Some(enum_name) = self.owner_of(name)? {
+                *pattern = Pattern::Variant { enum_name, variant: name.clone(), fields: Vec::new() };
+            }
+        }
+        match pattern {
+            Pattern::Variant { enum_name, variant, fields } => {
+                if enum_name.is_empty() {
+                    *enum_name = self
+                        .owner_of(variant)?
+                        .ok_or_else(|| enum_error(format!("'{}' no es una variante de ningún enum", variant)))?;
+                } else if !self.enums.contains_key(enum_name.as_str()) {
+                    return Err(enum_error(format!("enum '{}' no definido en patrón", enum_name)));
+                }
+                let expected = self.variant(enum_name, variant)?.fields.len();
+                // `Rect` sin paréntesis equivale a `Rect(_, _)`
+                if fields.is_empty() {
+                    fields.resize(expected, Pattern::Wildcard);
+                }
+                if fields.len() != expected {
+                    return Err(enum_error(format!(
+                        "la variante '{}.{}' tiene {} campo(s) pero el patrón tiene {}",
+                        enum_name,
+                        variant,
+                        expected,
+                        fields.len()
+                    )));
+                }
+                fields.iter_mut().try_for_each(|field| self.resolve_pattern(field))
+            }
+            Pattern::Some(inner) | Pattern::Ok(inner) | Pattern::Err(inner) => self.resolve_pattern(inner),
+            Pattern::Binding { pattern, .. } => self.resolve_pattern(pattern),
+            Pattern::Tuple(items) | Pattern::Or(items) => items.iter_mut().try_for_each(|item| self.resolve_pattern(item)),
+            Pattern::Struct { fields, .. } => fields.iter_mut().try_for_each(|(_, field)| self.resolve_pattern(field)),
+            _ => Ok(()),
+        }
+    }
+}
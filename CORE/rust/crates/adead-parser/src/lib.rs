@@ -1,6 +1,9 @@
-use adead_common::{ADeadError, Result};
+use adead_common::log::{self, Phase};
+use adead_common::{log_debug, log_trace, ADeadError, Diagnostic, Result, Type};
+// Spans: cada Stmt del AST lleva su ubicación en el código fuente
+pub use adead_common::{Span, Spanned};
+use chumsky::error::SimpleReason;
 use chumsky::prelude::*;
-use std::io::{self, Write};
 
 // ═══════════════════════════════════════════════════════════════════════════
 // ADead Parser - Compilador de ADead a NASM (x86_64)
@@ -22,6 +25,9 @@ pub mod c_while_if_parser;
 // Resolución de módulos (import básico)
 pub mod module_resolver;
 
+// Resolución de variantes de enums de usuario (post-procesador)
+mod enum_resolver;
+
 // Selector de pipeline (prioriza NASM directo)
 pub mod pipeline_selector;
 
@@ -48,122 +54,123 @@ pub enum Expr {
     Ident(String),
     BinaryOp {
         op: BinOp,
-        left: Box<Expr>,
-        right: Box<Expr>,
+        left: Box<Spanned<Expr>>,
+        right: Box<Spanned<Expr>>,
     },
     Assign {
         name: String,
-        value: Box<Expr>,
+        value: Box<Spanned<Expr>>,
     },
     Call {
         module: Option<String>,  // None = función local, Some("math") = math.factorial (Sprint 1.3)
         name: String,
-        args: Vec<Expr>,
+        args: Vec<Spanned<Expr>>,
     },
     // Ownership y Borrowing (O0.2)
     Borrow {
-        expr: Box<Expr>,
+        expr: Box<Spanned<Expr>>,
         mutable: bool,  // false = &T, true = &mut T
     },
-    Deref(Box<Expr>),  // *expr para dereferenciar
+    Deref(Box<Spanned<Expr>>),  // *expr para dereferenciar
     
     // Option y Result Types (O0.4)
-    Some(Box<Expr>),           // Some(value)
+    Some(Box<Spanned<Expr>>),           // Some(value)
     None,                      // None (para Option)
-    Ok(Box<Expr>),             // Ok(value)
-    Err(Box<Expr>),            // Err(error)
+    Ok(Box<Spanned<Expr>>),             // Ok(value)
+    Err(Box<Spanned<Expr>>),            // Err(error)
     Match {                     // match expr { pattern => body, ... }
-        expr: Box<Expr>,
+        expr: Box<Spanned<Expr>>,
         arms: Vec<MatchArm>,
     },
-    PropagateError(Box<Expr>), // expr? - Propaga error automáticamente
+    PropagateError(Box<Spanned<Expr>>), // expr? - Propaga error automáticamente
     // Structs/Clases (Fase 1.2 - O1)
     StructLiteral {             // StructName { field1: value1, field2: value2 }
         name: String,
-        fields: Vec<(String, Expr)>,  // (field_name, value)
+        fields: Vec<(String, Spanned<Expr>)>,  // (field_name, value)
+    },
+    // Enums de usuario (tagged unions)
+    EnumVariant {               // Shape.Circle(5) o Shape.Empty
+        enum_name: String,
+        variant: String,
+        args: Vec<Spanned<Expr>>,        // payload (vacío en variantes sin datos)
     },
     FieldAccess {               // expr.field_name
-        object: Box<Expr>,
+        object: Box<Spanned<Expr>>,
         field: String,
     },
     FieldAssign {               // expr.field_name = value
-        object: Box<Expr>,
+        object: Box<Spanned<Expr>>,
         field: String,
-        value: Box<Expr>,
+        value: Box<Spanned<Expr>>,
     },
     MethodCall {                // expr.method_name(args)
-        object: Box<Expr>,
+        object: Box<Spanned<Expr>>,
         method: String,
-        args: Vec<Expr>,
+        args: Vec<Spanned<Expr>>,
     },
     SuperCall {                 // super.method_name(args) - llamada a método del padre
         method: String,
-        args: Vec<Expr>,
+        args: Vec<Spanned<Expr>>,
     },
     // Arrays (Sprint 1.2)
-    ArrayLiteral(Vec<Expr>),    // [1, 2, 3]
+    ArrayLiteral(Vec<Spanned<Expr>>),    // [1, 2, 3]
     Index {                     // arr[0]
-        array: Box<Expr>,
-        index: Box<Expr>,
+        array: Box<Spanned<Expr>>,
+        index: Box<Spanned<Expr>>,
     },
     // Strings (Sprint 2)
-    Slice {                     // s[0:4]
-        object: Box<Expr>,
-        start: Box<Expr>,
-        end: Box<Expr>,
+    Slice {                     // s[0:4], s[::-1], s[-3:] (límites y paso opcionales)
+        object: Box<Spanned<Expr>>,
+        start: Option<Box<Spanned<Expr>>>,
+        end: Option<Box<Spanned<Expr>>>,
+        step: Option<Box<Spanned<Expr>>>,
     },
     // Operadores lógicos (Prioridad 2)
-    Not(Box<Expr>),             // !expr - Negación lógica
+    Not(Box<Spanned<Expr>>),             // !expr - Negación lógica
     // Operadores compuestos (Sprint 1 - Python-like)
     CompoundAssign {            // x += 5, x -= 3, x *= 2, x /= 4
         name: String,
         op: BinOp,              // Add, Sub, Mul, Div
-        value: Box<Expr>,
+        value: Box<Spanned<Expr>>,
     },
     // F-strings (Sprint 2.3 - Python-like)
     FString {                   // f"Hola {nombre}, tienes {edad} años"
         parts: Vec<FStringPart>,
     },
     // Tuplas (Sprint 2 - Python-like)
-    TupleLiteral(Vec<Expr>),    // (1, 2, 3) o (a, b)
+    TupleLiteral(Vec<Spanned<Expr>>),    // (1, 2, 3) o (a, b)
     // Lambda expressions (Sprint 3 - Python-like)
     Lambda {                    // lambda x, y: x + y
         params: Vec<String>,
-        body: Box<Expr>,
+        body: Box<Spanned<Expr>>,
     },
     // List comprehension (Sprint 4 - Python-like)
     ListComprehension {         // [x * 2 for x in lista]
-        expr: Box<Expr>,        // expresión a evaluar
-        var: String,            // variable de iteración
-        iter: Box<Expr>,        // iterable
-        condition: Option<Box<Expr>>, // condición opcional (if)
+        expr: Box<Spanned<Expr>>,        // expresión a evaluar
+        clauses: Vec<ComprehensionClause>, // cláusulas `for ... in ... if ...` (al menos una)
     },
     // Diccionarios (Sprint 2 - Python-like)
     DictLiteral {               // {"key": value, "key2": value2}
-        pairs: Vec<(Expr, Expr)>,  // (key, value) pairs
+        pairs: Vec<(Spanned<Expr>, Spanned<Expr>)>,  // (key, value) pairs
     },
     // Sets (Sprint 2 - Python-like)
-    SetLiteral(Vec<Expr>),      // {1, 2, 3}
+    SetLiteral(Vec<Spanned<Expr>>),      // {1, 2, 3}
     // Operador ternario (Sprint 5 - Python-like)
     Ternary {                   // x if condition else y
-        condition: Box<Expr>,
-        then_expr: Box<Expr>,
-        else_expr: Box<Expr>,
+        condition: Box<Spanned<Expr>>,
+        then_expr: Box<Spanned<Expr>>,
+        else_expr: Box<Spanned<Expr>>,
     },
     // Dict comprehension (Sprint 4 - Python-like)
-    DictComprehension {         // {k: v for k, v in items}
-        key_expr: Box<Expr>,
-        value_expr: Box<Expr>,
-        var: String,
-        iter: Box<Expr>,
-        condition: Option<Box<Expr>>,
+    DictComprehension {         // {k: k * 2 for k in lista}
+        key_expr: Box<Spanned<Expr>>,
+        value_expr: Box<Spanned<Expr>>,
+        clauses: Vec<ComprehensionClause>,
     },
     // Set comprehension (Sprint 4 - Python-like)
     SetComprehension {          // {x * 2 for x in lista}
-        expr: Box<Expr>,
-        var: String,
-        iter: Box<Expr>,
-        condition: Option<Box<Expr>>,
+        expr: Box<Spanned<Expr>>,
+        clauses: Vec<ComprehensionClause>,
     },
 }
 
@@ -171,7 +178,7 @@ pub enum Expr {
 #[derive(Debug, Clone, PartialEq)]
 pub enum FStringPart {
     Literal(String),    // Texto literal: "Hola "
-    Expr(Box<Expr>),    // Expresión interpolada: {nombre}
+    Expr(Box<Spanned<Expr>>),    // Expresión interpolada: {nombre}
 }
 
 /// Par├ímetro de funci├│n con informaci├│n de borrowing
@@ -179,7 +186,8 @@ pub enum FStringPart {
 pub struct FnParam {
     pub name: String,
     pub borrow_type: BorrowType,  // Tipo de borrowing del par├ímetro
-    pub default_value: Option<Box<Expr>>,  // Valor por defecto (Sprint 3 - Python-like)
+    pub ty: Option<Type>,  // Anotación opcional: `x: int32`
+    pub default_value: Option<Box<Spanned<Expr>>>,  // Valor por defecto (Sprint 3 - Python-like)
 }
 
 /// Tipo de borrowing para par├ímetros de funci├│n
@@ -193,21 +201,140 @@ pub enum BorrowType {
 /// Patr├│n para match expressions (O0.4)
 #[derive(Debug, Clone, PartialEq)]
 pub enum Pattern {
-    Some,       // Some(_)
-    None,       // None
-    Ok,         // Ok(_)
-    Err,        // Err(_)
+    Some(Box<Pattern>),  // Some(p) - `Some` solo equivale a Some(_)
+    None,                // None
+    Ok(Box<Pattern>),    // Ok(p)
+    Err(Box<Pattern>),   // Err(p)
     Ident(String), // Variable binding: x
-    LiteralNumber(i64), // 42
+    LiteralNumber(i64), // 42, -1
     LiteralString(String), // "hello"
+    LiteralBool(bool),  // true / false
+    Range { start: i64, end: i64, inclusive: bool },  // 1..5 (exclusivo) o 1..=5
+    Tuple(Vec<Pattern>),  // (a, 0, _)
+    Struct { name: String, fields: Vec<(String, Pattern)> },  // Punto { x, y: 0, .. }
+    Variant { enum_name: String, variant: String, fields: Vec<Pattern> },  // Shape.Rect(w, _) o Empty
+    Or(Vec<Pattern>),     // 1 | 2 | 3
+    Binding { name: String, pattern: Box<Pattern> },  // n @ 1..=9
     Wildcard,   // _ (catch-all)
 }
 
+impl Pattern {
+    /// Variables que enlaza el patrón (en orden de aparición, sin repetir)
+    pub fn bindings(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_bindings(&mut names);
+        names
+    }
+
+    fn collect_bindings<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Pattern::Ident(name) => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+            Pattern::Binding { name, pattern } => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+                pattern.collect_bindings(names);
+            }
+            Pattern::Some(inner) | Pattern::Ok(inner) | Pattern::Err(inner) => inner.collect_bindings(names),
+            Pattern::Tuple(items) | Pattern::Or(items) | Pattern::Variant { fields: items, .. } => {
+                for item in items {
+                    item.collect_bindings(names);
+                }
+            }
+            Pattern::Struct { fields, .. } => {
+                for (_, field) in fields {
+                    field.collect_bindings(names);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// ¿Encaja con cualquier valor de su tipo? (`_`, `x`, tuplas y structs de irrefutables)
+    pub fn is_irrefutable(&self) -> bool {
+        match self {
+            Pattern::Wildcard | Pattern::Ident(_) => true,
+            Pattern::Binding { pattern, .. } => pattern.is_irrefutable(),
+            Pattern::Tuple(items) => items.iter().all(Pattern::is_irrefutable),
+            Pattern::Struct { fields, .. } => fields.iter().all(|(_, p)| p.is_irrefutable()),
+            Pattern::Or(alternatives) => alternatives.iter().any(Pattern::is_irrefutable),
+            _ => false,
+        }
+    }
+}
+
+/// Cláusula de una comprehension: `for var in iter if cond if cond2 ...`
+/// Varias cláusulas se anidan de izquierda a derecha: `[(x, y) for x in a for y in b if x != y]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComprehensionClause {
+    pub var: String,
+    pub iter: Box<Spanned<Expr>>,
+    pub conditions: Vec<Spanned<Expr>>,  // filtros `if`: se deben cumplir todos
+}
+
+/// Lo que recorre un `for`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForIter {
+    /// `start..end` o `range(start, end, step)` (fin exclusivo); un `step` negativo recorre hacia atrás
+    Range { start: Spanned<Expr>, end: Spanned<Expr>, step: Option<Spanned<Expr>> },
+    /// Un iterable: array, string (por carácter), dict (claves), set,
+    /// `d.items()`, `enumerate(a)` o `zip(a, b)`
+    Iterable(Spanned<Expr>),
+}
+
+impl ForIter {
+    /// `range(...)` (1 a 3 argumentos, como en Python) es un rango; cualquier otra
+    /// expresión es un iterable
+    pub fn from_iterable(iter: Spanned<Expr>) -> ForIter {
+        let span = iter.span;
+        match iter.node {
+            Expr::Call { module: None, name, args } if name == "range" && (1..=3).contains(&args.len()) => {
+                let mut args = args.into_iter();
+                let first = args.next().unwrap();
+                match args.next() {
+                    Some(end) => ForIter::Range { start: first, end, step: args.next() },
+                    None => ForIter::Range { start: Spanned::dummy(Expr::Number(0)), end: first, step: None },
+                }
+            }
+            iter => ForIter::Iterable(Spanned::new(iter, span)),
+        }
+    }
+
+    /// Expresiones que el `for` evalúa antes de iterar
+    pub fn exprs(&self) -> Vec<&Spanned<Expr>> {
+        match self {
+            ForIter::Range { start, end, step } => [Some(start), Some(end), step.as_ref()].into_iter().flatten().collect(),
+            ForIter::Iterable(iterable) => vec![iterable],
+        }
+    }
+
+    /// Colecciones que recorre el `for` y si además numera los elementos:
+    /// `enumerate(a)` → ([a], true), `zip(a, b)` → ([a, b], false), otro → ([iterable], false).
+    /// Vacío para los rangos
+    pub fn sources(&self) -> (Vec<&Spanned<Expr>>, bool) {
+        match self {
+            ForIter::Range { .. } => (Vec::new(), false),
+            ForIter::Iterable(Spanned { node: Expr::Call { module: None, name, args }, .. }) if name == "enumerate" && args.len() == 1 => {
+                (vec![&args[0]], true)
+            }
+            ForIter::Iterable(Spanned { node: Expr::Call { module: None, name, args }, .. }) if name == "zip" && args.len() == 2 => {
+                (args.iter().collect(), false)
+            }
+            ForIter::Iterable(iterable) => (vec![iterable], false),
+        }
+    }
+}
+
 /// Brazo de match expression (O0.4)
 #[derive(Debug, Clone, PartialEq)]
 pub struct MatchArm {
     pub pattern: Pattern,
-    pub body: Box<Expr>,
+    pub guard: Option<Box<Spanned<Expr>>>,  // `patrón if condición => ...`
+    pub body: Box<Spanned<Expr>>,
 }
 
 /// Nivel de visibilidad (O5 - Encapsulaci├│n)
@@ -229,7 +356,7 @@ pub struct StructField {
     pub visibility: Visibility,  // O5 - Visibilidad del campo
     pub mutable: bool,  // true = mut field, false = inmutable (por defecto)
     pub name: String,
-    pub ty: Option<String>,  // Tipo opcional (None = inferido)
+    pub ty: Option<Type>,  // Tipo opcional (None = inferido): `valor: T`, `items: [T]`
 }
 
 /// M├®todo de struct (O2 - Constructores y Destructores, O5 - Encapsulaci├│n)
@@ -237,7 +364,32 @@ pub struct StructField {
 pub struct StructMethod {
     pub visibility: Visibility,  // O5 - Visibilidad del m├®todo
     pub params: Vec<FnParam>,  // Par├ímetros del m├®todo
-    pub body: Vec<Stmt>,        // Cuerpo del m├®todo
+    pub return_type: Option<Type>,  // Anotación opcional: `-> int64`
+    pub body: Vec<Spanned<Stmt>>,        // Cuerpo del m├®todo
+}
+
+/// Variante de un enum: `Circle(radius)`, `Rect(w: float64, h: float64)` o `Empty`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant {
+    pub name: String,
+    pub fields: Vec<(String, Option<Type>)>,  // Payload: nombre y tipo opcional
+}
+
+/// Método requerido por un trait: `fn area(self) -> float64` (sin cuerpo)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitMethod {
+    pub name: String,
+    pub params: Vec<FnParam>,
+    pub return_type: Option<Type>,
+}
+
+/// Miembro dentro del cuerpo de `struct Nombre { ... }` (solo durante el parsing)
+#[derive(Debug, Clone)]
+enum StructMember {
+    Field(StructField),
+    Init(StructMethod),
+    Destroy(StructMethod),
+    Method(String, StructMethod),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -255,6 +407,7 @@ pub enum BinOp {
     Le,
     Gt,
     Ge,
+    In,   // x in s: pertenencia a un set o clave de un dict
     // Operadores lógicos (Prioridad 2)
     And,  // && - AND lógico con short-circuit
     Or,   // || - OR lógico con short-circuit
@@ -262,33 +415,33 @@ pub enum BinOp {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Stmt {
-    Print(Expr),
+    Print(Spanned<Expr>),
     Let {
         mutable: bool,  // true = let mut, false = let (inmutable)
         name: String,
-        value: Expr,
+        ty: Option<Type>,  // Anotación opcional: `let x: int8 = 5`
+        value: Spanned<Expr>,
     },
     // Múltiple asignación Python-style: let a, b = 1, 2
     MultiLet {
         mutable: bool,
         names: Vec<String>,
-        values: Vec<Expr>,
+        values: Vec<Spanned<Expr>>,
     },
     If {
-        condition: Expr,
-        then_body: Vec<Stmt>,
-        else_body: Option<Vec<Stmt>>,
+        condition: Spanned<Expr>,
+        then_body: Vec<Spanned<Stmt>>,
+        else_body: Option<Vec<Spanned<Stmt>>>,
     },
     While {
-        condition: Expr,
-        body: Vec<Stmt>,
+        condition: Spanned<Expr>,
+        body: Vec<Spanned<Stmt>>,
     },
-    // For loops (NASM-Universal.md - Patrones para Futuras Implementaciones)
+    // For loops: `for i in 0..n`, `for x in lista`, `for (i, x) in enumerate(a)`
     For {
-        var: String,        // Variable de iteración
-        start: Expr,        // Inicio del rango
-        end: Expr,          // Fin del rango (exclusivo)
-        body: Vec<Stmt>,
+        vars: Vec<String>,  // Variable(s) de iteración: varias con `(a, b)` para destructurar
+        iter: ForIter,      // Rango o iterable recorrido
+        body: Vec<Spanned<Stmt>>,
     },
     // Control de flujo en loops (NASM-Universal.md)
     Break,      // Salir del loop más cercano
@@ -296,27 +449,46 @@ pub enum Stmt {
     Fn {
         visibility: Visibility,  // Sprint 1.3 - Import básico: pub fn o fn (privada)
         name: String,
+        type_params: Vec<String>,  // Genéricos: `fn max_of<T>(a: T, b: T)`
         params: Vec<FnParam>,  // Cambiado para soportar borrowing
-        body: Vec<Stmt>,
+        return_type: Option<Type>,  // Anotación opcional: `fn f() -> int64`
+        body: Vec<Spanned<Stmt>>,
     },
     // Structs/Clases (Fase 1.2 - O1, O2 - RAII)
     Struct {
         name: String,
+        type_params: Vec<String>,  // Genéricos: `struct Caja<T> { valor: T }`
         parent: Option<String>,  // Herencia: extends Parent
         fields: Vec<StructField>,
         init: Option<StructMethod>,      // Constructor (O2)
         destroy: Option<StructMethod>,    // Destructor (O2.1 - Drop Trait)
         methods: Vec<(String, StructMethod)>, // Métodos de instancia
     },
-    Expr(Expr),
-    Return(Option<Expr>),
+    // Enums de usuario (tagged unions): enum Shape { Circle(radius), Rect(w, h), Empty }
+    Enum {
+        name: String,
+        variants: Vec<EnumVariant>,
+    },
+    // Traits (interfaces): trait Drawable { fn draw(self) -> int64 }
+    Trait {
+        name: String,
+        methods: Vec<TraitMethod>,  // Métodos requeridos (solo firma)
+    },
+    // Implementación de un trait: impl Drawable for Circle { fn draw(self) { ... } }
+    Impl {
+        trait_name: String,
+        type_name: String,
+        methods: Vec<(String, StructMethod)>,
+    },
+    Expr(Spanned<Expr>),
+    Return(Option<Spanned<Expr>>),
     // Import básico (Sprint 1.3)
     Import(String),  // import nombre_modulo
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
-    pub statements: Vec<Stmt>,
+    pub statements: Vec<Spanned<Stmt>>,
 }
 
 pub fn parse(source: &str) -> Result<Program> {
@@ -328,37 +500,19 @@ pub fn parse_with_dir(source: &str, current_dir: Option<&std::path::Path>) -> Re
     // NOTA: El preprocesador de structs con sintaxis 'end' está deshabilitado
     // Ahora usamos sintaxis con llaves {} que el parser maneja directamente
     
-    // DEBUG: Activar análisis del parser
-    #[cfg(feature = "parser-debug")]
-    {
-        use crate::parser_debug::ParserDebugger;
-        let debugger = ParserDebugger::new(true, true);
-        eprintln!("[PARSER-DEBUG] Iniciando parsing del código fuente...");
-    }
-    
     let parser = program_parser();
     
-    eprintln!("[PARSER-DEBUG] Iniciando parsing del código fuente ({} caracteres)", source.len());
-    eprintln!("[PARSER-DEBUG] Primeras líneas del código:");
+    log_debug!(Phase::Parser, "Iniciando parsing del código fuente ({} caracteres)", source.len());
     for (i, line) in source.lines().take(5).enumerate() {
-        eprintln!("[PARSER-DEBUG]   Línea {}: '{}'", i + 1, line);
+        log_trace!(Phase::Parser, "  Línea {}: '{}'", i + 1, line);
     }
-    io::stderr().flush().ok();
     
-    // Intentar parsear con manejo detallado de errores
-    eprintln!("[PARSER-DEBUG] Llamando a parser.parse()...");
-    io::stderr().flush().ok();
-    
-    let parse_result = parser.parse(source);
-    
-    eprintln!("[PARSER-DEBUG] parser.parse() retornó resultado");
-    io::stderr().flush().ok();
+    // Comentarios y BOM → espacios (mismo número de caracteres, así los spans siguen
+    // apuntando al código original) para que se permitan en cualquier lugar, incluso dentro de bloques
+    let parse_result = parser.parse(blank_comments(source));
     
     match parse_result {
         Ok(mut program) => {
-            eprintln!("[PARSER-DEBUG] Parse exitoso (sin errores reportados)");
-            io::stderr().flush().ok();
-            
             // DEBUG: Analizar programa parseado
             #[cfg(feature = "parser-debug")]
             {
@@ -367,69 +521,307 @@ pub fn parse_with_dir(source: &str, current_dir: Option<&std::path::Path>) -> Re
                 debugger.analyze_parsed_program(&program, source);
             }
             
-            // Análisis siempre activo (sin feature flag) para debugging
-            eprintln!("[PARSER-INFO] Programa parseado: {} statements", program.statements.len());
-            let let_count = program.statements.iter().filter(|s| matches!(s, Stmt::Let { .. })).count();
-            let print_count = program.statements.iter().filter(|s| matches!(s, Stmt::Print(_))).count();
-            let fn_count = program.statements.iter().filter(|s| matches!(s, Stmt::Fn { .. })).count();
-            let struct_count = program.statements.iter().filter(|s| matches!(s, Stmt::Struct { .. })).count();
-            
-            eprintln!("[PARSER-INFO] Desglose: {} structs, {} funciones, {} let, {} print", 
-                struct_count, fn_count, let_count, print_count);
-            
-            // CRÍTICO: Si no se parseó nada, hay un problema grave
-            if program.statements.is_empty() {
-                eprintln!("[PARSER-ERROR] ⚠️⚠️⚠️  CRÍTICO: El parser retornó éxito pero NO parseó ningún statement!");
-                eprintln!("[PARSER-ERROR] Esto indica un problema grave en el parser o en el código fuente.");
-                eprintln!("[PARSER-ERROR] Primeras líneas del código fuente:");
-                for (i, line) in source.lines().take(10).enumerate() {
-                    eprintln!("[PARSER-ERROR]   Línea {}: {}", i + 1, line);
-                }
-                io::stderr().flush().ok();
+            if log::enabled(Phase::Parser, log::Level::Debug) {
+                let count = |pred: fn(&Stmt) -> bool| program.statements.iter().filter(|s| pred(&s.node)).count();
+                log_debug!(Phase::Parser, "Programa parseado: {} statements ({} structs, {} funciones, {} let, {} print)",
+                    program.statements.len(),
+                    count(|s| matches!(s, Stmt::Struct { .. })),
+                    count(|s| matches!(s, Stmt::Fn { .. })),
+                    count(|s| matches!(s, Stmt::Let { .. })),
+                    count(|s| matches!(s, Stmt::Print(_))));
             }
             
-            // Verificar si hay statements esperados pero no parseados
-            let expected_let_print = source.lines()
-                .filter(|line| {
-                    let trimmed = line.trim();
-                    trimmed.starts_with("let ") || trimmed.starts_with("print ")
-                })
-                .count();
-            
-            if expected_let_print > (let_count + print_count) {
-                eprintln!("[PARSER-WARNING] ⚠️  Se esperaban {} statements Let/Print pero solo se parsearon {}!", 
-                    expected_let_print, let_count + print_count);
-                eprintln!("[PARSER-WARNING] Posible problema: El parser puede estar deteniéndose después de funciones.");
-                io::stderr().flush().ok();
-            }
+            // Spans: quitar whitespace/comentarios que incluyen los parsers con .padded()
+            let char_to_byte = char_to_byte_table(source);
+            trim_spans(&mut program.statements, source, &char_to_byte);
             
             // POST-PROCESADOR: Resolver imports (Sprint 1.3)
             resolve_imports(&mut program, current_dir)?;
             
+            // POST-PROCESADOR: Shape.Circle(5) → Expr::EnumVariant, patrones de variantes
+            enum_resolver::resolve_enum_variants(&mut program)?;
+            
             Ok(program)
         }
         Err(errs) => {
-            eprintln!("[PARSER-ERROR] Error de parsing detectado:");
-            for (i, err) in errs.iter().enumerate() {
-                eprintln!("[PARSER-ERROR]   Error {}: {}", i + 1, err);
-            }
-            io::stderr().flush().ok();
+            log_debug!(Phase::Parser, "{} error(es) de sintaxis", errs.len());
             
-            // Mostrar contexto del código fuente donde falló
-            eprintln!("[PARSER-ERROR] Primeras líneas del código fuente:");
-            for (i, line) in source.lines().take(20).enumerate() {
-                eprintln!("[PARSER-ERROR]   Línea {}: {}", i + 1, line);
+            // Reportar todos los errores (la recuperación permite seguir después del primero)
+            let char_to_byte = char_to_byte_table(source);
+            let mut diagnostics: Vec<Diagnostic> = errs
+                .iter()
+                .map(|err| syntax_diagnostic(err, &char_to_byte))
+                .collect();
+            diagnostics.sort_by_key(|d| d.span.start);
+            diagnostics.dedup_by_key(|d| d.span.start);
+            Err(ADeadError::SyntaxErrors(diagnostics))
+        }
+    }
+}
+
+/// Convertir un error de chumsky en un diagnóstico con span en bytes,
+/// mensaje en español y la lista de tokens esperados
+fn syntax_diagnostic(err: &Simple<char>, char_to_byte: &[usize]) -> Diagnostic {
+    let to_byte = |offset: usize| char_to_byte[offset.min(char_to_byte.len() - 1)];
+    let span = Span::new(to_byte(err.span().start), to_byte(err.span().end));
+
+    let found = match err.found() {
+        Some(c) => describe_char(*c),
+        None => "fin de archivo".to_string(),
+    };
+    let message = match err.reason() {
+        SimpleReason::Custom(message) => message.clone(),
+        SimpleReason::Unclosed { delimiter, .. } => format!("delimitador {} sin cerrar", describe_char(*delimiter)),
+        SimpleReason::Unexpected => match err.label() {
+            Some(label) => format!("{} inesperado en {}", found, label),
+            None => format!("{} inesperado", found),
+        },
+    };
+
+    // Agrupar caracteres esperados: letras → identificador, dígitos → número
+    let mut expected: Vec<String> = Vec::new();
+    for token in err.expected() {
+        let hint = match token {
+            None => "fin de archivo".to_string(),
+            Some(c) if c.is_whitespace() => continue,
+            Some(c) if c.is_ascii_digit() => "número".to_string(),
+            Some(c) if c.is_alphabetic() || *c == '_' => "identificador".to_string(),
+            Some(c) => describe_char(*c),
+        };
+        if !expected.contains(&hint) {
+            expected.push(hint);
+        }
+    }
+    expected.sort();
+
+    Diagnostic::new(span, message).with_expected(expected)
+}
+
+fn describe_char(c: char) -> String {
+    match c {
+        '\n' => "salto de línea".to_string(),
+        c => format!("'{}'", c),
+    }
+}
+
+/// Reemplazar comentarios (`#` hasta fin de línea, fuera de strings) y el BOM UTF-8
+/// por espacios, conservando la cantidad de caracteres del código fuente
+fn blank_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut in_string = false;
+    let mut in_comment = false;
+    let mut escaped = false;
+    for c in source.chars() {
+        if in_comment {
+            if c == '\n' {
+                in_comment = false;
+                out.push(c);
+            } else {
+                out.push(' ');
+            }
+            continue;
+        }
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' || c == '\n' {
+                in_string = false;
+            }
+            out.push(c);
+            continue;
+        }
+        match c {
+            '#' => {
+                in_comment = true;
+                out.push(' ');
+            }
+            '\u{feff}' => out.push(' '),
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Tabla offset de caracteres → offset de bytes (los spans de chumsky sobre
+/// `&str` cuentan caracteres). Incluye una entrada final para el fin del texto.
+fn char_to_byte_table(source: &str) -> Vec<usize> {
+    source
+        .char_indices()
+        .map(|(byte, _)| byte)
+        .chain(std::iter::once(source.len()))
+        .collect()
+}
+
+/// Normalizar los spans de todos los statements y sus expresiones (recursivo):
+/// convertir offsets de caracteres a bytes y recortar whitespace/comentarios iniciales
+fn trim_spans(stmts: &mut [Spanned<Stmt>], source: &str, char_to_byte: &[usize]) {
+    let to_byte = |offset: usize| char_to_byte[offset.min(char_to_byte.len() - 1)];
+    let mut trim = |span: Span| Span::new(to_byte(span.start), to_byte(span.end)).trim(source);
+    for stmt in stmts.iter_mut() {
+        stmt.span = trim(stmt.span);
+        match &mut stmt.node {
+            Stmt::Print(expr) | Stmt::Expr(expr) | Stmt::Let { value: expr, .. } | Stmt::Return(Some(expr)) => {
+                map_expr_spans(expr, &mut trim);
+            }
+            Stmt::MultiLet { values, .. } => {
+                for value in values {
+                    map_expr_spans(value, &mut trim);
+                }
+            }
+            Stmt::If { condition, then_body, else_body } => {
+                map_expr_spans(condition, &mut trim);
+                trim_spans(then_body, source, char_to_byte);
+                if let Some(else_body) = else_body {
+                    trim_spans(else_body, source, char_to_byte);
+                }
+            }
+            Stmt::While { condition, body } => {
+                map_expr_spans(condition, &mut trim);
+                trim_spans(body, source, char_to_byte);
+            }
+            Stmt::For { iter, body, .. } => {
+                match iter {
+                    ForIter::Range { start, end, step } => {
+                        for bound in [Some(start), Some(end), step.as_mut()].into_iter().flatten() {
+                            map_expr_spans(bound, &mut trim);
+                        }
+                    }
+                    ForIter::Iterable(iterable) => map_expr_spans(iterable, &mut trim),
+                }
+                trim_spans(body, source, char_to_byte);
+            }
+            Stmt::Fn { params, body, .. } => {
+                for default in params.iter_mut().filter_map(|p| p.default_value.as_mut()) {
+                    map_expr_spans(default, &mut trim);
+                }
+                trim_spans(body, source, char_to_byte);
+            }
+            Stmt::Struct { init, destroy, methods, .. } => {
+                let bodies = init
+                    .iter_mut()
+                    .chain(destroy.iter_mut())
+                    .chain(methods.iter_mut().map(|(_, method)| method));
+                for method in bodies {
+                    for default in method.params.iter_mut().filter_map(|p| p.default_value.as_mut()) {
+                        map_expr_spans(default, &mut trim);
+                    }
+                    trim_spans(&mut method.body, source, char_to_byte);
+                }
+            }
+            Stmt::Impl { methods, .. } => {
+                for (_, method) in methods.iter_mut() {
+                    trim_spans(&mut method.body, source, char_to_byte);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Aplicar `f` al span de una expresión y de todas sus subexpresiones
+fn map_expr_spans(expr: &mut Spanned<Expr>, f: &mut dyn FnMut(Span) -> Span) {
+    expr.span = f(expr.span);
+    match &mut expr.node {
+        Expr::Number(_) | Expr::Float(_) | Expr::Bool(_) | Expr::String(_) | Expr::Ident(_) | Expr::None => {}
+        Expr::BinaryOp { left, right, .. } => {
+            map_expr_spans(left, f);
+            map_expr_spans(right, f);
+        }
+        Expr::Assign { value, .. } | Expr::CompoundAssign { value, .. } => map_expr_spans(value, f),
+        Expr::Call { args, .. }
+        | Expr::SuperCall { args, .. }
+        | Expr::EnumVariant { args, .. }
+        | Expr::ArrayLiteral(args)
+        | Expr::TupleLiteral(args)
+        | Expr::SetLiteral(args) => {
+            for arg in args {
+                map_expr_spans(arg, f);
+            }
+        }
+        Expr::Borrow { expr: inner, .. }
+        | Expr::Deref(inner)
+        | Expr::Some(inner)
+        | Expr::Ok(inner)
+        | Expr::Err(inner)
+        | Expr::PropagateError(inner)
+        | Expr::Not(inner)
+        | Expr::FieldAccess { object: inner, .. }
+        | Expr::Lambda { body: inner, .. } => map_expr_spans(inner, f),
+        Expr::Match { expr: scrutinee, arms } => {
+            map_expr_spans(scrutinee, f);
+            for arm in arms {
+                if let Some(guard) = &mut arm.guard {
+                    map_expr_spans(guard, f);
+                }
+                map_expr_spans(&mut arm.body, f);
+            }
+        }
+        Expr::StructLiteral { fields, .. } => {
+            for (_, value) in fields {
+                map_expr_spans(value, f);
+            }
+        }
+        Expr::FieldAssign { object, value, .. } => {
+            map_expr_spans(object, f);
+            map_expr_spans(value, f);
+        }
+        Expr::MethodCall { object, args, .. } => {
+            map_expr_spans(object, f);
+            for arg in args {
+                map_expr_spans(arg, f);
+            }
+        }
+        Expr::Index { array, index } => {
+            map_expr_spans(array, f);
+            map_expr_spans(index, f);
+        }
+        Expr::Slice { object, start, end, step } => {
+            map_expr_spans(object, f);
+            for bound in [start, end, step].into_iter().flatten() {
+                map_expr_spans(bound, f);
             }
-            io::stderr().flush().ok();
-            
-            let first = errs.first().unwrap();
-            // chumsky 0.9 uses usize for spans, approximate line/col
-            let (line, col) = (1, 1); // Simplified for MVP
-            Err(ADeadError::ParseError {
-                line,
-                col,
-                message: format!("{}", first),
-            })
+        }
+        Expr::FString { parts } => {
+            for part in parts {
+                if let FStringPart::Expr(inner) = part {
+                    map_expr_spans(inner, f);
+                }
+            }
+        }
+        Expr::ListComprehension { expr: inner, clauses } | Expr::SetComprehension { expr: inner, clauses } => {
+            map_expr_spans(inner, f);
+            map_clause_spans(clauses, f);
+        }
+        Expr::DictLiteral { pairs } => {
+            for (key, value) in pairs {
+                map_expr_spans(key, f);
+                map_expr_spans(value, f);
+            }
+        }
+        Expr::Ternary { condition, then_expr, else_expr } => {
+            map_expr_spans(condition, f);
+            map_expr_spans(then_expr, f);
+            map_expr_spans(else_expr, f);
+        }
+        Expr::DictComprehension { key_expr, value_expr, clauses } => {
+            map_expr_spans(key_expr, f);
+            map_expr_spans(value_expr, f);
+            map_clause_spans(clauses, f);
+        }
+    }
+}
+
+fn map_clause_spans(clauses: &mut [ComprehensionClause], f: &mut dyn FnMut(Span) -> Span) {
+    for clause in clauses {
+        map_expr_spans(&mut clause.iter, f);
+        for condition in &mut clause.conditions {
+            map_expr_spans(condition, f);
         }
     }
 }
@@ -450,7 +842,7 @@ fn resolve_imports(program: &mut Program, current_dir: Option<&std::path::Path>)
     let mut module_functions: HashMap<String, Vec<String>> = HashMap::new(); // Para detectar colisiones
     
     for stmt in &program.statements {
-        if let Stmt::Import(module_name) = stmt {
+        if let Stmt::Import(module_name) = &stmt.node {
             imports_to_resolve.push(module_name.clone());
         }
     }
@@ -470,7 +862,7 @@ fn resolve_imports(program: &mut Program, current_dir: Option<&std::path::Path>)
         // Filtrar solo funciones públicas (Sprint 1.3 - Import básico)
         let mut module_funcs = Vec::new();
         for stmt in module_program.statements {
-            match &stmt {
+            match &stmt.node {
                 Stmt::Fn { visibility, name, .. } => {
                     // Solo agregar funciones públicas
                     if *visibility == Visibility::Public {
@@ -496,7 +888,7 @@ fn resolve_imports(program: &mut Program, current_dir: Option<&std::path::Path>)
     let all_function_names: Vec<String> = program.statements
         .iter()
         .filter_map(|s| {
-            if let Stmt::Fn { name, .. } = s {
+            if let Stmt::Fn { name, .. } = &s.node {
                 Some(name.clone())
             } else {
                 None
@@ -696,33 +1088,143 @@ fn ws_and_comments() -> impl Parser<char, (), Error = Simple<char>> + Clone {
         .ignored()
 }
 
+/// Tipo de una anotación: los primitivos se mapean a `Type`, el resto son structs
+fn annotation_type(name: String) -> Type {
+    Type::from_name(&name).unwrap_or(Type::Struct(name))
+}
+
+// Tipo: `int32`, `Punto`, arrays `[Drawable]` (de tamaño dinámico), diccionarios
+// `dict<string, int64>`, sets `set<int64>`, instancias de structs genéricos `Caja<int64>`
+// o funciones `fn(int64, int64) -> int64` (sin `->` no retornan valor)
+fn type_expr() -> impl Parser<char, Type, Error = Simple<char>> + Clone {
+    recursive(|ty| {
+        let function = text::keyword("fn")
+            .padded()
+            .ignore_then(
+                ty.clone()
+                    .separated_by(just(',').padded())
+                    .delimited_by(just('(').padded(), just(')').padded()),
+            )
+            .then(just("->").padded().ignore_then(ty.clone()).or_not())
+            .map(|(params, ret)| Type::Function { params, ret: Box::new(ret.unwrap_or(Type::Void)) });
+
+        ty.clone()
+            .delimited_by(just('[').padded(), just(']').padded())
+            .map(|element| Type::Array { element_type: Box::new(element), size: None })
+            .or(function)
+            .or(text::ident()
+                .padded()
+                .then(
+                    ty.separated_by(just(',').padded())
+                        .at_least(1)
+                        .delimited_by(just('<').padded(), just('>').padded())
+                        .or_not(),
+                )
+                .map(|(name, args)| match args {
+                    Some(mut args) if name == "dict" && args.len() == 2 => {
+                        let value = args.pop().unwrap();
+                        let key = args.pop().unwrap();
+                        Type::Dict { key: Box::new(key), value: Box::new(value) }
+                    }
+                    Some(mut args) if name == "set" && args.len() == 1 => Type::Set(Box::new(args.pop().unwrap())),
+                    Some(args) => Type::Generic { name, args },
+                    None => annotation_type(name),
+                }))
+    })
+}
+
+// `arr[i] = v`: el backend reconoce el marcador `_array_set` con un `Index` a la izquierda
+// (mismo formato que genera el parser manual)
+fn index_assign(name: String, index: Spanned<Expr>, value: Spanned<Expr>, span: Span) -> Spanned<Expr> {
+    let target = Spanned::new(Expr::Index {
+        array: Box::new(Spanned::new(Expr::Ident(name), span)),
+        index: Box::new(index),
+    }, span);
+    Spanned::new(Expr::Assign {
+        name: "_array_set".to_string(),
+        value: Box::new(Spanned::new(Expr::BinaryOp {
+            op: BinOp::Eq,
+            left: Box::new(target),
+            right: Box::new(value),
+        }, span)),
+    }, span)
+}
+
+// Lo que recorre un `for`: `start..end` o cualquier expresión (ver `ForIter::from_iterable`)
+fn for_iter(iter: Spanned<Expr>, end: Option<Spanned<Expr>>) -> ForIter {
+    match (iter, end) {
+        (start, Some(end)) => ForIter::Range { start, end, step: None },
+        (iter, None) => ForIter::from_iterable(iter),
+    }
+}
+
+// Parámetros de tipo: `<T, U>` (vacío si no es genérico)
+fn type_params() -> impl Parser<char, Vec<String>, Error = Simple<char>> + Clone {
+    text::ident()
+        .padded()
+        .separated_by(just(',').padded())
+        .at_least(1)
+        .delimited_by(just('<').padded(), just('>').padded())
+        .or_not()
+        .map(Option::unwrap_or_default)
+}
+
+// Anotación de tipo: `: int32`
+fn type_annotation() -> impl Parser<char, Type, Error = Simple<char>> + Clone {
+    just(':')
+        .padded()
+        .ignore_then(type_expr())
+}
+
+// Tipo de retorno: `-> int64`
+fn return_type_annotation() -> impl Parser<char, Type, Error = Simple<char>> + Clone {
+    just("->")
+        .padded()
+        .ignore_then(type_expr())
+}
+
 fn program_parser() -> impl Parser<char, Program, Error = Simple<char>> {
     // Ignorar comentarios al inicio y entre statements
     ws_and_comments()
         .ignore_then(
             stmt_parser()
                 .padded()
+                .map(Some)
+                // Recuperación: saltar el statement inválido (hasta fin de línea, o el
+                // bloque {...} completo) y seguir parseando para reportar todos los errores
+                .recover_with(skip_parser(invalid_stmt().map(|_| None)))
                 .then_ignore(ws_and_comments())
                 .repeated()
+                .map(|stmts| stmts.into_iter().flatten().collect())
         )
-        .then_ignore(end().or_not())  // Permitir trailing whitespace/newlines
-        .try_map(|stmts: Vec<Stmt>, span| {
-            // DEBUG: Verificar qué se parseó
-            eprintln!("[PARSER-DEBUG] program_parser: Se parsearon {} statements", stmts.len());
-            io::stderr().flush().ok();
-            
-            // Si no se parseó nada pero el span no está vacío, puede haber un problema
-            if stmts.is_empty() {
-                eprintln!("[PARSER-DEBUG] WARNING: program_parser retornó 0 statements pero el input puede no estar vacío");
-                io::stderr().flush().ok();
-            }
-            
+        .then_ignore(end())
+        .try_map(|stmts: Vec<Spanned<Stmt>>, _span| {
+            log_debug!(Phase::Parser, "program_parser: Se parsearon {} statements", stmts.len());
             Ok(Program {
             statements: stmts,
             })
         })
 }
 
+/// Texto de un statement inválido: resto de la línea, incluyendo bloques `{...}`
+/// balanceados (aunque ocupen varias líneas) para no reportar su contenido como
+/// errores separados
+fn invalid_stmt() -> impl Parser<char, (), Error = Simple<char>> + Clone {
+    let block = recursive(|block| {
+        just('{')
+            .ignore_then(block.or(none_of("{}").ignored()).repeated())
+            .then_ignore(just('}'))
+            .ignored()
+    });
+    block
+        .or(none_of("{}\n").ignored())
+        .or(just('}').ignored())
+        .repeated()
+        .at_least(1)
+        .then_ignore(just('\n').or_not())
+        .ignored()
+}
+
 // Helper para detectar si una expresión contiene floats
 fn contains_float(expr: &Expr) -> bool {
     match expr {
@@ -734,7 +1236,9 @@ fn contains_float(expr: &Expr) -> bool {
     }
 }
 
-fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
+fn stmt_parser() -> impl Parser<char, Spanned<Stmt>, Error = Simple<char>> + Clone {
+    // Los parsers grandes terminan en .boxed() para limitar el tamaño de los tipos
+    // de chumsky (sin esto rustc necesita varios GB de memoria para compilar el crate)
     recursive(|stmt| {
         let ident = text::ident().padded();
         let expr = expr_parser();
@@ -752,19 +1256,26 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                     .repeated()
                     .at_least(1)
                     .collect::<String>()
+                    .map_with_span(|expr_str, span| (expr_str, span))
                     .padded()
                     .try_map({
                         let expr_clone = expr.clone(); // Clonar el parser de Rust
-                        move |expr_str: String, span| {
+                        move |(expr_str, text_span): (String, std::ops::Range<usize>), span| {
                             let trimmed = expr_str.trim();
+                            // La expresión se parsea aparte: sus spans se desplazan a su posición en el código
+                            let offset = text_span.start + expr_str.chars().take_while(|c| c.is_whitespace()).count();
                             // Detectar booleanos primero
                             if trimmed == "true" {
-                                Ok(Expr::Bool(true))
+                                Ok(spanned(Expr::Bool(true), offset..offset + 4))
                             } else if trimmed == "false" {
-                                Ok(Expr::Bool(false))
+                                Ok(spanned(Expr::Bool(false), offset..offset + 5))
                             } else {
                                 // Usar parser Rust estándar para todas las expresiones
                                 expr_clone.clone().parse(trimmed)
+                                    .map(|mut expr| {
+                                        map_expr_spans(&mut expr, &mut |s| Span::new(s.start + offset, s.end + offset));
+                                        expr
+                                    })
                                     .map_err(|_| Simple::custom(span, format!("Parse error: could not parse expression '{}'", trimmed)))
                             }
                         }
@@ -798,11 +1309,13 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
             .padded()
             .then(just("mut").padded().or_not())  // Opcional "mut"
             .then(ident.clone())
+            .then(type_annotation().or_not())
             .then_ignore(just("=").padded())
             .then(expr.clone())
-            .map(|(((_, mutable), name), value)| Stmt::Let {
+            .map(|((((_, mutable), name), ty), value)| Stmt::Let {
                 mutable: mutable.is_some(),  // true si hay "mut", false si no
                 name,
+                ty,
                 value,
             });
 
@@ -835,7 +1348,8 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                 condition,
                 then_body,
                 else_body,
-            });
+            })
+            .boxed();
 
         // While statement: Parser robusto para estructuras anidadas
         // CRÍTICO: El parser recursivo ya maneja bloques anidados correctamente
@@ -864,17 +1378,23 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                 condition,
                 body,
             })
-            .labelled("while statement");
+            .labelled("while statement")
+            .boxed();
 
-        // For loop: for VAR in START..END { BODY }
-        // El backend YA soporta generación NASM para for loops
-        let for_stmt = just("for")
+        // For loop: for VAR in START..END { BODY }, for VAR in ITERABLE { BODY }
+        // o for (A, B) in ITERABLE { BODY } para destructurar enumerate/zip/items
+        let for_vars = ident
+            .clone()
+            .separated_by(just(",").padded())
+            .at_least(1)
+            .delimited_by(just("(").padded(), just(")").padded())
+            .or(ident.clone().map(|var| vec![var]));
+        let for_stmt = text::keyword("for")
             .padded()
-            .ignore_then(ident.clone())
-            .then_ignore(just("in").padded())
-            .then(expr.clone())  // start expression
-            .then_ignore(just("..").padded())
-            .then(expr.clone())  // end expression
+            .ignore_then(for_vars)
+            .then_ignore(text::keyword("in").padded())
+            .then(expr.clone())  // start expression o iterable
+            .then(just("..").padded().ignore_then(expr.clone()).or_not())  // end expression
             .then(
                 just("{")
                     .padded()
@@ -886,13 +1406,13 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                     )
                     .then_ignore(just("}").padded())
             )
-            .map(|(((var, start), end), body)| Stmt::For {
-                var,
-                start,
-                end,
+            .map(|(((vars, iter), end), body)| Stmt::For {
+                vars,
+                iter: for_iter(iter, end),
                 body,
             })
-            .labelled("for statement");
+            .labelled("for statement")
+            .boxed();
 
         // Break statement: sale del loop más cercano
         let break_stmt = just("break")
@@ -912,20 +1432,19 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
             .padded()
             .then(just("mut").padded().or_not())
             .then(ident.clone())
-            .map(|((_, mutable), name)| FnParam {
-                name,
-                borrow_type: if mutable.is_some() {
-                    BorrowType::MutBorrowed
-                } else {
-                    BorrowType::Borrowed
-                },
-                default_value: None,
-            })
-            .or(ident.clone().map(|name| FnParam {
+            .map(|((_, mutable), name)| (name, if mutable.is_some() {
+                BorrowType::MutBorrowed
+            } else {
+                BorrowType::Borrowed
+            }))
+            .or(ident.clone().map(|name| (name, BorrowType::Owned)))
+            .then(type_annotation().or_not())
+            .map(|((name, borrow_type), ty)| FnParam {
                 name,
-                borrow_type: BorrowType::Owned,
+                borrow_type,
+                ty,
                 default_value: None,
-            }));
+            });
 
         // Parser específico para el cuerpo de funciones (sin return_stmt en nivel superior)
         // Esto evita que return se parse como statement de nivel superior
@@ -947,11 +1466,13 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                 .padded()
                 .then(just("mut").padded().or_not())
                 .then(ident.clone())
+                .then(type_annotation().or_not())
                 .then_ignore(just("=").padded())
                 .then(expr.clone())
-                .map(|(((_, mutable), name), value)| Stmt::Let {
+                .map(|((((_, mutable), name), ty), value)| Stmt::Let {
                     mutable: mutable.is_some(),
                     name,
+                    ty,
                     value,
                 });
 
@@ -1004,23 +1525,31 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
 
             let field_assign_stmt = ident
                 .clone()
+                .map_with_span(|name, span| spanned(Expr::Ident(name), span))
                 .then(just('.').ignore_then(ident.clone()))
                 .then_ignore(just("=").padded())
                 .then(expr.clone())
-                .map(|((obj_name, field_name), value)| Stmt::Expr(Expr::FieldAssign {
-                    object: Box::new(Expr::Ident(obj_name)),
+                .map_with_span(|((obj_name, field_name), value), span| Stmt::Expr(spanned(Expr::FieldAssign {
+                    object: Box::new(obj_name),
                     field: field_name,
                     value: Box::new(value),
-                }));
+                }, span)));
+
+            let index_assign_stmt = ident
+                .clone()
+                .then(expr.clone().delimited_by(just('[').padded(), just(']').padded()))
+                .then_ignore(just("=").padded())
+                .then(expr.clone())
+                .map_with_span(|((name, index), value), span| Stmt::Expr(index_assign(name, index, value, span.into())));
 
             let assign_stmt = ident
                 .clone()
                 .then_ignore(just("=").padded())
                 .then(expr.clone())
-                .map(|(name, value)| Stmt::Expr(Expr::Assign {
+                .map_with_span(|(name, value), span| Stmt::Expr(spanned(Expr::Assign {
                     name,
                     value: Box::new(value),
-                }));
+                }, span)));
 
             // Operadores compuestos: x += 5, x -= 3, x *= 2, x /= 4 (Sprint 1 - Python-like)
             let compound_assign_stmt = ident
@@ -1033,11 +1562,11 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                 )
                 .padded()
                 .then(expr.clone())
-                .map(|((name, op), value)| Stmt::Expr(Expr::CompoundAssign {
+                .map_with_span(|((name, op), value), span| Stmt::Expr(spanned(Expr::CompoundAssign {
                     name,
                     op,
                     value: Box::new(value),
-                }));
+                }, span)));
 
             let expr_stmt = expr_for_expr_stmt.map(Stmt::Expr);
 
@@ -1057,10 +1586,12 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                 .or(let_stmt)
                 .or(return_stmt)  // return_stmt está aquí para el cuerpo de funciones
                 .or(field_assign_stmt)
+                .or(index_assign_stmt)
                 .or(compound_assign_stmt)  // Sprint 1: +=, -=, *=, /= ANTES de assign_stmt
                 .or(assign_stmt)
                 .or(expr_stmt)
                 .padded()
+                .map_with_span(|stmt, span: std::ops::Range<usize>| Spanned::new(stmt, span.into()))
         });
 
         // Parser para funciones con visibilidad opcional (Sprint 1.3 - Import básico)
@@ -1070,16 +1601,18 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
             .then(just("fn")
                 .padded()
                 .ignore_then(ident.clone())
+                .then(type_params())
                 .then(
                     just("(")
                         .padded()
                         .ignore_then(
-                            fn_param
+                            fn_param.clone()
                                 .separated_by(just(",").padded())
                                 .allow_trailing(),
                         )
                         .then_ignore(just(")").padded()),
                 )
+                .then(return_type_annotation().or_not())
                 .then(
                     just("{")
                         .padded()
@@ -1093,62 +1626,215 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                         .then_ignore(just("}").padded())
                         .then_ignore(ws_and_comments()),  // CRÍTICO: Consumir whitespace/comentarios después del cierre
                 ))
-            .map(|(visibility, ((name, params), body))| Stmt::Fn {
+            .map(|(visibility, ((((name, type_params), params), return_type), body))| Stmt::Fn {
                 visibility: if visibility.is_some() { 
                     Visibility::Public 
                 } else { 
                     Visibility::Private 
                 },
                 name,
+                type_params,
                 params,
+                return_type,
                 body,
-            });
+            })
+            .boxed();
 
         // ═══════════════════════════════════════════════════════════════════════════
         // OOP: STRUCTS Y CLASES (Sintaxis con llaves {})
         // ═══════════════════════════════════════════════════════════════════════════
         
-        // Campo de struct: nombre (sin tipo explícito por ahora)
-        // Sintaxis: x, y, nombre, etc.
-        let struct_field_simple = text::ident()
+        // Campo de struct: [pub] [mut] nombre [: tipo] [,]
+        // Sin tipo (sintaxis simple: `struct Punto { x y }`) el campo es público y mutable.
+        // Con tipo se aplican las reglas de encapsulación (O5): privado e inmutable por
+        // defecto, salvo `pub` / `mut` explícitos.
+        let struct_field = just("pub").padded().or_not()
+            .then(just("mut").padded().or_not())
+            .then(ident.clone())
+            .then(type_annotation().or_not())
+            .then_ignore(just(',').padded().or_not())
+            .map(|(((public, mutable), name), ty)| {
+                let typed = ty.is_some();
+                StructMember::Field(StructField {
+                    visibility: if public.is_some() || !typed { Visibility::Public } else { Visibility::Private },
+                    mutable: mutable.is_some() || !typed,
+                    name,
+                    ty,
+                })
+            });
+
+        // Parámetros de métodos de struct (admiten anotación de tipo: `valor: int64`)
+        let struct_method_params = just("(")
             .padded()
-            .map(|name: String| StructField {
-                visibility: Visibility::Public,  // Público por defecto para structs simples
-                mutable: true,  // Mutable por defecto
-                name,
-                ty: None,
+            .ignore_then(
+                fn_param.clone()
+                    .separated_by(just(",").padded())
+                    .allow_trailing()
+            )
+            .then_ignore(just(")").padded());
+
+        let struct_method_body = just("{")
+            .padded()
+            .ignore_then(stmt.clone().repeated())
+            .then_ignore(just("}").padded());
+
+        // Constructor, destructor y métodos: [pub] init(...) { }, [pub] destroy() { }, [pub] fn nombre(...) { }
+        let struct_method = just("pub").padded().or_not()
+            .then(
+                just("init").to(None)
+                    .or(just("destroy").to(Some(None)))
+                    .or(just("fn").padded().ignore_then(ident.clone()).map(|name| Some(Some(name))))
+                    .padded()
+            )
+            .then(struct_method_params.clone())
+            .then(return_type_annotation().or_not())
+            .then(struct_method_body.clone())
+            .map(|((((public, kind), params), return_type), body)| {
+                let method = StructMethod {
+                    visibility: if public.is_some() { Visibility::Public } else { Visibility::Private },
+                    params,
+                    return_type,
+                    body,
+                };
+                match kind {
+                    None => StructMember::Init(method),
+                    Some(None) => StructMember::Destroy(method),
+                    Some(Some(name)) => StructMember::Method(name, method),
+                }
             });
 
-        // Struct definition: struct Nombre { campo1 campo2 ... }
+        // Struct definition: struct Nombre[<T, ...>] [extends Padre] { campos, init, destroy y métodos }
         // Los campos pueden estar separados por comas, espacios o newlines
-        // IMPORTANTE: Los campos son OPCIONALES (puede haber structs vacíos)
+        // IMPORTANTE: Los miembros son OPCIONALES (puede haber structs vacíos)
         let struct_stmt = just("struct")
                     .padded()
                     .ignore_then(text::ident())
+            .then(type_params())
+            .then(
+                just("extends")
+                    .padded()
+                    .ignore_then(ident.clone())
+                    .or_not()
+            )
             .then(
                 just("{")
                     .padded()
                     .ignore_then(
-                        struct_field_simple
-                            .padded()  // Permite whitespace (incluyendo newlines) alrededor de cada campo
-                            .repeated()  // Campos opcionales (puede ser vacío)
+                        struct_method
+                            .or(struct_field)
+                            .padded()  // Permite whitespace (incluyendo newlines) alrededor de cada miembro
+                            .repeated()  // Miembros opcionales (puede ser vacío)
                             .collect::<Vec<_>>()  // Convertir a Vec explícitamente
                     )
                     .then_ignore(just("}").padded())
             )
-            .map(|(name, fields)| {
-                eprintln!("[PARSER-DEBUG] struct_stmt: Parseando struct '{}' con {} campos", name, fields.len());
-                io::stderr().flush().ok();
+            .map(|(((name, type_params), parent), members)| {
+                let mut fields = Vec::new();
+                let mut init = None;
+                let mut destroy = None;
+                let mut methods = Vec::new();
+                for member in members {
+                    match member {
+                        StructMember::Field(field) => fields.push(field),
+                        StructMember::Init(method) => init = Some(method),
+                        StructMember::Destroy(method) => destroy = Some(method),
+                        StructMember::Method(method_name, method) => methods.push((method_name, method)),
+                    }
+                }
+                log_trace!(Phase::Parser, "struct_stmt: Parseando struct '{}' con {} campos", name, fields.len());
                 Stmt::Struct {
                 name,
-                parent: None,
+                type_params,
+                parent,
                 fields,
-                init: None,
-                destroy: None,
-                methods: Vec::new(),
+                init,
+                destroy,
+                methods,
                 }
             })
-            .labelled("struct statement");
+            .labelled("struct statement")
+            .boxed();
+
+        // Enum (tagged union): enum Nombre { Variante, Variante(campo [: tipo], ...), ... }
+        // Las variantes pueden estar separadas por comas, espacios o newlines
+        let enum_variant = ident.clone()
+            .then(
+                ident.clone()
+                    .then(type_annotation().or_not())
+                    .separated_by(just(',').padded())
+                    .allow_trailing()
+                    .delimited_by(just('(').padded(), just(')').padded())
+                    .or_not()
+            )
+            .then_ignore(just(',').padded().or_not())
+            .map(|(name, fields)| EnumVariant { name, fields: fields.unwrap_or_default() });
+
+        // Trait: trait Nombre { fn metodo(self, ...) [-> tipo] ... }
+        // Solo firmas: cada struct que lo implementa aporta el cuerpo en su `impl`
+        let trait_method = just("fn")
+            .padded()
+            .ignore_then(ident.clone())
+            .then(struct_method_params.clone())
+            .then(return_type_annotation().or_not())
+            .then_ignore(just(';').padded().or_not())
+            .map(|((name, params), return_type)| TraitMethod { name, params, return_type });
+
+        let trait_stmt = text::keyword("trait")
+            .padded()
+            .ignore_then(text::ident())
+            .then(
+                just("{")
+                    .padded()
+                    .ignore_then(trait_method.padded().repeated())
+                    .then_ignore(just("}").padded())
+            )
+            .map(|(name, methods)| Stmt::Trait { name, methods })
+            .labelled("trait statement")
+            .boxed();
+
+        // Implementación: impl Trait for Struct { [pub] fn metodo(self, ...) [-> tipo] { ... } }
+        let impl_method = just("pub").padded().or_not()
+            .then(just("fn").padded().ignore_then(ident.clone()))
+            .then(struct_method_params.clone())
+            .then(return_type_annotation().or_not())
+            .then(struct_method_body.clone())
+            .map(|((((public, name), params), return_type), body)| {
+                let method = StructMethod {
+                    visibility: if public.is_some() { Visibility::Public } else { Visibility::Private },
+                    params,
+                    return_type,
+                    body,
+                };
+                (name, method)
+            });
+
+        let impl_stmt = text::keyword("impl")
+            .padded()
+            .ignore_then(text::ident())
+            .then_ignore(text::keyword("for").padded())
+            .then(text::ident().padded())
+            .then(
+                just("{")
+                    .padded()
+                    .ignore_then(impl_method.padded().repeated())
+                    .then_ignore(just("}").padded())
+            )
+            .map(|((trait_name, type_name), methods)| Stmt::Impl { trait_name, type_name, methods })
+            .labelled("impl statement")
+            .boxed();
+
+        let enum_stmt = text::keyword("enum")
+            .padded()
+            .ignore_then(text::ident())
+            .then(
+                just("{")
+                    .padded()
+                    .ignore_then(enum_variant.padded().repeated())
+                    .then_ignore(just("}").padded())
+            )
+            .map(|(name, variants)| Stmt::Enum { name, variants })
+            .labelled("enum statement")
+            .boxed();
 
         // ═══════════════════════════════════════════════════════════════════════════
         // CLASS: Clases con constructor y métodos
@@ -1209,9 +1895,9 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                         // Extraer campos del constructor (self.campo = ...)
                         for stmt in &body {
                             // Buscar FieldAssign: self.campo = valor
-                            if let Stmt::Expr(Expr::FieldAssign { object, field, .. }) = stmt {
+                            if let Stmt::Expr(Spanned { node: Expr::FieldAssign { object, field, .. }, .. }) = &stmt.node {
                                 // Verificar que el objeto es "self"
-                                if let Expr::Ident(obj_name) = object.as_ref() {
+                                if let Expr::Ident(obj_name) = &object.node {
                                     if obj_name == "self" {
                                         fields.push(StructField {
                                             visibility: Visibility::Public,
@@ -1226,12 +1912,14 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                         init_method = Some(StructMethod {
                             visibility: Visibility::Public,
                             params: params.clone(),
+                            return_type: None,
                             body: body.clone(),
                         });
                     } else if method_name == "destroy" {
                         destroy_method = Some(StructMethod {
                             visibility: Visibility::Public,
                             params,
+                            return_type: None,
                             body,
                         });
                     } else {
@@ -1239,6 +1927,7 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                         other_methods.push((method_name, StructMethod {
                             visibility: Visibility::Public,
                             params,
+                            return_type: None,
                             body,
                         }));
                     }
@@ -1246,6 +1935,7 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                 
                 Stmt::Struct {
                     name: class_name,
+                    type_params: Vec::new(),
                     parent,
                     fields,
                     init: init_method,
@@ -1253,29 +1943,39 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                     methods: other_methods,
                 }
             })
-            .labelled("class statement");
+            .labelled("class statement")
+            .boxed();
 
         // Field assignment: ident.field = expr (as statement)
         let field_assign_stmt = ident
             .clone()
+            .map_with_span(|name, span| spanned(Expr::Ident(name), span))
             .then(just('.').ignore_then(ident.clone()))
             .then_ignore(just("=").padded())
             .then(expr.clone())
-            .map(|((obj_name, field_name), value)| Stmt::Expr(Expr::FieldAssign {
-                object: Box::new(Expr::Ident(obj_name)),
+            .map_with_span(|((obj_name, field_name), value), span| Stmt::Expr(spanned(Expr::FieldAssign {
+                object: Box::new(obj_name),
                 field: field_name,
                 value: Box::new(value),
-            }));
+            }, span)));
         
+        // Asignación indexada: arr[i] = expr o d["clave"] = expr (as statement)
+        let index_assign_stmt = ident
+            .clone()
+            .then(expr.clone().delimited_by(just('[').padded(), just(']').padded()))
+            .then_ignore(just("=").padded())
+            .then(expr.clone())
+            .map_with_span(|((name, index), value), span| Stmt::Expr(index_assign(name, index, value, span.into())));
+
         // Assignment: ident = expr (as statement)
         let assign_stmt = ident
             .clone()
             .then_ignore(just("=").padded())
             .then(expr.clone())
-            .map(|(name, value)| Stmt::Expr(Expr::Assign {
+            .map_with_span(|(name, value), span| Stmt::Expr(spanned(Expr::Assign {
                 name,
                 value: Box::new(value),
-            }));
+            }, span)));
 
         // Operadores compuestos: x += 5, x -= 3, x *= 2, x /= 4 (Sprint 1 - Python-like)
         let compound_assign_stmt = ident
@@ -1288,11 +1988,11 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
             )
             .padded()
             .then(expr.clone())
-            .map(|((name, op), value)| Stmt::Expr(Expr::CompoundAssign {
+            .map_with_span(|((name, op), value), span| Stmt::Expr(spanned(Expr::CompoundAssign {
                 name,
                 op,
                 value: Box::new(value),
-            }));
+            }, span)));
 
         let expr_stmt = expr_for_expr_stmt.map(Stmt::Expr);
 
@@ -1319,6 +2019,9 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
             .or(if_stmt)      // If tiene alta precedencia
             .or(class_stmt)   // OOP: Clases con métodos
             .or(struct_stmt)  // OOP: Structs simples (DEBE estar antes de fn_stmt)
+            .or(enum_stmt)    // Enums de usuario (tagged unions)
+            .or(trait_stmt)   // Traits (interfaces)
+            .or(impl_stmt)    // impl Trait for Struct
             .or(import_stmt)
             .or(fn_stmt)      // CRÍTICO: fn_stmt ANTES de return_stmt para que return dentro de funciones se parse correctamente
             .or(print)
@@ -1326,12 +2029,15 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
             .or(let_stmt)
             .or(return_stmt)  // return_stmt DESPUÉS de fn_stmt para evitar conflictos
             .or(field_assign_stmt)  // Field assignment ANTES de assign_stmt
+            .or(index_assign_stmt)  // arr[i] = v ANTES de expr_stmt
             .or(compound_assign_stmt)  // Sprint 1: +=, -=, *=, /= ANTES de assign_stmt
             .or(assign_stmt)
             .or(expr_stmt)
             .padded()
             .try_map(|stmt: Stmt, span| {
-                // DEBUG: Ver qué statement se parseó exitosamente
+                if !log::enabled(Phase::Parser, log::Level::Trace) {
+                    return Ok(Spanned::new(stmt, span.into()));
+                }
                 let stmt_type = match &stmt {
                     Stmt::Struct { name, .. } => format!("Struct({})", name),
                     Stmt::Fn { name, .. } => format!("Function({})", name),
@@ -1345,16 +2051,15 @@ fn stmt_parser() -> impl Parser<char, Stmt, Error = Simple<char>> + Clone {
                     Stmt::Import(name) => format!("Import({})", name),
                     _ => format!("Other({:?})", stmt),
                 };
-                eprintln!("[PARSER-DEBUG] stmt_parser: ✅ Se parseó exitosamente: {} (span: {:?})", stmt_type, span);
-                io::stderr().flush().ok();
-                Ok(stmt)
+                log_trace!(Phase::Parser, "stmt_parser: Se parseó {} (span: {:?})", stmt_type, span);
+                Ok(Spanned::new(stmt, span.into()))
             });
         
         stmt_with_debug
     })
 }
 
-fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
+fn expr_parser() -> impl Parser<char, Spanned<Expr>, Error = Simple<char>> + Clone {
         // ZIG ES EL PARSER PRINCIPAL - Intentar parsear con Zig primero para TODAS las expresiones
         recursive(|expr| {
             // Definir literales primero (number, float, string)
@@ -1374,19 +2079,36 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
         // Luego verificamos si ese carácter es un punto
         // Float parser: NO debe consumir "0.." como float (eso es rango para for)
         // Solo consume "0.5", "3.14", "5." cuando NO viene seguido de otro punto
+        // Exponente opcional: e10, E-3, e+2
+        let exponent = just('e').or(just('E'))
+            .ignore_then(just('+').or(just('-')).or_not().then(text::int(10)))
+            .map(|(sign, num): (Option<char>, String)| {
+                if sign == Some('-') {
+                    format!("-{}", num)
+                } else {
+                    num
+                }
+            });
+        
         let float_with_int_part = text::int(10)
             .then(
                 just('.')
-                    .then(just('.').not())  // Rechazar ".." (rango)
+                    // Rechazar ".." (rango) y "1.metodo" (xs[1].metodo()) sin consumir el carácter siguiente
+                    .then_ignore(filter(|c: &char| *c == '.' || c.is_alphabetic() || *c == '_').not().rewind())
                     .then(text::digits(10).or_not())  // Dígitos opcionales después del punto
             )
-            .try_map(|(int_part, ((_, _), dec_opt)), span| {
+            .then(exponent.clone().or_not())
+            .try_map(|((int_part, (_, dec_opt)), exp_part), span| {
                 let dec = dec_opt.unwrap_or_default();
                 let float_str = if dec.is_empty() {
                     format!("{}.0", int_part)  // 5. -> 5.0
                 } else {
                     format!("{}.{}", int_part, dec)
                 };
+                let float_str = match exp_part {
+                    Some(exp) => format!("{}e{}", float_str, exp),
+                    None => float_str,
+                };
                 float_str.parse::<f64>()
                     .map_err(|_| Simple::custom(span, format!("Invalid float literal: {}", float_str)))
             })
@@ -1396,21 +2118,7 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
         // También manejar .5 (sin parte entera)
         let float_without_int_part = just('.')
             .ignore_then(text::digits(10))  // .5
-            .then(
-                just('e').or(just('E'))
-                    .ignore_then(
-                        just('+').or(just('-')).or_not()
-                            .then(text::int(10))
-                            .map(|(sign, num): (Option<char>, String)| {
-                                if sign == Some('-') {
-                                    format!("-{}", num)
-                                } else {
-                                    num
-                                }
-                            })
-                    )
-                    .or_not()
-            )
+            .then(exponent.or_not())
             .try_map(|(dec_part, exp_part), span| {
                 let float_str = format!("0.{}", dec_part);
                 let float_str = match exp_part {
@@ -1473,7 +2181,7 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
             .ignore_then(none_of('"').repeated())
             .then_ignore(just('"'))
             .collect::<String>()
-            .map(|content| {
+            .map_with_span(|content, span: std::ops::Range<usize>| {
                 // Parsear el contenido del f-string para extraer partes
                 let mut parts = Vec::new();
                 let mut current_literal = String::new();
@@ -1512,7 +2220,7 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                             var_name.push(chars.next().unwrap());
                         }
                         if !var_name.is_empty() {
-                            parts.push(FStringPart::Expr(Box::new(Expr::Ident(var_name))));
+                            parts.push(FStringPart::Expr(Box::new(spanned(Expr::Ident(var_name), span.clone()))));
                         }
                     } else {
                         current_literal.push(c);
@@ -1527,28 +2235,67 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
             })
             .labelled("f-string");
 
-        // List comprehension: [x * 2 for x in lista] o [x for x in lista if x > 0]
-        let list_comprehension = just('[')
+        // Cláusulas de comprehension: `for x in lista if x > 0 for y in otra if y != x ...`
+        let comprehension_clauses = text::keyword("for")
             .padded()
-            .ignore_then(expr.clone())
-            .then_ignore(just("for").padded())
-            .then(text::ident().padded())
-            .then_ignore(just("in").padded())
+            .ignore_then(text::ident().padded())
+            .then_ignore(text::keyword("in").padded())
             .then(expr.clone())
             .then(
-                just("if").padded()
+                text::keyword("if").padded()
                     .ignore_then(expr.clone())
-                    .or_not()
+                    .repeated()
             )
+            .map(|((var, iter), conditions)| ComprehensionClause {
+                var,
+                iter: Box::new(iter),
+                conditions,
+            })
+            .repeated()
+            .at_least(1)
+            .boxed();
+
+        // List comprehension: [x * 2 for x in lista] o [x for x in lista if x > 0]
+        let list_comprehension = just('[')
+            .padded()
+            .ignore_then(expr.clone())
+            .then(comprehension_clauses.clone())
             .then_ignore(just(']').padded())
-            .map(|(((map_expr, var), iter_expr), cond)| Expr::ListComprehension {
+            .map(|(map_expr, clauses)| Expr::ListComprehension {
                 expr: Box::new(map_expr),
-                var,
-                iter: Box::new(iter_expr),
-                condition: cond.map(Box::new),
+                clauses,
             })
             .labelled("list comprehension");
 
+        // Set comprehension: {x * 2 for x in lista} o {x for x in lista if x > 0}
+        let set_comprehension = just('{')
+            .padded()
+            .ignore_then(expr.clone())
+            .then(comprehension_clauses.clone())
+            .then_ignore(just('}').padded())
+            .map(|(map_expr, clauses)| Expr::SetComprehension {
+                expr: Box::new(map_expr),
+                clauses,
+            })
+            .labelled("set comprehension")
+            .boxed();
+
+        // Dict comprehension: {x: x * x for x in lista if x > 0}
+        let dict_comprehension = just('{')
+            .padded()
+            .ignore_then(expr.clone())
+            .then_ignore(just(':').padded())
+            .then(expr.clone())
+            .then(comprehension_clauses)
+            .then_ignore(just('}').padded())
+            .map(|((key_expr, value_expr), clauses)| Expr::DictComprehension {
+                key_expr: Box::new(key_expr),
+                value_expr: Box::new(value_expr),
+                clauses,
+            })
+            .labelled("dict comprehension")
+            .boxed();
+
         // Array literal: [1, 2, 3] (Sprint 1.2)
         let array_literal = just('[')
             .padded()
@@ -1598,7 +2345,7 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     "while", "if", "else", "let", "print", "fn", "struct", "return",
                     "true", "false", "Some", "None", "Ok", "Err", "match", "end",
                     "for", "in", "break", "continue",  // For loops y control de flujo
-                    "import", "pub", "mut", "class",  // Otros keywords (self NO está aquí)
+                    "import", "pub", "mut", "class", "enum", "trait", "impl",  // Otros keywords (self NO está aquí)
                 ];
                 if keywords.contains(&s.as_str()) {
                     Err(Simple::custom(span, format!("'{}' is a keyword and cannot be used as an identifier", s)))
@@ -1749,7 +2496,9 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
             .or(string)
             .or(list_comprehension)  // List comprehension ANTES de array_literal
             .or(array_literal)  // Array literal antes de borrow
+            .or(dict_comprehension)  // Dict comprehension ANTES de dict_literal
             .or(dict_literal)  // Dict literal: {"key": value}
+            .or(set_comprehension)  // Set comprehension ANTES de set_literal
             .or(set_literal)   // Set literal: {1, 2, 3}
             .or(borrow)  // Borrow debe ir ANTES de ident para que &x se parse como Borrow, not como Call
             .or(deref)
@@ -1764,7 +2513,10 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
             .or(tuple_literal)  // Tuplas antes de paréntesis simples
             .or(expr
                 .clone()
-                .delimited_by(just("(").padded(), just(")").padded()));
+                .delimited_by(just("(").padded(), just(")").padded())
+                .map(|e: Spanned<Expr>| e.node))
+            .map_with_span(spanned)
+            .boxed();
 
         // Parser para nombres con namespace: modulo.funcion o solo funcion (Sprint 1.3)
         let qualified_name = text::ident()
@@ -1804,15 +2556,16 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                 )
                 .then_ignore(just(")").padded())
             )
-            .map(|(method, args)| Expr::SuperCall {
+            .map_with_span(|(method, args), span| spanned(Expr::SuperCall {
                 method,
                 args,
-            });
+            }, span));
 
         // Parser para method calls: obj.metodo(args)
         // NOTA: ClassName.new() se parsea como Call, no como MethodCall
         // Solo obj.metodo() donde obj es una variable se parsea como MethodCall
         let method_call = text::ident()
+            .map_with_span(|name, span| (name, span))
             .padded()
             .then(
                 just(".")
@@ -1829,22 +2582,22 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     .then_ignore(just(")").padded())
                 )
             )
-            .try_map(|(obj_name, (method, args)), span| {
+            .try_map(|((obj_name, obj_span), (method, args)), span| {
                 // Si el método es "new", parsear como Call (constructor), no MethodCall
                 if method == "new" {
                     // Es un constructor: ClassName.new() -> Call { module: Some("ClassName"), name: "new", args }
-                    Ok(Expr::Call {
+                    Ok(spanned(Expr::Call {
                         module: Some(obj_name),
                         name: method,
                         args,
-                    })
+                    }, span))
                 } else {
                     // Es un método de instancia: obj.metodo() -> MethodCall
-                    Ok(Expr::MethodCall {
-                        object: Box::new(Expr::Ident(obj_name)),
+                    Ok(spanned(Expr::MethodCall {
+                        object: Box::new(spanned(Expr::Ident(obj_name), obj_span)),
                         method,
                         args,
-                    })
+                    }, span))
                 }
             });
 
@@ -1859,11 +2612,11 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     )
                     .then_ignore(just(")").padded()),
             )
-            .map(|((module, name), args)| Expr::Call {
+            .map_with_span(|((module, name), args), span| spanned(Expr::Call {
                 module,
                 name,
                 args,
-            })
+            }, span))
             .or(atom);
 
         // Combinar: super_call tiene prioridad, luego method_call, luego call
@@ -1871,41 +2624,145 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
         // method_call puede retornar Call (para constructores) o MethodCall (para métodos)
         let call_or_method = super_call
             .or(method_call)
-            .or(call);
+            .or(call)
+            .boxed();
 
         // Match expression (O0.4)
-        let pattern = recursive(|_pattern| {
-            let some_pattern = just("Some").padded().to(Pattern::Some);
-            let none_pattern = just("None").padded().to(Pattern::None);
-            let ok_pattern = just("Ok").padded().to(Pattern::Ok);
-            let err_pattern = just("Err").padded().to(Pattern::Err);
-            let wildcard = just("_").padded().to(Pattern::Wildcard);
-            let ident_pattern = text::ident().map(Pattern::Ident);
-            let number_pattern = text::int(10)
-                .map(|s: String| s.parse::<i64>().unwrap())
-                .map(Pattern::LiteralNumber);
+        // Patrones: literales (42, -1, "hola", true), rangos (1..5, 1..=5), Some(p) / None /
+        // Ok(p) / Err(p), tuplas (a, _), structs (Punto { x, y: 0, .. }), alternativas con `|`
+        // y bindings con nombre (n @ 1..=9)
+        let pattern = recursive(|pattern| {
+            let int_literal = just('-')
+                .or_not()
+                .then(text::int(10))
+                .try_map(|(sign, digits): (Option<char>, String), span| {
+                    let text = if sign.is_some() { format!("-{}", digits) } else { digits };
+                    text.parse::<i64>()
+                        .map_err(|_| Simple::custom(span, format!("número fuera de rango en patrón: {}", text)))
+                })
+                .padded();
+            let number_or_range = int_literal
+                .clone()
+                .then(
+                    just("..=").to(true)
+                        .or(just("..").to(false))
+                        .then(int_literal)
+                        .or_not()
+                )
+                .map(|(start, range)| match range {
+                    Some((inclusive, end)) => Pattern::Range { start, end, inclusive },
+                    None => Pattern::LiteralNumber(start),
+                });
             let string_pattern = just('"')
                 .ignore_then(none_of('"').repeated())
                 .then_ignore(just('"'))
                 .collect::<String>()
+                .padded()
                 .map(Pattern::LiteralString);
-            
-            some_pattern
-                .or(none_pattern)
-                .or(ok_pattern)
-                .or(err_pattern)
-                .or(wildcard)
-                .or(number_pattern)
+            let tuple_pattern = pattern
+                .clone()
+                .separated_by(just(',').padded())
+                .allow_trailing()
+                .delimited_by(just('(').padded(), just(')').padded())
+                .map(|mut items: Vec<Pattern>| {
+                    // `(p)` es solo agrupación; `(p,)` y `(a, b)` son tuplas
+                    if items.len() == 1 { items.remove(0) } else { Pattern::Tuple(items) }
+                });
+
+            // Sufijos de un identificador: Some(p), Shape.Rect(w, h), Punto { ... }, n @ p
+            let payload = pattern
+                .clone()
+                .separated_by(just(',').padded())
+                .allow_trailing()
+                .delimited_by(just('(').padded(), just(')').padded());
+            let field_pattern = text::ident()
+                .padded()
+                .then(just(':').padded().ignore_then(pattern.clone()).or_not())
+                .map(|(name, pat): (String, Option<Pattern>)| {
+                    let pat = pat.unwrap_or_else(|| Pattern::Ident(name.clone()));
+                    Some((name, pat))
+                })
+                .or(just("..").padded().to(None));
+            let struct_fields = field_pattern
+                .separated_by(just(',').padded())
+                .allow_trailing()
+                .delimited_by(just('{').padded(), just('}').padded())
+                .map(|fields| fields.into_iter().flatten().collect::<Vec<_>>());
+            let at_pattern = just('@').padded().ignore_then(pattern.clone());
+
+            #[derive(Clone)]
+            enum Suffix {
+                Payload(Vec<Pattern>),
+                Fields(Vec<(String, Pattern)>),
+                At(Pattern),
+            }
+            // `Shape.Circle`: variante de enum calificada con el nombre del enum
+            let qualified = text::ident()
+                .padded()
+                .then(just('.').ignore_then(text::ident()).padded().or_not());
+            let named = qualified
+                .then(
+                    payload.map(Suffix::Payload)
+                        .or(struct_fields.map(Suffix::Fields))
+                        .or(at_pattern.map(Suffix::At))
+                        .or_not()
+                )
+                .try_map(|((name, variant), suffix): ((String, Option<String>), Option<Suffix>), span: std::ops::Range<usize>| {
+                    // Variante de enum: el nombre del enum se completa al resolver el programa
+                    // (`Circle(r)` sin calificar queda con `enum_name` vacío hasta entonces)
+                    if let Some(variant) = variant {
+                        return match suffix {
+                            None => Ok(Pattern::Variant { enum_name: name, variant, fields: Vec::new() }),
+                            Some(Suffix::Payload(fields)) => Ok(Pattern::Variant { enum_name: name, variant, fields }),
+                            Some(_) => Err(Simple::custom(span, format!("patrón inválido para la variante '{}.{}'", name, variant))),
+                        };
+                    }
+                    let wrap = |p: Option<Vec<Pattern>>| -> std::result::Result<Box<Pattern>, Simple<char>> {
+                        match p {
+                            None => Ok(Box::new(Pattern::Wildcard)),
+                            Some(mut items) if items.len() == 1 => Ok(Box::new(items.remove(0))),
+                            Some(_) => Err(Simple::custom(span.clone(), format!("'{}' espera un solo patrón entre paréntesis", name))),
+                        }
+                    };
+                    Ok(match (name.as_str(), suffix) {
+                        ("Some", None) => Pattern::Some(wrap(None)?),
+                        ("Some", Some(Suffix::Payload(p))) => Pattern::Some(wrap(Some(p))?),
+                        ("Ok", None) => Pattern::Ok(wrap(None)?),
+                        ("Ok", Some(Suffix::Payload(p))) => Pattern::Ok(wrap(Some(p))?),
+                        ("Err", None) => Pattern::Err(wrap(None)?),
+                        ("Err", Some(Suffix::Payload(p))) => Pattern::Err(wrap(Some(p))?),
+                        ("None", None) => Pattern::None,
+                        ("true", None) => Pattern::LiteralBool(true),
+                        ("false", None) => Pattern::LiteralBool(false),
+                        ("_", None) => Pattern::Wildcard,
+                        (_, Some(Suffix::Fields(fields))) => Pattern::Struct { name, fields },
+                        (_, Some(Suffix::At(p))) => Pattern::Binding { name, pattern: Box::new(p) },
+                        (_, None) => Pattern::Ident(name),
+                        (_, Some(Suffix::Payload(fields))) => Pattern::Variant { enum_name: String::new(), variant: name, fields },
+                    })
+                });
+
+            let atom = number_or_range
                 .or(string_pattern)
-                .or(ident_pattern)
+                .or(tuple_pattern)
+                .or(named)
+                .boxed();
+
+            atom.separated_by(just('|').padded())
+                .at_least(1)
+                .map(|mut alternatives| {
+                    if alternatives.len() == 1 { alternatives.remove(0) } else { Pattern::Or(alternatives) }
+                })
                 .labelled("pattern")
         });
 
         let match_arm = pattern
+            .then(just("if").padded().ignore_then(expr.clone()).or_not())
             .then_ignore(just("=>").padded())
             .then(expr.clone())
-            .map(|(pat, body)| MatchArm {
+            .map(|((pat, guard), body)| MatchArm {
                 pattern: pat,
+                guard: guard.map(Box::new),
                 body: Box::new(body),
             })
             .labelled("match arm");
@@ -1923,92 +2780,94 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     )
                     .then_ignore(just("}").padded())
             )
-            .map(|(expr, arms)| Expr::Match {
+            .map_with_span(|(expr, arms), span| spanned(Expr::Match {
                 expr: Box::new(expr),
                 arms,
-            })
+            }, span))
             .labelled("match");
 
         let unary = call_or_method
             .or(match_expr);
 
-        // Aplicar field/method access despu├®s de call/match (Fase 1.2 - O1, O4)
-        let with_access = unary
+        // Acceso a campo/método e indexación/slicing, encadenados en cualquier orden:
+        // obj.campo, obj.metodo(args), arr[0], s[0:4], s[::2], xs[1].metodo() (Fase 1.2 - O1, O4, Sprint 1.2, Sprint 2)
+        // El primer ':' dentro de los corchetes marca un slice; el segundo (opcional) introduce el paso
+        enum Postfix {
+            Access(String, Option<Vec<Spanned<Expr>>>),
+            Index(Option<Spanned<Expr>>, Option<(Option<Spanned<Expr>>, Option<Spanned<Expr>>)>),
+        }
+        let access = just(".")
+            .padded()
+            .ignore_then(text::ident())
             .then(
-                just(".")
+                just("(")
                     .padded()
-                    .ignore_then(text::ident())
-                    .then(
-                        just("(")
-                            .padded()
-                            .ignore_then(
-                                expr.clone()
-                                    .separated_by(just(",").padded())
-                                    .allow_trailing(),
-                            )
-                            .then_ignore(just(")").padded())
-                            .or_not(),
+                    .ignore_then(
+                        expr.clone()
+                            .separated_by(just(",").padded())
+                            .allow_trailing(),
                     )
-                    .repeated(),
+                    .then_ignore(just(")").padded())
+                    .or_not(),
+            )
+            .map(|(name, args)| Postfix::Access(name, args));
+        let index = just('[')
+            .padded()
+            .ignore_then(expr.clone().or_not())
+            .then(
+                just(':')
+                    .padded()
+                    .ignore_then(expr.clone().or_not())
+                    .then(just(':').padded().ignore_then(expr.clone().or_not()).or_not())
+                    .or_not()
             )
-            .foldl(|obj, (name, args)| {
-                if let Some(args) = args {
+            .then_ignore(just(']').padded())
+            .try_map(|(idx, slice), span| match (idx, slice) {
+                (Some(idx), None) => Ok(Postfix::Index(Some(idx), None)),
+                (None, None) => Err(Simple::custom(span, "Falta el índice entre '[' y ']'")),
+                (start, Some((end, step))) => Ok(Postfix::Index(start, Some((end, step.flatten())))),
+            });
+        let index_or_slice = unary
+            .then(access.or(index).map_with_span(|postfix, span| (postfix, span)).repeated())
+            .foldl(|obj, (postfix, span)| {
+                let span = obj.span.merge(span.into());
+                let expr = match postfix {
                     // Method call
-                    Expr::MethodCall {
+                    Postfix::Access(method, Some(args)) => Expr::MethodCall {
                         object: Box::new(obj),
-                        method: name,
+                        method,
                         args,
-                    }
-                } else {
+                    },
                     // Field access
-                    Expr::FieldAccess {
+                    Postfix::Access(field, None) => Expr::FieldAccess {
                         object: Box::new(obj),
-                        field: name,
-                    }
-                }
-            });
-
-        // Indexación: arr[0] o Slicing: s[0:4] (Sprint 1.2, Sprint 2)
-        let index_or_slice = with_access
-            .then(
-                just('[')
-                    .padded()
-                    .ignore_then(expr.clone())
-                    .then(
-                        // Detectar si viene ':' para slicing
-                        just(':')
-                            .padded()
-                            .ignore_then(expr.clone())
-                            .or_not()
-                    )
-                    .then_ignore(just(']').padded())
-                    .repeated(),
-            )
-            .foldl(|arr, (idx, end_opt)| {
-                if let Some(end) = end_opt {
-                    // Slicing: s[0:4]
-                    Expr::Slice {
-                        object: Box::new(arr),
-                        start: Box::new(idx),
-                        end: Box::new(end),
-                    }
-                } else {
+                        field,
+                    },
+                    // Slicing: s[0:4], s[::-1]
+                    Postfix::Index(start, Some((end, step))) => Expr::Slice {
+                        object: Box::new(obj),
+                        start: start.map(Box::new),
+                        end: end.map(Box::new),
+                        step: step.map(Box::new),
+                    },
                     // Indexación: arr[0]
-                    Expr::Index {
-                        array: Box::new(arr),
-                        index: Box::new(idx),
-                    }
-                }
-            });
+                    Postfix::Index(idx, None) => Expr::Index {
+                        array: Box::new(obj),
+                        index: Box::new(idx.expect("índice validado en try_map")),
+                    },
+                };
+                Spanned::new(expr, span)
+            })
+            .boxed();
         
         let with_index = index_or_slice;
 
         // Operador ? para propagación de errores (expr?)
         let with_propagate = with_index
             .then(just("?").padded().or_not())
-            .map(|(expr, has_question)| {
+            .map_with_span(|(expr, has_question), span| {
                 if has_question.is_some() {
-                    Expr::PropagateError(Box::new(expr))
+                    spanned(Expr::PropagateError(Box::new(expr)), span)
                 } else {
                     expr
                 }
@@ -2024,14 +2883,29 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     .then(with_propagate.clone())
                     .repeated(),
             )
-            .foldl(|l, (op, r)| Expr::BinaryOp {
-                op,
-                left: Box::new(l),
-                right: Box::new(r),
-            });
+            .foldl(binary_op);
+
+        // Menos unario: -x es 0 - x (un literal entero queda como número negativo: `range(9, 0, -1)`)
+        let negation = just("-")
+            .padded()
+            .map_with_span(|_, span: std::ops::Range<usize>| Span::from(span))
+            .repeated()
+            .then(power)
+            .foldr(|minus, e| {
+                let span = minus.merge(e.span);
+                match e.node {
+                    Expr::Number(n) => Spanned::new(Expr::Number(-n), span),
+                    node => Spanned::new(Expr::BinaryOp {
+                        op: BinOp::Sub,
+                        left: Box::new(Spanned::dummy(Expr::Number(0))),
+                        right: Box::new(Spanned::new(node, e.span)),
+                    }, span),
+                }
+            })
+            .boxed();
 
         // Multiplicación, división, módulo, división entera
-        let product = power
+        let product = negation
             .clone()
             .then(
                 just("//")
@@ -2040,14 +2914,11 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     .or(just("*").padded().to(BinOp::Mul))
                     .or(just("/").padded().to(BinOp::Div))
                     .or(just("%").padded().to(BinOp::Mod))
-                    .then(power.clone())
+                    .then(negation.clone())
                     .repeated(),
             )
-            .foldl(|l, (op, r)| Expr::BinaryOp {
-                op,
-                left: Box::new(l),
-                right: Box::new(r),
-            });
+            .foldl(binary_op)
+            .boxed();
 
         let sum = product
             .clone()
@@ -2059,11 +2930,8 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     .then(product.clone())
                     .repeated(),
             )
-            .foldl(|l, (op, r)| Expr::BinaryOp {
-                op,
-                left: Box::new(l),
-                right: Box::new(r),
-            });
+            .foldl(binary_op)
+            .boxed();
 
         // Operadores de comparación
         // IMPORTANTE: Los operadores de dos caracteres (<=, >=, ==, !=) DEBEN ir antes de los de un carácter (<, >)
@@ -2076,14 +2944,12 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     .or(just("!=").padded().to(BinOp::Ne))
                     .or(just("<").padded().to(BinOp::Lt))
                     .or(just(">").padded().to(BinOp::Gt))
+                    .or(text::keyword("in").padded().to(BinOp::In))
                     .then(sum.clone())
                     .repeated(),
             )
-            .foldl(|l, (op, r)| Expr::BinaryOp {
-                op,
-                left: Box::new(l),
-                right: Box::new(r),
-            });
+            .foldl(binary_op)
+            .boxed();
 
         // Operadores lógicos (Prioridad 2)
         // AND lógico: && o "and" (Sprint 1 - Python-like)
@@ -2096,11 +2962,8 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     .then(comparison.clone())
                     .repeated(),
             )
-            .foldl(|l, (op, r)| Expr::BinaryOp {
-                op,
-                left: Box::new(l),
-                right: Box::new(r),
-            });
+            .foldl(binary_op)
+            .boxed();
 
         // OR lógico: || o "or" (Sprint 1 - Python-like)
         let logical_or = logical_and
@@ -2112,17 +2975,29 @@ fn expr_parser() -> impl Parser<char, Expr, Error = Simple<char>> + Clone {
                     .then(logical_and.clone())
                     .repeated(),
             )
-            .foldl(|l, (op, r)| Expr::BinaryOp {
-                op,
-                left: Box::new(l),
-                right: Box::new(r),
-            });
+            .foldl(binary_op);
 
         // Parser de expresiones final con todos los operadores
         logical_or
     })
 }
 
+/// Nodo de expresión con el span (en caracteres) que reporta chumsky;
+/// `trim_spans` lo pasa a bytes
+fn spanned(expr: Expr, span: std::ops::Range<usize>) -> Spanned<Expr> {
+    Spanned::new(expr, span.into())
+}
+
+/// `left op right`, con el span que cubre ambos operandos
+fn binary_op(left: Spanned<Expr>, (op, right): (BinOp, Spanned<Expr>)) -> Spanned<Expr> {
+    let span = left.span.merge(right.span);
+    Spanned::new(Expr::BinaryOp {
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+    }, span)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2133,7 +3008,7 @@ mod tests {
         let program = parse(src).unwrap();
         assert_eq!(
             program.statements,
-            vec![Stmt::Print(Expr::String("Hola Mundo".to_string()))]
+            vec![Stmt::Print(Expr::String("Hola Mundo".to_string()).into())]
         );
     }
 
@@ -2146,7 +3021,8 @@ mod tests {
             vec![Stmt::Let {
                 mutable: false,  // Inmutable por defecto
                 name: "x".to_string(),
-                value: Expr::Number(42)
+                ty: None,
+                value: Expr::Number(42).into()
             }]
         );
     }
@@ -2159,15 +3035,15 @@ mod tests {
             }
         "#;
         let program = parse(src).unwrap();
-        assert!(matches!(&program.statements[0], Stmt::If { .. }));
+        assert!(matches!(&program.statements[0].node, Stmt::If { .. }));
     }
 
     #[test]
     fn test_parse_borrow() {
         let src = r#"let r = &x"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::Borrow { mutable: false, .. }));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::Borrow { mutable: false, .. }));
         } else {
             panic!("Expected Let statement");
         }
@@ -2177,8 +3053,8 @@ mod tests {
     fn test_parse_mut_borrow() {
         let src = r#"let r = &mut x"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::Borrow { mutable: true, .. }));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::Borrow { mutable: true, .. }));
         } else {
             panic!("Expected Let statement");
         }
@@ -2188,8 +3064,8 @@ mod tests {
     fn test_parse_deref() {
         let src = r#"let val = *ptr"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::Deref(_)));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::Deref(_)));
         } else {
             panic!("Expected Let statement");
         }
@@ -2203,7 +3079,7 @@ mod tests {
             }
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Fn { params, .. } = &program.statements[0] {
+        if let Stmt::Fn { params, .. } = &program.statements[0].node {
             assert_eq!(params.len(), 1);
             assert_eq!(params[0].name, "texto");
             assert_eq!(params[0].borrow_type, BorrowType::Borrowed);
@@ -2220,7 +3096,7 @@ mod tests {
             }
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Fn { params, .. } = &program.statements[0] {
+        if let Stmt::Fn { params, .. } = &program.statements[0].node {
             assert_eq!(params.len(), 1);
             assert_eq!(params[0].name, "valor");
             assert_eq!(params[0].borrow_type, BorrowType::MutBorrowed);
@@ -2229,16 +3105,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_type_annotations() {
+        let src = r#"
+            let mut x: int8 = 5
+            fn escalar(&v: float64, n: uint32) -> int64 {
+                let r: int64 = n
+                return r
+            }
+        "#;
+        let program = parse(src).unwrap();
+        assert!(matches!(
+            &program.statements[0].node,
+            Stmt::Let { mutable: true, ty: Some(Type::Int8), .. }
+        ));
+        if let Stmt::Fn { params, return_type, body, .. } = &program.statements[1].node {
+            assert_eq!(params[0].borrow_type, BorrowType::Borrowed);
+            assert_eq!(params[0].ty, Some(Type::Float64));
+            assert_eq!(params[1].ty, Some(Type::UInt32));
+            assert_eq!(*return_type, Some(Type::Int64));
+            assert!(matches!(&body[0].node, Stmt::Let { ty: Some(Type::Int64), .. }));
+        } else {
+            panic!("Expected Fn statement");
+        }
+    }
+
     // ========== Tests para Option/Result (O0.4) ==========
     
     #[test]
     fn test_parse_some() {
         let src = r#"let x = Some(42)"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::Some(_)));
-            if let Expr::Some(inner) = value {
-                assert!(matches!(inner.as_ref(), Expr::Number(42)));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::Some(_)));
+            if let Expr::Some(inner) = &value.node {
+                assert!(matches!(&inner.node, Expr::Number(42)));
             }
         } else {
             panic!("Expected Let statement");
@@ -2249,8 +3150,8 @@ mod tests {
     fn test_parse_none() {
         let src = r#"let x = None"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::None));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::None));
         } else {
             panic!("Expected Let statement");
         }
@@ -2260,10 +3161,10 @@ mod tests {
     fn test_parse_ok() {
         let src = r#"let x = Ok(10)"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::Ok(_)));
-            if let Expr::Ok(inner) = value {
-                assert!(matches!(inner.as_ref(), Expr::Number(10)));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::Ok(_)));
+            if let Expr::Ok(inner) = &value.node {
+                assert!(matches!(&inner.node, Expr::Number(10)));
             }
         } else {
             panic!("Expected Let statement");
@@ -2274,10 +3175,10 @@ mod tests {
     fn test_parse_err() {
         let src = r#"let x = Err("error")"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::Err(_)));
-            if let Expr::Err(inner) = value {
-                assert!(matches!(inner.as_ref(), Expr::String(_)));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::Err(_)));
+            if let Expr::Err(inner) = &value.node {
+                assert!(matches!(&inner.node, Expr::String(_)));
             }
         } else {
             panic!("Expected Let statement");
@@ -2293,10 +3194,10 @@ mod tests {
             }
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Expr(Expr::Match { expr, arms }) = &program.statements[0] {
-            assert!(matches!(expr.as_ref(), Expr::Ident(_)));
+        if let Stmt::Expr(Spanned { node: Expr::Match { expr, arms }, .. }) = &program.statements[0].node {
+            assert!(matches!(&expr.node, Expr::Ident(_)));
             assert_eq!(arms.len(), 2);
-            assert!(matches!(arms[0].pattern, Pattern::Some));
+            assert!(matches!(arms[0].pattern, Pattern::Some(_)));
             assert!(matches!(arms[1].pattern, Pattern::None));
         } else {
             panic!("Expected Match expression");
@@ -2313,10 +3214,10 @@ mod tests {
             }
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Expr(Expr::Match { arms, .. }) = &program.statements[0] {
+        if let Stmt::Expr(Spanned { node: Expr::Match { arms, .. }, .. }) = &program.statements[0].node {
             assert_eq!(arms.len(), 3);
-            assert!(matches!(arms[0].pattern, Pattern::Ok));
-            assert!(matches!(arms[1].pattern, Pattern::Err));
+            assert!(matches!(arms[0].pattern, Pattern::Ok(_)));
+            assert!(matches!(arms[1].pattern, Pattern::Err(_)));
             assert!(matches!(arms[2].pattern, Pattern::Wildcard));
         } else {
             panic!("Expected Match expression");
@@ -2333,7 +3234,7 @@ mod tests {
             }
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Expr(Expr::Match { arms, .. }) = &program.statements[0] {
+        if let Stmt::Expr(Spanned { node: Expr::Match { arms, .. }, .. }) = &program.statements[0].node {
             assert_eq!(arms.len(), 3);
             assert!(matches!(arms[0].pattern, Pattern::LiteralNumber(0)));
             assert!(matches!(arms[1].pattern, Pattern::LiteralNumber(1)));
@@ -2343,14 +3244,257 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_match_rich_patterns() {
+        let src = r#"
+            match x {
+                Some(Ok(n)) if n > 0 => n,
+                1 | 2 => 3,
+                -5..=5 => 0,
+                "hola" => 1,
+                (a, _) => a,
+                Punto { x, y: 0, .. } => x,
+                todo @ 10..20 => todo,
+                _ => 0
+            }
+        "#;
+        let program = parse(src).unwrap();
+        if let Stmt::Expr(Spanned { node: Expr::Match { arms, .. }, .. }) = &program.statements[0].node {
+            assert_eq!(arms.len(), 8);
+            assert!(arms[0].guard.is_some());
+            assert_eq!(
+                arms[0].pattern,
+                Pattern::Some(Box::new(Pattern::Ok(Box::new(Pattern::Ident("n".to_string())))))
+            );
+            assert_eq!(arms[1].pattern, Pattern::Or(vec![Pattern::LiteralNumber(1), Pattern::LiteralNumber(2)]));
+            assert_eq!(arms[2].pattern, Pattern::Range { start: -5, end: 5, inclusive: true });
+            assert_eq!(arms[3].pattern, Pattern::LiteralString("hola".to_string()));
+            assert_eq!(arms[4].pattern, Pattern::Tuple(vec![Pattern::Ident("a".to_string()), Pattern::Wildcard]));
+            assert_eq!(
+                arms[5].pattern,
+                Pattern::Struct {
+                    name: "Punto".to_string(),
+                    fields: vec![
+                        ("x".to_string(), Pattern::Ident("x".to_string())),
+                        ("y".to_string(), Pattern::LiteralNumber(0)),
+                    ],
+                }
+            );
+            assert_eq!(arms[6].pattern.bindings(), vec!["todo"]);
+            assert!(arms[7].pattern.is_irrefutable());
+        } else {
+            panic!("Expected Match expression");
+        }
+    }
+
+    #[test]
+    fn test_parse_generics() {
+        let src = r#"
+            fn max_of<T>(a: T, b: T) -> T { return a }
+            struct Par<A, B> {
+                primero: A
+                segundo: B
+                resto: [A]
+            }
+            let p: Par<int64, [string]> = Par { primero: 1, segundo: ["x"], resto: [] }
+        "#;
+        let program = parse(src).unwrap();
+        assert!(matches!(
+            &program.statements[0].node,
+            Stmt::Fn { type_params, return_type: Some(Type::Struct(t)), .. } if type_params == &["T"] && t == "T"
+        ));
+        if let Stmt::Struct { type_params, fields, .. } = &program.statements[1].node {
+            assert_eq!(type_params, &["A", "B"]);
+            assert_eq!(fields[1].ty, Some(Type::Struct("B".to_string())));
+            let array_of_a = Type::Array { element_type: Box::new(Type::Struct("A".to_string())), size: None };
+            assert_eq!(fields[2].ty, Some(array_of_a));
+        } else {
+            panic!("Expected Struct statement");
+        }
+        let expected = Type::Generic {
+            name: "Par".to_string(),
+            args: vec![Type::Int64, Type::Array { element_type: Box::new(Type::String), size: None }],
+        };
+        assert!(matches!(&program.statements[2].node, Stmt::Let { ty: Some(ty), .. } if *ty == expected));
+    }
+
+    #[test]
+    fn test_parse_function_type() {
+        let src = "fn aplicar(f: fn(int64) -> int64, x: int64) -> int64 {\n    return f(x)\n}\nlet g: fn() = saludar\n";
+        let program = parse(src).unwrap();
+        let expected = Type::Function { params: vec![Type::Int64], ret: Box::new(Type::Int64) };
+        assert!(matches!(
+            &program.statements[0].node,
+            Stmt::Fn { params, .. } if params[0].ty.as_ref() == Some(&expected)
+        ));
+        // Sin `->` la función no devuelve nada
+        let expected = Type::Function { params: vec![], ret: Box::new(Type::Void) };
+        assert!(matches!(&program.statements[1].node, Stmt::Let { ty: Some(ty), .. } if *ty == expected));
+    }
+
+    #[test]
+    fn test_parse_index_assign_and_dict_type() {
+        let src = r#"
+            let d: dict<string, int64> = {"a": 1}
+            d["b"] = 2
+        "#;
+        let program = parse(src).unwrap();
+        let expected = Type::Dict { key: Box::new(Type::String), value: Box::new(Type::Int64) };
+        assert!(matches!(&program.statements[0].node, Stmt::Let { ty: Some(ty), .. } if *ty == expected));
+        if let Stmt::Expr(Spanned { node: Expr::Assign { name, value }, .. }) = &program.statements[1].node {
+            assert_eq!(name, "_array_set");
+            assert!(matches!(
+                &value.node,
+                Expr::BinaryOp { left, right, .. }
+                    if matches!(&left.node, Expr::Index { .. }) && matches!(&right.node, Expr::Number(2))
+            ));
+        } else {
+            panic!("Expected index assignment");
+        }
+    }
+
+    #[test]
+    fn test_parse_set_comprehension_and_in() {
+        let src = r#"
+            let s: set<int64> = {x * 2 for x in lista if x > 0}
+            let b = 3 in s && true
+        "#;
+        let program = parse(src).unwrap();
+        if let Stmt::Let { ty, value, .. } = &program.statements[0].node {
+            assert_eq!(ty.as_ref(), Some(&Type::Set(Box::new(Type::Int64))));
+            assert!(matches!(&value.node, Expr::SetComprehension { clauses, .. } if clauses[0].var == "x" && clauses[0].conditions.len() == 1));
+        } else {
+            panic!("Expected let statement");
+        }
+        if let Stmt::Let { value: Spanned { node: Expr::BinaryOp { op: BinOp::And, left, .. }, .. }, .. } = &program.statements[1].node {
+            assert!(matches!(&left.node, Expr::BinaryOp { op: BinOp::In, .. }));
+        } else {
+            panic!("Expected `in` inside `&&`");
+        }
+    }
+
+    #[test]
+    fn test_parse_nested_comprehensions() {
+        let src = r#"
+            let pares = [x * y for x in xs if x > 0 if x < 9 for y in ys if y != x]
+            let cuadrados = {x: x * x for x in xs}
+        "#;
+        let program = parse(src).unwrap();
+        if let Stmt::Let { value: Spanned { node: Expr::ListComprehension { clauses, .. }, .. }, .. } = &program.statements[0].node {
+            assert_eq!(clauses.len(), 2);
+            assert_eq!((clauses[0].var.as_str(), clauses[0].conditions.len()), ("x", 2));
+            assert_eq!((clauses[1].var.as_str(), clauses[1].conditions.len()), ("y", 1));
+        } else {
+            panic!("Expected list comprehension");
+        }
+        assert!(matches!(
+            &program.statements[1].node,
+            Stmt::Let { value: Spanned { node: Expr::DictComprehension { clauses, .. }, .. }, .. } if clauses.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_parse_trait_and_impl() {
+        let src = r#"
+            trait Drawable {
+                fn area(self) -> float64
+                fn mover(self, dx: int64, dy: int64);
+            }
+            impl Drawable for Circle {
+                fn area(self) -> float64 { return 3.14 }
+                pub fn mover(self, dx: int64, dy: int64) { }
+            }
+            let formas: [Drawable] = []
+        "#;
+        let program = parse(src).unwrap();
+        if let Stmt::Trait { name, methods } = &program.statements[0].node {
+            assert_eq!(name, "Drawable");
+            assert_eq!(methods.len(), 2);
+            assert_eq!(methods[0].return_type, Some(Type::Float64));
+            assert_eq!(methods[1].params.len(), 3);
+        } else {
+            panic!("Expected Trait statement");
+        }
+        if let Stmt::Impl { trait_name, type_name, methods } = &program.statements[1].node {
+            assert_eq!((trait_name.as_str(), type_name.as_str()), ("Drawable", "Circle"));
+            assert_eq!(methods.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(), vec!["area", "mover"]);
+            assert_eq!(methods[1].1.visibility, Visibility::Public);
+        } else {
+            panic!("Expected Impl statement");
+        }
+        let array_of = |ty: Type| Type::Array { element_type: Box::new(ty), size: None };
+        assert!(matches!(
+            &program.statements[2].node,
+            Stmt::Let { ty: Some(ty), .. } if *ty == array_of(Type::Struct("Drawable".to_string()))
+        ));
+    }
+
+    #[test]
+    fn test_parse_enum_and_variants() {
+        let src = r#"
+            enum Shape {
+                Circle(radius: float64),
+                Rect(w, h),
+                Empty
+            }
+            let s = Shape.Rect(3, 4)
+            let e = Shape.Empty
+            let a = match s {
+                Circle(r) => 1,
+                Shape.Rect(w, _) => w,
+                Empty => 0
+            }
+        "#;
+        let program = parse(src).unwrap();
+        if let Stmt::Enum { name, variants } = &program.statements[0].node {
+            assert_eq!(name, "Shape");
+            assert_eq!(variants.len(), 3);
+            assert_eq!(variants[0].fields, vec![("radius".to_string(), Some(Type::Float64))]);
+            assert_eq!(variants[1].fields.len(), 2);
+            assert!(variants[2].fields.is_empty());
+        } else {
+            panic!("Expected Enum statement");
+        }
+        // Shape.Rect(3, 4) y Shape.Empty se resuelven como variantes, no como método/campo
+        assert!(matches!(
+            &program.statements[1].node,
+            Stmt::Let { value: Spanned { node: Expr::EnumVariant { enum_name, variant, args }, .. }, .. }
+                if enum_name == "Shape" && variant == "Rect" && args.len() == 2
+        ));
+        assert!(matches!(
+            &program.statements[2].node,
+            Stmt::Let { value: Spanned { node: Expr::EnumVariant { variant, args, .. }, .. }, .. } if variant == "Empty" && args.is_empty()
+        ));
+        if let Stmt::Let { value: Spanned { node: Expr::Match { arms, .. }, .. }, .. } = &program.statements[3].node {
+            let variant = |enum_name: &str, variant: &str, fields: Vec<Pattern>| Pattern::Variant {
+                enum_name: enum_name.to_string(),
+                variant: variant.to_string(),
+                fields,
+            };
+            assert_eq!(arms[0].pattern, variant("Shape", "Circle", vec![Pattern::Ident("r".to_string())]));
+            assert_eq!(
+                arms[1].pattern,
+                variant("Shape", "Rect", vec![Pattern::Ident("w".to_string()), Pattern::Wildcard])
+            );
+            assert_eq!(arms[2].pattern, variant("Shape", "Empty", vec![]));
+        } else {
+            panic!("Expected Match expression");
+        }
+
+        let err = parse("enum E { A(x) }\nlet v = E.A(1, 2)\n").unwrap_err();
+        assert!(err.to_string().contains("la variante 'E.A' espera 1 valor(es), se pasaron 2"));
+        let err = parse("enum E { A(x) }\nlet v = E.B\n").unwrap_err();
+        assert!(err.to_string().contains("el enum 'E' no tiene la variante 'B'"));
+    }
+
     #[test]
     fn test_parse_nested_some() {
         let src = r#"let x = Some(Some(42))"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::Some(_)));
-            if let Expr::Some(inner) = value {
-                assert!(matches!(inner.as_ref(), Expr::Some(_)));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::Some(_)));
+            if let Expr::Some(inner) = &value.node {
+                assert!(matches!(&inner.node, Expr::Some(_)));
             }
         } else {
             panic!("Expected Let statement");
@@ -2368,7 +3512,7 @@ mod tests {
             }
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Struct { name, fields, init, destroy, .. } = &program.statements[0] {
+        if let Stmt::Struct { name, fields, init, destroy, .. } = &program.statements[0].node {
             assert_eq!(name, "Persona");
             assert_eq!(fields.len(), 2);
             assert_eq!(fields[0].name, "nombre");
@@ -2391,7 +3535,7 @@ mod tests {
             }
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Struct { fields, init, destroy, .. } = &program.statements[0] {
+        if let Stmt::Struct { fields, init, destroy, .. } = &program.statements[0].node {
             assert_eq!(fields[0].mutable, true);  // Campo mutable
             assert_eq!(fields[0].visibility, Visibility::Private);  // Privado por defecto (O5)
             assert!(init.is_none());  // Sin constructor
@@ -2410,9 +3554,9 @@ mod tests {
             }
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::StructLiteral { .. }));
-            if let Expr::StructLiteral { name, fields } = value {
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::StructLiteral { .. }));
+            if let Expr::StructLiteral { name, fields } = &value.node {
                 assert_eq!(name, "Persona");
                 assert_eq!(fields.len(), 2);
                 assert_eq!(fields[0].0, "nombre");
@@ -2429,10 +3573,10 @@ mod tests {
             let nombre = p.nombre
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::FieldAccess { .. }));
-            if let Expr::FieldAccess { object, field } = value {
-                assert!(matches!(object.as_ref(), Expr::Ident(_)));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::FieldAccess { .. }));
+            if let Expr::FieldAccess { object, field } = &value.node {
+                assert!(matches!(&object.node, Expr::Ident(_)));
                 assert_eq!(field, "nombre");
             }
         } else {
@@ -2446,10 +3590,10 @@ mod tests {
             let resultado = objeto.metodo(10, 20)
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::MethodCall { .. }));
-            if let Expr::MethodCall { object, method, args } = value {
-                assert!(matches!(object.as_ref(), Expr::Ident(_)));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::MethodCall { .. }));
+            if let Expr::MethodCall { object, method, args } = &value.node {
+                assert!(matches!(&object.node, Expr::Ident(_)));
                 assert_eq!(method, "metodo");
                 assert_eq!(args.len(), 2);
             }
@@ -2463,11 +3607,11 @@ mod tests {
         // Test del operador ? para propagación de errores
         let src = r#"let valor = funcion()?"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::PropagateError(_)));
-            if let Expr::PropagateError(inner) = value {
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::PropagateError(_)));
+            if let Expr::PropagateError(inner) = &value.node {
                 // El inner debe ser una llamada a función
-                assert!(matches!(inner.as_ref(), Expr::Call { .. }));
+                assert!(matches!(&inner.node, Expr::Call { .. }));
             }
         } else {
             panic!("Expected Let statement with PropagateError");
@@ -2479,8 +3623,8 @@ mod tests {
         // Test de propagación con método: objeto.metodo()?
         let src = r#"let resultado = objeto.metodo()?"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::PropagateError(_)));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::PropagateError(_)));
         } else {
             panic!("Expected Let statement with PropagateError");
         }
@@ -2491,11 +3635,11 @@ mod tests {
         // Test de propagación con Ok(): Ok(42)?
         let src = r#"let valor = Ok(42)?"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::PropagateError(_)));
-            if let Expr::PropagateError(inner) = value {
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::PropagateError(_)));
+            if let Expr::PropagateError(inner) = &value.node {
                 // El inner debe ser Ok(42)
-                assert!(matches!(inner.as_ref(), Expr::Ok(_)));
+                assert!(matches!(&inner.node, Expr::Ok(_)));
             }
         } else {
             panic!("Expected Let statement with PropagateError");
@@ -2507,9 +3651,9 @@ mod tests {
         // Test de múltiples propagaciones: funcion1()? + funcion2()?
         let src = r#"let suma = funcion1()? + funcion2()?"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
             // Debe ser una BinaryOp con PropagateError en ambos lados
-            assert!(matches!(value, Expr::BinaryOp { .. }));
+            assert!(matches!(&value.node, Expr::BinaryOp { .. }));
         } else {
             panic!("Expected Let statement with BinaryOp");
         }
@@ -2522,27 +3666,69 @@ mod tests {
         // Test de literal de array: [1, 2, 3]
         let src = r#"let arr = [1, 2, 3]"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::ArrayLiteral(_)));
-            if let Expr::ArrayLiteral(elements) = value {
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::ArrayLiteral(_)));
+            if let Expr::ArrayLiteral(elements) = &value.node {
                 assert_eq!(elements.len(), 3);
-                assert!(matches!(elements[0], Expr::Number(1)));
-                assert!(matches!(elements[1], Expr::Number(2)));
-                assert!(matches!(elements[2], Expr::Number(3)));
+                assert!(matches!(elements[0], Spanned { node: Expr::Number(1), .. }));
+                assert!(matches!(elements[1], Spanned { node: Expr::Number(2), .. }));
+                assert!(matches!(elements[2], Spanned { node: Expr::Number(3), .. }));
             }
         } else {
             panic!("Expected Let statement with ArrayLiteral");
         }
     }
 
+    #[test]
+    fn test_parse_float_literals() {
+        // Todos los decimales y el exponente opcional; `0..3` sigue siendo un rango
+        let program = parse("let xs = [3.25, 5., 1.5e3, 2.0E-2, .5e1]\nfor i in 0..3 {\n    print i\n}\n").unwrap();
+        if let Stmt::Let { value: Spanned { node: Expr::ArrayLiteral(elements), .. }, .. } = &program.statements[0].node {
+            let values: Vec<f64> = elements
+                .iter()
+                .map(|e| match &e.node {
+                    Expr::Float(f) => *f,
+                    other => panic!("se esperaba un float: {:?}", other),
+                })
+                .collect();
+            assert_eq!(values, vec![3.25, 5.0, 1500.0, 0.02, 5.0]);
+        } else {
+            panic!("Expected Let statement with ArrayLiteral");
+        }
+        assert!(matches!(&program.statements[1].node, Stmt::For { .. }));
+    }
+
+    #[test]
+    fn test_parse_slices_with_step() {
+        // Límites y paso opcionales: s[1:3], s[::-1], s[-2:], s[:4:2]
+        let program = parse("let a = s[1:3]\nlet b = s[::-1]\nlet c = s[-2:]\nlet d = s[:4:2]\nlet e = s[-1]\n").unwrap();
+        let values: Vec<&Expr> = program
+            .statements
+            .iter()
+            .map(|stmt| match &stmt.node {
+                Stmt::Let { value, .. } => &value.node,
+                other => panic!("se esperaba un let: {:?}", other),
+            })
+            .collect();
+        let shape = |expr: &Expr| match expr {
+            Expr::Slice { start, end, step, .. } => (start.is_some(), end.is_some(), step.is_some()),
+            other => panic!("se esperaba un slice: {:?}", other),
+        };
+        assert_eq!(shape(values[0]), (true, true, false));
+        assert_eq!(shape(values[1]), (false, false, true));
+        assert_eq!(shape(values[2]), (true, false, false));
+        assert_eq!(shape(values[3]), (false, true, true));
+        assert!(matches!(values[4], Expr::Index { .. }));
+    }
+
     #[test]
     fn test_parse_array_literal_empty() {
         // Test de array vacío: []
         let src = r#"let arr = []"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::ArrayLiteral(_)));
-            if let Expr::ArrayLiteral(elements) = value {
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::ArrayLiteral(_)));
+            if let Expr::ArrayLiteral(elements) = &value.node {
                 assert_eq!(elements.len(), 0);
             }
         } else {
@@ -2555,11 +3741,11 @@ mod tests {
         // Test de indexación: arr[0]
         let src = r#"let valor = arr[0]"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::Index { .. }));
-            if let Expr::Index { array, index } = value {
-                assert!(matches!(array.as_ref(), Expr::Ident(_)));
-                assert!(matches!(index.as_ref(), Expr::Number(0)));
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::Index { .. }));
+            if let Expr::Index { array, index } = &value.node {
+                assert!(matches!(&array.node, Expr::Ident(_)));
+                assert!(matches!(&index.node, Expr::Number(0)));
             }
         } else {
             panic!("Expected Let statement with Index");
@@ -2571,16 +3757,16 @@ mod tests {
         // Test de indexación anidada: arr[i][j]
         let src = r#"let valor = matriz[i][j]"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::Index { .. }));
-            if let Expr::Index { array, index } = value {
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::Index { .. }));
+            if let Expr::Index { array, index } = &value.node {
                 // El array debe ser otro Index
-                assert!(matches!(array.as_ref(), Expr::Index { .. }));
-                if let Expr::Index { array: inner_array, index: inner_index } = array.as_ref() {
-                    assert!(matches!(inner_array.as_ref(), Expr::Ident(_)));
-                    assert!(matches!(inner_index.as_ref(), Expr::Ident(_)));
+                assert!(matches!(&array.node, Expr::Index { .. }));
+                if let Expr::Index { array: inner_array, index: inner_index } = &array.node {
+                    assert!(matches!(&inner_array.node, Expr::Ident(_)));
+                    assert!(matches!(&inner_index.node, Expr::Ident(_)));
                 }
-                assert!(matches!(index.as_ref(), Expr::Ident(_)));
+                assert!(matches!(&index.node, Expr::Ident(_)));
             }
         } else {
             panic!("Expected Let statement with nested Index");
@@ -2592,12 +3778,12 @@ mod tests {
         // Test de array con expresiones: [1 + 2, 3 * 4]
         let src = r#"let arr = [1 + 2, 3 * 4]"#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::ArrayLiteral(_)));
-            if let Expr::ArrayLiteral(elements) = value {
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::ArrayLiteral(_)));
+            if let Expr::ArrayLiteral(elements) = &value.node {
                 assert_eq!(elements.len(), 2);
-                assert!(matches!(elements[0], Expr::BinaryOp { .. }));
-                assert!(matches!(elements[1], Expr::BinaryOp { .. }));
+                assert!(matches!(elements[0], Spanned { node: Expr::BinaryOp { .. }, .. }));
+                assert!(matches!(elements[1], Spanned { node: Expr::BinaryOp { .. }, .. }));
             }
         } else {
             panic!("Expected Let statement with ArrayLiteral containing expressions");
@@ -2610,15 +3796,135 @@ mod tests {
             let valor = objeto.campo.subcampo
         "#;
         let program = parse(src).unwrap();
-        if let Stmt::Let { value, .. } = &program.statements[0] {
-            assert!(matches!(value, Expr::FieldAccess { .. }));
-            if let Expr::FieldAccess { object, field } = value {
+        if let Stmt::Let { value, .. } = &program.statements[0].node {
+            assert!(matches!(&value.node, Expr::FieldAccess { .. }));
+            if let Expr::FieldAccess { object, field } = &value.node {
                 assert_eq!(field, "subcampo");
                 // El object deber├¡a ser otro FieldAccess
-                assert!(matches!(object.as_ref(), Expr::FieldAccess { .. }));
+                assert!(matches!(&object.node, Expr::FieldAccess { .. }));
             }
         } else {
             panic!("Expected Let statement");
         }
     }
+
+    #[test]
+    fn test_parse_access_after_index() {
+        // Indexación y acceso en cualquier orden; "1.hablar" no es un float
+        let program = parse("let a = xs[1].hablar()\nlet b = xs[0].nombre\nlet c = o.items[2].nombre\n").unwrap();
+        let values: Vec<&Expr> = program
+            .statements
+            .iter()
+            .map(|stmt| match &stmt.node {
+                Stmt::Let { value, .. } => &value.node,
+                other => panic!("se esperaba un let: {:?}", other),
+            })
+            .collect();
+        assert!(matches!(values[0], Expr::MethodCall { object, method, .. }
+            if method == "hablar" && matches!(&object.node, Expr::Index { index, .. } if matches!(&index.node, Expr::Number(1)))));
+        assert!(matches!(values[1], Expr::FieldAccess { object, field }
+            if field == "nombre" && matches!(&object.node, Expr::Index { .. })));
+        assert!(matches!(values[2], Expr::FieldAccess { object, .. }
+            if matches!(&object.node, Expr::Index { array, .. } if matches!(&array.node, Expr::FieldAccess { .. }))));
+    }
+
+    #[test]
+    fn test_statement_spans() {
+        let src = "let x = 1\n\n  # comentario\n  print x\n";
+        let program = parse(src).unwrap();
+        assert_eq!(program.statements.len(), 2);
+        let print_span = program.statements[1].span;
+        assert_eq!(&src[print_span.start..print_span.end], "print x");
+        assert_eq!(print_span.line_col(src), (4, 3));
+    }
+
+    #[test]
+    fn test_nested_statement_spans() {
+        let src = "while x < 3 {\n    print x\n}";
+        let program = parse(src).unwrap();
+        if let Stmt::While { body, .. } = &program.statements[0].node {
+            assert_eq!(body[0].span.line_col(src), (2, 5));
+        } else {
+            panic!("Expected While statement");
+        }
+    }
+
+    #[test]
+    fn test_expression_spans() {
+        let src = "let s = \"ñ\"\nprint s + f(1, 2 * x)\n";
+        let program = parse(src).unwrap();
+        if let Stmt::Print(expr) = &program.statements[1].node {
+            assert_eq!(&src[expr.span.start..expr.span.end], "s + f(1, 2 * x)");
+            if let Expr::BinaryOp { right, .. } = &expr.node {
+                if let Expr::Call { args, .. } = &right.node {
+                    assert_eq!(&src[args[1].span.start..args[1].span.end], "2 * x");
+                    assert_eq!(args[1].span.line_col(src), (2, 16));
+                    return;
+                }
+            }
+        }
+        panic!("Expected print s + f(...)");
+    }
+
+    #[test]
+    fn test_parse_for_loops() {
+        let src = "for i in range(10, 0, -2) {\n    print i\n}\nfor (i, x) in enumerate(xs) {\n    print x\n}\nfor i in 0..n {\n    print i\n}\n";
+        let program = parse(src).unwrap();
+        match &program.statements[0].node {
+            Stmt::For { vars, iter: ForIter::Range { start, end, step }, .. } => {
+                assert_eq!(vars, &vec!["i".to_string()]);
+                assert!(matches!(&start.node, Expr::Number(10)));
+                assert!(matches!(&end.node, Expr::Number(0)));
+                assert!(matches!(step, Some(Spanned { node: Expr::Number(-2), .. })));
+            }
+            other => panic!("Expected range For, got {:?}", other),
+        }
+        match &program.statements[1].node {
+            Stmt::For { vars, iter: ForIter::Iterable(Spanned { node: Expr::Call { name, .. }, .. }), .. } => {
+                assert_eq!(vars, &vec!["i".to_string(), "x".to_string()]);
+                assert_eq!(name, "enumerate");
+            }
+            other => panic!("Expected enumerate For, got {:?}", other),
+        }
+        assert!(matches!(&program.statements[2].node, Stmt::For { iter: ForIter::Range { step: None, .. }, .. }));
+    }
+
+    #[test]
+    fn test_syntax_errors_report_every_statement() {
+        let src = "let x = 1\nlet y = )\nprint x\nwhile x < 3 {\n    let z = (1\n}\nprint x\n";
+        match parse(src) {
+            Err(ADeadError::SyntaxErrors(diagnostics)) => {
+                let lines: Vec<usize> = diagnostics.iter().map(|d| d.span.line_col(src).0).collect();
+                // El paréntesis sin cerrar se detecta en la `}` de la línea 6
+                assert_eq!(lines, vec![2, 6], "{:?}", diagnostics);
+            }
+            other => panic!("Expected SyntaxErrors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_comments_inside_blocks() {
+        let src = "\u{feff}struct Punto extends Base {\n    # coordenadas\n    x\n}\nwhile 1 < 2 {\n    # comentario\n    print \"# no es comentario\"\n}\n";
+        let program = parse(src).unwrap();
+        assert_eq!(program.statements.len(), 2);
+        if let Stmt::Struct { parent, fields, .. } = &program.statements[0].node {
+            assert_eq!(parent.as_deref(), Some("Base"));
+            assert_eq!(fields.len(), 1);
+        } else {
+            panic!("Expected Struct statement");
+        }
+        if let Stmt::While { body, .. } = &program.statements[1].node {
+            assert_eq!(body[0].node, Stmt::Print(Expr::String("# no es comentario".to_string()).into()));
+        } else {
+            panic!("Expected While statement");
+        }
+    }
+
+    #[test]
+    fn test_syntax_error_line_col_multibyte() {
+        // Los offsets de chumsky son de caracteres: el span debe convertirse a bytes
+        let src = "# año\nprint 1\nlet y = )\n";
+        let err = parse(src).unwrap_err();
+        assert_eq!(err.line_col(src), Some((3, 9)), "{:?}", err);
+    }
 }
@@ -52,7 +52,7 @@ impl ParallelPipeline {
         
         // Crear directorio de cache si no existe
         if let Err(e) = fs::create_dir_all(&cache_dir) {
-            eprintln!("⚠️  Warning: No se pudo crear directorio de cache: {}", e);
+            adead_common::log_warn!(adead_common::log::Phase::Parser, "No se pudo crear directorio de cache: {}", e);
         }
         
         Self {
@@ -229,7 +229,7 @@ impl ParallelPipeline {
                 }
                 Err(e) => {
                     // Cache hit pero error al escribir, continuar con compilación
-                    eprintln!("⚠️  Warning: Cache hit pero error al escribir: {}", e);
+                    adead_common::log_warn!(adead_common::log::Phase::Parser, "Cache hit pero error al escribir: {}", e);
                 }
             }
         }
@@ -48,7 +48,7 @@ impl ParserDebugger {
                 Stmt::Fn { name, params, body, .. } => {
                     functions.push((i, name.clone(), params.len(), body.len()));
                 }
-                Stmt::Let { name, mutable, value } => {
+                Stmt::Let { name, mutable, value, .. } => {
                     let value_type = format!("{:?}", value);
                     let value_preview = if value_type.len() > 50 {
                         format!("{}...", &value_type[..50])
@@ -237,12 +237,12 @@ fn generate_via_c(source: &str, output_path: Option<&Path>) -> Result<String, St
             if is_valid_asm(&asm_code) {
                 Ok(crate::clean_asm::clean_asm(&asm_code))
             } else {
-                eprintln!("   ⚠️  ASM generado no es válido, retornando código C");
+                adead_common::log_warn!(adead_common::log::Phase::Backend, "ASM generado no es válido, retornando código C");
                 Ok(format!("// Código C generado\n{}", c_code))
             }
         }
         Err(e) => {
-            eprintln!("   ⚠️  Error compilando C a ASM: {}", e);
+            adead_common::log_warn!(adead_common::log::Phase::Backend, "Error compilando C a ASM: {}", e);
             Ok(format!("// Código C generado\n{}", c_code))
         }
     }
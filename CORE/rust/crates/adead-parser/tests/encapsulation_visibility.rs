@@ -9,7 +9,7 @@ fn test_parse_struct_with_public_fields() {
         }
     "#;
     let program = parse(src).unwrap();
-    if let adead_parser::Stmt::Struct { fields, .. } = &program.statements[0] {
+    if let adead_parser::Stmt::Struct { fields, .. } = &program.statements[0].node {
         assert_eq!(fields.len(), 2);
         assert_eq!(fields[0].name, "nombre");
         assert_eq!(fields[0].visibility, Visibility::Public);  // Campo público
@@ -29,7 +29,7 @@ fn test_parse_struct_all_private_by_default() {
         }
     "#;
     let program = parse(src).unwrap();
-    if let adead_parser::Stmt::Struct { fields, .. } = &program.statements[0] {
+    if let adead_parser::Stmt::Struct { fields, .. } = &program.statements[0].node {
         assert_eq!(fields.len(), 2);
         assert_eq!(fields[0].visibility, Visibility::Private);  // Privado por defecto
         assert_eq!(fields[1].visibility, Visibility::Private);  // Privado por defecto
@@ -49,7 +49,7 @@ fn test_parse_struct_with_public_init() {
         }
     "#;
     let program = parse(src).unwrap();
-    if let adead_parser::Stmt::Struct { init, .. } = &program.statements[0] {
+    if let adead_parser::Stmt::Struct { init, .. } = &program.statements[0].node {
         assert!(init.is_some());
         if let Some(init_method) = init {
             assert_eq!(init_method.visibility, Visibility::Public);
@@ -70,7 +70,7 @@ fn test_parse_struct_with_private_destroy() {
         }
     "#;
     let program = parse(src).unwrap();
-    if let adead_parser::Stmt::Struct { destroy, .. } = &program.statements[0] {
+    if let adead_parser::Stmt::Struct { destroy, .. } = &program.statements[0].node {
         assert!(destroy.is_some());
         if let Some(destroy_method) = destroy {
             assert_eq!(destroy_method.visibility, Visibility::Private);  // Privado por defecto
@@ -96,7 +96,7 @@ fn test_parse_struct_mixed_visibility() {
         }
     "#;
     let program = parse(src).unwrap();
-    if let adead_parser::Stmt::Struct { fields, init, destroy, .. } = &program.statements[0] {
+    if let adead_parser::Stmt::Struct { fields, init, destroy, .. } = &program.statements[0].node {
         assert_eq!(fields.len(), 2);
         assert_eq!(fields[0].visibility, Visibility::Public);  // nombre es público
         assert_eq!(fields[1].visibility, Visibility::Private);  // saldo es privado
@@ -29,7 +29,7 @@ fn test_parse_struct_with_methods_and_fields_visibility() {
         }
     "#;
     let program = parse(src).unwrap();
-    if let Stmt::Struct { name, fields, init, destroy, .. } = &program.statements[0] {
+    if let Stmt::Struct { name, fields, init, destroy, .. } = &program.statements[0].node {
         assert_eq!(name, "Banco");
         
         // Verificar campos
@@ -71,15 +71,15 @@ fn test_parse_struct_literal_with_public_fields() {
     let program = parse(src).unwrap();
     
     // Verificar que el struct se parseó
-    if let Stmt::Struct { .. } = &program.statements[0] {
+    if let Stmt::Struct { .. } = &program.statements[0].node {
         // OK
     } else {
         panic!("Expected Struct statement");
     }
     
     // Verificar el literal de struct
-    if let Stmt::Let { value, .. } = &program.statements[1] {
-        if let Expr::StructLiteral { name, fields } = value {
+    if let Stmt::Let { value, .. } = &program.statements[1].node {
+        if let Expr::StructLiteral { name, fields } = &value.node {
             assert_eq!(name, "Persona");
             assert_eq!(fields.len(), 2);
         } else {
@@ -90,9 +90,9 @@ fn test_parse_struct_literal_with_public_fields() {
     }
     
     // Verificar acceso a campo
-    if let Stmt::Let { value, .. } = &program.statements[2] {
-        if let Expr::FieldAccess { object, field } = value {
-            if let Expr::Ident(var_name) = object.as_ref() {
+    if let Stmt::Let { value, .. } = &program.statements[2].node {
+        if let Expr::FieldAccess { object, field } = &value.node {
+            if let Expr::Ident(var_name) = &object.node {
                 assert_eq!(var_name, "p");
             } else {
                 panic!("Expected Ident in FieldAccess");
@@ -127,14 +127,14 @@ fn test_parse_nested_struct_with_visibility() {
     assert_eq!(program.statements.len(), 5);
     
     // Verificar primer struct
-    if let Stmt::Struct { name: name1, fields: fields1, .. } = &program.statements[0] {
+    if let Stmt::Struct { name: name1, fields: fields1, .. } = &program.statements[0].node {
         assert_eq!(name1, "Direccion");
         assert_eq!(fields1[0].visibility, Visibility::Public);
         assert_eq!(fields1[1].visibility, Visibility::Private);
     }
     
     // Verificar segundo struct
-    if let Stmt::Struct { name: name2, fields: fields2, .. } = &program.statements[1] {
+    if let Stmt::Struct { name: name2, fields: fields2, .. } = &program.statements[1].node {
         assert_eq!(name2, "Persona");
         assert_eq!(fields2[0].visibility, Visibility::Public);
         assert_eq!(fields2[1].visibility, Visibility::Private);
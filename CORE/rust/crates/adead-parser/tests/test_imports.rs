@@ -9,7 +9,7 @@ fn test_import_statement_parsing() {
     let program = parse_with_dir(source, None).unwrap();
     
     assert_eq!(program.statements.len(), 1);
-    if let Stmt::Import(module_name) = &program.statements[0] {
+    if let Stmt::Import(module_name) = &program.statements[0].node {
         assert_eq!(module_name, "math");
     } else {
         panic!("Expected Import statement");
@@ -28,7 +28,7 @@ import strings
     let imports: Vec<_> = program.statements
         .iter()
         .filter_map(|s| {
-            if let Stmt::Import(name) = s {
+            if let Stmt::Import(name) = &s.node {
                 Some(name.clone())
             } else {
                 None
@@ -68,7 +68,7 @@ fn private_func() {
     let functions: Vec<_> = program.statements
         .iter()
         .filter_map(|s| {
-            if let Stmt::Fn { visibility, name, .. } = s {
+            if let Stmt::Fn { visibility, name, .. } = &s.node {
                 Some((visibility.clone(), name.clone()))
             } else {
                 None
@@ -132,7 +132,7 @@ mod integration {
         
         // Verificar que se importó
         assert!(program.statements.iter().any(|s| {
-            if let Stmt::Fn { name, .. } = s {
+            if let Stmt::Fn { name, .. } = &s.node {
                 name == "add"
             } else {
                 false
@@ -159,7 +159,7 @@ fn private_func() {
         let functions: Vec<_> = program.statements
             .iter()
             .filter_map(|s| {
-                if let Stmt::Fn { name, .. } = s {
+                if let Stmt::Fn { name, .. } = &s.node {
                     Some(name.clone())
                 } else {
                     None
@@ -7,8 +7,8 @@ mod tests {
         let src = r#"print 42"#;
         let program = parse(src).unwrap();
         assert_eq!(
-            program.statements[0],
-            Stmt::Print(Expr::Number(42))
+            program.statements[0].node,
+            Stmt::Print(Expr::Number(42).into())
         );
     }
 
@@ -16,8 +16,8 @@ mod tests {
     fn test_parse_print_arithmetic() {
         let src = r#"print 2 + 5"#;
         let program = parse(src).unwrap();
-        match &program.statements[0] {
-            Stmt::Print(Expr::BinaryOp { op, left, right }) => {
+        match &program.statements[0].node {
+            Stmt::Print(Spanned { node: Expr::BinaryOp { op, left, right }, .. }) => {
                 assert_eq!(*op, BinOp::Add);
                 assert_eq!(*left.as_ref(), Expr::Number(2));
                 assert_eq!(*right.as_ref(), Expr::Number(5));
@@ -30,8 +30,8 @@ mod tests {
     fn test_parse_print_multiplication() {
         let src = r#"print 10 * 3"#;
         let program = parse(src).unwrap();
-        match &program.statements[0] {
-            Stmt::Print(Expr::BinaryOp { op, left, right }) => {
+        match &program.statements[0].node {
+            Stmt::Print(Spanned { node: Expr::BinaryOp { op, left, right }, .. }) => {
                 assert_eq!(*op, BinOp::Mul);
             }
             _ => panic!("Expected BinaryOp"),
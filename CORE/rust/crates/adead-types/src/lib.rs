@@ -0,0 +1,2473 @@
+//! Type checker para ADead - Inferencia y verificación estática de tipos
+//!
+//! Asigna un `adead_common::Type` a cada expresión del programa y reporta
+//! combinaciones inválidas (por ejemplo sumar un string con un int64):
+//! - Literales: int64, float64, bool, string
+//! - Variables: tipo anotado (`let x: int8 = 5`) o el del valor con el que se declararon
+//! - Operadores: promoción int → float, concatenación string + string, comparaciones → bool
+//! - Funciones y métodos: tipo de retorno declarado (`-> int64`) o inferido de sus `return`;
+//!   los parámetros anotados (`n: int32`) se verifican en cada llamada
+//! - Structs: `Type::Struct(nombre)` y campos con tipo declarado (`edad: int64`)
+//! - Traits: cada `impl Trait for Struct` debe definir los métodos del trait; un struct
+//!   que lo implementa puede usarse donde se espera el trait (`Type::Trait`)
+//! - Genéricos: los parámetros de tipo (`fn max_of<T>`, `struct Caja<T>`) se infieren en
+//!   cada llamada o literal a partir de los argumentos; `generic_args` los registra para
+//!   que el backend genere una copia especializada por cada combinación de tipos
+//! - Dicts: `Type::Dict { key, value }` (`dict<string, int64>`); las claves son enteros
+//!   o strings y todas las claves y todos los valores de un dict comparten tipo
+//! - Sets: `Type::Set(elemento)` (`set<int64>`), con elementos enteros o strings;
+//!   `x in s` también consulta las claves de un dict
+//! - For: `for x in a` recorre arrays, strings (por carácter), dicts (claves) y sets;
+//!   `for (i, x) in enumerate(a)`, `zip(a, b)` y `d.items()` se desestructuran
+//!
+//! El resultado (`TypedProgram`) es el AST anotado: `type_of(expr)` devuelve el tipo
+//! de cualquier expresión del programa. El backend lo usa para elegir registros
+//! XMM o de propósito general en lugar de adivinar por el nombre de la variable.
+//! Lo que no se puede inferir queda como `Type::Unknown` y no genera errores.
+
+use adead_common::{ADeadError, Result, Type};
+use adead_parser::{BinOp, ComprehensionClause, Expr, ForIter, FStringPart, FnParam, Pattern, Program, Spanned, Stmt, StructField, StructMethod};
+use std::collections::HashMap;
+
+mod patterns;
+
+/// Tipos de las expresiones de un programa, indexados por la dirección de cada nodo.
+/// Solo es válido mientras el `Program` analizado no se modifique ni se mueva.
+#[derive(Debug, Default, Clone)]
+pub struct ExprTypes {
+    types: HashMap<usize, Type>,
+}
+
+impl ExprTypes {
+    fn key(expr: &Expr) -> usize {
+        expr as *const Expr as usize
+    }
+
+    fn insert(&mut self, expr: &Expr, ty: Type) {
+        self.types.insert(Self::key(expr), ty);
+    }
+
+    /// Tipo de una expresión del programa (`Unknown` si no se pudo inferir)
+    pub fn get(&self, expr: &Expr) -> &Type {
+        self.types.get(&Self::key(expr)).unwrap_or(&Type::Unknown)
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+}
+
+/// Argumentos de tipo inferidos en cada uso de una función o struct genérico
+/// (llamadas y struct literals), indexados igual que `ExprTypes`
+#[derive(Debug, Default, Clone)]
+pub struct GenericArgs {
+    args: HashMap<usize, Vec<Type>>,
+}
+
+impl GenericArgs {
+    /// Argumentos de tipo de una llamada o literal genérico, en el orden de declaración
+    pub fn get(&self, expr: &Expr) -> Option<&[Type]> {
+        self.args.get(&ExprTypes::key(expr)).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+}
+
+/// AST anotado: el programa junto con el tipo de cada expresión
+pub struct TypedProgram<'a> {
+    pub program: &'a Program,
+    pub types: ExprTypes,
+    /// Instanciación de cada uso de una función o struct genérico
+    pub generic_args: GenericArgs,
+    /// Tipo de retorno inferido de cada función de nivel superior
+    pub functions: HashMap<String, Type>,
+}
+
+impl<'a> TypedProgram<'a> {
+    pub fn type_of(&self, expr: &Expr) -> &Type {
+        self.types.get(expr)
+    }
+}
+
+/// Firma de una función genérica tal como se declaró: los parámetros de tipo se
+/// sustituyen en cada llamada
+#[derive(Debug, Clone)]
+struct GenericFn {
+    type_params: Vec<String>,
+    params: Vec<FnParam>,
+    return_type: Option<Type>,
+}
+
+/// Struct genérico: tipos declarados de sus campos y retornos de sus métodos
+#[derive(Debug, Clone)]
+struct GenericStruct {
+    type_params: Vec<String>,
+    fields: HashMap<String, Type>,
+    methods: HashMap<String, Option<Type>>,
+    /// Parámetros del init (`Stack.new(...)`), si lo tiene
+    init_params: Option<Vec<FnParam>>,
+}
+
+/// Información de un struct necesaria para tipar accesos a campos y métodos
+#[derive(Debug, Clone, Default)]
+struct StructInfo {
+    fields: HashMap<String, Type>,
+    methods: HashMap<String, Type>,
+    parent: Option<String>,
+}
+
+/// Type Checker - Infiere el tipo de cada expresión y verifica las operaciones
+pub struct TypeChecker {
+    /// Stack de scopes: nombre de variable -> tipo (el primero es el global)
+    scopes: Vec<HashMap<String, Type>>,
+    /// Tipo de retorno de cada función
+    functions: HashMap<String, Type>,
+    /// Parámetros anotados de cada función (nombre, tipo o `Unknown` si no tiene anotación)
+    signatures: HashMap<String, Vec<(String, Type)>>,
+    structs: HashMap<String, StructInfo>,
+    /// Variantes de cada enum con los tipos de su payload (en orden de declaración)
+    enums: HashMap<String, Vec<(String, Vec<Type>)>>,
+    /// Métodos requeridos por cada trait: nombre, número de parámetros (con self) y retorno
+    traits: HashMap<String, Vec<(String, usize, Type)>>,
+    /// Traits implementados por cada struct (`impl Trait for Struct`)
+    impls: HashMap<String, Vec<String>>,
+    generic_fns: HashMap<String, GenericFn>,
+    generic_structs: HashMap<String, GenericStruct>,
+    /// > 0 mientras se verifica el cuerpo de una declaración genérica: ahí los
+    /// parámetros de tipo son desconocidos y no se exige inferir instancias
+    generic_depth: usize,
+    /// Tipos de retorno encontrados en la función que se está verificando
+    returns: Vec<Type>,
+    /// Tipo de retorno anotado de la función que se está verificando
+    declared_return: Option<Type>,
+    /// Struct cuyo método se está verificando (tipo de `self`)
+    current_struct: Option<String>,
+    types: ExprTypes,
+    generic_args: GenericArgs,
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            signatures: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            traits: HashMap::new(),
+            impls: HashMap::new(),
+            generic_fns: HashMap::new(),
+            generic_structs: HashMap::new(),
+            generic_depth: 0,
+            returns: Vec::new(),
+            declared_return: None,
+            current_struct: None,
+            types: ExprTypes::default(),
+            generic_args: GenericArgs::default(),
+        }
+    }
+
+    /// Verificar un programa completo y devolver el AST anotado con tipos
+    pub fn check<'a>(mut self, program: &'a Program) -> Result<TypedProgram<'a>> {
+        // Primera pasada: enums, traits y structs (campos con tipo declarado). Los nombres
+        // de enums y traits se registran primero para que los campos puedan usarlos como tipo.
+        for stmt in &program.statements {
+            match &stmt.node {
+                Stmt::Enum { name, .. } => {
+                    self.enums.insert(name.clone(), Vec::new());
+                }
+                Stmt::Trait { name, .. } => {
+                    self.traits.insert(name.clone(), Vec::new());
+                }
+                Stmt::Impl { trait_name, type_name, .. } => {
+                    self.impls.entry(type_name.clone()).or_default().push(trait_name.clone());
+                }
+                // Las funciones existen desde el inicio: una puede llamar a otra declarada después
+                Stmt::Fn { name, .. } => {
+                    self.functions.insert(name.clone(), Type::Unknown);
+                }
+                _ => {}
+            }
+        }
+        for stmt in &program.statements {
+            if let Stmt::Struct { name, type_params, parent, fields, init, methods, .. } = &stmt.node {
+                if !type_params.is_empty() {
+                    let generic = GenericStruct {
+                        type_params: type_params.clone(),
+                        fields: fields
+                            .iter()
+                            .map(|f| (f.name.clone(), f.ty.clone().unwrap_or(Type::Unknown)))
+                            .collect(),
+                        methods: methods.iter().map(|(m, method)| (m.clone(), method.return_type.clone())).collect(),
+                        init_params: init.as_ref().map(|method| method.params.clone()),
+                    };
+                    self.generic_structs.insert(name.clone(), generic);
+                }
+                let info = StructInfo {
+                    fields: HashMap::new(),
+                    methods: HashMap::new(),
+                    parent: parent.clone(),
+                };
+                self.structs.insert(name.clone(), info);
+                let field_types = fields
+                    .iter()
+                    .map(|field| (field.name.clone(), self.field_type(field)))
+                    .collect();
+                self.structs.get_mut(name).unwrap().fields = field_types;
+            }
+        }
+        for stmt in &program.statements {
+            if let Stmt::Enum { name, variants } = &stmt.node {
+                let variants = variants
+                    .iter()
+                    .map(|variant| {
+                        let payload = variant
+                            .fields
+                            .iter()
+                            .map(|(_, ty)| ty.as_ref().map_or(Type::Unknown, |ty| self.resolve(ty)))
+                            .collect();
+                        (variant.name.clone(), payload)
+                    })
+                    .collect();
+                self.enums.insert(name.clone(), variants);
+            }
+            if let Stmt::Trait { name, methods } = &stmt.node {
+                let methods = methods
+                    .iter()
+                    .map(|method| {
+                        let ret = method.return_type.as_ref().map_or(Type::Unknown, |ty| self.resolve(ty));
+                        (method.name.clone(), method.params.len(), ret)
+                    })
+                    .collect();
+                self.traits.insert(name.clone(), methods);
+            }
+        }
+
+        // Segunda pasada: tipos de retorno de funciones y métodos. Se infieren en orden,
+        // así una función puede usar el tipo de retorno de las declaradas antes.
+        for stmt in &program.statements {
+            match &stmt.node {
+                Stmt::Fn { name, type_params, params, return_type, body, .. } => {
+                    if !type_params.is_empty() {
+                        let generic = GenericFn {
+                            type_params: type_params.clone(),
+                            params: params.clone(),
+                            return_type: return_type.clone(),
+                        };
+                        self.generic_fns.insert(name.clone(), generic);
+                        self.generic_depth += 1;
+                    }
+                    let owner = self.associated_struct(name, params);
+                    let ret = self.infer_return(owner.as_ref(), params, return_type.as_ref(), body);
+                    if !type_params.is_empty() {
+                        self.generic_depth -= 1;
+                    }
+                    if let Some(owner) = &owner {
+                        let method_name = &name[owner.len() + 1..];
+                        self.structs.get_mut(owner).unwrap().methods.insert(method_name.to_string(), ret.clone());
+                    }
+                    let signature = params
+                        .iter()
+                        .map(|p| (p.name.clone(), self.param_type(p)))
+                        .collect();
+                    self.functions.insert(name.clone(), ret);
+                    self.signatures.insert(name.clone(), signature);
+                }
+                Stmt::Struct { name, init, methods, .. } => {
+                    for (method_name, method) in methods {
+                        let declared = method.return_type.as_ref();
+                        let ret = self.infer_return(Some(name), &method.params, declared, &method.body);
+                        if let Some(info) = self.structs.get_mut(name) {
+                            info.methods.insert(method_name.clone(), ret);
+                        }
+                    }
+                    if init.is_some() {
+                        if let Some(info) = self.structs.get_mut(name) {
+                            info.methods.insert("new".to_string(), Type::Struct(name.clone()));
+                        }
+                    }
+                }
+                // Los métodos de un impl son métodos más del struct
+                Stmt::Impl { type_name, methods, .. } if self.structs.contains_key(type_name) => {
+                    for (method_name, method) in methods {
+                        let declared = method.return_type.as_ref();
+                        let ret = self.infer_return(Some(type_name), &method.params, declared, &method.body);
+                        if let Some(info) = self.structs.get_mut(type_name) {
+                            info.methods.insert(method_name.clone(), ret);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.types = ExprTypes::default();
+        self.generic_args = GenericArgs::default();
+
+        // Tercera pasada: verificar todo el programa registrando el tipo de cada expresión
+        for stmt in &program.statements {
+            self.check_spanned_stmt(stmt)?;
+        }
+
+        Ok(TypedProgram {
+            program,
+            types: self.types,
+            generic_args: self.generic_args,
+            functions: self.functions,
+        })
+    }
+
+    /// Tipo declarado de un campo (`None` o un nombre desconocido = inferido)
+    fn field_type(&self, field: &StructField) -> Type {
+        field.ty.as_ref().map_or(Type::Unknown, |ty| self.resolve(ty))
+    }
+
+    /// Tipo de una anotación: un struct que no está definido queda como `Unknown`
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Struct(name) => self.type_from_name(name),
+            Type::Array { element_type, size } => Type::Array {
+                element_type: Box::new(self.resolve(element_type)),
+                size: *size,
+            },
+            // Una instancia ya generada (`Caja__int64`) es un struct más
+            Type::Generic { name, args } if self.structs.contains_key(&instance_name(name, args)) => {
+                Type::Struct(instance_name(name, args))
+            }
+            Type::Generic { name, args } => Type::Generic {
+                name: name.clone(),
+                args: args.iter().map(|arg| self.resolve(arg)).collect(),
+            },
+            Type::Function { params, ret } => Type::Function {
+                params: params.iter().map(|param| self.resolve(param)).collect(),
+                ret: Box::new(self.resolve(ret)),
+            },
+            other => other.clone(),
+        }
+    }
+
+    fn param_type(&self, param: &FnParam) -> Type {
+        param.ty.as_ref().map_or(Type::Unknown, |ty| self.resolve(ty))
+    }
+
+    fn type_from_name(&self, name: &str) -> Type {
+        Type::from_name(name).unwrap_or_else(|| {
+            if self.structs.contains_key(name) {
+                Type::Struct(name.to_string())
+            } else if self.enums.contains_key(name) {
+                Type::Enum(name.to_string())
+            } else if self.traits.contains_key(name) {
+                Type::Trait(name.to_string())
+            } else {
+                Type::Unknown
+            }
+        })
+    }
+
+    /// Inferir el tipo de retorno de una función (sin reportar errores: eso lo hace la tercera pasada).
+    /// Si está anotado, manda la anotación.
+    fn infer_return(
+        &mut self,
+        struct_name: Option<&String>,
+        params: &[FnParam],
+        declared: Option<&Type>,
+        body: &[Spanned<Stmt>],
+    ) -> Type {
+        if let Some(ty) = declared {
+            return self.resolve(ty);
+        }
+        self.enter_function(struct_name, params, None);
+        for stmt in body {
+            if self.check_stmt(&stmt.node).is_err() {
+                break;
+            }
+        }
+        self.leave_function()
+    }
+
+    /// Struct del que una función global `Struct_metodo(self, ...)` es método de
+    /// instancia (el backend la genera junto con los métodos del struct)
+    fn associated_struct(&self, name: &str, params: &[FnParam]) -> Option<String> {
+        let (struct_name, _) = name.split_once('_')?;
+        let is_instance_method = params.first().map_or(false, |p| p.name == "self");
+        (is_instance_method && self.structs.contains_key(struct_name)).then(|| struct_name.to_string())
+    }
+
+    fn enter_function(&mut self, struct_name: Option<&String>, params: &[FnParam], declared: Option<&Type>) {
+        let mut scope: HashMap<String, Type> =
+            params.iter().map(|p| (p.name.clone(), self.param_type(p))).collect();
+        if let Some(name) = struct_name {
+            scope.insert("self".to_string(), Type::Struct(name.clone()));
+        }
+        self.scopes.push(scope);
+        self.returns.clear();
+        self.declared_return = declared.map(|ty| self.resolve(ty));
+        self.current_struct = struct_name.cloned();
+    }
+
+    fn leave_function(&mut self) -> Type {
+        self.scopes.pop();
+        self.current_struct = None;
+        self.declared_return = None;
+        let returns = std::mem::take(&mut self.returns);
+        if returns.is_empty() {
+            return Type::Void;
+        }
+        returns
+            .into_iter()
+            .reduce(|a, b| unify(&a, &b).unwrap_or(Type::Unknown))
+            .unwrap_or(Type::Unknown)
+    }
+
+    /// Verificar un statement con ubicación: los errores llevan el span del statement
+    fn check_spanned_stmt(&mut self, stmt: &Spanned<Stmt>) -> Result<()> {
+        self.check_stmt(&stmt.node).map_err(|e| e.at(stmt.span))
+    }
+
+    fn check_block(&mut self, body: &[Spanned<Stmt>]) -> Result<()> {
+        self.scopes.push(HashMap::new());
+        let result = body.iter().try_for_each(|stmt| self.check_spanned_stmt(stmt));
+        self.scopes.pop();
+        result
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) -> Result<()> {
+        match stmt {
+            Stmt::Print(expr) | Stmt::Expr(expr) => {
+                self.check_expr(expr)?;
+            }
+            Stmt::Let { name, ty: Some(declared), value, .. } => {
+                let declared = self.resolve(declared);
+                let value_ty = self.check_expr_against(&declared, value)?;
+                check_annotation(name, &declared, &value_ty)?;
+                self.declare(name, if is_known(&declared) { declared } else { value_ty });
+            }
+            Stmt::Let { name, value, .. } => {
+                let ty = self.check_expr(value)?;
+                self.declare(name, ty);
+            }
+            Stmt::MultiLet { names, values, .. } => {
+                let mut types = Vec::new();
+                for value in values {
+                    types.push(self.check_expr(value)?);
+                }
+                for (i, name) in names.iter().enumerate() {
+                    // Con menos valores que nombres se usa el último (igual que el backend)
+                    let ty = types.get(i).or(types.last()).cloned().unwrap_or(Type::Unknown);
+                    self.declare(name, ty);
+                }
+            }
+            Stmt::If { condition, then_body, else_body } => {
+                self.check_expr(condition)?;
+                self.check_block(then_body)?;
+                if let Some(else_body) = else_body {
+                    self.check_block(else_body)?;
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.check_expr(condition)?;
+                self.check_block(body)?;
+            }
+            Stmt::For { vars, iter, body } => {
+                let element_types = match self.for_element_types(iter)?.as_slice() {
+                    // Pares de `d.items()` (o cualquier array de tuplas) al destructurar
+                    [Type::Tuple(types)] if vars.len() > 1 => types.clone(),
+                    [Type::Unknown] => vec![Type::Unknown; vars.len()],
+                    types => types.to_vec(),
+                };
+                if element_types.len() != vars.len() {
+                    return Err(type_error(format!(
+                        "el for declara {} variable(s) pero cada elemento tiene {}",
+                        vars.len(),
+                        element_types.len()
+                    )));
+                }
+                self.scopes.push(vars.iter().cloned().zip(element_types).collect());
+                let result = self.check_block(body);
+                self.scopes.pop();
+                result?;
+            }
+            Stmt::Fn { name, type_params, params, return_type, body, .. } => {
+                // Función anidada en un bloque: visible desde su propio cuerpo y después
+                self.functions.entry(name.clone()).or_insert(Type::Unknown);
+                self.generic_depth += usize::from(!type_params.is_empty());
+                let owner = self.associated_struct(name, params);
+                self.enter_function(owner.as_ref(), params, return_type.as_ref());
+                let result = body.iter().try_for_each(|stmt| self.check_spanned_stmt(stmt));
+                self.leave_function();
+                self.generic_depth -= usize::from(!type_params.is_empty());
+                result?;
+            }
+            Stmt::Struct { name, type_params, init, destroy, methods, .. } => {
+                let bodies = init
+                    .iter()
+                    .chain(destroy.iter())
+                    .chain(methods.iter().map(|(_, method)| method));
+                self.generic_depth += usize::from(!type_params.is_empty());
+                let result = bodies.into_iter().try_for_each(|method| {
+                    self.enter_function(Some(name), &method.params, method.return_type.as_ref());
+                    let result = method.body.iter().try_for_each(|stmt| self.check_spanned_stmt(stmt));
+                    self.leave_function();
+                    result
+                });
+                self.generic_depth -= usize::from(!type_params.is_empty());
+                result?;
+            }
+            Stmt::Return(expr) => {
+                let ty = match expr {
+                    Some(expr) => self.check_expr(expr)?,
+                    None => Type::Void,
+                };
+                if let Some(declared) = &self.declared_return {
+                    let ty = self.upcast(declared, ty.clone());
+                    if !compatible(declared, &ty) || (is_integer_like(declared) && ty.is_float()) {
+                        return Err(type_error(format!(
+                            "tipo de retorno incompatible: se esperaba {}, se encontró {}",
+                            declared.to_string(),
+                            ty.to_string()
+                        )));
+                    }
+                }
+                self.returns.push(ty);
+            }
+            Stmt::Trait { name, methods } => {
+                if let Some(method) = methods.iter().find(|m| m.params.first().map_or(true, |p| p.name != "self")) {
+                    return Err(type_error(format!(
+                        "el método '{}' del trait '{}' debe recibir self como primer parámetro",
+                        method.name, name
+                    )));
+                }
+            }
+            Stmt::Impl { trait_name, type_name, methods } => {
+                self.check_impl(trait_name, type_name, methods)?;
+                for (_, method) in methods {
+                    self.enter_function(Some(type_name), &method.params, method.return_type.as_ref());
+                    let result = method.body.iter().try_for_each(|stmt| self.check_spanned_stmt(stmt));
+                    self.leave_function();
+                    result?;
+                }
+            }
+            Stmt::Break | Stmt::Continue | Stmt::Enum { .. } | Stmt::Import(_) => {}
+        }
+        Ok(())
+    }
+
+    fn declare(&mut self, name: &str, ty: Type) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), ty);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Type {
+        self.lookup_var(name).unwrap_or(Type::Unknown)
+    }
+
+    fn lookup_var(&self, name: &str) -> Option<Type> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned()
+    }
+
+    /// Tipo de una función del usuario usada como valor (`let f = cuadrado`)
+    fn function_value(&self, name: &str) -> Option<Type> {
+        let signature = self.signatures.get(name)?;
+        Some(Type::Function {
+            params: signature.iter().map(|(_, ty)| ty.clone()).collect(),
+            ret: Box::new(self.functions.get(name).cloned().unwrap_or(Type::Unknown)),
+        })
+    }
+
+    /// Llamada a través de un valor función (variable, parámetro o campo): verifica la
+    /// aridad y los argumentos contra su firma y devuelve el tipo de retorno
+    fn call_value(&self, name: &str, callee: &Type, args: &[Type]) -> Result<Type> {
+        let Type::Function { params, ret } = callee else {
+            return Ok(Type::Unknown);
+        };
+        if params.len() != args.len() {
+            return Err(type_error(format!(
+                "'{}' recibe {} argumento(s) pero se pasaron {}",
+                name,
+                params.len(),
+                args.len()
+            )));
+        }
+        for (i, (expected, arg)) in params.iter().zip(args).enumerate() {
+            if !compatible(expected, arg) {
+                return Err(type_error(format!(
+                    "argumento {} de '{}': se esperaba {}, se encontró {}",
+                    i + 1,
+                    name,
+                    expected.to_string(),
+                    arg.to_string()
+                )));
+            }
+        }
+        Ok((**ret).clone())
+    }
+
+    /// Inferir (y registrar) el tipo de una expresión
+    /// Verificar que un `impl Trait for Struct` define exactamente los métodos del
+    /// trait, con el mismo número de parámetros y un tipo de retorno compatible
+    fn check_impl(&self, trait_name: &str, type_name: &str, methods: &[(String, StructMethod)]) -> Result<()> {
+        let Some(required) = self.traits.get(trait_name) else {
+            return Err(type_error(format!("trait '{}' no definido", trait_name)));
+        };
+        if !self.structs.contains_key(type_name) {
+            return Err(type_error(format!(
+                "impl de '{}' para '{}': struct '{}' no definido",
+                trait_name, type_name, type_name
+            )));
+        }
+        for (method_name, method) in methods {
+            let Some((_, params, ret)) = required.iter().find(|(name, _, _)| name == method_name) else {
+                return Err(type_error(format!(
+                    "el método '{}' no pertenece al trait '{}'",
+                    method_name, trait_name
+                )));
+            };
+            if method.params.len() != *params {
+                return Err(type_error(format!(
+                    "el método '{}' de '{}' recibe {} parámetro(s) pero el trait '{}' declara {}",
+                    method_name,
+                    type_name,
+                    method.params.len(),
+                    trait_name,
+                    params
+                )));
+            }
+            let actual = self.method_type(type_name, method_name);
+            if !compatible(ret, &actual) {
+                return Err(type_error(format!(
+                    "el método '{}' de '{}' retorna {} pero el trait '{}' declara {}",
+                    method_name,
+                    type_name,
+                    actual.to_string(),
+                    trait_name,
+                    ret.to_string()
+                )));
+            }
+        }
+        for (method_name, _, _) in required {
+            if !methods.iter().any(|(name, _)| name == method_name) {
+                return Err(type_error(format!(
+                    "'{}' no implementa el método '{}' del trait '{}'",
+                    type_name, method_name, trait_name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Verificar una expresión cuyo tipo esperado se conoce (anotación): un array
+    /// literal de `[Drawable]` puede mezclar structs distintos que implementan el trait
+    fn check_expr_against(&mut self, expected: &Type, expr: &Spanned<Expr>) -> Result<Type> {
+        // `[Animal]` / `[Drawable]`: cada elemento puede ser un struct derivado o que implementa el trait
+        if let (Type::Array { element_type, .. }, Expr::ArrayLiteral(elements)) = (expected, &expr.node) {
+            if matches!(**element_type, Type::Trait(_) | Type::Struct(_)) {
+                for element in elements {
+                    let ty = self.check_expr_against(element_type, element)?;
+                    if !compatible(element_type, &ty) {
+                        return Err(type_error(format!(
+                            "los elementos del array deben ser {}, se encontró {}",
+                            element_type.to_string(),
+                            ty.to_string()
+                        ))
+                        .at(element.span));
+                    }
+                }
+                self.types.insert(expr, expected.clone());
+                return Ok(expected.clone());
+            }
+        }
+        if let (Type::Generic { name, args }, Expr::StructLiteral { name: literal, fields }) = (expected, &expr.node) {
+            if name == literal && self.generic_structs.contains_key(name) {
+                let ty = self.check_generic_literal(expr, name, fields, Some(args)).map_err(|e| e.at(expr.span))?;
+                self.types.insert(expr, ty.clone());
+                return Ok(ty);
+            }
+        }
+        if let (Type::Generic { name, args }, Expr::Call { module: Some(module), name: constructor, args: values }) = (expected, &expr.node) {
+            if name == module && constructor == "new" && self.generic_structs.contains_key(name) {
+                let mut arg_types = Vec::new();
+                for value in values {
+                    arg_types.push(self.check_expr(value)?);
+                }
+                let ty = self.check_generic_new(expr, name, &arg_types, Some(args)).map_err(|e| e.at(expr.span))?;
+                self.types.insert(expr, ty.clone());
+                return Ok(ty);
+            }
+        }
+        let ty = self.check_expr(expr)?;
+        Ok(self.upcast(expected, ty))
+    }
+
+    /// Llamada a una función genérica: infiere sus parámetros de tipo a partir de los
+    /// argumentos, verifica los argumentos con la firma sustituida y registra la instancia
+    fn check_generic_call(&mut self, expr: &Expr, name: &str, args: &[Type]) -> Result<Type> {
+        let generic = self.generic_fns[name].clone();
+        let mut bindings = HashMap::new();
+        bind_param_types(name, &generic.type_params, &generic.params, args, &mut bindings)?;
+        let Some(type_args) = self.instance_args(name, &generic.type_params, &bindings)? else {
+            return Ok(Type::Unknown);
+        };
+        self.check_generic_args(name, &generic.params, args, &bindings)?;
+        self.generic_args.args.insert(ExprTypes::key(expr), type_args);
+        Ok(match &generic.return_type {
+            Some(ret) => self.resolve(&substitute(ret, &bindings)),
+            None => self.functions.get(name).cloned().unwrap_or(Type::Unknown),
+        })
+    }
+
+    /// `Stack.new(...)` de un struct genérico con init: los parámetros de tipo salen de la
+    /// anotación (`let s: Stack<int64> = Stack.new()`) o de los argumentos del init
+    fn check_generic_new(&mut self, expr: &Expr, name: &str, args: &[Type], hint: Option<&[Type]>) -> Result<Type> {
+        let generic = self.generic_structs[name].clone();
+        let params = generic.init_params.unwrap_or_default();
+        let constructor = format!("{}.new", name);
+        let mut bindings: HashMap<String, Type> = match hint {
+            Some(args) => generic.type_params.iter().cloned().zip(args.iter().cloned()).collect(),
+            None => HashMap::new(),
+        };
+        bind_param_types(&constructor, &generic.type_params, &params, args, &mut bindings)?;
+        let Some(type_args) = self.instance_args(name, &generic.type_params, &bindings)? else {
+            let args = generic.type_params.iter().map(|param| bindings.get(param).cloned().unwrap_or(Type::Unknown));
+            return Ok(Type::Generic { name: name.to_string(), args: args.collect() });
+        };
+        self.check_generic_args(&constructor, &params, args, &bindings)?;
+        self.generic_args.args.insert(ExprTypes::key(expr), type_args.clone());
+        Ok(self.resolve(&Type::Generic { name: name.to_string(), args: type_args }))
+    }
+
+    /// Verificar los argumentos de una llamada genérica con la firma ya sustituida
+    fn check_generic_args(&self, name: &str, params: &[FnParam], args: &[Type], bindings: &HashMap<String, Type>) -> Result<()> {
+        for (param, arg) in params.iter().zip(args) {
+            let Some(ty) = &param.ty else { continue };
+            let expected = self.resolve(&substitute(ty, bindings));
+            let arg = self.upcast(&expected, arg.clone());
+            if !compatible(&expected, &arg) || (is_integer_like(&expected) && arg.is_float()) {
+                return Err(type_error(format!(
+                    "argumento '{}' de '{}': se esperaba {}, se encontró {}",
+                    param.name,
+                    name,
+                    expected.to_string(),
+                    arg.to_string()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Literal de un struct genérico: los parámetros de tipo salen de la anotación
+    /// (`let c: Caja<int64> = ...`) o de los valores de sus campos
+    fn check_generic_literal(
+        &mut self,
+        expr: &Expr,
+        name: &str,
+        fields: &[(String, Spanned<Expr>)],
+        hint: Option<&[Type]>,
+    ) -> Result<Type> {
+        let generic = self.generic_structs[name].clone();
+        let mut bindings: HashMap<String, Type> = match hint {
+            Some(args) => generic.type_params.iter().cloned().zip(args.iter().cloned()).collect(),
+            None => HashMap::new(),
+        };
+        let mut values = Vec::new();
+        for (field, value) in fields {
+            let value_ty = self.check_expr(value)?;
+            let declared = generic.fields.get(field).cloned().unwrap_or(Type::Unknown);
+            bind_type_params(&generic.type_params, &declared, &value_ty, &mut bindings).map_err(|(t, a, b)| {
+                type_error(format!(
+                    "'{}': el parámetro de tipo {} no puede ser {} y {} a la vez",
+                    name,
+                    t,
+                    a.to_string(),
+                    b.to_string()
+                ))
+            })?;
+            values.push((field, declared, value_ty));
+        }
+        let Some(type_args) = self.instance_args(name, &generic.type_params, &bindings)? else {
+            let args = generic.type_params.iter().map(|param| bindings.get(param).cloned().unwrap_or(Type::Unknown));
+            return Ok(Type::Generic { name: name.to_string(), args: args.collect() });
+        };
+        for (field, declared, value_ty) in values {
+            let field_ty = self.resolve(&substitute(&declared, &bindings));
+            let value_ty = self.upcast(&field_ty, value_ty);
+            if !compatible(&field_ty, &value_ty) {
+                return Err(type_error(format!(
+                    "el campo '{}' de '{}' es {} pero se le asigna {}",
+                    field,
+                    name,
+                    field_ty.to_string(),
+                    value_ty.to_string()
+                )));
+            }
+        }
+        self.generic_args.args.insert(ExprTypes::key(expr), type_args.clone());
+        Ok(self.resolve(&Type::Generic { name: name.to_string(), args: type_args }))
+    }
+
+    /// Argumentos de tipo de una instancia en el orden de declaración. Dentro de una
+    /// declaración genérica pueden quedar sin inferir (`None`: se instancia después)
+    fn instance_args(
+        &self,
+        name: &str,
+        type_params: &[String],
+        bindings: &HashMap<String, Type>,
+    ) -> Result<Option<Vec<Type>>> {
+        let mut args = Vec::new();
+        for param in type_params {
+            match bindings.get(param).filter(|ty| is_concrete(ty)) {
+                Some(ty) => args.push(ty.clone()),
+                None if self.generic_depth > 0 => return Ok(None),
+                None => {
+                    return Err(type_error(format!(
+                        "no se pudo inferir el parámetro de tipo {} de '{}'",
+                        param, name
+                    )))
+                }
+            }
+        }
+        Ok(Some(args))
+    }
+
+    /// Parámetros de tipo de un struct genérico asociados a los argumentos de una instancia
+    fn generic_bindings(&self, name: &str, args: &[Type]) -> HashMap<String, Type> {
+        let params = self.generic_structs.get(name).map(|g| g.type_params.clone()).unwrap_or_default();
+        params.into_iter().zip(args.iter().cloned()).collect()
+    }
+
+    /// Tipo de un campo de una instancia de struct genérico (`Caja<int64>.valor`)
+    fn generic_field(&self, name: &str, args: &[Type], field: &str) -> Type {
+        let declared = self.generic_structs.get(name).and_then(|g| g.fields.get(field)).cloned();
+        declared.map_or(Type::Unknown, |ty| self.resolve(&substitute(&ty, &self.generic_bindings(name, args))))
+    }
+
+    /// Tipo de una expresión; los errores se ubican en la subexpresión más interna
+    /// donde ocurrieron
+    fn check_expr(&mut self, expr: &Spanned<Expr>) -> Result<Type> {
+        let ty = self.infer_expr(expr).map_err(|e| e.at(expr.span))?;
+        self.types.insert(expr, ty.clone());
+        Ok(ty)
+    }
+
+    fn infer_expr(&mut self, expr: &Expr) -> Result<Type> {
+        let ty = match expr {
+            Expr::Number(_) => Type::default_int(),
+            Expr::Float(_) => Type::default_float(),
+            Expr::Bool(_) => Type::Bool,
+            Expr::String(_) => Type::String,
+            Expr::FString { parts } => {
+                for part in parts {
+                    if let FStringPart::Expr(expr) = part {
+                        self.check_expr(expr)?;
+                    }
+                }
+                Type::String
+            }
+            Expr::Ident(name) => match self.lookup_var(name) {
+                Some(ty) => ty,
+                None => self.function_value(name).unwrap_or(Type::Unknown),
+            },
+            Expr::BinaryOp { op, left, right } => {
+                let left = self.check_expr(left)?;
+                let right = self.check_expr(right)?;
+                binary_result(*op, &left, &right)?
+            }
+            Expr::Not(inner) => {
+                self.check_expr(inner)?;
+                Type::Bool
+            }
+            Expr::Assign { name, value } if name == "_array_set" => {
+                // arr[i] = v / d[k] = v: el parser lo codifica como `Index == valor`
+                let Expr::BinaryOp { left, right, .. } = &value.node else {
+                    return Ok(Type::Unknown);
+                };
+                let slot_ty = self.check_expr(left)?;
+                let value_ty = self.check_expr(right)?;
+                if !compatible(&slot_ty, &value_ty) {
+                    return Err(type_error(format!(
+                        "no se puede asignar {} a un elemento de tipo {}",
+                        value_ty.to_string(),
+                        slot_ty.to_string()
+                    )));
+                }
+                value_ty
+            }
+            Expr::Assign { name, value } => {
+                let value_ty = self.check_expr(value)?;
+                let var_ty = self.lookup(name);
+                let value_ty = self.upcast(&var_ty, value_ty);
+                check_assignable(name, &var_ty, &value_ty)?;
+                if is_known(&var_ty) { var_ty } else { value_ty }
+            }
+            Expr::CompoundAssign { name, op, value } => {
+                let value_ty = self.check_expr(value)?;
+                let var_ty = self.lookup(name);
+                let result = binary_result(*op, &var_ty, &value_ty)?;
+                check_assignable(name, &var_ty, &result)?;
+                var_ty
+            }
+            Expr::Call { module, name, args } => {
+                let mut arg_types = Vec::new();
+                for arg in args {
+                    arg_types.push(self.check_expr(arg)?);
+                }
+                match module {
+                    Some(struct_name) if name == "new" && self.generic_structs.contains_key(struct_name) => {
+                        self.check_generic_new(expr, struct_name, &arg_types, None)?
+                    }
+                    Some(struct_name) if self.structs.contains_key(struct_name) => {
+                        if name == "new" {
+                            Type::Struct(struct_name.clone())
+                        } else {
+                            self.method_type(struct_name, name)
+                        }
+                    }
+                    Some(_) => Type::Unknown,
+                    None if self.generic_fns.contains_key(name) => self.check_generic_call(expr, name, &arg_types)?,
+                    // Las funciones del usuario tienen prioridad sobre una variable con el mismo nombre
+                    None if !self.functions.contains_key(name) && self.lookup_var(name).is_some() => {
+                        self.call_value(name, &self.lookup(name), &arg_types)?
+                    }
+                    None if !self.functions.contains_key(name) && !is_builtin(name) => {
+                        return Err(type_error(format!("función '{}' no definida", name)));
+                    }
+                    None => {
+                        if let (Some(arity), Some(Type::Function { params, .. })) = (callback_arity(name), arg_types.first()) {
+                            if params.len() != arity {
+                                return Err(type_error(format!(
+                                    "la función de '{}' debe recibir {} argumento(s), recibe {}",
+                                    name,
+                                    arity,
+                                    params.len()
+                                )));
+                            }
+                        }
+                        self.check_call_args(name, &arg_types)?;
+                        self.call_type(name, &arg_types)
+                    }
+                }
+            }
+            Expr::Borrow { expr, mutable } => Type::Ref {
+                inner: Box::new(self.check_expr(expr)?),
+                mutable: *mutable,
+            },
+            Expr::Deref(inner) => match self.check_expr(inner)? {
+                Type::Ref { inner, .. } => *inner,
+                _ => Type::Unknown,
+            },
+            Expr::Some(inner) => Type::Option(Box::new(self.check_expr(inner)?)),
+            Expr::None => Type::Option(Box::new(Type::Unknown)),
+            Expr::Ok(inner) => Type::Result {
+                ok: Box::new(self.check_expr(inner)?),
+                err: Box::new(Type::Unknown),
+            },
+            Expr::Err(inner) => Type::Result {
+                ok: Box::new(Type::Unknown),
+                err: Box::new(self.check_expr(inner)?),
+            },
+            Expr::PropagateError(inner) => match self.check_expr(inner)? {
+                Type::Result { ok, .. } => *ok,
+                Type::Option(inner) => *inner,
+                _ => Type::Unknown,
+            },
+            Expr::Match { expr, arms } => {
+                let scrutinee = self.check_expr(expr)?;
+                let mut result: Option<Type> = None;
+                for arm in arms {
+                    let mut scope = HashMap::new();
+                    self.check_pattern(&arm.pattern, &scrutinee, &mut scope)?;
+                    self.scopes.push(scope);
+                    let arm_ty = match &arm.guard {
+                        Some(guard) => self.check_expr(guard).and_then(|_| self.check_expr(&arm.body)),
+                        None => self.check_expr(&arm.body),
+                    };
+                    self.scopes.pop();
+                    let arm_ty = arm_ty?;
+                    result = Some(match result {
+                        None => arm_ty,
+                        Some(prev) => unify(&prev, &arm_ty).ok_or_else(|| {
+                            type_error(format!(
+                                "los brazos del match tienen tipos incompatibles: {} y {}",
+                                prev.to_string(),
+                                arm_ty.to_string()
+                            ))
+                        })?,
+                    });
+                }
+                // Los brazos con guarda no cuentan para la exhaustividad
+                let unguarded: Vec<&Pattern> =
+                    arms.iter().filter(|arm| arm.guard.is_none()).map(|arm| &arm.pattern).collect();
+                if let Some(witness) = patterns::missing_pattern(&unguarded, &self.enums) {
+                    return Err(type_error(format!(
+                        "match no exhaustivo: el patrón `{}` no está cubierto",
+                        witness
+                    )));
+                }
+                result.unwrap_or(Type::Unknown)
+            }
+            Expr::StructLiteral { name, fields } if self.generic_structs.contains_key(name) => {
+                self.check_generic_literal(expr, name, fields, None)?
+            }
+            Expr::StructLiteral { name, fields } => {
+                for (field, value) in fields {
+                    let value_ty = self.check_expr(value)?;
+                    let field_ty = self.field_of(name, field);
+                    let value_ty = self.upcast(&field_ty, value_ty);
+                    if !compatible(&field_ty, &value_ty) {
+                        return Err(type_error(format!(
+                            "el campo '{}' de '{}' es {} pero se le asigna {}",
+                            field,
+                            name,
+                            field_ty.to_string(),
+                            value_ty.to_string()
+                        )));
+                    }
+                }
+                Type::Struct(name.clone())
+            }
+            Expr::EnumVariant { enum_name, variant, args } => {
+                // El parser ya verificó que la variante existe y recibe tantos valores como campos
+                let payload = self.variant_payload(enum_name, variant);
+                for (i, arg) in args.iter().enumerate() {
+                    let arg_ty = self.check_expr(arg)?;
+                    let field_ty = payload.get(i).cloned().unwrap_or(Type::Unknown);
+                    if !compatible(&field_ty, &arg_ty) {
+                        return Err(type_error(format!(
+                            "el campo {} de '{}.{}' es {} pero se le asigna {}",
+                            i + 1,
+                            enum_name,
+                            variant,
+                            field_ty.to_string(),
+                            arg_ty.to_string()
+                        )));
+                    }
+                }
+                Type::Enum(enum_name.clone())
+            }
+            Expr::FieldAccess { object, field } => match self.check_expr(object)? {
+                Type::Struct(struct_name) => self.field_of(&struct_name, field),
+                Type::Generic { name, args } => self.generic_field(&name, &args, field),
+                _ => Type::Unknown,
+            },
+            Expr::FieldAssign { object, field, value } => {
+                let object_ty = self.check_expr(object)?;
+                let value_ty = self.check_expr(value)?;
+                let field_ty = match &object_ty {
+                    Type::Struct(struct_name) => Some(self.field_of(struct_name, field)),
+                    Type::Generic { name, args } => Some(self.generic_field(name, args, field)),
+                    _ => None,
+                };
+                if let Some(field_ty) = field_ty {
+                    let value_ty = self.upcast(&field_ty, value_ty.clone());
+                    if !compatible(&field_ty, &value_ty) {
+                        return Err(type_error(format!(
+                            "no se puede asignar {} al campo '{}' de tipo {}",
+                            value_ty.to_string(),
+                            field,
+                            field_ty.to_string()
+                        )));
+                    }
+                }
+                value_ty
+            }
+            Expr::MethodCall { object, method, args } => {
+                let object_ty = self.check_expr(object)?;
+                let mut arg_types = Vec::new();
+                for arg in args {
+                    arg_types.push(self.check_expr(arg)?);
+                }
+                match &object_ty {
+                    // Campo con una función guardada: `op.aplicar(3)` llama al valor del campo
+                    Type::Struct(struct_name) if self.find_method(struct_name, method).is_none() => {
+                        let field = self.field_of(struct_name, method);
+                        self.call_value(method, &field, &arg_types)?
+                    }
+                    Type::Struct(struct_name) => self.method_type(struct_name, method),
+                    Type::Generic { name, args } => {
+                        let declared = self.generic_structs[name].methods.get(method).cloned().flatten();
+                        match declared {
+                            Some(ret) => self.resolve(&substitute(&ret, &self.generic_bindings(name, args))),
+                            None => self.method_type(name, method),
+                        }
+                    }
+                    Type::Trait(trait_name) => {
+                        let required = self.traits.get(trait_name).into_iter().flatten();
+                        let Some((_, _, ret)) = required.into_iter().find(|(name, _, _)| name == method) else {
+                            return Err(type_error(format!(
+                                "el trait '{}' no tiene el método '{}'",
+                                trait_name, method
+                            )));
+                        };
+                        ret.clone()
+                    }
+                    Type::String => string_method_type(method),
+                    Type::Dict { key, value } => {
+                        if let (Some(arg_ty), "get" | "remove" | "contains") = (arg_types.first(), method.as_str()) {
+                            check_dict_key_arg(key, arg_ty)?;
+                        }
+                        dict_method_type(key, value, method)
+                    }
+                    Type::Set(element) => {
+                        if let (Some(arg_ty), "add" | "remove" | "contains") = (arg_types.first(), method.as_str()) {
+                            check_set_element(element, arg_ty)?;
+                        }
+                        match (method.as_str(), arg_types.first()) {
+                            ("union" | "intersection" | "difference", Some(other)) => {
+                                let other_element = match other {
+                                    Type::Set(other_element) => (**other_element).clone(),
+                                    ty if is_known(ty) => {
+                                        return Err(type_error(format!(
+                                            "'{}' de un set espera otro set, se encontró {}",
+                                            method,
+                                            ty.to_string()
+                                        )));
+                                    }
+                                    _ => Type::Unknown,
+                                };
+                                let element = unify(element, &other_element).ok_or_else(|| {
+                                    type_error(format!(
+                                        "'{}' de sets con elementos incompatibles: {} y {}",
+                                        method,
+                                        element.to_string(),
+                                        other_element.to_string()
+                                    ))
+                                })?;
+                                Type::Set(Box::new(element))
+                            }
+                            ("add", _) => Type::Void,
+                            ("remove" | "contains", _) => Type::Bool,
+                            ("len", _) => Type::Int64,
+                            _ => Type::Unknown,
+                        }
+                    }
+                    Type::Array { element_type, .. } => match method.as_str() {
+                        "pop" => (**element_type).clone(),
+                        "len" | "index" | "count" => Type::Int64,
+                        _ => Type::Unknown,
+                    },
+                    _ => Type::Unknown,
+                }
+            }
+            Expr::SuperCall { method, args } => {
+                for arg in args {
+                    self.check_expr(arg)?;
+                }
+                let parent = self
+                    .current_struct
+                    .as_ref()
+                    .and_then(|name| self.structs.get(name))
+                    .and_then(|info| info.parent.clone());
+                match parent {
+                    Some(parent) => self.method_type(&parent, method),
+                    None => Type::Unknown,
+                }
+            }
+            Expr::ArrayLiteral(elements) => {
+                let mut element_type = Type::Unknown;
+                for (i, element) in elements.iter().enumerate() {
+                    let ty = self.check_expr(element)?;
+                    element_type = if i == 0 {
+                        ty
+                    } else {
+                        self.unify_structs(&element_type, &ty).ok_or_else(|| {
+                            type_error(format!(
+                                "los elementos del array tienen tipos incompatibles: {} y {}",
+                                element_type.to_string(),
+                                ty.to_string()
+                            ))
+                        })?
+                    };
+                }
+                Type::Array { element_type: Box::new(element_type), size: None }
+            }
+            Expr::Index { array, index } => {
+                let array_ty = self.check_expr(array)?;
+                let index_ty = self.check_expr(index)?;
+                match &array_ty {
+                    Type::Array { element_type, .. } => {
+                        if is_known(&index_ty) && !is_integer_like(&index_ty) {
+                            return Err(type_error(format!(
+                                "el índice de un array debe ser entero, se encontró {}",
+                                index_ty.to_string()
+                            )));
+                        }
+                        (**element_type).clone()
+                    }
+                    Type::String => {
+                        if is_known(&index_ty) && !is_integer_like(&index_ty) {
+                            return Err(type_error(format!(
+                                "el índice de un string debe ser entero, se encontró {}",
+                                index_ty.to_string()
+                            )));
+                        }
+                        // s[i] es un carácter: un String de longitud 1
+                        Type::String
+                    }
+                    Type::Dict { key, value } => {
+                        check_dict_key_arg(key, &index_ty)?;
+                        (**value).clone()
+                    }
+                    Type::Set(_) => {
+                        return Err(type_error(
+                            "no se puede indexar un set: usa `x in s` para consultar si contiene un elemento".to_string(),
+                        ));
+                    }
+                    Type::Tuple(elements) => match &index.node {
+                        Expr::Number(n) => elements.get(*n as usize).cloned().unwrap_or(Type::Unknown),
+                        _ => Type::Unknown,
+                    },
+                    ty if ty.is_numeric() || *ty == Type::Bool => {
+                        return Err(type_error(format!(
+                            "no se puede indexar un valor de tipo {}",
+                            ty.to_string()
+                        )));
+                    }
+                    _ => Type::Unknown,
+                }
+            }
+            Expr::Slice { object, start, end, step } => {
+                let ty = self.check_expr(object)?;
+                for bound in [start, end, step].into_iter().flatten() {
+                    let bound_ty = self.check_expr(bound)?;
+                    if is_known(&bound_ty) && !is_integer_like(&bound_ty) {
+                        return Err(type_error(format!(
+                            "los límites y el paso de un slice deben ser enteros, se encontró {}",
+                            bound_ty.to_string()
+                        )));
+                    }
+                }
+                if matches!(step.as_deref(), Some(Spanned { node: Expr::Number(0), .. })) {
+                    return Err(type_error("el paso de un slice no puede ser 0".to_string()));
+                }
+                ty
+            }
+            Expr::TupleLiteral(elements) => {
+                let mut types = Vec::new();
+                for element in elements {
+                    types.push(self.check_expr(element)?);
+                }
+                Type::Tuple(types)
+            }
+            Expr::Lambda { params, body } => {
+                let scope = params.iter().map(|p| (p.clone(), Type::Unknown)).collect();
+                self.scopes.push(scope);
+                let result = self.check_expr(body);
+                self.scopes.pop();
+                Type::Function { params: vec![Type::Unknown; params.len()], ret: Box::new(result?) }
+            }
+            Expr::ListComprehension { expr, clauses } => {
+                let element = self.comprehension(clauses, &[expr])?;
+                Type::Array { element_type: Box::new(element[0].clone()), size: None }
+            }
+            Expr::SetComprehension { expr, clauses } => {
+                let element = self.comprehension(clauses, &[expr])?;
+                check_hashable(&element[0], "los elementos de un set")?;
+                Type::Set(Box::new(element[0].clone()))
+            }
+            Expr::DictComprehension { key_expr, value_expr, clauses } => {
+                let mut types = self.comprehension(clauses, &[key_expr, value_expr])?;
+                check_hashable(&types[0], "las claves de un dict")?;
+                let value = types.pop().unwrap();
+                let key = types.pop().unwrap();
+                Type::Dict { key: Box::new(key), value: Box::new(value) }
+            }
+            Expr::DictLiteral { pairs } => {
+                let (mut key_type, mut value_type) = (Type::Unknown, Type::Unknown);
+                for (key, value) in pairs {
+                    let key_ty = self.check_expr(key)?;
+                    let value_ty = self.check_expr(value)?;
+                    key_type = unify(&key_type, &key_ty).ok_or_else(|| {
+                        type_error(format!(
+                            "las claves del dict tienen tipos incompatibles: {} y {}",
+                            key_type.to_string(),
+                            key_ty.to_string()
+                        ))
+                    })?;
+                    value_type = unify(&value_type, &value_ty).ok_or_else(|| {
+                        type_error(format!(
+                            "los valores del dict tienen tipos incompatibles: {} y {}",
+                            value_type.to_string(),
+                            value_ty.to_string()
+                        ))
+                    })?;
+                }
+                check_hashable(&key_type, "las claves de un dict")?;
+                Type::Dict { key: Box::new(key_type), value: Box::new(value_type) }
+            }
+            Expr::SetLiteral(elements) => {
+                let mut element_type = Type::Unknown;
+                for element in elements {
+                    let ty = self.check_expr(element)?;
+                    element_type = unify(&element_type, &ty).ok_or_else(|| {
+                        type_error(format!(
+                            "los elementos del set tienen tipos incompatibles: {} y {}",
+                            element_type.to_string(),
+                            ty.to_string()
+                        ))
+                    })?;
+                }
+                check_hashable(&element_type, "los elementos de un set")?;
+                Type::Set(Box::new(element_type))
+            }
+            Expr::Ternary { condition, then_expr, else_expr } => {
+                self.check_expr(condition)?;
+                let then_ty = self.check_expr(then_expr)?;
+                let else_ty = self.check_expr(else_expr)?;
+                unify(&then_ty, &else_ty).ok_or_else(|| {
+                    type_error(format!(
+                        "las ramas del operador ternario tienen tipos incompatibles: {} y {}",
+                        then_ty.to_string(),
+                        else_ty.to_string()
+                    ))
+                })?
+            }
+        };
+        Ok(ty)
+    }
+
+    /// Tipos que produce cada iteración de un `for`: uno por elemento, o uno por
+    /// componente en `enumerate(a)` y `zip(a, b)`
+    fn for_element_types(&mut self, iter: &ForIter) -> Result<Vec<Type>> {
+        match iter {
+            ForIter::Range { start, end, step } => {
+                for bound in [Some(start), Some(end), step.as_ref()].into_iter().flatten() {
+                    let ty = self.check_expr(bound)?;
+                    if is_known(&ty) && !is_integer_like(&ty) {
+                        return Err(type_error(format!(
+                            "los límites de un rango deben ser enteros, se encontró {}",
+                            ty.to_string()
+                        )));
+                    }
+                }
+                if matches!(step, Some(Spanned { node: Expr::Number(0), .. })) {
+                    return Err(type_error("el paso de range no puede ser 0".to_string()));
+                }
+                Ok(vec![Type::Int64])
+            }
+            ForIter::Iterable(_) => {
+                let (sources, numbered) = iter.sources();
+                let mut types = if numbered { vec![Type::Int64] } else { Vec::new() };
+                for source in sources {
+                    let ty = self.check_expr(source)?;
+                    types.push(iterable_element(&ty)?);
+                }
+                Ok(types)
+            }
+        }
+    }
+
+    /// Verificar las expresiones de una comprehension con las variables de iteración en scope.
+    /// Retorna el tipo de cada expresión.
+    fn comprehension(&mut self, clauses: &[ComprehensionClause], exprs: &[&Spanned<Expr>]) -> Result<Vec<Type>> {
+        let depth = self.scopes.len();
+        let result = self.comprehension_clauses(clauses, exprs);
+        self.scopes.truncate(depth);
+        result
+    }
+
+    /// Cada cláusula abre un scope con su variable: los iteradores y filtros siguientes la ven
+    fn comprehension_clauses(&mut self, clauses: &[ComprehensionClause], exprs: &[&Spanned<Expr>]) -> Result<Vec<Type>> {
+        for clause in clauses {
+            let element = match self.check_expr(&clause.iter)? {
+                Type::Array { element_type, .. } => *element_type,
+                Type::String => Type::String,
+                _ => Type::Unknown,
+            };
+            self.scopes.push(HashMap::from([(clause.var.clone(), element)]));
+            for condition in &clause.conditions {
+                self.check_expr(condition)?;
+            }
+        }
+        exprs.iter().map(|expr| self.check_expr(expr)).collect()
+    }
+
+    /// Verificar un patrón de match contra el tipo del valor y recoger sus variables
+    fn check_pattern(&self, pattern: &Pattern, ty: &Type, bindings: &mut HashMap<String, Type>) -> Result<()> {
+        let mismatch = || {
+            type_error(format!(
+                "el patrón `{}` no es compatible con {}",
+                pattern_head(pattern),
+                ty.to_string()
+            ))
+        };
+        match pattern {
+            Pattern::Wildcard => {}
+            Pattern::Ident(name) => bind_pattern_var(name, ty, bindings)?,
+            Pattern::Binding { name, pattern } => {
+                bind_pattern_var(name, ty, bindings)?;
+                self.check_pattern(pattern, ty, bindings)?;
+            }
+            Pattern::LiteralNumber(_) | Pattern::Range { .. } => {
+                if let Pattern::Range { start, end, inclusive } = pattern {
+                    if start > end || (start == end && !inclusive) {
+                        return Err(type_error(format!("rango vacío en patrón: {}", pattern_head(pattern))));
+                    }
+                }
+                if is_known(ty) && !ty.is_integer() && *ty != Type::Char {
+                    return Err(mismatch());
+                }
+            }
+            Pattern::LiteralString(_) if is_known(ty) && *ty != Type::String => return Err(mismatch()),
+            Pattern::LiteralBool(_) if is_known(ty) && *ty != Type::Bool => return Err(mismatch()),
+            Pattern::LiteralString(_) | Pattern::LiteralBool(_) => {}
+            Pattern::Some(_) | Pattern::None => {
+                let payload = match ty {
+                    Type::Option(payload) => payload.as_ref().clone(),
+                    Type::Unknown => Type::Unknown,
+                    _ => return Err(mismatch()),
+                };
+                if let Pattern::Some(inner) = pattern {
+                    self.check_pattern(inner, &payload, bindings)?;
+                }
+            }
+            Pattern::Ok(inner) | Pattern::Err(inner) => {
+                let payload = match (ty, pattern) {
+                    (Type::Result { ok, .. }, Pattern::Ok(_)) => ok.as_ref().clone(),
+                    (Type::Result { err, .. }, _) => err.as_ref().clone(),
+                    (Type::Unknown, _) => Type::Unknown,
+                    _ => return Err(mismatch()),
+                };
+                self.check_pattern(inner, &payload, bindings)?;
+            }
+            Pattern::Tuple(items) => {
+                let element_types = match ty {
+                    Type::Tuple(types) if types.len() == items.len() => types.clone(),
+                    Type::Tuple(types) => {
+                        return Err(type_error(format!(
+                            "el patrón de tupla tiene {} elementos pero el valor tiene {}",
+                            items.len(),
+                            types.len()
+                        )))
+                    }
+                    Type::Unknown => vec![Type::Unknown; items.len()],
+                    _ => return Err(mismatch()),
+                };
+                for (item, item_ty) in items.iter().zip(&element_types) {
+                    self.check_pattern(item, item_ty, bindings)?;
+                }
+            }
+            Pattern::Struct { name, fields } => {
+                if !self.structs.contains_key(name) {
+                    return Err(type_error(format!("struct '{}' no definido en patrón", name)));
+                }
+                let same_generic = matches!(ty, Type::Generic { name: generic, .. } if generic == name);
+                if !matches!(ty, Type::Unknown) && *ty != Type::Struct(name.clone()) && !same_generic {
+                    return Err(mismatch());
+                }
+                for (field, field_pattern) in fields {
+                    if !self.has_field(name, field) {
+                        return Err(type_error(format!("el struct '{}' no tiene el campo '{}'", name, field)));
+                    }
+                    self.check_pattern(field_pattern, &self.field_of(name, field), bindings)?;
+                }
+            }
+            Pattern::Variant { enum_name, variant, fields } => {
+                if is_known(ty) && *ty != Type::Enum(enum_name.clone()) {
+                    return Err(mismatch());
+                }
+                let payload = self.variant_payload(enum_name, variant);
+                for (i, field) in fields.iter().enumerate() {
+                    let field_ty = payload.get(i).cloned().unwrap_or(Type::Unknown);
+                    self.check_pattern(field, &field_ty, bindings)?;
+                }
+            }
+            Pattern::Or(alternatives) => {
+                // Todas las alternativas deben enlazar las mismas variables
+                let mut first: Option<HashMap<String, Type>> = None;
+                for alternative in alternatives {
+                    let mut alt_bindings = HashMap::new();
+                    self.check_pattern(alternative, ty, &mut alt_bindings)?;
+                    match &first {
+                        None => first = Some(alt_bindings),
+                        Some(expected) => {
+                            let mut a: Vec<&String> = expected.keys().collect();
+                            let mut b: Vec<&String> = alt_bindings.keys().collect();
+                            a.sort();
+                            b.sort();
+                            if a != b {
+                                return Err(type_error(
+                                    "todas las alternativas de `|` deben enlazar las mismas variables".to_string(),
+                                ));
+                            }
+                        }
+                    }
+                }
+                for (name, var_ty) in first.unwrap_or_default() {
+                    bind_pattern_var(&name, &var_ty, bindings)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Tipos del payload de una variante de enum (vacío si no se conoce)
+    fn variant_payload(&self, enum_name: &str, variant: &str) -> Vec<Type> {
+        self.enums
+            .get(enum_name)
+            .and_then(|variants| variants.iter().find(|(name, _)| name == variant))
+            .map(|(_, payload)| payload.clone())
+            .unwrap_or_default()
+    }
+
+    /// ¿Tiene el struct (o alguno de sus padres) el campo `field`?
+    fn has_field(&self, struct_name: &str, field: &str) -> bool {
+        let mut current = Some(struct_name.to_string());
+        while let Some(name) = current {
+            let Some(info) = self.structs.get(&name) else { break };
+            if info.fields.contains_key(field) {
+                return true;
+            }
+            current = info.parent.clone();
+        }
+        false
+    }
+
+    /// Tipo de un campo buscando también en los structs padre
+    fn field_of(&self, struct_name: &str, field: &str) -> Type {
+        let mut current = Some(struct_name.to_string());
+        while let Some(name) = current {
+            let Some(info) = self.structs.get(&name) else { break };
+            if let Some(ty) = info.fields.get(field) {
+                return ty.clone();
+            }
+            current = info.parent.clone();
+        }
+        Type::Unknown
+    }
+
+    /// Un objeto de una clase derivada puede usarse donde se espera su clase base:
+    /// devuelve `expected` si `value` es un struct que hereda (directa o
+    /// indirectamente) de él, y `value` sin cambios en cualquier otro caso
+    fn upcast(&self, expected: &Type, value: Type) -> Type {
+        if let (Type::Trait(trait_name), Type::Struct(struct_name)) = (expected, &value) {
+            return if self.implements(struct_name, trait_name) { expected.clone() } else { value };
+        }
+        let (Type::Struct(base), Type::Struct(derived)) = (expected, &value) else {
+            return value;
+        };
+        let mut current = self.structs.get(derived).and_then(|info| info.parent.clone());
+        while let Some(name) = current {
+            if &name == base {
+                return expected.clone();
+            }
+            current = self.structs.get(&name).and_then(|info| info.parent.clone());
+        }
+        value
+    }
+
+    /// `unify` que además lleva dos structs distintos a su ancestro común más cercano
+    /// (`[perro, gato]` es un array de `Animal`)
+    fn unify_structs(&self, a: &Type, b: &Type) -> Option<Type> {
+        if let (Type::Struct(x), Type::Struct(y)) = (a, b) {
+            let ancestors_of_x = self.ancestors(x);
+            return self.ancestors(y).into_iter().find(|name| ancestors_of_x.contains(name)).map(Type::Struct);
+        }
+        unify(a, b)
+    }
+
+    /// El struct y sus padres, del más cercano al más lejano
+    fn ancestors(&self, struct_name: &str) -> Vec<String> {
+        let mut chain = vec![struct_name.to_string()];
+        while let Some(parent) = self.structs.get(chain.last().unwrap()).and_then(|info| info.parent.clone()) {
+            if chain.contains(&parent) {
+                break;
+            }
+            chain.push(parent);
+        }
+        chain
+    }
+
+    /// ¿Implementa el struct (o alguno de sus padres) el trait?
+    fn implements(&self, struct_name: &str, trait_name: &str) -> bool {
+        let mut current = Some(struct_name.to_string());
+        while let Some(name) = current {
+            if self.impls.get(&name).map_or(false, |traits| traits.iter().any(|t| t == trait_name)) {
+                return true;
+            }
+            current = self.structs.get(&name).and_then(|info| info.parent.clone());
+        }
+        false
+    }
+
+    /// Tipo de retorno de un método buscando también en los structs padre
+    fn method_type(&self, struct_name: &str, method: &str) -> Type {
+        self.find_method(struct_name, method).unwrap_or(Type::Unknown)
+    }
+
+    fn find_method(&self, struct_name: &str, method: &str) -> Option<Type> {
+        let mut current = Some(struct_name.to_string());
+        while let Some(name) = current {
+            let Some(info) = self.structs.get(&name) else { break };
+            if let Some(ty) = info.methods.get(method) {
+                return Some(ty.clone());
+            }
+            current = info.parent.clone();
+        }
+        None
+    }
+
+    /// Verificar los argumentos de una llamada contra los parámetros anotados
+    fn check_call_args(&self, name: &str, args: &[Type]) -> Result<()> {
+        let Some(signature) = self.signatures.get(name) else {
+            return Ok(());
+        };
+        for ((param, expected), arg) in signature.iter().zip(args) {
+            let arg = &self.upcast(expected, arg.clone());
+            if !compatible(expected, arg) || (is_integer_like(expected) && arg.is_float()) {
+                return Err(type_error(format!(
+                    "argumento '{}' de '{}': se esperaba {}, se encontró {}",
+                    param,
+                    name,
+                    expected.to_string(),
+                    arg.to_string()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Tipo de retorno de una llamada a función (usuario o built-in)
+    fn call_type(&self, name: &str, args: &[Type]) -> Type {
+        if let Some(ty) = self.functions.get(name) {
+            return ty.clone();
+        }
+        let first = args.first().cloned().unwrap_or(Type::Unknown);
+        match name {
+            "len" | "int" | "ord" | "sum" | "count" | "index" | "find" => Type::Int64,
+            "float" => Type::Float64,
+            "str" | "input" | "chr" | "hex" | "bin" | "oct" | "type" => Type::String,
+            "upper" | "lower" | "strip" | "replace" | "join" => Type::String,
+            "split" => Type::Array { element_type: Box::new(Type::String), size: None },
+            "bool" | "all" | "any" | "isinstance" | "hasattr" | "startswith" | "endswith" => Type::Bool,
+            "abs" => first,
+            "min" | "max" if args.len() == 2 => unify(&args[0], &args[1]).unwrap_or(Type::Unknown),
+            "min" | "max" => match first {
+                Type::Array { element_type, .. } => *element_type,
+                _ => Type::Unknown,
+            },
+            "list" if matches!(first, Type::Set(_)) => match first {
+                Type::Set(element) => Type::Array { element_type: element, size: None },
+                _ => unreachable!(),
+            },
+            "sorted" | "reversed" | "list" => match first {
+                ty @ Type::Array { .. } => ty,
+                _ => Type::Unknown,
+            },
+            "dict" => Type::Dict { key: Box::new(Type::Unknown), value: Box::new(Type::Unknown) },
+            "set" => match first {
+                Type::Array { element_type, .. } => Type::Set(element_type),
+                _ => Type::Set(Box::new(Type::Unknown)),
+            },
+            "keys" | "values" | "items" | "get" => match first {
+                Type::Dict { key, value } => dict_method_type(&key, &value, name),
+                _ => Type::Unknown,
+            },
+            "map" => match first {
+                Type::Function { ret, .. } => Type::Array { element_type: ret, size: None },
+                _ => Type::Unknown,
+            },
+            "range" => Type::Array { element_type: Box::new(Type::Int64), size: None },
+            "filter" => args.get(1).cloned().unwrap_or(Type::Unknown),
+            "reduce" => match first {
+                Type::Function { ret, .. } if is_known(&ret) => *ret,
+                _ => args.get(2).cloned().unwrap_or(Type::Unknown),
+            },
+            _ => Type::Unknown,
+        }
+    }
+}
+
+/// Funciones built-in que el backend genera en línea o con una rutina del runtime
+const BUILTINS: &[&str] = &[
+    "len", "range", "int", "float", "str", "bool", "abs", "round", "pow", "sum", "min", "max",
+    "type", "sorted", "reversed", "all", "any", "enumerate", "zip", "map", "filter", "reduce",
+    "list", "set", "dict", "tuple", "keys", "values", "items", "get", "insert", "remove", "clear",
+    "copy", "count", "index", "chr", "ord", "hex", "bin", "oct", "input", "open", "isinstance",
+    "hasattr", "getattr", "setattr", "upper", "lower", "strip", "split", "join", "replace",
+    "find", "startswith", "endswith", "append", "pop", "array_free", "string_free",
+];
+
+fn is_builtin(name: &str) -> bool {
+    BUILTINS.contains(&name)
+}
+
+/// Parámetros que recibe la función pasada a `map`, `filter` o `reduce`
+fn callback_arity(builtin: &str) -> Option<usize> {
+    match builtin {
+        "map" | "filter" => Some(1),
+        "reduce" => Some(2),
+        _ => None,
+    }
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Métodos de string con tipo de retorno conocido
+fn string_method_type(method: &str) -> Type {
+    match method {
+        "upper" | "lower" | "strip" | "replace" | "join" | "slice" | "substring" => Type::String,
+        "len" | "find" | "count" | "index" => Type::Int64,
+        "startswith" | "endswith" => Type::Bool,
+        "split" => Type::Array { element_type: Box::new(Type::String), size: None },
+        _ => Type::Unknown,
+    }
+}
+
+/// Métodos de dict con tipo de retorno conocido
+fn dict_method_type(key: &Type, value: &Type, method: &str) -> Type {
+    let array_of = |ty: Type| Type::Array { element_type: Box::new(ty), size: None };
+    match method {
+        "get" => value.clone(),
+        "keys" => array_of(key.clone()),
+        "values" => array_of(value.clone()),
+        "items" => array_of(Type::Tuple(vec![key.clone(), value.clone()])),
+        "len" => Type::Int64,
+        "remove" | "contains" => Type::Bool,
+        _ => Type::Unknown,
+    }
+}
+
+/// Las claves de la tabla hash del runtime (dicts y sets) son enteros o strings
+fn check_hashable(key: &Type, what: &str) -> Result<()> {
+    if is_known(key) && !is_integer_like(key) && *key != Type::String {
+        return Err(type_error(format!(
+            "{} deben ser enteros o strings, se encontró {}",
+            what,
+            key.to_string()
+        )));
+    }
+    Ok(())
+}
+
+/// Verificar una clave usada para acceder a un dict con claves de tipo `key`
+fn check_dict_key_arg(key: &Type, arg: &Type) -> Result<()> {
+    if !compatible(key, arg) || (is_integer_like(key) && arg.is_float()) {
+        return Err(type_error(format!(
+            "clave de tipo {} en un dict con claves de tipo {}",
+            arg.to_string(),
+            key.to_string()
+        )));
+    }
+    check_hashable(arg, "las claves de un dict")
+}
+
+/// Verificar un elemento usado con un set de elementos de tipo `element`
+fn check_set_element(element: &Type, arg: &Type) -> Result<()> {
+    if !compatible(element, arg) || (is_integer_like(element) && arg.is_float()) {
+        return Err(type_error(format!(
+            "elemento de tipo {} en un set de tipo {}",
+            arg.to_string(),
+            element.to_string()
+        )));
+    }
+    check_hashable(arg, "los elementos de un set")
+}
+
+/// Declarar una variable de un patrón (no puede repetirse dentro del mismo patrón)
+fn bind_pattern_var(name: &str, ty: &Type, bindings: &mut HashMap<String, Type>) -> Result<()> {
+    if bindings.insert(name.to_string(), ty.clone()).is_some() {
+        return Err(type_error(format!("la variable '{}' aparece dos veces en el patrón", name)));
+    }
+    Ok(())
+}
+
+/// Forma abreviada de un patrón para los mensajes de error
+fn pattern_head(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Some(_) => "Some(..)".to_string(),
+        Pattern::None => "None".to_string(),
+        Pattern::Ok(_) => "Ok(..)".to_string(),
+        Pattern::Err(_) => "Err(..)".to_string(),
+        Pattern::Ident(name) => name.clone(),
+        Pattern::LiteralNumber(n) => n.to_string(),
+        Pattern::LiteralString(text) => format!("\"{}\"", text),
+        Pattern::LiteralBool(b) => b.to_string(),
+        Pattern::Range { start, end, inclusive } => {
+            format!("{}{}{}", start, if *inclusive { "..=" } else { ".." }, end)
+        }
+        Pattern::Tuple(_) => "(..)".to_string(),
+        Pattern::Struct { name, .. } => format!("{} {{ .. }}", name),
+        Pattern::Variant { enum_name, variant, fields } if fields.is_empty() => format!("{}.{}", enum_name, variant),
+        Pattern::Variant { enum_name, variant, .. } => format!("{}.{}(..)", enum_name, variant),
+        Pattern::Or(alternatives) => alternatives.iter().map(pattern_head).collect::<Vec<_>>().join(" | "),
+        Pattern::Binding { name, pattern } => format!("{} @ {}", name, pattern_head(pattern)),
+        Pattern::Wildcard => "_".to_string(),
+    }
+}
+
+/// Asociar los parámetros de tipo con los argumentos de una llamada a `name`
+fn bind_param_types(
+    name: &str,
+    type_params: &[String],
+    params: &[FnParam],
+    args: &[Type],
+    bindings: &mut HashMap<String, Type>,
+) -> Result<()> {
+    for (param, arg) in params.iter().zip(args) {
+        if let Some(ty) = &param.ty {
+            bind_type_params(type_params, ty, arg, bindings).map_err(|(t, a, b)| {
+                type_error(format!(
+                    "'{}': el parámetro de tipo {} no puede ser {} y {} a la vez",
+                    name,
+                    t,
+                    a.to_string(),
+                    b.to_string()
+                ))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Asociar los parámetros de tipo que aparecen en `declared` con las partes
+/// correspondientes de `actual`. Error `(T, a, b)` si T ya estaba asociado a otro tipo
+fn bind_type_params(
+    params: &[String],
+    declared: &Type,
+    actual: &Type,
+    bindings: &mut HashMap<String, Type>,
+) -> std::result::Result<(), (String, Type, Type)> {
+    match (declared, actual) {
+        (Type::Struct(name), _) if params.contains(name) && is_concrete(actual) => {
+            match bindings.get(name) {
+                Some(bound) if bound != actual => return Err((name.clone(), bound.clone(), actual.clone())),
+                Some(_) => {}
+                None => {
+                    bindings.insert(name.clone(), actual.clone());
+                }
+            }
+        }
+        (Type::Array { element_type: d, .. }, Type::Array { element_type: a, .. }) => {
+            bind_type_params(params, d, a, bindings)?;
+        }
+        (Type::Generic { name: d_name, args: d_args }, Type::Generic { name: a_name, args: a_args }) if d_name == a_name => {
+            for (d, a) in d_args.iter().zip(a_args) {
+                bind_type_params(params, d, a, bindings)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Reemplazar los parámetros de tipo de `ty` por su tipo concreto
+pub fn substitute(ty: &Type, bindings: &HashMap<String, Type>) -> Type {
+    match ty {
+        Type::Struct(name) => bindings.get(name).cloned().unwrap_or_else(|| ty.clone()),
+        Type::Array { element_type, size } => Type::Array {
+            element_type: Box::new(substitute(element_type, bindings)),
+            size: *size,
+        },
+        Type::Generic { name, args } => Type::Generic {
+            name: name.clone(),
+            args: args.iter().map(|arg| substitute(arg, bindings)).collect(),
+        },
+        Type::Function { params, ret } => Type::Function {
+            params: params.iter().map(|param| substitute(param, bindings)).collect(),
+            ret: Box::new(substitute(ret, bindings)),
+        },
+        _ => ty.clone(),
+    }
+}
+
+/// Nombre de la instancia de una función o struct genérico: `max_of__int64`,
+/// `Par__int64__string`, `Caja__arr_float64`. Es un identificador válido en NASM.
+pub fn instance_name(name: &str, args: &[Type]) -> String {
+    let mut mangled = name.to_string();
+    for arg in args {
+        mangled.push_str("__");
+        mangled.push_str(&mangle_type(arg));
+    }
+    mangled
+}
+
+fn mangle_type(ty: &Type) -> String {
+    match ty {
+        Type::Array { element_type, .. } => format!("arr_{}", mangle_type(element_type)),
+        Type::Generic { name, args } => instance_name(name, args),
+        other => other
+            .to_string()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect(),
+    }
+}
+
+/// ¿Tipo completamente conocido? (sin `Unknown` en ninguna parte)
+fn is_concrete(ty: &Type) -> bool {
+    match ty {
+        Type::Unknown => false,
+        Type::Array { element_type, .. } => is_concrete(element_type),
+        Type::Generic { args, .. } => args.iter().all(is_concrete),
+        _ => true,
+    }
+}
+
+/// Tipo de los elementos que produce un `for` sobre un valor de tipo `ty`
+fn iterable_element(ty: &Type) -> Result<Type> {
+    match ty {
+        Type::Array { element_type, .. } => Ok(*element_type.clone()),
+        Type::String => Ok(Type::String),
+        Type::Dict { key, .. } => Ok(*key.clone()),
+        Type::Set(element) => Ok(*element.clone()),
+        Type::Unknown => Ok(Type::Unknown),
+        _ => Err(type_error(format!("no se puede iterar sobre un valor de tipo {}", ty.to_string()))),
+    }
+}
+
+fn type_error(message: String) -> ADeadError {
+    ADeadError::TypeError { message }
+}
+
+fn is_known(ty: &Type) -> bool {
+    *ty != Type::Unknown
+}
+
+/// Enteros y bool (bool se comporta como 0/1 en operaciones aritméticas)
+fn is_integer_like(ty: &Type) -> bool {
+    ty.is_integer() || *ty == Type::Bool
+}
+
+fn is_numeric_like(ty: &Type) -> bool {
+    ty.is_numeric() || *ty == Type::Bool
+}
+
+/// ¿Se puede usar un valor de tipo `value` donde se espera `expected`?
+fn compatible(expected: &Type, value: &Type) -> bool {
+    unify(expected, value).is_some()
+}
+
+/// Tipo común de dos tipos (promoción int → float), o `None` si son incompatibles
+fn unify(a: &Type, b: &Type) -> Option<Type> {
+    match (a, b) {
+        (Type::Unknown, other) | (other, Type::Unknown) => Some(other.clone()),
+        (Type::Never, other) | (other, Type::Never) => Some(other.clone()),
+        _ if a == b => Some(a.clone()),
+        _ if a.is_float() && is_numeric_like(b) => Some(a.clone()),
+        _ if b.is_float() && is_numeric_like(a) => Some(b.clone()),
+        _ if is_integer_like(a) && is_integer_like(b) => Some(Type::Int64),
+        (Type::Option(x), Type::Option(y)) => Some(Type::Option(Box::new(unify(x, y)?))),
+        (Type::Result { ok: ok_a, err: err_a }, Type::Result { ok: ok_b, err: err_b }) => Some(Type::Result {
+            ok: Box::new(unify(ok_a, ok_b)?),
+            err: Box::new(unify(err_a, err_b)?),
+        }),
+        (
+            Type::Array { element_type: x, size: size_a },
+            Type::Array { element_type: y, size: size_b },
+        ) => Some(Type::Array {
+            element_type: Box::new(unify(x, y)?),
+            size: if size_a == size_b { *size_a } else { None },
+        }),
+        (Type::Dict { key: key_a, value: value_a }, Type::Dict { key: key_b, value: value_b }) => Some(Type::Dict {
+            key: Box::new(unify(key_a, key_b)?),
+            value: Box::new(unify(value_a, value_b)?),
+        }),
+        (Type::Set(x), Type::Set(y)) => Some(Type::Set(Box::new(unify(x, y)?))),
+        (Type::Function { params: params_a, ret: ret_a }, Type::Function { params: params_b, ret: ret_b })
+            if params_a.len() == params_b.len() =>
+        {
+            let params: Option<Vec<Type>> = params_a.iter().zip(params_b).map(|(a, b)| unify(a, b)).collect();
+            Some(Type::Function { params: params?, ret: Box::new(unify(ret_a, ret_b)?) })
+        }
+        (Type::Generic { name: x, args: args_a }, Type::Generic { name: y, args: args_b }) if x == y => {
+            let args: Option<Vec<Type>> = args_a.iter().zip(args_b).map(|(a, b)| unify(a, b)).collect();
+            Some(Type::Generic { name: x.clone(), args: args? })
+        }
+        // Dentro de un struct genérico `self` es `Caja` sin instanciar
+        (Type::Generic { name, .. }, Type::Struct(other)) | (Type::Struct(other), Type::Generic { name, .. })
+            if name == other =>
+        {
+            Some(Type::Struct(name.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// Verificar que se puede asignar `value` a la variable `name` de tipo `var`
+fn check_assignable(name: &str, var: &Type, value: &Type) -> Result<()> {
+    if compatible(var, value) {
+        return Ok(());
+    }
+    Err(type_error(format!(
+        "no se puede asignar {} a la variable '{}' de tipo {}",
+        value.to_string(),
+        name,
+        var.to_string()
+    )))
+}
+
+/// Verificar el valor inicial de una variable anotada: además de lo que admite
+/// una asignación, un float no se trunca implícitamente a un entero
+fn check_annotation(name: &str, declared: &Type, value: &Type) -> Result<()> {
+    if is_integer_like(declared) && value.is_float() {
+        return Err(type_error(format!(
+            "no se puede asignar {} a la variable '{}' de tipo {}",
+            value.to_string(),
+            name,
+            declared.to_string()
+        )));
+    }
+    check_assignable(name, declared, value)
+}
+
+fn op_symbol(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Mod => "%",
+        BinOp::Pow => "**",
+        BinOp::FloorDiv => "//",
+        BinOp::Eq => "==",
+        BinOp::Ne => "!=",
+        BinOp::Lt => "<",
+        BinOp::Le => "<=",
+        BinOp::Gt => ">",
+        BinOp::Ge => ">=",
+        BinOp::And => "&&",
+        BinOp::Or => "||",
+        BinOp::In => "in",
+    }
+}
+
+/// Tipo del resultado de `left op right`, o error si los operandos no son válidos
+fn binary_result(op: BinOp, left: &Type, right: &Type) -> Result<Type> {
+    let mismatch = || {
+        type_error(format!(
+            "tipos incompatibles: no se puede aplicar '{}' a {} y {}",
+            op_symbol(op),
+            left.to_string(),
+            right.to_string()
+        ))
+    };
+    match op {
+        BinOp::And | BinOp::Or => Ok(Type::Bool),
+        BinOp::In => {
+            match right {
+                Type::Set(element) => check_set_element(element, left)?,
+                Type::Dict { key, .. } => check_dict_key_arg(key, left)?,
+                Type::Unknown => {}
+                _ => {
+                    return Err(type_error(format!(
+                        "el operador 'in' requiere un set o un dict, se encontró {}",
+                        right.to_string()
+                    )));
+                }
+            }
+            Ok(Type::Bool)
+        }
+        BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+            if unify(left, right).is_none() {
+                return Err(mismatch());
+            }
+            Ok(Type::Bool)
+        }
+        BinOp::Add if *left == Type::String || *right == Type::String => {
+            // Concatenación: solo string + string
+            if (is_known(left) && *left != Type::String) || (is_known(right) && *right != Type::String) {
+                return Err(mismatch());
+            }
+            Ok(Type::String)
+        }
+        _ => {
+            let left_ok = !is_known(left) || is_numeric_like(left);
+            let right_ok = !is_known(right) || is_numeric_like(right);
+            if !left_ok || !right_ok {
+                return Err(mismatch());
+            }
+            if left.is_float() || right.is_float() {
+                Ok(Type::Float64)
+            } else if is_known(left) && is_known(right) {
+                Ok(Type::Int64)
+            } else {
+                Ok(Type::Unknown)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adead_parser::parse;
+
+    fn check(src: &str) -> Result<HashMap<String, Type>> {
+        let program = parse(src).unwrap();
+        let typed = TypeChecker::new().check(&program)?;
+        // Tipo de cada `let` de nivel superior (el anotado o el de su valor)
+        Ok(program
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.node {
+                Stmt::Let { name, ty, value, .. } => {
+                    Some((name.clone(), ty.clone().unwrap_or_else(|| typed.type_of(value).clone())))
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_literals_and_promotion() {
+        let types = check("let a = 1\nlet b = 2.5\nlet c = a + b\nlet d = a * 3\nlet e = a < 2\n").unwrap();
+        assert_eq!(types["a"], Type::Int64);
+        assert_eq!(types["b"], Type::Float64);
+        assert_eq!(types["c"], Type::Float64);
+        assert_eq!(types["d"], Type::Int64);
+        assert_eq!(types["e"], Type::Bool);
+    }
+
+    #[test]
+    fn test_string_concat_and_methods() {
+        let types = check("let s = \"a\" + \"b\"\nlet n = len(s)\nlet u = s.upper()\n").unwrap();
+        assert_eq!(types["s"], Type::String);
+        assert_eq!(types["n"], Type::Int64);
+        assert_eq!(types["u"], Type::String);
+    }
+
+    #[test]
+    fn test_string_plus_int_is_error() {
+        let src = "let a = 1\nlet s = \"x\"\nlet c = s + a\n";
+        let program = parse(src).unwrap();
+        let err = TypeChecker::new().check(&program).err().unwrap();
+        assert!(err.to_string().contains("'+' a string y int64"), "{}", err);
+        assert_eq!(err.line_col(src), Some((3, 9)));
+    }
+
+    #[test]
+    fn test_error_reports_innermost_subexpression() {
+        let src = "let a = 1\nlet s = \"x\"\nlet c = [a, a * (s + a)]\n";
+        let program = parse(src).unwrap();
+        let err = TypeChecker::new().check(&program).err().unwrap();
+        assert_eq!(err.line_col(src), Some((3, 17)), "{}", err);
+    }
+
+    #[test]
+    fn test_assignment_mismatch() {
+        let src = "let mut x = 1\nx = \"hola\"\n";
+        let program = parse(src).unwrap();
+        let err = TypeChecker::new().check(&program).err().unwrap();
+        assert!(err.to_string().contains("variable 'x' de tipo int64"), "{}", err);
+    }
+
+    #[test]
+    fn test_function_return_and_struct_types() {
+        let src = r#"
+            struct Punto {
+                x: float64
+                y: float64
+            }
+            fn doble(n) {
+                return n * 2.0
+            }
+            let p = Punto { x: 1.0, y: 2.0 }
+            let px = p.x
+            let d = doble(3)
+        "#;
+        let types = check(src).unwrap();
+        assert_eq!(types["p"], Type::Struct("Punto".to_string()));
+        assert_eq!(types["px"], Type::Float64);
+        assert_eq!(types["d"], Type::Float64);
+    }
+
+    #[test]
+    fn test_locals_are_typed_per_scope() {
+        let src = "let s = 1\nfn f() {\n    let s = \"texto\"\n    return s\n}\nlet r = f()\n";
+        let types = check(src).unwrap();
+        assert_eq!(types["s"], Type::Int64);
+        assert_eq!(types["r"], Type::String);
+    }
+
+    #[test]
+    fn test_annotations() {
+        let vars = check("let a: int8 = 5\nlet f: float64 = 1\nlet b = a + 1\n").unwrap();
+        assert_eq!(vars["a"], Type::Int8);
+        assert_eq!(vars["f"], Type::Float64);
+        assert_eq!(vars["b"], Type::Int64);
+
+        let err = check("let n: int32 = \"hola\"\n").unwrap_err();
+        assert!(err.to_string().contains("no se puede asignar string a la variable 'n' de tipo int32"));
+        assert!(check("let n: int64 = 2.5\n").is_err());
+    }
+
+    #[test]
+    fn test_annotated_signatures() {
+        let src = "fn area(w: int64, h: int64) -> float64 {\n    return w * h\n}\nlet a = area(2, 3)\n";
+        assert_eq!(check(src).unwrap()["a"], Type::Float64);
+
+        let err = check("fn f(n: int32) {\n    print n\n}\nf(\"x\")\n").unwrap_err();
+        assert!(err.to_string().contains("argumento 'n' de 'f': se esperaba int32, se encontró string"));
+        assert_eq!(err.line_col("fn f(n: int32) {\n    print n\n}\nf(\"x\")\n"), Some((4, 1)));
+
+        let err = check("fn g() -> int64 {\n    return \"no\"\n}\n").unwrap_err();
+        assert!(err.to_string().contains("tipo de retorno incompatible: se esperaba int64, se encontró string"));
+    }
+
+    #[test]
+    fn test_derived_struct_where_base_expected() {
+        let src = "struct Animal {\n    n: int64\n}\nstruct Perro extends Animal {\n}\nstruct Cachorro extends Perro {\n}\nfn f(a: Animal) {\n    print a.n\n}\nlet c = Cachorro { n: 1 }\nf(c)\nlet a: Animal = c\n";
+        assert_eq!(check(src).unwrap()["a"], Type::Struct("Animal".to_string()));
+
+        let err = check("struct Animal {\n}\nstruct Perro extends Animal {\n}\nfn f(p: Perro) {\n}\nf(Animal {})\n").unwrap_err();
+        assert!(err.to_string().contains("argumento 'p' de 'f': se esperaba Perro, se encontró Animal"), "{}", err);
+    }
+
+    #[test]
+    fn test_array_of_derived_structs_unifies_to_common_ancestor() {
+        let structs = "struct Animal {\n    n: int64\n}\nstruct Perro extends Animal {\n}\nstruct Gato extends Animal {\n}\nstruct Cachorro extends Perro {\n}\nlet a = Animal { n: 1 }\nlet p = Perro { n: 2 }\nlet g = Gato { n: 3 }\nlet c = Cachorro { n: 4 }\n";
+        let vars = check(&format!("{}let todos = [a, p, g]\nlet perros = [c, p]\nlet mascotas: [Animal] = [p, g]\nlet m = mascotas[1]\n", structs)).unwrap();
+        let element = |name: &str| match &vars[name] {
+            Type::Array { element_type, .. } => (**element_type).clone(),
+            other => panic!("se esperaba un array: {:?}", other),
+        };
+        assert_eq!(element("todos"), Type::Struct("Animal".to_string()));
+        assert_eq!(element("perros"), Type::Struct("Perro".to_string()));
+        assert_eq!(element("mascotas"), Type::Struct("Animal".to_string()));
+        assert_eq!(vars["m"], Type::Struct("Animal".to_string()));
+
+        let err = check(&format!("{}let xs: [Perro] = [p, g]\n", structs)).unwrap_err();
+        assert!(err.to_string().contains("los elementos del array deben ser Perro, se encontró Gato"), "{}", err);
+    }
+
+    #[test]
+    fn test_generics() {
+        let src = "fn max_of<T>(a: T, b: T) -> T {\n    if a > b {\n        return a\n    }\n    return b\n}\nstruct Caja<T> {\n    valor: T\n    fn get(self) -> T {\n        return self.valor\n    }\n}\nlet a = max_of(1, 2)\nlet b = max_of(\"x\", \"y\")\nlet c = Caja { valor: 2.5 }\nlet d = c.get()\nlet e = c.valor\n";
+        let program = parse(src).unwrap();
+        let typed = TypeChecker::new().check(&program).unwrap();
+        let value = |i: usize| match &program.statements[i].node {
+            Stmt::Let { value, .. } => value,
+            _ => panic!("Expected Let statement"),
+        };
+        assert_eq!(typed.type_of(value(2)), &Type::Int64);
+        assert_eq!(typed.type_of(value(3)), &Type::String);
+        let caja = Type::Generic { name: "Caja".to_string(), args: vec![Type::Float64] };
+        assert_eq!(typed.type_of(value(4)), &caja);
+        assert_eq!(typed.type_of(value(5)), &Type::Float64);
+        assert_eq!(typed.type_of(value(6)), &Type::Float64);
+        // Una instancia por uso, con los argumentos de tipo en orden de declaración
+        assert_eq!(typed.generic_args.get(value(2)), Some(&[Type::Int64][..]));
+        assert_eq!(typed.generic_args.get(value(4)), Some(&[Type::Float64][..]));
+        assert_eq!(typed.generic_args.len(), 3);
+        assert_eq!(instance_name("Par", &[Type::Int64, caja]), "Par__int64__Caja__float64");
+
+        let err = check("fn f<T>(a: T, b: T) {\n}\nf(1, \"x\")\n").unwrap_err();
+        assert!(err.to_string().contains("'f': el parámetro de tipo T no puede ser int64 y string a la vez"), "{}", err);
+        let err = check("struct Caja<T> {\n    valor: T\n}\nlet c = Caja { }\n").unwrap_err();
+        assert!(err.to_string().contains("no se pudo inferir el parámetro de tipo T de 'Caja'"), "{}", err);
+        // La anotación fija el parámetro de tipo
+        let err = check("struct Caja<T> {\n    valor: T\n}\nlet c: Caja<string> = Caja { valor: 1 }\n").unwrap_err();
+        assert!(err.to_string().contains("el parámetro de tipo T no puede ser string y int64"), "{}", err);
+    }
+
+    #[test]
+    fn test_generic_struct_init() {
+        let stack = "struct Stack<T> {\n    items: [T]\n    init(primero: T) {\n        self.items = [primero]\n    }\n    fn pop(self) -> T {\n        return self.items.pop()\n    }\n}\n";
+        let types = check(&format!("{}let s = Stack.new(1.5)\nlet x = s.pop()\nlet t: Stack<int64> = Stack.new(2)\n", stack)).unwrap();
+        assert_eq!(types["s"], Type::Generic { name: "Stack".to_string(), args: vec![Type::Float64] });
+        assert_eq!(types["x"], Type::Float64);
+        assert_eq!(types["t"], Type::Generic { name: "Stack".to_string(), args: vec![Type::Int64] });
+
+        let err = check(&format!("{}let s: Stack<string> = Stack.new(1)\n", stack)).unwrap_err();
+        assert!(err.to_string().contains("'Stack.new': el parámetro de tipo T no puede ser string y int64"), "{}", err);
+        let err = check("struct Pila<T> {\n    items: [T]\n    init() {\n    }\n}\nlet p = Pila.new()\n").unwrap_err();
+        assert!(err.to_string().contains("no se pudo inferir el parámetro de tipo T de 'Pila'"), "{}", err);
+    }
+
+    #[test]
+    fn test_dicts() {
+        let types = check("let d = {\"a\": 1, \"b\": 2}\nlet v = d[\"a\"]\nlet ks = d.keys()\nlet g = get(d, \"z\", 0)\nlet n: dict<int64, float64> = dict()\n").unwrap();
+        let dict_of = |key: Type, value: Type| Type::Dict { key: Box::new(key), value: Box::new(value) };
+        assert_eq!(types["d"], dict_of(Type::String, Type::Int64));
+        assert_eq!(types["v"], Type::Int64);
+        assert_eq!(types["ks"], Type::Array { element_type: Box::new(Type::String), size: None });
+        assert_eq!(types["g"], Type::Int64);
+        assert_eq!(types["n"], dict_of(Type::Int64, Type::Float64));
+
+        let err = check("let d = {\"a\": 1, 2: 3}\n").unwrap_err();
+        assert!(err.to_string().contains("las claves del dict tienen tipos incompatibles: string y int64"), "{}", err);
+        let err = check("let d = {1.5: 1}\n").unwrap_err();
+        assert!(err.to_string().contains("las claves de un dict deben ser enteros o strings, se encontró float64"), "{}", err);
+        let err = check("let d = {\"a\": 1}\nlet v = d[3]\n").unwrap_err();
+        assert!(err.to_string().contains("clave de tipo int64 en un dict con claves de tipo string"), "{}", err);
+        let err = check("let d = {\"a\": 1}\nd[\"b\"] = \"x\"\n").unwrap_err();
+        assert!(err.to_string().contains("no se puede asignar string a un elemento de tipo int64"), "{}", err);
+    }
+
+    #[test]
+    fn test_sets() {
+        let src = "let s = {1, 2, 2}\nlet t = {x * 2 for x in [1, 2]}\nlet u = s.union(t)\nlet b = 3 in s\nlet n = len(u)\nlet l = list(s)\nlet e: set<string> = set()\nlet k = \"a\" in {\"a\": 1}\n";
+        let types = check(src).unwrap();
+        let int_set = Type::Set(Box::new(Type::Int64));
+        assert_eq!(types["s"], int_set);
+        assert_eq!(types["t"], int_set);
+        assert_eq!(types["u"], int_set);
+        assert_eq!(types["b"], Type::Bool);
+        assert_eq!(types["n"], Type::Int64);
+        assert_eq!(types["l"], Type::Array { element_type: Box::new(Type::Int64), size: None });
+        assert_eq!(types["e"], Type::Set(Box::new(Type::String)));
+        assert_eq!(types["k"], Type::Bool);
+
+        let err = check("let s = {1, \"a\"}\n").unwrap_err();
+        assert!(err.to_string().contains("los elementos del set tienen tipos incompatibles: int64 y string"), "{}", err);
+        let err = check("let s = {1.5}\n").unwrap_err();
+        assert!(err.to_string().contains("los elementos de un set deben ser enteros o strings, se encontró float64"), "{}", err);
+        let err = check("let s = {1}\ns.add(\"x\")\n").unwrap_err();
+        assert!(err.to_string().contains("elemento de tipo string en un set de tipo int64"), "{}", err);
+        let err = check("let b = 1 in [1, 2]\n").unwrap_err();
+        assert!(err.to_string().contains("el operador 'in' requiere un set o un dict"), "{}", err);
+        let err = check("let s = {1}\nlet v = s[0]\n").unwrap_err();
+        assert!(err.to_string().contains("no se puede indexar un set"), "{}", err);
+    }
+
+    #[test]
+    fn test_nested_comprehensions() {
+        let src = "let xs = [1, 2]\nlet ns = [\"a\", \"b\"]\nlet ps = [(x, n) for x in xs if x > 0 for n in ns]\nlet d = {n: len(n) for n in ns}\n";
+        let types = check(src).unwrap();
+        let pair = Type::Tuple(vec![Type::Int64, Type::String]);
+        assert_eq!(types["ps"], Type::Array { element_type: Box::new(pair), size: None });
+        assert_eq!(types["d"], Type::Dict { key: Box::new(Type::String), value: Box::new(Type::Int64) });
+
+        // El iterador de una cláusula ve las variables de las anteriores
+        let types = check("let ns = [\"a\"]\nlet cs = [c for n in ns for c in [n, n]]\n").unwrap();
+        assert_eq!(types["cs"], Type::Array { element_type: Box::new(Type::String), size: None });
+    }
+
+    #[test]
+    fn test_traits() {
+        let src = "trait Forma {\n    fn area(self) -> int64\n}\nstruct C {\n    r: int64\n}\nstruct Q {\n    l: int64\n}\nimpl Forma for C {\n    fn area(self) -> int64 { return self.r }\n}\nimpl Forma for Q {\n    fn area(self) -> int64 { return self.l }\n}\nlet v: [Forma] = [C { r: 1 }, Q { l: 2 }]\nlet e = v[0]\nlet a = e.area()\nlet b = C { r: 3 }.area()\n";
+        let types = check(src).unwrap();
+        let forma = Type::Trait("Forma".to_string());
+        assert_eq!(types["e"], forma);
+        assert_eq!(types["a"], Type::Int64);
+        assert_eq!(types["b"], Type::Int64);
+
+        let base = "trait Forma {\n    fn area(self) -> int64\n    fn lados(self)\n}\nstruct C {\n}\n";
+        let err = check(&format!("{}impl Forma for C {{\n    fn area(self) -> int64 {{ return 1 }}\n}}\n", base)).unwrap_err();
+        assert!(err.to_string().contains("'C' no implementa el método 'lados' del trait 'Forma'"), "{}", err);
+        let err = check(&format!("{}impl Forma for C {{\n    fn area(self) {{ return \"x\" }}\n    fn lados(self) {{ }}\n}}\n", base)).unwrap_err();
+        assert!(err.to_string().contains("el método 'area' de 'C' retorna string pero el trait 'Forma' declara int64"), "{}", err);
+        let err = check(&format!("{}impl Forma for C {{\n    fn area(self, x) {{ return 1 }}\n    fn lados(self) {{ }}\n}}\n", base)).unwrap_err();
+        assert!(err.to_string().contains("recibe 2 parámetro(s) pero el trait 'Forma' declara 1"), "{}", err);
+        let err = check(&format!("{}fn f(x: Forma) {{\n}}\nf(C {{}})\n", base)).unwrap_err();
+        assert!(err.to_string().contains("se esperaba Forma, se encontró C"), "{}", err);
+    }
+
+    #[test]
+    fn test_enums() {
+        let src = "enum Shape {\n    Circle(radius: int64),\n    Rect(w, h),\n    Empty\n}\nlet s = Shape.Circle(2)\nlet a = match s {\n    Circle(r) => r * r,\n    Rect(w, h) => w * h,\n    Empty => 0\n}\n";
+        let types = check(src).unwrap();
+        assert_eq!(types["s"], Type::Enum("Shape".to_string()));
+        assert_eq!(types["a"], Type::Int64);
+
+        let err = check("enum Shape {\n    Circle(radius: int64),\n    Empty\n}\nlet s = Shape.Circle(\"x\")\n").unwrap_err();
+        assert!(err.to_string().contains("el campo 1 de 'Shape.Circle' es int64 pero se le asigna string"));
+
+        let err = check("enum Luz {\n    Roja,\n    Verde,\n    Ambar(n)\n}\nlet l = Luz.Roja\nlet r = match l {\n    Roja => 1,\n    Verde => 2\n}\n").unwrap_err();
+        assert!(err.to_string().contains("match no exhaustivo: el patrón `Luz.Ambar(_)` no está cubierto"));
+
+        let err = check("enum Luz {\n    Roja,\n    Verde\n}\nlet r = match 3 {\n    Roja => 1,\n    _ => 2\n}\n").unwrap_err();
+        assert!(err.to_string().contains("el patrón `Luz.Roja` no es compatible con int64"));
+    }
+
+    #[test]
+    fn test_match_patterns_and_exhaustiveness() {
+        let src = "let o = Some(Ok(3))\nlet r = match o {\n    Some(Ok(n)) if n > 0 => n,\n    Some(Ok(n)) => 0 - n,\n    Some(Err(_)) => 0,\n    None => 0\n}\n";
+        assert_eq!(check(src).unwrap()["r"], Type::Int64);
+
+        let err = check("let o = Some(Ok(3))\nlet r = match o {\n    Some(Ok(n)) if n > 0 => n,\n    None => 0\n}\n").unwrap_err();
+        assert!(err.to_string().contains("match no exhaustivo: el patrón `Some(_)` no está cubierto"));
+
+        let err = check("let n = 4\nlet r = match n {\n    1 | 2 => 1,\n    3..=9 => 2\n}\n").unwrap_err();
+        assert!(err.to_string().contains("match no exhaustivo: el patrón `_` no está cubierto"));
+
+        let err = check("let n = 4\nlet r = match n {\n    \"hola\" => 1,\n    _ => 2\n}\n").unwrap_err();
+        assert!(err.to_string().contains("el patrón `\"hola\"` no es compatible con int64"));
+
+        let err = check("let t = (1, 2)\nlet r = match t {\n    (a, 0) | (0, b) => 1,\n    _ => 2\n}\n").unwrap_err();
+        assert!(err.to_string().contains("todas las alternativas de `|` deben enlazar las mismas variables"));
+    }
+
+    #[test]
+    fn test_for_loops() {
+        // Las variables del for toman el tipo del elemento y solo viven dentro del cuerpo
+        let src = "let xs = [1, 2]\nlet d = {\"a\": 1}\nlet mut s = \"\"\nlet mut n = 0\nfor (i, x) in enumerate(xs) {\n    n = n + i + x\n}\nfor k in d {\n    s = k\n}\nfor c in \"hola\" {\n    s = c\n}\nfor (x, k) in zip(xs, d.keys()) {\n    s = k\n}\nfor i in range(10, 0, -2) {\n    n = n + i\n}\n";
+        let types = check(src).unwrap();
+        assert_eq!(types["s"], Type::String);
+        assert!(!types.contains_key("i"));
+
+        let err = check("let mut s = \"\"\nfor x in [1, 2] {\n    s = x\n}\n").unwrap_err();
+        assert!(err.to_string().contains("no se puede asignar int64"), "{}", err);
+        let err = check("for (a, b) in [1, 2] {\n    print a\n}\n").unwrap_err();
+        assert!(err.to_string().contains("el for declara 2 variable(s) pero cada elemento tiene 1"), "{}", err);
+        let err = check("for i in range(0, 5, 0) {\n    print i\n}\n").unwrap_err();
+        assert!(err.to_string().contains("el paso de range no puede ser 0"), "{}", err);
+        let err = check("for i in 1.5 {\n    print i\n}\n").unwrap_err();
+        assert!(err.to_string().contains("no se puede iterar sobre un valor de tipo float64"), "{}", err);
+    }
+
+    #[test]
+    fn test_function_values() {
+        let src = "fn cuadrado(x: int64) -> int64 {\n    return x * x\n}\nfn aplicar(f: fn(int64) -> int64, v: int64) -> int64 {\n    return f(v)\n}\nlet f = cuadrado\nlet r = f(3) + aplicar(cuadrado, 2)\nlet ys = map(lambda x: x + 1, [1, 2])\n";
+        let types = check(src).unwrap();
+        assert_eq!(types["f"], Type::Function { params: vec![Type::Int64], ret: Box::new(Type::Int64) });
+        assert_eq!(types["r"], Type::Int64);
+        assert!(matches!(&types["ys"], Type::Array { .. }));
+
+        let fns = "fn cuadrado(x: int64) -> int64 {\n    return x * x\n}\nfn suma(a: int64, b: int64) -> int64 {\n    return a + b\n}\nfn aplicar(f: fn(int64) -> int64, v: int64) -> int64 {\n    return f(v)\n}\n";
+        let err = check(&format!("{}let f = cuadrado\nlet r = f(1, 2)\n", fns)).unwrap_err();
+        assert!(err.to_string().contains("'f' recibe 1 argumento(s) pero se pasaron 2"), "{}", err);
+        let err = check(&format!("{}let f = cuadrado\nlet r = f(\"a\")\n", fns)).unwrap_err();
+        assert!(err.to_string().contains("argumento 1 de 'f': se esperaba int64, se encontró string"), "{}", err);
+        let err = check(&format!("{}let r = aplicar(suma, 1)\n", fns)).unwrap_err();
+        assert!(err.to_string().contains("se esperaba fn(int64) -> int64, se encontró fn(int64, int64) -> int64"), "{}", err);
+        let err = check("let ys = map(lambda a, b: a + b, [1])\n").unwrap_err();
+        assert!(err.to_string().contains("la función de 'map' debe recibir 1 argumento(s), recibe 2"), "{}", err);
+    }
+
+    #[test]
+    fn test_string_builtins_and_slices() {
+        let src = "let s = \"a,b,c\"\nlet partes = split(s, \",\")\nlet j = join(\"-\", partes)\nlet m = s.upper().replace(\"A\", \"x\")\nlet c = s[-1]\nlet r = s[::-1]\nlet t = s[1:]\nlet n = find(s, \"b\")\nlet p = s.startswith(\"a\")\n";
+        let types = check(src).unwrap();
+        assert_eq!(types["partes"], Type::Array { element_type: Box::new(Type::String), size: None });
+        for name in ["j", "m", "c", "r", "t"] {
+            assert_eq!(types[name], Type::String, "{}", name);
+        }
+        assert_eq!(types["n"], Type::Int64);
+        assert_eq!(types["p"], Type::Bool);
+
+        let err = check("let s = \"hola\"\nlet c = s[\"x\"]\n").unwrap_err();
+        assert!(err.to_string().contains("el índice de un string debe ser entero, se encontró string"), "{}", err);
+        let err = check("let s = \"hola\"\nlet r = s[::0]\n").unwrap_err();
+        assert!(err.to_string().contains("el paso de un slice no puede ser 0"), "{}", err);
+        let err = check("let s = \"hola\"\nlet r = s[0:1.5]\n").unwrap_err();
+        assert!(err.to_string().contains("los límites y el paso de un slice deben ser enteros, se encontró float64"), "{}", err);
+    }
+
+    #[test]
+    fn test_undefined_functions_and_range() {
+        let err = check("let y = foo(3)\n").unwrap_err();
+        assert!(err.to_string().contains("función 'foo' no definida"), "{}", err);
+
+        // Una función puede llamar a otra declarada después; range es built-in
+        let src = "fn a(n) {\n    return b(n)\n}\nfn b(n) {\n    return n\n}\nlet sq = [x * x for x in range(1, 4)]\nlet r = range(5)\n";
+        let types = check(src).unwrap();
+        let ints = Type::Array { element_type: Box::new(Type::Int64), size: None };
+        assert_eq!(types["sq"], ints);
+        assert_eq!(types["r"], ints);
+    }
+
+}
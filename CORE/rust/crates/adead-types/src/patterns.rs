@@ -0,0 +1,312 @@
+//! Exhaustividad de `match` - algoritmo de "usefulness" (Maranget)
+//!
+//! Los brazos sin guarda forman una matriz de patrones; el match es exhaustivo si el
+//! patrón `_` no es "útil" respecto a esa matriz (no hay valor que ningún brazo cubra).
+//! Si lo es, el algoritmo construye un testigo: un patrón no cubierto (`Some(None)`).
+//!
+//! Constructores con dominio finito: `Some`/`None`, `Ok`/`Err`, `true`/`false`,
+//! las variantes de cada enum, tuplas y structs (un solo constructor cada uno).
+//! Los enteros y strings tienen dominio infinito: solo `_` o una variable los cubre.
+//! Los brazos con guarda no cuentan, porque la guarda puede ser falsa.
+
+use adead_common::Type;
+use adead_parser::Pattern;
+use std::collections::{BTreeSet, HashMap};
+
+/// Constructor en la cabeza de un patrón
+#[derive(Debug, Clone, PartialEq)]
+enum Ctor {
+    Some,
+    None,
+    Ok,
+    Err,
+    Bool(bool),
+    Tuple(usize),
+    Struct(String),
+    /// Variante de enum: (enum, variante)
+    Variant(String, String),
+    /// Literal o rango de int/string (dominio infinito)
+    Literal,
+}
+
+/// Patrón simplificado: sin variables ni `@`
+#[derive(Debug, Clone)]
+enum Pat {
+    Wild,
+    Ctor(Ctor, Vec<Pat>),
+    Or(Vec<Pat>),
+}
+
+struct Matrix<'e> {
+    /// Campos de cada struct: unión ordenada de los nombrados en todos los patrones
+    struct_fields: HashMap<String, Vec<String>>,
+    /// Variantes de cada enum con los tipos de su payload
+    enums: &'e HashMap<String, Vec<(String, Vec<Type>)>>,
+}
+
+/// Devuelve un patrón no cubierto por `patterns` (en texto), o `None` si son exhaustivos
+pub(crate) fn missing_pattern(
+    patterns: &[&Pattern],
+    enums: &HashMap<String, Vec<(String, Vec<Type>)>>,
+) -> Option<String> {
+    let mut fields: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for pattern in patterns {
+        collect_struct_fields(pattern, &mut fields);
+    }
+    let matrix = Matrix {
+        struct_fields: fields
+            .into_iter()
+            .map(|(name, set)| (name, set.into_iter().collect()))
+            .collect(),
+        enums,
+    };
+    let rows = patterns.iter().map(|p| vec![matrix.lower(p)]).collect();
+    let witness = matrix.missing(rows, 1)?;
+    Some(matrix.render(&witness[0]))
+}
+
+fn collect_struct_fields(pattern: &Pattern, fields: &mut HashMap<String, BTreeSet<String>>) {
+    match pattern {
+        Pattern::Struct { name, fields: named } => {
+            let set = fields.entry(name.clone()).or_default();
+            set.extend(named.iter().map(|(field, _)| field.clone()));
+            for (_, sub) in named {
+                collect_struct_fields(sub, fields);
+            }
+        }
+        Pattern::Some(inner) | Pattern::Ok(inner) | Pattern::Err(inner) => collect_struct_fields(inner, fields),
+        Pattern::Binding { pattern, .. } => collect_struct_fields(pattern, fields),
+        Pattern::Tuple(items) | Pattern::Or(items) | Pattern::Variant { fields: items, .. } => {
+            for item in items {
+                collect_struct_fields(item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Matrix<'_> {
+    fn lower(&self, pattern: &Pattern) -> Pat {
+        match pattern {
+            Pattern::Wildcard | Pattern::Ident(_) => Pat::Wild,
+            Pattern::Binding { pattern, .. } => self.lower(pattern),
+            Pattern::LiteralNumber(_) | Pattern::LiteralString(_) | Pattern::Range { .. } => {
+                Pat::Ctor(Ctor::Literal, vec![])
+            }
+            Pattern::LiteralBool(b) => Pat::Ctor(Ctor::Bool(*b), vec![]),
+            Pattern::Some(inner) => Pat::Ctor(Ctor::Some, vec![self.lower(inner)]),
+            Pattern::None => Pat::Ctor(Ctor::None, vec![]),
+            Pattern::Ok(inner) => Pat::Ctor(Ctor::Ok, vec![self.lower(inner)]),
+            Pattern::Err(inner) => Pat::Ctor(Ctor::Err, vec![self.lower(inner)]),
+            Pattern::Tuple(items) => Pat::Ctor(Ctor::Tuple(items.len()), items.iter().map(|p| self.lower(p)).collect()),
+            Pattern::Struct { name, fields } => {
+                let args = self.struct_fields[name]
+                    .iter()
+                    .map(|field| match fields.iter().find(|(f, _)| f == field) {
+                        Some((_, sub)) => self.lower(sub),
+                        None => Pat::Wild,
+                    })
+                    .collect();
+                Pat::Ctor(Ctor::Struct(name.clone()), args)
+            }
+            Pattern::Variant { enum_name, variant, fields } => Pat::Ctor(
+                Ctor::Variant(enum_name.clone(), variant.clone()),
+                fields.iter().map(|p| self.lower(p)).collect(),
+            ),
+            Pattern::Or(alternatives) => Pat::Or(alternatives.iter().map(|p| self.lower(p)).collect()),
+        }
+    }
+
+    fn arity(&self, ctor: &Ctor) -> usize {
+        match ctor {
+            Ctor::Some | Ctor::Ok | Ctor::Err => 1,
+            Ctor::None | Ctor::Bool(_) | Ctor::Literal => 0,
+            Ctor::Tuple(n) => *n,
+            Ctor::Struct(name) => self.struct_fields.get(name).map_or(0, Vec::len),
+            Ctor::Variant(enum_name, variant) => self
+                .enums
+                .get(enum_name)
+                .and_then(|variants| variants.iter().find(|(name, _)| name == variant))
+                .map_or(0, |(_, payload)| payload.len()),
+        }
+    }
+
+    /// Todos los constructores del tipo de la columna (deducido de sus cabezas);
+    /// `None` si el dominio es infinito o la columna no tiene constructores
+    fn signature(&self, heads: &[&Ctor]) -> Option<Vec<Ctor>> {
+        if heads.contains(&&Ctor::Literal) {
+            return None;
+        }
+        Some(match heads.first()? {
+            Ctor::Some | Ctor::None => vec![Ctor::Some, Ctor::None],
+            Ctor::Ok | Ctor::Err => vec![Ctor::Ok, Ctor::Err],
+            Ctor::Bool(_) => vec![Ctor::Bool(true), Ctor::Bool(false)],
+            Ctor::Variant(enum_name, _) => self
+                .enums
+                .get(enum_name)?
+                .iter()
+                .map(|(variant, _)| Ctor::Variant(enum_name.clone(), variant.clone()))
+                .collect(),
+            ctor => vec![(*ctor).clone()],
+        })
+    }
+
+    /// Vector de `width` patrones que ninguna fila cubre (testigo), si existe
+    fn missing(&self, rows: Vec<Vec<Pat>>, width: usize) -> Option<Vec<Pat>> {
+        if width == 0 {
+            return if rows.is_empty() { Some(vec![]) } else { None };
+        }
+        let rows = expand_or(rows);
+        let heads: Vec<&Ctor> = rows
+            .iter()
+            .filter_map(|row| match &row[0] {
+                Pat::Ctor(ctor, _) => Some(ctor),
+                _ => None,
+            })
+            .collect();
+
+        match self.signature(&heads) {
+            Some(signature) if signature.iter().all(|ctor| heads.contains(&ctor)) => {
+                // Columna completa: el testigo está en alguno de los constructores
+                signature.into_iter().find_map(|ctor| {
+                    let arity = self.arity(&ctor);
+                    let specialized = self.specialize(&rows, &ctor, arity);
+                    let mut witness = self.missing(specialized, arity + width - 1)?;
+                    let rest = witness.split_off(arity);
+                    Some(std::iter::once(Pat::Ctor(ctor, witness)).chain(rest).collect())
+                })
+            }
+            signature => {
+                // Columna incompleta: basta con las filas cuya cabeza es `_`
+                let default = rows
+                    .iter()
+                    .filter(|row| matches!(row[0], Pat::Wild))
+                    .map(|row| row[1..].to_vec())
+                    .collect();
+                let mut witness = self.missing(default, width - 1)?;
+                let head = signature
+                    .and_then(|signature| signature.into_iter().find(|ctor| !heads.contains(&ctor)))
+                    .map(|ctor| Pat::Ctor(ctor.clone(), vec![Pat::Wild; self.arity(&ctor)]))
+                    .unwrap_or(Pat::Wild);
+                witness.insert(0, head);
+                Some(witness)
+            }
+        }
+    }
+
+    /// Filas que aceptan `ctor` en la primera columna, con sus argumentos desplegados
+    fn specialize(&self, rows: &[Vec<Pat>], ctor: &Ctor, arity: usize) -> Vec<Vec<Pat>> {
+        rows.iter()
+            .filter_map(|row| {
+                let args = match &row[0] {
+                    Pat::Ctor(head, args) if head == ctor => args.clone(),
+                    Pat::Wild => vec![Pat::Wild; arity],
+                    _ => return None,
+                };
+                Some(args.into_iter().chain(row[1..].iter().cloned()).collect())
+            })
+            .collect()
+    }
+
+    fn render(&self, pat: &Pat) -> String {
+        match pat {
+            Pat::Wild | Pat::Ctor(Ctor::Literal, _) => "_".to_string(),
+            Pat::Ctor(Ctor::Some, args) => format!("Some({})", self.render(&args[0])),
+            Pat::Ctor(Ctor::None, _) => "None".to_string(),
+            Pat::Ctor(Ctor::Ok, args) => format!("Ok({})", self.render(&args[0])),
+            Pat::Ctor(Ctor::Err, args) => format!("Err({})", self.render(&args[0])),
+            Pat::Ctor(Ctor::Bool(b), _) => b.to_string(),
+            Pat::Ctor(Ctor::Variant(enum_name, variant), args) if args.is_empty() => format!("{}.{}", enum_name, variant),
+            Pat::Ctor(Ctor::Variant(enum_name, variant), args) => {
+                let items: Vec<String> = args.iter().map(|arg| self.render(arg)).collect();
+                format!("{}.{}({})", enum_name, variant, items.join(", "))
+            }
+            Pat::Ctor(Ctor::Tuple(_), args) => {
+                let items: Vec<String> = args.iter().map(|arg| self.render(arg)).collect();
+                format!("({})", items.join(", "))
+            }
+            Pat::Ctor(Ctor::Struct(name), args) => {
+                let fields: Vec<String> = self.struct_fields[name]
+                    .iter()
+                    .zip(args)
+                    .filter(|(_, arg)| !matches!(arg, Pat::Wild))
+                    .map(|(field, arg)| format!("{}: {}", field, self.render(arg)))
+                    .chain(std::iter::once("..".to_string()))
+                    .collect();
+                format!("{} {{ {} }}", name, fields.join(", "))
+            }
+            Pat::Or(alternatives) => self.render(&alternatives[0]),
+        }
+    }
+}
+
+/// Desplegar las alternativas `|` de la primera columna en filas separadas
+fn expand_or(rows: Vec<Vec<Pat>>) -> Vec<Vec<Pat>> {
+    let mut expanded = Vec::new();
+    for row in rows {
+        match &row[0] {
+            Pat::Or(alternatives) => {
+                let split = alternatives
+                    .iter()
+                    .map(|alt| std::iter::once(alt.clone()).chain(row[1..].iter().cloned()).collect())
+                    .collect();
+                expanded.extend(expand_or(split));
+            }
+            _ => expanded.push(row),
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn some(p: Pattern) -> Pattern {
+        Pattern::Some(Box::new(p))
+    }
+
+    fn ok(p: Pattern) -> Pattern {
+        Pattern::Ok(Box::new(p))
+    }
+
+    #[test]
+    fn test_option_result_nesting() {
+        let arms = [some(ok(Pattern::Ident("x".to_string()))), Pattern::None];
+        assert_eq!(missing_pattern(&arms.iter().collect::<Vec<_>>(), &HashMap::new()), Some("Some(Err(_))".to_string()));
+
+        let arms = [
+            some(ok(Pattern::Wildcard)),
+            some(Pattern::Err(Box::new(Pattern::Wildcard))),
+            Pattern::None,
+        ];
+        assert_eq!(missing_pattern(&arms.iter().collect::<Vec<_>>(), &HashMap::new()), None);
+    }
+
+    #[test]
+    fn test_infinite_domains_and_or() {
+        let arms = [Pattern::LiteralNumber(0), Pattern::Range { start: 1, end: 10, inclusive: true }];
+        assert_eq!(missing_pattern(&arms.iter().collect::<Vec<_>>(), &HashMap::new()), Some("_".to_string()));
+
+        let arms = [Pattern::Or(vec![Pattern::LiteralBool(true), Pattern::LiteralBool(false)])];
+        assert_eq!(missing_pattern(&arms.iter().collect::<Vec<_>>(), &HashMap::new()), None);
+    }
+
+    #[test]
+    fn test_tuples_and_structs() {
+        let arms = [
+            Pattern::Tuple(vec![Pattern::LiteralBool(true), Pattern::Wildcard]),
+            Pattern::Tuple(vec![Pattern::Wildcard, Pattern::None]),
+        ];
+        assert_eq!(missing_pattern(&arms.iter().collect::<Vec<_>>(), &HashMap::new()), Some("(false, Some(_))".to_string()));
+
+        let arms = [Pattern::Struct {
+            name: "Punto".to_string(),
+            fields: vec![("activo".to_string(), Pattern::LiteralBool(true))],
+        }];
+        assert_eq!(
+            missing_pattern(&arms.iter().collect::<Vec<_>>(), &HashMap::new()),
+            Some("Punto { activo: false, .. }".to_string())
+        );
+    }
+}